@@ -0,0 +1,262 @@
+//! Identifier-aware tokenizer for the `code` BM25 analyzer
+//!
+//! Identifiers are kept whole and also split into their words on underscores
+//! and case boundaries, so `getUserName`, `GetUserName` and `get_user_name`
+//! all index `get`, `user` and `name`, and a query for `parse http` finds
+//! `parseHttpRequest`.
+
+use tantivy::tokenizer::{
+    LowerCaser, RemoveLongFilter, TextAnalyzer, Token, TokenStream, Tokenizer,
+};
+
+/// Name the analyzer is registered under
+pub const CODE_ANALYZER: &str = "code";
+
+/// Tokens longer than this are dropped, as by Tantivy's default analyzer
+pub(crate) const MAX_TOKEN_LEN: usize = 40;
+
+/// The `code` analyzer: identifier-aware tokenization, lowercased
+pub(crate) fn code_analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(CodeTokenizer)
+        .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+        .filter(LowerCaser)
+        .build()
+}
+
+/// Splits text into identifiers (runs of alphanumerics and underscores)
+///
+/// An identifier made of several words is emitted whole, followed by each of
+/// its words; the whole token shares the position of the first word.
+#[derive(Clone, Default)]
+pub(crate) struct CodeTokenizer;
+
+/// Tokens of a text, produced up front
+pub(crate) struct CodeTokenStream {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Tokenizer for CodeTokenizer {
+    type TokenStream<'a> = CodeTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        let mut tokens = Vec::new();
+        let mut position = 0;
+
+        for (start, identifier) in identifiers(text) {
+            let words = split_identifier(identifier);
+            if words.is_empty() {
+                continue; // Only underscores
+            }
+            if words.len() > 1 {
+                tokens.push(Token {
+                    offset_from: start,
+                    offset_to: start + identifier.len(),
+                    position,
+                    text: identifier.to_string(),
+                    position_length: words.len(),
+                });
+            }
+            for (word_start, word) in &words {
+                tokens.push(Token {
+                    offset_from: start + word_start,
+                    offset_to: start + word_start + word.len(),
+                    position,
+                    text: word.to_string(),
+                    position_length: 1,
+                });
+                position += 1;
+            }
+        }
+
+        CodeTokenStream { tokens, next: 0 }
+    }
+}
+
+impl TokenStream for CodeTokenStream {
+    fn advance(&mut self) -> bool {
+        self.next += 1;
+        self.next <= self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.next - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.next - 1]
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Identifiers in `text` with their byte offsets
+fn identifiers(text: &str) -> Vec<(usize, &str)> {
+    let mut identifiers = Vec::new();
+    let mut start = None;
+    for (idx, c) in text.char_indices() {
+        match (is_identifier_char(c), start) {
+            (true, None) => start = Some(idx),
+            (false, Some(from)) => {
+                identifiers.push((from, &text[from..idx]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(from) = start {
+        identifiers.push((from, &text[from..]));
+    }
+    identifiers
+}
+
+/// Words of an identifier with their byte offsets within it
+///
+/// Splits on underscores, before an uppercase letter following a lowercase
+/// letter or digit (`parseHttp`), and before the last uppercase letter of an
+/// acronym followed by lowercase (`HTTPRequest`).
+fn split_identifier(identifier: &str) -> Vec<(usize, &str)> {
+    let chars: Vec<(usize, char)> = identifier.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start: Option<usize> = None;
+
+    for (i, &(idx, c)) in chars.iter().enumerate() {
+        if c == '_' {
+            if let Some(from) = start.take() {
+                words.push((from, &identifier[from..idx]));
+            }
+            continue;
+        }
+
+        let boundary = i > 0 && c.is_uppercase() && {
+            let prev = chars[i - 1].1;
+            let next = chars.get(i + 1).map(|&(_, c)| c);
+            prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next.is_some_and(char::is_lowercase))
+        };
+        match start {
+            Some(from) if boundary => {
+                words.push((from, &identifier[from..idx]));
+                start = Some(idx);
+            }
+            None => start = Some(idx),
+            _ => {}
+        }
+    }
+    if let Some(from) = start {
+        words.push((from, &identifier[from..]));
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bm25_search::BM25Search;
+    use tempfile::TempDir;
+
+    fn token_texts(text: &str) -> Vec<String> {
+        let mut analyzer = code_analyzer();
+        let mut stream = analyzer.token_stream(text);
+        let mut texts = Vec::new();
+        stream.process(&mut |token| texts.push(token.text.clone()));
+        texts
+    }
+
+    fn words(identifier: &str) -> Vec<&str> {
+        split_identifier(identifier)
+            .into_iter()
+            .map(|(_, word)| word)
+            .collect()
+    }
+
+    #[test]
+    fn test_split_identifier() {
+        assert_eq!(words("parseHttpRequest"), ["parse", "Http", "Request"]);
+        assert_eq!(words("GetUserName"), ["Get", "User", "Name"]);
+        assert_eq!(words("get_user_name"), ["get", "user", "name"]);
+        assert_eq!(words("HTTPRequest"), ["HTTP", "Request"]);
+        assert_eq!(words("__init__"), ["init"]);
+        assert_eq!(words("utf8Decode"), ["utf8", "Decode"]);
+        assert!(words("___").is_empty());
+    }
+
+    #[test]
+    fn test_identifiers_are_emitted_whole_and_split() {
+        assert_eq!(
+            token_texts("let name = getUserName(id);"),
+            ["let", "name", "getusername", "get", "user", "name", "id"]
+        );
+        assert_eq!(
+            token_texts("get_user_name"),
+            ["get_user_name", "get", "user", "name"]
+        );
+    }
+
+    fn search_ids(bm25: &BM25Search, query: &str) -> Vec<u64> {
+        let mut ids: Vec<u64> = bm25
+            .search(query, 10)
+            .unwrap()
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_query_words_find_camel_case_identifier() {
+        let dir = TempDir::new().unwrap();
+        let bm25 = BM25Search::new(dir.path()).unwrap();
+        bm25.add_documents(vec![
+            (
+                1,
+                "fn parseHttpRequest(raw: &str) {}".to_string(),
+                "http.rs".to_string(),
+            ),
+            (2, "fn render_page() {}".to_string(), "page.rs".to_string()),
+        ])
+        .unwrap();
+
+        assert_eq!(search_ids(&bm25, "parse http"), [1]);
+        assert_eq!(search_ids(&bm25, "parseHttpRequest"), [1]);
+    }
+
+    #[test]
+    fn test_identifier_styles_match_each_other() {
+        let dir = TempDir::new().unwrap();
+        let bm25 = BM25Search::new(dir.path()).unwrap();
+        bm25.add_documents(vec![
+            (
+                1,
+                "let n = get_user_name(id);".to_string(),
+                "a.rs".to_string(),
+            ),
+            (2, "n := GetUserName(id)".to_string(), "b.go".to_string()),
+            (3, "fn set_password() {}".to_string(), "c.rs".to_string()),
+        ])
+        .unwrap();
+
+        assert_eq!(search_ids(&bm25, "getUserName"), [1, 2]);
+    }
+
+    #[test]
+    fn test_highlights_cover_matched_identifiers() {
+        let dir = TempDir::new().unwrap();
+        let bm25 = BM25Search::new(dir.path()).unwrap();
+        let text = "let req = parseHttpRequest(raw);\nrender(req);";
+
+        let spans: Vec<&str> = bm25
+            .highlights("http request render", text)
+            .unwrap()
+            .iter()
+            .map(|&(start, end)| &text[start..end])
+            .collect();
+        assert_eq!(spans, ["HttpRequest", "render"]);
+
+        assert!(bm25.highlights("zebra", text).unwrap().is_empty());
+    }
+}
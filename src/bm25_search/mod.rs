@@ -1,22 +1,110 @@
+use crate::error::{RagError, VectorDbError};
 use anyhow::{Context, Result};
 use std::collections::HashMap;
 use std::path::Path;
-use std::sync::Mutex;
-use tantivy::collector::TopDocs;
-use tantivy::query::QueryParser;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tantivy::collector::{Count, TopDocs};
+use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
 use tantivy::schema::*;
 use tantivy::{Index, IndexWriter, ReloadPolicy, TantivyDocument, doc};
 
+mod code_tokenizer;
+mod stemming;
+mod writer_lock;
+
+pub use code_tokenizer::CODE_ANALYZER;
+pub use stemming::{BM25_LANGUAGES, stemmed_analyzer_name};
+pub(crate) use writer_lock::HEARTBEAT_INTERVAL;
+use writer_lock::WriterLock;
+
+/// Analyzers the content field can be indexed with
+///
+/// - `code`: splits identifiers on underscores and case boundaries while also
+///   keeping them whole, so `getUserName` matches `get_user_name`
+/// - `default`: splits on non-alphanumeric characters and lowercases
+/// - `en_stem`: `default` plus English stemming ("authenticating" matches "authenticate")
+/// - `whitespace`: splits on whitespace only, keeping `snake_case` and `a::b` paths whole
+///
+/// Any of them can also drop stop words and stem words, see [`stemmed_analyzer_name`].
+pub const BM25_ANALYZERS: &[&str] = &[CODE_ANALYZER, "default", "en_stem", "whitespace"];
+
+/// Analyzer new indexes are built with unless another one is configured
+pub const DEFAULT_BM25_ANALYZER: &str = CODE_ANALYZER;
+
+/// Analyzer of indexes built before the analyzer was configurable
+const LEGACY_BM25_ANALYZER: &str = "default";
+
+/// Default time writes wait for a BM25 writer in another process
+pub const DEFAULT_WRITER_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default heartbeat age after which a writer in another process is presumed dead
+pub const DEFAULT_STALE_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// File in the index directory recording the analyzer the index was built with
+const ANALYZER_MARKER: &str = "analyzer";
+
+/// Writes after which the open index writer commits
+const COMMIT_INTERVAL: usize = 256;
+
+/// Memory budget of the index writer
+const WRITER_HEAP_SIZE: usize = 50_000_000;
+
+/// A BM25 index was built with another analyzer than the configured one
+///
+/// Queries would be tokenized differently from the indexed content and quietly
+/// under-match, so the index has to be rebuilt before it is used.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "BM25 index at {path} was built with analyzer '{built_with}', but '{configured}' is configured"
+)]
+pub struct AnalyzerMismatch {
+    pub path: String,
+    pub built_with: String,
+    pub configured: String,
+}
+
 /// BM25-based keyword search using Tantivy
 pub struct BM25Search {
     index: Index,
     id_field: Field,
     content_field: Field,
     file_path_field: Field,
+    /// Analyzer the content field is tokenized with
+    analyzer: String,
     /// Path to the index directory (needed for lock cleanup)
     index_path: std::path::PathBuf,
-    /// Mutex to ensure only one IndexWriter is created at a time
-    writer_lock: Mutex<()>,
+    /// Index writer kept open between writes, if any
+    ///
+    /// The mutex also ensures only one IndexWriter is created at a time.
+    writer_lock: Mutex<Option<OpenWriter>>,
+    /// How long writes wait for a writer in another process
+    writer_lock_timeout: Duration,
+    /// Heartbeat age after which lock files of a previous writer are reclaimed
+    /// (`None` never removes them)
+    stale_lock_timeout: Option<Duration>,
+}
+
+/// Index writer kept open across writes, committed every [`COMMIT_INTERVAL`]
+/// writes and on [`BM25Search::flush`]
+struct OpenWriter {
+    writer: IndexWriter<TantivyDocument>,
+    /// Writes since the last commit
+    pending: usize,
+    /// Cross-process lock, released after the writer is dropped
+    _process_lock: WriterLock,
+}
+
+impl OpenWriter {
+    fn commit(&mut self) -> Result<()> {
+        if self.pending > 0 {
+            self.writer
+                .commit()
+                .context("Failed to commit index changes")?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
 }
 
 /// Search result from BM25
@@ -29,12 +117,48 @@ pub struct BM25Result {
 impl BM25Search {
     /// Create a new BM25 search index
     pub fn new<P: AsRef<Path>>(index_path: P) -> Result<Self> {
+        Self::with_analyzer(index_path, DEFAULT_BM25_ANALYZER)
+    }
+
+    /// Create or open a BM25 search index whose content is tokenized by `analyzer`
+    ///
+    /// Opening an existing index built with another analyzer fails with
+    /// [`AnalyzerMismatch`].
+    pub fn with_analyzer<P: AsRef<Path>>(index_path: P, analyzer: &str) -> Result<Self> {
         let index_path = index_path.as_ref().to_path_buf();
 
+        let (base_analyzer, language) = match stemming::split_stemmed_name(analyzer) {
+            Some((base, language)) => (base, Some(language)),
+            None => (analyzer, None),
+        };
+        if !BM25_ANALYZERS.contains(&base_analyzer) {
+            anyhow::bail!(
+                "Unknown BM25 analyzer '{}' (expected one of: {})",
+                base_analyzer,
+                BM25_ANALYZERS.join(", ")
+            );
+        }
+        let language = language
+            .map(|language| {
+                stemming::parse_language(language).with_context(|| {
+                    format!(
+                        "Unknown BM25 stemming language '{}' (expected one of: {})",
+                        language,
+                        BM25_LANGUAGES.join(", ")
+                    )
+                })
+            })
+            .transpose()?;
+
         // Create schema with ID, content, and file_path fields
         let mut schema_builder = Schema::builder();
         let id_field = schema_builder.add_u64_field("id", STORED | INDEXED);
-        let content_field = schema_builder.add_text_field("content", TEXT);
+        let content_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(analyzer)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        );
+        let content_field = schema_builder.add_text_field("content", content_options);
         let file_path_field = schema_builder.add_text_field("file_path", STRING | STORED);
         let schema = schema_builder.build();
 
@@ -42,145 +166,230 @@ impl BM25Search {
         std::fs::create_dir_all(&index_path).context("Failed to create BM25 index directory")?;
 
         let index = if index_path.join("meta.json").exists() {
+            let built_with = Self::recorded_analyzer(&index_path);
+            if built_with != analyzer {
+                return Err(AnalyzerMismatch {
+                    path: index_path.display().to_string(),
+                    built_with,
+                    configured: analyzer.to_string(),
+                }
+                .into());
+            }
             Index::open_in_dir(&index_path).context("Failed to open existing BM25 index")?
         } else {
-            Index::create_in_dir(&index_path, schema.clone())
-                .context("Failed to create BM25 index")?
+            let index = Index::create_in_dir(&index_path, schema.clone())
+                .context("Failed to create BM25 index")?;
+            std::fs::write(index_path.join(ANALYZER_MARKER), analyzer)
+                .context("Failed to record BM25 analyzer")?;
+            index
         };
+        // Custom tokenizers are not persisted with the index
+        index
+            .tokenizers()
+            .register(CODE_ANALYZER, code_tokenizer::code_analyzer());
+        if let Some(language) = language {
+            index.tokenizers().register(
+                analyzer,
+                stemming::stemmed_analyzer(base_analyzer, language),
+            );
+        }
 
         Ok(Self {
             index,
             id_field,
             content_field,
             file_path_field,
+            analyzer: analyzer.to_string(),
             index_path,
-            writer_lock: Mutex::new(()),
+            writer_lock: Mutex::new(None),
+            writer_lock_timeout: DEFAULT_WRITER_LOCK_TIMEOUT,
+            stale_lock_timeout: Some(DEFAULT_STALE_LOCK_TIMEOUT),
         })
     }
 
-    /// Check if a lock file is stale (older than 5 minutes with no recent activity)
-    fn is_lock_stale(lock_path: &Path) -> bool {
-        if !lock_path.exists() {
-            return false;
-        }
-
-        // Check file modification time
-        if let Ok(metadata) = std::fs::metadata(lock_path)
-            && let Ok(modified) = metadata.modified()
-            && let Ok(elapsed) = modified.elapsed()
-        {
-            // Consider lock stale if older than 5 minutes
-            return elapsed.as_secs() > 300;
-        }
-
-        false
+    /// Analyzer an index directory was built with
+    ///
+    /// Indexes without a marker predate configurable analyzers and used the default one.
+    pub fn recorded_analyzer(index_path: &Path) -> String {
+        std::fs::read_to_string(index_path.join(ANALYZER_MARKER))
+            .map(|name| name.trim().to_string())
+            .unwrap_or_else(|_| LEGACY_BM25_ANALYZER.to_string())
     }
 
-    /// Try to clean up stale lock files only if they appear to be from crashed processes
-    fn try_cleanup_stale_locks(index_path: &Path) -> Result<bool> {
-        let writer_lock = index_path.join(".tantivy-writer.lock");
-        let meta_lock = index_path.join(".tantivy-meta.lock");
+    /// Set how long writes wait for a writer in another process to finish
+    pub fn with_writer_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.writer_lock_timeout = timeout;
+        self
+    }
 
-        let writer_stale = Self::is_lock_stale(&writer_lock);
-        let meta_stale = Self::is_lock_stale(&meta_lock);
+    /// Set the heartbeat age after which Tantivy lock files of a previous
+    /// writer are removed, or `None` to never remove them
+    ///
+    /// With `None`, a locked index fails writes with
+    /// [`VectorDbError::Bm25Locked`] until the lock files are removed by hand.
+    pub fn with_stale_lock_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.stale_lock_timeout = timeout;
+        self
+    }
 
-        if !writer_stale && !meta_stale {
-            return Ok(false); // Locks appear to be active
-        }
+    /// Lock the open index writer, if any
+    fn lock_writer(&self) -> Result<MutexGuard<'_, Option<OpenWriter>>> {
+        self.writer_lock
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire writer lock: {}", e))
+    }
 
-        if writer_stale && writer_lock.exists() {
-            tracing::warn!(
-                "Removing stale Tantivy writer lock file (>5min old): {:?}",
-                writer_lock
-            );
-            std::fs::remove_file(&writer_lock)
-                .context("Failed to remove stale writer lock file")?;
-        }
+    /// Run `write` with the open index writer, opening it if needed
+    ///
+    /// Changes are committed every [`COMMIT_INTERVAL`] writes, before reads
+    /// and on [`flush`](Self::flush), rather than after each write. If `write`
+    /// fails, the writer is dropped without committing, so the partial write
+    /// is discarded along with the writes since the last commit.
+    fn with_writer<T>(
+        &self,
+        write: impl FnOnce(&mut IndexWriter<TantivyDocument>) -> Result<T>,
+    ) -> Result<T> {
+        // Lock to ensure only one writer at a time (within this process)
+        let mut open = self.lock_writer()?;
+        let open_writer = match &mut *open {
+            Some(open_writer) => open_writer,
+            closed => closed.insert(self.open_writer()?),
+        };
 
-        if meta_stale && meta_lock.exists() {
-            tracing::warn!(
-                "Removing stale Tantivy meta lock file (>5min old): {:?}",
-                meta_lock
-            );
-            std::fs::remove_file(&meta_lock).context("Failed to remove stale meta lock file")?;
+        let result = match write(&mut open_writer.writer) {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::warn!(
+                    "BM25 write failed, discarding {} uncommitted writes to {}",
+                    open_writer.pending + 1,
+                    self.index_path.display()
+                );
+                *open = None;
+                return Err(e);
+            }
+        };
+        open_writer.pending += 1;
+        if open_writer.pending >= COMMIT_INTERVAL {
+            open_writer.commit()?;
         }
-
-        Ok(true) // Cleaned up stale locks
+        Ok(result)
     }
 
-    /// Add documents to the index
+    /// Create an index writer, holding the cross-process [`WriterLock`]
     ///
-    /// Arguments:
-    /// * `documents` - Vec of (id, content, file_path) tuples
-    pub fn add_documents(&self, documents: Vec<(u64, String, String)>) -> Result<()> {
-        // Lock to ensure only one writer at a time (within this process)
-        let _guard = self
-            .writer_lock
-            .lock()
-            .map_err(|e| anyhow::anyhow!("Failed to acquire writer lock: {}", e))?;
+    /// Tantivy lock files left by a crashed writer are reclaimed once its PID
+    /// is dead; those of a live writer are respected. With reclaiming
+    /// disabled, a locked index is reported instead.
+    fn open_writer(&self) -> Result<OpenWriter> {
+        let process_lock = WriterLock::acquire(&self.index_path, self.writer_lock_timeout)?;
 
-        // Try to create the index writer
-        let mut index_writer: IndexWriter<TantivyDocument> = match self.index.writer(50_000_000) {
+        let writer: IndexWriter<TantivyDocument> = match self.index.writer(WRITER_HEAP_SIZE) {
             Ok(writer) => writer,
             Err(e) => {
                 // Check if this is a lock error
                 let error_msg = format!("{}", e);
-                if error_msg.contains("lock") || error_msg.contains("Lock") {
-                    tracing::warn!(
-                        "Index writer creation failed (possibly locked), checking for stale locks..."
-                    );
-
-                    // Try to cleanup stale locks
-                    match Self::try_cleanup_stale_locks(&self.index_path) {
-                        Ok(true) => {
-                            // Stale locks were cleaned up, retry once
-                            tracing::info!("Stale locks cleaned up, retrying writer creation...");
-                            self.index.writer(50_000_000).context(
-                                "Failed to create index writer after cleaning stale locks",
-                            )?
-                        }
-                        Ok(false) => {
-                            // Locks exist but are not stale (another process is actively using the index)
-                            return Err(anyhow::anyhow!(
-                                "BM25 index is currently being used by another process. Please wait and try again later."
-                            ));
-                        }
-                        Err(cleanup_err) => {
-                            // Failed to cleanup locks
-                            return Err(anyhow::anyhow!(
-                                "Failed to create index writer (locked) and failed to cleanup stale locks: {}. Original error: {}",
-                                cleanup_err,
-                                e
-                            ));
-                        }
-                    }
-                } else {
-                    // Not a lock error, propagate original error
+                if !error_msg.contains("lock") && !error_msg.contains("Lock") {
                     return Err(e).context("Failed to create index writer");
                 }
+                let Some(stale_lock_timeout) = self.stale_lock_timeout else {
+                    return Err(RagError::from(VectorDbError::Bm25Locked {
+                        path: self.index_path.display().to_string(),
+                        reason: error_msg,
+                    })
+                    .into());
+                };
+                tracing::warn!(
+                    "Index writer creation failed (possibly locked), checking for stale locks..."
+                );
+
+                match process_lock.reclaim_stale_locks(stale_lock_timeout) {
+                    Ok(true) => {
+                        // Stale locks were cleaned up, retry once
+                        tracing::info!("Stale locks cleaned up, retrying writer creation...");
+                        self.index
+                            .writer(WRITER_HEAP_SIZE)
+                            .context("Failed to create index writer after cleaning stale locks")?
+                    }
+                    Ok(false) => {
+                        // The previous writer is still alive
+                        return Err(anyhow::anyhow!(
+                            "BM25 index is currently being used by another process. Please wait and try again later."
+                        ));
+                    }
+                    Err(cleanup_err) => {
+                        return Err(anyhow::anyhow!(
+                            "Failed to create index writer (locked) and failed to cleanup stale locks: {}. Original error: {}",
+                            cleanup_err,
+                            e
+                        ));
+                    }
+                }
             }
         };
 
-        for (id, content, file_path) in documents {
-            let doc = doc!(
-                self.id_field => id,
-                self.content_field => content,
-                self.file_path_field => file_path,
-            );
-            index_writer
-                .add_document(doc)
-                .context("Failed to add document")?;
-        }
+        Ok(OpenWriter {
+            writer,
+            pending: 0,
+            _process_lock: process_lock,
+        })
+    }
 
-        index_writer
-            .commit()
-            .context("Failed to commit documents")?;
+    /// Commit pending changes so readers see them
+    fn commit_pending(&self) -> Result<()> {
+        if let Some(open_writer) = self.lock_writer()?.as_mut() {
+            open_writer.commit()?;
+        }
+        Ok(())
+    }
 
+    /// Commit pending changes and close the index writer
+    ///
+    /// Once this returns, the committed documents are on disk and survive a
+    /// crash: segment merges started by the commit are waited for, so no
+    /// background thread is still rewriting segment files. Closing releases
+    /// the writer locks, so other processes can write the index until the
+    /// next write here opens a writer again.
+    pub fn flush(&self) -> Result<()> {
+        let mut open = self.lock_writer()?;
+        if let Some(open_writer) = open.as_mut() {
+            open_writer.commit()?;
+        }
+        if let Some(open_writer) = open.take() {
+            let OpenWriter {
+                writer,
+                _process_lock,
+                ..
+            } = open_writer;
+            writer
+                .wait_merging_threads()
+                .context("Failed to wait for index segment merges")?;
+        }
         Ok(())
     }
 
+    /// Add documents to the index
+    ///
+    /// Arguments:
+    /// * `documents` - Vec of (id, content, file_path) tuples
+    pub fn add_documents(&self, documents: Vec<(u64, String, String)>) -> Result<()> {
+        self.with_writer(|index_writer| {
+            for (id, content, file_path) in documents {
+                let doc = doc!(
+                    self.id_field => id,
+                    self.content_field => content,
+                    self.file_path_field => file_path,
+                );
+                index_writer
+                    .add_document(doc)
+                    .context("Failed to add document")?;
+            }
+            Ok(())
+        })
+    }
+
     /// Search the index with BM25 scoring
     pub fn search(&self, query_text: &str, limit: usize) -> Result<Vec<BM25Result>> {
+        self.commit_pending()?;
         let reader = self
             .index
             .reader_builder()
@@ -189,11 +398,7 @@ impl BM25Search {
             .context("Failed to create index reader")?;
 
         let searcher = reader.searcher();
-
-        // Parse query using lenient mode to handle special characters like :: in code
-        // (e.g., "Tool::new" would fail strict parsing since : is a field separator)
-        let query_parser = QueryParser::for_index(&self.index, vec![self.content_field]);
-        let (query, _errors) = query_parser.parse_query_lenient(query_text);
+        let query = self.parse_query(query_text)?;
 
         // Search with BM25
         let top_docs = searcher
@@ -216,78 +421,188 @@ impl BM25Search {
         Ok(results)
     }
 
-    /// Delete all documents for a specific ID
-    pub fn delete_by_id(&self, id: u64) -> Result<()> {
-        // Lock to ensure only one writer at a time
-        let _guard = self
-            .writer_lock
-            .lock()
-            .map_err(|e| anyhow::anyhow!("Failed to acquire writer lock: {}", e))?;
+    /// Byte ranges of `text` holding terms of the keyword query
+    ///
+    /// Content is not stored in the index, so the text of a hit is tokenized
+    /// again with the content analyzer and every token matching a query term
+    /// is reported. Overlapping ranges (a whole identifier and its words) are
+    /// merged, and the ranges are sorted.
+    pub fn highlights(&self, query_text: &str, text: &str) -> Result<Vec<(usize, usize)>> {
+        let query = self.parse_query(query_text)?;
+        let mut query_terms = Vec::new();
+        query.query_terms(&mut |term, _| {
+            if term.field() == self.content_field
+                && let Some(text) = term.value().as_str()
+            {
+                query_terms.push(text.to_string());
+            }
+        });
+        if query_terms.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        let mut index_writer: IndexWriter<TantivyDocument> = self
+        let mut analyzer = self
             .index
-            .writer(50_000_000)
-            .context("Failed to create index writer")?;
+            .tokenizer_for_field(self.content_field)
+            .context("Failed to get BM25 content tokenizer")?;
+        let mut ranges = Vec::new();
+        analyzer.token_stream(text).process(&mut |token| {
+            if query_terms.contains(&token.text) {
+                ranges.push((token.offset_from, token.offset_to));
+            }
+        });
+
+        ranges.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Query for `query_text` as the content analyzer tokenizes it
+    fn parse_query(&self, query_text: &str) -> Result<Box<dyn Query>> {
+        let base_analyzer = stemming::split_stemmed_name(&self.analyzer)
+            .map_or(self.analyzer.as_str(), |(base, _)| base);
+        if base_analyzer == CODE_ANALYZER {
+            self.code_query(query_text)
+        } else {
+            // Parse query using lenient mode to handle special characters like :: in code
+            // (e.g., "Tool::new" would fail strict parsing since : is a field separator)
+            let query_parser = QueryParser::for_index(&self.index, vec![self.content_field]);
+            Ok(query_parser.parse_query_lenient(query_text).0)
+        }
+    }
 
-        let term = Term::from_field_u64(self.id_field, id);
-        index_writer.delete_term(term);
+    /// Query matching any token the `code` analyzer produces for `query_text`
+    ///
+    /// The query parser would turn the words of a split identifier into a
+    /// phrase that has to match the whole identifier as well, so the terms are
+    /// combined directly instead.
+    fn code_query(&self, query_text: &str) -> Result<Box<dyn Query>> {
+        let mut analyzer = self
+            .index
+            .tokenizer_for_field(self.content_field)
+            .context("Failed to get BM25 content tokenizer")?;
+
+        let mut terms = Vec::new();
+        analyzer.token_stream(query_text).process(&mut |token| {
+            let term = Term::from_field_text(self.content_field, &token.text);
+            if !terms.contains(&term) {
+                terms.push(term);
+            }
+        });
+
+        let clauses = terms
+            .into_iter()
+            .map(|term| {
+                let query: Box<dyn Query> =
+                    Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs));
+                (Occur::Should, query)
+            })
+            .collect();
+        Ok(Box::new(BooleanQuery::new(clauses)))
+    }
 
-        index_writer.commit().context("Failed to commit deletion")?;
+    /// Delete all documents for a specific ID
+    pub fn delete_by_id(&self, id: u64) -> Result<()> {
+        self.with_writer(|index_writer| {
+            index_writer.delete_term(Term::from_field_u64(self.id_field, id));
+            Ok(())
+        })
+    }
 
-        Ok(())
+    /// Delete the documents with any of the given IDs in a single commit
+    pub fn delete_by_ids(&self, ids: &[u64]) -> Result<()> {
+        self.with_writer(|index_writer| {
+            for &id in ids {
+                index_writer.delete_term(Term::from_field_u64(self.id_field, id));
+            }
+            Ok(())
+        })
     }
 
     /// Delete all documents with a specific file_path
     ///
     /// This is used for incremental updates when files are deleted or modified.
+    /// Returns the number of committed documents deleted; documents added since
+    /// the last commit are deleted as well but not counted.
     pub fn delete_by_file_path(&self, file_path: &str) -> Result<usize> {
-        // Lock to ensure only one writer at a time
-        let _guard = self
-            .writer_lock
-            .lock()
-            .map_err(|e| anyhow::anyhow!("Failed to acquire writer lock: {}", e))?;
-
-        let mut index_writer: IndexWriter<TantivyDocument> = self
-            .index
-            .writer(50_000_000)
-            .context("Failed to create index writer")?;
-
-        let term = Term::from_field_text(self.file_path_field, file_path);
-        index_writer.delete_term(term);
-
-        index_writer
-            .commit()
-            .context("Failed to commit file_path deletion")?;
-
-        // Note: Tantivy doesn't return count of deleted documents
-        // Return 0 as placeholder
-        Ok(0)
+        self.with_writer(|index_writer| {
+            let term = Term::from_field_text(self.file_path_field, file_path);
+
+            // Tantivy doesn't report deletions, so count the matches first
+            // (under the writer lock, nothing can change in between)
+            let reader = self
+                .index
+                .reader_builder()
+                .reload_policy(ReloadPolicy::Manual)
+                .try_into()
+                .context("Failed to create index reader")?;
+            let deleted = reader
+                .searcher()
+                .search(
+                    &TermQuery::new(term.clone(), IndexRecordOption::Basic),
+                    &Count,
+                )
+                .context("Failed to count documents to delete")?;
+
+            index_writer.delete_term(term);
+            Ok(deleted)
+        })
     }
 
-    /// Clear the entire index
+    /// Clear the entire index, committing and closing the writer
     pub fn clear(&self) -> Result<()> {
-        // Lock to ensure only one writer at a time
-        let _guard = self
-            .writer_lock
-            .lock()
-            .map_err(|e| anyhow::anyhow!("Failed to acquire writer lock: {}", e))?;
+        self.with_writer(|index_writer| {
+            index_writer
+                .delete_all_documents()
+                .context("Failed to delete all documents")?;
+            Ok(())
+        })?;
+        self.flush()
+    }
 
-        let mut index_writer: IndexWriter<TantivyDocument> = self
+    /// Merge all segments into one and delete the files they leave behind
+    ///
+    /// Returns the number of segments before and after merging.
+    pub fn merge_segments(&self) -> Result<(usize, usize)> {
+        self.commit_pending()?;
+        let segment_ids = self
             .index
-            .writer(50_000_000)
-            .context("Failed to create index writer")?;
-
-        index_writer
-            .delete_all_documents()
-            .context("Failed to delete all documents")?;
-
-        index_writer.commit().context("Failed to commit clear")?;
+            .searchable_segment_ids()
+            .context("Failed to list index segments")?;
+        let before = segment_ids.len();
+        if before < 2 {
+            return Ok((before, before));
+        }
 
-        Ok(())
+        self.with_writer(|index_writer| {
+            index_writer
+                .merge(&segment_ids)
+                .wait()
+                .context("Failed to merge index segments")?;
+            index_writer
+                .garbage_collect_files()
+                .wait()
+                .context("Failed to delete merged segment files")?;
+            Ok(())
+        })?;
+        self.flush()?;
+        let after = self
+            .index
+            .searchable_segment_ids()
+            .context("Failed to list index segments")?
+            .len();
+        Ok((before, after))
     }
 
     /// Get index statistics
     pub fn get_stats(&self) -> Result<BM25Stats> {
+        self.commit_pending()?;
         let reader = self
             .index
             .reader_builder()
@@ -304,6 +619,18 @@ impl BM25Search {
     }
 }
 
+impl Drop for BM25Search {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::warn!(
+                "Failed to commit BM25 index at {}: {:#}",
+                self.index_path.display(),
+                e
+            );
+        }
+    }
+}
+
 /// Statistics about the BM25 index
 #[derive(Debug, Clone)]
 pub struct BM25Stats {
@@ -346,18 +673,35 @@ where
     I: IntoIterator<Item = L>,
     L: IntoIterator<Item = (T, f32)>,
 {
-    let mut score_map: HashMap<T, f32> = HashMap::new();
+    // Combined score and first-seen order of each id; ties are broken by the
+    // order ids first appear so the fused ranking is deterministic
+    let mut score_map: HashMap<T, (f32, usize)> = HashMap::new();
 
     for list in ranked_lists {
         for (rank, (id, _score)) in list.into_iter().enumerate() {
-            let rrf_score = 1.0 / (RRF_K_CONSTANT + (rank + 1) as f32);
-            *score_map.entry(id).or_insert(0.0) += rrf_score;
+            let next_order = score_map.len();
+            score_map.entry(id).or_insert((0.0, next_order)).0 += rrf_contribution(rank + 1);
         }
     }
 
-    let mut combined: Vec<(T, f32)> = score_map.into_iter().collect();
-    combined.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    let mut combined: Vec<(T, f32, usize)> = score_map
+        .into_iter()
+        .map(|(id, (score, order))| (id, score, order))
+        .collect();
+    combined.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.2.cmp(&b.2))
+    });
     combined.truncate(limit);
 
     combined
+        .into_iter()
+        .map(|(id, score, _)| (id, score))
+        .collect()
+}
+
+/// RRF score contributed by a result at a 1-based `rank` in one ranked list
+pub fn rrf_contribution(rank: usize) -> f32 {
+    1.0 / (RRF_K_CONSTANT + rank as f32)
 }
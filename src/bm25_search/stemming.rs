@@ -0,0 +1,260 @@
+//! Stemming and stop-word filtering for the BM25 analyzers
+//!
+//! With `bm25.stemming` enabled, any analyzer also drops stop words and
+//! reduces words to their stem, so "authenticating" matches "authenticate".
+//! Only plain words are stemmed: whole identifiers emitted by the `code`
+//! analyzer and tokens containing digits or underscores are left as they are,
+//! so `user_settings` is still found as written. Snowball stemmers only strip
+//! suffixes, so short words like `config` stay intact, but related words do
+//! collapse (`configure` and `configuration` both become `configur`), which
+//! trades some precision for recall.
+
+use super::code_tokenizer::{CODE_ANALYZER, CodeTokenizer, MAX_TOKEN_LEN};
+use tantivy::tokenizer::{
+    Language, LowerCaser, RawTokenizer, RemoveLongFilter, SimpleTokenizer, Stemmer, StopWordFilter,
+    TextAnalyzer, Token, TokenFilter, TokenStream, Tokenizer, WhitespaceTokenizer,
+};
+
+/// Languages `bm25.language` accepts
+pub const BM25_LANGUAGES: &[&str] = &[
+    "arabic",
+    "danish",
+    "dutch",
+    "english",
+    "finnish",
+    "french",
+    "german",
+    "greek",
+    "hungarian",
+    "italian",
+    "norwegian",
+    "portuguese",
+    "romanian",
+    "russian",
+    "spanish",
+    "swedish",
+    "tamil",
+    "turkish",
+];
+
+/// Separates the base analyzer from the stemming language in an analyzer name
+const STEM_SEPARATOR: &str = "+stem_";
+
+/// Name of `analyzer` with stemming and stop-word filtering for `language` added
+///
+/// The name is recorded with each index, so changing either setting rebuilds
+/// indexes built with the other one.
+pub fn stemmed_analyzer_name(analyzer: &str, language: &str) -> String {
+    format!("{}{}{}", analyzer, STEM_SEPARATOR, language.to_lowercase())
+}
+
+/// Base analyzer and stemming language of a stemmed analyzer name
+pub(crate) fn split_stemmed_name(name: &str) -> Option<(&str, &str)> {
+    name.split_once(STEM_SEPARATOR)
+}
+
+/// Tantivy language for a `bm25.language` name
+pub(crate) fn parse_language(name: &str) -> Option<Language> {
+    let language = match name.to_lowercase().as_str() {
+        "arabic" => Language::Arabic,
+        "danish" => Language::Danish,
+        "dutch" => Language::Dutch,
+        "english" => Language::English,
+        "finnish" => Language::Finnish,
+        "french" => Language::French,
+        "german" => Language::German,
+        "greek" => Language::Greek,
+        "hungarian" => Language::Hungarian,
+        "italian" => Language::Italian,
+        "norwegian" => Language::Norwegian,
+        "portuguese" => Language::Portuguese,
+        "romanian" => Language::Romanian,
+        "russian" => Language::Russian,
+        "spanish" => Language::Spanish,
+        "swedish" => Language::Swedish,
+        "tamil" => Language::Tamil,
+        "turkish" => Language::Turkish,
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// `base` analyzer with stop-word filtering and stemming for `language`
+///
+/// `en_stem` is tokenized like `default`, with the configured stemmer in place
+/// of its English one.
+pub(crate) fn stemmed_analyzer(base: &str, language: Language) -> TextAnalyzer {
+    if base == CODE_ANALYZER {
+        with_stemming(CodeTokenizer, language)
+    } else if base == "whitespace" {
+        with_stemming(WhitespaceTokenizer::default(), language)
+    } else {
+        with_stemming(SimpleTokenizer::default(), language)
+    }
+}
+
+fn with_stemming<T: Tokenizer>(tokenizer: T, language: Language) -> TextAnalyzer {
+    // Not every language has a stop-word list; those are only stemmed
+    let stop_words =
+        StopWordFilter::new(language).unwrap_or_else(|| StopWordFilter::remove(Vec::new()));
+    TextAnalyzer::builder(tokenizer)
+        .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+        .filter(LowerCaser)
+        .filter(stop_words)
+        .filter(WordStemmer::new(language))
+        .build()
+}
+
+/// Token filter stemming plain words and leaving identifiers as they are
+#[derive(Clone)]
+pub(crate) struct WordStemmer {
+    stemmer: TextAnalyzer,
+}
+
+impl WordStemmer {
+    pub(crate) fn new(language: Language) -> Self {
+        Self {
+            stemmer: TextAnalyzer::builder(RawTokenizer::default())
+                .filter(Stemmer::new(language))
+                .build(),
+        }
+    }
+}
+
+impl TokenFilter for WordStemmer {
+    type Tokenizer<T: Tokenizer> = WordStemmerFilter<T>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> WordStemmerFilter<T> {
+        WordStemmerFilter {
+            stemmer: self.stemmer,
+            inner: tokenizer,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct WordStemmerFilter<T> {
+    stemmer: TextAnalyzer,
+    inner: T,
+}
+
+impl<T: Tokenizer> Tokenizer for WordStemmerFilter<T> {
+    type TokenStream<'a> = WordStemmerTokenStream<'a, T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        WordStemmerTokenStream {
+            stemmer: &mut self.stemmer,
+            tail: self.inner.token_stream(text),
+        }
+    }
+}
+
+pub(crate) struct WordStemmerTokenStream<'a, S> {
+    stemmer: &'a mut TextAnalyzer,
+    tail: S,
+}
+
+impl<S: TokenStream> TokenStream for WordStemmerTokenStream<'_, S> {
+    fn advance(&mut self) -> bool {
+        if !self.tail.advance() {
+            return false;
+        }
+
+        let token = self.tail.token_mut();
+        if is_plain_word(token) {
+            let stemmed = {
+                let mut stream = self.stemmer.token_stream(&token.text);
+                stream.advance().then(|| stream.token().text.clone())
+            };
+            if let Some(stemmed) = stemmed {
+                token.text = stemmed;
+            }
+        }
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tail.token_mut()
+    }
+}
+
+/// A single word of letters, not a whole multi-word identifier
+fn is_plain_word(token: &Token) -> bool {
+    token.position_length == 1 && token.text.chars().all(char::is_alphabetic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bm25_search::BM25Search;
+    use tempfile::TempDir;
+
+    fn token_texts(analyzer: &str, text: &str) -> Vec<String> {
+        let mut analyzer = stemmed_analyzer(analyzer, Language::English);
+        let mut stream = analyzer.token_stream(text);
+        let mut texts = Vec::new();
+        stream.process(&mut |token| texts.push(token.text.clone()));
+        texts
+    }
+
+    #[test]
+    fn test_words_are_stemmed_and_identifiers_kept() {
+        assert_eq!(
+            token_texts(CODE_ANALYZER, "if the user_settings are loaded"),
+            ["user_settings", "user", "set", "load"]
+        );
+        assert_eq!(
+            token_texts(CODE_ANALYZER, "config utf8"),
+            ["config", "utf8"]
+        );
+        assert_eq!(
+            token_texts(CODE_ANALYZER, "parseHttpRequests"),
+            ["parsehttprequests", "pars", "http", "request"]
+        );
+    }
+
+    #[test]
+    fn test_stemmed_query_matches_other_word_forms() {
+        let dir = TempDir::new().unwrap();
+        let analyzer = stemmed_analyzer_name(CODE_ANALYZER, "english");
+        let bm25 = BM25Search::with_analyzer(dir.path(), &analyzer).unwrap();
+        bm25.add_documents(vec![
+            (
+                1,
+                "/// Authenticate the user against the session store\nfn check() {}".to_string(),
+                "auth.rs".to_string(),
+            ),
+            (
+                2,
+                "fn load_config() -> Config {}".to_string(),
+                "config.rs".to_string(),
+            ),
+        ])
+        .unwrap();
+
+        let ids = |query: &str| -> Vec<u64> {
+            bm25.search(query, 10)
+                .unwrap()
+                .iter()
+                .map(|r| r.id)
+                .collect()
+        };
+        assert_eq!(ids("authenticating"), [1]);
+        assert_eq!(ids("config"), [2]);
+        assert_eq!(ids("load_config"), [2]);
+    }
+
+    #[test]
+    fn test_analyzer_names_round_trip() {
+        let name = stemmed_analyzer_name("default", "English");
+        assert_eq!(name, "default+stem_english");
+        assert_eq!(split_stemmed_name(&name), Some(("default", "english")));
+        assert_eq!(split_stemmed_name("default"), None);
+        assert!(BM25_LANGUAGES.iter().all(|l| parse_language(l).is_some()));
+        assert!(parse_language("klingon").is_none());
+    }
+}
@@ -0,0 +1,360 @@
+//! Cross-process writer lock for a BM25 index directory
+//!
+//! Tantivy allows a single `IndexWriter` per index. Within a process writers
+//! are serialized by a mutex; across processes, this advisory lock (flock via
+//! fs2) makes writers wait for each other. The holder records its PID and a
+//! heartbeat in an owner file, so Tantivy lock files left behind by a crashed
+//! writer are reclaimed only once that writer is known to be dead, unless
+//! reclaiming is disabled (`bm25.auto_unlock = false`).
+
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Advisory lock file held while writing the index
+const LOCK_FILE: &str = ".bm25-writer.lock";
+
+/// PID and heartbeat of the current lock holder
+const OWNER_FILE: &str = ".bm25-writer.owner";
+
+/// Tantivy's own lock files, stale after a crash
+const TANTIVY_LOCK_FILES: [&str; 2] = [".tantivy-writer.lock", ".tantivy-meta.lock"];
+
+/// How often the holder refreshes its heartbeat
+pub(crate) const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Poll interval while waiting for another process's writer
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Process recorded as the holder of a writer lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LockOwner {
+    pub pid: u32,
+    /// Unix time in seconds of the last heartbeat
+    pub heartbeat: u64,
+}
+
+impl LockOwner {
+    fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            heartbeat: unix_now(),
+        }
+    }
+
+    /// Owner recorded in `path`, if any
+    fn read(path: &Path) -> Option<Self> {
+        let record = fs::read_to_string(path).ok()?;
+        let (pid, heartbeat) = record.trim().split_once(' ')?;
+        Some(Self {
+            pid: pid.parse().ok()?,
+            heartbeat: heartbeat.parse().ok()?,
+        })
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, format!("{} {}", self.pid, self.heartbeat))
+            .context("Failed to write BM25 writer lock owner")
+    }
+
+    /// Whether the owning process is still running
+    ///
+    /// A dead PID means the owner crashed. A live PID only counts while the
+    /// heartbeat is younger than `heartbeat_timeout`, since PIDs are reused;
+    /// where PIDs cannot be checked, the heartbeat alone decides.
+    pub fn is_alive(&self, heartbeat_timeout: Duration) -> bool {
+        let heartbeat_age = unix_now().saturating_sub(self.heartbeat);
+        pid_alive(self.pid).unwrap_or(true) && heartbeat_age <= heartbeat_timeout.as_secs()
+    }
+}
+
+/// Guard holding the cross-process writer lock of an index directory
+///
+/// Dropping it removes the owner record and releases the lock. If the process
+/// crashes, the OS releases the lock and the owner record stays behind.
+pub(crate) struct WriterLock {
+    _file: File,
+    index_path: PathBuf,
+    /// Owner record left behind by a writer that crashed while holding the lock
+    previous_owner: Option<LockOwner>,
+    /// Dropping the sender stops the heartbeat thread
+    heartbeat: Option<(mpsc::Sender<()>, JoinHandle<()>)>,
+}
+
+impl WriterLock {
+    /// Acquire the writer lock of `index_path`, waiting up to `timeout` for
+    /// another process to finish writing
+    pub fn acquire(index_path: &Path, timeout: Duration) -> Result<Self> {
+        let file = File::create(index_path.join(LOCK_FILE))
+            .context("Failed to create BM25 writer lock file")?;
+        let owner_path = index_path.join(OWNER_FILE);
+
+        let start = Instant::now();
+        loop {
+            match file.try_lock_exclusive() {
+                Ok(()) => break,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    if start.elapsed() >= timeout {
+                        let holder = LockOwner::read(&owner_path)
+                            .map(|owner| format!(" (pid {})", owner.pid))
+                            .unwrap_or_default();
+                        anyhow::bail!(
+                            "BM25 index at {} is being written by another process{}; gave up after {:?}",
+                            index_path.display(),
+                            holder,
+                            timeout
+                        );
+                    }
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+                Err(e) => return Err(e).context("Failed to acquire BM25 writer lock"),
+            }
+        }
+
+        // An owner record that is still present belongs to a crashed writer
+        let previous_owner = LockOwner::read(&owner_path);
+        LockOwner::current().write(&owner_path)?;
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let heartbeat = std::thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(HEARTBEAT_INTERVAL) {
+                if let Err(e) = LockOwner::current().write(&owner_path) {
+                    tracing::debug!("Failed to refresh BM25 writer heartbeat: {:#}", e);
+                }
+            }
+        });
+
+        Ok(Self {
+            _file: file,
+            index_path: index_path.to_path_buf(),
+            previous_owner,
+            heartbeat: Some((stop, heartbeat)),
+        })
+    }
+
+    /// Remove Tantivy lock files left behind by a crashed writer
+    ///
+    /// Returns `Ok(false)`, leaving the files in place, while the previous
+    /// owner is still alive (see [`LockOwner::is_alive`]).
+    pub fn reclaim_stale_locks(&self, heartbeat_timeout: Duration) -> Result<bool> {
+        if let Some(owner) = self.previous_owner
+            && owner.is_alive(heartbeat_timeout)
+        {
+            return Ok(false);
+        }
+
+        for name in TANTIVY_LOCK_FILES {
+            let lock_path = self.index_path.join(name);
+            if lock_path.exists() {
+                tracing::warn!(
+                    "Removing stale Tantivy lock file left by a dead writer{}: {:?}",
+                    self.previous_owner
+                        .map(|owner| format!(" (pid {})", owner.pid))
+                        .unwrap_or_default(),
+                    lock_path
+                );
+                fs::remove_file(&lock_path)
+                    .with_context(|| format!("Failed to remove stale lock file {:?}", lock_path))?;
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl Drop for WriterLock {
+    fn drop(&mut self) {
+        if let Some((stop, heartbeat)) = self.heartbeat.take() {
+            drop(stop);
+            let _ = heartbeat.join();
+        }
+        // Remove the record before the file closes and the lock is released
+        let _ = fs::remove_file(self.index_path.join(OWNER_FILE));
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Whether a process with `pid` is running (`None` if it cannot be checked)
+#[cfg(target_os = "linux")]
+fn pid_alive(pid: u32) -> Option<bool> {
+    Some(Path::new("/proc").join(pid.to_string()).exists())
+}
+
+/// Whether a process with `pid` is running (`None` if it cannot be checked)
+#[cfg(all(unix, not(target_os = "linux")))]
+fn pid_alive(pid: u32) -> Option<bool> {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .ok()
+        .map(|status| status.success())
+}
+
+/// Whether a process with `pid` is running (`None` if it cannot be checked)
+#[cfg(not(unix))]
+fn pid_alive(_pid: u32) -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bm25_search::DEFAULT_STALE_LOCK_TIMEOUT;
+    use crate::error::{RagError, VectorDbError};
+    use tantivy::{Index, IndexWriter, TantivyDocument};
+    use tempfile::TempDir;
+
+    /// PID of a process that has already exited
+    fn dead_pid() -> u32 {
+        let mut child = std::process::Command::new(std::env::current_exe().unwrap())
+            .arg("--list")
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        child.wait().unwrap();
+        child.id()
+    }
+
+    fn leave_tantivy_locks(dir: &Path) {
+        for name in TANTIVY_LOCK_FILES {
+            fs::write(dir.join(name), "").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_dead_owner_locks_are_reclaimed() {
+        let dir = TempDir::new().unwrap();
+        leave_tantivy_locks(dir.path());
+        let crashed = LockOwner {
+            pid: dead_pid(),
+            heartbeat: unix_now(),
+        };
+        crashed.write(&dir.path().join(OWNER_FILE)).unwrap();
+        assert!(!crashed.is_alive(DEFAULT_STALE_LOCK_TIMEOUT));
+
+        let lock = WriterLock::acquire(dir.path(), Duration::ZERO).unwrap();
+        assert!(
+            lock.reclaim_stale_locks(DEFAULT_STALE_LOCK_TIMEOUT)
+                .unwrap()
+        );
+        for name in TANTIVY_LOCK_FILES {
+            assert!(!dir.path().join(name).exists());
+        }
+    }
+
+    #[test]
+    fn test_live_owner_locks_are_respected() {
+        let dir = TempDir::new().unwrap();
+        leave_tantivy_locks(dir.path());
+        LockOwner::current()
+            .write(&dir.path().join(OWNER_FILE))
+            .unwrap();
+
+        let lock = WriterLock::acquire(dir.path(), Duration::ZERO).unwrap();
+        assert!(
+            !lock
+                .reclaim_stale_locks(DEFAULT_STALE_LOCK_TIMEOUT)
+                .unwrap()
+        );
+        for name in TANTIVY_LOCK_FILES {
+            assert!(dir.path().join(name).exists());
+        }
+    }
+
+    #[test]
+    fn test_lock_excludes_other_writers_until_dropped() {
+        let dir = TempDir::new().unwrap();
+        let owner_path = dir.path().join(OWNER_FILE);
+
+        let lock = WriterLock::acquire(dir.path(), Duration::ZERO).unwrap();
+        assert_eq!(
+            LockOwner::read(&owner_path).unwrap().pid,
+            std::process::id()
+        );
+
+        let err = WriterLock::acquire(dir.path(), Duration::from_millis(200))
+            .err()
+            .expect("a second writer should time out");
+        assert!(err.to_string().contains("another process"));
+
+        drop(lock);
+        assert!(!owner_path.exists());
+        assert!(WriterLock::acquire(dir.path(), Duration::ZERO).is_ok());
+    }
+
+    #[test]
+    fn test_index_writer_stays_open_until_flushed() {
+        let dir = TempDir::new().unwrap();
+        let bm25 = crate::bm25_search::BM25Search::new(dir.path()).unwrap();
+        let document = |id: u64| (id, format!("fn parse_{}() {{}}", id), "a.rs".to_string());
+
+        bm25.add_documents(vec![document(1)]).unwrap();
+        assert!(WriterLock::acquire(dir.path(), Duration::ZERO).is_err());
+        // Reads commit the pending writes first
+        assert_eq!(bm25.search("parse", 10).unwrap().len(), 1);
+
+        bm25.flush().unwrap();
+        assert!(WriterLock::acquire(dir.path(), Duration::ZERO).is_ok());
+
+        // Dropping the index commits what is still pending
+        bm25.add_documents(vec![document(2)]).unwrap();
+        drop(bm25);
+        let reopened = crate::bm25_search::BM25Search::new(dir.path()).unwrap();
+        assert_eq!(reopened.get_stats().unwrap().total_documents, 2);
+    }
+
+    #[test]
+    fn test_locks_are_kept_when_auto_unlock_is_disabled() {
+        let dir = TempDir::new().unwrap();
+        let bm25 = crate::bm25_search::BM25Search::new(dir.path())
+            .unwrap()
+            .with_stale_lock_timeout(None);
+
+        // A writer outside the cross-process lock holds Tantivy's own lock
+        let _foreign_writer: IndexWriter<TantivyDocument> = Index::open_in_dir(dir.path())
+            .unwrap()
+            .writer_with_num_threads(1, 15_000_000)
+            .unwrap();
+
+        let err = bm25
+            .add_documents(vec![(1, "fn parse() {}".to_string(), "a.rs".to_string())])
+            .expect_err("the index is locked");
+        assert!(matches!(
+            err.downcast_ref::<RagError>(),
+            Some(RagError::VectorDb(VectorDbError::Bm25Locked { .. }))
+        ));
+        assert!(dir.path().join(TANTIVY_LOCK_FILES[0]).exists());
+    }
+
+    #[test]
+    fn test_failed_write_is_not_committed() {
+        let dir = TempDir::new().unwrap();
+        let bm25 = crate::bm25_search::BM25Search::new(dir.path()).unwrap();
+        bm25.add_documents(vec![(1, "fn kept() {}".to_string(), "a.rs".to_string())])
+            .unwrap();
+        bm25.flush().unwrap();
+
+        let failed = bm25.with_writer(|writer| -> Result<()> {
+            writer.delete_all_documents()?;
+            anyhow::bail!("interrupted")
+        });
+        assert!(failed.is_err());
+
+        // Neither a later flush nor the next write commits the partial write
+        bm25.add_documents(vec![(2, "fn added() {}".to_string(), "b.rs".to_string())])
+            .unwrap();
+        bm25.flush().unwrap();
+        assert_eq!(bm25.get_stats().unwrap().total_documents, 2);
+    }
+}
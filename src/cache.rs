@@ -2,6 +2,7 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -59,6 +60,34 @@ impl Default for DirtyInfo {
     }
 }
 
+/// On-disk format of the hash cache file
+///
+/// Either format can be loaded regardless of the configured format; the
+/// setting only controls how the file is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheFormat {
+    /// Indented JSON, easy to inspect by hand
+    #[default]
+    Pretty,
+    /// Binary (bincode) encoding, smaller and faster to read and write than
+    /// JSON for caches with many files
+    Compact,
+}
+
+/// Outcome of rewriting the hash cache in the compact format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheCompaction {
+    /// Number of indexed roots in the cache
+    pub roots: usize,
+    /// Number of file hashes across all roots
+    pub files: usize,
+    /// Size of the cache file before compaction (0 if it did not exist)
+    pub bytes_before: u64,
+    /// Size of the cache file after compaction
+    pub bytes_after: u64,
+}
+
 /// Cache for file hashes to support incremental updates
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct HashCache {
@@ -68,6 +97,16 @@ pub struct HashCache {
     /// If a root is in this map, its index may be incomplete/corrupted
     #[serde(default)]
     pub dirty_roots: HashMap<String, DirtyInfo>,
+    /// Map of root path -> embedding model, for roots indexed with a non-default model
+    #[serde(default)]
+    pub root_models: HashMap<String, String>,
+    /// Map of root path -> (file path -> modification time in nanoseconds), letting
+    /// incremental updates skip hashing files that were not touched
+    #[serde(default)]
+    pub mtimes: HashMap<String, HashMap<String, u64>>,
+    /// Format used when saving (not persisted; set from `cache.format`)
+    #[serde(skip)]
+    pub format: CacheFormat,
 }
 
 /// Legacy cache format for migration (dirty_roots was a HashSet)
@@ -87,16 +126,25 @@ impl HashCache {
             return Ok(Self::default());
         }
 
-        let content = fs::read_to_string(cache_path).context("Failed to read cache file")?;
+        let content = fs::read(cache_path).context("Failed to read cache file")?;
 
         // Try to parse as new format first
-        if let Ok(cache) = serde_json::from_str::<HashCache>(&content) {
+        if let Ok(cache) = serde_json::from_slice::<HashCache>(&content) {
             tracing::info!("Loaded cache with {} indexed roots", cache.roots.len());
             return Ok(cache);
         }
 
+        // Then as a compact (binary) cache
+        if let Ok(cache) = bincode::deserialize::<HashCache>(&content) {
+            tracing::info!(
+                "Loaded compact cache with {} indexed roots",
+                cache.roots.len()
+            );
+            return Ok(cache);
+        }
+
         // Try to parse as legacy format and migrate
-        if let Ok(legacy) = serde_json::from_str::<LegacyHashCache>(&content) {
+        if let Ok(legacy) = serde_json::from_slice::<LegacyHashCache>(&content) {
             tracing::info!(
                 "Migrating cache from legacy format ({} roots, {} dirty roots)",
                 legacy.roots.len(),
@@ -113,6 +161,9 @@ impl HashCache {
             let cache = HashCache {
                 roots: legacy.roots,
                 dirty_roots,
+                root_models: HashMap::new(),
+                mtimes: HashMap::new(),
+                format: CacheFormat::default(),
             };
 
             // Save the migrated cache immediately
@@ -126,7 +177,7 @@ impl HashCache {
         }
 
         // Neither format worked
-        anyhow::bail!("Failed to parse cache file as either new or legacy format")
+        anyhow::bail!("Failed to parse cache file as either new, compact or legacy format")
     }
 
     /// Save cache to disk
@@ -136,28 +187,99 @@ impl HashCache {
             fs::create_dir_all(parent).context("Failed to create cache directory")?;
         }
 
-        let content = serde_json::to_string_pretty(self).context("Failed to serialize cache")?;
-
-        fs::write(cache_path, content).context("Failed to write cache file")?;
+        let file = fs::File::create(cache_path).context("Failed to write cache file")?;
+        let mut writer = BufWriter::new(file);
+        match self.format {
+            CacheFormat::Pretty => serde_json::to_writer_pretty(&mut writer, self)
+                .context("Failed to serialize cache")?,
+            CacheFormat::Compact => {
+                bincode::serialize_into(&mut writer, self).context("Failed to serialize cache")?
+            }
+        }
+        writer.flush().context("Failed to write cache file")?;
 
         tracing::debug!("Saved cache to {:?}", cache_path);
         Ok(())
     }
 
+    /// Rewrite the cache file in the compact format
+    ///
+    /// Switches this cache to [`CacheFormat::Compact`] until it is dropped. A
+    /// client sets the format from `cache.format` when it starts, so set
+    /// `cache.format = "compact"` to keep the file compact across restarts.
+    pub fn compact(&mut self, cache_path: &Path) -> Result<CacheCompaction> {
+        let bytes_before = fs::metadata(cache_path).map(|m| m.len()).unwrap_or(0);
+
+        self.format = CacheFormat::Compact;
+        self.save(cache_path)?;
+
+        let bytes_after = fs::metadata(cache_path)
+            .context("Failed to read compacted cache file")?
+            .len();
+
+        Ok(CacheCompaction {
+            roots: self.roots.len(),
+            files: self.roots.values().map(HashMap::len).sum(),
+            bytes_before,
+            bytes_after,
+        })
+    }
+
     /// Get file hashes for a root path
     pub fn get_root(&self, root: &str) -> Option<&HashMap<String, String>> {
         self.roots.get(root)
     }
 
     /// Update file hashes for a root path
+    ///
+    /// Drops the root's recorded modification times, which may not match the
+    /// new hashes; see [`set_root_mtimes`](Self::set_root_mtimes).
     pub fn update_root(&mut self, root: String, hashes: HashMap<String, String>) {
+        self.mtimes.remove(&root);
         self.roots.insert(root, hashes);
     }
 
+    /// Get the modification times of the hashed files of a root path
+    pub fn get_root_mtimes(&self, root: &str) -> Option<&HashMap<String, u64>> {
+        self.mtimes.get(root)
+    }
+
+    /// Record the modification times of a root path's files, as of their hashes
+    ///
+    /// Times of files without a hash are not kept, nor those of files cached
+    /// with an empty hash to be embedded again, which an unchanged time would
+    /// otherwise skip.
+    pub fn set_root_mtimes(&mut self, root: &str, mut mtimes: HashMap<String, u64>) {
+        let Some(hashes) = self.roots.get(root) else {
+            return;
+        };
+        mtimes.retain(|file, _| hashes.get(file).is_some_and(|hash| !hash.is_empty()));
+        self.mtimes.insert(root.to_string(), mtimes);
+    }
+
     /// Remove a root path from the cache
     pub fn remove_root(&mut self, root: &str) {
         self.roots.remove(root);
         self.dirty_roots.remove(root);
+        self.root_models.remove(root);
+        self.mtimes.remove(root);
+    }
+
+    /// Get the non-default embedding model a root path was indexed with
+    pub fn root_model(&self, root: &str) -> Option<&str> {
+        self.root_models.get(root).map(String::as_str)
+    }
+
+    /// Record the embedding model for a root path (`None` for the default model)
+    pub fn set_root_model(&mut self, root: &str, model_name: Option<String>) {
+        match model_name {
+            Some(model_name) => {
+                self.root_models.insert(root.to_string(), model_name);
+            }
+            None => {
+                self.root_models.remove(root);
+            }
+        }
     }
 
     /// Mark a root path as dirty (indexing in progress)
@@ -344,6 +466,36 @@ mod tests {
         assert!(path.to_string_lossy().contains("hash_cache.json"));
     }
 
+    #[test]
+    fn test_root_mtimes_follow_hashes() {
+        let mut cache = HashCache::default();
+        let hashes = HashMap::from([
+            ("file1.rs".to_string(), "hash1".to_string()),
+            ("file3.rs".to_string(), String::new()),
+        ]);
+        cache.update_root("/test/path".to_string(), hashes.clone());
+
+        // Only files with a (non-empty) hash keep their modification time
+        let mtimes = HashMap::from([
+            ("file1.rs".to_string(), 1),
+            ("file2.rs".to_string(), 2),
+            ("file3.rs".to_string(), 3),
+        ]);
+        cache.set_root_mtimes("/test/path", mtimes);
+        assert_eq!(
+            cache.get_root_mtimes("/test/path"),
+            Some(&HashMap::from([("file1.rs".to_string(), 1)]))
+        );
+
+        // New hashes invalidate the times, which may belong to other content
+        cache.update_root("/test/path".to_string(), hashes);
+        assert!(cache.get_root_mtimes("/test/path").is_none());
+
+        // Roots without hashes record no times
+        cache.set_root_mtimes("/other", HashMap::from([("file1.rs".to_string(), 1)]));
+        assert!(cache.get_root_mtimes("/other").is_none());
+    }
+
     #[test]
     fn test_update_root_replaces_existing() {
         let mut cache = HashCache::default();
@@ -396,6 +548,56 @@ mod tests {
         assert_eq!(cache.roots.len(), 0);
     }
 
+    #[test]
+    fn test_compact_preserves_roots_and_reduces_size() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let cache_path = temp_file.path().to_path_buf();
+
+        let mut cache = HashCache::default();
+        for root in ["/repo/a", "/repo/b"] {
+            let hashes: HashMap<String, String> = (0..50)
+                .map(|i| (format!("src/file_{}.rs", i), format!("{:064x}", i)))
+                .collect();
+            cache.update_root(root.to_string(), hashes);
+        }
+        cache.mark_dirty("/repo/b");
+        cache.set_root_model("/repo/a", Some("BAAI/bge-small-en-v1.5".to_string()));
+        cache.save(&cache_path).unwrap();
+
+        let compaction = cache.compact(&cache_path).unwrap();
+        assert_eq!(compaction.roots, 2);
+        assert_eq!(compaction.files, 100);
+        assert!(compaction.bytes_after < compaction.bytes_before);
+        assert_eq!(
+            compaction.bytes_after,
+            fs::metadata(&cache_path).unwrap().len()
+        );
+        assert_eq!(cache.format, CacheFormat::Compact);
+        let content = fs::read(&cache_path).unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&content).is_err());
+
+        let loaded = HashCache::load(&cache_path).unwrap();
+        assert_eq!(loaded.roots, cache.roots);
+        assert!(loaded.is_dirty("/repo/b"));
+        assert_eq!(loaded.root_model("/repo/a"), Some("BAAI/bge-small-en-v1.5"));
+    }
+
+    #[test]
+    fn test_root_model_operations() {
+        let mut cache = HashCache::default();
+        assert_eq!(cache.root_model("/project"), None);
+
+        cache.set_root_model("/project", Some("BAAI/bge-small-en-v1.5".to_string()));
+        assert_eq!(cache.root_model("/project"), Some("BAAI/bge-small-en-v1.5"));
+
+        cache.set_root_model("/project", None);
+        assert_eq!(cache.root_model("/project"), None);
+
+        cache.set_root_model("/project", Some("BAAI/bge-small-en-v1.5".to_string()));
+        cache.remove_root("/project");
+        assert_eq!(cache.root_model("/project"), None);
+    }
+
     #[test]
     fn test_dirty_flag_operations() {
         let mut cache = HashCache::default();
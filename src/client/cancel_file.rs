@@ -0,0 +1,40 @@
+//! File-based cancellation signal for indexing
+//!
+//! Lets a process without access to the `CancellationToken` (a script, an
+//! editor task, another terminal) stop a running CLI index by creating a file.
+
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Interval between checks for the cancel file
+pub const CANCEL_FILE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Cancel `token` once a file appears at `path`
+///
+/// A file already present when watching starts is left over from an earlier
+/// run and removed; the file that triggers cancellation is removed as well,
+/// so the next run isn't cancelled right away. The task ends once the token
+/// is cancelled, by the file or otherwise.
+pub fn cancel_on_file(path: PathBuf, token: CancellationToken) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        if path.exists() {
+            tracing::warn!("Removing stale cancel file {:?}", path);
+            let _ = std::fs::remove_file(&path);
+        }
+
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => return,
+                _ = tokio::time::sleep(CANCEL_FILE_POLL_INTERVAL) => {}
+            }
+            if path.exists() {
+                tracing::info!("Cancel file {:?} found, cancelling indexing", path);
+                let _ = std::fs::remove_file(&path);
+                token.cancel();
+                return;
+            }
+        }
+    })
+}
@@ -3,9 +3,12 @@
 //! This module provides semantic search over git commit history with on-demand indexing.
 
 use crate::embedding::EmbeddingProvider;
-use crate::git::{CommitChunker, GitWalker};
+use crate::git::walker::CommitInfo;
+use crate::git::{CommitChunker, GitWalker, TagInfo};
 use crate::git_cache::GitCache;
-use crate::types::{GitSearchResult, SearchGitHistoryRequest, SearchGitHistoryResponse};
+use crate::types::{
+    GitEntryKind, GitSearchResult, SearchGitHistoryRequest, SearchGitHistoryResponse,
+};
 use crate::vector_db::VectorDatabase;
 use anyhow::{Context, Result};
 use chrono::DateTime;
@@ -25,7 +28,7 @@ pub async fn do_search_git_history<E, V>(
     req: SearchGitHistoryRequest,
 ) -> Result<SearchGitHistoryResponse>
 where
-    E: EmbeddingProvider + Send + Sync,
+    E: EmbeddingProvider + Send + Sync + ?Sized,
     V: VectorDatabase + Send + Sync,
 {
     let start_time = Instant::now();
@@ -53,11 +56,19 @@ where
 
     tracing::info!("Discovered git repository at: {}", repo_path);
 
+    // Branch recorded on newly indexed commits (HEAD's branch if none was given)
+    let branch_name = req.branch.clone().or_else(|| walker.current_branch());
+
     // Parse date filters if provided
     let since_timestamp = req.since.as_ref().and_then(|s| parse_date_filter(s).ok());
 
     let until_timestamp = req.until.as_ref().and_then(|s| parse_date_filter(s).ok());
 
+    let author_regex = req
+        .author
+        .as_ref()
+        .and_then(|pattern| Regex::new(pattern).ok());
+
     // Determine which commits to index (on-demand strategy)
     let mut git_cache_guard = git_cache.write().await;
     let cached_commits = git_cache_guard
@@ -65,78 +76,161 @@ where
         .cloned()
         .unwrap_or_default();
 
-    let cached_count = cached_commits.len();
-    tracing::info!("Found {} cached commits for this repo", cached_count);
-
-    // Decide if we need to index more commits
-    let commits_to_index = if cached_count >= req.max_commits {
-        tracing::info!("Cache has enough commits, skipping indexing");
-        0
-    } else {
-        req.max_commits - cached_count
-    };
-
-    let mut newly_indexed = 0;
-
-    if commits_to_index > 0 {
-        tracing::info!("Need to index {} more commits", commits_to_index);
-
-        // Walk git history and extract new commits
-        let commits = tokio::task::spawn_blocking({
-            let branch = req.branch.clone();
-            let max = Some(req.max_commits); // Walk up to max_commits
-            move || {
-                walker.iter_commits(
-                    branch.as_deref(),
-                    max,
-                    since_timestamp,
-                    until_timestamp,
-                    &cached_commits,
-                )
-            }
-        })
-        .await
-        .context("Failed to spawn blocking task for commit iteration")??;
-
-        newly_indexed = commits.len();
-        tracing::info!("Extracted {} new commits from git history", newly_indexed);
-
-        if newly_indexed > 0 {
-            // Convert commits to chunks
-            let chunker = CommitChunker::new();
-            let chunks = chunker.commits_to_chunks(&commits, &repo_path, req.project.clone())?;
-
-            tracing::info!("Created {} chunks from commits", chunks.len());
-
-            // Generate embeddings in batches
-            let contents: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
-            let metadatas = chunks.iter().map(|c| c.metadata.clone()).collect();
+    let cached_tags = git_cache_guard
+        .get_tags(&repo_path)
+        .cloned()
+        .unwrap_or_default();
 
-            let embeddings = embedding_provider
-                .embed_batch(contents.clone())
-                .context("Failed to generate embeddings for commits")?;
+    tracing::info!(
+        "Found {} cached commits for this repo",
+        cached_commits.len()
+    );
 
-            tracing::info!("Generated {} embeddings", embeddings.len());
+    // Commits are cached per repository rather than per branch, so history
+    // shared between branches is only indexed once. Index whatever part of
+    // this branch's most recent `max_commits` commits isn't cached yet.
+    // Annotated tags are few, so all of them are indexed.
+    let (rebuild, commits, tags) = tokio::task::spawn_blocking({
+        let branch = req.branch.clone();
+        let max = req.max_commits;
+        let kind = req.kind;
+        let reset = req.reset;
+        let author_regex = author_regex.clone();
+        move || -> Result<(bool, Vec<CommitInfo>, Vec<TagInfo>)> {
+            // A rebase or force push leaves cached commits that nothing reaches
+            // any more. All entries of a repository share its `git://` path, so
+            // they can't be deleted one by one and the history is rebuilt.
+            let rebuild = reset || {
+                let stale = walker.unreachable_commits(&cached_commits)?;
+                if !stale.is_empty() {
+                    tracing::info!(
+                        "{} cached commits are no longer reachable, rebuilding git history index",
+                        stale.len()
+                    );
+                }
+                !stale.is_empty()
+            };
+            let (cached_commits, cached_tags) = if rebuild {
+                (HashSet::new(), HashSet::new())
+            } else {
+                (cached_commits, cached_tags)
+            };
 
-            // Store in vector database (use repo_path for per-project BM25)
-            let stored = vector_db
-                .store_embeddings(embeddings, metadatas, contents, &repo_path)
-                .await
-                .context("Failed to store commit embeddings")?;
+            let tags = if kind.includes_tags() {
+                walker.iter_tags(&cached_tags)?
+            } else {
+                Vec::new()
+            };
+            if !kind.includes_commits() {
+                return Ok((rebuild, Vec::new(), tags));
+            }
 
-            tracing::info!("Stored {} commit embeddings in vector database", stored);
+            let window = walker.recent_commit_hashes(
+                branch.as_deref(),
+                max,
+                since_timestamp,
+                until_timestamp,
+                author_regex.as_ref(),
+            )?;
+            let commits_to_index = window
+                .iter()
+                .filter(|hash| !cached_commits.contains(*hash))
+                .count();
+
+            if commits_to_index == 0 {
+                tracing::info!("Cache has all commits for this branch, skipping indexing");
+                return Ok((rebuild, Vec::new(), tags));
+            }
 
-            // Update cache with new commit hashes
-            let new_hashes: HashSet<String> = commits.iter().map(|c| c.hash.clone()).collect();
-            git_cache_guard.add_commits(repo_path.clone(), new_hashes);
+            tracing::info!("Need to index {} more commits", commits_to_index);
+            let commits = walker.iter_commits(
+                branch.as_deref(),
+                Some(commits_to_index),
+                since_timestamp,
+                until_timestamp,
+                author_regex.as_ref(),
+                &cached_commits,
+            )?;
+            Ok((rebuild, commits, tags))
+        }
+    })
+    .await
+    .context("Failed to spawn blocking task for commit iteration")??;
+
+    if rebuild {
+        let deleted = vector_db
+            .delete_by_file(&format!("git://{}", repo_path))
+            .await
+            .context("Failed to delete indexed git history")?;
+        vector_db
+            .flush()
+            .await
+            .context("Failed to flush deleted git history")?;
+        git_cache_guard.remove_repo(&repo_path);
+        git_cache_guard
+            .save(cache_path)
+            .context("Failed to save git cache")?;
+        tracing::info!(
+            "Deleted {} indexed git history entries for {}",
+            deleted,
+            repo_path
+        );
+    }
 
-            // Persist cache to disk
-            git_cache_guard
-                .save(cache_path)
-                .context("Failed to save git cache")?;
+    let newly_indexed = commits.len();
+    tracing::info!("Extracted {} new commits from git history", newly_indexed);
 
-            tracing::info!("Updated git cache with {} new commits", newly_indexed);
+    if newly_indexed > 0 || !tags.is_empty() {
+        // Convert commits and tags to chunks
+        let chunker = CommitChunker::new().with_branch(branch_name);
+        let mut chunks = chunker.commits_to_chunks(&commits, &repo_path, req.project.clone())?;
+        for tag in &tags {
+            chunks.push(chunker.tag_to_chunk(tag, &repo_path, req.project.clone())?);
         }
+
+        tracing::info!("Created {} chunks from commits and tags", chunks.len());
+
+        // Generate embeddings in batches
+        let contents: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let metadatas = chunks.iter().map(|c| c.metadata.clone()).collect();
+
+        let embeddings = embedding_provider
+            .embed_batch(contents.clone())
+            .context("Failed to generate embeddings for commits")?;
+
+        tracing::info!("Generated {} embeddings", embeddings.len());
+
+        // Store in vector database (use repo_path for per-project BM25)
+        let stored = vector_db
+            .store_embeddings(embeddings, metadatas, contents, &repo_path)
+            .await
+            .context("Failed to store commit embeddings")?;
+
+        tracing::info!("Stored {} commit embeddings in vector database", stored);
+
+        // Commit the keyword index, releasing its writer for other processes
+        // (writers otherwise stay open until the next flush)
+        vector_db
+            .flush()
+            .await
+            .context("Failed to flush commit embeddings")?;
+
+        // Update cache with new commit hashes
+        let new_hashes: HashSet<String> = commits.iter().map(|c| c.hash.clone()).collect();
+        git_cache_guard.add_commits(repo_path.clone(), new_hashes);
+        let new_tags: HashSet<String> = tags.iter().map(|t| t.hash.clone()).collect();
+        git_cache_guard.add_tags(repo_path.clone(), new_tags);
+
+        // Persist cache to disk
+        git_cache_guard
+            .save(cache_path)
+            .context("Failed to save git cache")?;
+
+        tracing::info!(
+            "Updated git cache with {} new commits and {} new tags",
+            newly_indexed,
+            tags.len()
+        );
     }
 
     drop(git_cache_guard); // Release write lock before search
@@ -151,32 +245,37 @@ where
         .next()
         .context("No query embedding generated")?;
 
-    // Search vector database for git commits
-    // Filter by language="git-commit" to only get commits
+    // Search vector database for the requested kinds of git entries
+    let mut languages = Vec::new();
+    if req.kind.includes_commits() {
+        languages.push("git-commit".to_string());
+    }
+    if req.kind.includes_tags() {
+        languages.push("git-tag".to_string());
+    }
+
     let search_results = vector_db
         .search_filtered(
             query_vector,
             &req.query,
             req.limit * 2, // Get more results for post-filtering
             req.min_score,
-            req.project.clone(),
-            None,                           // root_path
-            true,                           // hybrid search
-            vec![],                         // no extension filter
-            vec!["git-commit".to_string()], // filter by git-commit language
-            vec![],                         // no path pattern
+            req.project.as_slice(),
+            None,      // root_path
+            true,      // hybrid search
+            vec![],    // no extension filter
+            languages, // filter by git entry language
+            vec![],    // no path pattern
+            vec![],    // no symbol kind filter
         )
         .await
         .context("Failed to search vector database")?;
 
     tracing::info!("Found {} search results", search_results.len());
 
-    // Post-process results and apply regex filters
-    let author_regex = req
-        .author
-        .as_ref()
-        .and_then(|pattern| Regex::new(pattern).ok());
-
+    // Post-process results and apply regex filters. Commits were already
+    // filtered by date and author while walking, but earlier searches may have
+    // cached commits outside this request's filters.
     let file_pattern_regex = req
         .file_pattern
         .as_ref()
@@ -190,12 +289,57 @@ where
             continue;
         }
 
-        // Extract commit hash from file_hash field
+        if result.language == "git-tag" {
+            let tag = parse_tag_content(&result.content);
+
+            if !in_date_range(tag.tag_date, since_timestamp, until_timestamp) {
+                continue;
+            }
+
+            // Taggers are matched like authors; tags have no changed files
+            if let Some(ref regex) = author_regex
+                && !(regex.is_match(&tag.tagger_name) || regex.is_match(&tag.tagger_email))
+            {
+                continue;
+            }
+            if file_pattern_regex.is_some() {
+                continue;
+            }
+
+            filtered_results.push(GitSearchResult {
+                commit_hash: tag.target_hash,
+                commit_message: tag.message,
+                author: tag.tagger_name,
+                author_email: tag.tagger_email,
+                commit_date: tag.tag_date,
+                score: result.score,
+                vector_score: result.vector_score,
+                keyword_score: result.keyword_score,
+                files_changed: Vec::new(),
+                diff_snippet: String::new(),
+                branch: None,
+                kind: GitEntryKind::Tag,
+                tag_name: Some(tag.name),
+            });
+            if filtered_results.len() >= req.limit {
+                break;
+            }
+            continue;
+        }
+
+        // Commits indexed before dates were stored carry the date in indexed_at
+        let commit_date = result.commit_date.or(result.indexed_at).unwrap_or(0);
+
+        // Cached commits may predate this request's date range
+        if !in_date_range(commit_date, since_timestamp, until_timestamp) {
+            continue;
+        }
+
+        // The chunk id of a git entry carries its commit hash
         let commit_hash = result
-            .file_path
-            .split('/')
-            .next_back()
-            .unwrap_or(&result.file_path);
+            .chunk_id
+            .strip_prefix("git:")
+            .unwrap_or(&result.chunk_id);
 
         // Parse content to extract commit details
         // Content format: "Commit Message:\n{message}\n\nAuthor: {name} <{email}>\n\nFiles Changed:\n..."
@@ -257,10 +401,6 @@ where
             String::new()
         };
 
-        // Parse commit date from start_line (we stored it there as a hack)
-        // Actually, we should get it from the vector DB metadata
-        let commit_date = 0; // TODO: Extract from proper metadata
-
         filtered_results.push(GitSearchResult {
             commit_hash: commit_hash.to_string(),
             commit_message,
@@ -272,6 +412,9 @@ where
             keyword_score: result.keyword_score,
             files_changed,
             diff_snippet,
+            branch: result.branch.clone(),
+            kind: GitEntryKind::Commit,
+            tag_name: None,
         });
 
         if filtered_results.len() >= req.limit {
@@ -291,6 +434,11 @@ where
     })
 }
 
+/// Whether a timestamp lies within the optional `since`/`until` bounds (inclusive)
+fn in_date_range(timestamp: i64, since: Option<i64>, until: Option<i64>) -> bool {
+    since.is_none_or(|since| timestamp >= since) && until.is_none_or(|until| timestamp <= until)
+}
+
 /// Parse a date filter string (ISO 8601 or Unix timestamp)
 pub(crate) fn parse_date_filter(date_str: &str) -> Result<i64> {
     // Try parsing as Unix timestamp first
@@ -311,6 +459,37 @@ pub(crate) fn parse_date_filter(date_str: &str) -> Result<i64> {
     anyhow::bail!("Invalid date format: {}", date_str)
 }
 
+/// Recover a tag from its chunk content (see [`CommitChunker::tag_to_chunk`])
+///
+/// The object hash is not part of the content and is left empty.
+pub(crate) fn parse_tag_content(content: &str) -> TagInfo {
+    let (header, message) = content
+        .split_once("\n\nTag Message:\n")
+        .unwrap_or((content, ""));
+
+    let mut tag = TagInfo {
+        hash: String::new(),
+        name: String::new(),
+        message: message.to_string(),
+        target_hash: String::new(),
+        tagger_name: String::new(),
+        tagger_email: String::new(),
+        tag_date: 0,
+    };
+    for line in header.lines() {
+        if let Some(name) = line.strip_prefix("Tag: ") {
+            tag.name = name.to_string();
+        } else if let Some(target) = line.strip_prefix("Target Commit: ") {
+            tag.target_hash = target.to_string();
+        } else if let Some(tagger) = line.strip_prefix("Tagger: ") {
+            (tag.tagger_name, tag.tagger_email) = parse_author_line(tagger);
+        } else if let Some(date) = line.strip_prefix("Date: ") {
+            tag.tag_date = date.parse().unwrap_or(0);
+        }
+    }
+    tag
+}
+
 /// Parse author line: "Author: Name <email>"
 pub(crate) fn parse_author_line(line: &str) -> (String, String) {
     let author_part = line.strip_prefix("Author: ").unwrap_or(line);
@@ -65,10 +65,12 @@ async fn test_search_git_history_first_time() {
         max_commits: 5,
         limit: 10,
         min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -108,11 +110,13 @@ async fn test_search_git_history_second_time_uses_cache() {
         max_commits: 5,
         limit: 10,
         min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     // First search
     let response1 = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -126,7 +130,7 @@ async fn test_search_git_history_second_time_uses_cache() {
 
     // Second search with same parameters
     let response2 = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -163,10 +167,12 @@ async fn test_search_git_history_with_author_filter() {
         max_commits: 5,
         limit: 10,
         min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -194,10 +200,12 @@ async fn test_search_git_history_with_file_pattern_filter() {
         max_commits: 5,
         limit: 10,
         min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -229,10 +237,12 @@ async fn test_search_git_history_with_date_filters() {
         max_commits: 5,
         limit: 10,
         min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -260,10 +270,12 @@ async fn test_search_git_history_with_project_isolation() {
         max_commits: 3,
         limit: 5,
         min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -296,10 +308,12 @@ async fn test_search_git_history_incremental_indexing() {
         max_commits: 2,
         limit: 10,
         min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let response1 = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -324,10 +338,12 @@ async fn test_search_git_history_incremental_indexing() {
         max_commits: 5,
         limit: 10,
         min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let response2 = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -347,6 +363,471 @@ async fn test_search_git_history_incremental_indexing() {
     );
 }
 
+/// Commit a new file on `branch`, creating the branch if it doesn't exist
+fn commit_to_branch(repo: &git2::Repository, branch: &str, file_name: &str, message: &str) {
+    let signature = git2::Signature::now("Test Author", "test@example.com").unwrap();
+    commit_signed(repo, branch, file_name, message, &signature);
+}
+
+/// Commit a file to `branch` with the given author and committer signature
+fn commit_signed(
+    repo: &git2::Repository,
+    branch: &str,
+    file_name: &str,
+    message: &str,
+    signature: &git2::Signature,
+) {
+    let workdir = repo.workdir().unwrap();
+    std::fs::write(workdir.join(file_name), message).unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(file_name)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+    let refname = format!("refs/heads/{}", branch);
+    let parent = repo
+        .find_reference(&refname)
+        .ok()
+        .and_then(|r| r.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+    repo.commit(
+        Some(&refname),
+        signature,
+        signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_search_git_history_shares_commits_across_branches() {
+    let (client, temp_dir) = create_test_client().await;
+    let cache_path = temp_dir.path().join("git_cache.json");
+
+    let repo_dir = temp_dir.path().join("repo");
+    std::fs::create_dir(&repo_dir).unwrap();
+    let repo = git2::Repository::init(&repo_dir).unwrap();
+    commit_to_branch(&repo, "main", "setup.txt", "Initial project setup");
+    commit_to_branch(&repo, "main", "parser.txt", "Add config parser");
+
+    let main_head = repo
+        .find_reference("refs/heads/main")
+        .unwrap()
+        .peel_to_commit()
+        .unwrap();
+    repo.branch("feature", &main_head, false).unwrap();
+    commit_to_branch(&repo, "feature", "retry.txt", "Add retry support");
+
+    let search = |branch: &str| SearchGitHistoryRequest {
+        query: "config parser".to_string(),
+        path: repo_dir.to_string_lossy().to_string(),
+        project: None,
+        branch: Some(branch.to_string()),
+        since: None,
+        until: None,
+        author: None,
+        file_pattern: None,
+        max_commits: 2,
+        limit: 10,
+        min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
+    };
+
+    let main_response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        search("main"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(main_response.commits_indexed, 2);
+
+    // The feature branch's two most recent commits include one shared with
+    // main; only the commit unique to the branch is indexed
+    let feature_response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        search("feature"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(feature_response.commits_indexed, 1);
+    assert_eq!(feature_response.total_cached_commits, 3);
+
+    // Going back to main re-indexes nothing
+    let main_again = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        search("main"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(main_again.commits_indexed, 0);
+    assert_eq!(main_again.total_cached_commits, 3);
+}
+
+#[tokio::test]
+async fn test_search_git_history_drops_commits_removed_from_history() {
+    let (client, temp_dir) = create_test_client().await;
+    let cache_path = temp_dir.path().join("git_cache.json");
+
+    let repo_dir = temp_dir.path().join("repo");
+    std::fs::create_dir(&repo_dir).unwrap();
+    let repo = git2::Repository::init(&repo_dir).unwrap();
+    commit_to_branch(&repo, "main", "setup.txt", "Initial project setup");
+    commit_to_branch(&repo, "main", "parser.txt", "Add config parser");
+    commit_to_branch(&repo, "main", "retry.txt", "Add retry support for uploads");
+
+    let search = |reset: bool| SearchGitHistoryRequest {
+        query: "retry support for uploads".to_string(),
+        path: repo_dir.to_string_lossy().to_string(),
+        project: None,
+        branch: Some("main".to_string()),
+        since: None,
+        until: None,
+        author: None,
+        file_pattern: None,
+        max_commits: 10,
+        limit: 10,
+        min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset,
+    };
+    let messages = |response: &SearchGitHistoryResponse| -> Vec<String> {
+        response
+            .results
+            .iter()
+            .map(|r| r.commit_message.clone())
+            .collect()
+    };
+
+    let response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        search(false),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.commits_indexed, 3);
+    assert!(messages(&response).contains(&"Add retry support for uploads".to_string()));
+
+    // Rewrite history: move main back to its parent, so nothing reaches the last commit
+    let head = repo
+        .find_reference("refs/heads/main")
+        .unwrap()
+        .peel_to_commit()
+        .unwrap();
+    let parent = head.parent(0).unwrap();
+    repo.reference("refs/heads/main", parent.id(), true, "drop last commit")
+        .unwrap();
+
+    let response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        search(false),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.commits_indexed, 2);
+    assert_eq!(response.total_cached_commits, 2);
+    let mut remaining = messages(&response);
+    remaining.sort();
+    assert_eq!(remaining, ["Add config parser", "Initial project setup"]);
+
+    // Nothing is stale now, so only a reset re-indexes the history
+    let response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        search(false),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.commits_indexed, 0);
+
+    let response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        search(true),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.commits_indexed, 2);
+    assert_eq!(response.results.len(), 2);
+}
+
+#[tokio::test]
+async fn test_search_git_history_returns_commit_dates() {
+    let (client, temp_dir) = create_test_client().await;
+    let cache_path = temp_dir.path().join("git_cache.json");
+
+    let repo_dir = temp_dir.path().join("repo");
+    std::fs::create_dir(&repo_dir).unwrap();
+    let repo = git2::Repository::init(&repo_dir).unwrap();
+    let signed_at = |timestamp: i64| {
+        git2::Signature::new(
+            "Test Author",
+            "test@example.com",
+            &git2::Time::new(timestamp, 0),
+        )
+        .unwrap()
+    };
+    // 2024-01-01 and 2024-06-01
+    commit_signed(
+        &repo,
+        "main",
+        "setup.txt",
+        "Initial project setup",
+        &signed_at(1704067200),
+    );
+    commit_signed(
+        &repo,
+        "main",
+        "parser.txt",
+        "Add config parser",
+        &signed_at(1717200000),
+    );
+
+    let search = |since: Option<&str>| SearchGitHistoryRequest {
+        query: "project setup and config parser".to_string(),
+        path: repo_dir.to_string_lossy().to_string(),
+        project: None,
+        branch: Some("main".to_string()),
+        since: since.map(String::from),
+        until: None,
+        author: None,
+        file_pattern: None,
+        max_commits: 10,
+        limit: 10,
+        min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
+    };
+
+    let response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        search(None),
+    )
+    .await
+    .unwrap();
+    let mut dates: Vec<(String, i64)> = response
+        .results
+        .iter()
+        .map(|r| (r.commit_message.clone(), r.commit_date))
+        .collect();
+    dates.sort();
+    assert_eq!(
+        dates,
+        vec![
+            ("Add config parser".to_string(), 1717200000),
+            ("Initial project setup".to_string(), 1704067200),
+        ]
+    );
+
+    // Both commits are cached now; `since` still drops the older one
+    let response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        search(Some("2024-03-01T00:00:00Z")),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(response.results[0].commit_date, 1717200000);
+}
+
+#[tokio::test]
+async fn test_search_git_history_date_window_skips_commits_while_walking() {
+    let (client, temp_dir) = create_test_client().await;
+    let cache_path = temp_dir.path().join("git_cache.json");
+
+    let repo_dir = temp_dir.path().join("repo");
+    std::fs::create_dir(&repo_dir).unwrap();
+    let repo = git2::Repository::init(&repo_dir).unwrap();
+    let signed_at = |timestamp: i64| {
+        git2::Signature::new(
+            "Test Author",
+            "test@example.com",
+            &git2::Time::new(timestamp, 0),
+        )
+        .unwrap()
+    };
+    // 2024-01-01, 2024-06-01 and 2024-09-01
+    commit_signed(
+        &repo,
+        "main",
+        "setup.txt",
+        "Initial project setup",
+        &signed_at(1704067200),
+    );
+    commit_signed(
+        &repo,
+        "main",
+        "parser.txt",
+        "Add config parser",
+        &signed_at(1717200000),
+    );
+    commit_signed(
+        &repo,
+        "main",
+        "retry.txt",
+        "Add retry support",
+        &signed_at(1725148800),
+    );
+
+    let response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        SearchGitHistoryRequest {
+            query: "project setup, config parser and retry support".to_string(),
+            path: repo_dir.to_string_lossy().to_string(),
+            project: None,
+            branch: Some("main".to_string()),
+            since: Some("2024-03-01T00:00:00Z".to_string()),
+            until: Some("2024-07-01T00:00:00Z".to_string()),
+            author: None,
+            file_pattern: None,
+            max_commits: 10,
+            limit: 10,
+            min_score: 0.0,
+            kind: GitEntryKind::Commit,
+            reset: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    // Only the commit inside the window is embedded and cached
+    assert_eq!(response.commits_indexed, 1);
+    assert_eq!(response.total_cached_commits, 1);
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(response.results[0].commit_date, 1717200000);
+}
+
+#[tokio::test]
+async fn test_search_git_history_author_filter_applies_while_walking() {
+    let (client, temp_dir) = create_test_client().await;
+    let cache_path = temp_dir.path().join("git_cache.json");
+
+    let repo_dir = temp_dir.path().join("repo");
+    std::fs::create_dir(&repo_dir).unwrap();
+    let repo = git2::Repository::init(&repo_dir).unwrap();
+    let alice = git2::Signature::now("Alice", "alice@example.com").unwrap();
+    let bob = git2::Signature::now("Bob", "bob@example.com").unwrap();
+    commit_signed(&repo, "main", "setup.txt", "Initial project setup", &alice);
+    commit_signed(&repo, "main", "parser.txt", "Add config parser", &bob);
+    commit_signed(&repo, "main", "retry.txt", "Add retry support", &bob);
+
+    let response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        SearchGitHistoryRequest {
+            query: "project setup".to_string(),
+            path: repo_dir.to_string_lossy().to_string(),
+            project: None,
+            branch: Some("main".to_string()),
+            since: None,
+            until: None,
+            author: Some("alice@".to_string()),
+            file_pattern: None,
+            max_commits: 1,
+            limit: 10,
+            min_score: 0.0,
+            kind: GitEntryKind::Commit,
+            reset: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    // Bob's newer commits neither use up `max_commits` nor get embedded
+    assert_eq!(response.commits_indexed, 1);
+    assert_eq!(response.total_cached_commits, 1);
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(response.results[0].author_email, "alice@example.com");
+}
+
+#[tokio::test]
+async fn test_search_git_history_results_carry_branch() {
+    let (client, temp_dir) = create_test_client().await;
+    let cache_path = temp_dir.path().join("git_cache.json");
+
+    let repo_dir = temp_dir.path().join("repo");
+    std::fs::create_dir(&repo_dir).unwrap();
+    let repo = git2::Repository::init(&repo_dir).unwrap();
+    commit_to_branch(&repo, "main", "setup.txt", "Initial project setup");
+
+    let main_head = repo
+        .find_reference("refs/heads/main")
+        .unwrap()
+        .peel_to_commit()
+        .unwrap();
+    repo.branch("feature", &main_head, false).unwrap();
+    commit_to_branch(&repo, "feature", "retry.txt", "Add retry support");
+
+    let req = SearchGitHistoryRequest {
+        query: "retry support".to_string(),
+        path: repo_dir.to_string_lossy().to_string(),
+        project: None,
+        branch: Some("feature".to_string()),
+        since: None,
+        until: None,
+        author: None,
+        file_pattern: None,
+        max_commits: 10,
+        limit: 10,
+        min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
+    };
+
+    let response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        req,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(response.commits_indexed, 2);
+    assert!(!response.results.is_empty());
+    assert!(
+        response
+            .results
+            .iter()
+            .all(|r| r.branch.as_deref() == Some("feature"))
+    );
+}
+
 #[tokio::test]
 async fn test_search_git_history_response_structure() {
     let (client, temp_dir) = create_test_client().await;
@@ -364,10 +845,12 @@ async fn test_search_git_history_response_structure() {
         max_commits: 5,
         limit: 10,
         min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let response = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -407,10 +890,12 @@ async fn test_search_git_history_invalid_path() {
         max_commits: 5,
         limit: 10,
         min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -439,10 +924,12 @@ async fn test_search_git_history_limit_respected() {
         max_commits: 10,
         limit: 3, // Limit to 3 results
         min_score: 0.0,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let response = do_search_git_history(
-        client.embedding_provider.clone(),
+        client.embedding_provider(),
         client.vector_db.clone(),
         client.git_cache.clone(),
         &cache_path,
@@ -457,3 +944,143 @@ async fn test_search_git_history_limit_respected() {
         "Results should respect limit parameter"
     );
 }
+
+#[tokio::test]
+async fn test_search_git_history_finds_annotated_tag() {
+    let (client, temp_dir) = create_test_client().await;
+    let cache_path = temp_dir.path().join("git_cache.json");
+
+    let repo_dir = temp_dir.path().join("repo");
+    std::fs::create_dir(&repo_dir).unwrap();
+    let repo = git2::Repository::init(&repo_dir).unwrap();
+    commit_to_branch(&repo, "main", "setup.txt", "Initial project setup");
+    commit_to_branch(&repo, "main", "cache.txt", "Release cache buffers");
+
+    let head = repo
+        .find_reference("refs/heads/main")
+        .unwrap()
+        .peel_to_commit()
+        .unwrap();
+    let tagger = git2::Signature::now("Release Bot", "release@example.com").unwrap();
+    repo.tag(
+        "v1.0.0",
+        head.as_object(),
+        &tagger,
+        "Release 1.0.0\n\nFixes the memory leak in the indexer.",
+        false,
+    )
+    .unwrap();
+    // Lightweight tags carry no message and are not indexed
+    repo.tag_lightweight("nightly", head.as_object(), false)
+        .unwrap();
+
+    let req = SearchGitHistoryRequest {
+        query: "which release fixed the memory leak".to_string(),
+        path: repo_dir.to_string_lossy().to_string(),
+        project: None,
+        branch: None,
+        since: None,
+        until: None,
+        author: None,
+        file_pattern: None,
+        max_commits: 10,
+        limit: 10,
+        min_score: 0.0,
+        kind: GitEntryKind::Tag,
+        reset: false,
+    };
+
+    let response = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        req,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(response.commits_indexed, 0);
+    assert_eq!(response.results.len(), 1);
+    let tag = &response.results[0];
+    assert_eq!(tag.kind, GitEntryKind::Tag);
+    assert_eq!(tag.tag_name.as_deref(), Some("v1.0.0"));
+    assert_eq!(tag.commit_hash, head.id().to_string());
+    assert!(tag.commit_message.contains("memory leak"));
+    assert_eq!(tag.author, "Release Bot");
+}
+
+#[tokio::test]
+async fn test_code_queries_leave_out_git_commits_by_default() {
+    let (client, temp_dir) = create_test_client().await;
+    let cache_path = temp_dir.path().join("git_cache.json");
+
+    let repo_dir = temp_dir.path().join("repo");
+    std::fs::create_dir(&repo_dir).unwrap();
+    let repo = git2::Repository::init(&repo_dir).unwrap();
+    commit_to_branch(&repo, "main", "parser.txt", "Add config parser");
+
+    // Index the working tree as code, and the history as commits
+    crate::client::indexing::do_index(
+        &client,
+        repo_dir.to_string_lossy().to_string(),
+        None,
+        vec![],
+        vec![],
+        1024 * 1024,
+        None,
+        None,
+        None,
+        tokio_util::sync::CancellationToken::new(),
+    )
+    .await
+    .unwrap();
+    let history = do_search_git_history(
+        client.embedding_provider(),
+        client.vector_db.clone(),
+        client.git_cache.clone(),
+        &cache_path,
+        SearchGitHistoryRequest {
+            query: "config parser".to_string(),
+            path: repo_dir.to_string_lossy().to_string(),
+            project: None,
+            branch: Some("main".to_string()),
+            since: None,
+            until: None,
+            author: None,
+            file_pattern: None,
+            max_commits: 10,
+            limit: 10,
+            min_score: 0.0,
+            kind: GitEntryKind::Commit,
+            reset: false,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(history.commits_indexed, 1);
+
+    let query = |include_git_commits| crate::types::QueryRequest {
+        query: "config parser".to_string(),
+        path: None,
+        project: None,
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits,
+    };
+
+    let response = client.query_codebase(query(false)).await.unwrap();
+    assert!(!response.results.is_empty());
+    assert!(response.results.iter().all(|r| r.language != "git-commit"));
+
+    let response = client.query_codebase(query(true)).await.unwrap();
+    assert!(response.results.iter().any(|r| r.language == "git-commit"));
+}
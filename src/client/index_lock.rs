@@ -127,6 +127,7 @@ impl Drop for IndexLockGuard {
                 errors: vec!["Indexing operation was interrupted (panic or early return)".to_string()],
                 files_updated: 0,
                 files_removed: 0,
+                file_errors: vec![],
             };
             let _ = self.result_tx.send(error_response);
 
@@ -0,0 +1,161 @@
+//! Embedding model the default index was built with
+//!
+//! Vectors of different models can't be compared, and LanceDB fixes the vector
+//! dimension when the table is created. The model name and dimension are
+//! recorded in a file for each backend and checked at startup, so a changed
+//! `embedding.model_name` is reported up front (or the index cleared) instead
+//! of failing later with a schema error while storing embeddings. An index
+//! that predates the file is checked against the dimension it was built with.
+
+use crate::cache::HashCache;
+use crate::config::Config;
+use crate::error::{EmbeddingError, RagError};
+use crate::vector_db::VectorDatabase;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the metadata inside the vector database directory
+#[cfg(not(feature = "qdrant-backend"))]
+const INDEX_METADATA_FILE: &str = "index_metadata.json";
+
+/// Model name reported for an index built before its model was recorded
+const UNRECORDED_MODEL: &str = "unknown";
+
+/// Path of the metadata file describing the configured backend's default index
+///
+/// LanceDB keeps it inside the database directory. A Qdrant server has no
+/// local directory, so its file sits next to the hash cache, named after the
+/// server URL.
+pub(crate) fn index_metadata_path(config: &Config) -> PathBuf {
+    #[cfg(feature = "qdrant-backend")]
+    {
+        use sha2::{Digest, Sha256};
+
+        let url_hash = format!(
+            "{:x}",
+            Sha256::digest(config.vector_db.qdrant_url.as_bytes())
+        );
+        config
+            .cache
+            .hash_cache_path
+            .with_file_name(format!("index_metadata_qdrant_{}.json", &url_hash[..16]))
+    }
+
+    #[cfg(not(feature = "qdrant-backend"))]
+    {
+        config.vector_db.lancedb_path.join(INDEX_METADATA_FILE)
+    }
+}
+
+/// Embedding model and dimension of the vectors in the default index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct IndexMetadata {
+    pub(crate) model_name: String,
+    pub(crate) dimension: usize,
+}
+
+impl IndexMetadata {
+    /// Load the metadata, or `None` for a new index or one that predates it
+    pub(crate) fn load(path: &Path) -> Result<Option<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .with_context(|| format!("Failed to parse index metadata {:?}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read index metadata {:?}", path)),
+        }
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write index metadata {:?}", path))
+    }
+}
+
+/// Check the recorded model against `current` before the database is initialized
+///
+/// Without a metadata file, an existing index of another dimension counts as
+/// a mismatch; one of the same dimension is assumed to match.
+///
+/// A mismatch is an [`EmbeddingError::IndexModelChanged`] error unless
+/// `auto_reindex` is set. In that case the default index is dropped, along
+/// with the keyword indexes and hash cache entries of the codebases indexed
+/// with the default model, so the next run re-indexes them in full.
+pub(crate) async fn check_index_model<V: VectorDatabase + ?Sized>(
+    metadata_path: &Path,
+    current: &IndexMetadata,
+    vector_db: &V,
+    hash_cache: &mut HashCache,
+    cache_path: &Path,
+    auto_reindex: bool,
+) -> Result<()> {
+    let indexed = match IndexMetadata::load(metadata_path)? {
+        Some(indexed) => indexed,
+        None => match vector_db
+            .indexed_dimension()
+            .await
+            .context("Failed to read the dimension of the existing index")?
+        {
+            Some(dimension) if dimension != current.dimension => IndexMetadata {
+                model_name: UNRECORDED_MODEL.to_string(),
+                dimension,
+            },
+            _ => return Ok(()),
+        },
+    };
+    if &indexed == current {
+        return Ok(());
+    }
+
+    if !auto_reindex {
+        return Err(RagError::from(EmbeddingError::IndexModelChanged {
+            indexed_model: indexed.model_name,
+            indexed_dimension: indexed.dimension,
+            model: current.model_name.clone(),
+            dimension: current.dimension,
+        })
+        .into());
+    }
+
+    tracing::warn!(
+        "Embedding model changed from '{}' ({} dimensions) to '{}' ({} dimensions), clearing the index",
+        indexed.model_name,
+        indexed.dimension,
+        current.model_name,
+        current.dimension
+    );
+
+    // Codebases indexed with a per-project model live in their own tables
+    let roots: Vec<String> = hash_cache
+        .roots
+        .keys()
+        .filter(|root| hash_cache.root_model(root).is_none())
+        .cloned()
+        .collect();
+    for root in &roots {
+        vector_db
+            .drop_keyword_index(root)
+            .await
+            .with_context(|| format!("Failed to drop keyword index for {}", root))?;
+        hash_cache.remove_root(root);
+    }
+    vector_db
+        .clear()
+        .await
+        .context("Failed to clear the index built with the previous model")?;
+    hash_cache
+        .save(cache_path)
+        .context("Failed to save hash cache after clearing the index")?;
+
+    tracing::info!(
+        "Cleared {} codebases; they are re-indexed in full on their next index run",
+        roots.len()
+    );
+    Ok(())
+}
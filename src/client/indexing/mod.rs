@@ -1,153 +1,717 @@
 use super::RagClient;
-use crate::embedding::EmbeddingProvider;
-use crate::indexer::{CodeChunk, FileWalker};
-use crate::types::{ChunkMetadata, IndexResponse};
+use super::related_search::build_symbol_index;
+use crate::embedding::{
+    AdaptiveBatchSize, EmbeddingProvider, GPU_MIN_BATCH_SIZE, content_token_budget,
+    truncate_for_embedding,
+};
+use crate::error::is_retryable_error;
+use crate::indexer::{CodeChunk, FileInfo, FileWalker, WalkedFiles, split_by_tokens};
+use crate::relations::repomap::SymbolExtractor;
+use crate::relations::storage::RelationsStore;
+use crate::relations::{Definition, Reference, RelationsProvider};
+use crate::telemetry;
+use crate::types::{ChunkMetadata, IndexPhase, IndexProgress, IndexResponse};
 use crate::vector_db::VectorDatabase;
 use anyhow::{Context, Result};
 use rayon::prelude::*;
 use rmcp::{Peer, RoleServer, model::ProgressNotificationParam, model::ProgressToken};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
 /// Helper macro to check for cancellation and return early if cancelled
+///
+/// The optional second argument records the work completed so far.
 macro_rules! check_cancelled {
     ($cancel_token:expr) => {
+        check_cancelled!($cancel_token, IndexingCancelled::default())
+    };
+    ($cancel_token:expr, $progress:expr) => {
         if $cancel_token.is_cancelled() {
             tracing::info!("Indexing operation cancelled");
-            anyhow::bail!("Indexing was cancelled");
+            return Err(anyhow::Error::new($progress));
         }
     };
 }
 
+/// Error returned when indexing is cancelled, with the work completed up to that point
+///
+/// The root stays marked dirty, so the next run re-indexes it in full.
+#[derive(Debug, Default)]
+pub struct IndexingCancelled {
+    pub files_indexed: usize,
+    pub chunks_created: usize,
+    pub embeddings_generated: usize,
+    pub files_updated: usize,
+    pub files_removed: usize,
+}
+
+impl std::fmt::Display for IndexingCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Indexing was cancelled")
+    }
+}
+
+impl std::error::Error for IndexingCancelled {}
+
+/// Error carrying the partial response of a run whose embedding phase hit
+/// `embedding.phase_timeout_secs`
+///
+/// The embeddings generated in time are stored, but the root stays marked dirty
+/// so the next run embeds the rest; smart indexing reports the response as is.
+#[derive(Debug)]
+struct EmbeddingDeadlineExceeded(IndexResponse);
+
+impl std::fmt::Display for EmbeddingDeadlineExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Embedding phase deadline exceeded")
+    }
+}
+
+impl std::error::Error for EmbeddingDeadlineExceeded {}
+
+/// Record caller-side progress on a cancellation raised further down
+fn with_cancel_progress(
+    mut error: anyhow::Error,
+    update: impl FnOnce(&mut IndexingCancelled),
+) -> anyhow::Error {
+    if let Some(cancelled) = error.downcast_mut::<IndexingCancelled>() {
+        update(cancelled);
+    }
+    error
+}
+
 /// Result of embedding generation with cancellation support
 struct EmbeddingResult {
     embeddings: Vec<Vec<f32>>,
     successful_chunks: Vec<CodeChunk>,
     errors: Vec<String>,
+    /// Whether `embedding.phase_timeout_secs` stopped embedding before every chunk was done
+    deadline_exceeded: bool,
+}
+
+/// Files with chunks missing from `embedded`, which have to be embedded again
+fn unembedded_files(chunks: &[CodeChunk], embedded: &[CodeChunk]) -> HashSet<String> {
+    let mut remaining: HashMap<&str, usize> = HashMap::new();
+    for chunk in chunks {
+        *remaining.entry(&chunk.metadata.file_path).or_default() += 1;
+    }
+    for chunk in embedded {
+        if let Some(count) = remaining.get_mut(chunk.metadata.file_path.as_str()) {
+            *count -= 1;
+        }
+    }
+    remaining
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(file, _)| file.to_string())
+        .collect()
+}
+
+/// Report indexing progress to the MCP client and to the library progress channel, if any
+///
+/// Updates wait for room in the channel, so every phase reaches the consumer;
+/// once its receiver is dropped they are silently discarded.
+async fn report_progress(
+    peer: &Option<Peer<RoleServer>>,
+    progress_token: &Option<ProgressToken>,
+    progress_tx: &Option<mpsc::Sender<IndexProgress>>,
+    phase: IndexPhase,
+    percent: f64,
+    message: String,
+) {
+    if let Some(tx) = progress_tx {
+        let _ = tx
+            .send(IndexProgress {
+                phase,
+                percent,
+                message: message.clone(),
+            })
+            .await;
+    }
+    if let (Some(peer), Some(token)) = (peer, progress_token) {
+        let _ = peer
+            .notify_progress(ProgressNotificationParam {
+                progress_token: token.clone(),
+                progress: percent,
+                total: Some(100.0),
+                message: Some(message),
+            })
+            .await;
+    }
+}
+
+/// Walk a directory on a blocking thread (CPU-intensive), inside a "walk" span
+async fn walk_files(
+    client: &RagClient,
+    walker: Arc<FileWalker>,
+    path: &str,
+) -> Result<WalkedFiles> {
+    let span = client.phase_spans().span(|| {
+        tracing::info_span!(
+            "walk",
+            path,
+            files = tracing::field::Empty,
+            duration_ms = tracing::field::Empty
+        )
+    });
+    let start = Instant::now();
+
+    let public_only = client.config.indexing.public_symbols_only;
+    let walked = tokio::task::spawn_blocking(move || {
+        let mut walked = walker.walk_changes()?;
+        if public_only {
+            retain_public_surface(&mut walked.files);
+        }
+        Ok::<_, anyhow::Error>(walked)
+    })
+    .instrument(span.clone())
+    .await
+    .context("Failed to spawn file walker task")?
+    .context("Failed to walk directory")?;
+
+    span.record("files", walked.files.len());
+    telemetry::record_duration(&span, start);
+    Ok(walked)
+}
+
+/// Drop source files that define no public/exported symbols
+///
+/// Files whose language has no symbol extraction, or that fail to parse, are kept.
+fn retain_public_surface(files: &mut Vec<FileInfo>) {
+    let extractor = SymbolExtractor::new();
+    let before = files.len();
+    files.retain(|file| match extractor.exports_public_symbols(file) {
+        Ok(exports) => exports.unwrap_or(true),
+        Err(e) => {
+            tracing::debug!(
+                "Keeping {}: symbol extraction failed: {}",
+                file.relative_path,
+                e
+            );
+            true
+        }
+    });
+
+    let skipped = before - files.len();
+    if skipped > 0 {
+        tracing::info!("Skipped {} files without public symbols", skipped);
+    }
+}
+
+/// Chunk files in parallel for better performance, inside a "chunk" span
+fn chunk_files(client: &RagClient, files: &[FileInfo]) -> Vec<CodeChunk> {
+    let span = client.phase_spans().span(|| {
+        tracing::info_span!(
+            "chunk",
+            files = files.len(),
+            chunks = tracing::field::Empty,
+            duration_ms = tracing::field::Empty
+        )
+    });
+    let start = Instant::now();
+
+    let chunker = client.chunker.clone();
+    let diagnostics = client.config.observability.chunk_diagnostics;
+    let chunks: Vec<_> = span.in_scope(|| {
+        files
+            .par_iter()
+            .flat_map(|file| {
+                if !diagnostics {
+                    return chunker.chunk_file(file);
+                }
+                let (chunks, diagnostic) = chunker.chunk_file_with_diagnostic(file);
+                tracing::info!(
+                    file = %diagnostic.file_path,
+                    applied = %diagnostic.applied,
+                    reason = %diagnostic.reason,
+                    chunks = diagnostic.chunks,
+                    "Chunked file"
+                );
+                chunks
+            })
+            .collect()
+    });
+
+    // Split chunks the model would otherwise cut off, so their tail is
+    // embedded too
+    let (chunks, split) = split_by_tokens(chunks, content_token_budget(max_input_tokens(client)));
+    if split > 0 {
+        tracing::debug!(
+            "Split {} chunks exceeding the embedding model's input limit",
+            split
+        );
+    }
+
+    span.record("chunks", chunks.len());
+    telemetry::record_duration(&span, start);
+    chunks
+}
+
+/// Tokens the embedding model reads per input, capped by `embedding.max_input_tokens`
+fn max_input_tokens(client: &RagClient) -> usize {
+    let model_max_tokens = client.embedding_provider().max_sequence_length();
+    client
+        .config
+        .embedding
+        .max_input_tokens
+        .map_or(model_max_tokens, |limit| limit.min(model_max_tokens))
+}
+
+/// Extract the definitions and references of `files` on a blocking thread
+///
+/// References are resolved against the extracted definitions together with
+/// `known_definitions`, those stored for the root's other files, so that
+/// references into other files resolve too. Files that fail to parse are
+/// skipped.
+async fn extract_relations(
+    client: &RagClient,
+    files: Arc<Vec<FileInfo>>,
+    known_definitions: Vec<Definition>,
+) -> Result<(Vec<Definition>, Vec<Reference>)> {
+    let provider = client.relations_provider.clone();
+    tokio::task::spawn_blocking(move || {
+        let definitions: Vec<Definition> = files
+            .par_iter()
+            .filter(|file| !file.binary)
+            .flat_map_iter(|file| {
+                provider.extract_definitions(file).unwrap_or_else(|e| {
+                    tracing::debug!("Skipping relations for {}: {}", file.relative_path, e);
+                    Vec::new()
+                })
+            })
+            .collect();
+
+        let all_definitions: Vec<Definition> = known_definitions
+            .into_iter()
+            .chain(definitions.iter().cloned())
+            .collect();
+        let symbol_index = build_symbol_index(&all_definitions);
+        let references: Vec<Reference> = files
+            .par_iter()
+            .filter(|file| !file.binary)
+            .flat_map_iter(|file| {
+                provider
+                    .extract_references(file, &symbol_index)
+                    .unwrap_or_else(|e| {
+                        tracing::debug!("Skipping references in {}: {}", file.relative_path, e);
+                        Vec::new()
+                    })
+            })
+            .collect();
+        (definitions, references)
+    })
+    .await
+    .context("Failed to spawn blocking task for relation extraction")
+}
+
+/// Store extracted definitions and references in the relations store
+async fn store_relations(
+    client: &RagClient,
+    definitions: Vec<Definition>,
+    references: Vec<Reference>,
+    root_path: &str,
+) -> Result<()> {
+    let definitions_stored = client
+        .relations_store
+        .store_definitions(definitions, root_path)
+        .await
+        .context("Failed to store definitions")?;
+    let references_stored = client
+        .relations_store
+        .store_references(references, root_path)
+        .await
+        .context("Failed to store references")?;
+
+    tracing::debug!(
+        "Stored {} definitions and {} references under {}",
+        definitions_stored,
+        references_stored,
+        root_path
+    );
+    Ok(())
+}
+
+/// Replace the stored relations of a root with those of `files`
+async fn index_relations(
+    client: &RagClient,
+    files: Arc<Vec<FileInfo>>,
+    root_path: &str,
+) -> Result<()> {
+    let (definitions, references) = extract_relations(client, files, Vec::new()).await?;
+
+    // Stores only append, so relations of a previous index would linger
+    client
+        .relations_store
+        .delete_by_root(root_path)
+        .await
+        .context("Failed to delete previous relations")?;
+    store_relations(client, definitions, references, root_path).await
+}
+
+/// Refresh stored relations after an incremental update
+///
+/// `stale_paths` are the modified and removed files, whose relations are
+/// deleted; `changed` files are then extracted again. Unchanged files that
+/// reference a stale file are read and re-extracted as well, since their
+/// references point at the old definition lines. References resolve against
+/// the definitions still stored for the other files, which are not re-read.
+async fn update_relations(
+    client: &RagClient,
+    walker: &Arc<FileWalker>,
+    changed: &[FileInfo],
+    stale_paths: &[String],
+    root_path: &str,
+) -> Result<()> {
+    let store = &client.relations_store;
+
+    let mut dependents = HashSet::new();
+    for file_path in stale_paths {
+        dependents.extend(
+            store
+                .find_referencing_files(file_path, root_path)
+                .await
+                .context("Failed to find referencing files")?,
+        );
+    }
+
+    for file_path in stale_paths.iter().chain(&dependents) {
+        store
+            .delete_by_file(file_path, root_path)
+            .await
+            .with_context(|| format!("Failed to delete relations for {}", file_path))?;
+    }
+
+    let known_definitions = store
+        .find_definitions_by_root(root_path)
+        .await
+        .context("Failed to load stored definitions")?;
+
+    // Removed dependents are left out by the walker as unreadable
+    let to_read: Vec<String> = dependents
+        .into_iter()
+        .filter(|file_path| !changed.iter().any(|file| &file.relative_path == file_path))
+        .collect();
+    let walker = walker.clone();
+    let mut to_extract = tokio::task::spawn_blocking(move || walker.read_files(&to_read))
+        .await
+        .context("Failed to spawn file reader task")?
+        .context("Failed to read referencing files")?;
+    to_extract.extend(changed.iter().cloned());
+    let (definitions, references) =
+        extract_relations(client, Arc::new(to_extract), known_definitions).await?;
+    store_relations(client, definitions, references, root_path).await
+}
+
+/// Store embeddings in the vector database, inside a "store" span
+async fn store_embeddings(
+    client: &RagClient,
+    embeddings: Vec<Vec<f32>>,
+    metadata: Vec<ChunkMetadata>,
+    contents: Vec<String>,
+    path: &str,
+) -> Result<()> {
+    let span = client.phase_spans().span(|| {
+        tracing::info_span!(
+            "store",
+            embeddings = embeddings.len(),
+            duration_ms = tracing::field::Empty
+        )
+    });
+    let start = Instant::now();
+
+    client
+        .vector_db
+        .store_embeddings(embeddings, metadata, contents, path)
+        .instrument(span.clone())
+        .await
+        .context("Failed to store embeddings")?;
+
+    telemetry::record_duration(&span, start);
+    Ok(())
+}
+
+/// Text embedded for a chunk
+///
+/// Binary files have no content, so their path and extension stand in for it.
+fn embedding_text(chunk: &CodeChunk) -> String {
+    if chunk.metadata.binary {
+        format!(
+            "Binary file {} ({})",
+            chunk.metadata.file_path,
+            chunk
+                .metadata
+                .extension
+                .as_deref()
+                .unwrap_or("no extension")
+        )
+    } else {
+        chunk.content.clone()
+    }
 }
 
 /// Generate embeddings for chunks with frequent cancellation checks
 ///
 /// This function processes chunks in small batches and checks for cancellation
 /// between each batch, allowing for faster response to cancellation requests.
+/// With `embedding.batch_size = 0`, each batch is embedded in a single call
+/// whose size is tuned from the latency of the previous ones. On a GPU, every
+/// call embeds at least [`GPU_MIN_BATCH_SIZE`] chunks.
+///
+/// Up to `embedding.parallelism` batches are embedded concurrently; results are
+/// assembled in chunk order regardless of the order batches finish in.
+///
+/// Once `embedding.phase_timeout_secs` is exceeded, batches still in flight are
+/// abandoned and the chunks embedded so far are returned with `deadline_exceeded` set.
 async fn generate_embeddings_with_cancellation(
     client: &RagClient,
     chunks: &[CodeChunk],
     cancel_token: &CancellationToken,
     peer: &Option<Peer<RoleServer>>,
     progress_token: &Option<ProgressToken>,
+    progress_tx: &Option<mpsc::Sender<IndexProgress>>,
     progress_start: f64,
     progress_end: f64,
 ) -> Result<EmbeddingResult> {
     let batch_size = client.config.embedding.batch_size;
+    let mut adaptive =
+        (batch_size == 0).then(|| AdaptiveBatchSize::new(client.config.embedding.max_batch_size));
+    let parallelism = client.config.embedding.parallelism.max(1);
     let timeout_secs = client.config.embedding.timeout_secs;
+    let phase_timeout_secs = client.config.embedding.phase_timeout_secs;
+    let deadline = (phase_timeout_secs > 0)
+        .then(|| tokio::time::Instant::now() + Duration::from_secs(phase_timeout_secs));
+    let mut deadline_exceeded = false;
     let check_interval = if client.config.embedding.cancellation_check_interval > 0 {
         client.config.embedding.cancellation_check_interval
     } else {
         batch_size // Fall back to batch size if interval is 0
     };
+    // One provider for the whole run, even if the model is reloaded meanwhile
+    let embedding_provider = client.embedding_provider();
+    // GPUs only reach their throughput with larger batches
+    let min_batch_len = if embedding_provider.device().is_gpu() {
+        GPU_MIN_BATCH_SIZE
+    } else {
+        1
+    };
+
+    // Truncate chunks still over the limit (single over-long lines) ourselves
+    // so the cut point is predictable
+    let max_tokens = max_input_tokens(client);
+    let mut truncated_chunks = 0;
+
+    let span = client.phase_spans().span(|| {
+        tracing::info_span!(
+            "embed",
+            chunks = chunks.len(),
+            embeddings = tracing::field::Empty,
+            truncated = tracing::field::Empty,
+            errors = tracing::field::Empty,
+            duration_ms = tracing::field::Empty
+        )
+    });
+    let start = Instant::now();
 
-    let mut all_embeddings = Vec::with_capacity(chunks.len());
-    let mut successful_chunks = Vec::with_capacity(chunks.len());
     let mut errors = Vec::new();
 
-    let total_batches = chunks.len().div_ceil(batch_size);
+    // Chunk range of every dispatched batch, and its embeddings once generated
+    let mut batch_ranges: Vec<Range<usize>> = Vec::new();
+    let mut batch_embeddings: Vec<Option<Vec<Vec<f32>>>> = Vec::new();
+    let mut in_flight = JoinSet::new();
+    let mut next_chunk = 0;
     let mut chunks_processed = 0;
+    // Time spent inside embedding calls, i.e. what the serial path would take
+    let mut embedding_time = Duration::ZERO;
 
-    for (batch_idx, chunk_batch) in chunks.chunks(batch_size).enumerate() {
-        // Check for cancellation at start of each batch
-        if cancel_token.is_cancelled() {
-            tracing::info!(
-                "Embedding generation cancelled after {} chunks",
-                chunks_processed
-            );
-            anyhow::bail!("Indexing was cancelled");
-        }
-
-        // Process batch in smaller sub-batches for more frequent cancellation checks
-        let mut batch_embeddings = Vec::new();
-        let mut batch_successful_chunks = Vec::new();
-
-        for sub_batch in chunk_batch.chunks(check_interval) {
-            // Check cancellation before each sub-batch
+    loop {
+        // Keep up to `parallelism` batches in flight
+        while in_flight.len() < parallelism && next_chunk < chunks.len() {
+            // Check cancellation before dispatching each batch
             if cancel_token.is_cancelled() {
+                in_flight.abort_all();
                 tracing::info!(
-                    "Embedding generation cancelled during batch {} after {} chunks",
-                    batch_idx,
+                    "Embedding generation cancelled after {} chunks",
                     chunks_processed
                 );
-                anyhow::bail!("Indexing was cancelled");
+                return Err(anyhow::Error::new(IndexingCancelled {
+                    embeddings_generated: chunks_processed,
+                    ..Default::default()
+                }));
             }
 
-            let texts: Vec<String> = sub_batch.iter().map(|c| c.content.clone()).collect();
+            let batch_len = adaptive
+                .as_ref()
+                .map_or(check_interval, AdaptiveBatchSize::size)
+                .max(min_batch_len);
+            let range = next_chunk..(next_chunk + batch_len).min(chunks.len());
+            next_chunk = range.end;
+
+            let texts: Vec<String> = chunks[range.clone()].iter().map(embedding_text).collect();
+            let (texts, truncated) = truncate_for_embedding(texts, max_tokens);
+            truncated_chunks += truncated;
 
             // Generate embeddings with timeout protection
-            let provider = client.embedding_provider.clone();
-            let embed_future = tokio::task::spawn_blocking(move || provider.embed_batch(texts));
+            let batch = batch_ranges.len();
+            batch_ranges.push(range);
+            batch_embeddings.push(None);
+            let provider = embedding_provider.clone();
+            in_flight.spawn(
+                async move {
+                    let embed_start = Instant::now();
+                    let result = tokio::time::timeout(
+                        Duration::from_secs(timeout_secs),
+                        tokio::task::spawn_blocking(move || provider.embed_batch(texts)),
+                    )
+                    .await;
+                    (batch, result, embed_start.elapsed())
+                }
+                .instrument(span.clone()),
+            );
+        }
 
-            match tokio::time::timeout(
-                std::time::Duration::from_secs(timeout_secs),
-                embed_future,
-            )
-            .await
+        // Wait for the next batch to finish, aborting the rest on cancellation
+        // or once the phase deadline passes
+        let joined = tokio::select! {
+            joined = in_flight.join_next() => joined,
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(tokio::time::Instant::now)),
+                if deadline.is_some() =>
             {
-                Ok(Ok(Ok(embeddings))) => {
-                    batch_embeddings.extend(embeddings);
-                    batch_successful_chunks.extend(sub_batch.iter().cloned());
-                    chunks_processed += sub_batch.len();
-                }
-                Ok(Ok(Err(e))) => {
-                    errors.push(format!(
-                        "Failed to generate embeddings for sub-batch: {}",
-                        e
-                    ));
-                    // Continue with next sub-batch
-                }
-                Ok(Err(e)) => {
-                    errors.push(format!("Embedding task panicked: {}", e));
-                    // Continue with next sub-batch
+                in_flight.abort_all();
+                tracing::warn!(
+                    "Embedding phase exceeded {}s with {} of {} chunks embedded, keeping partial results",
+                    phase_timeout_secs,
+                    chunks_processed,
+                    chunks.len()
+                );
+                errors.push(format!(
+                    "Embedding stopped after exceeding the {}s phase deadline with {} of {} chunks embedded; the index is marked dirty and the next run will embed the rest",
+                    phase_timeout_secs,
+                    chunks_processed,
+                    chunks.len()
+                ));
+                deadline_exceeded = true;
+                break;
+            }
+            _ = cancel_token.cancelled() => {
+                in_flight.abort_all();
+                tracing::info!(
+                    "Embedding generation cancelled with {} batches in flight after {} chunks",
+                    in_flight.len(),
+                    chunks_processed
+                );
+                return Err(anyhow::Error::new(IndexingCancelled {
+                    embeddings_generated: chunks_processed,
+                    ..Default::default()
+                }));
+            }
+        };
+        let Some(joined) = joined else {
+            break; // Every batch is done
+        };
+
+        match joined {
+            Ok((batch, Ok(Ok(Ok(embeddings))), elapsed)) => {
+                let batch_len = batch_ranges[batch].len();
+                if let Some(adaptive) = &mut adaptive {
+                    adaptive.record(batch_len, elapsed);
                 }
-                Err(_) => {
-                    errors.push(format!(
-                        "Embedding generation timed out after {} seconds",
-                        timeout_secs
-                    ));
-                    // Continue with next sub-batch
+                embedding_time += elapsed;
+                chunks_processed += batch_len;
+                batch_embeddings[batch] = Some(embeddings);
+            }
+            Ok((_, Ok(Ok(Err(e))), _)) => {
+                errors.push(format!(
+                    "Failed to generate embeddings for sub-batch: {}",
+                    e
+                ));
+                // Continue with next sub-batch
+            }
+            Ok((_, Ok(Err(e)), _)) => {
+                errors.push(format!("Embedding task panicked: {}", e));
+                // Continue with next sub-batch
+            }
+            Ok((_, Err(_), _)) => {
+                if let Some(adaptive) = &mut adaptive {
+                    adaptive.record_timeout();
                 }
+                errors.push(format!(
+                    "Embedding generation timed out after {} seconds",
+                    timeout_secs
+                ));
+                // Continue with next sub-batch
+            }
+            Err(e) => {
+                errors.push(format!("Embedding task panicked: {}", e));
+                // Continue with next sub-batch
             }
         }
 
-        // Add batch results to overall results
-        all_embeddings.extend(batch_embeddings);
-        successful_chunks.extend(batch_successful_chunks);
-
         // Send progress during embedding
-        if let (Some(peer), Some(token)) = (peer, progress_token) {
-            let progress =
-                progress_start + ((batch_idx + 1) as f64 / total_batches as f64) * (progress_end - progress_start);
-            let _ = peer
-                .notify_progress(ProgressNotificationParam {
-                    progress_token: token.clone(),
-                    progress,
-                    total: Some(100.0),
-                    message: Some(format!(
-                        "Generating embeddings... {}/{} batches ({} chunks)",
-                        batch_idx + 1,
-                        total_batches,
-                        chunks_processed
-                    )),
-                })
-                .await;
+        let progress = progress_start
+            + (chunks_processed as f64 / chunks.len() as f64) * (progress_end - progress_start);
+        report_progress(
+            peer,
+            progress_token,
+            progress_tx,
+            IndexPhase::Embedding,
+            progress,
+            format!(
+                "Generating embeddings... {}/{} chunks",
+                chunks_processed,
+                chunks.len()
+            ),
+        )
+        .await;
+    }
+
+    // Assemble results in chunk order
+    let mut all_embeddings = Vec::with_capacity(chunks_processed);
+    let mut successful_chunks = Vec::with_capacity(chunks_processed);
+    for (range, embeddings) in batch_ranges.into_iter().zip(batch_embeddings) {
+        if let Some(embeddings) = embeddings {
+            all_embeddings.extend(embeddings);
+            successful_chunks.extend(chunks[range].iter().cloned());
         }
     }
 
+    if parallelism > 1 && chunks_processed > 0 {
+        let elapsed = start.elapsed();
+        tracing::info!(
+            "Embedded {} chunks in {} ms with {} parallel batches ({} ms of embedding work, {:.1}x speedup over serial)",
+            chunks_processed,
+            elapsed.as_millis(),
+            parallelism,
+            embedding_time.as_millis(),
+            embedding_time.as_secs_f64() / elapsed.as_secs_f64()
+        );
+    }
+
+    if truncated_chunks > 0 {
+        tracing::info!(
+            "Truncated {} of {} chunks to {} tokens before embedding",
+            truncated_chunks,
+            chunks.len(),
+            max_tokens
+        );
+    }
+
+    span.record("embeddings", all_embeddings.len());
+    span.record("truncated", truncated_chunks);
+    span.record("errors", errors.len());
+    telemetry::record_duration(&span, start);
+
     Ok(EmbeddingResult {
         embeddings: all_embeddings,
         successful_chunks,
         errors,
+        deadline_exceeded,
     })
 }
 
@@ -162,22 +726,22 @@ pub async fn do_index(
     max_file_size: usize,
     peer: Option<Peer<RoleServer>>,
     progress_token: Option<ProgressToken>,
+    progress_tx: Option<mpsc::Sender<IndexProgress>>,
     cancel_token: CancellationToken,
 ) -> Result<IndexResponse> {
     let start = Instant::now();
     let mut errors = Vec::new();
 
     // Send initial progress
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 0.0,
-                total: Some(100.0),
-                message: Some("Starting file walk...".into()),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Scanning,
+        0.0,
+        "Starting file walk...".into(),
+    )
+    .await;
 
     // Walk the directory (on a blocking thread since it's CPU-intensive)
     // Create a cancellation flag for the blocking file walker
@@ -195,52 +759,58 @@ pub async fn do_index(
     let walker = FileWalker::new(&path, max_file_size)
         .with_project(project.clone())
         .with_patterns(include_patterns.clone(), exclude_patterns.clone())
+        .with_binary_metadata(client.config.indexing.index_binary_metadata)
+        .with_skip_hidden(client.config.indexing.skip_hidden)
+        .with_content_language_detection(client.config.indexing.content_language_detection)
+        .with_extension_languages(client.config.indexing.extension_languages.clone())
+        .with_text_sample_size(client.config.indexing.text_detection_sample_kb * 1024)
+        .with_max_content_chars(client.config.indexing.max_content_chars)
+        .with_read_concurrency(client.config.indexing.read_concurrency)
+        .with_excluded_dirs(client.config.indexing.excluded_dirs.clone())
         .with_cancellation_flag(cancelled_flag);
 
-    let files = tokio::task::spawn_blocking(move || walker.walk())
-        .await
-        .context("Failed to spawn file walker task")?
-        .context("Failed to walk directory")?;
+    let walked = walk_files(client, Arc::new(walker), &path).await?;
+    let files = Arc::new(walked.files);
     let files_indexed = files.len();
 
     // Check for cancellation after file walk
-    check_cancelled!(cancel_token);
+    check_cancelled!(
+        cancel_token,
+        IndexingCancelled {
+            files_indexed,
+            ..Default::default()
+        }
+    );
 
     // Send progress after file walk
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 20.0,
-                total: Some(100.0),
-                message: Some(format!("Found {} files, chunking...", files_indexed)),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Chunking,
+        20.0,
+        format!("Found {} files, chunking...", files_indexed),
+    )
+    .await;
 
     // Chunk all files in parallel for better performance
-    let chunker = client.chunker.clone();
-    let all_chunks: Vec<_> = files
-        .par_iter()
-        .flat_map(|file| chunker.chunk_file(file))
-        .collect();
+    let all_chunks = chunk_files(client, &files);
 
     let chunks_created = all_chunks.len();
 
     // Send progress after chunking
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 40.0,
-                total: Some(100.0),
-                message: Some(format!(
-                    "Created {} chunks, generating embeddings...",
-                    chunks_created
-                )),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Embedding,
+        40.0,
+        format!(
+            "Created {} chunks, generating embeddings...",
+            chunks_created
+        ),
+    )
+    .await;
 
     if all_chunks.is_empty() {
         return Ok(IndexResponse {
@@ -252,6 +822,7 @@ pub async fn do_index(
             errors: vec!["No code chunks found to index".to_string()],
             files_updated: 0,
             files_removed: 0,
+            file_errors: walked.failed,
         });
     }
 
@@ -263,31 +834,42 @@ pub async fn do_index(
         &cancel_token,
         &peer,
         &progress_token,
+        &progress_tx,
         40.0,
         80.0,
     )
-    .await?;
+    .await
+    .map_err(|e| {
+        with_cancel_progress(e, |cancelled| {
+            cancelled.files_indexed = files_indexed;
+            cancelled.chunks_created = chunks_created;
+        })
+    })?;
 
     let all_embeddings = embed_result.embeddings;
     let successful_chunks = embed_result.successful_chunks;
     errors.extend(embed_result.errors);
 
+    // Files the phase deadline left partly embedded are cached without a valid
+    // hash, so the next run replaces what was stored for them
+    let unembedded = if embed_result.deadline_exceeded {
+        unembedded_files(&all_chunks, &successful_chunks)
+    } else {
+        HashSet::new()
+    };
+
     let embeddings_generated = all_embeddings.len();
 
     // Send progress before storing
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 85.0,
-                total: Some(100.0),
-                message: Some(format!(
-                    "Storing {} embeddings in database...",
-                    embeddings_generated
-                )),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Storing,
+        85.0,
+        format!("Storing {} embeddings in database...", embeddings_generated),
+    )
+    .await;
 
     // Store in vector database (pass normalized root path for per-project BM25)
     // Use successful_chunks to ensure metadata/contents match embeddings count
@@ -310,36 +892,53 @@ pub async fn do_index(
     );
 
     // Check for cancellation before storing
-    check_cancelled!(cancel_token);
+    check_cancelled!(
+        cancel_token,
+        IndexingCancelled {
+            files_indexed,
+            chunks_created,
+            embeddings_generated,
+            ..Default::default()
+        }
+    );
 
     if !all_embeddings.is_empty() {
-        client
-            .vector_db
-            .store_embeddings(all_embeddings, metadata, contents, &path)
-            .await
-            .context("Failed to store embeddings")?;
+        store_embeddings(client, all_embeddings, metadata, contents, &path).await?;
     }
 
-    // Send progress before saving cache
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 95.0,
-                total: Some(100.0),
-                message: Some("Saving cache...".into()),
-            })
-            .await;
+    // Store definitions and references for repo-wide code navigation
+    if let Err(e) = index_relations(client, files.clone(), &path).await {
+        tracing::warn!("Failed to store code relations: {:#}", e);
+        errors.push(format!("Failed to store code relations: {:#}", e));
     }
 
+    // Send progress before saving cache
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Finalizing,
+        95.0,
+        "Saving cache...".into(),
+    )
+    .await;
+
     // Save file hashes to persistent cache
     let file_hashes: HashMap<String, String> = files
         .iter()
-        .map(|f| (f.relative_path.clone(), f.hash.clone()))
+        .map(|f| {
+            let hash = if unembedded.contains(&f.relative_path) {
+                String::new()
+            } else {
+                f.hash.clone()
+            };
+            (f.relative_path.clone(), hash)
+        })
         .collect();
 
     let mut cache = client.hash_cache.write().await;
-    cache.update_root(path, file_hashes);
+    cache.update_root(path.clone(), file_hashes);
+    cache.set_root_mtimes(&path, walked.mtimes);
 
     // Persist to disk
     if let Err(e) = cache.save(&client.cache_path) {
@@ -347,16 +946,15 @@ pub async fn do_index(
     }
 
     // Send progress before flush
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 98.0,
-                total: Some(100.0),
-                message: Some("Flushing index to disk...".into()),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Finalizing,
+        98.0,
+        "Flushing index to disk...".into(),
+    )
+    .await;
 
     // Flush the index to disk
     client
@@ -366,18 +964,17 @@ pub async fn do_index(
         .map_err(|e| anyhow::anyhow!("Failed to flush index to disk: {}", e))?;
 
     // Send final completion progress
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 100.0,
-                total: Some(100.0),
-                message: Some("Indexing complete!".into()),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Complete,
+        100.0,
+        "Indexing complete!".into(),
+    )
+    .await;
 
-    Ok(IndexResponse {
+    let response = IndexResponse {
         mode: crate::types::IndexingMode::Full,
         files_indexed,
         chunks_created,
@@ -386,7 +983,12 @@ pub async fn do_index(
         errors,
         files_updated: 0,
         files_removed: 0,
-    })
+        file_errors: walked.failed,
+    };
+    if embed_result.deadline_exceeded {
+        return Err(anyhow::Error::new(EmbeddingDeadlineExceeded(response)));
+    }
+    Ok(response)
 }
 
 /// Perform incremental update (only changed files)
@@ -400,41 +1002,48 @@ pub async fn do_incremental_update(
     max_file_size: usize,
     peer: Option<Peer<RoleServer>>,
     progress_token: Option<ProgressToken>,
+    progress_tx: Option<mpsc::Sender<IndexProgress>>,
     cancel_token: CancellationToken,
 ) -> Result<IndexResponse> {
     let start = Instant::now();
 
     // Send initial progress
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 0.0,
-                total: Some(100.0),
-                message: Some("Checking for changes...".into()),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Scanning,
+        0.0,
+        "Checking for changes...".into(),
+    )
+    .await;
 
-    // Get existing file hashes from persistent cache
+    // Get existing file hashes from persistent cache, with the modification
+    // times of hashed files so untouched ones need not be hashed again
     let cache = client.hash_cache.read().await;
     let existing_hashes = cache.get_root(&path).cloned().unwrap_or_default();
+    let known_mtimes: HashMap<String, u64> = cache
+        .get_root_mtimes(&path)
+        .into_iter()
+        .flatten()
+        .filter(|(file, _)| existing_hashes.contains_key(*file))
+        .map(|(file, mtime)| (file.clone(), *mtime))
+        .collect();
     drop(cache);
 
     // Send progress after reading cache
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 10.0,
-                total: Some(100.0),
-                message: Some(format!(
-                    "Found {} cached files, scanning directory...",
-                    existing_hashes.len()
-                )),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Scanning,
+        10.0,
+        format!(
+            "Found {} cached files, scanning directory...",
+            existing_hashes.len()
+        ),
+    )
+    .await;
 
     // Walk directory to find current files (on a blocking thread)
     // Create a cancellation flag for the blocking file walker
@@ -452,12 +1061,20 @@ pub async fn do_incremental_update(
     let walker = FileWalker::new(&path, max_file_size)
         .with_project(project.clone())
         .with_patterns(include_patterns.clone(), exclude_patterns.clone())
+        .with_binary_metadata(client.config.indexing.index_binary_metadata)
+        .with_skip_hidden(client.config.indexing.skip_hidden)
+        .with_content_language_detection(client.config.indexing.content_language_detection)
+        .with_extension_languages(client.config.indexing.extension_languages.clone())
+        .with_text_sample_size(client.config.indexing.text_detection_sample_kb * 1024)
+        .with_max_content_chars(client.config.indexing.max_content_chars)
+        .with_read_concurrency(client.config.indexing.read_concurrency)
+        .with_excluded_dirs(client.config.indexing.excluded_dirs.clone())
+        .with_known_mtimes(known_mtimes)
         .with_cancellation_flag(cancelled_flag);
+    let walker = Arc::new(walker);
 
-    let current_files = tokio::task::spawn_blocking(move || walker.walk())
-        .await
-        .context("Failed to spawn file walker task")?
-        .context("Failed to walk directory")?;
+    let walked = walk_files(client, walker.clone(), &path).await?;
+    let current_files = walked.files;
 
     // Check for cancellation after file walk
     check_cancelled!(cancel_token);
@@ -466,25 +1083,33 @@ pub async fn do_incremental_update(
     let mut files_updated = 0;
     let mut files_removed = 0;
     let mut chunks_modified = 0;
+    // Old chunks deleted for modified and removed files
+    let mut chunks_removed = 0;
 
     // Send progress after file walk
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 30.0,
-                total: Some(100.0),
-                message: Some(format!(
-                    "Found {} files, comparing with cache...",
-                    current_files.len()
-                )),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Scanning,
+        30.0,
+        format!(
+            "Found {} files, comparing with cache...",
+            current_files.len() + walked.unchanged.len()
+        ),
+    )
+    .await;
 
-    // Find new and modified files
-    let mut new_hashes = HashMap::with_capacity(current_files.len());
+    // Find new and modified files; files with an unchanged mtime keep their hash
+    let mut new_hashes = HashMap::with_capacity(current_files.len() + walked.unchanged.len());
+    for file_path in &walked.unchanged {
+        if let Some(hash) = existing_hashes.get(file_path) {
+            new_hashes.insert(file_path.clone(), hash.clone());
+        }
+    }
     let mut files_to_index = Vec::with_capacity(current_files.len());
+    // Modified and removed files whose stored relations are out of date
+    let mut stale_paths = Vec::new();
 
     for file in current_files {
         new_hashes.insert(file.relative_path.clone(), file.hash.clone());
@@ -497,10 +1122,12 @@ pub async fn do_incremental_update(
             }
             Some(old_hash) if old_hash != &file.hash => {
                 // Modified file - delete old embeddings first
-                if let Err(e) = client.vector_db.delete_by_file(&file.relative_path).await {
-                    tracing::warn!("Failed to delete old embeddings: {}", e);
+                match client.vector_db.delete_by_file(&file.relative_path).await {
+                    Ok(deleted) => chunks_removed += deleted,
+                    Err(e) => tracing::warn!("Failed to delete old embeddings: {}", e),
                 }
                 files_updated += 1;
+                stale_paths.push(file.relative_path.clone());
                 files_to_index.push(file);
             }
             _ => {
@@ -513,52 +1140,46 @@ pub async fn do_incremental_update(
     for old_file in existing_hashes.keys() {
         if !new_hashes.contains_key(old_file) {
             files_removed += 1;
-            if let Err(e) = client.vector_db.delete_by_file(old_file).await {
-                tracing::warn!("Failed to delete embeddings for removed file: {}", e);
+            stale_paths.push(old_file.clone());
+            match client.vector_db.delete_by_file(old_file).await {
+                Ok(deleted) => chunks_removed += deleted,
+                Err(e) => tracing::warn!("Failed to delete embeddings for removed file: {}", e),
             }
         }
     }
 
     // Send progress after identifying changes
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 50.0,
-                total: Some(100.0),
-                message: Some(format!(
-                    "Processing {} changed files...",
-                    files_to_index.len()
-                )),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Chunking,
+        50.0,
+        format!("Processing {} changed files...", files_to_index.len()),
+    )
+    .await;
 
-    // Index new/modified files
-    let (embeddings_generated, embed_errors) = if !files_to_index.is_empty() {
+    // Index new/modified files; files the phase deadline left partly embedded
+    // are returned so they can be retried
+    let (embeddings_generated, embed_errors, unembedded) = if !files_to_index.is_empty() {
         // Chunk files in parallel for better performance
-        let chunker = client.chunker.clone();
-        let all_chunks: Vec<_> = files_to_index
-            .par_iter()
-            .flat_map(|file| chunker.chunk_file(file))
-            .collect();
+        let all_chunks = chunk_files(client, &files_to_index);
 
         chunks_modified = all_chunks.len();
 
         // Send progress after chunking
-        if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-            let _ = peer
-                .notify_progress(ProgressNotificationParam {
-                    progress_token: token.clone(),
-                    progress: 60.0,
-                    total: Some(100.0),
-                    message: Some(format!(
-                        "Created {} chunks, generating embeddings...",
-                        chunks_modified
-                    )),
-                })
-                .await;
-        }
+        report_progress(
+            &peer,
+            &progress_token,
+            &progress_tx,
+            IndexPhase::Embedding,
+            60.0,
+            format!(
+                "Created {} chunks, generating embeddings...",
+                chunks_modified
+            ),
+        )
+        .await;
 
         // Generate embeddings with frequent cancellation checks
         // Progress range: 60% to 85%
@@ -568,28 +1189,48 @@ pub async fn do_incremental_update(
             &cancel_token,
             &peer,
             &progress_token,
+            &progress_tx,
             60.0,
             85.0,
         )
-        .await?;
+        .await
+        .map_err(|e| {
+            with_cancel_progress(e, |cancelled| {
+                cancelled.files_indexed = files_added;
+                cancelled.chunks_created = chunks_modified;
+                cancelled.files_updated = files_updated;
+                cancelled.files_removed = files_removed;
+            })
+        })?;
 
         let all_embeddings = embed_result.embeddings;
         let successful_chunks = embed_result.successful_chunks;
+        let unembedded = embed_result
+            .deadline_exceeded
+            .then(|| unembedded_files(&all_chunks, &successful_chunks));
 
         // Send progress before storing
-        if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-            let _ = peer
-                .notify_progress(ProgressNotificationParam {
-                    progress_token: token.clone(),
-                    progress: 90.0,
-                    total: Some(100.0),
-                    message: Some(format!("Storing {} embeddings...", all_embeddings.len())),
-                })
-                .await;
-        }
+        report_progress(
+            &peer,
+            &progress_token,
+            &progress_tx,
+            IndexPhase::Storing,
+            90.0,
+            format!("Storing {} embeddings...", all_embeddings.len()),
+        )
+        .await;
 
         // Check for cancellation before storing
-        check_cancelled!(cancel_token);
+        check_cancelled!(
+            cancel_token,
+            IndexingCancelled {
+                files_indexed: files_added,
+                chunks_created: chunks_modified,
+                embeddings_generated: all_embeddings.len(),
+                files_updated,
+                files_removed,
+            }
+        );
 
         // Store all embeddings (pass normalized root path for per-project BM25)
         // Use successful_chunks to ensure metadata/contents match embeddings count
@@ -600,38 +1241,50 @@ pub async fn do_incremental_update(
         let contents: Vec<String> = successful_chunks.iter().map(|c| c.content.clone()).collect();
 
         if !all_embeddings.is_empty() {
-            client
-                .vector_db
-                .store_embeddings(all_embeddings.clone(), metadata, contents, &path)
-                .await
-                .context("Failed to store embeddings")?;
+            store_embeddings(client, all_embeddings.clone(), metadata, contents, &path).await?;
         }
 
-        (all_embeddings.len(), embed_result.errors)
+        (all_embeddings.len(), embed_result.errors, unembedded)
     } else {
-        (0, vec![])
+        (0, vec![], None)
     };
 
-    // Collect any embedding errors (logged but not fatal)
-    for err in embed_errors {
-        tracing::warn!("Embedding error during incremental update: {}", err);
+    // Collect any embedding errors (logged but not fatal)
+    for err in &embed_errors {
+        tracing::warn!("Embedding error during incremental update: {}", err);
+    }
+
+    // Cache files left partly embedded without a valid hash, so the next run
+    // deletes what was stored for them and embeds them again
+    let deadline_exceeded = unembedded.is_some();
+    for file in unembedded.into_iter().flatten() {
+        new_hashes.insert(file, String::new());
+    }
+
+    // Refresh definitions and references for repo-wide code navigation
+    if !files_to_index.is_empty() || !stale_paths.is_empty() {
+        if let Err(e) =
+            update_relations(client, &walker, &files_to_index, &stale_paths, &path).await
+        {
+            tracing::warn!("Failed to update code relations: {:#}", e);
+        }
     }
 
     // Send progress before saving cache
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 95.0,
-                total: Some(100.0),
-                message: Some("Saving cache...".into()),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Finalizing,
+        95.0,
+        "Saving cache...".into(),
+    )
+    .await;
 
     // Update persistent cache
     let mut cache = client.hash_cache.write().await;
-    cache.update_root(path, new_hashes);
+    cache.update_root(path.clone(), new_hashes);
+    cache.set_root_mtimes(&path, walked.mtimes);
 
     // Persist to disk
     if let Err(e) = cache.save(&client.cache_path) {
@@ -640,16 +1293,15 @@ pub async fn do_incremental_update(
     drop(cache);
 
     // Send progress before flush
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 98.0,
-                total: Some(100.0),
-                message: Some("Flushing index to disk...".into()),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Finalizing,
+        98.0,
+        "Flushing index to disk...".into(),
+    )
+    .await;
 
     // Flush the vector database to disk
     client
@@ -659,27 +1311,44 @@ pub async fn do_incremental_update(
         .context("Failed to flush index to disk")?;
 
     // Send final completion progress
-    if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-        let _ = peer
-            .notify_progress(ProgressNotificationParam {
-                progress_token: token.clone(),
-                progress: 100.0,
-                total: Some(100.0),
-                message: Some("Incremental update complete!".into()),
-            })
-            .await;
-    }
+    report_progress(
+        &peer,
+        &progress_token,
+        &progress_tx,
+        IndexPhase::Complete,
+        100.0,
+        "Incremental update complete!".into(),
+    )
+    .await;
+
+    tracing::info!(
+        "Incremental update: {} files added, {} updated, {} removed; {} old chunks deleted, {} chunks created",
+        files_added,
+        files_updated,
+        files_removed,
+        chunks_removed,
+        chunks_modified
+    );
 
-    Ok(IndexResponse {
+    let response = IndexResponse {
         mode: crate::types::IndexingMode::Incremental,
         files_indexed: files_added,
         chunks_created: chunks_modified,
         embeddings_generated,
         duration_ms: start.elapsed().as_millis() as u64,
-        errors: vec![],
+        errors: if deadline_exceeded {
+            embed_errors
+        } else {
+            vec![]
+        },
         files_updated,
         files_removed,
-    })
+        file_errors: walked.failed,
+    };
+    if deadline_exceeded {
+        return Err(anyhow::Error::new(EmbeddingDeadlineExceeded(response)));
+    }
+    Ok(response)
 }
 
 /// Smart index that automatically chooses between full and incremental based on existing cache
@@ -693,6 +1362,7 @@ pub async fn do_index_smart(
     max_file_size: usize,
     peer: Option<Peer<RoleServer>>,
     progress_token: Option<ProgressToken>,
+    progress_tx: Option<mpsc::Sender<IndexProgress>>,
     cancel_token: CancellationToken,
 ) -> Result<IndexResponse> {
     use super::IndexLockResult;
@@ -706,16 +1376,15 @@ pub async fn do_index_smart(
             tracing::info!("Waiting for existing indexing operation in this process to complete for: {}", path);
 
             // Send progress notification if we have a peer
-            if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-                let _ = peer
-                    .notify_progress(ProgressNotificationParam {
-                        progress_token: token.clone(),
-                        progress: 0.0,
-                        total: Some(100.0),
-                        message: Some("Waiting for existing indexing operation to complete...".into()),
-                    })
-                    .await;
-            }
+            report_progress(
+                &peer,
+                &progress_token,
+                &progress_tx,
+                IndexPhase::Preparing,
+                0.0,
+                "Waiting for existing indexing operation to complete...".into(),
+            )
+            .await;
 
             // Wait for the result from the other operation
             match receiver.recv().await {
@@ -740,21 +1409,19 @@ pub async fn do_index_smart(
             );
 
             // Send progress notification if we have a peer
-            if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-                let _ = peer
-                    .notify_progress(ProgressNotificationParam {
-                        progress_token: token.clone(),
-                        progress: 0.0,
-                        total: Some(100.0),
-                        message: Some("Waiting for another process to finish indexing...".into()),
-                    })
-                    .await;
-            }
+            report_progress(
+                &peer,
+                &progress_token,
+                &progress_tx,
+                IndexPhase::Preparing,
+                0.0,
+                "Waiting for another process to finish indexing...".into(),
+            )
+            .await;
 
             // Block until we can acquire the filesystem lock (with 30 min timeout)
             // This happens when the other process finishes indexing
             use super::FsLockGuard;
-            use std::time::Duration;
 
             let path_for_lock = normalized_path.clone();
             let fs_lock_result = tokio::task::spawn_blocking(move || {
@@ -788,6 +1455,7 @@ pub async fn do_index_smart(
                         errors: vec![],
                         files_updated: 0,
                         files_removed: 0,
+                        file_errors: vec![],
                     })
                 }
                 None => {
@@ -801,17 +1469,20 @@ pub async fn do_index_smart(
         }
         IndexLockResult::Acquired(lock) => {
             // We acquired the lock, perform the actual indexing
-            let result = do_index_smart_inner(
-                client,
-                path.clone(),
-                project,
-                include_patterns,
-                exclude_patterns,
-                max_file_size,
-                peer,
-                progress_token,
-                cancel_token,
-            )
+            let result = with_operation_retries(client, &path, || {
+                do_index_smart_inner(
+                    client,
+                    path.clone(),
+                    project.clone(),
+                    include_patterns.clone(),
+                    exclude_patterns.clone(),
+                    max_file_size,
+                    peer.clone(),
+                    progress_token.clone(),
+                    progress_tx.clone(),
+                    cancel_token.clone(),
+                )
+            })
             .await;
 
             // Broadcast the result to any waiters (even on error, so they don't hang)
@@ -822,17 +1493,8 @@ pub async fn do_index_smart(
                 Err(e) => {
                     // On error, broadcast an error response so waiters don't hang
                     tracing::error!("Indexing failed for {}: {}", path, e);
-                    let error_response = IndexResponse {
-                        mode: crate::types::IndexingMode::Full,
-                        files_indexed: 0,
-                        chunks_created: 0,
-                        embeddings_generated: 0,
-                        duration_ms: 0,
-                        errors: vec![format!("Indexing failed: {}", e)],
-                        files_updated: 0,
-                        files_removed: 0,
-                    };
-                    lock.broadcast_result(&error_response);
+                    flush_after_failure(client).await;
+                    lock.broadcast_result(&error_response(e));
                 }
             }
 
@@ -845,6 +1507,341 @@ pub async fn do_index_smart(
     }
 }
 
+/// Flush what a failed or cancelled run stored
+///
+/// BM25 writers stay open between writes, holding the index locks other
+/// processes wait for, until the vector database is flushed.
+async fn flush_after_failure(client: &RagClient) {
+    if let Err(e) = client.vector_db.flush().await {
+        tracing::warn!("Failed to flush index after a failed run: {:#}", e);
+    }
+}
+
+/// Delay before the first retry of a failed index operation (doubled per retry)
+const OPERATION_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest delay between retries of a failed index operation
+const OPERATION_RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Run an index operation, restarting it from scratch on transient failures
+///
+/// Failures recognized by [`is_retryable_error`] are retried up to
+/// `indexing.max_operation_retries` times with exponential backoff; anything
+/// else (and the last failure) is returned as is. Before each retry the data
+/// of the failed attempt is dropped while the root stays marked dirty, so the
+/// next attempt is a clean full index.
+async fn with_operation_retries<F, Fut>(
+    client: &RagClient,
+    path: &str,
+    mut attempt: F,
+) -> Result<IndexResponse>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<IndexResponse>>,
+{
+    let max_retries = client.config.indexing.max_operation_retries;
+    let mut retries = 0;
+    loop {
+        match attempt().await {
+            Err(e) if retries < max_retries && is_retryable_error(&e) => {
+                retries += 1;
+                let delay = OPERATION_RETRY_BASE_DELAY
+                    .saturating_mul(2u32.saturating_pow(retries - 1))
+                    .min(OPERATION_RETRY_MAX_DELAY);
+                tracing::warn!(
+                    "Indexing {} failed with a transient error: {:#}. Retrying from scratch in {:?} (retry {}/{})",
+                    path,
+                    e,
+                    delay,
+                    retries,
+                    max_retries
+                );
+
+                let normalized_path = RagClient::normalize_path(path)?;
+                reset_failed_attempt(client, &normalized_path).await?;
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Drop everything a failed index attempt left for a root
+///
+/// The root is forgotten by the hash cache but stays marked dirty until an
+/// attempt succeeds.
+async fn reset_failed_attempt(client: &RagClient, normalized_path: &str) -> Result<()> {
+    clear_path_data(client, normalized_path).await?;
+
+    // Chunks stored by the failed attempt are not in the hash cache yet
+    let partial_files = client
+        .vector_db
+        .get_indexed_files(normalized_path)
+        .await
+        .context("Failed to list files stored by the failed attempt")?;
+    for file_path in partial_files {
+        if let Err(e) = client.vector_db.delete_by_file(&file_path).await {
+            tracing::warn!(
+                "Failed to delete embeddings for file '{}': {}",
+                file_path,
+                e
+            );
+        }
+        if let Err(e) = client
+            .relations_store
+            .delete_by_file(&file_path, normalized_path)
+            .await
+        {
+            tracing::warn!("Failed to delete relations for file '{}': {}", file_path, e);
+        }
+    }
+
+    let mut cache = client.hash_cache.write().await;
+    cache.remove_root(normalized_path);
+    cache.mark_dirty(normalized_path);
+    cache
+        .save(&client.cache_path)
+        .context("Failed to save cache before retrying indexing")?;
+    Ok(())
+}
+
+/// Response broadcast to waiters when indexing fails
+fn error_response(error: &anyhow::Error) -> IndexResponse {
+    IndexResponse {
+        mode: crate::types::IndexingMode::Full,
+        files_indexed: 0,
+        chunks_created: 0,
+        embeddings_generated: 0,
+        duration_ms: 0,
+        errors: vec![format!("Indexing failed: {}", error)],
+        files_updated: 0,
+        files_removed: 0,
+        file_errors: vec![],
+    }
+}
+
+/// Rebuild the index of a codebase from scratch
+///
+/// With `indexing.warm_reindex` enabled (LanceDB only), the new index is built
+/// in a staging table while queries keep returning the old results, and is
+/// swapped in once complete. Otherwise the existing data is dropped first and
+/// the codebase is fully re-indexed in place.
+#[allow(clippy::too_many_arguments)]
+pub async fn do_reindex(
+    client: &RagClient,
+    path: String,
+    project: Option<String>,
+    include_patterns: Vec<String>,
+    exclude_patterns: Vec<String>,
+    max_file_size: usize,
+    peer: Option<Peer<RoleServer>>,
+    progress_token: Option<ProgressToken>,
+    progress_tx: Option<mpsc::Sender<IndexProgress>>,
+    cancel_token: CancellationToken,
+) -> Result<IndexResponse> {
+    use super::IndexLockResult;
+
+    let lock = match client.try_acquire_index_lock(&path).await? {
+        IndexLockResult::Acquired(lock) => lock,
+        _ => anyhow::bail!("Indexing already in progress for {}", path),
+    };
+
+    let result = do_reindex_inner(
+        client,
+        path.clone(),
+        project,
+        include_patterns,
+        exclude_patterns,
+        max_file_size,
+        peer,
+        progress_token,
+        progress_tx,
+        cancel_token,
+    )
+    .await;
+
+    match &result {
+        Ok(response) => lock.broadcast_result(response),
+        Err(e) => {
+            tracing::error!("Reindexing failed for {}: {}", path, e);
+            flush_after_failure(client).await;
+            lock.broadcast_result(&error_response(e));
+        }
+    }
+    lock.release().await;
+
+    result
+}
+
+/// Inner implementation of reindexing (called when we have the lock)
+#[allow(clippy::too_many_arguments)]
+async fn do_reindex_inner(
+    client: &RagClient,
+    path: String,
+    project: Option<String>,
+    include_patterns: Vec<String>,
+    exclude_patterns: Vec<String>,
+    max_file_size: usize,
+    peer: Option<Peer<RoleServer>>,
+    progress_token: Option<ProgressToken>,
+    progress_tx: Option<mpsc::Sender<IndexProgress>>,
+    cancel_token: CancellationToken,
+) -> Result<IndexResponse> {
+    let normalized_path = RagClient::normalize_path(&path)?;
+
+    // Only a healthy index built with the same model can keep serving during
+    // the rebuild; anything else goes through the regular smart indexing path
+    let (indexed, same_model, is_dirty) = {
+        let cache = client.hash_cache.read().await;
+        (
+            cache.get_root(&normalized_path).is_some(),
+            cache.root_model(&normalized_path) == client.model_override.as_deref(),
+            cache.is_dirty(&normalized_path),
+        )
+    };
+
+    if indexed && same_model && !is_dirty && client.config.indexing.warm_reindex {
+        #[cfg(feature = "qdrant-backend")]
+        anyhow::bail!(
+            "Warm reindex is not supported by the Qdrant backend; \
+             disable indexing.warm_reindex to reindex in place"
+        );
+
+        #[cfg(not(feature = "qdrant-backend"))]
+        return warm_reindex(
+            client,
+            normalized_path,
+            project,
+            include_patterns,
+            exclude_patterns,
+            max_file_size,
+            peer,
+            progress_token,
+            progress_tx,
+            cancel_token,
+        )
+        .await;
+    }
+
+    if indexed && same_model {
+        clear_path_data(client, &normalized_path).await?;
+
+        let mut cache = client.hash_cache.write().await;
+        cache.remove_root(&normalized_path);
+        if let Err(e) = cache.save(&client.cache_path) {
+            tracing::warn!("Failed to save cache before reindexing: {}", e);
+        }
+    }
+
+    do_index_smart_inner(
+        client,
+        normalized_path,
+        project,
+        include_patterns,
+        exclude_patterns,
+        max_file_size,
+        peer,
+        progress_token,
+        progress_tx,
+        cancel_token,
+    )
+    .await
+}
+
+/// Build the codebase into a staging table and swap it in when complete
+///
+/// The staging table receives the fresh index of `normalized_path` first and
+/// a copy of every other codebase last. Writes to other codebases wait while
+/// they are copied and the staging table is swapped in, so none are missed.
+#[cfg(not(feature = "qdrant-backend"))]
+#[allow(clippy::too_many_arguments)]
+async fn warm_reindex(
+    client: &RagClient,
+    normalized_path: String,
+    project: Option<String>,
+    include_patterns: Vec<String>,
+    exclude_patterns: Vec<String>,
+    max_file_size: usize,
+    peer: Option<Peer<RoleServer>>,
+    progress_token: Option<ProgressToken>,
+    progress_tx: Option<mpsc::Sender<IndexProgress>>,
+    cancel_token: CancellationToken,
+) -> Result<IndexResponse> {
+    tracing::info!("Warm reindex of '{}' into a staging table", normalized_path);
+
+    let live = client.vector_db.clone();
+    let staging = Arc::new(
+        live.create_staging(client.embedding_provider().dimension())
+            .await?,
+    );
+
+    // The live index stays intact, but the cached hashes are rewritten by the
+    // build, so mark dirty until the staging table is swapped in
+    let previous_hashes = {
+        let mut cache = client.hash_cache.write().await;
+        let previous_hashes = cache
+            .get_root(&normalized_path)
+            .cloned()
+            .unwrap_or_default();
+        cache.mark_dirty(&normalized_path);
+        if let Err(e) = cache.save(&client.cache_path) {
+            cache.clear_dirty(&normalized_path);
+            drop(cache);
+            let _ = live.drop_staging(&staging).await;
+            anyhow::bail!("Failed to mark index as dirty before reindexing: {}", e);
+        }
+        previous_hashes
+    };
+
+    let mut staging_client = client.clone();
+    staging_client.vector_db = staging.clone();
+
+    let result = async {
+        let response = do_index(
+            &staging_client,
+            normalized_path.clone(),
+            project,
+            include_patterns,
+            exclude_patterns,
+            max_file_size,
+            peer,
+            progress_token,
+            progress_tx,
+            cancel_token,
+        )
+        .await?;
+        live.swap_in_with_roots_except(&staging, &normalized_path)
+            .await?;
+        Ok::<_, anyhow::Error>(response)
+    }
+    .await;
+
+    if let Err(e) = &result {
+        tracing::warn!(
+            "Warm reindex failed for '{}', keeping the previous index: {}",
+            normalized_path,
+            e
+        );
+        if let Err(e) = live.drop_staging(&staging).await {
+            tracing::warn!("Failed to drop staging table: {}", e);
+        }
+    }
+
+    let mut cache = client.hash_cache.write().await;
+    if result.is_ok() {
+        cache.set_root_model(&normalized_path, client.model_override.clone());
+    } else {
+        // The previous index is still live, so its hashes are still accurate
+        cache.update_root(normalized_path.clone(), previous_hashes);
+    }
+    cache.clear_dirty(&normalized_path);
+    if let Err(e) = cache.save(&client.cache_path) {
+        tracing::warn!("Failed to save cache after reindexing: {}", e);
+    }
+
+    result
+}
+
 /// Default stale dirty flag timeout: 2 hours
 /// If a dirty flag is older than this, it's likely from a crashed/cancelled process
 const STALE_DIRTY_FLAG_TIMEOUT_SECS: u64 = 2 * 60 * 60;
@@ -957,11 +1954,27 @@ async fn do_index_smart_inner(
     max_file_size: usize,
     peer: Option<Peer<RoleServer>>,
     progress_token: Option<ProgressToken>,
+    progress_tx: Option<mpsc::Sender<IndexProgress>>,
     cancel_token: CancellationToken,
 ) -> Result<IndexResponse> {
+    let start = Instant::now();
+
     // Normalize path to canonical form for consistent cache lookups
     let normalized_path = RagClient::normalize_path(&path)?;
 
+    // Stop at the next cancellation checkpoint once the time limit is reached
+    let time_limit = client.config.indexing.max_duration();
+    let parent_token = cancel_token;
+    let cancel_token = parent_token.child_token();
+    let deadline_timer = time_limit.map(|limit| {
+        let token = cancel_token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(limit).await;
+            tracing::warn!("Indexing time limit of {:?} reached, stopping", limit);
+            token.cancel();
+        })
+    });
+
     // Check if index is dirty (previous indexing was interrupted)
     let is_dirty = {
         let cache = client.hash_cache.read().await;
@@ -988,16 +2001,15 @@ async fn do_index_smart_inner(
                 );
 
                 // Send progress notification about dirty state
-                if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-                    let _ = peer
-                        .notify_progress(ProgressNotificationParam {
-                            progress_token: token.clone(),
-                            progress: 0.0,
-                            total: Some(100.0),
-                            message: Some(format!("Corrupted index detected ({}), clearing...", reason)),
-                        })
-                        .await;
-                }
+                report_progress(
+                    &peer,
+                    &progress_token,
+                    &progress_tx,
+                    IndexPhase::Preparing,
+                    0.0,
+                    format!("Corrupted index detected ({}), clearing...", reason),
+                )
+                .await;
 
                 // Clear any existing embeddings for this path
                 if let Err(e) = clear_path_data(client, &normalized_path).await {
@@ -1026,19 +2038,18 @@ async fn do_index_smart_inner(
                 );
 
                 // Send progress notification
-                if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-                    let _ = peer
-                        .notify_progress(ProgressNotificationParam {
-                            progress_token: token.clone(),
-                            progress: 0.0,
-                            total: Some(100.0),
-                            message: Some(format!(
-                                "Stale dirty flag detected (age: {}s), clearing...",
-                                age_secs
-                            )),
-                        })
-                        .await;
-                }
+                report_progress(
+                    &peer,
+                    &progress_token,
+                    &progress_tx,
+                    IndexPhase::Preparing,
+                    0.0,
+                    format!(
+                        "Stale dirty flag detected (age: {}s), clearing...",
+                        age_secs
+                    ),
+                )
+                .await;
 
                 // Just clear the dirty flag, don't remove the cache
                 let mut cache = client.hash_cache.write().await;
@@ -1061,16 +2072,15 @@ async fn do_index_smart_inner(
                 );
 
                 // Send progress notification
-                if let (Some(peer), Some(token)) = (&peer, &progress_token) {
-                    let _ = peer
-                        .notify_progress(ProgressNotificationParam {
-                            progress_token: token.clone(),
-                            progress: 0.0,
-                            total: Some(100.0),
-                            message: Some("Index appears complete, clearing stale dirty flag...".into()),
-                        })
-                        .await;
-                }
+                report_progress(
+                    &peer,
+                    &progress_token,
+                    &progress_tx,
+                    IndexPhase::Preparing,
+                    0.0,
+                    "Index appears complete, clearing stale dirty flag...".into(),
+                )
+                .await;
 
                 // Clear the dirty flag
                 let mut cache = client.hash_cache.write().await;
@@ -1084,6 +2094,45 @@ async fn do_index_smart_inner(
         }
     }
 
+    // A codebase re-indexed with a different embedding model can't be updated
+    // incrementally: drop the old model's data and start over
+    let previous_model = {
+        let cache = client.hash_cache.read().await;
+        cache
+            .get_root(&normalized_path)
+            .map(|_| cache.root_model(&normalized_path).map(str::to_string))
+    };
+    if let Some(previous_model) = previous_model
+        && previous_model != client.model_override
+    {
+        tracing::info!(
+            "Embedding model for '{}' changed from '{}' to '{}', performing full reindex",
+            normalized_path,
+            previous_model
+                .clone()
+                .unwrap_or_else(|| client.default_model_name()),
+            client.model_name()
+        );
+
+        let previous_client = client.for_model(previous_model.as_deref()).await?;
+        if let Err(e) = clear_path_data(&previous_client, &normalized_path).await {
+            tracing::error!(
+                "Failed to clear index data of the previous model for '{}': {}",
+                normalized_path,
+                e
+            );
+        }
+
+        let mut cache = client.hash_cache.write().await;
+        cache.remove_root(&normalized_path);
+        if let Err(e) = cache.save(&client.cache_path) {
+            tracing::warn!("Failed to save cache after model change: {}", e);
+        }
+        drop(cache);
+
+        force_full_reindex = true;
+    }
+
     // Mark the index as dirty BEFORE starting (persisted immediately)
     // This ensures that if we crash/are killed, the next run knows the index is corrupted
     {
@@ -1103,7 +2152,8 @@ async fn do_index_smart_inner(
     drop(cache);
 
     // Perform the actual indexing
-    let result = if has_existing_index && !force_full_reindex {
+    let incremental = has_existing_index && !force_full_reindex;
+    let result = if incremental {
         tracing::info!(
             "Existing index found for '{}' (normalized: '{}'), performing incremental update",
             path,
@@ -1118,6 +2168,7 @@ async fn do_index_smart_inner(
             max_file_size,
             peer,
             progress_token,
+            progress_tx,
             cancel_token,
         )
         .await
@@ -1137,6 +2188,7 @@ async fn do_index_smart_inner(
             max_file_size,
             peer,
             progress_token,
+            progress_tx,
             cancel_token,
         )
         .await
@@ -1148,6 +2200,7 @@ async fn do_index_smart_inner(
         Ok(_) => {
             let mut cache = client.hash_cache.write().await;
             cache.clear_dirty(&normalized_path);
+            cache.set_root_model(&normalized_path, client.model_override.clone());
             if let Err(e) = cache.save(&client.cache_path) {
                 tracing::warn!("Failed to clear dirty flag after successful indexing: {}", e);
                 // Don't fail the whole operation for this
@@ -1164,7 +2217,49 @@ async fn do_index_smart_inner(
         }
     }
 
-    result
+    if let Some(timer) = deadline_timer {
+        timer.abort();
+    }
+
+    // Running out of time is reported as partial progress rather than a failure
+    if let (Some(limit), Err(e)) = (time_limit, &result)
+        && cancel_token.is_cancelled()
+        && !parent_token.is_cancelled()
+    {
+        let default_progress = IndexingCancelled::default();
+        let progress = e
+            .downcast_ref::<IndexingCancelled>()
+            .unwrap_or(&default_progress);
+
+        return Ok(IndexResponse {
+            mode: if incremental {
+                crate::types::IndexingMode::Incremental
+            } else {
+                crate::types::IndexingMode::Full
+            },
+            files_indexed: progress.files_indexed,
+            chunks_created: progress.chunks_created,
+            embeddings_generated: progress.embeddings_generated,
+            duration_ms: start.elapsed().as_millis() as u64,
+            errors: vec![format!(
+                "Indexing stopped after reaching the {:.1}s time limit; the index is marked dirty and the next run will complete it",
+                limit.as_secs_f64()
+            )],
+            files_updated: progress.files_updated,
+            files_removed: progress.files_removed,
+            file_errors: vec![],
+        });
+    }
+
+    // A run cut short by the embedding phase deadline stored what it could;
+    // its response explains the rest is left for the next run
+    match result {
+        Err(e) => match e.downcast::<EmbeddingDeadlineExceeded>() {
+            Ok(EmbeddingDeadlineExceeded(response)) => Ok(response),
+            Err(e) => Err(e),
+        },
+        ok => ok,
+    }
 }
 
 /// Clear all indexed data for a specific path
@@ -1177,11 +2272,18 @@ async fn clear_path_data(client: &RagClient, normalized_path: &str) -> Result<()
         .unwrap_or_default();
     drop(cache);
 
-    // Delete embeddings for each file
+    // Delete embeddings and relations for each file
     for file_path in file_paths {
         if let Err(e) = client.vector_db.delete_by_file(&file_path).await {
             tracing::warn!("Failed to delete embeddings for file '{}': {}", file_path, e);
         }
+        if let Err(e) = client
+            .relations_store
+            .delete_by_file(&file_path, normalized_path)
+            .await
+        {
+            tracing::warn!("Failed to delete relations for file '{}': {}", file_path, e);
+        }
     }
 
     tracing::info!("Cleared indexed data for path: {}", normalized_path);
@@ -35,6 +35,7 @@ async fn test_do_index_empty_directory() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -68,6 +69,7 @@ async fn test_do_index_single_file() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -81,6 +83,112 @@ async fn test_do_index_single_file() {
     assert!(response.errors.is_empty());
 }
 
+#[tokio::test]
+async fn test_full_reindex_replaces_stored_relations() {
+    let (client, temp_dir) = create_test_client().await;
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(
+        data_dir.join("main.rs"),
+        "fn helper() {}\n\nfn main() {\n    helper();\n}\n",
+    )
+    .unwrap();
+
+    let index = || {
+        do_index(
+            &client,
+            data_dir.to_string_lossy().to_string(),
+            None,
+            vec![],
+            vec![],
+            1024 * 1024,
+            None,
+            None,
+            None,
+            test_cancel_token(),
+        )
+    };
+    index().await.unwrap();
+    let first = client.relations_store.get_stats().await.unwrap();
+    assert!(first.definition_count > 0);
+
+    // Indexing again replaces the root's relations instead of adding to them
+    index().await.unwrap();
+    let second = client.relations_store.get_stats().await.unwrap();
+    assert_eq!(second.definition_count, first.definition_count);
+    assert_eq!(second.reference_count, first.reference_count);
+}
+
+#[tokio::test]
+async fn test_do_index_reports_skipped_files() {
+    let (client, temp_dir) = create_test_client().await;
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("main.rs"), "fn main() {}").unwrap();
+    std::fs::write(data_dir.join("generated.rs"), "// data\n".repeat(64)).unwrap();
+
+    let response = do_index(
+        &client,
+        data_dir.to_string_lossy().to_string(),
+        None,
+        vec![],
+        vec![],
+        256,
+        None,
+        None,
+        None,
+        test_cancel_token(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(response.files_indexed, 1);
+    assert!(response.errors.is_empty());
+    assert_eq!(response.file_errors.len(), 1);
+    assert_eq!(response.file_errors[0].path, "generated.rs");
+    assert!(response.file_errors[0].reason.contains("byte limit"));
+}
+
+#[tokio::test]
+async fn test_do_index_truncates_oversized_content() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.indexing.max_content_chars = 10_000;
+    client.config = Arc::new(config);
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    // 20000 lines of ~20 characters, well under max_file_size
+    let content: String = (0..20_000)
+        .map(|i| format!("generated line {:05}\n", i))
+        .collect();
+    std::fs::write(data_dir.join("bundle.txt"), content).unwrap();
+
+    let response = do_index(
+        &client,
+        data_dir.to_string_lossy().to_string(),
+        None,
+        vec![],
+        vec![],
+        1024 * 1024,
+        None,
+        None,
+        None,
+        test_cancel_token(),
+    )
+    .await
+    .unwrap();
+
+    // Only the first ~500 lines are chunked, 50 lines at a time
+    assert_eq!(response.files_indexed, 1);
+    assert!(response.chunks_created > 0);
+    assert!(
+        response.chunks_created <= 12,
+        "expected a bounded chunk count, got {}",
+        response.chunks_created
+    );
+}
+
 #[tokio::test]
 async fn test_do_index_multiple_files() {
     let (client, temp_dir) = create_test_client().await;
@@ -99,6 +207,7 @@ async fn test_do_index_multiple_files() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -126,6 +235,7 @@ async fn test_do_index_with_exclude_patterns() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -155,6 +265,7 @@ async fn test_incremental_update_no_changes() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await
@@ -170,6 +281,7 @@ async fn test_incremental_update_no_changes() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -182,6 +294,74 @@ async fn test_incremental_update_no_changes() {
     assert_eq!(response.files_removed, 0);
 }
 
+#[tokio::test]
+async fn test_incremental_update_skips_files_with_unchanged_mtime() {
+    let (client, temp_dir) = create_test_client().await;
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    let file_path = data_dir.join("test.rs");
+    let set_mtime = |mtime: std::time::SystemTime| {
+        std::fs::File::options()
+            .write(true)
+            .open(&file_path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    };
+    let indexed_at = std::time::SystemTime::now() - Duration::from_secs(3600);
+    std::fs::write(&file_path, "fn main() {}").unwrap();
+    set_mtime(indexed_at);
+
+    let root = data_dir.to_string_lossy().to_string();
+    let update = || {
+        do_incremental_update(
+            &client,
+            root.clone(),
+            None,
+            vec![],
+            vec![],
+            1024 * 1024,
+            None,
+            None,
+            None,
+            test_cancel_token(),
+        )
+    };
+    do_index(
+        &client,
+        root.clone(),
+        None,
+        vec![],
+        vec![],
+        1024 * 1024,
+        None,
+        None,
+        None,
+        test_cancel_token(),
+    )
+    .await
+    .unwrap();
+    assert!(
+        client
+            .hash_cache
+            .read()
+            .await
+            .get_root_mtimes(&root)
+            .is_some_and(|mtimes| mtimes.contains_key("test.rs"))
+    );
+
+    // Same mtime: the file is not read again, even though its content changed
+    std::fs::write(&file_path, "fn main() { println!(); }").unwrap();
+    set_mtime(indexed_at);
+    let response = update().await.unwrap();
+    assert_eq!(response.files_updated, 0);
+
+    // A new mtime falls back to comparing content hashes
+    set_mtime(indexed_at + Duration::from_secs(60));
+    let response = update().await.unwrap();
+    assert_eq!(response.files_updated, 1);
+}
+
 #[tokio::test]
 async fn test_incremental_update_new_file() {
     let (client, temp_dir) = create_test_client().await;
@@ -199,6 +379,7 @@ async fn test_incremental_update_new_file() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await
@@ -217,6 +398,7 @@ async fn test_incremental_update_new_file() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -246,6 +428,7 @@ async fn test_incremental_update_modified_file() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await
@@ -264,6 +447,7 @@ async fn test_incremental_update_modified_file() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -293,6 +477,7 @@ async fn test_incremental_update_removed_file() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await
@@ -311,6 +496,7 @@ async fn test_incremental_update_removed_file() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -341,6 +527,7 @@ async fn test_incremental_update_mixed_changes() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await
@@ -361,6 +548,7 @@ async fn test_incremental_update_mixed_changes() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -390,6 +578,7 @@ async fn test_smart_index_first_time_full() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -417,6 +606,7 @@ async fn test_smart_index_second_time_incremental() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await
@@ -433,6 +623,7 @@ async fn test_smart_index_second_time_incremental() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await
@@ -459,6 +650,7 @@ async fn test_smart_index_path_normalization() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await
@@ -475,6 +667,7 @@ async fn test_smart_index_path_normalization() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -501,6 +694,7 @@ async fn test_index_with_project_name() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -527,6 +721,7 @@ async fn test_index_preserves_cache_across_operations() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await
@@ -555,6 +750,7 @@ async fn test_incremental_update_empty_directory() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -580,6 +776,7 @@ async fn test_do_index_nonexistent_path() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -615,6 +812,7 @@ async fn test_do_index_with_very_large_file() {
         1024, // 1KB limit
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -625,6 +823,47 @@ async fn test_do_index_with_very_large_file() {
     assert_eq!(response.files_indexed, 1);
 }
 
+#[tokio::test]
+async fn test_chunks_over_model_limit_are_split() {
+    let (client, temp_dir) = create_test_client().await;
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+
+    // A single function well past the model's 256-token input limit
+    let body = (1..=100)
+        .map(|i| format!("    let value_{} = compute({}, {} + 1);", i, i, i))
+        .collect::<Vec<_>>()
+        .join("\n");
+    std::fs::write(
+        data_dir.join("long.rs"),
+        format!("fn long() {{\n{}\n}}\n", body),
+    )
+    .unwrap();
+
+    let files = walk_files(
+        &client,
+        Arc::new(FileWalker::new(&data_dir, 1024 * 1024)),
+        &data_dir.to_string_lossy(),
+    )
+    .await
+    .unwrap()
+    .files;
+    let chunks = chunk_files(&client, &files);
+
+    let budget = content_token_budget(client.embedding_provider().max_sequence_length());
+    assert!(chunks.len() > 1);
+    assert!(
+        chunks
+            .iter()
+            .all(|c| crate::embedding::estimate_tokens(&c.content) <= budget)
+    );
+    assert_eq!(chunks[0].metadata.start_line, 1);
+    for pair in chunks.windows(2) {
+        assert_eq!(pair[1].metadata.start_line, pair[0].metadata.end_line + 1);
+    }
+    assert_eq!(chunks.last().unwrap().metadata.end_line, 102);
+}
+
 #[tokio::test]
 async fn test_do_index_with_empty_file() {
     let (client, temp_dir) = create_test_client().await;
@@ -643,6 +882,7 @@ async fn test_do_index_with_empty_file() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -675,6 +915,7 @@ async fn test_do_index_with_binary_file() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -703,6 +944,7 @@ async fn test_do_index_with_include_patterns() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -732,6 +974,7 @@ async fn test_do_index_with_special_characters_in_filename() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -766,6 +1009,7 @@ async fn test_do_index_with_nested_directories() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -789,6 +1033,7 @@ async fn test_incremental_update_nonexistent_path() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -809,6 +1054,7 @@ async fn test_smart_index_with_invalid_path() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -832,6 +1078,7 @@ async fn test_do_index_respects_duration_tracking() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -861,6 +1108,7 @@ async fn test_do_index_with_whitespace_only_file() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -888,6 +1136,7 @@ async fn test_incremental_update_with_concurrent_file_changes() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await
@@ -906,6 +1155,7 @@ async fn test_incremental_update_with_concurrent_file_changes() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await;
@@ -946,6 +1196,7 @@ async fn test_concurrent_index_same_path_waits_for_result() {
             1024 * 1024,
             None,
             None,
+            None,
             CancellationToken::new(),
         )
         .await
@@ -965,6 +1216,7 @@ async fn test_concurrent_index_same_path_waits_for_result() {
             1024 * 1024,
             None,
             None,
+            None,
             CancellationToken::new(),
         )
         .await
@@ -1021,6 +1273,7 @@ async fn test_concurrent_index_different_paths_both_run() {
             1024 * 1024,
             None,
             None,
+            None,
             CancellationToken::new(),
         )
         .await
@@ -1040,6 +1293,7 @@ async fn test_concurrent_index_different_paths_both_run() {
             1024 * 1024,
             None,
             None,
+            None,
             CancellationToken::new(),
         )
         .await
@@ -1073,6 +1327,7 @@ async fn test_cancellation_before_indexing_starts() {
         1024 * 1024,
         None,
         None,
+        None,
         cancel_token,
     )
     .await;
@@ -1124,6 +1379,7 @@ async fn test_cancellation_during_file_walk() {
         1024 * 1024,
         None,
         None,
+        None,
         cancel_token,
     )
     .await;
@@ -1167,6 +1423,7 @@ async fn test_cancellation_stops_early_incremental() {
         1024 * 1024,
         None,
         None,
+        None,
         test_cancel_token(),
     )
     .await
@@ -1194,6 +1451,7 @@ async fn test_cancellation_stops_early_incremental() {
         1024 * 1024,
         None,
         None,
+        None,
         cancel_token,
     )
     .await;
@@ -1231,6 +1489,7 @@ async fn test_cancellation_stops_smart_index() {
         1024 * 1024,
         None,
         None,
+        None,
         cancel_token,
     )
     .await;
@@ -1264,6 +1523,7 @@ async fn test_uncancelled_token_completes_normally() {
         1024 * 1024,
         None,
         None,
+        None,
         cancel_token,
     )
     .await;
@@ -1320,6 +1580,7 @@ async fn test_cancellation_during_embedding_batch() {
         1024 * 1024,
         None,
         None,
+        None,
         cancel_token,
     )
     .await;
@@ -1335,3 +1596,446 @@ async fn test_cancellation_during_embedding_batch() {
         );
     }
 }
+
+#[tokio::test]
+async fn test_auto_batch_size_embeds_every_chunk() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.embedding.batch_size = 0;
+    config.embedding.max_batch_size = 16;
+    client.config = Arc::new(config);
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    for i in 0..60 {
+        std::fs::write(
+            data_dir.join(format!("file{}.rs", i)),
+            format!("fn func_{}() -> u32 {{\n    {}\n}}", i, i),
+        )
+        .unwrap();
+    }
+
+    let response = do_index(
+        &client,
+        data_dir.to_string_lossy().to_string(),
+        None,
+        vec![],
+        vec![],
+        1024 * 1024,
+        None,
+        None,
+        None,
+        test_cancel_token(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(response.files_indexed, 60);
+    assert_eq!(response.embeddings_generated, response.chunks_created);
+    assert!(response.errors.is_empty());
+}
+
+#[tokio::test]
+async fn test_parallel_embedding_keeps_chunk_order() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.embedding.parallelism = 3;
+    client.config = Arc::new(config);
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    let topics = [
+        "database connection pool",
+        "http request router",
+        "image thumbnail resizer",
+    ];
+    for (i, topic) in topics.iter().cycle().take(30).enumerate() {
+        std::fs::write(
+            data_dir.join(format!("file{}.rs", i)),
+            format!("/// {}\nfn item_{}() {{}}", topic, i),
+        )
+        .unwrap();
+    }
+    let chunks: Vec<CodeChunk> = walk_files(
+        &client,
+        Arc::new(FileWalker::new(&data_dir, 1024 * 1024)),
+        &data_dir.to_string_lossy(),
+    )
+    .await
+    .unwrap()
+    .files
+    .iter()
+    .flat_map(|file| client.chunker.chunk_file(file))
+    .collect();
+
+    let result = generate_embeddings_with_cancellation(
+        &client,
+        &chunks,
+        &test_cancel_token(),
+        &None,
+        &None,
+        &None,
+        0.0,
+        100.0,
+    )
+    .await
+    .unwrap();
+
+    // Chunks keep their order and their own embeddings, whichever batch finished first
+    assert!(result.errors.is_empty());
+    let contents = |chunks: &[CodeChunk]| chunks.iter().map(|c| c.content.clone()).collect();
+    let ordered: Vec<String> = contents(&result.successful_chunks);
+    assert_eq!(ordered, contents(&chunks));
+    let expected = client.embedding_provider().embed_batch(ordered).unwrap();
+    for (actual, expected) in result.embeddings.iter().zip(&expected) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-4);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_time_limit_stops_indexing_with_partial_progress() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.indexing.max_duration_secs = 0.001;
+    client.config = Arc::new(config);
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    for i in 0..60 {
+        std::fs::write(
+            data_dir.join(format!("file{}.rs", i)),
+            format!("fn func_{}() {{\n    println!(\"{}\");\n}}", i, i),
+        )
+        .unwrap();
+    }
+
+    let response = do_index_smart(
+        &client,
+        data_dir.to_string_lossy().to_string(),
+        None,
+        vec![],
+        vec![],
+        1024 * 1024,
+        None,
+        None,
+        None,
+        test_cancel_token(),
+    )
+    .await
+    .expect("Hitting the time limit should report partial progress, not fail");
+
+    assert_eq!(response.mode, crate::types::IndexingMode::Full);
+    assert!(response.files_indexed <= 60);
+    assert!(response.embeddings_generated <= response.chunks_created);
+    assert!(
+        response.errors.iter().any(|e| e.contains("time limit")),
+        "Response should explain the early stop: {:?}",
+        response.errors
+    );
+
+    // Nothing was stored and the index stays dirty for the next run
+    let root = RagClient::normalize_path(&data_dir.to_string_lossy()).unwrap();
+    assert!(client.hash_cache.read().await.is_dirty(&root));
+    assert_eq!(client.vector_db.count_by_root_path(&root).await.unwrap(), 0);
+
+    // Without the limit, the next run completes the index
+    let mut config = (*client.config).clone();
+    config.indexing.max_duration_secs = 0.0;
+    client.config = Arc::new(config);
+
+    let response = do_index_smart(
+        &client,
+        data_dir.to_string_lossy().to_string(),
+        None,
+        vec![],
+        vec![],
+        1024 * 1024,
+        None,
+        None,
+        None,
+        test_cancel_token(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.files_indexed, 60);
+    assert!(!client.hash_cache.read().await.is_dirty(&root));
+}
+
+/// Embeds with the wrapped provider on a pretend GPU, recording each batch size
+struct GpuProvider {
+    inner: Arc<dyn EmbeddingProvider>,
+    batch_lens: std::sync::Mutex<Vec<usize>>,
+}
+
+impl EmbeddingProvider for GpuProvider {
+    fn embed_batch(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        self.batch_lens.lock().unwrap().push(texts.len());
+        self.inner.embed_batch(texts)
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+
+    fn device(&self) -> crate::config::EmbeddingDevice {
+        crate::config::EmbeddingDevice::Cuda
+    }
+}
+
+#[tokio::test]
+async fn test_gpu_embeds_at_least_min_batch_per_call() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let provider = Arc::new(GpuProvider {
+        inner: client.embedding_provider(),
+        batch_lens: Default::default(),
+    });
+    client.embedding_override = Some(provider.clone());
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    for i in 0..GPU_MIN_BATCH_SIZE + 6 {
+        std::fs::write(
+            data_dir.join(format!("file{}.rs", i)),
+            format!("fn item_{}() {{}}", i),
+        )
+        .unwrap();
+    }
+    let chunks: Vec<CodeChunk> = walk_files(
+        &client,
+        Arc::new(FileWalker::new(&data_dir, 1024 * 1024)),
+        &data_dir.to_string_lossy(),
+    )
+    .await
+    .unwrap()
+    .files
+    .iter()
+    .flat_map(|file| client.chunker.chunk_file(file))
+    .collect();
+
+    // The cancellation check interval (4 by default) doesn't shrink GPU batches
+    let result = generate_embeddings_with_cancellation(
+        &client,
+        &chunks,
+        &test_cancel_token(),
+        &None,
+        &None,
+        &None,
+        0.0,
+        100.0,
+    )
+    .await
+    .unwrap();
+    assert_eq!(result.embeddings.len(), chunks.len());
+    let mut batch_lens = provider.batch_lens.lock().unwrap().clone();
+    batch_lens.sort_unstable();
+    assert_eq!(
+        batch_lens,
+        vec![chunks.len() - GPU_MIN_BATCH_SIZE, GPU_MIN_BATCH_SIZE]
+    );
+}
+
+/// Embeds with the wrapped provider, but stalls on batches mentioning "sleepy"
+struct SleepyProvider {
+    inner: Arc<dyn EmbeddingProvider>,
+}
+
+impl EmbeddingProvider for SleepyProvider {
+    fn embed_batch(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        if texts.iter().any(|text| text.contains("sleepy")) {
+            std::thread::sleep(Duration::from_secs(3));
+        }
+        self.inner.embed_batch(texts)
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+
+    fn max_sequence_length(&self) -> usize {
+        self.inner.max_sequence_length()
+    }
+}
+
+#[tokio::test]
+async fn test_embedding_deadline_stores_partial_results() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.embedding.batch_size = 1;
+    config.embedding.cancellation_check_interval = 1;
+    config.embedding.parallelism = 2;
+    config.embedding.phase_timeout_secs = 1;
+    client.config = Arc::new(config);
+    let inner = client.embedding_provider();
+    client.embedding_override = Some(Arc::new(SleepyProvider {
+        inner: inner.clone(),
+    }));
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    for i in 0..4 {
+        std::fs::write(
+            data_dir.join(format!("awake{}.rs", i)),
+            format!("fn awake_{}() {{}}", i),
+        )
+        .unwrap();
+    }
+    std::fs::write(data_dir.join("sleepy.rs"), "fn sleepy() {}").unwrap();
+
+    // Old enough for their mtimes to be trusted, so only a missing hash can
+    // make the next run embed the stalled file again
+    let indexed_at = std::time::SystemTime::now() - Duration::from_secs(3600);
+    for entry in std::fs::read_dir(&data_dir).unwrap() {
+        std::fs::File::options()
+            .write(true)
+            .open(entry.unwrap().path())
+            .unwrap()
+            .set_modified(indexed_at)
+            .unwrap();
+    }
+
+    let response = do_index_smart(
+        &client,
+        data_dir.to_string_lossy().to_string(),
+        None,
+        vec![],
+        vec![],
+        1024 * 1024,
+        None,
+        None,
+        None,
+        test_cancel_token(),
+    )
+    .await
+    .expect("Exceeding the embedding deadline should report partial results, not fail");
+
+    assert!(
+        response.errors.iter().any(|e| e.contains("phase deadline")),
+        "Response should explain the early stop: {:?}",
+        response.errors
+    );
+    assert!(response.embeddings_generated > 0);
+    assert!(response.embeddings_generated < response.chunks_created);
+
+    // What was embedded in time is stored, and the stalled file is left for the next run
+    let root = RagClient::normalize_path(&data_dir.to_string_lossy()).unwrap();
+    assert!(client.hash_cache.read().await.is_dirty(&root));
+    assert_eq!(
+        client.vector_db.count_by_root_path(&root).await.unwrap(),
+        response.embeddings_generated
+    );
+    assert_eq!(
+        client.hash_cache.read().await.get_root(&root).unwrap()["sleepy.rs"],
+        ""
+    );
+
+    // Once the model keeps up, the next run completes the index
+    client.embedding_override = Some(inner);
+    do_index_smart(
+        &client,
+        data_dir.to_string_lossy().to_string(),
+        None,
+        vec![],
+        vec![],
+        1024 * 1024,
+        None,
+        None,
+        None,
+        test_cancel_token(),
+    )
+    .await
+    .unwrap();
+    assert!(!client.hash_cache.read().await.is_dirty(&root));
+    assert_eq!(client.vector_db.count_by_root_path(&root).await.unwrap(), 5);
+}
+
+#[tokio::test]
+async fn test_transient_failure_restarts_indexing_from_scratch() {
+    use std::sync::atomic::AtomicUsize;
+
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.indexing.max_operation_retries = 2;
+    client.config = Arc::new(config);
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    for i in 0..3 {
+        std::fs::write(
+            data_dir.join(format!("file{}.rs", i)),
+            format!("fn func_{}() {{\n    println!(\"{}\");\n}}", i, i),
+        )
+        .unwrap();
+    }
+    let path = data_dir.to_string_lossy().to_string();
+
+    // The first attempt stores its chunks, then the backend connection drops
+    let attempts = AtomicUsize::new(0);
+    let (client_ref, path_ref, attempts_ref) = (&client, &path, &attempts);
+    let response = with_operation_retries(&client, &path, move || async move {
+        let response = do_index_smart_inner(
+            client_ref,
+            path_ref.clone(),
+            None,
+            vec![],
+            vec![],
+            1024 * 1024,
+            None,
+            None,
+            test_cancel_token(),
+        )
+        .await?;
+        if attempts_ref.fetch_add(1, Ordering::SeqCst) == 0 {
+            return Err(crate::error::VectorDbError::ConnectionFailed(
+                "connection reset".to_string(),
+            )
+            .into());
+        }
+        Ok(response)
+    })
+    .await
+    .expect("The retry should complete indexing");
+
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    assert_eq!(response.mode, crate::types::IndexingMode::Full);
+    assert_eq!(response.files_indexed, 3);
+
+    // The retry started from a clean slate: nothing is stored twice
+    let root = RagClient::normalize_path(&path).unwrap();
+    assert_eq!(
+        client.vector_db.count_by_root_path(&root).await.unwrap(),
+        response.chunks_created
+    );
+    assert!(!client.hash_cache.read().await.is_dirty(&root));
+}
+
+#[tokio::test]
+async fn test_fatal_failure_is_not_retried() {
+    use std::sync::atomic::AtomicUsize;
+
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.indexing.max_operation_retries = 2;
+    client.config = Arc::new(config);
+    let path = temp_dir.path().to_string_lossy().to_string();
+
+    let attempts = AtomicUsize::new(0);
+    let attempts_ref = &attempts;
+    let result = with_operation_retries(&client, &path, move || async move {
+        attempts_ref.fetch_add(1, Ordering::SeqCst);
+        Err(anyhow::anyhow!("Invalid chunk metadata"))
+    })
+    .await;
+
+    assert!(result.is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
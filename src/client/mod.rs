@@ -3,14 +3,17 @@
 //! This module provides the main client interface for using project-rag
 //! as a library in your own Rust applications.
 
-use crate::cache::HashCache;
+use crate::cache::{CacheCompaction, HashCache};
 use crate::config::Config;
-use crate::embedding::{EmbeddingProvider, FastEmbedManager};
+use crate::embedding::{EmbeddingProvider, Reranker, provider_from_config};
 use crate::git_cache::GitCache;
-use crate::indexer::{CodeChunker, FileInfo, detect_language};
+use crate::indexer::{ChunkingDiagnostic, CodeChunker, FileInfo, detect_language_with_overrides};
+use crate::query_cache::{QUERY_EMBEDDING_CACHE_FILE, QueryEmbeddingCache};
+use crate::relations::storage::{LanceRelationsStore, RelationsStore};
 use crate::relations::{
     DefinitionResult, HybridRelationsProvider, ReferenceResult, RelationsProvider,
 };
+use crate::telemetry::{self, PhaseSpans};
 use crate::types::*;
 use crate::vector_db::VectorDatabase;
 
@@ -22,12 +25,13 @@ use crate::vector_db::QdrantVectorDB;
 use crate::vector_db::LanceVectorDB;
 
 use anyhow::{Context, Result};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
+use tokio::sync::{OnceCell, RwLock};
+use tracing::Instrument;
 
 // Filesystem locking for cross-process coordination
 mod fs_lock;
@@ -37,6 +41,23 @@ pub(crate) use fs_lock::FsLockGuard;
 mod index_lock;
 pub(crate) use index_lock::{IndexLockGuard, IndexLockResult, IndexingOperation};
 
+// File-based cancellation for callers without access to the cancellation token
+mod cancel_file;
+pub use cancel_file::{CANCEL_FILE_POLL_INTERVAL, cancel_on_file};
+
+// Re-exported so library callers can cancel indexing without depending on tokio-util
+pub use tokio_util::sync::CancellationToken;
+
+// Embedding model recorded alongside the index, checked at startup
+mod index_metadata;
+use index_metadata::{IndexMetadata, check_index_model, index_metadata_path};
+
+/// Interval between checks while `clear_index` waits for indexing to finish
+const CLEAR_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Directory, inside the vector database directory, holding stored code relations
+const RELATIONS_DB_DIR: &str = "relations";
+
 /// Main client for interacting with the RAG system
 ///
 /// This client provides a high-level API for indexing codebases and performing
@@ -60,6 +81,7 @@ pub(crate) use index_lock::{IndexLockGuard, IndexLockResult, IndexingOperation};
 ///         include_patterns: vec!["**/*.rs".to_string()],
 ///         exclude_patterns: vec!["**/target/**".to_string()],
 ///         max_file_size: 1_048_576,
+///         model_name: None,
 ///     };
 ///     let response = client.index_codebase(index_req).await?;
 ///     println!("Indexed {} files", response.files_indexed);
@@ -69,7 +91,8 @@ pub(crate) use index_lock::{IndexLockGuard, IndexLockResult, IndexingOperation};
 /// ```
 #[derive(Clone)]
 pub struct RagClient {
-    pub(crate) embedding_provider: Arc<FastEmbedManager>,
+    // Provider this client embeds with (None = the current default model's)
+    pub(crate) embedding_override: Option<Arc<dyn EmbeddingProvider>>,
     #[cfg(feature = "qdrant-backend")]
     pub(crate) vector_db: Arc<QdrantVectorDB>,
     #[cfg(not(feature = "qdrant-backend"))]
@@ -87,6 +110,17 @@ pub struct RagClient {
     pub(crate) indexing_ops: Arc<RwLock<HashMap<String, IndexingOperation>>>,
     // Relations provider for code navigation (find definition, references, call graph)
     pub(crate) relations_provider: Arc<HybridRelationsProvider>,
+    // Definitions and references stored at index time, for repo-wide lookups
+    pub(crate) relations_store: Arc<LanceRelationsStore>,
+    // Default model (swapped in place on reload), and lazily loaded per-project models
+    pub(crate) default_model: Arc<std::sync::RwLock<DefaultModel>>,
+    pub(crate) model_backends: Arc<RwLock<HashMap<String, Arc<OnceCell<ModelBackend>>>>>,
+    // Non-default model this client embeds with (None = configured default)
+    pub(crate) model_override: Option<String>,
+    // Query embeddings of recent queries (shared by all models, keyed by model)
+    pub(crate) query_embeddings: Arc<std::sync::Mutex<QueryEmbeddingCache>>,
+    // Cross-encoder re-ranking query results (None = `search.rerank` off)
+    pub(crate) reranker: Option<Arc<Reranker>>,
 }
 
 impl RagClient {
@@ -123,16 +157,41 @@ impl RagClient {
     /// }
     /// ```
     pub async fn with_config(config: Config) -> Result<Self> {
+        // Initialize embedding provider with configured model
+        let embedding_provider = provider_from_config(&config.embedding)
+            .context("Failed to initialize embedding provider")?;
+
+        Self::with_config_and_provider(config, embedding_provider).await
+    }
+
+    /// Create a new RAG client embedding with the given provider instead of
+    /// the one `config.embedding` selects
+    ///
+    /// Lets tests inject a deterministic fake and applications bring their own
+    /// embedding service. The index is still recorded under
+    /// `config.embedding.model_name`, so it should name the provider's model.
+    pub async fn with_config_and_provider(
+        config: Config,
+        embedding_provider: Arc<dyn EmbeddingProvider>,
+    ) -> Result<Self> {
         tracing::info!("Initializing RAG client with configuration");
         tracing::debug!("Vector DB backend: {}", config.vector_db.backend);
         tracing::debug!("Embedding model: {}", config.embedding.model_name);
         tracing::debug!("Chunk size: {}", config.indexing.chunk_size);
 
-        // Initialize embedding provider with configured model
-        let embedding_provider = Arc::new(
-            FastEmbedManager::from_model_name(&config.embedding.model_name)
-                .context("Failed to initialize embedding provider")?,
-        );
+        // Load the cross-encoder only when re-ranking is enabled
+        let reranker = if config.search.rerank {
+            Some(Arc::new(
+                Reranker::from_model_name(
+                    &config.search.rerank_model,
+                    config.embedding.model_cache_dir.as_deref(),
+                    embedding_provider.device(),
+                )
+                .context("Failed to initialize rerank model")?,
+            ))
+        } else {
+            None
+        };
 
         // Initialize the appropriate vector database backend
         #[cfg(feature = "qdrant-backend")]
@@ -144,7 +203,9 @@ impl RagClient {
             Arc::new(
                 QdrantVectorDB::with_url(&config.vector_db.qdrant_url)
                     .await
-                    .context("Failed to initialize Qdrant vector database")?,
+                    .context("Failed to initialize Qdrant vector database")?
+                    .with_phase_spans(PhaseSpans::new(config.observability.phase_spans))
+                    .with_doc_boost(config.search.doc_boost()),
             )
         };
 
@@ -157,28 +218,64 @@ impl RagClient {
             Arc::new(
                 LanceVectorDB::with_path(&config.vector_db.lancedb_path.to_string_lossy())
                     .await
-                    .context("Failed to initialize LanceDB vector database")?,
+                    .context("Failed to initialize LanceDB vector database")?
+                    .with_phase_spans(PhaseSpans::new(config.observability.phase_spans))
+                    .with_ann_params(config.search.ann_params())
+                    .with_ann_index(config.vector_db.ann_index_params())
+                    .with_distance(config.vector_db.distance)
+                    .with_bm25_analyzer(config.bm25_analyzer())
+                    .with_bm25_writer_lock_timeout(Duration::from_secs(
+                        config.search.bm25_writer_lock_timeout_secs,
+                    ))
+                    .with_bm25_stale_lock_timeout(config.bm25_stale_lock_timeout())
+                    .with_doc_boost(config.search.doc_boost()),
             )
         };
 
-        // Initialize the database with the embedding dimension
-        vector_db
-            .initialize(embedding_provider.dimension())
-            .await
-            .context("Failed to initialize vector database collections")?;
-
-        // Create chunker with configured chunk size
-        let chunker = Arc::new(CodeChunker::default_strategy());
-
         // Load persistent hash cache
         let cache_path = config.cache.hash_cache_path.clone();
-        let hash_cache = HashCache::load(&cache_path).unwrap_or_else(|e| {
+        let mut hash_cache = HashCache::load(&cache_path).unwrap_or_else(|e| {
             tracing::warn!("Failed to load hash cache: {}, starting fresh", e);
             HashCache::default()
         });
+        hash_cache.format = config.cache.format;
 
         tracing::info!("Using hash cache file: {:?}", cache_path);
 
+        // An index built with another model can't take this model's vectors
+        let index_metadata = IndexMetadata {
+            model_name: config.embedding.model_name.clone(),
+            dimension: embedding_provider
+                .try_dimension()
+                .context("Failed to determine the embedding dimension")?,
+        };
+        let metadata_path = index_metadata_path(&config);
+        check_index_model(
+            &metadata_path,
+            &index_metadata,
+            vector_db.as_ref(),
+            &mut hash_cache,
+            &cache_path,
+            config.vector_db.auto_reindex_on_model_change,
+        )
+        .await?;
+
+        // Initialize the database with the embedding dimension
+        vector_db
+            .initialize(index_metadata.dimension)
+            .await
+            .context("Failed to initialize vector database collections")?;
+        index_metadata.save(&metadata_path)?;
+
+        // Create chunker with configured strategy and chunk size
+        let chunker = Arc::new(
+            CodeChunker::new(config.indexing.chunk_strategy())
+                .with_overlap(config.indexing.chunk_overlap)
+                .with_min_chunk_lines(config.indexing.min_chunk_lines)
+                .with_markup_chunking(config.indexing.markup_chunking)
+                .with_docs_project(config.indexing.docs_project),
+        );
+
         // Load persistent git cache
         let git_cache_path = config.cache.git_cache_path.clone();
         let git_cache = GitCache::load(&git_cache_path).unwrap_or_else(|e| {
@@ -188,14 +285,42 @@ impl RagClient {
 
         tracing::info!("Using git cache file: {:?}", git_cache_path);
 
+        // Query embedding cache: persisted next to the database when
+        // configured, otherwise kept in memory
+        let query_embeddings = if config.cache.persist_query_embeddings {
+            QueryEmbeddingCache::load(
+                &config
+                    .vector_db
+                    .lancedb_path
+                    .join(QUERY_EMBEDDING_CACHE_FILE),
+                config.search.query_cache_size,
+                &config.embedding.model_name,
+            )
+        } else {
+            QueryEmbeddingCache::in_memory(config.search.query_cache_size)
+        };
+
         // Initialize relations provider for code navigation
         let relations_provider = Arc::new(
             HybridRelationsProvider::new(false) // stack-graphs disabled by default
                 .context("Failed to initialize relations provider")?,
         );
+        let relations_store = Arc::new(
+            LanceRelationsStore::new(config.vector_db.lancedb_path.join(RELATIONS_DB_DIR))
+                .await
+                .context("Failed to initialize relations store")?,
+        );
+
+        let default_model = DefaultModel {
+            embedding: config.embedding.clone(),
+            backend: ModelBackend {
+                embedding_provider,
+                vector_db: vector_db.clone(),
+            },
+        };
 
         Ok(Self {
-            embedding_provider,
+            embedding_override: None,
             vector_db,
             chunker,
             hash_cache: Arc::new(RwLock::new(hash_cache)),
@@ -205,6 +330,12 @@ impl RagClient {
             config: Arc::new(config),
             indexing_ops: Arc::new(RwLock::new(HashMap::new())),
             relations_provider,
+            relations_store,
+            default_model: Arc::new(std::sync::RwLock::new(default_model)),
+            model_backends: Arc::new(RwLock::new(HashMap::new())),
+            model_override: None,
+            query_embeddings: Arc::new(std::sync::Mutex::new(query_embeddings)),
+            reranker,
         })
     }
 
@@ -228,6 +359,20 @@ impl RagClient {
         let canonical = std::fs::canonicalize(path)
             .with_context(|| format!("Failed to canonicalize path: {}", file_path))?;
 
+        // Refuse oversized files up front rather than parsing them in full
+        let size = std::fs::metadata(&canonical)
+            .with_context(|| format!("Failed to read metadata: {}", file_path))?
+            .len();
+        let max_file_size = self.config.relations.max_file_size;
+        if size > max_file_size {
+            anyhow::bail!(
+                "File too large for relations: {} is {} bytes, limit is {} (relations.max_file_size)",
+                file_path,
+                size,
+                max_file_size
+            );
+        }
+
         let content = std::fs::read_to_string(&canonical)
             .with_context(|| format!("Failed to read file: {}", file_path))?;
 
@@ -237,7 +382,7 @@ impl RagClient {
             .map(|s| s.to_string());
 
         let language = extension.as_ref().and_then(|ext| {
-            detect_language(ext)
+            detect_language_with_overrides(ext, &self.config.indexing.extension_languages)
         });
 
         // Compute file hash
@@ -266,6 +411,8 @@ impl RagClient {
             language,
             content,
             hash,
+            binary: false,
+            file_size: None,
         })
     }
 
@@ -441,6 +588,7 @@ impl RagClient {
     ///     include_patterns: vec!["**/*.rs".to_string()],
     ///     exclude_patterns: vec!["**/target/**".to_string()],
     ///     max_file_size: 1_048_576,
+    ///     model_name: None,
     /// };
     ///
     /// let response = client.index_codebase(request).await?;
@@ -451,14 +599,102 @@ impl RagClient {
     /// # }
     /// ```
     pub async fn index_codebase(&self, request: IndexRequest) -> Result<IndexResponse> {
+        // Default cancellation token - not cancellable from this API
+        self.index_codebase_with_cancellation(request, CancellationToken::new())
+            .await
+    }
+
+    /// Index a codebase, stopping at the next checkpoint once `cancel_token` is cancelled
+    ///
+    /// A cancelled run fails with an [`IndexingCancelled`] error carrying the
+    /// work completed so far; the codebase stays marked dirty and the next run
+    /// re-indexes it in full.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use project_rag::client::{CancellationToken, IndexingCancelled};
+    /// use project_rag::{IndexRequest, RagClient};
+    ///
+    /// # async fn example(request: IndexRequest) -> anyhow::Result<()> {
+    /// let client = RagClient::new().await?;
+    ///
+    /// let cancel_token = CancellationToken::new();
+    /// let on_ctrl_c = cancel_token.clone();
+    /// tokio::spawn(async move {
+    ///     let _ = tokio::signal::ctrl_c().await;
+    ///     on_ctrl_c.cancel();
+    /// });
+    ///
+    /// match client.index_codebase_with_cancellation(request, cancel_token).await {
+    ///     Ok(response) => println!("Indexed {} files", response.files_indexed),
+    ///     Err(e) => match e.downcast_ref::<IndexingCancelled>() {
+    ///         Some(progress) => println!("Stopped after {} files", progress.files_indexed),
+    ///         None => return Err(e),
+    ///     },
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn index_codebase_with_cancellation(
+        &self,
+        request: IndexRequest,
+        cancel_token: CancellationToken,
+    ) -> Result<IndexResponse> {
+        self.index_codebase_inner(request, cancel_token, None).await
+    }
+
+    /// Index a codebase, sending progress updates to `tx` as indexing advances
+    ///
+    /// The updates carry the same phase, percentage and message as the MCP
+    /// progress notifications, for CLI and TUI progress bars. A full channel
+    /// pauses indexing until the receiver catches up; a dropped receiver
+    /// doesn't stop indexing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use project_rag::{IndexRequest, RagClient};
+    ///
+    /// # async fn example(request: IndexRequest) -> anyhow::Result<()> {
+    /// let client = RagClient::new().await?;
+    ///
+    /// let (tx, mut rx) = tokio::sync::mpsc::channel(32);
+    /// let printer = tokio::spawn(async move {
+    ///     while let Some(progress) = rx.recv().await {
+    ///         println!("[{:>3.0}%] {}", progress.percent, progress.message);
+    ///     }
+    /// });
+    ///
+    /// client.index_codebase_with_progress(request, tx).await?;
+    /// printer.await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn index_codebase_with_progress(
+        &self,
+        request: IndexRequest,
+        tx: tokio::sync::mpsc::Sender<IndexProgress>,
+    ) -> Result<IndexResponse> {
+        self.index_codebase_inner(request, CancellationToken::new(), Some(tx))
+            .await
+    }
+
+    async fn index_codebase_inner(
+        &self,
+        request: IndexRequest,
+        cancel_token: CancellationToken,
+        progress_tx: Option<tokio::sync::mpsc::Sender<IndexProgress>>,
+    ) -> Result<IndexResponse> {
         // Validate request
         request.validate().map_err(|e| anyhow::anyhow!(e))?;
 
-        // Use the smart indexing logic without progress notifications
-        // Default cancellation token - not cancellable from this API
-        let cancel_token = tokio_util::sync::CancellationToken::new();
+        // Use the smart indexing logic, without MCP progress notifications
+        let client = self
+            .for_indexing(&request.path, request.model_name.as_deref())
+            .await?;
         indexing::do_index_smart(
-            self,
+            &client,
             request.path,
             request.project,
             request.include_patterns,
@@ -466,6 +702,49 @@ impl RagClient {
             request.max_file_size,
             None, // No peer
             None, // No progress token
+            progress_tx,
+            cancel_token,
+        )
+        .await
+    }
+
+    /// Rebuild the index of a codebase from scratch
+    ///
+    /// Unlike [`index_codebase`](Self::index_codebase), every file is re-chunked
+    /// and re-embedded. With `indexing.warm_reindex` enabled, the rebuild goes
+    /// into a staging table and queries keep returning the previous results
+    /// until it is swapped in.
+    pub async fn reindex_codebase(&self, request: IndexRequest) -> Result<IndexResponse> {
+        self.reindex_codebase_with_cancellation(request, CancellationToken::new())
+            .await
+    }
+
+    /// Rebuild the index of a codebase, stopping once `cancel_token` is cancelled
+    ///
+    /// A cancelled run fails with an [`IndexingCancelled`] error. An in-place
+    /// rebuild leaves the codebase marked dirty, so the next run re-indexes it
+    /// in full; a cancelled warm reindex drops the staging table and keeps
+    /// serving the previous index.
+    pub async fn reindex_codebase_with_cancellation(
+        &self,
+        request: IndexRequest,
+        cancel_token: CancellationToken,
+    ) -> Result<IndexResponse> {
+        request.validate().map_err(|e| anyhow::anyhow!(e))?;
+
+        let client = self
+            .for_indexing(&request.path, request.model_name.as_deref())
+            .await?;
+        indexing::do_reindex(
+            &client,
+            request.path,
+            request.project,
+            request.include_patterns,
+            request.exclude_patterns,
+            request.max_file_size,
+            None, // No peer
+            None, // No progress token
+            None, // No progress channel
             cancel_token,
         )
         .await
@@ -484,9 +763,17 @@ impl RagClient {
     /// let request = QueryRequest {
     ///     query: "authentication logic".to_string(),
     ///     project: Some("my-project".to_string()),
+    ///     projects: vec![],
     ///     limit: 10,
+    ///     offset: 0,
     ///     min_score: 0.7,
     ///     hybrid: true,
+    ///     exclude_files: vec![],
+    ///     include_signature: false,
+    ///     include_summary: false,
+    ///     include_relations_precision: false,
+    ///     context_lines: 0,
+    ///     include_git_commits: false,
     /// };
     ///
     /// let response = client.query_codebase(request).await?;
@@ -497,39 +784,228 @@ impl RagClient {
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn query_codebase(&self, request: QueryRequest) -> Result<QueryResponse> {
+    pub async fn query_codebase(&self, mut request: QueryRequest) -> Result<QueryResponse> {
         request.validate().map_err(|e| anyhow::anyhow!(e))?;
+        let truncated = self.clamp_limit(&mut request.limit);
 
         // Check if the target path is dirty (if path filter is specified)
         self.check_path_not_dirty(request.path.as_deref()).await?;
 
+        // Search with the model(s) the target path was indexed with
+        let clients = self.for_query(request.path.as_deref()).await?;
+        let mut response = Self::query_models(&clients, request).await?;
+        response.truncated = truncated;
+        Ok(response)
+    }
+
+    /// Search like [`query_codebase`](Self::query_codebase), returning only the matching files
+    ///
+    /// Chunk results are collapsed into one [`FileMatch`] per file, ordered by
+    /// its best chunk score, so no chunk content is sent back. `limit` and
+    /// `offset` count files: chunks are ranked deeper until they cover the
+    /// requested page of files, and `chunk_count` counts a file's chunks
+    /// among those ranked.
+    pub async fn query_files(&self, mut request: QueryRequest) -> Result<QueryFilesResponse> {
         let start = Instant::now();
+        request.validate().map_err(|e| anyhow::anyhow!(e))?;
+        let truncated = self.clamp_limit(&mut request.limit);
 
-        let query_embedding = self
-            .embedding_provider
-            .embed_batch(vec![request.query.clone()])
-            .context("Failed to generate query embedding")?
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("No embedding generated"))?;
+        // None of the per-chunk extras survive the collapse
+        request.include_signature = false;
+        request.include_summary = false;
+        request.include_relations_precision = false;
+        request.context_lines = 0;
+
+        self.check_path_not_dirty(request.path.as_deref()).await?;
+        let clients = self.for_query(request.path.as_deref()).await?;
+
+        // Rank chunks from the top, doubling the depth until they hold the
+        // requested page of files plus one (to tell whether more follow)
+        const MAX_CHUNK_DEPTH: usize = MAX_QUERY_OFFSET + MAX_QUERY_LIMIT;
+        let (offset, limit) = (request.offset, request.limit);
+        request.offset = 0;
+        request.limit = (offset + limit + 1).min(MAX_CHUNK_DEPTH);
+        loop {
+            let response = Self::query_models(&clients, request.clone()).await?;
+            let mut files = FileMatch::from_results(&response.results);
+            let complete = files.len() > offset + limit;
+            if complete || !response.has_more || request.limit >= MAX_CHUNK_DEPTH {
+                files.truncate(offset + limit);
+                files.drain(..offset.min(files.len()));
+                return Ok(QueryFilesResponse {
+                    files,
+                    duration_ms: start.elapsed().as_millis() as u64,
+                    threshold_used: response.threshold_used,
+                    threshold_lowered: response.threshold_lowered,
+                    truncated,
+                    has_more: complete || response.has_more,
+                });
+            }
+            request.limit = (request.limit * 2).min(MAX_CHUNK_DEPTH);
+        }
+    }
+
+    /// Set the relations precision of results that lie inside a known symbol
+    fn annotate_relations_precision(&self, results: &mut [SearchResult]) {
+        for result in results {
+            result.relations_precision = result
+                .symbol_kind
+                .is_some()
+                .then(|| self.relations_provider.precision_level(&result.language));
+        }
+    }
+
+    /// Set each result's context to its lines plus `context_lines` before and after
+    ///
+    /// Files are re-read from disk, once per file. Results whose file was moved,
+    /// deleted or shortened since indexing keep only their stored chunk.
+    fn attach_context(results: &mut [SearchResult], context_lines: usize) {
+        let mut file_lines: HashMap<PathBuf, Option<Vec<String>>> = HashMap::new();
+
+        for result in results.iter_mut().filter(|r| !r.binary) {
+            let path = related_search::resolve_result_path(result);
+            let lines = file_lines.entry(path).or_insert_with_key(|path| {
+                std::fs::read_to_string(path)
+                    .ok()
+                    .map(|content| content.lines().map(String::from).collect())
+            });
+
+            let Some(lines) = lines else {
+                continue;
+            };
+            if result.start_line == 0 || result.end_line > lines.len() {
+                continue;
+            }
+
+            let start_line = result.start_line.saturating_sub(context_lines).max(1);
+            let end_line = (result.end_line + context_lines).min(lines.len());
+            result.context = Some(ResultContext {
+                start_line,
+                end_line,
+                content: lines[start_line - 1..end_line].join("\n"),
+            });
+        }
+    }
+
+    /// Reorder results by their cross-encoder score against the query
+    ///
+    /// The rerank score replaces `score`; `vector_score` and `keyword_score`
+    /// keep the first-stage scores for transparency.
+    fn rerank(&self, query: &str, results: &mut [SearchResult]) -> Result<()> {
+        let Some(reranker) = &self.reranker else {
+            return Ok(());
+        };
+
+        let passages: Vec<&str> = results.iter().map(|r| r.content.as_str()).collect();
+        let scores = reranker.score(query, &passages)?;
+        for (result, score) in results.iter_mut().zip(scores) {
+            result.score = score;
+        }
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(())
+    }
+
+    /// Clamp a requested result limit to the server's `max_results` cap
+    ///
+    /// Returns whether the limit was lowered.
+    fn clamp_limit(&self, limit: &mut usize) -> bool {
+        let max_results = self.config.server.max_results;
+        if *limit > max_results {
+            tracing::debug!("Clamping result limit {} to {}", limit, max_results);
+            *limit = max_results;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Search every client's model and store, merging the results into one page
+    ///
+    /// Similarity scores of different models are ranked against each other as
+    /// they are.
+    async fn query_models(clients: &[RagClient], request: QueryRequest) -> Result<QueryResponse> {
+        if let [client] = clients {
+            return client.query_with_model(request).await;
+        }
+
+        let start = Instant::now();
+        let (offset, limit) = (request.offset, request.limit);
+        let include_summary = request.include_summary;
+        let page = QueryRequest {
+            offset: 0,
+            limit: offset + limit,
+            ..request
+        };
+        let mut responses = Vec::with_capacity(clients.len());
+        for client in clients {
+            responses.push(client.query_with_model(page.clone()).await?);
+        }
+
+        let mut response = Self::merge_model_responses(responses, offset, limit, start);
+        response.summary = include_summary.then(|| QuerySummary::from_results(&response.results));
+        Ok(response)
+    }
+
+    /// Merge the first pages of several models' responses and slice out the
+    /// page at `offset`
+    fn merge_model_responses(
+        responses: Vec<QueryResponse>,
+        offset: usize,
+        limit: usize,
+        start: Instant,
+    ) -> QueryResponse {
+        let mut merged = QueryResponse {
+            results: Vec::new(),
+            duration_ms: 0,
+            threshold_used: f32::INFINITY,
+            threshold_lowered: false,
+            truncated: false,
+            has_more: false,
+            summary: None,
+        };
+        for response in responses {
+            merged.results.extend(response.results);
+            merged.threshold_used = merged.threshold_used.min(response.threshold_used);
+            merged.threshold_lowered |= response.threshold_lowered;
+            merged.has_more |= response.has_more;
+        }
+
+        merged.results.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.chunk_id.cmp(&b.chunk_id))
+        });
+        merged.has_more |= merged.results.len() > offset + limit;
+        merged.results.truncate(offset + limit);
+        merged.results.drain(..offset.min(merged.results.len()));
+        merged.duration_ms = start.elapsed().as_millis() as u64;
+        merged
+    }
+
+    /// Semantic search using this client's embedding model and store
+    async fn query_with_model(&self, mut request: QueryRequest) -> Result<QueryResponse> {
+        let start = Instant::now();
+
+        // Rank the results up to the end of the requested page, plus one to
+        // tell whether more follow; the page is sliced out at the end
+        let (offset, limit) = (request.offset, request.limit);
+        let ranked = offset + limit + 1;
+        request.limit = ranked;
+
+        // Re-ranking reorders a deeper candidate pool, cut to the limit afterwards
+        if self.reranker.is_some() {
+            request.limit = ranked.max(self.config.search.rerank_candidates);
+        }
+
+        let query_embedding = self.embed_query(&request.query)?;
 
         let original_threshold = request.min_score;
         let mut threshold_used = original_threshold;
         let mut threshold_lowered = false;
 
         let mut results = self
-            .vector_db
-            .search(
-                query_embedding.clone(),
-                &request.query,
-                request.limit,
-                threshold_used,
-                request.project.clone(),
-                request.path.clone(),
-                request.hybrid,
-            )
-            .await
-            .context("Failed to search")?;
+            .search_excluding_files(&query_embedding, &request, threshold_used)
+            .await?;
 
         if results.is_empty() && original_threshold > 0.3 {
             let fallback_thresholds = [0.6, 0.5, 0.4, 0.3];
@@ -540,18 +1016,8 @@ impl RagClient {
                 }
 
                 results = self
-                    .vector_db
-                    .search(
-                        query_embedding.clone(),
-                        &request.query,
-                        request.limit,
-                        threshold,
-                        request.project.clone(),
-                        request.path.clone(),
-                        request.hybrid,
-                    )
-                    .await
-                    .context("Failed to search")?;
+                    .search_excluding_files(&query_embedding, &request, threshold)
+                    .await?;
 
                 if !results.is_empty() {
                     threshold_used = threshold;
@@ -561,38 +1027,327 @@ impl RagClient {
             }
         }
 
+        // Overlapping chunks can match the same passage twice
+        SearchResult::dedup_overlapping(&mut results);
+
+        if self.reranker.is_some() {
+            self.rerank(&request.query, &mut results)?;
+        }
+
+        // Ties break by chunk id, so pages of the same query line up
+        results.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.chunk_id.cmp(&b.chunk_id))
+        });
+        let has_more = results.len() > offset + limit;
+        results.truncate(offset + limit);
+        results.drain(..offset.min(results.len()));
+
+        if request.include_signature {
+            related_search::prepend_enclosing_signatures(self, &mut results);
+        }
+
+        if request.include_relations_precision {
+            self.annotate_relations_precision(&mut results);
+        }
+
+        if request.context_lines > 0 {
+            Self::attach_context(&mut results, request.context_lines);
+        }
+
+        let summary = request
+            .include_summary
+            .then(|| QuerySummary::from_results(&results));
+
         Ok(QueryResponse {
             results,
             duration_ms: start.elapsed().as_millis() as u64,
             threshold_used,
             threshold_lowered,
+            truncated: false,
+            has_more,
+            summary,
+        })
+    }
+
+    /// Score a query and report how each result's score was computed
+    ///
+    /// Runs the same search as [`query_codebase`](Self::query_codebase) and
+    /// returns vector distances, keyword scores, rank positions and fusion
+    /// contributions for every result. The output carries no timings, so for a
+    /// fixed query and index it is stable and can be kept as a golden file.
+    pub async fn query_debug(&self, request: QueryRequest) -> Result<QueryDebugResponse> {
+        let query = request.query.clone();
+        let hybrid = request.hybrid;
+        let offset = request.offset;
+
+        let response = self.query_codebase(request).await?;
+        let results = response
+            .results
+            .iter()
+            .enumerate()
+            .map(|(idx, result)| ScoreBreakdown::new(offset + idx + 1, result, hybrid))
+            .collect();
+
+        Ok(QueryDebugResponse {
+            query,
+            hybrid,
+            threshold_used: response.threshold_used,
+            threshold_lowered: response.threshold_lowered,
+            rrf_k: crate::bm25_search::RRF_K_CONSTANT,
+            results,
+        })
+    }
+
+    /// Report how a file is chunked: which method applied and why
+    ///
+    /// Chunks the file the way indexing does (AST units, line fallback,
+    /// markup sections, ...) without storing anything, so relevance issues can
+    /// be traced back to the chunk boundaries.
+    pub fn explain_chunking(&self, file_path: &str) -> Result<ChunkingDiagnostic> {
+        let file_info = self.create_file_info(file_path, None)?;
+        Ok(self.chunker.chunk_file_with_diagnostic(&file_info).1)
+    }
+
+    /// Run a hybrid query and return the vector and keyword candidate lists
+    /// alongside the fused results
+    ///
+    /// The fused list is what [`query_codebase`](Self::query_codebase) returns
+    /// for a hybrid query. The per-side lists go as deep as fusion draws from
+    /// and are not filtered by `min_score`, so they hold every candidate with
+    /// its native score.
+    pub async fn query_dual(&self, mut request: QueryRequest) -> Result<DualQueryResponse> {
+        let start = Instant::now();
+        request.hybrid = true;
+
+        let query = request.query.clone();
+        let projects = request.project_filter();
+        let path = request.path.clone();
+        let include_git_commits = request.include_git_commits;
+        let candidates = request.limit * crate::vector_db::HYBRID_CANDIDATE_FACTOR;
+
+        let client = self.for_path(path.as_deref()).await?;
+        let fused = self.query_codebase(request).await?;
+
+        let query_embedding = client.embed_query(&query)?;
+        let vector = client
+            .vector_db
+            .search(
+                query_embedding,
+                &query,
+                candidates,
+                0.0,
+                &projects,
+                path.clone(),
+                false,
+                include_git_commits,
+            )
+            .await
+            .context("Failed to run vector search")?;
+        let keyword = client
+            .vector_db
+            .search_keyword(&query, candidates, &projects, path)
+            .await
+            .context("Failed to run keyword search")?;
+
+        Ok(DualQueryResponse {
+            fused: fused.results,
+            vector,
+            keyword,
+            threshold_used: fused.threshold_used,
+            duration_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Embed a search query, reusing the cached embedding of a recurring query
+    fn embed_query(&self, query: &str) -> Result<Vec<f32>> {
+        let model = self.model_name();
+        if let Some(embedding) = self.lock_query_embeddings()?.get(&model, query) {
+            tracing::debug!("Reusing cached embedding for query '{}'", query);
+            return Ok(embedding);
+        }
+
+        let embedding = self
+            .embedding_provider()
+            .embed_batch(vec![query.to_string()])
+            .context("Failed to generate query embedding")?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No embedding generated"))?;
+
+        self.lock_query_embeddings()?
+            .insert(&model, query, embedding.clone());
+        self.save_query_embeddings();
+        Ok(embedding)
+    }
+
+    /// Write changes of the query embedding cache on a blocking thread
+    ///
+    /// Only one save runs at a time; it keeps going while the cache changes.
+    fn save_query_embeddings(&self) {
+        let Some(save) = self
+            .query_embeddings
+            .lock()
+            .ok()
+            .and_then(|mut cache| cache.begin_save())
+        else {
+            return;
+        };
+
+        let cache = self.query_embeddings.clone();
+        let write = move || {
+            let mut next = Some(save);
+            while let Some(save) = next {
+                if let Err(e) = save.write() {
+                    tracing::warn!("Failed to save query embedding cache: {:#}", e);
+                }
+                next = cache.lock().ok().and_then(|mut cache| cache.finish_save());
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(write);
+            }
+            Err(_) => write(),
+        }
+    }
+
+    fn lock_query_embeddings(&self) -> Result<std::sync::MutexGuard<'_, QueryEmbeddingCache>> {
+        self.query_embeddings
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire query embedding cache lock: {}", e))
+    }
+
+    /// Run a vector search, dropping results from `request.exclude_files`
+    ///
+    /// When exclusions are present, extra candidates are fetched so the excluded
+    /// files are replaced by the next-best results before truncating to the
+    /// limit; the fetch grows until the limit is met or the matches run out.
+    async fn search_excluding_files(
+        &self,
+        query_embedding: &[f32],
+        request: &QueryRequest,
+        threshold: f32,
+    ) -> Result<Vec<SearchResult>> {
+        const EXCLUDE_OVERFETCH_FACTOR: usize = 4;
+
+        let mut fetch_limit = if request.exclude_files.is_empty() {
+            request.limit
+        } else {
+            request.limit.saturating_mul(EXCLUDE_OVERFETCH_FACTOR)
+        };
+
+        loop {
+            let span = self.search_span(fetch_limit, threshold, request.hybrid);
+            let search_start = Instant::now();
+
+            let mut results = self
+                .vector_db
+                .search(
+                    query_embedding.to_vec(),
+                    &request.query,
+                    fetch_limit,
+                    threshold,
+                    &request.project_filter(),
+                    request.path.clone(),
+                    request.hybrid,
+                    request.include_git_commits,
+                )
+                .instrument(span.clone())
+                .await
+                .context("Failed to search")?;
+
+            span.record("results", results.len());
+            telemetry::record_duration(&span, search_start);
+
+            if request.exclude_files.is_empty() {
+                return Ok(results);
+            }
+
+            // Fewer results than fetched means every match has been seen
+            let exhausted = results.len() < fetch_limit;
+            results.retain(|r| {
+                !crate::glob_utils::matches_any_pattern(&r.file_path, &request.exclude_files)
+            });
+            if results.len() >= request.limit || exhausted {
+                results.truncate(request.limit);
+                return Ok(results);
+            }
+
+            tracing::debug!(
+                "Only {} of {} results left after excluding files, fetching {} candidates",
+                results.len(),
+                request.limit,
+                fetch_limit.saturating_mul(2)
+            );
+            fetch_limit = fetch_limit.saturating_mul(2);
+        }
+    }
+
+    /// Span factory for pipeline phases, per `observability.phase_spans`
+    pub(crate) fn phase_spans(&self) -> PhaseSpans {
+        PhaseSpans::new(self.config.observability.phase_spans)
+    }
+
+    /// Span for a single vector database search attempt
+    fn search_span(&self, limit: usize, min_score: f32, hybrid: bool) -> tracing::Span {
+        self.phase_spans().span(|| {
+            tracing::info_span!(
+                "search",
+                limit,
+                min_score,
+                hybrid,
+                results = tracing::field::Empty,
+                duration_ms = tracing::field::Empty
+            )
         })
     }
 
     /// Advanced search with filters for file type, language, and path patterns
     pub async fn search_with_filters(
         &self,
-        request: AdvancedSearchRequest,
+        mut request: AdvancedSearchRequest,
     ) -> Result<QueryResponse> {
         request.validate().map_err(|e| anyhow::anyhow!(e))?;
+        let truncated = self.clamp_limit(&mut request.limit);
 
         // Check if the target path is dirty (if path filter is specified)
         self.check_path_not_dirty(request.path.as_deref()).await?;
 
+        // Search with the model(s) the target path was indexed with
+        let clients = self.for_query(request.path.as_deref()).await?;
+        let (start, limit) = (Instant::now(), request.limit);
+        let mut responses = Vec::with_capacity(clients.len());
+        for client in &clients {
+            responses.push(client.search_filters_with_model(request.clone()).await?);
+        }
+        let mut response = Self::merge_model_responses(responses, 0, limit, start);
+        response.truncated = truncated;
+        Ok(response)
+    }
+
+    /// Filtered search using this client's embedding model and store
+    async fn search_filters_with_model(
+        &self,
+        request: AdvancedSearchRequest,
+    ) -> Result<QueryResponse> {
         let start = Instant::now();
 
-        let query_embedding = self
-            .embedding_provider
-            .embed_batch(vec![request.query.clone()])
-            .context("Failed to generate query embedding")?
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("No embedding generated"))?;
+        let query_embedding = self.embed_query(&request.query)?;
+
+        let symbol_kinds: Vec<String> = request
+            .symbol_kinds
+            .iter()
+            .map(|kind| kind.as_str().to_string())
+            .collect();
 
         let original_threshold = request.min_score;
         let mut threshold_used = original_threshold;
         let mut threshold_lowered = false;
 
+        let span = self.search_span(request.limit, threshold_used, true);
+        let search_start = Instant::now();
         let mut results = self
             .vector_db
             .search_filtered(
@@ -600,15 +1355,19 @@ impl RagClient {
                 &request.query,
                 request.limit,
                 threshold_used,
-                request.project.clone(),
+                request.project.as_slice(),
                 request.path.clone(),
                 true,
                 request.file_extensions.clone(),
                 request.languages.clone(),
                 request.path_patterns.clone(),
+                symbol_kinds.clone(),
             )
+            .instrument(span.clone())
             .await
             .context("Failed to search with filters")?;
+        span.record("results", results.len());
+        telemetry::record_duration(&span, search_start);
 
         // Adaptive threshold lowering if no results found
         if results.is_empty() && original_threshold > 0.3 {
@@ -619,6 +1378,8 @@ impl RagClient {
                     continue;
                 }
 
+                let span = self.search_span(request.limit, threshold, true);
+                let search_start = Instant::now();
                 results = self
                     .vector_db
                     .search_filtered(
@@ -626,15 +1387,19 @@ impl RagClient {
                         &request.query,
                         request.limit,
                         threshold,
-                        request.project.clone(),
+                        request.project.as_slice(),
                         request.path.clone(),
                         true,
                         request.file_extensions.clone(),
                         request.languages.clone(),
                         request.path_patterns.clone(),
+                        symbol_kinds.clone(),
                     )
+                    .instrument(span.clone())
                     .await
                     .context("Failed to search with filters")?;
+                span.record("results", results.len());
+                telemetry::record_duration(&span, search_start);
 
                 if !results.is_empty() {
                     threshold_used = threshold;
@@ -649,6 +1414,9 @@ impl RagClient {
             duration_ms: start.elapsed().as_millis() as u64,
             threshold_used,
             threshold_lowered,
+            truncated: false,
+            has_more: false,
+            summary: None,
         })
     }
 
@@ -660,39 +1428,130 @@ impl RagClient {
             .await
             .context("Failed to get statistics")?;
 
-        let language_breakdown = stats
-            .language_breakdown
-            .into_iter()
-            .map(|(language, count)| LanguageStats {
-                language,
-                file_count: count,
-                chunk_count: count,
-            })
-            .collect();
-
         Ok(StatisticsResponse {
-            total_files: stats.total_points,
+            total_files: stats.total_files,
             total_chunks: stats.total_vectors,
             total_embeddings: stats.total_vectors,
-            database_size_bytes: 0,
-            language_breakdown,
+            database_size_bytes: stats.size_bytes.unwrap_or(0),
+            language_breakdown: stats.language_breakdown,
         })
     }
 
+    /// Check that every subsystem is ready to serve queries
+    ///
+    /// Embeds a probe string, counts the rows of the vector database and opens
+    /// a reader on every BM25 index, timing each. A failing probe marks its
+    /// subsystem (and the report) unhealthy instead of failing the call, so the
+    /// report can serve as a readiness probe.
+    pub async fn health_check(&self) -> Result<HealthReport> {
+        let start = Instant::now();
+        let provider = self.embedding_provider();
+        let embedding = tokio::task::spawn_blocking(move || {
+            provider.embed_batch(vec!["health check".to_string()])
+        })
+        .await
+        .context("Embedding probe panicked")
+        .and_then(|result| result)
+        .map(|embeddings| {
+            format!(
+                "embedded a probe string into {} dimensions",
+                embeddings.first().map_or(0, Vec::len)
+            )
+        });
+        let embedding = subsystem_health(embedding, start.elapsed());
+
+        let start = Instant::now();
+        let vector_db = self
+            .vector_db
+            .get_statistics()
+            .await
+            .map(|stats| format!("{} rows", stats.total_vectors));
+        let vector_db = subsystem_health(vector_db, start.elapsed());
+
+        let start = Instant::now();
+        let bm25 = self
+            .vector_db
+            .check_keyword_indexes()
+            .await
+            .map(|opened| format!("{} keyword indexes opened", opened));
+        let bm25 = subsystem_health(bm25, start.elapsed());
+
+        Ok(HealthReport {
+            healthy: embedding.healthy && vector_db.healthy && bm25.healthy,
+            embedding,
+            vector_db,
+            bm25,
+        })
+    }
+
+    /// List the indexed projects with their chunk counts and languages
+    ///
+    /// Projects are the distinct `project` values of the stored chunks, sorted
+    /// by name; chunks indexed without a project are not listed.
+    pub async fn list_projects(&self) -> Result<Vec<ProjectSummary>> {
+        self.vector_db
+            .list_projects()
+            .await
+            .context("Failed to list projects")
+    }
+
+    /// Get every chunk indexed for `file_path`, ordered by start line
+    ///
+    /// The chunks are read from the default index without a vector search, so
+    /// each result has a score of 1.0. Handy for assembling the indexed
+    /// content of a whole file. `project` limits the chunks to one project.
+    pub async fn get_chunks_for_file(
+        &self,
+        file_path: &str,
+        project: Option<String>,
+    ) -> Result<Vec<SearchResult>> {
+        self.vector_db
+            .get_file_chunks(file_path, project)
+            .await
+            .with_context(|| format!("Failed to get chunks for {}", file_path))
+    }
+
     /// Clear all indexed data from the vector database
+    ///
+    /// Clearing drops the underlying table, so it is coordinated with indexing:
+    /// if any indexing operation is in flight (in this process or another one),
+    /// the clear waits up to `indexing.clear_wait_timeout_secs` for it to finish
+    /// and otherwise fails with an "indexing in progress" error.
     pub async fn clear_index(&self) -> Result<ClearResponse> {
-        match self.vector_db.clear().await {
+        let timeout = Duration::from_secs(self.config.indexing.clear_wait_timeout_secs);
+        let deadline = Instant::now() + timeout;
+
+        // Holding the ops map keeps new in-process indexing from starting mid-clear
+        let _ops = self.lock_indexing_ops_for_clear(deadline).await?;
+        let _fs_locks = self.acquire_root_fs_locks_for_clear(deadline).await?;
+
+        // Per-project model tables are cleared first, while the cache still lists them
+        if let Err(e) = self.clear_model_backends().await {
+            return Ok(ClearResponse {
+                success: false,
+                message: format!("Failed to clear index: {:#}", e),
+            });
+        }
+
+        let default_backend = self.read_default_model().backend.clone();
+        match default_backend.vector_db.clear().await {
             Ok(_) => {
                 let mut cache = self.hash_cache.write().await;
                 cache.roots.clear();
+                cache.root_models.clear();
+                cache.mtimes.clear();
 
                 if let Err(e) = cache.save(&self.cache_path) {
                     tracing::warn!("Failed to save cleared cache: {}", e);
                 }
 
-                if let Err(e) = self
+                if let Err(e) = self.relations_store.clear().await {
+                    tracing::warn!("Failed to clear code relations: {}", e);
+                }
+
+                if let Err(e) = default_backend
                     .vector_db
-                    .initialize(self.embedding_provider.dimension())
+                    .initialize(default_backend.embedding_provider.dimension())
                     .await
                 {
                     Ok(ClearResponse {
@@ -713,12 +1572,263 @@ impl RagClient {
         }
     }
 
+    /// Delete every indexed chunk matching `filter`, returning how many were deleted
+    ///
+    /// Criteria combine with AND, e.g. a path prefix plus `indexed_before`
+    /// deletes only the old chunks under that path. The files the chunks came
+    /// from are dropped from the hash cache, so a later incremental index
+    /// re-adds any of them that still exist.
+    pub async fn delete_where(&self, filter: DeleteFilter) -> Result<usize> {
+        let deleted = self
+            .vector_db
+            .delete_where(&filter)
+            .await
+            .context("Failed to delete matching chunks")?;
+        self.vector_db
+            .flush()
+            .await
+            .context("Failed to flush deleted chunks")?;
+
+        if !deleted.files.is_empty() {
+            let mut cache = self.hash_cache.write().await;
+            for (root_path, file_path) in &deleted.files {
+                if let Some(root_path) = root_path
+                    && let Some(hashes) = cache.roots.get_mut(root_path)
+                {
+                    hashes.remove(file_path);
+                }
+            }
+            if let Err(e) = cache.save(&self.cache_path) {
+                tracing::warn!("Failed to save cache after deletion: {}", e);
+            }
+        }
+        self.delete_file_relations(&deleted.files).await;
+
+        Ok(deleted.count)
+    }
+
+    /// Delete stored relations for `(root path, file path)` pairs dropped from the index
+    ///
+    /// Failures are logged; the files are re-extracted when next indexed.
+    async fn delete_file_relations(&self, files: &[(Option<String>, String)]) {
+        for (root_path, file_path) in files {
+            if let Some(root_path) = root_path
+                && let Err(e) = self
+                    .relations_store
+                    .delete_by_file(file_path, root_path)
+                    .await
+            {
+                tracing::warn!("Failed to delete relations for file '{}': {}", file_path, e);
+            }
+        }
+    }
+
+    /// Remove everything indexed for one project
+    ///
+    /// Deletes the project's chunks from the vectors and keyword index. Root
+    /// paths left without any chunks also lose their keyword index directory
+    /// and their hash cache entry, so indexing them again starts fresh; other
+    /// roots only drop the deleted files from the cache. An unknown project
+    /// removes nothing and is not an error.
+    pub async fn remove_project(&self, project: &str) -> Result<RemoveProjectResponse> {
+        let filter = DeleteFilter {
+            project: Some(project.to_string()),
+            ..Default::default()
+        };
+        let deleted = self
+            .vector_db
+            .delete_where(&filter)
+            .await
+            .with_context(|| format!("Failed to delete chunks of project '{}'", project))?;
+        self.vector_db
+            .flush()
+            .await
+            .context("Failed to flush deleted chunks")?;
+
+        let roots: BTreeSet<&String> = deleted
+            .files
+            .iter()
+            .filter_map(|(root_path, _)| root_path.as_ref())
+            .collect();
+        let mut roots_removed = Vec::new();
+        for root in roots {
+            let remaining = self
+                .vector_db
+                .count_by_root_path(root)
+                .await
+                .context("Failed to count remaining chunks")?;
+            if remaining == 0 {
+                self.vector_db
+                    .drop_keyword_index(root)
+                    .await
+                    .with_context(|| format!("Failed to drop keyword index of {}", root))?;
+                roots_removed.push(root.clone());
+            }
+        }
+
+        if !deleted.files.is_empty() {
+            let mut cache = self.hash_cache.write().await;
+            for root in &roots_removed {
+                cache.remove_root(root);
+            }
+            for (root_path, file_path) in &deleted.files {
+                if let Some(root_path) = root_path
+                    && let Some(hashes) = cache.roots.get_mut(root_path)
+                {
+                    hashes.remove(file_path);
+                }
+            }
+            if let Err(e) = cache.save(&self.cache_path) {
+                tracing::warn!("Failed to save cache after removing project: {}", e);
+            }
+        }
+        self.delete_file_relations(&deleted.files).await;
+
+        tracing::info!(
+            "Removed project '{}': {} chunks from {} files",
+            project,
+            deleted.count,
+            deleted.files.len()
+        );
+
+        Ok(RemoveProjectResponse {
+            chunks_removed: deleted.count,
+            files_removed: deleted.files.len(),
+            roots_removed,
+        })
+    }
+
+    /// Rewrite the hash cache file in the compact format
+    ///
+    /// All roots, file hashes and dirty flags are preserved; only the on-disk
+    /// representation shrinks. Later saves by this client stay compact; a new
+    /// client saves in `cache.format` again, so set `cache.format = "compact"`
+    /// to keep the file compact across restarts.
+    pub async fn compact_cache(&self) -> Result<CacheCompaction> {
+        let mut cache = self.hash_cache.write().await;
+        let compaction = cache
+            .compact(&self.cache_path)
+            .context("Failed to compact hash cache")?;
+
+        tracing::info!(
+            "Compacted hash cache ({} roots, {} files): {} -> {} bytes",
+            compaction.roots,
+            compaction.files,
+            compaction.bytes_before,
+            compaction.bytes_after
+        );
+        Ok(compaction)
+    }
+
+    /// Compact the vector table and merge the segments of every BM25 index
+    ///
+    /// Incremental updates leave many small fragments and segments behind,
+    /// which slows scans down; running this after a large batch of updates
+    /// restores query latency. Indexed data is unchanged.
+    pub async fn optimize_index(&self) -> Result<OptimizeResponse> {
+        let start = Instant::now();
+        let stats = self
+            .vector_db
+            .optimize()
+            .await
+            .context("Failed to optimize index")?;
+
+        Ok(OptimizeResponse {
+            fragments_before: stats.fragments_before,
+            fragments_after: stats.fragments_after,
+            bm25_indexes: stats.keyword_indexes,
+            bm25_segments_before: stats.keyword_segments_before,
+            bm25_segments_after: stats.keyword_segments_after,
+            duration_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Take the indexing ops map once no in-process indexing is active
+    ///
+    /// Polls until every registered operation has finished or gone stale, or
+    /// fails once `deadline` has passed.
+    async fn lock_indexing_ops_for_clear(
+        &self,
+        deadline: Instant,
+    ) -> Result<tokio::sync::RwLockWriteGuard<'_, HashMap<String, IndexingOperation>>> {
+        use std::sync::atomic::Ordering;
+
+        loop {
+            let ops = self.indexing_ops.write().await;
+            let active: Vec<&str> = ops
+                .iter()
+                .filter(|(_, op)| op.active.load(Ordering::Acquire) && !op.is_stale())
+                .map(|(path, _)| path.as_str())
+                .collect();
+
+            if active.is_empty() {
+                return Ok(ops);
+            }
+
+            if Instant::now() >= deadline {
+                anyhow::bail!(
+                    "Cannot clear index: indexing in progress for {}. Retry once it completes.",
+                    active.join(", ")
+                );
+            }
+
+            tracing::info!(
+                "Waiting for indexing of {} to finish before clearing",
+                active.join(", ")
+            );
+            drop(ops);
+            tokio::time::sleep(CLEAR_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Acquire the filesystem lock of every known root so no other process indexes mid-clear
+    async fn acquire_root_fs_locks_for_clear(&self, deadline: Instant) -> Result<Vec<FsLockGuard>> {
+        let roots: Vec<String> = {
+            let cache = self.hash_cache.read().await;
+            let mut roots: Vec<String> = cache
+                .roots
+                .keys()
+                .chain(cache.get_dirty_roots().keys())
+                .cloned()
+                .collect();
+            roots.sort();
+            roots.dedup();
+            roots
+        };
+
+        let mut guards = Vec::with_capacity(roots.len());
+        for root in roots {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let path = root.clone();
+            let guard = tokio::task::spawn_blocking(move || {
+                if remaining.is_zero() {
+                    FsLockGuard::try_acquire(&path)
+                } else {
+                    FsLockGuard::acquire_blocking(&path, remaining)
+                }
+            })
+            .await
+            .context("Filesystem lock task panicked")??;
+
+            match guard {
+                Some(guard) => guards.push(guard),
+                None => anyhow::bail!(
+                    "Cannot clear index: indexing in progress for {} in another process. \
+                    Retry once it completes.",
+                    root
+                ),
+            }
+        }
+
+        Ok(guards)
+    }
+
     /// Search git commit history using semantic search
     ///
     /// # Example
     ///
     /// ```no_run
-    /// use project_rag::{RagClient, SearchGitHistoryRequest};
+    /// use project_rag::{GitEntryKind, RagClient, SearchGitHistoryRequest};
     ///
     /// # async fn example() -> anyhow::Result<()> {
     /// let client = RagClient::new().await?;
@@ -735,6 +1845,8 @@ impl RagClient {
     ///     since: None,
     ///     until: None,
     ///     file_pattern: None,
+    ///     kind: GitEntryKind::Commit,
+    ///     reset: false,
     /// };
     ///
     /// let response = client.search_git_history(request).await?;
@@ -753,7 +1865,7 @@ impl RagClient {
 
         // Forward to git indexing implementation
         git_indexing::do_search_git_history(
-            self.embedding_provider.clone(),
+            self.embedding_provider(),
             self.vector_db.clone(),
             self.git_cache.clone(),
             &self.git_cache_path,
@@ -769,7 +1881,35 @@ impl RagClient {
 
     /// Get the embedding dimension used by this client
     pub fn embedding_dimension(&self) -> usize {
-        self.embedding_provider.dimension()
+        self.embedding_provider().dimension()
+    }
+
+    /// Run a throwaway embedding on every model session so their one-time setup is paid now
+    ///
+    /// The first embedding otherwise pays for initializing the model session
+    /// (or, with `embedding.api_url`, for the first round trip), which shows up
+    /// as a latency spike on the first query or index call. Calling this while
+    /// the application starts moves that cost off the critical path.
+    pub async fn warm_up(&self) -> Result<()> {
+        let start = Instant::now();
+        let tasks: Vec<_> = (0..self.config.embedding.parallelism.max(1))
+            .map(|_| {
+                let provider = self.embedding_provider();
+                tokio::task::spawn_blocking(move || provider.embed_batch(vec!["warm-up".into()]))
+            })
+            .collect();
+        for task in tasks {
+            task.await
+                .context("Failed to spawn embedding warm-up task")?
+                .context("Failed to warm up the embedding model")?;
+        }
+
+        tracing::info!(
+            "Warmed up embedding model '{}' in {} ms",
+            self.model_name(),
+            start.elapsed().as_millis()
+        );
+        Ok(())
     }
 
     /// Find the definition of a symbol at a given file location
@@ -822,7 +1962,8 @@ impl RagClient {
     /// Find all references to a symbol at a given file location
     ///
     /// This method finds all locations where the symbol at the given position
-    /// is referenced throughout the indexed codebase.
+    /// is referenced throughout the indexed codebase. Files that haven't been
+    /// indexed are searched on their own.
     ///
     /// # Arguments
     ///
@@ -870,33 +2011,49 @@ impl RagClient {
             });
         }
 
-        let symbol_name_str = symbol_name.clone().unwrap();
+        let target_symbol = target_symbol.unwrap();
 
-        // Build symbol index from definitions
-        let mut symbol_index: std::collections::HashMap<String, Vec<crate::relations::Definition>> =
-            std::collections::HashMap::new();
-        for def in definitions {
-            symbol_index
-                .entry(def.symbol_id.name.clone())
-                .or_default()
-                .push(def);
-        }
+        // Look the symbol up among the relations stored at index time
+        let stored_definitions = self
+            .relations_store
+            .find_definitions_by_name(&target_symbol.symbol_id.name)
+            .await
+            .context("Failed to query stored definitions")?;
+        let stored_definition = stored_definitions.into_iter().find(|def| {
+            let stored_path = def
+                .root_path
+                .as_ref()
+                .map(|root| Path::new(root).join(def.file_path()));
+            def.start_line() == target_symbol.symbol_id.start_line
+                && stored_path.as_ref() == Some(&file_info.path)
+        });
 
-        // Find references in the same file
-        let references = self
-            .relations_provider
-            .extract_references(&file_info, &symbol_index)
-            .context("Failed to extract references")?;
+        let references = match stored_definition {
+            Some(def) => self
+                .relations_store
+                .find_references(&def.to_storage_id())
+                .await
+                .context("Failed to query stored references")?
+                .into_iter()
+                .filter(|r| r.root_path == def.root_path)
+                .collect::<Vec<_>>(),
+            None => {
+                // Not indexed: find references in the same file
+                let symbol_name_str = target_symbol.symbol_id.name.clone();
+                let symbol_index = related_search::build_symbol_index(&definitions);
+                self.relations_provider
+                    .extract_references(&file_info, &symbol_index)
+                    .context("Failed to extract references")?
+                    .into_iter()
+                    .filter(|r| r.target_symbol_id.contains(&symbol_name_str))
+                    .collect::<Vec<_>>()
+            }
+        };
 
-        // Filter to references matching our target symbol
         let matching_refs: Vec<ReferenceResult> = references
             .iter()
-            .filter(|r| {
-                // Check if this reference points to our target symbol
-                r.target_symbol_id.contains(&symbol_name_str)
-            })
             .take(request.limit)
-            .map(|r| ReferenceResult::from(r))
+            .map(ReferenceResult::from)
             .collect();
 
         let total_count = matching_refs.len();
@@ -944,10 +2101,8 @@ impl RagClient {
         // Find the function at the requested position
         let target_function = definitions.iter().find(|def| {
             // Only consider functions/methods
-            matches!(
-                def.symbol_id.kind,
-                crate::relations::SymbolKind::Function | crate::relations::SymbolKind::Method
-            ) && request.line >= def.symbol_id.start_line
+            related_search::is_callable(&def.symbol_id.kind)
+                && request.line >= def.symbol_id.start_line
                 && request.line <= def.end_line
                 && (request.column == 0 || request.column >= def.symbol_id.start_col)
         });
@@ -973,84 +2128,23 @@ impl RagClient {
             }
         };
 
-        let function_name = root_symbol.name.clone();
-
         // Build symbol index from definitions
-        let mut symbol_index: std::collections::HashMap<String, Vec<crate::relations::Definition>> =
-            std::collections::HashMap::new();
-        for def in &definitions {
-            symbol_index
-                .entry(def.symbol_id.name.clone())
-                .or_default()
-                .push(def.clone());
-        }
+        let symbol_index = related_search::build_symbol_index(&definitions);
 
-        // Find references in the same file to identify callers
+        // Find references in the same file to identify callers and callees
         let references = self
             .relations_provider
             .extract_references(&file_info, &symbol_index)
             .context("Failed to extract references")?;
 
-        // Find callers (references with Call kind pointing to our function)
-        let mut seen_callers = std::collections::HashSet::new();
-        let callers: Vec<crate::relations::CallGraphNode> = references
-            .iter()
-            .filter(|r| {
-                r.reference_kind == crate::relations::ReferenceKind::Call
-                    && r.target_symbol_id.contains(&function_name)
-            })
-            .filter_map(|r| {
-                // Try to find which function contains this call
-                definitions.iter().find(|def| {
-                    matches!(
-                        def.symbol_id.kind,
-                        crate::relations::SymbolKind::Function | crate::relations::SymbolKind::Method
-                    ) && r.start_line >= def.symbol_id.start_line
-                        && r.start_line <= def.end_line
-                })
-            })
-            .filter(|def| seen_callers.insert(def.symbol_id.name.clone()))
-            .map(|def| crate::relations::CallGraphNode {
-                name: def.symbol_id.name.clone(),
-                kind: def.symbol_id.kind.clone(),
-                file_path: request.file_path.clone(),
-                line: def.symbol_id.start_line,
-                children: Vec::new(),
-            })
-            .collect();
-
-        // Find callees (calls made from within our function)
-        let target_func = target_function.unwrap();
-        let mut seen_callees = std::collections::HashSet::new();
-        let callees: Vec<crate::relations::CallGraphNode> = references
-            .iter()
-            .filter(|r| {
-                r.reference_kind == crate::relations::ReferenceKind::Call
-                    && r.start_line >= target_func.symbol_id.start_line
-                    && r.start_line <= target_func.end_line
-            })
-            .filter_map(|r| {
-                // Extract the called function name from target_symbol_id
-                let parts: Vec<&str> = r.target_symbol_id.split(':').collect();
-                if parts.len() >= 2 {
-                    Some(parts[1].to_string())
-                } else {
-                    None
-                }
-            })
-            .filter(|name| seen_callees.insert(name.clone()))
-            .filter_map(|name| {
-                // Find the definition of the called function
-                symbol_index.get(&name).and_then(|defs| defs.first()).cloned()
-            })
-            .map(|def| crate::relations::CallGraphNode {
-                name: def.symbol_id.name.clone(),
-                kind: def.symbol_id.kind.clone(),
-                file_path: request.file_path.clone(),
-                line: def.symbol_id.start_line,
-                children: Vec::new(),
-            })
-            .collect();
+        let (callers, callees) = related_search::call_graph(
+            &definitions,
+            &symbol_index,
+            &references,
+            target_function.unwrap(),
+            &request.file_path,
+            request.depth,
+        );
 
         Ok(GetCallGraphResponse {
             root_symbol: Some(root_symbol),
@@ -1060,12 +2154,116 @@ impl RagClient {
             duration_ms: start.elapsed().as_millis() as u64,
         })
     }
+
+    /// List the symbols defined in a file, ordered by start line
+    ///
+    /// Functions, classes, methods and the other definitions the file's
+    /// grammar knows, with their line ranges, e.g. for a document outline.
+    pub async fn list_symbols(
+        &self,
+        file_path: &str,
+        project: Option<String>,
+    ) -> Result<Vec<crate::relations::SymbolInfo>> {
+        let file_info = self.create_file_info(file_path, project)?;
+
+        let mut definitions = self
+            .relations_provider
+            .extract_definitions(&file_info)
+            .context("Failed to extract definitions")?;
+        definitions.sort_by_key(|def| (def.symbol_id.start_line, def.symbol_id.start_col));
+
+        Ok(definitions
+            .into_iter()
+            .map(|def| crate::relations::SymbolInfo {
+                name: def.symbol_id.name,
+                kind: def.symbol_id.kind,
+                file_path: file_path.to_string(),
+                start_line: def.symbol_id.start_line,
+                end_line: def.end_line,
+                signature: def.signature,
+            })
+            .collect())
+    }
+
+    /// Find the indexed definitions of a symbol by its exact name
+    ///
+    /// Looks the name up in the relations stored at index time instead of
+    /// embedding a query, so only exact matches are returned, ordered by file
+    /// and line. File paths are relative to the indexed root.
+    pub async fn find_symbol(
+        &self,
+        name: &str,
+        project: Option<String>,
+    ) -> Result<Vec<crate::relations::SymbolInfo>> {
+        let mut definitions = self
+            .relations_store
+            .find_definitions_by_name(name)
+            .await
+            .context("Failed to query stored definitions")?;
+        if let Some(project) = &project {
+            definitions.retain(|def| def.project.as_ref() == Some(project));
+        }
+        definitions.sort_by(|a, b| {
+            (&a.root_path, &a.symbol_id.file_path, a.symbol_id.start_line).cmp(&(
+                &b.root_path,
+                &b.symbol_id.file_path,
+                b.symbol_id.start_line,
+            ))
+        });
+
+        Ok(definitions
+            .into_iter()
+            .map(|def| crate::relations::SymbolInfo {
+                name: def.symbol_id.name,
+                kind: def.symbol_id.kind,
+                file_path: def.symbol_id.file_path,
+                start_line: def.symbol_id.start_line,
+                end_line: def.end_line,
+                signature: def.signature,
+            })
+            .collect())
+    }
+
+    /// Search the codebase and return matching symbols with their immediate callers/callees
+    ///
+    /// Each search result is joined to the functions and methods it overlaps.
+    /// Results that are not inside a known symbol (or whose file can no longer
+    /// be read) are returned with an empty symbol list.
+    pub async fn search_with_relations(
+        &self,
+        request: SearchWithRelationsRequest,
+    ) -> Result<SearchWithRelationsResponse> {
+        related_search::do_search_with_relations(self, request).await
+    }
+}
+
+/// Report a timed subsystem probe, keeping its error as the detail
+fn subsystem_health(probe: Result<String>, elapsed: Duration) -> SubsystemHealth {
+    let latency_ms = elapsed.as_millis() as u64;
+    match probe {
+        Ok(detail) => SubsystemHealth {
+            healthy: true,
+            latency_ms,
+            detail,
+        },
+        Err(e) => SubsystemHealth {
+            healthy: false,
+            latency_ms,
+            detail: format!("{:#}", e),
+        },
+    }
 }
 
 // Indexing operations module
 pub(crate) mod indexing;
+pub use indexing::IndexingCancelled;
 // Git indexing operations module
 pub(crate) mod git_indexing;
+// Search joined with code relations
+pub(crate) mod related_search;
+// Per-project embedding models
+pub(crate) mod model_routing;
+use model_routing::{DefaultModel, ModelBackend};
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,481 @@
+//! Per-project embedding models
+//!
+//! A codebase may be indexed with an embedding model other than the configured
+//! default. Each such model gets its own embedding provider and its own
+//! table/collection (vectors of different models are not comparable and may
+//! differ in dimension), created lazily on first use. The model used for each
+//! indexed root is recorded in the hash cache so queries on that root are
+//! embedded with the same model.
+
+use super::RagClient;
+use super::index_metadata::{IndexMetadata, index_metadata_path};
+use crate::config::EmbeddingConfig;
+use crate::embedding::{EmbeddingProvider, provider_from_config};
+use crate::error::{EmbeddingError, RagError};
+use crate::telemetry::PhaseSpans;
+use crate::vector_db::VectorDatabase;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::{Arc, RwLockReadGuard};
+
+#[cfg(feature = "qdrant-backend")]
+use crate::vector_db::QdrantVectorDB;
+
+#[cfg(not(feature = "qdrant-backend"))]
+use crate::vector_db::LanceVectorDB;
+
+/// Vector store of the configured backend
+#[cfg(feature = "qdrant-backend")]
+type ModelStore = QdrantVectorDB;
+
+/// Vector store of the configured backend
+#[cfg(not(feature = "qdrant-backend"))]
+type ModelStore = LanceVectorDB;
+
+/// Embedding provider and vector store for one embedding model
+#[derive(Clone)]
+pub(crate) struct ModelBackend {
+    pub(crate) embedding_provider: Arc<dyn EmbeddingProvider>,
+    pub(crate) vector_db: Arc<ModelStore>,
+}
+
+/// The default embedding model, with the settings it was loaded from
+///
+/// Shared by every clone of a client, so a reload swaps the model for all of
+/// them at once.
+#[derive(Clone)]
+pub(crate) struct DefaultModel {
+    pub(crate) embedding: EmbeddingConfig,
+    pub(crate) backend: ModelBackend,
+}
+
+/// Table/collection name for a non-default embedding model
+///
+/// The model name is sanitized for use as an identifier, and a short hash of
+/// the original name keeps distinct models from colliding after sanitizing.
+pub(crate) fn table_name_for_model(model_name: &str) -> String {
+    let sanitized: String = model_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let mut hasher = Sha256::new();
+    hasher.update(model_name.as_bytes());
+    let hash = format!("{:x}", hasher.finalize());
+
+    format!("code_embeddings_{}_{}", sanitized, &hash[..8])
+}
+
+impl RagClient {
+    /// The embedding model this client embeds and stores with
+    pub fn model_name(&self) -> String {
+        match &self.model_override {
+            Some(model_name) => model_name.clone(),
+            None => self.default_model_name(),
+        }
+    }
+
+    /// The current default embedding model
+    pub(crate) fn default_model_name(&self) -> String {
+        self.read_default_model().embedding.model_name.clone()
+    }
+
+    /// The embedding provider this client embeds with
+    pub(crate) fn embedding_provider(&self) -> Arc<dyn EmbeddingProvider> {
+        match &self.embedding_override {
+            Some(provider) => provider.clone(),
+            None => self.read_default_model().backend.embedding_provider.clone(),
+        }
+    }
+
+    /// Default model shared by all clones of this client
+    pub(crate) fn read_default_model(&self) -> RwLockReadGuard<'_, DefaultModel> {
+        self.default_model
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Reload the default embedding model, optionally switching to `model_name`
+    ///
+    /// The model is rebuilt from scratch (picking up a swapped model cache
+    /// directory) and swapped in for subsequent indexing and queries. Switching
+    /// to a model of another dimension is only allowed while the default index
+    /// is empty, in which case the index is recreated with the new dimension.
+    ///
+    /// The swap applies to every clone of this client using the default model;
+    /// [`RagClient::config`] keeps reporting the model the client started with.
+    pub async fn reload_embedding_model(&self, model_name: Option<&str>) -> Result<()> {
+        let current = self.read_default_model().clone();
+        let embedding_config = EmbeddingConfig {
+            model_name: model_name
+                .unwrap_or(&current.embedding.model_name)
+                .to_string(),
+            ..current.embedding.clone()
+        };
+        let model_name = embedding_config.model_name.clone();
+
+        tracing::info!("Reloading embedding model '{}'", model_name);
+        let load_config = embedding_config.clone();
+        let embedding_provider =
+            tokio::task::spawn_blocking(move || provider_from_config(&load_config))
+                .await
+                .context("Failed to spawn model loading task")?
+                .with_context(|| format!("Failed to load embedding model '{}'", model_name))?;
+
+        let vector_db = &current.backend.vector_db;
+        let current_dimension = current.backend.embedding_provider.dimension();
+        let new_dimension = embedding_provider
+            .try_dimension()
+            .context("Failed to determine the embedding dimension")?;
+        if new_dimension != current_dimension {
+            let stats = vector_db
+                .get_statistics()
+                .await
+                .context("Failed to check whether the index is empty")?;
+            if stats.total_points > 0 {
+                return Err(anyhow::Error::from(RagError::from(
+                    EmbeddingError::DimensionMismatch {
+                        expected: current_dimension,
+                        actual: new_dimension,
+                    },
+                )))
+                .with_context(|| {
+                    format!(
+                        "Cannot switch to embedding model '{}': the index holds {} chunks \
+                         of another dimension; clear it first",
+                        model_name, stats.total_points
+                    )
+                });
+            }
+
+            vector_db
+                .clear()
+                .await
+                .context("Failed to clear the empty index")?;
+            vector_db
+                .initialize(new_dimension)
+                .await
+                .context("Failed to reinitialize the index for the new dimension")?;
+        }
+
+        // Embeddings cached under either name may come from the replaced weights
+        {
+            let mut query_embeddings = self.lock_query_embeddings()?;
+            query_embeddings.invalidate_model(&current.embedding.model_name);
+            query_embeddings.invalidate_model(&model_name);
+        }
+        self.save_query_embeddings();
+
+        // Swap the model and its settings together, for every clone at once
+        *self
+            .default_model
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = DefaultModel {
+            embedding: embedding_config,
+            backend: ModelBackend {
+                embedding_provider,
+                vector_db: current.backend.vector_db.clone(),
+            },
+        };
+
+        IndexMetadata {
+            model_name: model_name.clone(),
+            dimension: new_dimension,
+        }
+        .save(&index_metadata_path(&self.config))?;
+
+        tracing::info!(
+            "Embedding model '{}' ({} dimensions) is now active",
+            model_name,
+            new_dimension
+        );
+        Ok(())
+    }
+
+    /// Get a client that embeds and stores with `model_name`
+    ///
+    /// `None` (or the configured default model) returns a client for the default
+    /// model. Other models are initialized on first use and shared afterwards.
+    pub(crate) async fn for_model(&self, model_name: Option<&str>) -> Result<RagClient> {
+        let model_name = match model_name {
+            Some(name) if name != self.default_model_name() => name,
+            _ => return Ok(self.default_model_client()),
+        };
+
+        let backend = self.model_backend(model_name).await?;
+
+        let mut client = self.clone();
+        client.embedding_override = Some(backend.embedding_provider);
+        client.vector_db = backend.vector_db;
+        client.model_override = Some(model_name.to_string());
+        Ok(client)
+    }
+
+    /// Get a client for the model the root containing `path` was indexed with
+    ///
+    /// Paths that are not indexed, or were indexed with the default model, use
+    /// the default model.
+    pub(crate) async fn for_path(&self, path: Option<&str>) -> Result<RagClient> {
+        let Some(normalized) = path.and_then(|p| Self::normalize_path(p).ok()) else {
+            return self.for_model(None).await;
+        };
+
+        let model_name = {
+            let cache = self.hash_cache.read().await;
+            cache
+                .root_models
+                .iter()
+                .filter(|(root, _)| Path::new(&normalized).starts_with(root.as_str()))
+                .max_by_key(|(root, _)| root.len())
+                .map(|(_, model)| model.clone())
+        };
+
+        self.for_model(model_name.as_deref()).await
+    }
+
+    /// Get the clients to search for a query scoped to `path`
+    ///
+    /// A path inside an indexed root is searched with that root's model.
+    /// Otherwise (no path, or one above the indexed roots) the default model
+    /// is searched along with every model that indexed a root under `path`.
+    pub(crate) async fn for_query(&self, path: Option<&str>) -> Result<Vec<RagClient>> {
+        let normalized = match path.map(Self::normalize_path) {
+            Some(Ok(normalized)) => Some(normalized),
+            Some(Err(_)) => return Ok(vec![self.for_model(None).await?]),
+            None => None,
+        };
+
+        let model_names: BTreeSet<String> = {
+            let cache = self.hash_cache.read().await;
+            let containing_root = normalized.as_deref().and_then(|normalized| {
+                cache
+                    .roots
+                    .keys()
+                    .filter(|root| Path::new(normalized).starts_with(root.as_str()))
+                    .max_by_key(|root| root.len())
+            });
+            if let Some(root) = containing_root {
+                let model_name = cache.root_model(root).map(str::to_string);
+                drop(cache);
+                return Ok(vec![self.for_model(model_name.as_deref()).await?]);
+            }
+
+            cache
+                .root_models
+                .iter()
+                .filter(|(root, _)| {
+                    normalized
+                        .as_deref()
+                        .is_none_or(|normalized| Path::new(root.as_str()).starts_with(normalized))
+                })
+                .map(|(_, model)| model.clone())
+                .collect()
+        };
+
+        let default_model_name = self.default_model_name();
+        let mut clients = vec![self.default_model_client()];
+        for model_name in &model_names {
+            if *model_name != default_model_name {
+                clients.push(self.for_model(Some(model_name)).await?);
+            }
+        }
+        Ok(clients)
+    }
+
+    /// Get a client for indexing `path`
+    ///
+    /// An explicit `model_name` wins; otherwise the model the path was last
+    /// indexed with is reused, falling back to the default model.
+    pub(crate) async fn for_indexing(
+        &self,
+        path: &str,
+        model_name: Option<&str>,
+    ) -> Result<RagClient> {
+        match model_name {
+            Some(model_name) => self.for_model(Some(model_name)).await,
+            None => self.for_path(Some(path)).await,
+        }
+    }
+
+    /// Client for the default model, regardless of this client's model
+    fn default_model_client(&self) -> RagClient {
+        let mut client = self.clone();
+        client.embedding_override = None;
+        client.vector_db = self.read_default_model().backend.vector_db.clone();
+        client.model_override = None;
+        client
+    }
+
+    /// Get or lazily create the backend for a non-default model
+    async fn model_backend(&self, model_name: &str) -> Result<ModelBackend> {
+        let loaded = self.model_backends.read().await.get(model_name).cloned();
+        let cell = match loaded {
+            Some(cell) => cell,
+            None => self
+                .model_backends
+                .write()
+                .await
+                .entry(model_name.to_string())
+                .or_default()
+                .clone(),
+        };
+
+        // Concurrent callers wait on the same cell, so a model is only loaded once
+        cell.get_or_try_init(|| self.load_model_backend(model_name))
+            .await
+            .cloned()
+    }
+
+    /// Load a non-default model and initialize its storage
+    async fn load_model_backend(&self, model_name: &str) -> Result<ModelBackend> {
+        tracing::info!("Initializing embedding model '{}'", model_name);
+
+        let embedding_config = EmbeddingConfig {
+            model_name: model_name.to_string(),
+            ..self.read_default_model().embedding.clone()
+        };
+        let embedding_provider =
+            tokio::task::spawn_blocking(move || provider_from_config(&embedding_config))
+                .await
+                .context("Failed to spawn model loading task")?
+                .with_context(|| {
+                    format!("Failed to initialize embedding model '{}'", model_name)
+                })?;
+
+        let vector_db = self.open_model_store(model_name).await?;
+
+        let dimension = embedding_provider
+            .try_dimension()
+            .context("Failed to determine the embedding dimension")?;
+        vector_db
+            .initialize(dimension)
+            .await
+            .with_context(|| format!("Failed to initialize storage for model '{}'", model_name))?;
+
+        Ok(ModelBackend {
+            embedding_provider,
+            vector_db,
+        })
+    }
+
+    /// Open the vector store of a non-default model, without loading the model
+    async fn open_model_store(&self, model_name: &str) -> Result<Arc<ModelStore>> {
+        let table_name = table_name_for_model(model_name);
+        let phase_spans = PhaseSpans::new(self.config.observability.phase_spans);
+
+        #[cfg(feature = "qdrant-backend")]
+        let vector_db = Arc::new(
+            QdrantVectorDB::with_url_and_collection(&self.config.vector_db.qdrant_url, &table_name)
+                .await
+                .context("Failed to initialize Qdrant vector database")?
+                .with_phase_spans(phase_spans)
+                .with_doc_boost(self.config.search.doc_boost()),
+        );
+
+        #[cfg(not(feature = "qdrant-backend"))]
+        let vector_db = Arc::new(
+            LanceVectorDB::with_path_and_table(
+                &self.config.vector_db.lancedb_path.to_string_lossy(),
+                &table_name,
+            )
+            .await
+            .context("Failed to initialize LanceDB vector database")?
+            .with_phase_spans(phase_spans)
+            .with_ann_params(self.config.search.ann_params())
+            .with_ann_index(self.config.vector_db.ann_index_params())
+            .with_distance(self.config.vector_db.distance)
+            .with_bm25_analyzer(self.config.bm25_analyzer())
+            .with_bm25_writer_lock_timeout(std::time::Duration::from_secs(
+                self.config.search.bm25_writer_lock_timeout_secs,
+            ))
+            .with_bm25_stale_lock_timeout(self.config.bm25_stale_lock_timeout())
+            .with_doc_boost(self.config.search.doc_boost()),
+        );
+
+        Ok(vector_db)
+    }
+
+    /// Clear and reinitialize the storage of every non-default model
+    ///
+    /// Covers models loaded in this process as well as models recorded in the
+    /// hash cache by earlier runs. The latter are not loaded; their storage is
+    /// only dropped.
+    pub(crate) async fn clear_model_backends(&self) -> Result<()> {
+        let mut model_names: Vec<String> = {
+            let cache = self.hash_cache.read().await;
+            cache.root_models.values().cloned().collect()
+        };
+        model_names.extend(self.model_backends.read().await.keys().cloned());
+        model_names.sort();
+        model_names.dedup();
+        let default_model_name = self.default_model_name();
+        model_names.retain(|name| *name != default_model_name);
+
+        for model_name in model_names {
+            let loaded = self
+                .model_backends
+                .read()
+                .await
+                .get(&model_name)
+                .and_then(|cell| cell.get().cloned());
+            let Some(backend) = loaded else {
+                // Storage of a model not loaded yet is initialized when it is
+                let vector_db = self.open_model_store(&model_name).await?;
+                if vector_db.indexed_dimension().await?.is_some() {
+                    vector_db.clear().await.with_context(|| {
+                        format!("Failed to clear storage for model '{}'", model_name)
+                    })?;
+                }
+                continue;
+            };
+
+            backend
+                .vector_db
+                .clear()
+                .await
+                .with_context(|| format!("Failed to clear storage for model '{}'", model_name))?;
+            backend
+                .vector_db
+                .initialize(backend.embedding_provider.dimension())
+                .await
+                .with_context(|| {
+                    format!("Failed to reinitialize storage for model '{}'", model_name)
+                })?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_name_for_model_is_sanitized() {
+        let name = table_name_for_model("BAAI/bge-small-en-v1.5");
+        assert!(name.starts_with("code_embeddings_baai_bge_small_en_v1_5_"));
+        assert!(name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    }
+
+    #[test]
+    fn test_table_name_for_model_distinguishes_models() {
+        // Same sanitized form, different models
+        assert_ne!(
+            table_name_for_model("org/model-a"),
+            table_name_for_model("org/model_a")
+        );
+        assert_eq!(
+            table_name_for_model("org/model-a"),
+            table_name_for_model("org/model-a")
+        );
+    }
+}
@@ -0,0 +1,393 @@
+//! Search combined with code relations
+//!
+//! Runs a semantic search and joins each matching chunk to the functions and
+//! methods it covers, attaching their immediate callers and callees.
+
+use super::RagClient;
+use crate::indexer::FileInfo;
+use crate::relations::{
+    CallGraphNode, Definition, Reference, ReferenceKind, SymbolInfo, SymbolKind,
+};
+use crate::types::{
+    RelatedSearchResult, RelatedSymbol, SearchResult, SearchWithRelationsRequest,
+    SearchWithRelationsResponse,
+};
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Definitions and references extracted from a single file
+struct FileRelations {
+    definitions: Vec<Definition>,
+    symbol_index: HashMap<String, Vec<Definition>>,
+    references: Vec<Reference>,
+}
+
+/// Search the codebase and attach callers/callees to every matching symbol
+pub(crate) async fn do_search_with_relations(
+    client: &RagClient,
+    request: SearchWithRelationsRequest,
+) -> Result<SearchWithRelationsResponse> {
+    let start = Instant::now();
+
+    request.validate().map_err(|e| anyhow::anyhow!(e))?;
+
+    let response = client.query_codebase(request.to_query_request()).await?;
+
+    // Relations are extracted once per file, even if several chunks match it
+    let mut files: HashMap<PathBuf, Option<String>> = HashMap::new();
+    for result in &response.results {
+        files
+            .entry(resolve_result_path(result))
+            .or_insert_with(|| result.project.clone());
+    }
+
+    // Reading and parsing the files blocks, so keep it off the async runtime
+    let file_relations: HashMap<PathBuf, Option<FileRelations>> = tokio::task::spawn_blocking({
+        let client = client.clone();
+        move || {
+            files
+                .into_iter()
+                .map(|(path, project)| {
+                    let relations = load_file_relations(&client, &path, project);
+                    (path, relations)
+                })
+                .collect()
+        }
+    })
+    .await
+    .context("Failed to spawn blocking task for relation extraction")?;
+
+    let mut results = Vec::with_capacity(response.results.len());
+    for result in response.results {
+        let symbols = match &file_relations[&resolve_result_path(&result)] {
+            Some(relations) => symbols_for_result(relations, &result),
+            None => Vec::new(),
+        };
+
+        results.push(RelatedSearchResult { result, symbols });
+    }
+
+    Ok(SearchWithRelationsResponse {
+        results,
+        duration_ms: start.elapsed().as_millis() as u64,
+        threshold_used: response.threshold_used,
+        threshold_lowered: response.threshold_lowered,
+        truncated: response.truncated,
+    })
+}
+
+/// Resolve the on-disk location of a search result
+pub(super) fn resolve_result_path(result: &SearchResult) -> PathBuf {
+    match &result.root_path {
+        Some(root) => PathBuf::from(root).join(&result.file_path),
+        None => PathBuf::from(&result.file_path),
+    }
+}
+
+/// Extract definitions and references for a file
+///
+/// Returns `None` if the file can no longer be read or parsed (e.g. it was
+/// deleted since indexing); such results are returned without symbols.
+fn load_file_relations(
+    client: &RagClient,
+    path: &std::path::Path,
+    project: Option<String>,
+) -> Option<FileRelations> {
+    let (file_info, definitions) = load_definitions(client, path, project)?;
+
+    let symbol_index = build_symbol_index(&definitions);
+
+    let references = client
+        .relations_provider
+        .extract_references(&file_info, &symbol_index)
+        .unwrap_or_else(|e| {
+            tracing::debug!(
+                "Failed to extract references from {}: {:#}",
+                path.display(),
+                e
+            );
+            Vec::new()
+        });
+
+    Some(FileRelations {
+        definitions,
+        symbol_index,
+        references,
+    })
+}
+
+/// Read a file and extract its definitions, or `None` if it can't be read or parsed
+fn load_definitions(
+    client: &RagClient,
+    path: &std::path::Path,
+    project: Option<String>,
+) -> Option<(FileInfo, Vec<Definition>)> {
+    let file_info = match client.create_file_info(&path.to_string_lossy(), project) {
+        Ok(info) => info,
+        Err(e) => {
+            tracing::debug!("Skipping relations for {}: {:#}", path.display(), e);
+            return None;
+        }
+    };
+
+    match client.relations_provider.extract_definitions(&file_info) {
+        Ok(definitions) => Some((file_info, definitions)),
+        Err(e) => {
+            tracing::debug!(
+                "Failed to extract definitions from {}: {:#}",
+                path.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Line separating a prepended signature from the matched snippet
+pub(crate) const SIGNATURE_SEPARATOR: &str = "\n---\n";
+
+/// Prefix each result's content with the signature of its enclosing function
+///
+/// Results that don't start inside a function or method body (top-level code,
+/// or chunks that already begin at the declaration) are left unchanged.
+pub(super) fn prepend_enclosing_signatures(client: &RagClient, results: &mut [SearchResult]) {
+    // Definitions are extracted once per file, even if several chunks match it
+    let mut file_definitions: HashMap<PathBuf, Option<Vec<Definition>>> = HashMap::new();
+
+    for result in results.iter_mut() {
+        let absolute_path = resolve_result_path(result);
+        let definitions = file_definitions
+            .entry(absolute_path.clone())
+            .or_insert_with(|| {
+                load_definitions(client, &absolute_path, result.project.clone())
+                    .map(|(_, definitions)| definitions)
+            });
+
+        if let Some(definitions) = definitions
+            && let Some(def) = enclosing_callable(definitions, result)
+        {
+            result.content = format!("{}{}{}", def.signature, SIGNATURE_SEPARATOR, result.content);
+            // Keep highlights pointing at the same text
+            let offset = def.signature.len() + SIGNATURE_SEPARATOR.len();
+            for (start, end) in &mut result.highlights {
+                *start += offset;
+                *end += offset;
+            }
+        }
+    }
+}
+
+/// Innermost function or method whose body contains the start of a result chunk
+fn enclosing_callable<'a>(
+    definitions: &'a [Definition],
+    result: &SearchResult,
+) -> Option<&'a Definition> {
+    definitions
+        .iter()
+        .filter(|def| {
+            is_callable(&def.symbol_id.kind)
+                && def.symbol_id.start_line < result.start_line
+                && def.end_line >= result.start_line
+        })
+        .max_by_key(|def| def.symbol_id.start_line)
+}
+
+/// Find the functions/methods overlapping a result chunk and resolve their relations
+fn symbols_for_result(relations: &FileRelations, result: &SearchResult) -> Vec<RelatedSymbol> {
+    relations
+        .definitions
+        .iter()
+        .filter(|def| {
+            is_callable(&def.symbol_id.kind)
+                && def.symbol_id.start_line <= result.end_line
+                && def.end_line >= result.start_line
+        })
+        .map(|def| {
+            let (callers, callees) = call_relations(
+                &relations.definitions,
+                &relations.symbol_index,
+                &relations.references,
+                def,
+                &result.file_path,
+            );
+
+            RelatedSymbol {
+                symbol: SymbolInfo {
+                    name: def.symbol_id.name.clone(),
+                    kind: def.symbol_id.kind.clone(),
+                    file_path: result.file_path.clone(),
+                    start_line: def.symbol_id.start_line,
+                    end_line: def.end_line,
+                    signature: def.signature.clone(),
+                },
+                callers,
+                callees,
+            }
+        })
+        .collect()
+}
+
+/// Whether a symbol kind can take part in a call graph
+pub(super) fn is_callable(kind: &SymbolKind) -> bool {
+    matches!(kind, SymbolKind::Function | SymbolKind::Method)
+}
+
+/// Group definitions by symbol name for reference lookups
+pub(super) fn build_symbol_index(definitions: &[Definition]) -> HashMap<String, Vec<Definition>> {
+    let mut symbol_index: HashMap<String, Vec<Definition>> = HashMap::new();
+    for def in definitions {
+        symbol_index
+            .entry(def.symbol_id.name.clone())
+            .or_default()
+            .push(def.clone());
+    }
+    symbol_index
+}
+
+/// Compute the immediate callers and callees of a function within one file
+///
+/// Callers are the functions containing a call to `target`; callees are the
+/// known functions called from inside `target`'s body.
+pub(super) fn call_relations(
+    definitions: &[Definition],
+    symbol_index: &HashMap<String, Vec<Definition>>,
+    references: &[Reference],
+    target: &Definition,
+    file_path: &str,
+) -> (Vec<CallGraphNode>, Vec<CallGraphNode>) {
+    call_graph(definitions, symbol_index, references, target, file_path, 1)
+}
+
+/// Compute the callers and callees of a function within one file, `depth` levels deep
+///
+/// A caller's children are its own callers and a callee's children its own
+/// callees. The first level is always returned. A function already expanded
+/// in the same direction is listed again without children, so recursive and
+/// mutually recursive functions don't loop.
+pub(super) fn call_graph(
+    definitions: &[Definition],
+    symbol_index: &HashMap<String, Vec<Definition>>,
+    references: &[Reference],
+    target: &Definition,
+    file_path: &str,
+    depth: usize,
+) -> (Vec<CallGraphNode>, Vec<CallGraphNode>) {
+    let relations = CallRelations {
+        definitions,
+        symbol_index,
+        references,
+        file_path,
+    };
+    let depth = depth.max(1);
+
+    let mut visited = HashSet::from([target.to_storage_id()]);
+    let callers = relations.expand(target, CallDirection::Callers, depth, &mut visited);
+
+    let mut visited = HashSet::from([target.to_storage_id()]);
+    let callees = relations.expand(target, CallDirection::Callees, depth, &mut visited);
+
+    (callers, callees)
+}
+
+/// Which way a call graph is walked
+#[derive(Clone, Copy)]
+enum CallDirection {
+    Callers,
+    Callees,
+}
+
+/// The relations of one file, as used to walk its call graph
+struct CallRelations<'a> {
+    definitions: &'a [Definition],
+    symbol_index: &'a HashMap<String, Vec<Definition>>,
+    references: &'a [Reference],
+    file_path: &'a str,
+}
+
+impl<'a> CallRelations<'a> {
+    /// Nodes for the functions calling (or called by) `target`, `depth` levels deep
+    fn expand(
+        &self,
+        target: &Definition,
+        direction: CallDirection,
+        depth: usize,
+        visited: &mut HashSet<String>,
+    ) -> Vec<CallGraphNode> {
+        let next = match direction {
+            CallDirection::Callers => self.callers(target),
+            CallDirection::Callees => self.callees(target),
+        };
+
+        next.into_iter()
+            .map(|def| {
+                let mut node = call_graph_node(def, self.file_path);
+                if depth > 1 && visited.insert(def.to_storage_id()) {
+                    node.children = self.expand(def, direction, depth - 1, visited);
+                }
+                node
+            })
+            .collect()
+    }
+
+    /// Functions containing a call to `target`
+    fn callers(&self, target: &Definition) -> Vec<&'a Definition> {
+        let function_name = &target.symbol_id.name;
+
+        // Find callers (references with Call kind pointing to our function)
+        let mut seen_callers = HashSet::new();
+        self.references
+            .iter()
+            .filter(|r| {
+                r.reference_kind == ReferenceKind::Call
+                    && target_name(&r.target_symbol_id) == Some(function_name.as_str())
+            })
+            .filter_map(|r| {
+                // Find which function contains this call
+                self.definitions.iter().find(|def| {
+                    is_callable(&def.symbol_id.kind)
+                        && r.start_line >= def.symbol_id.start_line
+                        && r.start_line <= def.end_line
+                })
+            })
+            .filter(|def| seen_callers.insert(def.symbol_id.name.clone()))
+            .collect()
+    }
+
+    /// Known functions called from inside `target`'s body
+    fn callees(&self, target: &Definition) -> Vec<&'a Definition> {
+        // Find callees (calls made from within our function)
+        let mut seen_callees = HashSet::new();
+        self.references
+            .iter()
+            .filter(|r| {
+                r.reference_kind == ReferenceKind::Call
+                    && r.start_line >= target.symbol_id.start_line
+                    && r.start_line <= target.end_line
+            })
+            .filter_map(|r| {
+                let defs = self.symbol_index.get(target_name(&r.target_symbol_id)?)?;
+                defs.iter()
+                    .find(|def| def.to_storage_id() == r.target_symbol_id)
+                    .or(defs.first())
+            })
+            .filter(|def| seen_callees.insert(def.symbol_id.name.clone()))
+            .collect()
+    }
+}
+
+/// Symbol name in a definition storage id (`def:{file}:{name}:{line}`)
+fn target_name(target_symbol_id: &str) -> Option<&str> {
+    target_symbol_id.rsplit(':').nth(1)
+}
+
+fn call_graph_node(def: &Definition, file_path: &str) -> CallGraphNode {
+    CallGraphNode {
+        name: def.symbol_id.name.clone(),
+        kind: def.symbol_id.kind.clone(),
+        file_path: file_path.to_string(),
+        line: def.symbol_id.start_line,
+        children: Vec::new(),
+    }
+}
@@ -1,4 +1,7 @@
 use super::*;
+use crate::error::{EmbeddingError, RagError};
+use crate::indexer::ChunkStrategyKind;
+use crate::relations::PrecisionLevel;
 use tempfile::TempDir;
 
 // Helper to create a test client
@@ -95,6 +98,7 @@ async fn test_index_codebase_empty_directory() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
 
     let result = client.index_codebase(request).await;
@@ -117,6 +121,7 @@ async fn test_index_codebase_with_single_file() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
 
     let result = client.index_codebase(request).await;
@@ -128,6 +133,63 @@ async fn test_index_codebase_with_single_file() {
     assert!(response.embeddings_generated > 0);
 }
 
+#[tokio::test]
+async fn test_index_codebase_with_progress_streams_phases() {
+    let (client, temp_dir) = create_test_client().await;
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn main() {}").unwrap();
+
+    let request = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+
+    // A single-slot channel makes indexing wait on the receiver
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    let collector = tokio::spawn(async move {
+        let mut updates = Vec::new();
+        while let Some(progress) = rx.recv().await {
+            updates.push(progress);
+        }
+        updates
+    });
+
+    let response = client
+        .index_codebase_with_progress(request, tx)
+        .await
+        .unwrap();
+    assert_eq!(response.files_indexed, 1);
+
+    // The sender is dropped with the indexing run, which ends the stream
+    let updates = collector.await.unwrap();
+    let phases: Vec<IndexPhase> = updates.iter().map(|update| update.phase).collect();
+    assert_eq!(phases.first(), Some(&IndexPhase::Scanning));
+    assert_eq!(phases.last(), Some(&IndexPhase::Complete));
+    for phase in [
+        IndexPhase::Chunking,
+        IndexPhase::Embedding,
+        IndexPhase::Storing,
+    ] {
+        assert!(
+            phases.contains(&phase),
+            "missing {:?} in {:?}",
+            phase,
+            phases
+        );
+    }
+    assert!(
+        updates
+            .windows(2)
+            .all(|pair| pair[0].percent <= pair[1].percent)
+    );
+    assert_eq!(updates.last().unwrap().percent, 100.0);
+}
+
 #[tokio::test]
 async fn test_index_codebase_validation_failure() {
     let (client, _temp_dir) = create_test_client().await;
@@ -138,6 +200,7 @@ async fn test_index_codebase_validation_failure() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
 
     let result = client.index_codebase(request).await;
@@ -155,9 +218,17 @@ async fn test_query_codebase_empty_index() {
         query: "test query".to_string(),
         path: None,
         project: None,
+        projects: vec![],
         limit: 10,
+        offset: 0,
         min_score: 0.7,
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
     let result = client.query_codebase(request).await;
@@ -188,6 +259,7 @@ async fn test_query_codebase_with_data() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
     client.index_codebase(index_req).await.unwrap();
 
@@ -196,9 +268,17 @@ async fn test_query_codebase_with_data() {
         query: "authentication".to_string(),
         path: None,
         project: Some("test-project".to_string()),
+        projects: vec![],
         limit: 10,
+        offset: 0,
         min_score: 0.3,
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
     let result = client.query_codebase(query_req).await;
@@ -224,6 +304,7 @@ async fn test_query_codebase_adaptive_threshold() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
     client.index_codebase(index_req).await.unwrap();
 
@@ -232,9 +313,17 @@ async fn test_query_codebase_adaptive_threshold() {
         query: "completely unrelated query about databases".to_string(),
         path: None,
         project: None,
+        projects: vec![],
         limit: 10,
+        offset: 0,
         min_score: 0.9, // Very high threshold
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
     let result = client.query_codebase(query_req).await;
@@ -243,829 +332,2904 @@ async fn test_query_codebase_adaptive_threshold() {
 }
 
 #[tokio::test]
-async fn test_query_codebase_validation_failure() {
-    let (client, _temp_dir) = create_test_client().await;
+async fn test_query_codebase_clamps_to_server_max_results() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.server.max_results = 2;
+    client.config = Arc::new(config);
 
-    let request = QueryRequest {
-        query: "   ".to_string(), // Empty query
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    for name in ["a", "b", "c", "d"] {
+        std::fs::write(
+            data_dir.join(format!("{}.rs", name)),
+            format!("fn handler_{}() {{ /* request handler */ }}", name),
+        )
+        .unwrap();
+    }
+
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
+
+    let query = |limit| QueryRequest {
+        query: "request handler".to_string(),
         path: None,
         project: None,
-        limit: 10,
-        min_score: 0.7,
+        projects: vec![],
+        limit,
+        offset: 0,
+        min_score: 0.0,
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
-    let result = client.query_codebase(request).await;
-    assert!(result.is_err());
-    assert!(result.unwrap_err().to_string().contains("cannot be empty"));
-}
+    // Over the cap: clamped and flagged
+    let response = client.query_codebase(query(10)).await.unwrap();
+    assert_eq!(response.results.len(), 2);
+    assert!(response.truncated);
 
-// ===== search_with_filters Tests =====
+    // Within the cap: untouched
+    let response = client.query_codebase(query(2)).await.unwrap();
+    assert_eq!(response.results.len(), 2);
+    assert!(!response.truncated);
+}
 
 #[tokio::test]
-async fn test_search_with_filters_empty_index() {
-    let (client, _temp_dir) = create_test_client().await;
+async fn test_query_codebase_pages_through_results() {
+    let (client, temp_dir) = create_test_client().await;
 
-    let request = AdvancedSearchRequest {
-        query: "test".to_string(),
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    for name in ["a", "b", "c", "d", "e"] {
+        std::fs::write(
+            data_dir.join(format!("{}.rs", name)),
+            format!("fn handler_{}() {{ /* request handler */ }}", name),
+        )
+        .unwrap();
+    }
+
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
+
+    let page = |offset, limit| QueryRequest {
+        query: "request handler".to_string(),
         path: None,
         project: None,
-        limit: 10,
-        min_score: 0.7,
-        file_extensions: vec!["rs".to_string()],
-        languages: vec!["Rust".to_string()],
-        path_patterns: vec!["src/**".to_string()],
+        projects: vec![],
+        limit,
+        offset,
+        min_score: 0.0,
+        hybrid: false,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    let chunk_ids = |response: &QueryResponse| -> Vec<String> {
+        response
+            .results
+            .iter()
+            .map(|r| r.chunk_id.clone())
+            .collect()
     };
 
-    let result = client.search_with_filters(request).await;
-    assert!(result.is_ok());
+    let all = client.query_codebase(page(0, 10)).await.unwrap();
+    assert_eq!(all.results.len(), 5);
+    assert!(!all.has_more);
 
-    let response = result.unwrap();
-    assert_eq!(response.results.len(), 0);
+    let first = client.query_codebase(page(0, 2)).await.unwrap();
+    let second = client.query_codebase(page(2, 2)).await.unwrap();
+    let last = client.query_codebase(page(4, 2)).await.unwrap();
+    assert!(first.has_more && second.has_more && !last.has_more);
+
+    let paged = [chunk_ids(&first), chunk_ids(&second), chunk_ids(&last)].concat();
+    assert_eq!(paged, chunk_ids(&all));
 }
 
 #[tokio::test]
-async fn test_search_with_filters_validation_failure() {
-    let (client, _temp_dir) = create_test_client().await;
+async fn test_query_files_lists_each_file_once() {
+    let (client, temp_dir) = create_test_client().await;
 
-    let request = AdvancedSearchRequest {
-        query: "test".to_string(),
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(
+        data_dir.join("users.rs"),
+        "fn create_user() {}\n\nfn delete_user() {}\n\nfn rename_user() {}\n",
+    )
+    .unwrap();
+    std::fs::write(data_dir.join("orders.rs"), "fn place_order() {}").unwrap();
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
+
+    let query_req = QueryRequest {
+        query: "user".to_string(),
         path: None,
         project: None,
+        projects: vec![],
         limit: 10,
-        min_score: 0.7,
-        file_extensions: vec!["".to_string()], // Invalid
-        languages: vec![],
-        path_patterns: vec![],
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
-
-    let result = client.search_with_filters(request).await;
-    assert!(result.is_err());
+    let chunks = client.query_codebase(query_req.clone()).await.unwrap();
+    let user_chunks = chunks
+        .results
+        .iter()
+        .filter(|r| r.file_path == "users.rs")
+        .count();
+    assert!(user_chunks > 1, "users.rs should match with several chunks");
+
+    let response = client.query_files(query_req).await.unwrap();
+    let users: Vec<_> = response
+        .files
+        .iter()
+        .filter(|f| f.file_path == "users.rs")
+        .collect();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].chunk_count, user_chunks);
+    assert_eq!(response.files.len(), 2);
     assert!(
-        result
-            .unwrap_err()
-            .to_string()
-            .contains("file extension cannot be empty")
+        response
+            .files
+            .windows(2)
+            .all(|pair| pair[0].best_score >= pair[1].best_score)
     );
 }
 
 #[tokio::test]
-async fn test_search_with_filters_with_data() {
+async fn test_query_files_pages_count_files() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Create a directory structure for testing filters
     let data_dir = temp_dir.path().join("data");
-    let src_dir = data_dir.join("src");
-    std::fs::create_dir_all(&src_dir).unwrap();
-
-    // Create Rust file
-    std::fs::write(
-        src_dir.join("auth.rs"),
-        "fn authenticate_user(username: &str) -> bool { true }",
-    )
-    .unwrap();
-
-    // Create Python file
+    std::fs::create_dir(&data_dir).unwrap();
     std::fs::write(
-        src_dir.join("auth.py"),
-        "def authenticate_user(username): return True",
+        data_dir.join("users.rs"),
+        "fn create_user() {}\n\nfn delete_user() {}\n\nfn rename_user() {}\n",
     )
     .unwrap();
-
-    // Index the data
+    std::fs::write(data_dir.join("accounts.rs"), "fn close_user_account() {}").unwrap();
+    std::fs::write(data_dir.join("orders.rs"), "fn place_order() {}").unwrap();
     let index_req = IndexRequest {
         path: data_dir.to_string_lossy().to_string(),
-        project: Some("filter-test".to_string()),
+        project: None,
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
     client.index_codebase(index_req).await.unwrap();
 
-    // Search with file extension filter for Rust only
-    let request = AdvancedSearchRequest {
-        query: "authenticate user".to_string(),
+    let page = |offset: usize, limit: usize| QueryRequest {
+        query: "user".to_string(),
         path: None,
-        project: Some("filter-test".to_string()),
-        limit: 10,
-        min_score: 0.3,
-        file_extensions: vec!["rs".to_string()],
-        languages: vec![],
-        path_patterns: vec![],
+        project: None,
+        projects: vec![],
+        limit,
+        offset,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    let file_paths = |response: &QueryFilesResponse| -> Vec<String> {
+        response.files.iter().map(|f| f.file_path.clone()).collect()
     };
 
-    let result = client.search_with_filters(request).await;
-    assert!(result.is_ok());
+    let all = client.query_files(page(0, 10)).await.unwrap();
+    assert_eq!(all.files.len(), 3);
+    assert!(!all.has_more);
 
-    let response = result.unwrap();
-    // Should find results and all should be .rs files
-    for result in &response.results {
-        assert!(
-            result.file_path.ends_with(".rs"),
-            "Expected .rs file, got: {}",
-            result.file_path
-        );
-    }
+    // Each page holds `limit` files, even when one file spans several chunks
+    let first = client.query_files(page(0, 2)).await.unwrap();
+    let last = client.query_files(page(2, 2)).await.unwrap();
+    assert_eq!(first.files.len(), 2);
+    assert!(first.has_more && !last.has_more);
+
+    let paged = [file_paths(&first), file_paths(&last)].concat();
+    assert_eq!(paged, file_paths(&all));
 }
 
 #[tokio::test]
-async fn test_search_with_filters_adaptive_threshold_lowering() {
+async fn test_query_codebase_summary_matches_results() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Index some data
+    for (project, files) in [
+        (
+            "api",
+            vec![
+                ("login.rs", "fn login_user() {}"),
+                ("logout.py", "def logout_user(): pass"),
+            ],
+        ),
+        ("web", vec![("session.rs", "fn user_session() {}")]),
+    ] {
+        let data_dir = temp_dir.path().join(project);
+        std::fs::create_dir(&data_dir).unwrap();
+        for (name, content) in files {
+            std::fs::write(data_dir.join(name), content).unwrap();
+        }
+        let index_req = IndexRequest {
+            path: data_dir.to_string_lossy().to_string(),
+            project: Some(project.to_string()),
+            include_patterns: vec![],
+            exclude_patterns: vec![],
+            max_file_size: 1024 * 1024,
+            model_name: None,
+        };
+        client.index_codebase(index_req).await.unwrap();
+    }
+
+    let query_req = QueryRequest {
+        query: "user".to_string(),
+        path: None,
+        project: None,
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: true,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    let response = client.query_codebase(query_req).await.unwrap();
+    let results = &response.results;
+    assert!(!results.is_empty());
+
+    let summary = response.summary.expect("summary was requested");
+    assert_eq!(summary.result_count, results.len());
+
+    let files: std::collections::HashSet<_> = results.iter().map(|r| &r.file_path).collect();
+    assert_eq!(summary.file_count, files.len());
+
+    for bucket in &summary.projects {
+        let count = results
+            .iter()
+            .filter(|r| r.project.as_deref() == Some(bucket.name.as_str()))
+            .count();
+        assert_eq!(bucket.results, count);
+    }
+    let projects: usize = summary.projects.iter().map(|b| b.results).sum();
+    assert_eq!(projects, results.len());
+
+    let languages: usize = summary.languages.iter().map(|b| b.results).sum();
+    assert_eq!(languages, results.len());
+
+    assert_eq!(summary.max_score, results[0].score);
+    assert_eq!(summary.top_file.as_ref(), Some(&results[0].file_path));
+    assert!(summary.min_score <= summary.mean_score && summary.mean_score <= summary.max_score);
+}
+
+#[tokio::test]
+async fn test_query_codebase_reports_relations_precision() {
+    let (mut client, temp_dir) = create_test_client().await;
+
     let data_dir = temp_dir.path().join("data");
     std::fs::create_dir(&data_dir).unwrap();
     std::fs::write(
-        data_dir.join("code.rs"),
-        "fn process_data() { /* some logic here */ }",
+        data_dir.join("auth.rs"),
+        "fn authenticate_user(name: &str) -> bool {\n    !name.is_empty()\n}\n",
     )
     .unwrap();
-
     let index_req = IndexRequest {
         path: data_dir.to_string_lossy().to_string(),
-        project: Some("adaptive-test".to_string()),
+        project: None,
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
     client.index_codebase(index_req).await.unwrap();
 
-    // Search with high threshold - should trigger adaptive lowering
-    let request = AdvancedSearchRequest {
-        query: "process data function".to_string(),
+    let query_req = QueryRequest {
+        query: "authenticate user".to_string(),
         path: None,
-        project: Some("adaptive-test".to_string()),
+        project: None,
+        projects: vec![],
         limit: 10,
-        min_score: 0.9, // Very high threshold that will likely not match
-        file_extensions: vec![],
-        languages: vec![],
-        path_patterns: vec![],
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: true,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
-    let result = client.search_with_filters(request).await;
-    assert!(result.is_ok());
+    // RepoMap resolves Rust symbols by name only
+    let response = client.query_codebase(query_req.clone()).await.unwrap();
+    let result = response
+        .results
+        .iter()
+        .find(|r| r.language == "Rust" && r.symbol_kind.is_some())
+        .expect("a Rust result inside a symbol");
+    assert_eq!(result.relations_precision, Some(PrecisionLevel::Medium));
+    assert_eq!(
+        serde_json::to_value(result).unwrap()["relations_precision"],
+        "medium"
+    );
 
-    let response = result.unwrap();
-    // If results were found, threshold should have been lowered
-    if !response.results.is_empty() {
-        assert!(
-            response.threshold_lowered,
-            "Expected threshold_lowered to be true when results found with high initial threshold"
-        );
-        assert!(
-            response.threshold_used < 0.9,
-            "Expected threshold_used ({}) to be lower than initial 0.9",
-            response.threshold_used
-        );
-    }
+    // Results are only annotated when asked to
+    let plain = QueryRequest {
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+        ..query_req.clone()
+    };
+    let response = client.query_codebase(plain).await.unwrap();
+    assert!(
+        response
+            .results
+            .iter()
+            .all(|r| r.relations_precision.is_none())
+    );
+
+    // Languages covered by stack-graphs report high precision once it is enabled
+    client.relations_provider = Arc::new(HybridRelationsProvider::new(true).unwrap());
+    let expected = if client.relations_provider.has_stack_graphs_for("Rust") {
+        PrecisionLevel::High
+    } else {
+        PrecisionLevel::Medium
+    };
+    let response = client.query_codebase(query_req).await.unwrap();
+    let result = response
+        .results
+        .iter()
+        .find(|r| r.language == "Rust" && r.symbol_kind.is_some())
+        .expect("a Rust result inside a symbol");
+    assert_eq!(result.relations_precision, Some(expected));
 }
 
 #[tokio::test]
-async fn test_search_with_filters_no_adaptive_when_results_found() {
+async fn test_query_codebase_validation_failure() {
+    let (client, _temp_dir) = create_test_client().await;
+
+    let request = QueryRequest {
+        query: "   ".to_string(), // Empty query
+        path: None,
+        project: None,
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.7,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+
+    let result = client.query_codebase(request).await;
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("cannot be empty"));
+}
+
+#[tokio::test]
+async fn test_query_codebase_exclude_files() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Index data with highly relevant content
     let data_dir = temp_dir.path().join("data");
     std::fs::create_dir(&data_dir).unwrap();
     std::fs::write(
         data_dir.join("auth.rs"),
-        "fn authenticate_user_with_password(username: &str, password: &str) -> Result<User, AuthError> { authenticate(username, password) }",
+        "fn authenticate_user() { /* authentication logic */ }",
+    )
+    .unwrap();
+    std::fs::write(
+        data_dir.join("login.rs"),
+        "fn login_user() { /* user authentication and login */ }",
     )
     .unwrap();
 
     let index_req = IndexRequest {
         path: data_dir.to_string_lossy().to_string(),
-        project: Some("no-adaptive-test".to_string()),
+        project: Some("exclude-project".to_string()),
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
     client.index_codebase(index_req).await.unwrap();
 
-    // Search with low threshold - should find results without lowering
-    let request = AdvancedSearchRequest {
-        query: "authenticate user password".to_string(),
+    let mut query_req = QueryRequest {
+        query: "authentication".to_string(),
         path: None,
-        project: Some("no-adaptive-test".to_string()),
-        limit: 10,
-        min_score: 0.3, // Low threshold
-        file_extensions: vec![],
-        languages: vec![],
-        path_patterns: vec![],
+        project: Some("exclude-project".to_string()),
+        projects: vec![],
+        limit: 1,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
-    let result = client.search_with_filters(request).await;
-    assert!(result.is_ok());
+    let first = client.query_codebase(query_req.clone()).await.unwrap();
+    assert_eq!(first.results.len(), 1);
+    let top_file = first.results[0].file_path.clone();
 
-    let response = result.unwrap();
-    if !response.results.is_empty() {
-        // Threshold should NOT have been lowered since 0.3 is already low
-        assert!(
-            !response.threshold_lowered,
-            "Expected threshold_lowered to be false when initial threshold is low"
-        );
-        assert_eq!(
-            response.threshold_used, 0.3,
-            "Expected threshold_used to remain at 0.3"
-        );
-    }
+    query_req.exclude_files = vec![top_file.clone()];
+    let second = client.query_codebase(query_req).await.unwrap();
+
+    // The excluded file is gone and the next-best file takes its place
+    assert_eq!(second.results.len(), 1);
+    assert_ne!(second.results[0].file_path, top_file);
 }
 
 #[tokio::test]
-async fn test_search_with_filters_language_filter() {
+async fn test_query_codebase_exclude_files_fetches_past_exclusions() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Create files in different languages
     let data_dir = temp_dir.path().join("data");
-    std::fs::create_dir(&data_dir).unwrap();
-    std::fs::write(data_dir.join("main.rs"), "fn main() { println!(\"Hello\"); }").unwrap();
-    std::fs::write(data_dir.join("main.py"), "def main(): print('Hello')").unwrap();
-    std::fs::write(data_dir.join("main.js"), "function main() { console.log('Hello'); }").unwrap();
+    std::fs::create_dir_all(data_dir.join("generated")).unwrap();
+    // More excluded matches than the initial overfetch covers
+    for i in 0..12 {
+        std::fs::write(
+            data_dir.join("generated").join(format!("auth_{}.rs", i)),
+            format!(
+                "fn authenticate_user_{}() {{ /* authentication logic */ }}",
+                i
+            ),
+        )
+        .unwrap();
+    }
+    std::fs::write(data_dir.join("config.rs"), "fn parse_config() {}").unwrap();
 
     let index_req = IndexRequest {
         path: data_dir.to_string_lossy().to_string(),
-        project: Some("lang-filter-test".to_string()),
+        project: Some("exclude-many-project".to_string()),
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
     client.index_codebase(index_req).await.unwrap();
 
-    // Search filtering by Rust language only
-    let request = AdvancedSearchRequest {
-        query: "main function".to_string(),
+    let query_req = QueryRequest {
+        query: "authentication".to_string(),
         path: None,
-        project: Some("lang-filter-test".to_string()),
-        limit: 10,
-        min_score: 0.3,
-        file_extensions: vec![],
-        languages: vec!["Rust".to_string()],
-        path_patterns: vec![],
+        project: Some("exclude-many-project".to_string()),
+        projects: vec![],
+        limit: 1,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec!["generated/**".to_string()],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
-    let result = client.search_with_filters(request).await;
-    assert!(result.is_ok());
-
-    let response = result.unwrap();
-    // All results should be Rust
-    for result in &response.results {
-        assert_eq!(
-            result.language, "Rust",
-            "Expected Rust language, got: {}",
-            result.language
-        );
-    }
+    let response = client.query_codebase(query_req).await.unwrap();
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(response.results[0].file_path, "config.rs");
 }
 
 #[tokio::test]
-async fn test_search_with_filters_path_pattern() {
-    let (client, temp_dir) = create_test_client().await;
+async fn test_query_codebase_include_signature() {
+    let (mut client, temp_dir) = create_test_client().await;
+    // Small fixed-line chunks so one chunk starts inside the method body
+    client.chunker = Arc::new(CodeChunker::new(crate::indexer::ChunkStrategy::FixedLines(
+        3,
+    )));
 
-    // Create nested directory structure
     let data_dir = temp_dir.path().join("data");
-    let src_dir = data_dir.join("src");
-    let tests_dir = data_dir.join("tests");
-    std::fs::create_dir_all(&src_dir).unwrap();
-    std::fs::create_dir_all(&tests_dir).unwrap();
-
-    std::fs::write(src_dir.join("lib.rs"), "pub fn add(a: i32, b: i32) -> i32 { a + b }").unwrap();
+    std::fs::create_dir(&data_dir).unwrap();
     std::fs::write(
-        tests_dir.join("test_lib.rs"),
-        "fn test_add() { assert_eq!(add(1, 2), 3); }",
+        data_dir.join("ledger.rs"),
+        r#"struct Ledger {
+    entries: Vec<u64>,
+}
+impl Ledger {
+    fn reconcile_balances(&self) -> u64 {
+        let mut total = 0;
+        for entry in &self.entries {
+            total += entry;
+        }
+        total
+    }
+}
+"#,
     )
     .unwrap();
 
     let index_req = IndexRequest {
         path: data_dir.to_string_lossy().to_string(),
-        project: Some("path-pattern-test".to_string()),
+        project: Some("signature-project".to_string()),
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
     client.index_codebase(index_req).await.unwrap();
 
-    // Search with path pattern for src only
-    let request = AdvancedSearchRequest {
-        query: "add function".to_string(),
+    let query_req = QueryRequest {
+        query: "total += entry".to_string(),
         path: None,
-        project: Some("path-pattern-test".to_string()),
+        project: Some("signature-project".to_string()),
+        projects: vec![],
         limit: 10,
-        min_score: 0.3,
-        file_extensions: vec![],
-        languages: vec![],
-        path_patterns: vec!["**/src/**".to_string()],
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: true,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
-    let result = client.search_with_filters(request).await;
-    assert!(result.is_ok());
+    let response = client.query_codebase(query_req).await.unwrap();
+
+    // Lines 7-9 sit inside the method body and get its signature prepended
+    let body = response
+        .results
+        .iter()
+        .find(|r| r.start_line == 7)
+        .expect("chunk inside the method body should match");
+    assert_eq!(
+        body.content,
+        "fn reconcile_balances(&self) -> u64 {\n---\n        for entry in &self.entries {\n            total += entry;\n        }"
+    );
 
-    let response = result.unwrap();
-    // All results should be from src directory
-    for result in &response.results {
-        assert!(
-            result.file_path.contains("src/") || result.file_path.starts_with("src/"),
-            "Expected path to contain src/, got: {}",
-            result.file_path
-        );
-    }
+    // Top-level code has no enclosing function and is returned unchanged
+    let top_level = response
+        .results
+        .iter()
+        .find(|r| r.start_line == 1)
+        .expect("top-level chunk should match");
+    assert!(top_level.content.starts_with("struct Ledger {"));
 }
 
 #[tokio::test]
-async fn test_search_with_filters_combined_filters() {
+async fn test_query_debug_is_stable_across_runs() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Create a complex directory structure
     let data_dir = temp_dir.path().join("data");
-    let src_dir = data_dir.join("src");
-    let lib_dir = data_dir.join("lib");
-    std::fs::create_dir_all(&src_dir).unwrap();
-    std::fs::create_dir_all(&lib_dir).unwrap();
-
-    // Rust files in src
+    std::fs::create_dir(&data_dir).unwrap();
     std::fs::write(
-        src_dir.join("handler.rs"),
-        "pub fn handle_request() -> Response { Response::ok() }",
+        data_dir.join("auth.rs"),
+        "fn authenticate_user(token: &str) -> bool { validate_token(token) }",
     )
     .unwrap();
-
-    // Python files in src
     std::fs::write(
-        src_dir.join("handler.py"),
-        "def handle_request(): return Response.ok()",
+        data_dir.join("session.rs"),
+        "fn refresh_session(user: &User) { renew_token(user) }",
     )
     .unwrap();
-
-    // Rust files in lib
     std::fs::write(
-        lib_dir.join("utils.rs"),
-        "pub fn handle_utils() -> String { String::new() }",
+        data_dir.join("math.rs"),
+        "fn add(a: i32, b: i32) -> i32 { a + b }",
     )
     .unwrap();
 
     let index_req = IndexRequest {
         path: data_dir.to_string_lossy().to_string(),
-        project: Some("combined-filter-test".to_string()),
+        project: Some("debug-project".to_string()),
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
     client.index_codebase(index_req).await.unwrap();
 
-    // Search with combined filters: Rust files in src directory only
+    let query_req = QueryRequest {
+        query: "token authentication".to_string(),
+        path: None,
+        project: Some("debug-project".to_string()),
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+
+    let first = client.query_debug(query_req.clone()).await.unwrap();
+    let second = client.query_debug(query_req).await.unwrap();
+
+    assert!(!first.results.is_empty());
+    assert_eq!(
+        serde_json::to_string(&first).unwrap(),
+        serde_json::to_string(&second).unwrap()
+    );
+
+    for (idx, breakdown) in first.results.iter().enumerate() {
+        assert_eq!(breakdown.rank, idx + 1);
+        assert!(breakdown.vector_rank.is_some());
+        assert!(breakdown.vector_distance.is_some());
+        assert_eq!(
+            breakdown.vector_rrf.is_some(),
+            breakdown.vector_rank.is_some()
+        );
+        assert_eq!(
+            breakdown.keyword_rrf.is_some(),
+            breakdown.keyword_rank.is_some()
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_explain_chunking_reports_strategy_per_file() {
+    let (client, temp_dir) = create_test_client().await;
+
+    let rust_file = temp_dir.path().join("lib.rs");
+    std::fs::write(
+        &rust_file,
+        "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n",
+    )
+    .unwrap();
+    let toml_file = temp_dir.path().join("settings.toml");
+    std::fs::write(&toml_file, "[server]\nport = 8080\n").unwrap();
+
+    let rust = client
+        .explain_chunking(&rust_file.to_string_lossy())
+        .unwrap();
+    assert_eq!(rust.applied.to_string(), "AST");
+    assert!(rust.reason.contains("Rust grammar available"));
+    assert_eq!(rust.file_path, "lib.rs");
+
+    let toml = client
+        .explain_chunking(&toml_file.to_string_lossy())
+        .unwrap();
+    assert_eq!(toml.applied.to_string(), "line fallback");
+    assert!(toml.reason.contains("no grammar"));
+    assert_eq!(toml.chunks, 1);
+}
+
+#[tokio::test]
+async fn test_query_reranks_candidates_with_cross_encoder() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.search.rerank_candidates = 10;
+    client.config = Arc::new(config);
+    client.reranker = Some(Arc::new(
+        Reranker::from_model_name(
+            crate::embedding::DEFAULT_RERANK_MODEL,
+            None,
+            crate::config::EmbeddingDevice::Cpu,
+        )
+        .unwrap(),
+    ));
+
+    index_project(
+        &client,
+        &temp_dir,
+        "rerank-project",
+        &[
+            (
+                "chart.rs",
+                "pub fn render_chart(values: &[f64]) -> String {\n    format!(\"{:?}\", values)\n}\n",
+            ),
+            (
+                "auth.rs",
+                "/// Hash a user's password with a random salt before it is stored\npub fn hash_password(password: &str, salt: &str) -> String {\n    format!(\"{}{}\", salt, password)\n}\n",
+            ),
+        ],
+    )
+    .await;
+
+    let request = QueryRequest {
+        query: "how are user passwords hashed before storing them?".to_string(),
+        path: None,
+        project: Some("rerank-project".to_string()),
+        projects: vec![],
+        limit: 1,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    let response = client.query_codebase(request).await.unwrap();
+
+    assert_eq!(response.results.len(), 1);
+    let top = &response.results[0];
+    assert!(top.file_path.ends_with("auth.rs"));
+    assert!((0.0..=1.0).contains(&top.score));
+    // The first-stage vector score is kept alongside the rerank score
+    assert!(top.vector_score > 0.0);
+}
+
+#[tokio::test]
+async fn test_query_returns_context_lines_around_results() {
+    let (client, temp_dir) = create_test_client().await;
+    let source: String = (0..3)
+        .map(|i| format!("// section {i}\npub fn handler_{i}() -> u32 {{\n    {i}\n}}\n\n"))
+        .collect();
+    let root = index_project(
+        &client,
+        &temp_dir,
+        "context-project",
+        &[("lib.rs", &source)],
+    )
+    .await;
+    let total_lines = source.lines().count();
+
+    let request = QueryRequest {
+        query: "handler".to_string(),
+        path: None,
+        project: Some("context-project".to_string()),
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: false,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 2,
+        include_git_commits: false,
+    };
+    let response = client.query_codebase(request.clone()).await.unwrap();
+    assert!(!response.results.is_empty());
+    for result in &response.results {
+        let context = result.context.as_ref().unwrap();
+        assert_eq!(
+            context.start_line,
+            result.start_line.saturating_sub(2).max(1)
+        );
+        assert_eq!(context.end_line, (result.end_line + 2).min(total_lines));
+        let expected: Vec<&str> = source
+            .lines()
+            .skip(context.start_line - 1)
+            .take(context.end_line - context.start_line + 1)
+            .collect();
+        assert_eq!(context.content, expected.join("\n"));
+    }
+
+    // A file deleted since indexing falls back to the stored chunk
+    std::fs::remove_file(Path::new(&root).join("lib.rs")).unwrap();
+    let response = client.query_codebase(request).await.unwrap();
+    assert!(!response.results.is_empty());
+    assert!(response.results.iter().all(|r| r.context.is_none()));
+}
+
+#[tokio::test]
+async fn test_query_path_filter_isolates_roots() {
+    let (client, temp_dir) = create_test_client().await;
+    let root_a = index_project(
+        &client,
+        &temp_dir,
+        "root-a",
+        &[(
+            "auth.rs",
+            "pub fn authenticate_user(token: &str) -> bool { true }",
+        )],
+    )
+    .await;
+    let root_b = index_project(
+        &client,
+        &temp_dir,
+        "root-b",
+        &[(
+            "auth.rs",
+            "pub fn authenticate_admin(token: &str) -> bool { false }",
+        )],
+    )
+    .await;
+
+    let query = |path: &str| QueryRequest {
+        query: "authenticate".to_string(),
+        path: Some(path.to_string()),
+        project: None,
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+
+    for root in [&root_a, &root_b] {
+        let response = client.query_codebase(query(root)).await.unwrap();
+        assert!(!response.results.is_empty());
+        assert!(
+            response
+                .results
+                .iter()
+                .all(|r| r.root_path.as_deref() == Some(root.as_str()))
+        );
+    }
+}
+
+// ===== search_with_filters Tests =====
+
+#[tokio::test]
+async fn test_search_with_filters_empty_index() {
+    let (client, _temp_dir) = create_test_client().await;
+
     let request = AdvancedSearchRequest {
-        query: "handle request".to_string(),
+        query: "test".to_string(),
         path: None,
-        project: Some("combined-filter-test".to_string()),
+        project: None,
         limit: 10,
-        min_score: 0.3,
+        min_score: 0.7,
         file_extensions: vec!["rs".to_string()],
         languages: vec!["Rust".to_string()],
-        path_patterns: vec!["**/src/**".to_string()],
+        path_patterns: vec!["src/**".to_string()],
+        symbol_kinds: vec![],
     };
 
     let result = client.search_with_filters(request).await;
     assert!(result.is_ok());
 
     let response = result.unwrap();
-    // All results should be Rust files in src directory
-    for result in &response.results {
-        assert!(
-            result.file_path.ends_with(".rs"),
-            "Expected .rs file, got: {}",
-            result.file_path
-        );
-        assert!(
-            result.file_path.contains("src/") || result.file_path.starts_with("src/"),
-            "Expected path to contain src/, got: {}",
-            result.file_path
-        );
-        assert_eq!(
-            result.language, "Rust",
-            "Expected Rust language, got: {}",
-            result.language
-        );
-    }
+    assert_eq!(response.results.len(), 0);
 }
 
 #[tokio::test]
-async fn test_search_with_filters_empty_query_validation() {
+async fn test_search_with_filters_validation_failure() {
     let (client, _temp_dir) = create_test_client().await;
 
     let request = AdvancedSearchRequest {
-        query: "   ".to_string(), // Empty/whitespace query
+        query: "test".to_string(),
         path: None,
         project: None,
         limit: 10,
         min_score: 0.7,
-        file_extensions: vec![],
+        file_extensions: vec!["".to_string()], // Invalid
         languages: vec![],
         path_patterns: vec![],
+        symbol_kinds: vec![],
     };
 
     let result = client.search_with_filters(request).await;
     assert!(result.is_err());
-    assert!(result.unwrap_err().to_string().contains("cannot be empty"));
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("file extension cannot be empty")
+    );
 }
 
 #[tokio::test]
-async fn test_search_with_filters_threshold_boundary_at_0_3() {
+async fn test_search_with_filters_with_data() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Index some data
+    // Create a directory structure for testing filters
     let data_dir = temp_dir.path().join("data");
-    std::fs::create_dir(&data_dir).unwrap();
-    std::fs::write(data_dir.join("test.rs"), "fn test_function() {}").unwrap();
+    let src_dir = data_dir.join("src");
+    std::fs::create_dir_all(&src_dir).unwrap();
+
+    // Create Rust file
+    std::fs::write(
+        src_dir.join("auth.rs"),
+        "fn authenticate_user(username: &str) -> bool { true }",
+    )
+    .unwrap();
+
+    // Create Python file
+    std::fs::write(
+        src_dir.join("auth.py"),
+        "def authenticate_user(username): return True",
+    )
+    .unwrap();
 
+    // Index the data
     let index_req = IndexRequest {
         path: data_dir.to_string_lossy().to_string(),
-        project: Some("boundary-test".to_string()),
+        project: Some("filter-test".to_string()),
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
     client.index_codebase(index_req).await.unwrap();
 
-    // Search with threshold at 0.3 - should NOT trigger adaptive lowering
+    // Search with file extension filter for Rust only
     let request = AdvancedSearchRequest {
-        query: "completely unrelated xyz abc 123".to_string(),
+        query: "authenticate user".to_string(),
         path: None,
-        project: Some("boundary-test".to_string()),
+        project: Some("filter-test".to_string()),
         limit: 10,
-        min_score: 0.3, // At the boundary, should not lower further
-        file_extensions: vec![],
+        min_score: 0.3,
+        file_extensions: vec!["rs".to_string()],
         languages: vec![],
         path_patterns: vec![],
+        symbol_kinds: vec![],
     };
 
     let result = client.search_with_filters(request).await;
     assert!(result.is_ok());
 
     let response = result.unwrap();
-    // Threshold should not be lowered below 0.3
-    assert!(
-        !response.threshold_lowered,
-        "Threshold should not be lowered when already at 0.3"
-    );
-    assert_eq!(response.threshold_used, 0.3);
+    // Should find results and all should be .rs files
+    for result in &response.results {
+        assert!(
+            result.file_path.ends_with(".rs"),
+            "Expected .rs file, got: {}",
+            result.file_path
+        );
+    }
 }
 
 #[tokio::test]
-async fn test_search_with_filters_multiple_extensions() {
+async fn test_search_with_filters_adaptive_threshold_lowering() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Create files with different extensions
+    // Index some data
     let data_dir = temp_dir.path().join("data");
     std::fs::create_dir(&data_dir).unwrap();
-    std::fs::write(data_dir.join("code.rs"), "fn rust_code() {}").unwrap();
-    std::fs::write(data_dir.join("code.ts"), "function tsCode() {}").unwrap();
-    std::fs::write(data_dir.join("code.py"), "def python_code(): pass").unwrap();
+    std::fs::write(
+        data_dir.join("code.rs"),
+        "fn process_data() { /* some logic here */ }",
+    )
+    .unwrap();
 
     let index_req = IndexRequest {
         path: data_dir.to_string_lossy().to_string(),
-        project: Some("multi-ext-test".to_string()),
+        project: Some("adaptive-test".to_string()),
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
     client.index_codebase(index_req).await.unwrap();
 
-    // Search filtering by multiple extensions
+    // Search with high threshold - should trigger adaptive lowering
     let request = AdvancedSearchRequest {
-        query: "code function".to_string(),
+        query: "process data function".to_string(),
         path: None,
-        project: Some("multi-ext-test".to_string()),
+        project: Some("adaptive-test".to_string()),
         limit: 10,
-        min_score: 0.3,
-        file_extensions: vec!["rs".to_string(), "ts".to_string()],
+        min_score: 0.9, // Very high threshold that will likely not match
+        file_extensions: vec![],
         languages: vec![],
         path_patterns: vec![],
+        symbol_kinds: vec![],
     };
 
     let result = client.search_with_filters(request).await;
     assert!(result.is_ok());
 
     let response = result.unwrap();
-    // All results should be .rs or .ts files (not .py)
-    for result in &response.results {
+    // If results were found, threshold should have been lowered
+    if !response.results.is_empty() {
         assert!(
-            result.file_path.ends_with(".rs") || result.file_path.ends_with(".ts"),
-            "Expected .rs or .ts file, got: {}",
-            result.file_path
+            response.threshold_lowered,
+            "Expected threshold_lowered to be true when results found with high initial threshold"
+        );
+        assert!(
+            response.threshold_used < 0.9,
+            "Expected threshold_used ({}) to be lower than initial 0.9",
+            response.threshold_used
         );
     }
 }
 
-// ===== get_statistics Tests =====
-
-#[tokio::test]
-async fn test_get_statistics_empty() {
-    let (client, _temp_dir) = create_test_client().await;
-
-    let result = client.get_statistics().await;
-    assert!(result.is_ok());
-
-    let response = result.unwrap();
-    assert_eq!(response.total_files, 0);
-    assert_eq!(response.total_chunks, 0);
-    assert_eq!(response.total_embeddings, 0);
-}
-
 #[tokio::test]
-async fn test_get_statistics_with_data() {
+async fn test_search_with_filters_no_adaptive_when_results_found() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Index some data
+    // Index data with highly relevant content
     let data_dir = temp_dir.path().join("data");
     std::fs::create_dir(&data_dir).unwrap();
-    std::fs::write(data_dir.join("test.rs"), "fn main() {}").unwrap();
+    std::fs::write(
+        data_dir.join("auth.rs"),
+        "fn authenticate_user_with_password(username: &str, password: &str) -> Result<User, AuthError> { authenticate(username, password) }",
+    )
+    .unwrap();
 
-    let request = IndexRequest {
+    let index_req = IndexRequest {
         path: data_dir.to_string_lossy().to_string(),
-        project: None,
+        project: Some("no-adaptive-test".to_string()),
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
-    client.index_codebase(request).await.unwrap();
-
-    // Get statistics
-    let result = client.get_statistics().await;
-    assert!(result.is_ok());
-
-    let response = result.unwrap();
-    assert!(response.total_files > 0);
-    assert!(response.total_chunks > 0);
-    assert!(response.total_embeddings > 0);
-}
-
-// ===== clear_index Tests =====
+    client.index_codebase(index_req).await.unwrap();
 
-#[tokio::test]
-async fn test_clear_index_empty() {
-    let (client, _temp_dir) = create_test_client().await;
+    // Search with low threshold - should find results without lowering
+    let request = AdvancedSearchRequest {
+        query: "authenticate user password".to_string(),
+        path: None,
+        project: Some("no-adaptive-test".to_string()),
+        limit: 10,
+        min_score: 0.3, // Low threshold
+        file_extensions: vec![],
+        languages: vec![],
+        path_patterns: vec![],
+        symbol_kinds: vec![],
+    };
 
-    let result = client.clear_index().await;
+    let result = client.search_with_filters(request).await;
     assert!(result.is_ok());
 
     let response = result.unwrap();
-    assert!(response.success);
+    if !response.results.is_empty() {
+        // Threshold should NOT have been lowered since 0.3 is already low
+        assert!(
+            !response.threshold_lowered,
+            "Expected threshold_lowered to be false when initial threshold is low"
+        );
+        assert_eq!(
+            response.threshold_used, 0.3,
+            "Expected threshold_used to remain at 0.3"
+        );
+    }
 }
 
 #[tokio::test]
-async fn test_clear_index_with_data() {
+async fn test_search_with_filters_language_filter() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Index some data
+    // Create files in different languages
     let data_dir = temp_dir.path().join("data");
     std::fs::create_dir(&data_dir).unwrap();
-    std::fs::write(data_dir.join("test.rs"), "fn main() {}").unwrap();
+    std::fs::write(data_dir.join("main.rs"), "fn main() { println!(\"Hello\"); }").unwrap();
+    std::fs::write(data_dir.join("main.py"), "def main(): print('Hello')").unwrap();
+    std::fs::write(data_dir.join("main.js"), "function main() { console.log('Hello'); }").unwrap();
 
-    let request = IndexRequest {
+    let index_req = IndexRequest {
         path: data_dir.to_string_lossy().to_string(),
-        project: None,
+        project: Some("lang-filter-test".to_string()),
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
-    client.index_codebase(request).await.unwrap();
+    client.index_codebase(index_req).await.unwrap();
 
-    // Clear the index
-    let result = client.clear_index().await;
+    // Search filtering by Rust language only
+    let request = AdvancedSearchRequest {
+        query: "main function".to_string(),
+        path: None,
+        project: Some("lang-filter-test".to_string()),
+        limit: 10,
+        min_score: 0.3,
+        file_extensions: vec![],
+        languages: vec!["Rust".to_string()],
+        path_patterns: vec![],
+        symbol_kinds: vec![],
+    };
+
+    let result = client.search_with_filters(request).await;
     assert!(result.is_ok());
 
     let response = result.unwrap();
-    assert!(response.success);
-    assert!(response.message.contains("Successfully cleared"));
-
-    // Verify it's empty
-    let stats = client.get_statistics().await.unwrap();
-    assert_eq!(stats.total_files, 0);
+    // All results should be Rust
+    for result in &response.results {
+        assert_eq!(
+            result.language, "Rust",
+            "Expected Rust language, got: {}",
+            result.language
+        );
+    }
 }
 
-// ===== search_git_history Tests =====
-
 #[tokio::test]
-async fn test_search_git_history_validation_failure() {
-    let (client, _temp_dir) = create_test_client().await;
+async fn test_search_with_filters_symbol_kind() {
+    let (client, temp_dir) = create_test_client().await;
 
-    let request = SearchGitHistoryRequest {
-        query: "  ".to_string(), // Empty query
-        path: ".".to_string(),
-        project: None,
-        branch: None,
-        max_commits: 10,
-        limit: 10,
-        min_score: 0.7,
-        author: None,
-        since: None,
-        until: None,
-        file_pattern: None,
-    };
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(
+        data_dir.join("shapes.rs"),
+        r#"
+pub struct Rectangle {
+    pub width: u32,
+    pub height: u32,
+}
 
-    let result = client.search_git_history(request).await;
-    assert!(result.is_err());
-    assert!(result.unwrap_err().to_string().contains("cannot be empty"));
+pub fn area(rect: &Rectangle) -> u32 {
+    rect.width * rect.height
 }
 
-#[tokio::test]
-async fn test_search_git_history_nonexistent_path() {
-    let (client, _temp_dir) = create_test_client().await;
+pub struct Circle {
+    pub radius: f64,
+}
 
-    let request = SearchGitHistoryRequest {
-        query: "test".to_string(),
-        path: "/nonexistent/path".to_string(),
-        project: None,
-        branch: None,
-        max_commits: 10,
+pub fn circumference(circle: &Circle) -> f64 {
+    2.0 * std::f64::consts::PI * circle.radius
+}
+"#,
+    )
+    .unwrap();
+
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: Some("symbol-kind-test".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
+
+    let request = AdvancedSearchRequest {
+        query: "rectangle width height".to_string(),
+        path: None,
+        project: Some("symbol-kind-test".to_string()),
         limit: 10,
-        min_score: 0.7,
-        author: None,
-        since: None,
-        until: None,
-        file_pattern: None,
+        min_score: 0.0,
+        file_extensions: vec![],
+        languages: vec![],
+        path_patterns: vec![],
+        symbol_kinds: vec![crate::relations::SymbolKind::Struct],
     };
 
-    let result = client.search_git_history(request).await;
-    assert!(result.is_err());
-    assert!(result.unwrap_err().to_string().contains("does not exist"));
+    let response = client.search_with_filters(request).await.unwrap();
+    assert!(
+        !response.results.is_empty(),
+        "Expected struct chunks to match"
+    );
+    for result in &response.results {
+        assert_eq!(
+            result.symbol_kind.as_deref(),
+            Some("struct"),
+            "Expected only struct chunks, got: {:?}",
+            result.symbol_kind
+        );
+    }
 }
 
-// ===== Integration Tests =====
-
 #[tokio::test]
-async fn test_full_workflow_index_query_clear() {
+async fn test_search_with_filters_path_pattern() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Step 1: Index
+    // Create nested directory structure
     let data_dir = temp_dir.path().join("data");
-    std::fs::create_dir(&data_dir).unwrap();
+    let src_dir = data_dir.join("src");
+    let tests_dir = data_dir.join("tests");
+    std::fs::create_dir_all(&src_dir).unwrap();
+    std::fs::create_dir_all(&tests_dir).unwrap();
+
+    std::fs::write(src_dir.join("lib.rs"), "pub fn add(a: i32, b: i32) -> i32 { a + b }").unwrap();
     std::fs::write(
-        data_dir.join("math.rs"),
-        "fn add(a: i32, b: i32) -> i32 { a + b }",
+        tests_dir.join("test_lib.rs"),
+        "fn test_add() { assert_eq!(add(1, 2), 3); }",
     )
     .unwrap();
 
     let index_req = IndexRequest {
         path: data_dir.to_string_lossy().to_string(),
-        project: Some("math-lib".to_string()),
+        project: Some("path-pattern-test".to_string()),
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
-    let index_resp = client.index_codebase(index_req).await.unwrap();
-    assert_eq!(index_resp.files_indexed, 1);
+    client.index_codebase(index_req).await.unwrap();
 
-    // Step 2: Query
-    let query_req = QueryRequest {
-        query: "addition function".to_string(),
+    // Search with path pattern for src only
+    let request = AdvancedSearchRequest {
+        query: "add function".to_string(),
         path: None,
-        project: Some("math-lib".to_string()),
-        limit: 5,
+        project: Some("path-pattern-test".to_string()),
+        limit: 10,
         min_score: 0.3,
-        hybrid: true,
+        file_extensions: vec![],
+        languages: vec![],
+        path_patterns: vec!["**/src/**".to_string()],
+        symbol_kinds: vec![],
     };
-    let query_resp = client.query_codebase(query_req).await.unwrap();
-    assert!(query_resp.results.len() > 0);
-
-    // Step 3: Statistics
-    let stats = client.get_statistics().await.unwrap();
-    assert!(stats.total_files > 0);
 
-    // Step 4: Clear
-    let clear_resp = client.clear_index().await.unwrap();
-    assert!(clear_resp.success);
+    let result = client.search_with_filters(request).await;
+    assert!(result.is_ok());
 
-    // Step 5: Verify empty
-    let stats_after = client.get_statistics().await.unwrap();
-    assert_eq!(stats_after.total_files, 0);
+    let response = result.unwrap();
+    // All results should be from src directory
+    for result in &response.results {
+        assert!(
+            result.file_path.contains("src/") || result.file_path.starts_with("src/"),
+            "Expected path to contain src/, got: {}",
+            result.file_path
+        );
+    }
 }
 
 #[tokio::test]
-async fn test_project_isolation() {
+async fn test_search_with_filters_combined_filters() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Index for project A
-    let data_dir_a = temp_dir.path().join("project_a");
-    std::fs::create_dir(&data_dir_a).unwrap();
-    std::fs::write(data_dir_a.join("a.rs"), "fn project_a() {}").unwrap();
+    // Create a complex directory structure
+    let data_dir = temp_dir.path().join("data");
+    let src_dir = data_dir.join("src");
+    let lib_dir = data_dir.join("lib");
+    std::fs::create_dir_all(&src_dir).unwrap();
+    std::fs::create_dir_all(&lib_dir).unwrap();
 
-    let req_a = IndexRequest {
-        path: data_dir_a.to_string_lossy().to_string(),
-        project: Some("project-a".to_string()),
-        include_patterns: vec![],
-        exclude_patterns: vec![],
-        max_file_size: 1024 * 1024,
-    };
-    client.index_codebase(req_a).await.unwrap();
+    // Rust files in src
+    std::fs::write(
+        src_dir.join("handler.rs"),
+        "pub fn handle_request() -> Response { Response::ok() }",
+    )
+    .unwrap();
 
-    // Index for project B
-    let data_dir_b = temp_dir.path().join("project_b");
-    std::fs::create_dir(&data_dir_b).unwrap();
-    std::fs::write(data_dir_b.join("b.rs"), "fn project_b() {}").unwrap();
+    // Python files in src
+    std::fs::write(
+        src_dir.join("handler.py"),
+        "def handle_request(): return Response.ok()",
+    )
+    .unwrap();
 
-    let req_b = IndexRequest {
-        path: data_dir_b.to_string_lossy().to_string(),
-        project: Some("project-b".to_string()),
+    // Rust files in lib
+    std::fs::write(
+        lib_dir.join("utils.rs"),
+        "pub fn handle_utils() -> String { String::new() }",
+    )
+    .unwrap();
+
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: Some("combined-filter-test".to_string()),
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 1024 * 1024,
+        model_name: None,
     };
-    client.index_codebase(req_b).await.unwrap();
+    client.index_codebase(index_req).await.unwrap();
 
-    // Query only project A
-    let query_a = QueryRequest {
-        query: "project".to_string(),
+    // Search with combined filters: Rust files in src directory only
+    let request = AdvancedSearchRequest {
+        query: "handle request".to_string(),
         path: None,
-        project: Some("project-a".to_string()),
+        project: Some("combined-filter-test".to_string()),
         limit: 10,
         min_score: 0.3,
-        hybrid: true,
+        file_extensions: vec!["rs".to_string()],
+        languages: vec!["Rust".to_string()],
+        path_patterns: vec!["**/src/**".to_string()],
+        symbol_kinds: vec![],
     };
-    let results_a = client.query_codebase(query_a).await.unwrap();
 
-    // Results should only be from project A
-    for result in results_a.results {
-        assert_eq!(result.project, Some("project-a".to_string()));
+    let result = client.search_with_filters(request).await;
+    assert!(result.is_ok());
+
+    let response = result.unwrap();
+    // All results should be Rust files in src directory
+    for result in &response.results {
+        assert!(
+            result.file_path.ends_with(".rs"),
+            "Expected .rs file, got: {}",
+            result.file_path
+        );
+        assert!(
+            result.file_path.contains("src/") || result.file_path.starts_with("src/"),
+            "Expected path to contain src/, got: {}",
+            result.file_path
+        );
+        assert_eq!(
+            result.language, "Rust",
+            "Expected Rust language, got: {}",
+            result.language
+        );
     }
 }
 
-// ===== Concurrent Indexing Lock Tests =====
+#[tokio::test]
+async fn test_search_with_filters_empty_query_validation() {
+    let (client, _temp_dir) = create_test_client().await;
+
+    let request = AdvancedSearchRequest {
+        query: "   ".to_string(), // Empty/whitespace query
+        path: None,
+        project: None,
+        limit: 10,
+        min_score: 0.7,
+        file_extensions: vec![],
+        languages: vec![],
+        path_patterns: vec![],
+        symbol_kinds: vec![],
+    };
+
+    let result = client.search_with_filters(request).await;
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("cannot be empty"));
+}
 
 #[tokio::test]
-async fn test_index_lock_prevents_duplicate_indexing() {
+async fn test_search_with_filters_threshold_boundary_at_0_3() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Create data to index
+    // Index some data
     let data_dir = temp_dir.path().join("data");
     std::fs::create_dir(&data_dir).unwrap();
-    std::fs::write(data_dir.join("test.rs"), "fn main() { println!(\"test\"); }").unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn test_function() {}").unwrap();
 
-    let path = data_dir.to_string_lossy().to_string();
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: Some("boundary-test".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
 
-    // Start first indexing
-    let lock_result1 = client.try_acquire_index_lock(&path).await.unwrap();
-    assert!(
-        matches!(lock_result1, IndexLockResult::Acquired(_)),
-        "First call should acquire the lock"
-    );
+    // Search with threshold at 0.3 - should NOT trigger adaptive lowering
+    let request = AdvancedSearchRequest {
+        query: "completely unrelated xyz abc 123".to_string(),
+        path: None,
+        project: Some("boundary-test".to_string()),
+        limit: 10,
+        min_score: 0.3, // At the boundary, should not lower further
+        file_extensions: vec![],
+        languages: vec![],
+        path_patterns: vec![],
+        symbol_kinds: vec![],
+    };
 
-    // Try to acquire lock again while first is still held
-    let lock_result2 = client.try_acquire_index_lock(&path).await.unwrap();
-    // With cross-process locking, this could be WaitForResult (same process, in-memory)
-    // or WaitForFilesystemLock (different process holding filesystem lock)
+    let result = client.search_with_filters(request).await;
+    assert!(result.is_ok());
+
+    let response = result.unwrap();
+    // Threshold should not be lowered below 0.3
     assert!(
-        matches!(lock_result2, IndexLockResult::WaitForResult(_) | IndexLockResult::WaitForFilesystemLock(_)),
-        "Second call should wait for the first operation (got: {:?})",
-        match &lock_result2 {
-            IndexLockResult::Acquired(_) => "Acquired",
-            IndexLockResult::WaitForResult(_) => "WaitForResult",
-            IndexLockResult::WaitForFilesystemLock(_) => "WaitForFilesystemLock",
-        }
+        !response.threshold_lowered,
+        "Threshold should not be lowered when already at 0.3"
     );
-
-    // Release the first lock by dropping it (simulate completion)
-    if let IndexLockResult::Acquired(guard) = lock_result1 {
-        // Broadcast a result before releasing
-        let result = IndexResponse {
-            mode: crate::types::IndexingMode::Full,
-            files_indexed: 1,
-            chunks_created: 1,
-            embeddings_generated: 1,
-            duration_ms: 100,
-            errors: vec![],
-            files_updated: 0,
-            files_removed: 0,
-        };
-        guard.broadcast_result(&result);
-        guard.release().await;
-    }
+    assert_eq!(response.threshold_used, 0.3);
 }
 
 #[tokio::test]
-async fn test_index_lock_waiters_receive_result() {
+async fn test_search_with_filters_multiple_extensions() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Create data to index
+    // Create files with different extensions
     let data_dir = temp_dir.path().join("data");
     std::fs::create_dir(&data_dir).unwrap();
-    std::fs::write(data_dir.join("test.rs"), "fn test() {}").unwrap();
+    std::fs::write(data_dir.join("code.rs"), "fn rust_code() {}").unwrap();
+    std::fs::write(data_dir.join("code.ts"), "function tsCode() {}").unwrap();
+    std::fs::write(data_dir.join("code.py"), "def python_code(): pass").unwrap();
 
-    let path = data_dir.to_string_lossy().to_string();
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: Some("multi-ext-test".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
 
-    // Acquire the lock first
-    let lock_result = client.try_acquire_index_lock(&path).await.unwrap();
-    let guard = match lock_result {
-        IndexLockResult::Acquired(g) => g,
-        _ => panic!("Expected to acquire lock"),
+    // Search filtering by multiple extensions
+    let request = AdvancedSearchRequest {
+        query: "code function".to_string(),
+        path: None,
+        project: Some("multi-ext-test".to_string()),
+        limit: 10,
+        min_score: 0.3,
+        file_extensions: vec!["rs".to_string(), "ts".to_string()],
+        languages: vec![],
+        path_patterns: vec![],
+        symbol_kinds: vec![],
     };
 
-    // Try to get a waiter - with filesystem locking, this returns WaitForFilesystemLock
-    // since the filesystem lock is held by the first guard
-    let lock_result2 = client.try_acquire_index_lock(&path).await.unwrap();
+    let result = client.search_with_filters(request).await;
+    assert!(result.is_ok());
 
-    match lock_result2 {
-        IndexLockResult::WaitForFilesystemLock(_) => {
-            // Expected behavior with cross-process filesystem locking
+    let response = result.unwrap();
+    // All results should be .rs or .ts files (not .py)
+    for result in &response.results {
+        assert!(
+            result.file_path.ends_with(".rs") || result.file_path.ends_with(".ts"),
+            "Expected .rs or .ts file, got: {}",
+            result.file_path
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_binary_metadata_is_findable_by_filename() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.indexing.index_binary_metadata = true;
+    client.config = Arc::new(config);
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("main.rs"), "fn main() { println!(\"Hello\"); }").unwrap();
+    std::fs::write(data_dir.join("logo.png"), vec![0x00; 256]).unwrap();
+
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    let response = client.index_codebase(index_req).await.unwrap();
+    assert_eq!(response.files_indexed, 2);
+
+    let request = AdvancedSearchRequest {
+        query: "logo.png".to_string(),
+        path: None,
+        project: None,
+        limit: 10,
+        min_score: 0.0,
+        file_extensions: vec![],
+        languages: vec![],
+        path_patterns: vec![],
+        symbol_kinds: vec![],
+    };
+    let response = client.search_with_filters(request).await.unwrap();
+
+    // Found by its name alone, ahead of the source file
+    let result = response.results.first().expect("the binary file is found");
+    assert!(result.file_path.ends_with("logo.png"));
+    assert!(result.binary);
+    assert!(result.content.is_empty());
+    assert_eq!(result.file_size, Some(256));
+}
+
+#[tokio::test]
+async fn test_public_symbols_only_skips_private_files() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.indexing.public_symbols_only = true;
+    client.config = Arc::new(config);
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(
+        data_dir.join("internal.rs"),
+        "fn helper() -> u32 { 1 }\nfn other_helper() -> u32 { 2 }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        data_dir.join("api.rs"),
+        "pub fn public_api() -> u32 { 3 }\n",
+    )
+    .unwrap();
+
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    let response = client.index_codebase(index_req).await.unwrap();
+    assert_eq!(response.files_indexed, 1);
+
+    let request = AdvancedSearchRequest {
+        query: "helper public_api".to_string(),
+        path: None,
+        project: None,
+        limit: 10,
+        min_score: 0.0,
+        file_extensions: vec![],
+        languages: vec![],
+        path_patterns: vec![],
+        symbol_kinds: vec![],
+    };
+    let response = client.search_with_filters(request).await.unwrap();
+    assert!(!response.results.is_empty());
+    assert!(
+        response
+            .results
+            .iter()
+            .all(|r| r.file_path.ends_with("api.rs"))
+    );
+}
+
+// ===== get_statistics Tests =====
+
+#[tokio::test]
+async fn test_get_statistics_empty() {
+    let (client, _temp_dir) = create_test_client().await;
+
+    let result = client.get_statistics().await;
+    assert!(result.is_ok());
+
+    let response = result.unwrap();
+    assert_eq!(response.total_files, 0);
+    assert_eq!(response.total_chunks, 0);
+    assert_eq!(response.total_embeddings, 0);
+}
+
+#[tokio::test]
+async fn test_get_statistics_with_data() {
+    let (client, temp_dir) = create_test_client().await;
+
+    // Index some data
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn main() {}").unwrap();
+
+    let request = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(request).await.unwrap();
+
+    // Get statistics
+    let result = client.get_statistics().await;
+    assert!(result.is_ok());
+
+    let response = result.unwrap();
+    assert!(response.total_files > 0);
+    assert!(response.total_chunks > 0);
+    assert!(response.total_embeddings > 0);
+}
+
+#[tokio::test]
+async fn test_get_chunks_for_file_returns_chunks_in_order() {
+    let (client, temp_dir) = create_test_client().await;
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(
+        data_dir.join("lib.rs"),
+        "fn first() {}\n\nfn second() {}\n\nfn third() {}\n",
+    )
+    .unwrap();
+    std::fs::write(data_dir.join("other.rs"), "fn other() {}").unwrap();
+
+    let request = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: Some("test-project".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(request).await.unwrap();
+
+    let chunks = client.get_chunks_for_file("lib.rs", None).await.unwrap();
+    let starts: Vec<usize> = chunks.iter().map(|c| c.start_line).collect();
+    assert_eq!(starts, [1, 3, 5]);
+    assert!(
+        chunks
+            .iter()
+            .all(|c| c.file_path == "lib.rs" && c.score == 1.0)
+    );
+    assert_eq!(chunks[1].content, "fn second() {}");
+    let root = chunks[1]
+        .root_path
+        .as_deref()
+        .expect("chunks carry their root");
+    assert_eq!(chunks[1].chunk_id, format!("{}/lib.rs:3", root));
+
+    let in_project = client
+        .get_chunks_for_file("lib.rs", Some("test-project".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(in_project.len(), 3);
+    let elsewhere = client
+        .get_chunks_for_file("lib.rs", Some("other-project".to_string()))
+        .await
+        .unwrap();
+    assert!(elsewhere.is_empty());
+}
+
+// ===== Phase span Tests =====
+
+/// Layer that records the name of every span created
+struct SpanNameRecorder(Arc<std::sync::Mutex<Vec<String>>>);
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanNameRecorder {
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        _id: &tracing::span::Id,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(attrs.metadata().name().to_string());
+    }
+}
+
+#[tokio::test]
+async fn test_indexing_emits_phase_spans_when_enabled() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.observability.phase_spans = true;
+    client.config = Arc::new(config);
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(
+        data_dir.join("test.rs"),
+        "fn main() { println!(\"spans\"); }",
+    )
+    .unwrap();
+
+    let span_names = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let subscriber = tracing_subscriber::registry().with(SpanNameRecorder(span_names.clone()));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let request = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(request).await.unwrap();
+
+    let names = span_names.lock().unwrap().clone();
+    for phase in ["walk", "chunk", "embed", "store"] {
+        assert!(
+            names.iter().any(|name| name == phase),
+            "Expected a '{}' span, got: {:?}",
+            phase,
+            names
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_indexing_emits_no_phase_spans_by_default() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let (client, temp_dir) = create_test_client().await;
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn main() {}").unwrap();
+
+    let span_names = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let subscriber = tracing_subscriber::registry().with(SpanNameRecorder(span_names.clone()));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let request = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(request).await.unwrap();
+
+    let names = span_names.lock().unwrap().clone();
+    assert!(
+        !names.iter().any(|name| name == "embed"),
+        "Expected no 'embed' span, got: {:?}",
+        names
+    );
+}
+
+// ===== clear_index Tests =====
+
+#[tokio::test]
+async fn test_clear_index_empty() {
+    let (client, _temp_dir) = create_test_client().await;
+
+    let result = client.clear_index().await;
+    assert!(result.is_ok());
+
+    let response = result.unwrap();
+    assert!(response.success);
+}
+
+#[tokio::test]
+async fn test_clear_index_with_data() {
+    let (client, temp_dir) = create_test_client().await;
+
+    // Index some data
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn main() {}").unwrap();
+
+    let request = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(request).await.unwrap();
+
+    // Clear the index
+    let result = client.clear_index().await;
+    assert!(result.is_ok());
+
+    let response = result.unwrap();
+    assert!(response.success);
+    assert!(response.message.contains("Successfully cleared"));
+
+    // Verify it's empty
+    let stats = client.get_statistics().await.unwrap();
+    assert_eq!(stats.total_files, 0);
+}
+
+// ===== remove_project Tests =====
+
+/// Index `files` under `temp_dir/name` as project `name`, returning the normalized root
+async fn index_project(
+    client: &RagClient,
+    temp_dir: &TempDir,
+    name: &str,
+    files: &[(&str, &str)],
+) -> String {
+    let data_dir = temp_dir.path().join(name);
+    std::fs::create_dir(&data_dir).unwrap();
+    for (file, content) in files {
+        std::fs::write(data_dir.join(file), content).unwrap();
+    }
+
+    let path = data_dir.to_string_lossy().to_string();
+    client
+        .index_codebase(IndexRequest {
+            path: path.clone(),
+            project: Some(name.to_string()),
+            include_patterns: vec![],
+            exclude_patterns: vec![],
+            max_file_size: 1024 * 1024,
+            model_name: None,
+        })
+        .await
+        .unwrap();
+    RagClient::normalize_path(&path).unwrap()
+}
+
+fn bm25_dirs(temp_dir: &TempDir) -> usize {
+    std::fs::read_dir(temp_dir.path().join("db"))
+        .unwrap()
+        .filter(|e| {
+            e.as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with("bm25_")
+        })
+        .count()
+}
+
+#[tokio::test]
+async fn test_remove_project_keeps_other_projects() {
+    let (client, temp_dir) = create_test_client().await;
+    let api_root = index_project(
+        &client,
+        &temp_dir,
+        "api",
+        &[
+            ("main.rs", "fn main() {\n    serve();\n}\n"),
+            ("serve.rs", "fn serve() {\n    listen();\n}\n"),
+        ],
+    )
+    .await;
+    let web_root = index_project(
+        &client,
+        &temp_dir,
+        "web",
+        &[("app.rs", "fn render() {\n    draw();\n}\n")],
+    )
+    .await;
+    assert_eq!(bm25_dirs(&temp_dir), 2);
+
+    let response = client.remove_project("api").await.unwrap();
+    assert!(response.chunks_removed >= 2);
+    assert_eq!(response.files_removed, 2);
+    assert_eq!(response.roots_removed, [api_root.clone()]);
+
+    let projects = client.list_projects().await.unwrap();
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].name, "web");
+    assert_eq!(bm25_dirs(&temp_dir), 1);
+
+    let cache = client.hash_cache.read().await;
+    assert!(!cache.roots.contains_key(&api_root));
+    assert!(cache.roots.contains_key(&web_root));
+}
+
+#[tokio::test]
+async fn test_remove_unknown_project_is_a_no_op() {
+    let (client, temp_dir) = create_test_client().await;
+    index_project(&client, &temp_dir, "api", &[("main.rs", "fn main() {}\n")]).await;
+
+    let response = client.remove_project("missing").await.unwrap();
+    assert_eq!(response, RemoveProjectResponse::default());
+    assert_eq!(client.list_projects().await.unwrap().len(), 1);
+}
+
+fn references_request(file_path: &Path, line: usize) -> FindReferencesRequest {
+    FindReferencesRequest {
+        file_path: file_path.to_string_lossy().to_string(),
+        line,
+        column: 0,
+        limit: 100,
+        project: None,
+        include_definition: false,
+    }
+}
+
+#[tokio::test]
+async fn test_find_references_across_indexed_files() {
+    let (client, temp_dir) = create_test_client().await;
+    let root = index_project(
+        &client,
+        &temp_dir,
+        "app",
+        &[
+            ("util.rs", "fn helper() {}\n"),
+            ("main.rs", "fn main() {\n    helper();\n}\n"),
+        ],
+    )
+    .await;
+
+    let util = Path::new(&root).join("util.rs");
+    let response = client
+        .find_references(references_request(&util, 1))
+        .await
+        .unwrap();
+
+    assert_eq!(response.symbol_name.as_deref(), Some("helper"));
+    assert!(
+        response
+            .references
+            .iter()
+            .any(|r| r.file_path == "main.rs" && r.start_line == 2),
+        "got: {:?}",
+        response.references
+    );
+}
+
+#[tokio::test]
+async fn test_find_references_after_incremental_update() {
+    let (client, temp_dir) = create_test_client().await;
+    let root = index_project(
+        &client,
+        &temp_dir,
+        "app",
+        &[
+            ("util.rs", "fn helper() {}\n"),
+            ("main.rs", "fn main() {\n    helper();\n}\n"),
+        ],
+    )
+    .await;
+
+    // Moving the definition changes its line, which unchanged callers refer to
+    let util = Path::new(&root).join("util.rs");
+    std::fs::write(&util, "// Helpers\n\nfn helper() {}\n").unwrap();
+    client
+        .index_codebase(IndexRequest {
+            path: root.clone(),
+            project: Some("app".to_string()),
+            include_patterns: vec![],
+            exclude_patterns: vec![],
+            max_file_size: 1024 * 1024,
+            model_name: None,
+        })
+        .await
+        .unwrap();
+
+    let response = client
+        .find_references(references_request(&util, 3))
+        .await
+        .unwrap();
+    assert!(
+        response.references.iter().any(|r| r.file_path == "main.rs"),
+        "got: {:?}",
+        response.references
+    );
+
+    let stats = client.relations_store.get_stats().await.unwrap();
+    assert_eq!(stats.definition_count, 2);
+}
+
+#[tokio::test]
+async fn test_new_file_references_resolve_against_stored_definitions() {
+    let (client, temp_dir) = create_test_client().await;
+    let root = index_project(
+        &client,
+        &temp_dir,
+        "app",
+        &[("util.rs", "fn helper() {}\n")],
+    )
+    .await;
+
+    // util.rs is unchanged and not read again; its definition comes from the store
+    std::fs::write(
+        Path::new(&root).join("main.rs"),
+        "fn main() {\n    helper();\n}\n",
+    )
+    .unwrap();
+    client
+        .index_codebase(IndexRequest {
+            path: root.clone(),
+            project: Some("app".to_string()),
+            include_patterns: vec![],
+            exclude_patterns: vec![],
+            max_file_size: 1024 * 1024,
+            model_name: None,
+        })
+        .await
+        .unwrap();
+
+    let response = client
+        .find_references(references_request(&Path::new(&root).join("util.rs"), 1))
+        .await
+        .unwrap();
+    assert!(
+        response
+            .references
+            .iter()
+            .any(|r| r.file_path == "main.rs" && r.start_line == 2),
+        "got: {:?}",
+        response.references
+    );
+}
+
+fn call_graph_request(file_path: &Path, line: usize, depth: usize) -> GetCallGraphRequest {
+    GetCallGraphRequest {
+        file_path: file_path.to_string_lossy().to_string(),
+        line,
+        column: 0,
+        depth,
+        project: None,
+        include_callers: true,
+        include_callees: true,
+    }
+}
+
+/// Names of a call graph, with each node's children in brackets
+fn call_tree(nodes: &[crate::relations::CallGraphNode]) -> String {
+    nodes
+        .iter()
+        .map(|node| {
+            if node.children.is_empty() {
+                node.name.clone()
+            } else {
+                format!("{}[{}]", node.name, call_tree(&node.children))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[tokio::test]
+async fn test_call_graph_follows_depth() {
+    let (client, temp_dir) = create_test_client().await;
+    let file = temp_dir.path().join("chain.rs");
+    std::fs::write(
+        &file,
+        "fn first() {\n    second();\n}\n\nfn second() {\n    third();\n}\n\nfn third() {}\n",
+    )
+    .unwrap();
+
+    let response = client
+        .get_call_graph(call_graph_request(&file, 1, 2))
+        .await
+        .unwrap();
+    assert_eq!(call_tree(&response.callees), "second[third]");
+
+    let response = client
+        .get_call_graph(call_graph_request(&file, 1, 1))
+        .await
+        .unwrap();
+    assert_eq!(call_tree(&response.callees), "second");
+
+    let response = client
+        .get_call_graph(call_graph_request(&file, 9, 2))
+        .await
+        .unwrap();
+    assert_eq!(call_tree(&response.callers), "second[first]");
+}
+
+#[tokio::test]
+async fn test_call_graph_stops_at_cycles() {
+    let (client, temp_dir) = create_test_client().await;
+    let file = temp_dir.path().join("cycle.rs");
+    std::fs::write(
+        &file,
+        "fn ping() {\n    pong();\n}\n\nfn pong() {\n    ping();\n}\n",
+    )
+    .unwrap();
+
+    let response = client
+        .get_call_graph(call_graph_request(&file, 1, 10))
+        .await
+        .unwrap();
+    assert_eq!(call_tree(&response.callees), "pong[ping]");
+    assert_eq!(call_tree(&response.callers), "pong[ping]");
+}
+
+#[tokio::test]
+async fn test_list_symbols_outlines_file() {
+    let (client, temp_dir) = create_test_client().await;
+    let file = temp_dir.path().join("shapes.rs");
+    std::fs::write(
+        &file,
+        "struct Circle {\n    r: f64,\n}\n\nimpl Circle {\n    fn area(&self) -> f64 {\n        self.r * self.r\n    }\n}\n\nfn unit() -> Circle {\n    Circle { r: 1.0 }\n}\n",
+    )
+    .unwrap();
+
+    let symbols = client
+        .list_symbols(&file.to_string_lossy(), None)
+        .await
+        .unwrap();
+    let outline: Vec<(&str, usize, usize)> = symbols
+        .iter()
+        .map(|s| (s.name.as_str(), s.start_line, s.end_line))
+        .collect();
+    // The struct, its impl block, the method and the function
+    assert_eq!(
+        outline,
+        [
+            ("Circle", 1, 3),
+            ("Circle", 5, 9),
+            ("area", 6, 8),
+            ("unit", 11, 13)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_find_symbol_lists_definitions_across_files() {
+    let (client, temp_dir) = create_test_client().await;
+    index_project(
+        &client,
+        &temp_dir,
+        "app",
+        &[
+            ("config.rs", "fn parse_config() {}\n\nfn load() {}\n"),
+            (
+                "legacy.rs",
+                "// Kept for old callers\nfn parse_config() -> bool {\n    true\n}\n",
+            ),
+            ("main.rs", "fn main() {\n    parse_config();\n}\n"),
+        ],
+    )
+    .await;
+    index_project(
+        &client,
+        &temp_dir,
+        "tools",
+        &[("cli.rs", "fn parse_config() {}\n")],
+    )
+    .await;
+
+    let found = |symbols: Vec<crate::relations::SymbolInfo>| -> Vec<(String, usize)> {
+        symbols
+            .into_iter()
+            .map(|s| (s.file_path, s.start_line))
+            .collect()
+    };
+
+    // Call sites and near misses are not definitions
+    let symbols = client
+        .find_symbol("parse_config", Some("app".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        found(symbols),
+        [("config.rs".to_string(), 1), ("legacy.rs".to_string(), 2)]
+    );
+
+    let symbols = client.find_symbol("parse_config", None).await.unwrap();
+    assert_eq!(symbols.len(), 3);
+
+    assert!(client.find_symbol("parse", None).await.unwrap().is_empty());
+}
+
+/// Register a fake in-flight indexing operation for `path`
+async fn register_active_indexing_op(
+    client: &RagClient,
+    path: &str,
+) -> Arc<std::sync::atomic::AtomicBool> {
+    let (result_tx, _) = broadcast::channel(1);
+    let active = Arc::new(std::sync::atomic::AtomicBool::new(true));
+    client.indexing_ops.write().await.insert(
+        path.to_string(),
+        IndexingOperation {
+            result_tx,
+            active: active.clone(),
+            started_at: Instant::now(),
+        },
+    );
+    active
+}
+
+#[tokio::test]
+async fn test_clear_index_rejected_while_indexing() {
+    let (client, _temp_dir) = create_test_client().await;
+    let active = register_active_indexing_op(&client, "/fake/project").await;
+
+    let result = client.clear_index().await;
+    let err = result.expect_err("clear should be rejected while indexing");
+    let message = format!("{:#}", err);
+    assert!(message.contains("indexing in progress"), "got: {}", message);
+    assert!(message.contains("/fake/project"), "got: {}", message);
+
+    // Once the operation finishes, clearing succeeds
+    active.store(false, std::sync::atomic::Ordering::Release);
+    let response = client.clear_index().await.unwrap();
+    assert!(response.success);
+}
+
+#[tokio::test]
+async fn test_clear_index_waits_for_indexing_to_finish() {
+    let (mut client, _temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.indexing.clear_wait_timeout_secs = 10;
+    client.config = Arc::new(config);
+
+    let active = register_active_indexing_op(&client, "/fake/project").await;
+
+    let finisher = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        active.store(false, std::sync::atomic::Ordering::Release);
+    });
+
+    let response = client.clear_index().await.unwrap();
+    assert!(response.success);
+    finisher.await.unwrap();
+}
+
+// ===== search_git_history Tests =====
+
+#[tokio::test]
+async fn test_search_git_history_validation_failure() {
+    let (client, _temp_dir) = create_test_client().await;
+
+    let request = SearchGitHistoryRequest {
+        query: "  ".to_string(), // Empty query
+        path: ".".to_string(),
+        project: None,
+        branch: None,
+        max_commits: 10,
+        limit: 10,
+        min_score: 0.7,
+        author: None,
+        since: None,
+        until: None,
+        file_pattern: None,
+        kind: GitEntryKind::Commit,
+        reset: false,
+    };
+
+    let result = client.search_git_history(request).await;
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("cannot be empty"));
+}
+
+#[tokio::test]
+async fn test_search_git_history_nonexistent_path() {
+    let (client, _temp_dir) = create_test_client().await;
+
+    let request = SearchGitHistoryRequest {
+        query: "test".to_string(),
+        path: "/nonexistent/path".to_string(),
+        project: None,
+        branch: None,
+        max_commits: 10,
+        limit: 10,
+        min_score: 0.7,
+        author: None,
+        since: None,
+        until: None,
+        file_pattern: None,
+        kind: GitEntryKind::Commit,
+        reset: false,
+    };
+
+    let result = client.search_git_history(request).await;
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("does not exist"));
+}
+
+// ===== Integration Tests =====
+
+#[tokio::test]
+async fn test_full_workflow_index_query_clear() {
+    let (client, temp_dir) = create_test_client().await;
+
+    // Step 1: Index
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(
+        data_dir.join("math.rs"),
+        "fn add(a: i32, b: i32) -> i32 { a + b }",
+    )
+    .unwrap();
+
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: Some("math-lib".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    let index_resp = client.index_codebase(index_req).await.unwrap();
+    assert_eq!(index_resp.files_indexed, 1);
+
+    // Step 2: Query
+    let query_req = QueryRequest {
+        query: "addition function".to_string(),
+        path: None,
+        project: Some("math-lib".to_string()),
+        projects: vec![],
+        limit: 5,
+        offset: 0,
+        min_score: 0.3,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    let query_resp = client.query_codebase(query_req).await.unwrap();
+    assert!(query_resp.results.len() > 0);
+
+    // Step 3: Statistics
+    let stats = client.get_statistics().await.unwrap();
+    assert!(stats.total_files > 0);
+
+    // Step 4: Clear
+    let clear_resp = client.clear_index().await.unwrap();
+    assert!(clear_resp.success);
+
+    // Step 5: Verify empty
+    let stats_after = client.get_statistics().await.unwrap();
+    assert_eq!(stats_after.total_files, 0);
+}
+
+#[tokio::test]
+async fn test_project_isolation() {
+    let (client, temp_dir) = create_test_client().await;
+
+    // Index for project A
+    let data_dir_a = temp_dir.path().join("project_a");
+    std::fs::create_dir(&data_dir_a).unwrap();
+    std::fs::write(data_dir_a.join("a.rs"), "fn project_a() {}").unwrap();
+
+    let req_a = IndexRequest {
+        path: data_dir_a.to_string_lossy().to_string(),
+        project: Some("project-a".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(req_a).await.unwrap();
+
+    // Index for project B
+    let data_dir_b = temp_dir.path().join("project_b");
+    std::fs::create_dir(&data_dir_b).unwrap();
+    std::fs::write(data_dir_b.join("b.rs"), "fn project_b() {}").unwrap();
+
+    let req_b = IndexRequest {
+        path: data_dir_b.to_string_lossy().to_string(),
+        project: Some("project-b".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(req_b).await.unwrap();
+
+    // Query only project A
+    let query_a = QueryRequest {
+        query: "project".to_string(),
+        path: None,
+        project: Some("project-a".to_string()),
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.3,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    let results_a = client.query_codebase(query_a).await.unwrap();
+
+    // Results should only be from project A
+    for result in results_a.results {
+        assert_eq!(result.project, Some("project-a".to_string()));
+    }
+}
+
+#[tokio::test]
+async fn test_per_project_embedding_models() {
+    let (client, temp_dir) = create_test_client().await;
+
+    // Project A uses the default model
+    let data_dir_a = temp_dir.path().join("model_a");
+    std::fs::create_dir(&data_dir_a).unwrap();
+    std::fs::write(
+        data_dir_a.join("auth.rs"),
+        "fn authenticate_user(password: &str) -> bool { !password.is_empty() }",
+    )
+    .unwrap();
+
+    let req_a = IndexRequest {
+        path: data_dir_a.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(req_a).await.unwrap();
+
+    // Project B uses a different model
+    let data_dir_b = temp_dir.path().join("model_b");
+    std::fs::create_dir(&data_dir_b).unwrap();
+    std::fs::write(
+        data_dir_b.join("parser.rs"),
+        "fn parse_config(input: &str) -> Vec<String> { input.lines().map(String::from).collect() }",
+    )
+    .unwrap();
+
+    let req_b = IndexRequest {
+        path: data_dir_b.to_string_lossy().to_string(),
+        project: Some("parser".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: Some("BAAI/bge-small-en-v1.5".to_string()),
+    };
+    client.index_codebase(req_b).await.unwrap();
+
+    // Each root records the model it was indexed with
+    {
+        let cache = client.hash_cache.read().await;
+        let root_a = RagClient::normalize_path(&data_dir_a.to_string_lossy()).unwrap();
+        let root_b = RagClient::normalize_path(&data_dir_b.to_string_lossy()).unwrap();
+        assert_eq!(cache.root_model(&root_a), None);
+        assert_eq!(cache.root_model(&root_b), Some("BAAI/bge-small-en-v1.5"));
+    }
+
+    // Querying each project by path uses its own model and table
+    let query = |path: &std::path::Path| QueryRequest {
+        query: "function".to_string(),
+        path: Some(path.to_string_lossy().to_string()),
+        project: None,
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: false,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+
+    let results_a = client.query_codebase(query(&data_dir_a)).await.unwrap();
+    assert!(!results_a.results.is_empty());
+    assert!(
+        results_a
+            .results
+            .iter()
+            .all(|r| r.file_path.ends_with("auth.rs"))
+    );
+
+    let results_b = client.query_codebase(query(&data_dir_b)).await.unwrap();
+    assert!(!results_b.results.is_empty());
+    assert!(
+        results_b
+            .results
+            .iter()
+            .all(|r| r.file_path.ends_with("parser.rs"))
+    );
+
+    // Unscoped queries search both models' tables
+    let unscoped = QueryRequest {
+        path: None,
+        ..query(&data_dir_a)
+    };
+    let results = client.query_codebase(unscoped).await.unwrap();
+    assert!(
+        results
+            .results
+            .iter()
+            .any(|r| r.file_path.ends_with("auth.rs"))
+    );
+    assert!(
+        results
+            .results
+            .iter()
+            .any(|r| r.file_path.ends_with("parser.rs"))
+    );
+
+    // A project filter finds the project in the other model's table
+    let by_project = QueryRequest {
+        path: None,
+        project: Some("parser".to_string()),
+        ..query(&data_dir_a)
+    };
+    let results = client.query_codebase(by_project).await.unwrap();
+    assert!(!results.results.is_empty());
+    assert!(
+        results
+            .results
+            .iter()
+            .all(|r| r.file_path.ends_with("parser.rs"))
+    );
+}
+
+#[tokio::test]
+async fn test_model_change_requires_reindex() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut config = Config::default();
+    config.vector_db.lancedb_path = temp_dir.path().join("db");
+    config.cache.hash_cache_path = temp_dir.path().join("cache.json");
+    config.cache.git_cache_path = temp_dir.path().join("git_cache.json");
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("auth.rs"), "fn authenticate() {}").unwrap();
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+
+    let client = RagClient::with_config(config.clone()).await.unwrap();
+    assert_eq!(client.embedding_dimension(), 384);
+    client.index_codebase(index_req.clone()).await.unwrap();
+    drop(client);
+
+    // A 768-dimension model can't use the 384-dimension index
+    config.embedding.model_name = "BAAI/bge-base-en-v1.5".to_string();
+    let error = match RagClient::with_config(config.clone()).await {
+        Ok(_) => panic!("the changed model should be refused"),
+        Err(e) => e,
+    };
+    assert!(matches!(
+        error.downcast_ref::<RagError>(),
+        Some(RagError::Embedding(EmbeddingError::IndexModelChanged {
+            indexed_dimension: 384,
+            dimension: 768,
+            ..
+        }))
+    ));
+
+    // With auto reindex the old index is cleared and the codebase re-indexed in full
+    config.vector_db.auto_reindex_on_model_change = true;
+    let client = RagClient::with_config(config.clone()).await.unwrap();
+    assert_eq!(client.embedding_dimension(), 768);
+    assert!(client.hash_cache.read().await.roots.is_empty());
+
+    let response = client.index_codebase(index_req).await.unwrap();
+    assert_eq!(response.mode, IndexingMode::Full);
+    assert_eq!(response.files_indexed, 1);
+    drop(client);
+
+    // The new model is recorded, so the next start needs no clearing
+    config.vector_db.auto_reindex_on_model_change = false;
+    let client = RagClient::with_config(config).await.unwrap();
+    assert_eq!(client.hash_cache.read().await.roots.len(), 1);
+}
+
+#[tokio::test]
+async fn test_model_change_detected_without_recorded_model() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut config = Config::default();
+    config.vector_db.lancedb_path = temp_dir.path().join("db");
+    config.cache.hash_cache_path = temp_dir.path().join("cache.json");
+    config.cache.git_cache_path = temp_dir.path().join("git_cache.json");
+
+    let client = RagClient::with_config(config.clone()).await.unwrap();
+    drop(client);
+
+    // An index created before the model was recorded still has its dimension
+    std::fs::remove_file(config.vector_db.lancedb_path.join("index_metadata.json")).unwrap();
+    config.embedding.model_name = "BAAI/bge-base-en-v1.5".to_string();
+    let error = match RagClient::with_config(config).await {
+        Ok(_) => panic!("the changed model should be refused"),
+        Err(e) => e,
+    };
+    assert!(matches!(
+        error.downcast_ref::<RagError>(),
+        Some(RagError::Embedding(EmbeddingError::IndexModelChanged {
+            indexed_dimension: 384,
+            dimension: 768,
+            ..
+        }))
+    ));
+}
+
+#[tokio::test]
+async fn test_reload_embedding_model_checks_dimension() {
+    let (client, temp_dir) = create_test_client().await;
+
+    let data_dir = temp_dir.path().join("reload");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("auth.rs"), "fn authenticate() {}").unwrap();
+
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
+
+    // Reloading the same model swaps in a fresh provider, for clones too, and
+    // keeps the index usable
+    let shared = Arc::new(client.clone());
+    let previous = client.embedding_provider();
+    shared.reload_embedding_model(None).await.unwrap();
+    assert!(!Arc::ptr_eq(&previous, &client.embedding_provider()));
+    assert!(Arc::ptr_eq(
+        &shared.embedding_provider(),
+        &client.embedding_provider()
+    ));
+    assert_eq!(client.model_name(), "all-MiniLM-L6-v2");
+
+    let query_req = QueryRequest {
+        query: "authenticate".to_string(),
+        path: None,
+        project: None,
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    let response = client.query_codebase(query_req).await.unwrap();
+    assert!(!response.results.is_empty());
+
+    // A 768-dimension model cannot replace the 384-dimension one on a populated index
+    let err = client
+        .reload_embedding_model(Some("BAAI/bge-base-en-v1.5"))
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("dimension"));
+    assert_eq!(client.model_name(), "all-MiniLM-L6-v2");
+    assert_eq!(client.embedding_provider().dimension(), 384);
+}
+
+#[tokio::test]
+async fn test_compact_cache_preserves_index_state() {
+    let (client, temp_dir) = create_test_client().await;
+
+    let data_dir = temp_dir.path().join("compact");
+    std::fs::create_dir(&data_dir).unwrap();
+    for i in 0..5 {
+        std::fs::write(
+            data_dir.join(format!("file_{}.rs", i)),
+            format!("fn function_{}() {{}}", i),
+        )
+        .unwrap();
+    }
+
+    let req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(req.clone()).await.unwrap();
+
+    let roots_before = client.hash_cache.read().await.roots.clone();
+    let compaction = client.compact_cache().await.unwrap();
+
+    assert_eq!(compaction.roots, 1);
+    assert_eq!(compaction.files, 5);
+    assert!(compaction.bytes_after < compaction.bytes_before);
+
+    let reloaded = HashCache::load(&client.cache_path).unwrap();
+    assert_eq!(reloaded.roots, roots_before);
+
+    // Incremental updates keep working against the compacted cache
+    let response = client.index_codebase(req).await.unwrap();
+    assert_eq!(response.mode, IndexingMode::Incremental);
+    assert_eq!(response.files_indexed, 0);
+}
+
+#[tokio::test]
+async fn test_optimize_index_compacts_after_incremental_updates() {
+    let (client, temp_dir) = create_test_client().await;
+
+    let data_dir = temp_dir.path().join("optimize");
+    std::fs::create_dir(&data_dir).unwrap();
+    for i in 0..3 {
+        std::fs::write(
+            data_dir.join(format!("file_{}.rs", i)),
+            format!("fn function_{}() {{}}", i),
+        )
+        .unwrap();
+    }
+
+    let req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(req.clone()).await.unwrap();
+    for revision in 0..3 {
+        std::fs::write(
+            data_dir.join("file_0.rs"),
+            format!("fn function_0_revision_{}() {{}}", revision),
+        )
+        .unwrap();
+        client.index_codebase(req.clone()).await.unwrap();
+    }
+    let stats_before = client.get_statistics().await.unwrap();
+
+    let response = client.optimize_index().await.unwrap();
+
+    assert!(response.fragments_after < response.fragments_before);
+    assert_eq!(response.bm25_indexes, 1);
+    assert!(response.bm25_segments_after < response.bm25_segments_before);
+    assert_eq!(response.bm25_segments_after, 1);
+
+    // Optimizing leaves the indexed data as it was
+    let stats_after = client.get_statistics().await.unwrap();
+    assert_eq!(stats_after.total_chunks, stats_before.total_chunks);
+    let results = client
+        .query_codebase(QueryRequest {
+            query: "function_0_revision_2".to_string(),
+            path: None,
+            project: None,
+            projects: vec![],
+            limit: 5,
+            offset: 0,
+            min_score: 0.0,
+            hybrid: true,
+            exclude_files: vec![],
+            include_signature: false,
+            include_summary: false,
+            include_relations_precision: false,
+            context_lines: 0,
+            include_git_commits: false,
+        })
+        .await
+        .unwrap();
+    assert!(
+        results
+            .results
+            .iter()
+            .any(|result| result.content.contains("function_0_revision_2"))
+    );
+}
+
+#[tokio::test]
+async fn test_warm_reindex_replaces_index() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut config = Config::default();
+    config.vector_db.lancedb_path = temp_dir.path().join("db");
+    config.cache.hash_cache_path = temp_dir.path().join("cache.json");
+    config.cache.git_cache_path = temp_dir.path().join("git_cache.json");
+    config.indexing.warm_reindex = true;
+    // A non-default metric, which the staging table must keep
+    config.vector_db.distance = crate::vector_db::DistanceMetric::Cosine;
+    let client = RagClient::with_config(config).await.unwrap();
+
+    let data_dir = temp_dir.path().join("warm");
+    let other_dir = temp_dir.path().join("other");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::create_dir(&other_dir).unwrap();
+    std::fs::write(data_dir.join("old.rs"), "fn old_handler() {}").unwrap();
+    std::fs::write(other_dir.join("keep.rs"), "fn other_handler() {}").unwrap();
+
+    let request = |dir: &std::path::Path| IndexRequest {
+        path: dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(request(&data_dir)).await.unwrap();
+    client.index_codebase(request(&other_dir)).await.unwrap();
+
+    std::fs::remove_file(data_dir.join("old.rs")).unwrap();
+    std::fs::write(data_dir.join("new.rs"), "fn new_handler() {}").unwrap();
+
+    let response = client.reindex_codebase(request(&data_dir)).await.unwrap();
+    assert_eq!(response.mode, IndexingMode::Full);
+    assert_eq!(response.files_indexed, 1);
+
+    let query = QueryRequest {
+        query: "handler".to_string(),
+        path: None,
+        project: None,
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: false,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    let mut files: Vec<_> = client
+        .query_codebase(query)
+        .await
+        .unwrap()
+        .results
+        .into_iter()
+        .map(|r| r.file_path)
+        .collect();
+    files.sort();
+    assert_eq!(files.len(), 2);
+    assert!(files[0].ends_with("keep.rs"));
+    assert!(files[1].ends_with("new.rs"));
+
+    let root = RagClient::normalize_path(&data_dir.to_string_lossy()).unwrap();
+    let cache = client.hash_cache.read().await;
+    assert!(!cache.is_dirty(&root));
+    let hashes = cache.get_root(&root).unwrap();
+    assert_eq!(hashes.len(), 1);
+    assert!(hashes.keys().all(|file| file.ends_with("new.rs")));
+}
+
+// ===== Concurrent Indexing Lock Tests =====
+
+#[tokio::test]
+async fn test_index_lock_prevents_duplicate_indexing() {
+    let (client, temp_dir) = create_test_client().await;
+
+    // Create data to index
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn main() { println!(\"test\"); }").unwrap();
+
+    let path = data_dir.to_string_lossy().to_string();
+
+    // Start first indexing
+    let lock_result1 = client.try_acquire_index_lock(&path).await.unwrap();
+    assert!(
+        matches!(lock_result1, IndexLockResult::Acquired(_)),
+        "First call should acquire the lock"
+    );
+
+    // Try to acquire lock again while first is still held
+    let lock_result2 = client.try_acquire_index_lock(&path).await.unwrap();
+    // With cross-process locking, this could be WaitForResult (same process, in-memory)
+    // or WaitForFilesystemLock (different process holding filesystem lock)
+    assert!(
+        matches!(lock_result2, IndexLockResult::WaitForResult(_) | IndexLockResult::WaitForFilesystemLock(_)),
+        "Second call should wait for the first operation (got: {:?})",
+        match &lock_result2 {
+            IndexLockResult::Acquired(_) => "Acquired",
+            IndexLockResult::WaitForResult(_) => "WaitForResult",
+            IndexLockResult::WaitForFilesystemLock(_) => "WaitForFilesystemLock",
+        }
+    );
+
+    // Release the first lock by dropping it (simulate completion)
+    if let IndexLockResult::Acquired(guard) = lock_result1 {
+        // Broadcast a result before releasing
+        let result = IndexResponse {
+            mode: crate::types::IndexingMode::Full,
+            files_indexed: 1,
+            chunks_created: 1,
+            embeddings_generated: 1,
+            duration_ms: 100,
+            errors: vec![],
+            files_updated: 0,
+            files_removed: 0,
+            file_errors: vec![],
+        };
+        guard.broadcast_result(&result);
+        guard.release().await;
+    }
+}
+
+#[tokio::test]
+async fn test_index_lock_waiters_receive_result() {
+    let (client, temp_dir) = create_test_client().await;
+
+    // Create data to index
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn test() {}").unwrap();
+
+    let path = data_dir.to_string_lossy().to_string();
+
+    // Acquire the lock first
+    let lock_result = client.try_acquire_index_lock(&path).await.unwrap();
+    let guard = match lock_result {
+        IndexLockResult::Acquired(g) => g,
+        _ => panic!("Expected to acquire lock"),
+    };
+
+    // Try to get a waiter - with filesystem locking, this returns WaitForFilesystemLock
+    // since the filesystem lock is held by the first guard
+    let lock_result2 = client.try_acquire_index_lock(&path).await.unwrap();
+
+    match lock_result2 {
+        IndexLockResult::WaitForFilesystemLock(_) => {
+            // Expected behavior with cross-process filesystem locking
             // Release the first lock
             guard.broadcast_result(&IndexResponse {
                 mode: crate::types::IndexingMode::Full,
@@ -1076,35 +3240,270 @@ async fn test_index_lock_waiters_receive_result() {
                 errors: vec![],
                 files_updated: 0,
                 files_removed: 0,
+                file_errors: vec![],
             });
             guard.release().await;
 
-            // Verify lock can now be acquired
+            // Verify lock can now be acquired
+            let lock_result3 = client.try_acquire_index_lock(&path).await.unwrap();
+            assert!(
+                matches!(lock_result3, IndexLockResult::Acquired(_)),
+                "Should acquire lock after first is released"
+            );
+        }
+        IndexLockResult::WaitForResult(mut receiver) => {
+            // In-process waiting (would only happen if same process, same in-memory state)
+            let expected_response = IndexResponse {
+                mode: crate::types::IndexingMode::Full,
+                files_indexed: 42,
+                chunks_created: 100,
+                embeddings_generated: 100,
+                duration_ms: 500,
+                errors: vec![],
+                files_updated: 0,
+                files_removed: 0,
+                file_errors: vec![],
+            };
+            guard.broadcast_result(&expected_response);
+            guard.release().await;
+
+            let received = receiver.recv().await.unwrap();
+            assert_eq!(received.files_indexed, 42);
+            assert_eq!(received.chunks_created, 100);
+            assert_eq!(received.embeddings_generated, 100);
+        }
+        IndexLockResult::Acquired(_) => {
+            panic!("Second call should NOT acquire lock while first is held");
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_index_lock_path_normalization() {
+    let (client, temp_dir) = create_test_client().await;
+
+    // Create data to index
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn test() {}").unwrap();
+
+    // Get different path representations
+    let path1 = data_dir.to_string_lossy().to_string();
+    let path2 = format!("{}/../data", data_dir.to_string_lossy()); // With ..
+
+    // Acquire lock with first path
+    let lock_result1 = client.try_acquire_index_lock(&path1).await.unwrap();
+    assert!(matches!(lock_result1, IndexLockResult::Acquired(_)));
+
+    // Try to acquire with different but equivalent path
+    // With filesystem locking, this returns WaitForFilesystemLock (cross-process)
+    // Both WaitForResult and WaitForFilesystemLock indicate the lock is shared
+    let lock_result2 = client.try_acquire_index_lock(&path2).await.unwrap();
+    assert!(
+        matches!(lock_result2, IndexLockResult::WaitForResult(_) | IndexLockResult::WaitForFilesystemLock(_)),
+        "Equivalent paths should share the same lock"
+    );
+
+    // Release the first lock
+    if let IndexLockResult::Acquired(guard) = lock_result1 {
+        let result = IndexResponse {
+            mode: crate::types::IndexingMode::Full,
+            files_indexed: 1,
+            chunks_created: 1,
+            embeddings_generated: 1,
+            duration_ms: 100,
+            errors: vec![],
+            files_updated: 0,
+            files_removed: 0,
+            file_errors: vec![],
+        };
+        guard.broadcast_result(&result);
+        guard.release().await;
+    }
+}
+
+#[tokio::test]
+async fn test_index_lock_released_after_completion() {
+    let (client, temp_dir) = create_test_client().await;
+
+    // Create data to index
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn test() {}").unwrap();
+
+    let path = data_dir.to_string_lossy().to_string();
+
+    // First: acquire, broadcast, release
+    {
+        let lock_result = client.try_acquire_index_lock(&path).await.unwrap();
+        let guard = match lock_result {
+            IndexLockResult::Acquired(g) => g,
+            _ => panic!("Expected to acquire lock"),
+        };
+
+        let result = IndexResponse {
+            mode: crate::types::IndexingMode::Full,
+            files_indexed: 1,
+            chunks_created: 1,
+            embeddings_generated: 1,
+            duration_ms: 100,
+            errors: vec![],
+            files_updated: 0,
+            files_removed: 0,
+            file_errors: vec![],
+        };
+        guard.broadcast_result(&result);
+        guard.release().await;
+    }
+
+    // Second: should be able to acquire lock again
+    let lock_result2 = client.try_acquire_index_lock(&path).await.unwrap();
+    assert!(
+        matches!(lock_result2, IndexLockResult::Acquired(_)),
+        "Should be able to acquire lock after previous operation completed"
+    );
+
+    // Clean up
+    if let IndexLockResult::Acquired(guard) = lock_result2 {
+        let result = IndexResponse {
+            mode: crate::types::IndexingMode::Incremental,
+            files_indexed: 0,
+            chunks_created: 0,
+            embeddings_generated: 0,
+            duration_ms: 50,
+            errors: vec![],
+            files_updated: 0,
+            files_removed: 0,
+            file_errors: vec![],
+        };
+        guard.broadcast_result(&result);
+        guard.release().await;
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_index_calls_share_result() {
+    use std::sync::Arc;
+    use tokio::sync::Barrier;
+
+    let (client, temp_dir) = create_test_client().await;
+
+    // Create data to index
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn main() {}").unwrap();
+
+    let path = data_dir.to_string_lossy().to_string();
+    let client = Arc::new(client);
+
+    // Use a barrier to synchronize the start of both tasks
+    let barrier = Arc::new(Barrier::new(2));
+
+    let client1 = client.clone();
+    let path1 = path.clone();
+    let barrier1 = barrier.clone();
+
+    let client2 = client.clone();
+    let path2 = path.clone();
+    let barrier2 = barrier.clone();
+
+    // Spawn two concurrent indexing tasks
+    let task1 = tokio::spawn(async move {
+        barrier1.wait().await;
+        let request = IndexRequest {
+            path: path1,
+            project: None,
+            include_patterns: vec![],
+            exclude_patterns: vec![],
+            max_file_size: 1024 * 1024,
+            model_name: None,
+        };
+        client1.index_codebase(request).await
+    });
+
+    let task2 = tokio::spawn(async move {
+        barrier2.wait().await;
+        let request = IndexRequest {
+            path: path2,
+            project: None,
+            include_patterns: vec![],
+            exclude_patterns: vec![],
+            max_file_size: 1024 * 1024,
+            model_name: None,
+        };
+        client2.index_codebase(request).await
+    });
+
+    // Wait for both to complete
+    let (result1, result2) = tokio::join!(task1, task2);
+
+    // Both should succeed (no errors)
+    let resp1 = result1.unwrap().unwrap();
+    let resp2 = result2.unwrap().unwrap();
+
+    // With filesystem locking, the behaviors are:
+    // - One task does full indexing (files_indexed > 0)
+    // - Other task either receives broadcast (same result) OR
+    //   waits for filesystem lock then returns immediately (files_indexed = 0)
+    //
+    // The important thing is both succeed without errors
+    assert!(resp1.errors.is_empty(), "Task 1 should succeed without errors");
+    assert!(resp2.errors.is_empty(), "Task 2 should succeed without errors");
+
+    // At least one should have done the actual indexing
+    let total_indexed = resp1.files_indexed + resp2.files_indexed;
+    assert!(total_indexed >= 1, "At least one task should have indexed files");
+}
+
+#[tokio::test]
+async fn test_index_lock_drop_without_release_broadcasts_error() {
+    let (client, temp_dir) = create_test_client().await;
+
+    // Create data to index
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn test() {}").unwrap();
+
+    let path = data_dir.to_string_lossy().to_string();
+
+    // Acquire the lock
+    let lock_result = client.try_acquire_index_lock(&path).await.unwrap();
+    let guard = match lock_result {
+        IndexLockResult::Acquired(g) => g,
+        _ => panic!("Expected to acquire lock"),
+    };
+
+    // Get a waiter - with filesystem locking, we get WaitForFilesystemLock
+    let lock_result2 = client.try_acquire_index_lock(&path).await.unwrap();
+    match lock_result2 {
+        IndexLockResult::WaitForFilesystemLock(_) => {
+            // With filesystem locking, the second call gets blocked on the filesystem lock
+            // Drop the guard - this releases the filesystem lock
+            drop(guard);
+
+            // Give cleanup time to run
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+            // Now we should be able to acquire the lock
             let lock_result3 = client.try_acquire_index_lock(&path).await.unwrap();
             assert!(
                 matches!(lock_result3, IndexLockResult::Acquired(_)),
-                "Should acquire lock after first is released"
+                "Should acquire after first is dropped"
             );
         }
         IndexLockResult::WaitForResult(mut receiver) => {
-            // In-process waiting (would only happen if same process, same in-memory state)
-            let expected_response = IndexResponse {
-                mode: crate::types::IndexingMode::Full,
-                files_indexed: 42,
-                chunks_created: 100,
-                embeddings_generated: 100,
-                duration_ms: 500,
-                errors: vec![],
-                files_updated: 0,
-                files_removed: 0,
-            };
-            guard.broadcast_result(&expected_response);
-            guard.release().await;
+            // In-process waiting path (legacy behavior)
+            // Drop the guard WITHOUT calling broadcast_result or release
+            drop(guard);
+
+            // Give the async cleanup task time to run
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
+            // Waiter should receive an error response (not hang forever)
             let received = receiver.recv().await.unwrap();
-            assert_eq!(received.files_indexed, 42);
-            assert_eq!(received.chunks_created, 100);
-            assert_eq!(received.embeddings_generated, 100);
+            assert_eq!(received.files_indexed, 0);
+            assert!(!received.errors.is_empty());
+            assert!(received.errors[0].contains("interrupted"));
         }
         IndexLockResult::Acquired(_) => {
             panic!("Second call should NOT acquire lock while first is held");
@@ -1113,7 +3512,7 @@ async fn test_index_lock_waiters_receive_result() {
 }
 
 #[tokio::test]
-async fn test_index_lock_path_normalization() {
+async fn test_index_lock_can_reacquire_after_drop_without_release() {
     let (client, temp_dir) = create_test_client().await;
 
     // Create data to index
@@ -1121,25 +3520,31 @@ async fn test_index_lock_path_normalization() {
     std::fs::create_dir(&data_dir).unwrap();
     std::fs::write(data_dir.join("test.rs"), "fn test() {}").unwrap();
 
-    // Get different path representations
-    let path1 = data_dir.to_string_lossy().to_string();
-    let path2 = format!("{}/../data", data_dir.to_string_lossy()); // With ..
+    let path = data_dir.to_string_lossy().to_string();
 
-    // Acquire lock with first path
-    let lock_result1 = client.try_acquire_index_lock(&path1).await.unwrap();
-    assert!(matches!(lock_result1, IndexLockResult::Acquired(_)));
+    // Acquire and immediately drop (simulating panic)
+    {
+        let lock_result = client.try_acquire_index_lock(&path).await.unwrap();
+        match lock_result {
+            IndexLockResult::Acquired(_guard) => {
+                // Drop without release
+            }
+            _ => panic!("Expected to acquire lock"),
+        }
+    }
 
-    // Try to acquire with different but equivalent path
-    // With filesystem locking, this returns WaitForFilesystemLock (cross-process)
-    // Both WaitForResult and WaitForFilesystemLock indicate the lock is shared
-    let lock_result2 = client.try_acquire_index_lock(&path2).await.unwrap();
+    // Give the async cleanup task time to run
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    // Should be able to acquire lock again
+    let lock_result2 = client.try_acquire_index_lock(&path).await.unwrap();
     assert!(
-        matches!(lock_result2, IndexLockResult::WaitForResult(_) | IndexLockResult::WaitForFilesystemLock(_)),
-        "Equivalent paths should share the same lock"
+        matches!(lock_result2, IndexLockResult::Acquired(_)),
+        "Should be able to acquire lock after previous guard was dropped"
     );
 
-    // Release the first lock
-    if let IndexLockResult::Acquired(guard) = lock_result1 {
+    // Clean up properly this time
+    if let IndexLockResult::Acquired(guard) = lock_result2 {
         let result = IndexResponse {
             mode: crate::types::IndexingMode::Full,
             files_indexed: 1,
@@ -1149,242 +3554,657 @@ async fn test_index_lock_path_normalization() {
             errors: vec![],
             files_updated: 0,
             files_removed: 0,
+            file_errors: vec![],
         };
         guard.broadcast_result(&result);
         guard.release().await;
     }
 }
 
+// ===== search_with_relations Tests =====
+
 #[tokio::test]
-async fn test_index_lock_released_after_completion() {
+async fn test_search_with_relations_returns_callers_and_callees() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Create data to index
     let data_dir = temp_dir.path().join("data");
     std::fs::create_dir(&data_dir).unwrap();
-    std::fs::write(data_dir.join("test.rs"), "fn test() {}").unwrap();
+    std::fs::write(
+        data_dir.join("checksum.rs"),
+        r#"fn entry_point() {
+    let total = compute_checksum(42);
+    println!("{}", total);
+}
 
-    let path = data_dir.to_string_lossy().to_string();
+fn compute_checksum(value: u32) -> u32 {
+    let doubled = double_value(value);
+    doubled + 1
+}
 
-    // First: acquire, broadcast, release
-    {
-        let lock_result = client.try_acquire_index_lock(&path).await.unwrap();
-        let guard = match lock_result {
-            IndexLockResult::Acquired(g) => g,
-            _ => panic!("Expected to acquire lock"),
-        };
+fn double_value(value: u32) -> u32 {
+    value * 2
+}
+"#,
+    )
+    .unwrap();
 
-        let result = IndexResponse {
-            mode: crate::types::IndexingMode::Full,
-            files_indexed: 1,
-            chunks_created: 1,
-            embeddings_generated: 1,
-            duration_ms: 100,
-            errors: vec![],
-            files_updated: 0,
-            files_removed: 0,
-        };
-        guard.broadcast_result(&result);
-        guard.release().await;
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: Some("relations-project".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
+
+    let request = SearchWithRelationsRequest {
+        query: "compute_checksum".to_string(),
+        path: None,
+        project: Some("relations-project".to_string()),
+        limit: 10,
+        min_score: 0.0,
+        hybrid: true,
+    };
+
+    let response = client.search_with_relations(request).await.unwrap();
+    assert!(!response.results.is_empty());
+
+    let related = response
+        .results
+        .iter()
+        .flat_map(|r| r.symbols.iter())
+        .find(|s| s.symbol.name == "compute_checksum")
+        .expect("compute_checksum should be among the matched symbols");
+
+    assert_eq!(related.callers.len(), 1);
+    assert_eq!(related.callers[0].name, "entry_point");
+    assert_eq!(related.callees.len(), 1);
+    assert_eq!(related.callees[0].name, "double_value");
+}
+
+#[tokio::test]
+async fn test_search_with_relations_result_outside_symbol() {
+    let (client, temp_dir) = create_test_client().await;
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(
+        data_dir.join("notes.txt"),
+        "Deployment notes: rotate the signing keys every quarter.",
+    )
+    .unwrap();
+
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: Some("notes-project".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
+
+    let request = SearchWithRelationsRequest {
+        query: "signing keys".to_string(),
+        path: None,
+        project: Some("notes-project".to_string()),
+        limit: 10,
+        min_score: 0.0,
+        hybrid: true,
+    };
+
+    let response = client.search_with_relations(request).await.unwrap();
+    assert!(!response.results.is_empty());
+    assert!(response.results.iter().all(|r| r.symbols.is_empty()));
+}
+
+#[tokio::test]
+async fn test_find_definition_rejects_file_over_relations_limit() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.relations.max_file_size = 1024;
+    client.config = Arc::new(config);
+
+    let generated = temp_dir.path().join("generated.rs");
+    let body: String = (0..200)
+        .map(|i| format!("pub fn generated_{}() {{}}\n", i))
+        .collect();
+    std::fs::write(&generated, body).unwrap();
+
+    let request = FindDefinitionRequest {
+        file_path: generated.to_string_lossy().to_string(),
+        line: 1,
+        column: 0,
+        project: None,
+    };
+    let err = client.find_definition(request).await.unwrap_err();
+    assert!(
+        err.to_string().contains("File too large for relations"),
+        "unexpected error: {}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_query_dual_returns_both_candidate_lists() {
+    let (client, temp_dir) = create_test_client().await;
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    for (name, content) in [
+        ("auth.rs", "fn authenticate_user(token: &str) {}"),
+        ("session.rs", "fn refresh_session(id: u64) { touch(id) }"),
+        ("math.rs", "fn add(a: i32, b: i32) -> i32 { a + b }"),
+    ] {
+        std::fs::write(data_dir.join(name), content).unwrap();
+    }
+
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
+
+    let request = QueryRequest {
+        query: "authenticate_user".to_string(),
+        path: None,
+        project: None,
+        projects: vec![],
+        limit: 5,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    let response = client.query_dual(request).await.unwrap();
+
+    assert!(!response.fused.is_empty());
+    assert!(!response.vector.is_empty());
+    assert!(!response.keyword.is_empty());
+    assert!(response.vector.iter().all(|r| r.keyword_score.is_none()));
+    assert!(response.keyword.iter().all(|r| r.keyword_score.is_some()));
+
+    let key = |r: &SearchResult| (r.file_path.clone(), r.start_line, r.end_line);
+    let union: std::collections::HashSet<_> = response
+        .vector
+        .iter()
+        .chain(response.keyword.iter())
+        .map(key)
+        .collect();
+    assert!(response.fused.iter().all(|r| union.contains(&key(r))));
+}
+
+#[tokio::test]
+async fn test_query_embedding_reused_from_disk_after_restart() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut config = Config::default();
+    config.vector_db.lancedb_path = temp_dir.path().join("db");
+    config.cache.hash_cache_path = temp_dir.path().join("cache.json");
+    config.cache.git_cache_path = temp_dir.path().join("git_cache.json");
+    config.cache.persist_query_embeddings = true;
+    config.search.query_cache_size = 8;
+
+    let client = RagClient::with_config(config.clone()).await.unwrap();
+    let embedding = client.embed_query("parse config file").unwrap();
+    let model = client.model_name().to_string();
+    client.lock_query_embeddings().unwrap().flush().unwrap();
+    drop(client);
+
+    let client = RagClient::with_config(config).await.unwrap();
+    let cached = client
+        .lock_query_embeddings()
+        .unwrap()
+        .get(&model, "parse config file");
+    assert_eq!(cached, Some(embedding.clone()));
+    assert_eq!(client.embed_query("parse config file").unwrap(), embedding);
+    assert_eq!(client.lock_query_embeddings().unwrap().len(), 1);
+}
+
+/// Embedding provider counting the batches it is asked to embed
+struct CountingProvider {
+    inner: Arc<dyn EmbeddingProvider>,
+    calls: std::sync::atomic::AtomicUsize,
+}
+
+impl EmbeddingProvider for CountingProvider {
+    fn embed_batch(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.inner.embed_batch(texts)
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+}
+
+#[tokio::test]
+async fn test_repeated_query_is_embedded_once() {
+    let (mut client, _temp_dir) = create_test_client().await;
+    let provider = Arc::new(CountingProvider {
+        inner: client.embedding_provider(),
+        calls: std::sync::atomic::AtomicUsize::new(0),
+    });
+    client.embedding_override = Some(provider.clone());
+
+    let request = QueryRequest {
+        query: "parse config file".to_string(),
+        path: None,
+        project: None,
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.7,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    client.query_codebase(request.clone()).await.unwrap();
+    client.query_codebase(request).await.unwrap();
+
+    assert_eq!(provider.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+/// Deterministic embedding provider counting a few keywords, with no model to load
+struct KeywordProvider;
+
+const PROVIDER_KEYWORDS: [&str; 3] = ["parse", "render", "socket"];
+
+impl EmbeddingProvider for KeywordProvider {
+    fn embed_batch(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        Ok(texts
+            .iter()
+            .map(|text| {
+                PROVIDER_KEYWORDS
+                    .iter()
+                    .map(|keyword| text.matches(keyword).count() as f32 + 0.01)
+                    .collect()
+            })
+            .collect())
     }
 
-    // Second: should be able to acquire lock again
-    let lock_result2 = client.try_acquire_index_lock(&path).await.unwrap();
-    assert!(
-        matches!(lock_result2, IndexLockResult::Acquired(_)),
-        "Should be able to acquire lock after previous operation completed"
-    );
+    fn dimension(&self) -> usize {
+        PROVIDER_KEYWORDS.len()
+    }
 
-    // Clean up
-    if let IndexLockResult::Acquired(guard) = lock_result2 {
-        let result = IndexResponse {
-            mode: crate::types::IndexingMode::Incremental,
-            files_indexed: 0,
-            chunks_created: 0,
-            embeddings_generated: 0,
-            duration_ms: 50,
-            errors: vec![],
-            files_updated: 0,
-            files_removed: 0,
-        };
-        guard.broadcast_result(&result);
-        guard.release().await;
+    fn model_name(&self) -> &str {
+        "test/keywords"
     }
 }
 
 #[tokio::test]
-async fn test_concurrent_index_calls_share_result() {
-    use std::sync::Arc;
-    use tokio::sync::Barrier;
+async fn test_client_embeds_with_injected_provider() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut config = Config::default();
+    config.vector_db.lancedb_path = temp_dir.path().join("db");
+    config.cache.hash_cache_path = temp_dir.path().join("cache.json");
+    config.cache.git_cache_path = temp_dir.path().join("git_cache.json");
+    config.embedding.model_name = "test/keywords".to_string();
 
-    let (client, temp_dir) = create_test_client().await;
+    let client = RagClient::with_config_and_provider(config, Arc::new(KeywordProvider))
+        .await
+        .unwrap();
+    assert_eq!(client.embedding_dimension(), 3);
 
-    // Create data to index
     let data_dir = temp_dir.path().join("data");
     std::fs::create_dir(&data_dir).unwrap();
-    std::fs::write(data_dir.join("test.rs"), "fn main() {}").unwrap();
+    std::fs::write(data_dir.join("config.rs"), "fn parse_config() {}").unwrap();
+    std::fs::write(data_dir.join("page.rs"), "fn render_page() {}").unwrap();
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
 
-    let path = data_dir.to_string_lossy().to_string();
-    let client = Arc::new(client);
+    let request = QueryRequest {
+        query: "render".to_string(),
+        path: None,
+        project: None,
+        projects: vec![],
+        limit: 1,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: false,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    let response = client.query_codebase(request).await.unwrap();
+    assert_eq!(response.results[0].file_path, "page.rs");
+}
 
-    // Use a barrier to synchronize the start of both tasks
-    let barrier = Arc::new(Barrier::new(2));
+/// Keyword embeddings behind a slow first call, like a model session's setup
+struct ColdStartProvider {
+    warm: std::sync::atomic::AtomicBool,
+}
 
-    let client1 = client.clone();
-    let path1 = path.clone();
-    let barrier1 = barrier.clone();
+impl EmbeddingProvider for ColdStartProvider {
+    fn embed_batch(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        if !self.warm.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(500));
+        }
+        KeywordProvider.embed_batch(texts)
+    }
 
-    let client2 = client.clone();
-    let path2 = path.clone();
-    let barrier2 = barrier.clone();
+    fn dimension(&self) -> usize {
+        KeywordProvider.dimension()
+    }
 
-    // Spawn two concurrent indexing tasks
-    let task1 = tokio::spawn(async move {
-        barrier1.wait().await;
-        let request = IndexRequest {
-            path: path1,
-            project: None,
-            include_patterns: vec![],
-            exclude_patterns: vec![],
-            max_file_size: 1024 * 1024,
-        };
-        client1.index_codebase(request).await
-    });
+    fn model_name(&self) -> &str {
+        "test/keywords"
+    }
+}
 
-    let task2 = tokio::spawn(async move {
-        barrier2.wait().await;
-        let request = IndexRequest {
-            path: path2,
-            project: None,
-            include_patterns: vec![],
-            exclude_patterns: vec![],
-            max_file_size: 1024 * 1024,
-        };
-        client2.index_codebase(request).await
+#[tokio::test]
+async fn test_warm_up_pays_the_first_embedding_cost() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut config = Config::default();
+    config.vector_db.lancedb_path = temp_dir.path().join("db");
+    config.cache.hash_cache_path = temp_dir.path().join("cache.json");
+    config.cache.git_cache_path = temp_dir.path().join("git_cache.json");
+    config.embedding.model_name = "test/keywords".to_string();
+    let provider = Arc::new(ColdStartProvider {
+        warm: Default::default(),
     });
+    let client = RagClient::with_config_and_provider(config, provider)
+        .await
+        .unwrap();
 
-    // Wait for both to complete
-    let (result1, result2) = tokio::join!(task1, task2);
+    let start = Instant::now();
+    client.warm_up().await.unwrap();
+    let cold = start.elapsed();
 
-    // Both should succeed (no errors)
-    let resp1 = result1.unwrap().unwrap();
-    let resp2 = result2.unwrap().unwrap();
+    let start = Instant::now();
+    client
+        .embedding_provider()
+        .embed_batch(vec!["fn parse() {}".to_string()])
+        .unwrap();
+    let warm = start.elapsed();
 
-    // With filesystem locking, the behaviors are:
-    // - One task does full indexing (files_indexed > 0)
-    // - Other task either receives broadcast (same result) OR
-    //   waits for filesystem lock then returns immediately (files_indexed = 0)
-    //
-    // The important thing is both succeed without errors
-    assert!(resp1.errors.is_empty(), "Task 1 should succeed without errors");
-    assert!(resp2.errors.is_empty(), "Task 2 should succeed without errors");
+    assert!(
+        warm * 2 < cold,
+        "Embedding after warm-up took {:?}, the cold one {:?}",
+        warm,
+        cold
+    );
+}
 
-    // At least one should have done the actual indexing
-    let total_indexed = resp1.files_indexed + resp2.files_indexed;
-    assert!(total_indexed >= 1, "At least one task should have indexed files");
+#[tokio::test]
+async fn test_configured_chunk_strategy_is_used() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut config = Config::default();
+    config.vector_db.lancedb_path = temp_dir.path().join("db");
+    config.cache.hash_cache_path = temp_dir.path().join("cache.json");
+    config.cache.git_cache_path = temp_dir.path().join("git_cache.json");
+    config.indexing.chunk_strategy = ChunkStrategyKind::FixedLines;
+    config.indexing.chunk_size = 3;
+
+    let file = temp_dir.path().join("notes.txt");
+    std::fs::write(&file, "one\ntwo\nthree\nfour\nfive\nsix\nseven\n").unwrap();
+
+    let client = RagClient::with_config(config).await.unwrap();
+    let file_info = client
+        .create_file_info(file.to_str().unwrap(), None)
+        .unwrap();
+    let ranges: Vec<_> = client
+        .chunker
+        .chunk_file(&file_info)
+        .iter()
+        .map(|c| (c.metadata.start_line, c.metadata.end_line))
+        .collect();
+    assert_eq!(ranges, [(1, 3), (4, 6), (7, 7)]);
 }
 
 #[tokio::test]
-async fn test_index_lock_drop_without_release_broadcasts_error() {
+async fn test_search_results_carry_indexed_at() {
     let (client, temp_dir) = create_test_client().await;
 
-    // Create data to index
     let data_dir = temp_dir.path().join("data");
     std::fs::create_dir(&data_dir).unwrap();
-    std::fs::write(data_dir.join("test.rs"), "fn test() {}").unwrap();
+    std::fs::write(data_dir.join("lib.rs"), "fn parse_config() {}").unwrap();
 
-    let path = data_dir.to_string_lossy().to_string();
-
-    // Acquire the lock
-    let lock_result = client.try_acquire_index_lock(&path).await.unwrap();
-    let guard = match lock_result {
-        IndexLockResult::Acquired(g) => g,
-        _ => panic!("Expected to acquire lock"),
+    let now = || chrono::Utc::now().timestamp();
+    let before = now();
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
     };
+    client.index_codebase(index_req).await.unwrap();
+    let after = now();
 
-    // Get a waiter - with filesystem locking, we get WaitForFilesystemLock
-    let lock_result2 = client.try_acquire_index_lock(&path).await.unwrap();
-    match lock_result2 {
-        IndexLockResult::WaitForFilesystemLock(_) => {
-            // With filesystem locking, the second call gets blocked on the filesystem lock
-            // Drop the guard - this releases the filesystem lock
-            drop(guard);
-
-            // Give cleanup time to run
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-
-            // Now we should be able to acquire the lock
-            let lock_result3 = client.try_acquire_index_lock(&path).await.unwrap();
-            assert!(
-                matches!(lock_result3, IndexLockResult::Acquired(_)),
-                "Should acquire after first is dropped"
-            );
-        }
-        IndexLockResult::WaitForResult(mut receiver) => {
-            // In-process waiting path (legacy behavior)
-            // Drop the guard WITHOUT calling broadcast_result or release
-            drop(guard);
-
-            // Give the async cleanup task time to run
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    for hybrid in [false, true] {
+        let request = QueryRequest {
+            query: "parse_config".to_string(),
+            path: None,
+            project: None,
+            projects: vec![],
+            limit: 5,
+            offset: 0,
+            min_score: 0.0,
+            hybrid,
+            exclude_files: vec![],
+            include_signature: false,
+            include_summary: false,
+            include_relations_precision: false,
+            context_lines: 0,
+            include_git_commits: false,
+        };
+        let response = client.query_codebase(request).await.unwrap();
 
-            // Waiter should receive an error response (not hang forever)
-            let received = receiver.recv().await.unwrap();
-            assert_eq!(received.files_indexed, 0);
-            assert!(!received.errors.is_empty());
-            assert!(received.errors[0].contains("interrupted"));
-        }
-        IndexLockResult::Acquired(_) => {
-            panic!("Second call should NOT acquire lock while first is held");
+        assert!(!response.results.is_empty());
+        for result in &response.results {
+            let indexed_at = result.indexed_at.expect("indexed_at should be set");
+            assert!((before..=after).contains(&indexed_at));
         }
     }
 }
 
 #[tokio::test]
-async fn test_index_lock_can_reacquire_after_drop_without_release() {
-    let (client, temp_dir) = create_test_client().await;
+async fn test_docs_project_separates_docs_from_code() {
+    let (mut client, temp_dir) = create_test_client().await;
+    client.chunker = Arc::new(CodeChunker::default_strategy().with_docs_project(true));
 
-    // Create data to index
     let data_dir = temp_dir.path().join("data");
     std::fs::create_dir(&data_dir).unwrap();
-    std::fs::write(data_dir.join("test.rs"), "fn test() {}").unwrap();
+    std::fs::write(
+        data_dir.join("lib.rs"),
+        "/// Parses the configuration file\npub fn parse_config() -> u32 { 1 }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        data_dir.join("guide.md"),
+        "# Configuration\n\nThe configuration file is parsed at startup.\n",
+    )
+    .unwrap();
 
-    let path = data_dir.to_string_lossy().to_string();
+    let index_req = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: Some("app".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    client.index_codebase(index_req).await.unwrap();
 
-    // Acquire and immediately drop (simulating panic)
-    {
-        let lock_result = client.try_acquire_index_lock(&path).await.unwrap();
-        match lock_result {
-            IndexLockResult::Acquired(_guard) => {
-                // Drop without release
-            }
-            _ => panic!("Expected to acquire lock"),
-        }
-    }
+    let query = |project: &str| QueryRequest {
+        query: "configuration file".to_string(),
+        path: None,
+        project: Some(project.to_string()),
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
 
-    // Give the async cleanup task time to run
-    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    let docs = client.query_codebase(query("app-docs")).await.unwrap();
+    let doc_files: Vec<_> = docs.results.iter().map(|r| r.file_path.as_str()).collect();
+    assert!(doc_files.contains(&"guide.md"));
+    assert!(
+        docs.results
+            .iter()
+            .any(|r| r.file_path == "lib.rs" && r.content.starts_with("/// Parses"))
+    );
+    assert!(
+        docs.results
+            .iter()
+            .all(|r| r.project.as_deref() == Some("app-docs"))
+    );
 
-    // Should be able to acquire lock again
-    let lock_result2 = client.try_acquire_index_lock(&path).await.unwrap();
+    let code = client.query_codebase(query("app")).await.unwrap();
+    assert!(!code.results.is_empty());
     assert!(
-        matches!(lock_result2, IndexLockResult::Acquired(_)),
-        "Should be able to acquire lock after previous guard was dropped"
+        code.results
+            .iter()
+            .all(|r| r.file_path == "lib.rs" && r.project.as_deref() == Some("app"))
     );
+}
 
-    // Clean up properly this time
-    if let IndexLockResult::Acquired(guard) = lock_result2 {
-        let result = IndexResponse {
-            mode: crate::types::IndexingMode::Full,
-            files_indexed: 1,
-            chunks_created: 1,
-            embeddings_generated: 1,
-            duration_ms: 100,
-            errors: vec![],
-            files_updated: 0,
-            files_removed: 0,
-        };
-        guard.broadcast_result(&result);
-        guard.release().await;
-    }
+#[tokio::test]
+async fn test_cancelled_index_reports_partial_progress() {
+    let (client, temp_dir) = create_test_client().await;
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("main.rs"), "fn main() {}").unwrap();
+
+    let cancel_token = CancellationToken::new();
+    cancel_token.cancel();
+    let request = IndexRequest {
+        path: data_dir.to_string_lossy().to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    let error = client
+        .index_codebase_with_cancellation(request, cancel_token)
+        .await
+        .expect_err("a cancelled run should not complete");
+
+    let progress = error
+        .downcast_ref::<IndexingCancelled>()
+        .expect("cancellation should carry the partial progress");
+    assert_eq!(progress.embeddings_generated, 0);
+
+    // The root stays dirty, on disk too, so the next run re-indexes it
+    let normalized = RagClient::normalize_path(&data_dir.to_string_lossy()).unwrap();
+    assert!(client.hash_cache.read().await.is_dirty(&normalized));
+    let saved = crate::cache::HashCache::load(&client.cache_path).unwrap();
+    assert!(saved.is_dirty(&normalized));
+}
+
+#[tokio::test]
+async fn test_cancelled_warm_reindex_keeps_previous_index() {
+    let (mut client, temp_dir) = create_test_client().await;
+    let mut config = (*client.config).clone();
+    config.indexing.warm_reindex = true;
+    client.config = Arc::new(config);
+
+    let root = index_project(
+        &client,
+        &temp_dir,
+        "warm",
+        &[("old.rs", "fn old_handler() {}")],
+    )
+    .await;
+    std::fs::write(
+        std::path::Path::new(&root).join("new.rs"),
+        "fn new_handler() {}",
+    )
+    .unwrap();
+
+    let cancel_token = CancellationToken::new();
+    cancel_token.cancel();
+    let request = IndexRequest {
+        path: root.clone(),
+        project: Some("warm".to_string()),
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: 1024 * 1024,
+        model_name: None,
+    };
+    let error = client
+        .reindex_codebase_with_cancellation(request, cancel_token)
+        .await
+        .expect_err("a cancelled reindex should not complete");
+    assert!(error.downcast_ref::<IndexingCancelled>().is_some());
+
+    // The previous index keeps serving queries, and needs no repair
+    let query = QueryRequest {
+        query: "handler".to_string(),
+        path: None,
+        project: None,
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: 0.0,
+        hybrid: false,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+    let results = client.query_codebase(query).await.unwrap().results;
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| r.file_path.ends_with("old.rs")));
+    assert!(!client.hash_cache.read().await.is_dirty(&root));
+}
+
+#[tokio::test]
+async fn test_cancel_file_cancels_token() {
+    let temp_dir = TempDir::new().unwrap();
+    let cancel_path = temp_dir.path().join("cancel");
+
+    // A file left over from an earlier run is removed rather than obeyed
+    std::fs::write(&cancel_path, "").unwrap();
+    let token = tokio_util::sync::CancellationToken::new();
+    let watcher = cancel_on_file(cancel_path.clone(), token.clone());
+    tokio::time::sleep(CANCEL_FILE_POLL_INTERVAL * 2).await;
+    assert!(!token.is_cancelled());
+    assert!(!cancel_path.exists());
+
+    std::fs::write(&cancel_path, "").unwrap();
+    tokio::time::timeout(Duration::from_secs(5), token.cancelled())
+        .await
+        .expect("the cancel file should cancel the token");
+    watcher.await.unwrap();
+    assert!(!cancel_path.exists());
 }
@@ -2,8 +2,13 @@
 ///
 /// Supports loading from multiple sources with priority:
 /// CLI args > Environment variables > Config file > Defaults
+use crate::cache::CacheFormat;
 use crate::error::{ConfigError, RagError};
+use crate::indexer::{ChunkStrategy, ChunkStrategyKind};
+use crate::vector_db::DistanceMetric;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
 
 /// Main configuration structure
@@ -23,6 +28,22 @@ pub struct Config {
 
     /// Cache configuration
     pub cache: CacheConfig,
+
+    /// Observability configuration
+    #[serde(default)]
+    pub observability: ObservabilityConfig,
+
+    /// Server configuration
+    #[serde(default)]
+    pub server: ServerConfig,
+
+    /// Code relations configuration
+    #[serde(default)]
+    pub relations: RelationsConfig,
+
+    /// BM25 keyword search configuration
+    #[serde(default)]
+    pub bm25: Bm25Config,
 }
 
 /// Vector database configuration
@@ -43,6 +64,63 @@ pub struct VectorDbConfig {
     /// Collection name for vector storage
     #[serde(default = "default_collection_name")]
     pub collection_name: String,
+
+    /// Rows a LanceDB table needs before an ANN (IVF_PQ) vector index is built
+    /// (0 = never index, always search exhaustively)
+    #[serde(default = "default_ann_index_min_rows")]
+    pub ann_index_min_rows: usize,
+
+    /// IVF partitions of the ANN index (unset = LanceDB default)
+    #[serde(default)]
+    pub ann_num_partitions: Option<u32>,
+
+    /// PQ sub-vectors of the ANN index; must divide the embedding dimension
+    /// (unset = LanceDB default)
+    #[serde(default)]
+    pub ann_num_sub_vectors: Option<u32>,
+
+    /// Distance LanceDB ranks vectors by: "l2", "cosine" or "dot"
+    #[serde(default)]
+    pub distance: DistanceMetric,
+
+    /// Clear the index at startup when it was built with another embedding model,
+    /// so codebases are re-indexed in full (false = refuse to start instead)
+    #[serde(default)]
+    pub auto_reindex_on_model_change: bool,
+}
+
+/// Hardware the ONNX Runtime session of the embedding model runs on
+///
+/// GPU devices need ONNX Runtime built with the matching execution provider
+/// (the `cuda` and `coreml` crate features). If the provider cannot be
+/// initialized, the model falls back to the CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingDevice {
+    /// ONNX Runtime's default CPU execution provider
+    #[default]
+    Cpu,
+    /// NVIDIA GPUs through the CUDA execution provider
+    Cuda,
+    /// Apple GPUs and the Neural Engine through the CoreML execution provider
+    CoreMl,
+}
+
+impl EmbeddingDevice {
+    /// Whether the device is a GPU (or other accelerator) rather than the CPU
+    pub fn is_gpu(self) -> bool {
+        self != Self::Cpu
+    }
+}
+
+impl fmt::Display for EmbeddingDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Cpu => "cpu",
+            Self::Cuda => "cuda",
+            Self::CoreMl => "coreml",
+        })
+    }
 }
 
 /// Embedding model configuration
@@ -54,28 +132,93 @@ pub struct EmbeddingConfig {
 
     /// Batch size for embedding generation
     /// Smaller values allow faster cancellation response but may be less efficient
+    /// Set to 0 to tune the batch size from observed throughput (auto)
     #[serde(default = "default_batch_size")]
     pub batch_size: usize,
 
+    /// Largest batch size the auto mode (`batch_size = 0`) may grow to
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
+
+    /// Embedding batches generated concurrently during indexing
+    /// Each one gets its own model session, so memory use grows with this value.
+    #[serde(default = "default_embedding_parallelism")]
+    pub parallelism: usize,
+
     /// Timeout in seconds for embedding generation per batch
     /// This is per-batch, not total - smaller batches mean faster timeout response
     #[serde(default = "default_embedding_timeout")]
     pub timeout_secs: u64,
 
+    /// Overall time limit in seconds for the embedding phase of an indexing run (0 = no limit)
+    /// Chunks embedded in time are stored; the rest are embedded by the next run.
+    #[serde(default)]
+    pub phase_timeout_secs: u64,
+
     /// Maximum number of chunks to process before checking for cancellation
     /// This provides more granular control over cancellation responsiveness
     /// Set to 0 to use batch_size (check once per batch)
     #[serde(default = "default_cancellation_check_interval")]
     pub cancellation_check_interval: usize,
+
+    /// Directory where downloaded model files are cached
+    /// For offline deployments, pre-place the model files here.
+    /// Defaults to FastEmbed's own cache directory when unset.
+    #[serde(default)]
+    pub model_cache_dir: Option<PathBuf>,
+
+    /// Maximum number of tokens per embedding input
+    /// Longer chunks are truncated (and logged) before embedding.
+    /// Defaults to the model's own maximum sequence length; can only lower it.
+    #[serde(default)]
+    pub max_input_tokens: Option<usize>,
+
+    /// Device the embedding model runs on ("cpu", "cuda" or "coreml")
+    /// Falls back to the CPU if the GPU execution provider fails to initialize.
+    /// On a GPU, each embedding call carries at least GPU_MIN_BATCH_SIZE chunks.
+    #[serde(default)]
+    pub device: EmbeddingDevice,
+
+    /// URL of a remote OpenAI-compatible embeddings API (e.g. "http://host:8080/v1")
+    /// When set, batches are sent to this service instead of running the model
+    /// locally, with model_name as the requested model. timeout_secs applies per request.
+    #[serde(default)]
+    pub api_url: Option<String>,
+
+    /// Bearer token for api_url, usually set through PROJECT_RAG_EMBED_API_KEY
+    /// Never written back when the configuration is saved.
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
 }
 
 /// Indexing configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexingConfig {
-    /// Default chunk size for FixedLines strategy
+    /// Lines per chunk for the fixed_lines and sliding_window strategies, and
+    /// per fallback chunk for files the ast strategy has no grammar for
     #[serde(default = "default_chunk_size")]
     pub chunk_size: usize,
 
+    /// How files are split into chunks: "ast", "fixed_lines", "sliding_window"
+    /// or "tokens"
+    #[serde(default)]
+    pub chunk_strategy: ChunkStrategyKind,
+
+    /// Estimated tokens per chunk for the tokens strategy; larger functions
+    /// and classes are split at line boundaries
+    #[serde(default = "default_max_chunk_tokens")]
+    pub max_chunk_tokens: usize,
+
+    /// Lines shared by consecutive chunks of a file, for every strategy (less
+    /// than chunk_size)
+    #[serde(default)]
+    pub chunk_overlap: usize,
+
+    /// Chunks with fewer lines are merged into an adjacent chunk or dropped
+    /// before embedding (1 = keep every chunk)
+    #[serde(default = "default_min_chunk_lines")]
+    pub min_chunk_lines: usize,
+
     /// Maximum file size to index (in bytes)
     #[serde(default = "default_max_file_size")]
     pub max_file_size: usize,
@@ -87,6 +230,73 @@ pub struct IndexingConfig {
     /// Default exclude patterns
     #[serde(default = "default_exclude_patterns")]
     pub exclude_patterns: Vec<String>,
+
+    /// Seconds `clear_index` waits for in-flight indexing to finish (0 = reject immediately)
+    #[serde(default)]
+    pub clear_wait_timeout_secs: u64,
+
+    /// Chunk Markdown and HTML by headings and paragraphs/blocks, and plain text by paragraphs
+    #[serde(default = "default_markup_chunking")]
+    pub markup_chunking: bool,
+    /// Rebuild into a staging table on reindex, keeping the old index queryable until it is swapped in
+    /// (LanceDB only; reindexing fails with the Qdrant backend)
+    #[serde(default)]
+    pub warm_reindex: bool,
+
+    /// Stop indexing cleanly after this many seconds and report partial progress (0 = no limit)
+    #[serde(default)]
+    pub max_duration_secs: f64,
+
+    /// Index binary files (images, fonts, ...) as metadata-only entries findable by path and extension
+    #[serde(default)]
+    pub index_binary_metadata: bool,
+
+    /// Skip hidden files and directories (names starting with a dot)
+    #[serde(default)]
+    pub skip_hidden: bool,
+
+    /// Directory names never walked, hidden or not (editor and tool caches by default)
+    #[serde(default = "default_excluded_dirs")]
+    pub excluded_dirs: Vec<String>,
+
+    /// Detect the language of extensionless or misnamed files from their content
+    /// (shebang lines such as `#!/usr/bin/env python3`)
+    #[serde(default = "default_content_language_detection")]
+    pub content_language_detection: bool,
+
+    /// Language names by file extension (without the dot), overriding the
+    /// built-in extension table, e.g. `{ "tpl" = "Handlebars" }`
+    #[serde(default)]
+    pub extension_languages: HashMap<String, String>,
+
+    /// Kilobytes read from the start of a file to decide whether it is text
+    /// or binary, instead of reading the whole file
+    #[serde(default = "default_text_detection_sample_kb")]
+    pub text_detection_sample_kb: usize,
+
+    /// Characters of a file's content (after PDF text extraction) kept for
+    /// chunking; longer content is truncated (0 = no limit)
+    #[serde(default = "default_max_content_chars")]
+    pub max_content_chars: usize,
+
+    /// Files read, hashed and language-detected in parallel while walking
+    /// (raise on network filesystems, lower to save file descriptors)
+    #[serde(default = "default_read_concurrency")]
+    pub read_concurrency: usize,
+
+    /// Only index source files that define at least one public/exported symbol
+    /// (files in languages without symbol extraction are always indexed)
+    #[serde(default)]
+    pub public_symbols_only: bool,
+
+    /// Index documentation files and doc comments into a separate `<project>-docs` project
+    #[serde(default)]
+    pub docs_project: bool,
+
+    /// Times a failed index operation is restarted from scratch when the failure
+    /// is transient (connection error, timeout); 0 gives up on the first failure
+    #[serde(default)]
+    pub max_operation_retries: u32,
 }
 
 /// Search configuration
@@ -103,6 +313,49 @@ pub struct SearchConfig {
     /// Enable hybrid search (vector + BM25) by default
     #[serde(default = "default_hybrid_search")]
     pub hybrid: bool,
+
+    /// IVF partitions probed per vector query on ANN-indexed tables (unset = LanceDB default)
+    #[serde(default)]
+    pub ann_nprobes: Option<usize>,
+
+    /// Re-rank `limit * ann_refine_factor` ANN candidates with exact distances (unset = off)
+    #[serde(default)]
+    pub ann_refine_factor: Option<u32>,
+
+    /// Tokenizer for BM25 keyword search: "code", "default", "en_stem" or "whitespace"
+    #[serde(default = "default_bm25_analyzer")]
+    pub bm25_analyzer: String,
+
+    /// Seconds a BM25 write waits for a writer in another process to finish
+    /// before failing (0 = fail immediately if another process is writing)
+    #[serde(default = "default_bm25_writer_lock_timeout")]
+    pub bm25_writer_lock_timeout_secs: u64,
+
+    /// Multiplier for the hybrid scores of documentation files matching
+    /// `doc_patterns` (1.0 = no boost)
+    #[serde(default = "default_doc_boost")]
+    pub doc_boost: f32,
+
+    /// Glob patterns of documentation files boosted by `doc_boost`
+    #[serde(default = "default_doc_patterns")]
+    pub doc_patterns: Vec<String>,
+
+    /// Re-rank query results with a cross-encoder model
+    #[serde(default)]
+    pub rerank: bool,
+
+    /// Candidates fetched and scored by the cross-encoder when `rerank` is on
+    #[serde(default = "default_rerank_candidates")]
+    pub rerank_candidates: usize,
+
+    /// Cross-encoder model used for re-ranking
+    #[serde(default = "default_rerank_model")]
+    pub rerank_model: String,
+
+    /// Query embeddings cached, so re-running a query skips the embedding
+    /// model (0 disables the cache; see `cache.persist_query_embeddings`)
+    #[serde(default = "default_query_cache_size")]
+    pub query_cache_size: usize,
 }
 
 /// Cache configuration
@@ -115,6 +368,73 @@ pub struct CacheConfig {
     /// Git cache file path
     #[serde(default = "default_git_cache_path")]
     pub git_cache_path: PathBuf,
+
+    /// Hash cache file format: "pretty" (indented JSON) or "compact" (binary)
+    #[serde(default)]
+    pub format: CacheFormat,
+
+    /// Keep the `search.query_cache_size` query embeddings in a file next to
+    /// the LanceDB data, so they survive a restart
+    #[serde(default)]
+    pub persist_query_embeddings: bool,
+}
+
+/// Observability configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ObservabilityConfig {
+    /// Emit tracing spans for pipeline phases (walk, chunk, embed, store, search, fuse)
+    /// so a subscriber such as an OpenTelemetry exporter can record them
+    #[serde(default)]
+    pub phase_spans: bool,
+
+    /// Log for every indexed file which chunking method was applied and why
+    /// (AST or line fallback, grammar available, parse succeeded or failed)
+    #[serde(default)]
+    pub chunk_diagnostics: bool,
+}
+
+/// Server configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// Hard cap on results returned per query; larger requested limits are
+    /// clamped and the response is flagged as truncated
+    #[serde(default = "default_max_results")]
+    pub max_results: usize,
+}
+
+/// Code relations configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationsConfig {
+    /// Largest file (in bytes) that relations queries will read and parse;
+    /// larger files are rejected instead of risking a slow or hung parse
+    #[serde(default = "default_relations_max_file_size")]
+    pub max_file_size: u64,
+}
+
+/// BM25 keyword search configuration
+///
+/// Stemming lets "authenticating" match "authenticate" but also conflates
+/// related words (`configure`, `configuration`), and stop words such as `if`
+/// or `for` can no longer be searched for. Whole identifiers are never
+/// stemmed. Changing either setting rebuilds the BM25 indexes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bm25Config {
+    /// Drop stop words and reduce words to their stem in indexed content and queries
+    #[serde(default)]
+    pub stemming: bool,
+
+    /// Language of the stemmer and stop-word list (e.g. "english", "german")
+    #[serde(default = "default_bm25_language")]
+    pub language: String,
+
+    /// Remove Tantivy lock files left by a writer presumed dead
+    /// When disabled, writes to a locked index fail until the files are removed by hand.
+    #[serde(default = "default_bm25_auto_unlock")]
+    pub auto_unlock: bool,
+
+    /// Seconds without a heartbeat after which a writer in another process is presumed dead
+    #[serde(default = "default_bm25_stale_lock_secs")]
+    pub stale_lock_secs: u64,
 }
 
 // Default value functions
@@ -137,6 +457,10 @@ fn default_collection_name() -> String {
     "code_embeddings".to_string()
 }
 
+fn default_ann_index_min_rows() -> usize {
+    100_000
+}
+
 fn default_model_name() -> String {
     "all-MiniLM-L6-v2".to_string()
 }
@@ -147,6 +471,14 @@ fn default_batch_size() -> usize {
     8
 }
 
+fn default_max_batch_size() -> usize {
+    256
+}
+
+fn default_embedding_parallelism() -> usize {
+    1
+}
+
 fn default_embedding_timeout() -> u64 {
     // Reduced from 30 to 10 seconds for faster timeout detection per batch
     10
@@ -162,10 +494,39 @@ fn default_chunk_size() -> usize {
     50
 }
 
+fn default_max_chunk_tokens() -> usize {
+    // Within the 256-token limit of the default model, special tokens included
+    250
+}
+
+fn default_min_chunk_lines() -> usize {
+    1
+}
+
 fn default_max_file_size() -> usize {
     1_048_576 // 1 MB
 }
 
+fn default_markup_chunking() -> bool {
+    true
+}
+
+fn default_content_language_detection() -> bool {
+    true
+}
+
+fn default_text_detection_sample_kb() -> usize {
+    crate::indexer::DEFAULT_TEXT_SAMPLE_BYTES / 1024
+}
+
+fn default_max_content_chars() -> usize {
+    crate::indexer::DEFAULT_MAX_CONTENT_CHARS
+}
+
+fn default_read_concurrency() -> usize {
+    crate::indexer::DEFAULT_READ_CONCURRENCY
+}
+
 fn default_exclude_patterns() -> Vec<String> {
     vec![
         "target".to_string(),
@@ -176,6 +537,13 @@ fn default_exclude_patterns() -> Vec<String> {
     ]
 }
 
+fn default_excluded_dirs() -> Vec<String> {
+    crate::indexer::DEFAULT_EXCLUDED_DIRS
+        .iter()
+        .map(|dir| dir.to_string())
+        .collect()
+}
+
 fn default_min_score() -> f32 {
     0.7
 }
@@ -188,6 +556,54 @@ fn default_hybrid_search() -> bool {
     true
 }
 
+fn default_max_results() -> usize {
+    crate::types::MAX_QUERY_LIMIT
+}
+
+fn default_relations_max_file_size() -> u64 {
+    1024 * 1024 // 1 MiB
+}
+
+fn default_bm25_writer_lock_timeout() -> u64 {
+    crate::bm25_search::DEFAULT_WRITER_LOCK_TIMEOUT.as_secs()
+}
+
+fn default_doc_boost() -> f32 {
+    1.0
+}
+
+fn default_doc_patterns() -> Vec<String> {
+    vec!["README*".to_string(), "docs/**".to_string()]
+}
+
+fn default_rerank_candidates() -> usize {
+    50
+}
+
+fn default_query_cache_size() -> usize {
+    64
+}
+
+fn default_rerank_model() -> String {
+    crate::embedding::DEFAULT_RERANK_MODEL.to_string()
+}
+
+fn default_bm25_analyzer() -> String {
+    crate::bm25_search::DEFAULT_BM25_ANALYZER.to_string()
+}
+
+fn default_bm25_language() -> String {
+    "english".to_string()
+}
+
+fn default_bm25_auto_unlock() -> bool {
+    true
+}
+
+fn default_bm25_stale_lock_secs() -> u64 {
+    crate::bm25_search::DEFAULT_STALE_LOCK_TIMEOUT.as_secs()
+}
+
 fn default_hash_cache_path() -> PathBuf {
     crate::paths::PlatformPaths::default_hash_cache_path()
 }
@@ -203,6 +619,22 @@ impl Default for VectorDbConfig {
             lancedb_path: default_lancedb_path(),
             qdrant_url: default_qdrant_url(),
             collection_name: default_collection_name(),
+            ann_index_min_rows: default_ann_index_min_rows(),
+            ann_num_partitions: None,
+            ann_num_sub_vectors: None,
+            distance: DistanceMetric::default(),
+            auto_reindex_on_model_change: false,
+        }
+    }
+}
+
+impl VectorDbConfig {
+    /// When and how LanceDB tables get an ANN vector index
+    pub fn ann_index_params(&self) -> crate::vector_db::AnnIndexParams {
+        crate::vector_db::AnnIndexParams {
+            min_rows: self.ann_index_min_rows,
+            num_partitions: self.ann_num_partitions,
+            num_sub_vectors: self.ann_num_sub_vectors,
         }
     }
 }
@@ -212,8 +644,16 @@ impl Default for EmbeddingConfig {
         Self {
             model_name: default_model_name(),
             batch_size: default_batch_size(),
+            max_batch_size: default_max_batch_size(),
+            parallelism: default_embedding_parallelism(),
             timeout_secs: default_embedding_timeout(),
+            phase_timeout_secs: 0,
             cancellation_check_interval: default_cancellation_check_interval(),
+            model_cache_dir: None,
+            max_input_tokens: None,
+            device: EmbeddingDevice::default(),
+            api_url: None,
+            api_key: None,
         }
     }
 }
@@ -222,21 +662,85 @@ impl Default for IndexingConfig {
     fn default() -> Self {
         Self {
             chunk_size: default_chunk_size(),
+            chunk_strategy: ChunkStrategyKind::default(),
+            max_chunk_tokens: default_max_chunk_tokens(),
+            chunk_overlap: 0,
+            min_chunk_lines: default_min_chunk_lines(),
             max_file_size: default_max_file_size(),
             include_patterns: Vec::new(),
             exclude_patterns: default_exclude_patterns(),
+            clear_wait_timeout_secs: 0,
+            markup_chunking: default_markup_chunking(),
+            warm_reindex: false,
+            max_duration_secs: 0.0,
+            index_binary_metadata: false,
+            skip_hidden: false,
+            excluded_dirs: default_excluded_dirs(),
+            content_language_detection: default_content_language_detection(),
+            extension_languages: HashMap::new(),
+            text_detection_sample_kb: default_text_detection_sample_kb(),
+            max_content_chars: default_max_content_chars(),
+            read_concurrency: default_read_concurrency(),
+            public_symbols_only: false,
+            docs_project: false,
+            max_operation_retries: 0,
         }
     }
 }
 
+impl IndexingConfig {
+    /// Indexing time limit, if one is set
+    pub fn max_duration(&self) -> Option<std::time::Duration> {
+        std::time::Duration::try_from_secs_f64(self.max_duration_secs)
+            .ok()
+            .filter(|limit| !limit.is_zero())
+    }
+
+    /// Chunking strategy built from `chunk_strategy`, `chunk_size`,
+    /// `chunk_overlap` and `max_chunk_tokens`
+    ///
+    /// The overlap also has to be given to the chunker with
+    /// [`CodeChunker::with_overlap`](crate::indexer::CodeChunker::with_overlap)
+    /// for the other strategies.
+    pub fn chunk_strategy(&self) -> ChunkStrategy {
+        self.chunk_strategy
+            .strategy(self.chunk_size, self.chunk_overlap, self.max_chunk_tokens)
+    }
+}
+
 impl Default for SearchConfig {
     fn default() -> Self {
         Self {
             min_score: default_min_score(),
             limit: default_result_limit(),
             hybrid: default_hybrid_search(),
+            ann_nprobes: None,
+            ann_refine_factor: None,
+            bm25_analyzer: default_bm25_analyzer(),
+            bm25_writer_lock_timeout_secs: default_bm25_writer_lock_timeout(),
+            doc_boost: default_doc_boost(),
+            doc_patterns: default_doc_patterns(),
+            rerank: false,
+            rerank_candidates: default_rerank_candidates(),
+            rerank_model: default_rerank_model(),
+            query_cache_size: default_query_cache_size(),
+        }
+    }
+}
+
+impl SearchConfig {
+    /// ANN recall/speed trade-off for LanceDB vector queries
+    pub fn ann_params(&self) -> crate::vector_db::AnnParams {
+        crate::vector_db::AnnParams {
+            nprobes: self.ann_nprobes,
+            refine_factor: self.ann_refine_factor,
         }
     }
+
+    /// Score boost for documentation files in hybrid search
+    pub fn doc_boost(&self) -> crate::vector_db::DocBoost {
+        crate::vector_db::DocBoost::new(self.doc_boost, &self.doc_patterns)
+    }
 }
 
 impl Default for CacheConfig {
@@ -244,6 +748,35 @@ impl Default for CacheConfig {
         Self {
             hash_cache_path: default_hash_cache_path(),
             git_cache_path: default_git_cache_path(),
+            format: CacheFormat::default(),
+            persist_query_embeddings: false,
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_results: default_max_results(),
+        }
+    }
+}
+
+impl Default for RelationsConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size: default_relations_max_file_size(),
+        }
+    }
+}
+
+impl Default for Bm25Config {
+    fn default() -> Self {
+        Self {
+            stemming: false,
+            language: default_bm25_language(),
+            auto_unlock: default_bm25_auto_unlock(),
+            stale_lock_secs: default_bm25_stale_lock_secs(),
         }
     }
 }
@@ -303,6 +836,27 @@ impl Config {
         self.save(&config_path)
     }
 
+    /// Analyzer BM25 indexes are built with: `search.bm25_analyzer`, stemmed
+    /// if `bm25.stemming` is set
+    pub fn bm25_analyzer(&self) -> String {
+        if self.bm25.stemming {
+            crate::bm25_search::stemmed_analyzer_name(
+                &self.search.bm25_analyzer,
+                &self.bm25.language,
+            )
+        } else {
+            self.search.bm25_analyzer.clone()
+        }
+    }
+
+    /// Heartbeat age after which BM25 lock files of a previous writer are
+    /// removed, or `None` if `bm25.auto_unlock` is disabled
+    pub fn bm25_stale_lock_timeout(&self) -> Option<std::time::Duration> {
+        self.bm25
+            .auto_unlock
+            .then(|| std::time::Duration::from_secs(self.bm25.stale_lock_secs))
+    }
+
     /// Validate configuration values
     pub fn validate(&self) -> Result<(), RagError> {
         // Validate vector DB backend
@@ -317,10 +871,40 @@ impl Config {
             .into());
         }
 
-        // Validate batch size
-        if self.embedding.batch_size == 0 {
+        // Validate batch size cap (batch_size = 0 means auto)
+        if self.embedding.max_batch_size == 0 {
             return Err(ConfigError::InvalidValue {
-                key: "embedding.batch_size".to_string(),
+                key: "embedding.max_batch_size".to_string(),
+                reason: "must be greater than 0".to_string(),
+            }
+            .into());
+        }
+
+        // Validate remote embedding API URL
+        if let Some(api_url) = &self.embedding.api_url
+            && !api_url.starts_with("http://")
+            && !api_url.starts_with("https://")
+        {
+            return Err(ConfigError::InvalidValue {
+                key: "embedding.api_url".to_string(),
+                reason: "must be an http:// or https:// URL".to_string(),
+            }
+            .into());
+        }
+
+        // Validate embedding parallelism
+        if self.embedding.parallelism == 0 {
+            return Err(ConfigError::InvalidValue {
+                key: "embedding.parallelism".to_string(),
+                reason: "must be greater than 0".to_string(),
+            }
+            .into());
+        }
+
+        // Validate max input tokens
+        if self.embedding.max_input_tokens == Some(0) {
+            return Err(ConfigError::InvalidValue {
+                key: "embedding.max_input_tokens".to_string(),
                 reason: "must be greater than 0".to_string(),
             }
             .into());
@@ -335,6 +919,24 @@ impl Config {
             .into());
         }
 
+        // Validate token budget
+        if self.indexing.max_chunk_tokens == 0 {
+            return Err(ConfigError::InvalidValue {
+                key: "indexing.max_chunk_tokens".to_string(),
+                reason: "must be greater than 0".to_string(),
+            }
+            .into());
+        }
+
+        // Validate chunk overlap
+        if self.indexing.chunk_overlap >= self.indexing.chunk_size {
+            return Err(ConfigError::InvalidValue {
+                key: "indexing.chunk_overlap".to_string(),
+                reason: "must be less than indexing.chunk_size".to_string(),
+            }
+            .into());
+        }
+
         // Validate max file size
         if self.indexing.max_file_size == 0 {
             return Err(ConfigError::InvalidValue {
@@ -344,6 +946,49 @@ impl Config {
             .into());
         }
 
+        // Validate indexing time limit
+        if !self.indexing.max_duration_secs.is_finite() || self.indexing.max_duration_secs < 0.0 {
+            return Err(ConfigError::InvalidValue {
+                key: "indexing.max_duration_secs".to_string(),
+                reason: format!(
+                    "must be a non-negative number of seconds, got {}",
+                    self.indexing.max_duration_secs
+                ),
+            }
+            .into());
+        }
+
+        // Validate excluded directory names
+        if self
+            .indexing
+            .excluded_dirs
+            .iter()
+            .any(|d| d.trim().is_empty())
+        {
+            return Err(ConfigError::InvalidValue {
+                key: "indexing.excluded_dirs".to_string(),
+                reason: "entries cannot be empty".to_string(),
+            }
+            .into());
+        }
+
+        // Validate text detection sample size
+        if self.indexing.text_detection_sample_kb == 0 {
+            return Err(ConfigError::InvalidValue {
+                key: "indexing.text_detection_sample_kb".to_string(),
+                reason: "must be greater than 0".to_string(),
+            }
+            .into());
+        }
+
+        if self.indexing.read_concurrency == 0 {
+            return Err(ConfigError::InvalidValue {
+                key: "indexing.read_concurrency".to_string(),
+                reason: "must be greater than 0".to_string(),
+            }
+            .into());
+        }
+
         // Validate min_score range
         if !(0.0..=1.0).contains(&self.search.min_score) {
             return Err(ConfigError::InvalidValue {
@@ -362,6 +1007,130 @@ impl Config {
             .into());
         }
 
+        // Validate ANN tuning
+        if self.search.ann_nprobes == Some(0) {
+            return Err(ConfigError::InvalidValue {
+                key: "search.ann_nprobes".to_string(),
+                reason: "must be greater than 0".to_string(),
+            }
+            .into());
+        }
+        if self.search.ann_refine_factor == Some(0) {
+            return Err(ConfigError::InvalidValue {
+                key: "search.ann_refine_factor".to_string(),
+                reason: "must be greater than 0".to_string(),
+            }
+            .into());
+        }
+
+        // Validate BM25 analyzer
+        if !crate::bm25_search::BM25_ANALYZERS.contains(&self.search.bm25_analyzer.as_str()) {
+            return Err(ConfigError::InvalidValue {
+                key: "search.bm25_analyzer".to_string(),
+                reason: format!(
+                    "must be one of {}, got '{}'",
+                    crate::bm25_search::BM25_ANALYZERS.join(", "),
+                    self.search.bm25_analyzer
+                ),
+            }
+            .into());
+        }
+        if self.bm25.stemming
+            && !crate::bm25_search::BM25_LANGUAGES
+                .contains(&self.bm25.language.to_lowercase().as_str())
+        {
+            return Err(ConfigError::InvalidValue {
+                key: "bm25.language".to_string(),
+                reason: format!(
+                    "must be one of {}, got '{}'",
+                    crate::bm25_search::BM25_LANGUAGES.join(", "),
+                    self.bm25.language
+                ),
+            }
+            .into());
+        }
+        // A live writer refreshes its heartbeat this often, so a shorter
+        // threshold would take it for dead
+        let heartbeat_interval = crate::bm25_search::HEARTBEAT_INTERVAL.as_secs();
+        if self.bm25.stale_lock_secs <= heartbeat_interval {
+            return Err(ConfigError::InvalidValue {
+                key: "bm25.stale_lock_secs".to_string(),
+                reason: format!(
+                    "must be greater than the {} second writer heartbeat interval, got {}",
+                    heartbeat_interval, self.bm25.stale_lock_secs
+                ),
+            }
+            .into());
+        }
+
+        // Validate documentation boost
+        if !(self.search.doc_boost.is_finite() && self.search.doc_boost > 0.0) {
+            return Err(ConfigError::InvalidValue {
+                key: "search.doc_boost".to_string(),
+                reason: format!("must be greater than 0.0, got {}", self.search.doc_boost),
+            }
+            .into());
+        }
+        if let Some(pattern) = self
+            .search
+            .doc_patterns
+            .iter()
+            .find(|pattern| globset::Glob::new(pattern).is_err())
+        {
+            return Err(ConfigError::InvalidValue {
+                key: "search.doc_patterns".to_string(),
+                reason: format!("invalid glob pattern '{}'", pattern),
+            }
+            .into());
+        }
+
+        // Validate ANN index shape
+        if self.vector_db.ann_num_partitions == Some(0) {
+            return Err(ConfigError::InvalidValue {
+                key: "vector_db.ann_num_partitions".to_string(),
+                reason: "must be greater than 0".to_string(),
+            }
+            .into());
+        }
+        if self.vector_db.ann_num_sub_vectors == Some(0) {
+            return Err(ConfigError::InvalidValue {
+                key: "vector_db.ann_num_sub_vectors".to_string(),
+                reason: "must be greater than 0".to_string(),
+            }
+            .into());
+        }
+
+        // Validate rerank candidate count
+        if self.search.rerank_candidates == 0 {
+            return Err(ConfigError::InvalidValue {
+                key: "search.rerank_candidates".to_string(),
+                reason: "must be greater than 0".to_string(),
+            }
+            .into());
+        }
+
+        // Validate result cap
+        if !(1..=crate::types::MAX_QUERY_LIMIT).contains(&self.server.max_results) {
+            return Err(ConfigError::InvalidValue {
+                key: "server.max_results".to_string(),
+                reason: format!(
+                    "must be between 1 and {}, got {}",
+                    crate::types::MAX_QUERY_LIMIT,
+                    self.server.max_results
+                ),
+            }
+            .into());
+        }
+
+        // Validate relations file size limit
+        if self.relations.max_file_size == 0 {
+            return Err(ConfigError::InvalidValue {
+                key: "relations.max_file_size".to_string(),
+                reason: "must be greater than 0".to_string(),
+            }
+            .into());
+        }
+
         Ok(())
     }
 
@@ -387,6 +1156,26 @@ impl Config {
             self.embedding.model_name = model;
         }
 
+        // Model cache directory
+        if let Ok(dir) = std::env::var("PROJECT_RAG_MODEL_CACHE_DIR") {
+            self.embedding.model_cache_dir = Some(PathBuf::from(dir));
+        }
+
+        // Remote embedding API key
+        if let Ok(api_key) = std::env::var("PROJECT_RAG_EMBED_API_KEY") {
+            self.embedding.api_key = Some(api_key);
+        }
+
+        // Embedding device
+        if let Ok(device) = std::env::var("PROJECT_RAG_DEVICE") {
+            match device.to_lowercase().as_str() {
+                "cpu" => self.embedding.device = EmbeddingDevice::Cpu,
+                "cuda" => self.embedding.device = EmbeddingDevice::Cuda,
+                "coreml" => self.embedding.device = EmbeddingDevice::CoreMl,
+                _ => tracing::warn!("Ignoring unknown PROJECT_RAG_DEVICE '{}'", device),
+            }
+        }
+
         // Batch size
         if let Ok(batch_size) = std::env::var("PROJECT_RAG_BATCH_SIZE")
             && let Ok(size) = batch_size.parse()
@@ -400,6 +1189,29 @@ impl Config {
         {
             self.search.min_score = score;
         }
+
+        // Hash cache format
+        if let Ok(format) = std::env::var("PROJECT_RAG_CACHE_FORMAT") {
+            match format.to_lowercase().as_str() {
+                "pretty" => self.cache.format = CacheFormat::Pretty,
+                "compact" => self.cache.format = CacheFormat::Compact,
+                _ => tracing::warn!("Ignoring unknown PROJECT_RAG_CACHE_FORMAT '{}'", format),
+            }
+        }
+
+        // Result cap
+        if let Ok(max_results) = std::env::var("PROJECT_RAG_MAX_RESULTS")
+            && let Ok(max) = max_results.parse()
+        {
+            self.server.max_results = max;
+        }
+
+        // Phase spans
+        if let Ok(phase_spans) = std::env::var("PROJECT_RAG_PHASE_SPANS")
+            && let Ok(enabled) = phase_spans.parse()
+        {
+            self.observability.phase_spans = enabled;
+        }
     }
 
     /// Create a new Config with defaults and environment overrides
@@ -414,9 +1226,20 @@ impl Config {
 // Tests are inline in this module
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn test_config_placeholder() {
         // Placeholder for config tests
         // TODO: Add comprehensive config tests
     }
+
+    #[test]
+    fn test_embedding_device_serde() {
+        let device: EmbeddingDevice = serde_json::from_str("\"coreml\"").unwrap();
+        assert_eq!(device, EmbeddingDevice::CoreMl);
+        assert_eq!(device.to_string(), "coreml");
+        assert!(device.is_gpu());
+        assert!(!EmbeddingDevice::default().is_gpu());
+    }
 }
@@ -0,0 +1,157 @@
+//! Embedding batch size tuned from observed throughput
+//!
+//! With `embedding.batch_size = 0`, indexing starts with small batches and
+//! doubles them while the time per embedded item keeps dropping. Once a larger
+//! batch stops paying off, the best size seen so far is kept for the rest of
+//! the run. Timeouts halve the size, so memory-constrained machines back off.
+
+use std::time::Duration;
+
+/// Batch size the search starts from
+const INITIAL_BATCH_SIZE: usize = 8;
+
+/// Relative drop in per-item latency a doubled batch must achieve to keep growing
+const MIN_IMPROVEMENT: f64 = 0.1;
+
+/// Batch size adapting to the measured latency of each embedding batch
+#[derive(Debug, Clone)]
+pub struct AdaptiveBatchSize {
+    size: usize,
+    max: usize,
+    /// Lowest per-item latency seen, with the batch size it was seen at
+    best: Option<(Duration, usize)>,
+    settled: bool,
+}
+
+impl AdaptiveBatchSize {
+    /// Start tuning, never exceeding `max` items per batch
+    pub fn new(max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            size: INITIAL_BATCH_SIZE.min(max),
+            max,
+            best: None,
+            settled: false,
+        }
+    }
+
+    /// Size of the next batch
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether growing has stopped
+    pub fn is_settled(&self) -> bool {
+        self.settled
+    }
+
+    /// Record that a batch of `items` was embedded in `elapsed`
+    ///
+    /// Batches smaller than the current size (the tail of a run) say nothing
+    /// about the current size and are ignored.
+    pub fn record(&mut self, items: usize, elapsed: Duration) {
+        if self.settled || items == 0 || items < self.size {
+            return;
+        }
+
+        let per_item = elapsed / items as u32;
+        let improved = self.best.is_none_or(|(best, _)| {
+            per_item.as_secs_f64() < best.as_secs_f64() * (1.0 - MIN_IMPROVEMENT)
+        });
+
+        if improved {
+            self.best = Some((per_item, self.size));
+            if self.size >= self.max {
+                self.settle(self.size);
+            } else {
+                self.size = (self.size * 2).min(self.max);
+            }
+        } else {
+            let best_size = self.best.map_or(self.size, |(_, size)| size);
+            self.settle(best_size);
+        }
+    }
+
+    /// Record that a batch timed out, halving the size
+    pub fn record_timeout(&mut self) {
+        let size = (self.size / 2).max(1);
+        tracing::warn!(
+            "Embedding batch of {} timed out, backing off to {}",
+            self.size,
+            size
+        );
+        self.best = None;
+        self.settle(size);
+    }
+
+    fn settle(&mut self, size: usize) {
+        self.size = size;
+        self.settled = true;
+        tracing::info!("Using embedding batch size {}", size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Latency of a batch with a fixed overhead plus a per-item cost
+    fn latency(items: usize, overhead_ms: u64, per_item_ms: u64) -> Duration {
+        Duration::from_millis(overhead_ms + per_item_ms * items as u64)
+    }
+
+    #[test]
+    fn test_grows_while_latency_per_item_improves() {
+        let mut sizer = AdaptiveBatchSize::new(256);
+        assert_eq!(sizer.size(), INITIAL_BATCH_SIZE);
+
+        // Doubling stops paying off once the per-item cost dominates the overhead
+        while !sizer.is_settled() {
+            let size = sizer.size();
+            sizer.record(size, latency(size, 100, 10));
+        }
+        assert_eq!(sizer.size(), 64);
+    }
+
+    #[test]
+    fn test_capped_by_max() {
+        let mut sizer = AdaptiveBatchSize::new(20);
+        while !sizer.is_settled() {
+            let size = sizer.size();
+            sizer.record(size, latency(size, 10_000, 1));
+        }
+        assert_eq!(sizer.size(), 20);
+    }
+
+    #[test]
+    fn test_settles_on_best_size_when_latency_stops_improving() {
+        let mut sizer = AdaptiveBatchSize::new(256);
+        sizer.record(8, latency(8, 0, 10));
+        assert_eq!(sizer.size(), 16);
+        sizer.record(16, latency(16, 0, 10));
+        assert!(sizer.is_settled());
+        assert_eq!(sizer.size(), 8);
+    }
+
+    #[test]
+    fn test_timeout_halves_size() {
+        let mut sizer = AdaptiveBatchSize::new(256);
+        sizer.record(8, latency(8, 800, 1));
+        sizer.record(16, latency(16, 800, 1));
+        assert_eq!(sizer.size(), 32);
+
+        sizer.record_timeout();
+        assert!(sizer.is_settled());
+        assert_eq!(sizer.size(), 16);
+        sizer.record_timeout();
+        assert_eq!(sizer.size(), 8);
+    }
+
+    #[test]
+    fn test_partial_batches_are_ignored() {
+        let mut sizer = AdaptiveBatchSize::new(256);
+        sizer.record(3, latency(3, 0, 1));
+        assert_eq!(sizer.size(), INITIAL_BATCH_SIZE);
+        assert!(!sizer.is_settled());
+    }
+}
@@ -1,14 +1,62 @@
 use super::EmbeddingProvider;
+use crate::config::{EmbeddingConfig, EmbeddingDevice};
+use crate::error::{EmbeddingError, RagError};
 use anyhow::{Context, Result};
-use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use fastembed::{
+    EmbeddingModel, InitOptions, InitOptionsUserDefined, Pooling, TextEmbedding, TokenizerFiles,
+    UserDefinedEmbeddingModel,
+};
+use ort::execution_providers::{
+    CUDAExecutionProvider, CoreMLExecutionProvider, ExecutionProviderDispatch,
+};
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Files required to load a model from a local directory
+const LOCAL_MODEL_FILES: [&str; 5] = [
+    "model.onnx",
+    "tokenizer.json",
+    "config.json",
+    "special_tokens_map.json",
+    "tokenizer_config.json",
+];
+
+/// Fewest chunks embedded per call when the model runs on a GPU
+///
+/// GPUs only reach their throughput with larger batches, while the small CPU
+/// default exists for cancellation responsiveness.
+pub const GPU_MIN_BATCH_SIZE: usize = 64;
+
+/// Execution providers to register for `device`
+///
+/// Providers are registered with `error_on_failure` so an unavailable GPU
+/// surfaces as an error (and a CPU fallback) instead of a silent slowdown.
+pub(super) fn execution_providers(device: EmbeddingDevice) -> Vec<ExecutionProviderDispatch> {
+    match device {
+        EmbeddingDevice::Cpu => Vec::new(),
+        EmbeddingDevice::Cuda => vec![CUDAExecutionProvider::default().build().error_on_failure()],
+        EmbeddingDevice::CoreMl => vec![
+            CoreMLExecutionProvider::default()
+                .build()
+                .error_on_failure(),
+        ],
+    }
+}
 
 /// FastEmbed-based embedding provider using all-MiniLM-L6-v2
 ///
 /// Uses RwLock for safe interior mutability since fastembed's embed() requires &mut self.
+/// Each lock guards one model session; with `embedding.parallelism` sessions,
+/// that many batches can be embedded at the same time.
 pub struct FastEmbedManager {
-    model: RwLock<TextEmbedding>,
+    sessions: Vec<RwLock<TextEmbedding>>,
+    /// Session handed out next when every session is busy (round robin)
+    next_session: AtomicUsize,
     dimension: usize,
+    model_name: String,
+    max_sequence_length: usize,
+    device: EmbeddingDevice,
 }
 
 impl FastEmbedManager {
@@ -18,7 +66,37 @@ impl FastEmbedManager {
     }
 
     /// Create a new FastEmbedManager from a model name string
-    pub fn from_model_name(model_name: &str) -> Result<Self> {
+    ///
+    /// If `model_name` points to an existing directory, the model is loaded
+    /// from that directory without any network access. The model runs on
+    /// `device`, or on the CPU if that device's execution provider fails.
+    pub fn from_model_name(model_name: &str, device: EmbeddingDevice) -> Result<Self> {
+        Self::from_model_name_with_cache(model_name, None, device, 1)
+    }
+
+    /// Create a new FastEmbedManager from the embedding configuration
+    ///
+    /// Loads `config.parallelism` sessions of the model.
+    pub fn from_config(config: &EmbeddingConfig) -> Result<Self> {
+        Self::from_model_name_with_cache(
+            &config.model_name,
+            config.model_cache_dir.as_deref(),
+            config.device,
+            config.parallelism,
+        )
+    }
+
+    fn from_model_name_with_cache(
+        model_name: &str,
+        cache_dir: Option<&Path>,
+        device: EmbeddingDevice,
+        sessions: usize,
+    ) -> Result<Self> {
+        let local_dir = Path::new(model_name);
+        if local_dir.is_dir() {
+            return Self::from_local_dir_with_sessions(local_dir, device, sessions);
+        }
+
         let model = match model_name {
             "all-MiniLM-L6-v2" => EmbeddingModel::AllMiniLML6V2,
             "all-MiniLM-L12-v2" => EmbeddingModel::AllMiniLML12V2,
@@ -32,34 +110,203 @@ impl FastEmbedManager {
                 EmbeddingModel::AllMiniLML6V2
             }
         };
-        Self::with_model(model)
+        Self::with_model_and_cache(model, cache_dir.map(Path::to_path_buf), device, sessions)
     }
 
     /// Create a new FastEmbedManager with a specific model
     pub fn with_model(model: EmbeddingModel) -> Result<Self> {
-        tracing::info!("Initializing FastEmbed model: {:?}", model);
-
-        // all-MiniLM-L6-v2 has 384 dimensions
-        let dimension = match model {
-            EmbeddingModel::AllMiniLML6V2 => 384,
-            EmbeddingModel::AllMiniLML12V2 => 384,
-            EmbeddingModel::BGEBaseENV15 => 768,
-            EmbeddingModel::BGESmallENV15 => 384,
-            _ => 384, // Default to 384 for unknown models
+        Self::with_model_and_cache(model, None, EmbeddingDevice::Cpu, 1)
+    }
+
+    fn with_model_and_cache(
+        model: EmbeddingModel,
+        cache_dir: Option<PathBuf>,
+        device: EmbeddingDevice,
+        sessions: usize,
+    ) -> Result<Self> {
+        tracing::info!("Initializing FastEmbed model: {:?} on {}", model, device);
+
+        // all-MiniLM-L6-v2 has 384 dimensions and was trained on 256-token sequences
+        let (dimension, model_name, max_sequence_length) = match model {
+            EmbeddingModel::AllMiniLML6V2 => (384, "all-MiniLM-L6-v2", 256),
+            EmbeddingModel::AllMiniLML12V2 => (384, "all-MiniLM-L12-v2", 256),
+            EmbeddingModel::BGEBaseENV15 => (768, "BAAI/bge-base-en-v1.5", 512),
+            EmbeddingModel::BGESmallENV15 => (384, "BAAI/bge-small-en-v1.5", 512),
+            _ => (384, "all-MiniLM-L6-v2", 256), // Default to 384 for unknown models
+        };
+
+        let cache_dir = cache_dir.unwrap_or_else(|| InitOptions::default().cache_dir);
+        let init = |device: EmbeddingDevice| {
+            let mut options = InitOptions::default();
+            options.model_name = model.clone();
+            options.show_download_progress = true;
+            options.cache_dir = cache_dir.clone();
+            options.execution_providers = execution_providers(device);
+            TextEmbedding::try_new(options)
+        };
+
+        let (sessions, device) = match init_sessions(sessions, device, init) {
+            Ok(initialized) => initialized,
+            Err(e) if is_download_failure(&e) => {
+                return Err(RagError::from(EmbeddingError::ModelUnavailable {
+                    model: model_name.to_string(),
+                    cache_dir: cache_dir.display().to_string(),
+                    reason: format!("{:#}", e),
+                })
+                .into());
+            }
+            Err(e) => return Err(e).context("Failed to initialize FastEmbed model"),
+        };
+
+        Ok(Self {
+            sessions,
+            next_session: AtomicUsize::new(0),
+            dimension,
+            model_name: model_name.to_string(),
+            max_sequence_length,
+            device,
+        })
+    }
+
+    /// Load a model from a local directory containing the ONNX model and tokenizer files
+    ///
+    /// This never touches the network, which makes it suitable for air-gapped
+    /// deployments. The embedding dimension is detected from the model output.
+    pub fn from_local_dir(dir: &Path, device: EmbeddingDevice) -> Result<Self> {
+        Self::from_local_dir_with_sessions(dir, device, 1)
+    }
+
+    fn from_local_dir_with_sessions(
+        dir: &Path,
+        device: EmbeddingDevice,
+        sessions: usize,
+    ) -> Result<Self> {
+        tracing::info!(
+            "Loading embedding model from local directory: {}",
+            dir.display()
+        );
+
+        let read = |name: &str| -> Result<Vec<u8>> {
+            let path = dir.join(name);
+            std::fs::read(&path).map_err(|e| {
+                RagError::from(EmbeddingError::InitializationFailed(format!(
+                    "local model directory '{}' is missing '{}' (expected files: {}): {}",
+                    dir.display(),
+                    name,
+                    LOCAL_MODEL_FILES.join(", "),
+                    e
+                )))
+                .into()
+            })
         };
 
-        let mut options = InitOptions::default();
-        options.model_name = model;
-        options.show_download_progress = true;
+        let tokenizer_files = TokenizerFiles {
+            tokenizer_file: read("tokenizer.json")?,
+            config_file: read("config.json")?,
+            special_tokens_map_file: read("special_tokens_map.json")?,
+            tokenizer_config_file: read("tokenizer_config.json")?,
+        };
+        let user_model = UserDefinedEmbeddingModel::new(read("model.onnx")?, tokenizer_files)
+            .with_pooling(Pooling::Mean);
 
-        let embedding_model =
-            TextEmbedding::try_new(options).context("Failed to initialize FastEmbed model")?;
+        let max_sequence_length = InitOptionsUserDefined::default().max_length;
+        let init = |device: EmbeddingDevice| {
+            let mut options = InitOptionsUserDefined::default();
+            options.execution_providers = execution_providers(device);
+            TextEmbedding::try_new_from_user_defined(user_model.clone(), options)
+        };
+
+        let (mut sessions, device) = init_sessions(sessions, device, init)
+            .with_context(|| format!("Failed to load local model from {}", dir.display()))?;
+
+        let dimension = sessions[0]
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .embed(vec!["dimension probe".to_string()], None)
+            .context("Failed to probe local model dimension")?
+            .first()
+            .map(|embedding| embedding.len())
+            .ok_or_else(|| anyhow::anyhow!("Local model produced no embedding"))?;
 
         Ok(Self {
-            model: RwLock::new(embedding_model),
+            sessions,
+            next_session: AtomicUsize::new(0),
             dimension,
+            model_name: dir.to_string_lossy().to_string(),
+            max_sequence_length,
+            device,
         })
     }
+
+    /// Device the model actually runs on (the CPU after a failed GPU setup)
+    pub fn device(&self) -> EmbeddingDevice {
+        self.device
+    }
+
+    /// Number of model sessions, i.e. batches that can be embedded concurrently
+    pub fn sessions(&self) -> usize {
+        self.sessions.len()
+    }
+}
+
+/// Initialize `count` sessions of a model, the first with a CPU fallback
+///
+/// The remaining sessions are created on whichever device the first one ended up on.
+fn init_sessions<T>(
+    count: usize,
+    device: EmbeddingDevice,
+    init: impl Fn(EmbeddingDevice) -> Result<T>,
+) -> Result<(Vec<RwLock<T>>, EmbeddingDevice)> {
+    let (first, device) = init_with_cpu_fallback(device, &init)?;
+    let mut sessions = vec![RwLock::new(first)];
+    for _ in 1..count {
+        sessions.push(RwLock::new(init(device)?));
+    }
+    Ok((sessions, device))
+}
+
+/// Initialize a model on `device`, retrying on the CPU if its execution
+/// provider fails to initialize
+///
+/// Returns the model with the device it ended up on. Download failures are
+/// returned as-is, since the CPU would fail the same way.
+fn init_with_cpu_fallback<T>(
+    device: EmbeddingDevice,
+    init: impl Fn(EmbeddingDevice) -> Result<T>,
+) -> Result<(T, EmbeddingDevice)> {
+    match init(device) {
+        Ok(model) => Ok((model, device)),
+        Err(e) if !device.is_gpu() || is_download_failure(&e) => Err(e),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to initialize the {} execution provider, falling back to CPU: {:#}",
+                device,
+                e
+            );
+            let model = init(EmbeddingDevice::Cpu)?;
+            Ok((model, EmbeddingDevice::Cpu))
+        }
+    }
+}
+
+/// Detect whether a model initialization error was caused by a failed
+/// download (offline, DNS failure, blocked proxy, ...)
+///
+/// fastembed wraps model file downloads in a "Failed to retrieve <file>"
+/// context, while tokenizer downloads surface hf-hub's `ApiError` as-is.
+fn is_download_failure(err: &anyhow::Error) -> bool {
+    const PREFIXES: [&str; 5] = [
+        "Failed to retrieve ",
+        "request error: ",
+        "Too many retries: ",
+        "Native tls: ",
+        "Lock acquisition failed: ",
+    ];
+
+    err.chain().any(|cause| {
+        let message = cause.to_string();
+        PREFIXES.iter().any(|prefix| message.starts_with(prefix))
+    })
 }
 
 impl EmbeddingProvider for FastEmbedManager {
@@ -70,12 +317,23 @@ impl EmbeddingProvider for FastEmbedManager {
 
         tracing::debug!("Generating embeddings for {} texts", texts.len());
 
-        // Acquire write lock safely. If the lock is poisoned (due to a panic while holding
-        // the lock), we recover by taking ownership of the inner value.
-        let mut model = self.model.write().unwrap_or_else(|poisoned| {
-            tracing::warn!("FastEmbed model lock was poisoned, recovering...");
-            poisoned.into_inner()
-        });
+        // Take the first idle session, or wait for the next one in turn if all are busy.
+        // If the lock is poisoned (due to a panic while holding the lock), we recover by
+        // taking ownership of the inner value.
+        let idle = self
+            .sessions
+            .iter()
+            .find_map(|session| session.try_write().ok());
+        let mut model = match idle {
+            Some(model) => model,
+            None => {
+                let idx = self.next_session.fetch_add(1, Ordering::Relaxed) % self.sessions.len();
+                self.sessions[idx].write().unwrap_or_else(|poisoned| {
+                    tracing::warn!("FastEmbed model lock was poisoned, recovering...");
+                    poisoned.into_inner()
+                })
+            }
+        };
 
         // Generate embeddings using the mutable reference
         // Note: For timeout protection, wrap calls to this method in tokio::time::timeout
@@ -92,7 +350,15 @@ impl EmbeddingProvider for FastEmbedManager {
     }
 
     fn model_name(&self) -> &str {
-        "all-MiniLM-L6-v2"
+        &self.model_name
+    }
+
+    fn max_sequence_length(&self) -> usize {
+        self.max_sequence_length
+    }
+
+    fn device(&self) -> EmbeddingDevice {
+        self.device
     }
 }
 
@@ -139,6 +405,12 @@ mod tests {
         assert_eq!(manager.model_name(), "all-MiniLM-L6-v2");
     }
 
+    #[test]
+    fn test_max_sequence_length() {
+        let manager = FastEmbedManager::new().unwrap();
+        assert_eq!(manager.max_sequence_length(), 256);
+    }
+
     #[test]
     fn test_default() {
         let manager = FastEmbedManager::default();
@@ -183,4 +455,117 @@ mod tests {
         let manager = FastEmbedManager::with_model(EmbeddingModel::BGESmallENV15).unwrap();
         assert_eq!(manager.dimension(), 384);
     }
+
+    #[test]
+    #[ignore = "downloads the default model to build the local directory fixture"]
+    fn test_from_model_name_local_directory() {
+        // Make sure the default model is in the FastEmbed cache, then copy its
+        // files into a standalone directory and load from there
+        FastEmbedManager::new().unwrap();
+        let cache_dir = InitOptions::default().cache_dir;
+        let snapshot = walkdir::WalkDir::new(&cache_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .find(|e| {
+                e.file_name() == "model.onnx"
+                    && e.path().to_string_lossy().contains("all-MiniLM-L6-v2")
+            })
+            .and_then(|e| e.path().parent().map(Path::to_path_buf))
+            .expect("default model should be cached after initialization");
+
+        let local_dir = tempfile::TempDir::new().unwrap();
+        for name in LOCAL_MODEL_FILES {
+            std::fs::copy(snapshot.join(name), local_dir.path().join(name)).unwrap();
+        }
+
+        let model_path = local_dir.path().to_string_lossy().to_string();
+        let manager = FastEmbedManager::from_model_name(&model_path, EmbeddingDevice::Cpu).unwrap();
+        assert_eq!(manager.dimension(), 384);
+        assert_eq!(manager.model_name(), model_path);
+
+        let embeddings = manager
+            .embed_batch(vec!["fn main() {}".to_string()])
+            .unwrap();
+        assert_eq!(embeddings.len(), 1);
+        assert_eq!(embeddings[0].len(), 384);
+    }
+
+    #[test]
+    fn test_from_local_dir_missing_files() {
+        let local_dir = tempfile::TempDir::new().unwrap();
+        let err = FastEmbedManager::from_local_dir(local_dir.path(), EmbeddingDevice::Cpu)
+            .err()
+            .expect("empty directory should not load");
+        assert!(format!("{:#}", err).contains("is missing"));
+    }
+
+    #[test]
+    fn test_gpu_device_matches_cpu_embeddings() {
+        // Without a usable GPU the CUDA request falls back to the CPU; either
+        // way the embeddings must agree with the CPU ones
+        let cpu =
+            FastEmbedManager::from_model_name("all-MiniLM-L6-v2", EmbeddingDevice::Cpu).unwrap();
+        let gpu =
+            FastEmbedManager::from_model_name("all-MiniLM-L6-v2", EmbeddingDevice::Cuda).unwrap();
+        assert_eq!(cpu.device(), EmbeddingDevice::Cpu);
+
+        let texts = vec![
+            "fn main() { println!(\"Hello, world!\"); }".to_string(),
+            "def greet(name): return f'hi {name}'".to_string(),
+        ];
+        let expected = cpu.embed_batch(texts.clone()).unwrap();
+        let actual = gpu.embed_batch(texts).unwrap();
+
+        assert_eq!(expected.len(), actual.len());
+        for (expected, actual) in expected.iter().zip(&actual) {
+            for (e, a) in expected.iter().zip(actual) {
+                assert!((e - a).abs() < 1e-3, "{} differs from CPU {}", a, e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel_sessions_embed_concurrently() {
+        let config = EmbeddingConfig {
+            parallelism: 3,
+            ..EmbeddingConfig::default()
+        };
+        let manager = FastEmbedManager::from_config(&config).unwrap();
+        assert_eq!(manager.sessions(), 3);
+
+        let expected = manager
+            .embed_batch(vec!["fn parse() {}".to_string()])
+            .unwrap();
+        let results: Vec<Vec<Vec<f32>>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..6)
+                .map(|_| scope.spawn(|| manager.embed_batch(vec!["fn parse() {}".to_string()])))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap().unwrap())
+                .collect()
+        });
+        for embeddings in results {
+            for (e, a) in expected[0].iter().zip(&embeddings[0]) {
+                assert!((e - a).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_download_failure() {
+        let offline = anyhow::anyhow!("dns error: failed to lookup address information")
+            .context("Failed to retrieve model.onnx");
+        assert!(is_download_failure(&offline));
+
+        let tokenizer = anyhow::anyhow!("request error: Connection refused");
+        assert!(is_download_failure(&tokenizer));
+
+        let other = anyhow::anyhow!("invalid onnx graph");
+        assert!(!is_download_failure(&other));
+
+        let provider = anyhow::anyhow!("CUDA request failed: could not connect to device")
+            .context("Failed to create session");
+        assert!(!is_download_failure(&provider));
+    }
 }
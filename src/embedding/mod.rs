@@ -1,8 +1,18 @@
+mod batch_size;
 mod fastembed_manager;
+mod openai_compat;
+mod reranker;
+mod truncation;
 
-pub use fastembed_manager::FastEmbedManager;
+pub use batch_size::AdaptiveBatchSize;
+pub use fastembed_manager::{FastEmbedManager, GPU_MIN_BATCH_SIZE};
+pub use openai_compat::OpenAiCompatEmbedding;
+pub use reranker::{DEFAULT_RERANK_MODEL, Reranker};
+pub use truncation::{content_token_budget, estimate_tokens, truncate_for_embedding};
 
+use crate::config::{EmbeddingConfig, EmbeddingDevice};
 use anyhow::Result;
+use std::sync::Arc;
 
 /// Trait for embedding generation
 pub trait EmbeddingProvider: Send + Sync {
@@ -12,6 +22,40 @@ pub trait EmbeddingProvider: Send + Sync {
     /// Get the dimension of the embeddings
     fn dimension(&self) -> usize;
 
+    /// Get the dimension of the embeddings, failing if it can't be determined
+    ///
+    /// Providers that learn the dimension from the model at run time report
+    /// failures here; `dimension()` can only log them.
+    fn try_dimension(&self) -> Result<usize> {
+        Ok(self.dimension())
+    }
+
     /// Get the model name
     fn model_name(&self) -> &str;
+
+    /// Maximum number of tokens the model accepts per input
+    ///
+    /// Inputs longer than this are truncated before embedding. Defaults to unlimited.
+    fn max_sequence_length(&self) -> usize {
+        usize::MAX
+    }
+
+    /// Device embeddings are computed on locally
+    ///
+    /// Remote providers compute nothing locally and report the CPU.
+    fn device(&self) -> EmbeddingDevice {
+        EmbeddingDevice::Cpu
+    }
+}
+
+/// Build the embedding provider selected by the configuration
+///
+/// With `api_url` set, embeddings come from a remote OpenAI-compatible service;
+/// otherwise the model runs locally with FastEmbed.
+pub fn provider_from_config(config: &EmbeddingConfig) -> Result<Arc<dyn EmbeddingProvider>> {
+    if config.api_url.is_some() {
+        Ok(Arc::new(OpenAiCompatEmbedding::from_config(config)?))
+    } else {
+        Ok(Arc::new(FastEmbedManager::from_config(config)?))
+    }
 }
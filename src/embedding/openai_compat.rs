@@ -0,0 +1,352 @@
+use super::EmbeddingProvider;
+use crate::config::EmbeddingConfig;
+use crate::error::{EmbeddingError, RagError};
+use anyhow::Result;
+use serde::Deserialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Input embedded to learn the dimension before anything else has been embedded
+const DIMENSION_PROBE_INPUT: &str = "dimension probe";
+
+/// Embedding provider calling a remote service that speaks the OpenAI `/v1/embeddings` API
+///
+/// Each batch is sent as one request using the `input: [...]` array form. The
+/// embedding dimension is taken from the first response and cached; if
+/// `dimension()` is needed before anything has been embedded, a one-input
+/// request is sent to learn it.
+pub struct OpenAiCompatEmbedding {
+    agent: ureq::Agent,
+    endpoint: String,
+    api_key: Option<String>,
+    model_name: String,
+    timeout_secs: u64,
+    dimension: OnceLock<usize>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: usize,
+}
+
+impl OpenAiCompatEmbedding {
+    /// Create a provider for `api_url`
+    ///
+    /// `api_url` is either the API base (e.g. `http://host:8080/v1`) or the
+    /// full `/embeddings` endpoint. Requests time out after `timeout_secs`.
+    pub fn new(
+        api_url: &str,
+        api_key: Option<String>,
+        model_name: &str,
+        timeout_secs: u64,
+    ) -> Self {
+        let api_url = api_url.trim_end_matches('/');
+        let endpoint = if api_url.ends_with("/embeddings") {
+            api_url.to_string()
+        } else {
+            format!("{}/embeddings", api_url)
+        };
+        tracing::info!(
+            "Using remote embedding model '{}' at {}",
+            model_name,
+            endpoint
+        );
+
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(timeout_secs))
+                .build(),
+            endpoint,
+            api_key,
+            model_name: model_name.to_string(),
+            timeout_secs,
+            dimension: OnceLock::new(),
+        }
+    }
+
+    /// Create a provider from `embedding.api_url`, `embedding.api_key` and `embedding.model_name`
+    pub fn from_config(config: &EmbeddingConfig) -> Result<Self> {
+        let api_url = config.api_url.as_deref().ok_or_else(|| {
+            anyhow::Error::from(RagError::from(EmbeddingError::InitializationFailed(
+                "embedding.api_url is not set".to_string(),
+            )))
+        })?;
+        Ok(Self::new(
+            api_url,
+            config.api_key.clone(),
+            &config.model_name,
+            config.timeout_secs,
+        ))
+    }
+
+    fn request(&self, texts: &[String]) -> Result<EmbeddingsResponse> {
+        let mut request = self.agent.post(&self.endpoint);
+        if let Some(api_key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", api_key));
+        }
+
+        let response = request
+            .send_json(serde_json::json!({
+                "model": self.model_name,
+                "input": texts,
+            }))
+            .map_err(|e| match e {
+                ureq::Error::Status(status, response) => {
+                    let body = response.into_string().unwrap_or_default();
+                    generation_failed(format!(
+                        "{} returned HTTP {}: {}",
+                        self.endpoint,
+                        status,
+                        body.trim()
+                    ))
+                }
+                ureq::Error::Transport(transport) if is_timeout(&transport) => {
+                    anyhow::Error::from(RagError::from(EmbeddingError::Timeout(self.timeout_secs)))
+                }
+                ureq::Error::Transport(transport) => generation_failed(format!(
+                    "request to {} failed: {}",
+                    self.endpoint, transport
+                )),
+            })?;
+
+        response.into_json().map_err(|e| {
+            generation_failed(format!(
+                "{} returned an invalid embeddings response: {}",
+                self.endpoint, e
+            ))
+        })
+    }
+}
+
+impl EmbeddingProvider for OpenAiCompatEmbedding {
+    fn embed_batch(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(vec![]);
+        }
+
+        tracing::debug!(
+            "Requesting embeddings for {} texts from {}",
+            texts.len(),
+            self.endpoint
+        );
+
+        let mut data = self.request(&texts)?.data;
+        if data.len() != texts.len() {
+            return Err(generation_failed(format!(
+                "{} returned {} embeddings for {} inputs",
+                self.endpoint,
+                data.len(),
+                texts.len()
+            )));
+        }
+        data.sort_by_key(|item| item.index);
+
+        let dimension = *self.dimension.get_or_init(|| data[0].embedding.len());
+        if let Some(item) = data.iter().find(|item| item.embedding.len() != dimension) {
+            return Err(anyhow::Error::from(RagError::from(
+                EmbeddingError::DimensionMismatch {
+                    expected: dimension,
+                    actual: item.embedding.len(),
+                },
+            )));
+        }
+
+        Ok(data.into_iter().map(|item| item.embedding).collect())
+    }
+
+    fn dimension(&self) -> usize {
+        self.try_dimension().unwrap_or_else(|e| {
+            tracing::error!("Failed to determine remote embedding dimension: {:#}", e);
+            0
+        })
+    }
+
+    fn try_dimension(&self) -> Result<usize> {
+        if let Some(dimension) = self.dimension.get() {
+            return Ok(*dimension);
+        }
+        self.embed_batch(vec![DIMENSION_PROBE_INPUT.to_string()])?;
+        self.dimension.get().copied().ok_or_else(|| {
+            generation_failed(format!(
+                "{} returned no embedding for the dimension probe",
+                self.endpoint
+            ))
+        })
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+}
+
+fn generation_failed(message: String) -> anyhow::Error {
+    anyhow::Error::from(RagError::from(EmbeddingError::GenerationFailed(message)))
+}
+
+fn is_timeout(transport: &ureq::Transport) -> bool {
+    std::error::Error::source(transport)
+        .and_then(|source| source.downcast_ref::<std::io::Error>())
+        .is_some_and(|e| {
+            matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// Serve `responses` in order from a local HTTP server, recording each request body
+    fn serve(responses: Vec<(u16, String)>) -> (String, Arc<Mutex<Vec<serde_json::Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut content_length = 0;
+                let mut authorization = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    let (name, value) = line.split_once(':').unwrap_or((line, ""));
+                    match name.to_ascii_lowercase().as_str() {
+                        "content-length" => content_length = value.trim().parse().unwrap(),
+                        "authorization" => authorization = Some(value.trim().to_string()),
+                        _ => {}
+                    }
+                }
+                let mut request_body = vec![0; content_length];
+                reader.read_exact(&mut request_body).unwrap();
+                let mut request: serde_json::Value = serde_json::from_slice(&request_body).unwrap();
+                request["authorization"] = serde_json::json!(authorization);
+                recorded.lock().unwrap().push(request);
+
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn embeddings_body(embeddings: &[(usize, Vec<f32>)]) -> String {
+        let data: Vec<_> = embeddings
+            .iter()
+            .map(|(index, embedding)| serde_json::json!({ "index": index, "embedding": embedding }))
+            .collect();
+        serde_json::json!({ "object": "list", "data": data }).to_string()
+    }
+
+    #[test]
+    fn test_embed_batch_sends_input_array_and_orders_by_index() {
+        let (url, requests) = serve(vec![(
+            200,
+            embeddings_body(&[(1, vec![0.0, 1.0, 0.0]), (0, vec![1.0, 0.0, 0.0])]),
+        )]);
+        let provider =
+            OpenAiCompatEmbedding::new(&url, Some("secret".to_string()), "embed-small", 5);
+
+        let embeddings = provider
+            .embed_batch(vec!["first".to_string(), "second".to_string()])
+            .unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0, 0.0], vec![0.0, 1.0, 0.0]]);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0]["model"], "embed-small");
+        assert_eq!(requests[0]["input"], serde_json::json!(["first", "second"]));
+        assert_eq!(requests[0]["authorization"], "Bearer secret");
+    }
+
+    #[test]
+    fn test_dimension_is_probed_once_and_cached() {
+        let (url, requests) = serve(vec![
+            (200, embeddings_body(&[(0, vec![0.5; 4])])),
+            (
+                200,
+                embeddings_body(&[(0, vec![0.5; 4]), (1, vec![0.25; 4])]),
+            ),
+        ]);
+        let provider = OpenAiCompatEmbedding::new(&url, None, "embed-small", 5);
+
+        assert_eq!(provider.dimension(), 4);
+        assert_eq!(provider.dimension(), 4);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        provider
+            .embed_batch(vec!["a".to_string(), "b".to_string()])
+            .unwrap();
+        assert_eq!(provider.dimension(), 4);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+        assert!(requests.lock().unwrap()[0]["authorization"].is_null());
+    }
+
+    #[test]
+    fn test_inconsistent_dimension_is_rejected() {
+        let (url, _requests) = serve(vec![
+            (200, embeddings_body(&[(0, vec![0.5; 4])])),
+            (200, embeddings_body(&[(0, vec![0.5; 8])])),
+        ]);
+        let provider = OpenAiCompatEmbedding::new(&url, None, "embed-small", 5);
+        provider.embed_batch(vec!["a".to_string()]).unwrap();
+
+        let error = provider.embed_batch(vec!["b".to_string()]).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<RagError>(),
+            Some(RagError::Embedding(EmbeddingError::DimensionMismatch {
+                expected: 4,
+                actual: 8
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_http_error_maps_to_generation_failed() {
+        let (url, _requests) = serve(vec![(401, r#"{"error":"invalid api key"}"#.to_string())]);
+        let provider = OpenAiCompatEmbedding::new(&url, None, "embed-small", 5);
+
+        let error = provider.embed_batch(vec!["a".to_string()]).unwrap_err();
+        match error.downcast_ref::<RagError>() {
+            Some(RagError::Embedding(EmbeddingError::GenerationFailed(message))) => {
+                assert!(message.contains("HTTP 401"), "{}", message);
+                assert!(message.contains("invalid api key"), "{}", message);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_endpoint_accepts_base_or_full_url() {
+        let base = OpenAiCompatEmbedding::new("http://localhost:8080/v1/", None, "m", 5);
+        assert_eq!(base.endpoint, "http://localhost:8080/v1/embeddings");
+
+        let full = OpenAiCompatEmbedding::new("http://localhost:8080/v1/embeddings", None, "m", 5);
+        assert_eq!(full.endpoint, "http://localhost:8080/v1/embeddings");
+    }
+}
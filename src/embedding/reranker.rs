@@ -0,0 +1,129 @@
+use super::fastembed_manager::execution_providers;
+use crate::config::EmbeddingDevice;
+use crate::error::{EmbeddingError, RagError};
+use anyhow::{Context, Result};
+use fastembed::{RerankInitOptions, RerankerModel, TextRerank};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Cross-encoder model used when `search.rerank_model` is not recognized
+pub const DEFAULT_RERANK_MODEL: &str = "jinaai/jina-reranker-v1-turbo-en";
+
+/// Cross-encoder that scores `(query, passage)` pairs for re-ranking search results
+///
+/// Unlike the bi-encoder embedding model, the cross-encoder reads the query and
+/// the passage together, which ranks natural-language questions noticeably
+/// better at the cost of one model run per candidate.
+pub struct Reranker {
+    model: Mutex<TextRerank>,
+    model_name: String,
+}
+
+impl Reranker {
+    /// Load a reranking model by name, downloading it into `cache_dir` if needed
+    pub fn from_model_name(
+        model_name: &str,
+        cache_dir: Option<&Path>,
+        device: EmbeddingDevice,
+    ) -> Result<Self> {
+        let (model, model_name) = match model_name {
+            "jinaai/jina-reranker-v1-turbo-en" => (
+                RerankerModel::JINARerankerV1TurboEn,
+                "jinaai/jina-reranker-v1-turbo-en",
+            ),
+            "BAAI/bge-reranker-base" => (RerankerModel::BGERerankerBase, "BAAI/bge-reranker-base"),
+            _ => {
+                tracing::warn!(
+                    "Unknown rerank model '{}', falling back to {}",
+                    model_name,
+                    DEFAULT_RERANK_MODEL
+                );
+                (RerankerModel::JINARerankerV1TurboEn, DEFAULT_RERANK_MODEL)
+            }
+        };
+        tracing::info!("Initializing rerank model: {} on {}", model_name, device);
+
+        let mut options = RerankInitOptions::new(model)
+            .with_show_download_progress(true)
+            .with_execution_providers(execution_providers(device));
+        if let Some(cache_dir) = cache_dir {
+            options = options.with_cache_dir(cache_dir.to_path_buf());
+        }
+
+        let model = TextRerank::try_new(options).map_err(|e| {
+            anyhow::Error::from(RagError::from(EmbeddingError::InitializationFailed(
+                format!("failed to load rerank model '{}': {:#}", model_name, e),
+            )))
+        })?;
+
+        Ok(Self {
+            model: Mutex::new(model),
+            model_name: model_name.to_string(),
+        })
+    }
+
+    /// Score each passage against the query, in the order of `passages`
+    ///
+    /// Scores are the cross-encoder logits squashed into `(0, 1)`, so they
+    /// compare with the similarity scores of the rest of the search pipeline.
+    pub fn score(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>> {
+        if passages.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut model = self.model.lock().unwrap_or_else(|poisoned| {
+            tracing::warn!("Rerank model lock was poisoned, recovering...");
+            poisoned.into_inner()
+        });
+
+        let ranked = model
+            .rerank(query, passages.to_vec(), false, None)
+            .context("Failed to re-rank search results")?;
+
+        let mut scores = vec![0.0; passages.len()];
+        for result in ranked {
+            scores[result.index] = sigmoid(result.score);
+        }
+        Ok(scores)
+    }
+
+    /// Name of the loaded reranking model
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+}
+
+fn sigmoid(logit: f32) -> f32 {
+    1.0 / (1.0 + (-logit).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rerank_scores_relevant_passage_higher() {
+        let reranker =
+            Reranker::from_model_name(DEFAULT_RERANK_MODEL, None, EmbeddingDevice::Cpu).unwrap();
+        let scores = reranker
+            .score(
+                "how are user passwords hashed?",
+                &[
+                    "fn render_chart(data: &[f64]) -> Svg { /* draws a bar chart */ }",
+                    "/// Hash a password with argon2 before storing the user\nfn hash_password(password: &str) -> String",
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(scores.len(), 2);
+        assert!(scores.iter().all(|score| (0.0..=1.0).contains(score)));
+        assert!(scores[1] > scores[0]);
+    }
+
+    #[test]
+    fn test_sigmoid_is_monotonic_and_bounded() {
+        assert!((sigmoid(0.0) - 0.5).abs() < f32::EPSILON);
+        assert!(sigmoid(-3.0) < sigmoid(3.0));
+        assert!(sigmoid(50.0) <= 1.0 && sigmoid(-50.0) >= 0.0);
+    }
+}
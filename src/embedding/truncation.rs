@@ -0,0 +1,102 @@
+//! Predictable truncation of embedding inputs
+//!
+//! Embedding models have a fixed maximum sequence length and silently drop
+//! anything past it. Truncating explicitly beforehand keeps the cut point
+//! predictable and lets us log how often it happens.
+
+/// Tokens reserved for the special tokens ([CLS]/[SEP]) added by the tokenizer
+const SPECIAL_TOKENS: usize = 2;
+
+/// Approximate characters per sub-word token within identifiers and words
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Byte offsets at which each (estimated) token starts
+///
+/// This approximates a WordPiece tokenizer: every punctuation character is its
+/// own token, and runs of word characters are split every few characters.
+fn token_starts(text: &str) -> impl Iterator<Item = usize> + '_ {
+    let mut word_len = 0usize;
+    text.char_indices().filter_map(move |(idx, ch)| {
+        let starts_token = if ch.is_alphanumeric() || ch == '_' {
+            word_len += 1;
+            (word_len - 1) % CHARS_PER_TOKEN == 0
+        } else {
+            word_len = 0;
+            !ch.is_whitespace()
+        };
+        starts_token.then_some(idx)
+    })
+}
+
+/// Estimate the number of tokens a text will be split into (excluding special tokens)
+pub fn estimate_tokens(text: &str) -> usize {
+    token_starts(text).count()
+}
+
+/// Tokens of text that fit within `max_tokens` once special tokens are added
+pub fn content_token_budget(max_tokens: usize) -> usize {
+    max_tokens.saturating_sub(SPECIAL_TOKENS)
+}
+
+/// Truncate texts so each fits within `max_tokens` (including special tokens)
+///
+/// Returns the (possibly truncated) texts and how many of them were cut.
+pub fn truncate_for_embedding(texts: Vec<String>, max_tokens: usize) -> (Vec<String>, usize) {
+    let budget = content_token_budget(max_tokens);
+    let mut truncated = 0;
+
+    let texts = texts
+        .into_iter()
+        .map(|text| {
+            let cut = token_starts(&text).nth(budget);
+            match cut {
+                Some(offset) => {
+                    truncated += 1;
+                    text[..offset].trim_end().to_string()
+                }
+                None => text,
+            }
+        })
+        .collect();
+
+    (texts, truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("fn main() {}"), 6); // fn, main, (, ), {, }
+        assert_eq!(estimate_tokens("authentication"), 4);
+    }
+
+    #[test]
+    fn test_short_text_untouched() {
+        let texts = vec!["fn main() {}".to_string()];
+        let (result, truncated) = truncate_for_embedding(texts.clone(), 256);
+        assert_eq!(result, texts);
+        assert_eq!(truncated, 0);
+    }
+
+    #[test]
+    fn test_over_length_text_truncated_to_limit() {
+        let long = "let value = compute(input);\n".repeat(200);
+        assert!(estimate_tokens(&long) > 256);
+
+        let (result, truncated) = truncate_for_embedding(vec![long.clone()], 256);
+        assert_eq!(truncated, 1);
+        assert!(estimate_tokens(&result[0]) <= 256 - SPECIAL_TOKENS);
+        assert!(long.starts_with(&result[0]));
+    }
+
+    #[test]
+    fn test_truncation_respects_char_boundaries() {
+        let long = "é".repeat(2000);
+        let (result, truncated) = truncate_for_embedding(vec![long], 16);
+        assert_eq!(truncated, 1);
+        assert!(estimate_tokens(&result[0]) <= 16 - SPECIAL_TOKENS);
+    }
+}
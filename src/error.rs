@@ -57,6 +57,29 @@ pub enum EmbeddingError {
 
     #[error("Model lock was poisoned: {0}")]
     LockPoisoned(String),
+
+    #[error(
+        "Embedding model '{model}' could not be downloaded ({reason}). For offline use, place the \
+         model files in '{cache_dir}' (embedding.model_cache_dir) or set embedding.model_name to \
+         a local model directory"
+    )]
+    ModelUnavailable {
+        model: String,
+        cache_dir: String,
+        reason: String,
+    },
+
+    #[error(
+        "The index was built with embedding model '{indexed_model}' ({indexed_dimension} \
+         dimensions) but the configured model is '{model}' ({dimension} dimensions). Clear the \
+         index and re-index, or set vector_db.auto_reindex_on_model_change to do so at startup"
+    )]
+    IndexModelChanged {
+        indexed_model: String,
+        indexed_dimension: usize,
+        model: String,
+        dimension: usize,
+    },
 }
 
 /// Errors related to vector database operations
@@ -94,6 +117,12 @@ pub enum VectorDbError {
 
     #[error("Database is not initialized")]
     NotInitialized,
+
+    #[error(
+        "BM25 index at {path} is locked by another writer ({reason}) and bm25.auto_unlock is \
+         disabled; once no other process writes to it, remove its .tantivy-*.lock files"
+    )]
+    Bm25Locked { path: String, reason: String },
 }
 
 /// Errors related to file indexing
@@ -288,6 +317,35 @@ impl RagError {
     }
 }
 
+/// Check whether an error chain contains a transient failure worth retrying
+///
+/// Besides [`RagError::is_retryable`], this recognizes bare connection and
+/// timeout errors wrapped in `anyhow` context along the way.
+pub fn is_retryable_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<RagError>() {
+            e.is_retryable()
+        } else if let Some(e) = cause.downcast_ref::<VectorDbError>() {
+            matches!(e, VectorDbError::ConnectionFailed(_))
+        } else if let Some(e) = cause.downcast_ref::<EmbeddingError>() {
+            matches!(e, EmbeddingError::Timeout(_))
+        } else if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::NotConnected
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::Interrupted
+            )
+        } else {
+            cause.is::<tokio::time::error::Elapsed>()
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -333,6 +391,23 @@ mod tests {
         assert!(!not_retryable.is_retryable());
     }
 
+    #[test]
+    fn test_is_retryable_error_looks_through_context() {
+        use anyhow::Context;
+
+        let transient: anyhow::Result<()> =
+            Err(VectorDbError::ConnectionFailed("reset".to_string()).into());
+        let err = transient.context("Failed to store embeddings").unwrap_err();
+        assert!(is_retryable_error(&err));
+
+        let io_err = std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out");
+        assert!(is_retryable_error(&anyhow::Error::new(io_err)));
+
+        let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        assert!(!is_retryable_error(&anyhow::Error::new(io_err)));
+        assert!(!is_retryable_error(&anyhow::anyhow!("Invalid chunk")));
+    }
+
     #[test]
     fn test_embedding_error_timeout() {
         let err = EmbeddingError::Timeout(30);
@@ -354,6 +429,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_embedding_error_model_unavailable() {
+        let err = EmbeddingError::ModelUnavailable {
+            model: "all-MiniLM-L6-v2".to_string(),
+            cache_dir: "/opt/models".to_string(),
+            reason: "dns error".to_string(),
+        };
+        let message = err.to_string();
+        assert!(message.contains("all-MiniLM-L6-v2"));
+        assert!(message.contains("/opt/models"));
+        assert!(message.contains("embedding.model_cache_dir"));
+    }
+
+    #[test]
+    fn test_embedding_error_index_model_changed() {
+        let err = EmbeddingError::IndexModelChanged {
+            indexed_model: "all-MiniLM-L6-v2".to_string(),
+            indexed_dimension: 384,
+            model: "BAAI/bge-base-en-v1.5".to_string(),
+            dimension: 768,
+        };
+        let message = err.to_string();
+        assert!(message.contains("'all-MiniLM-L6-v2' (384 dimensions)"));
+        assert!(message.contains("'BAAI/bge-base-en-v1.5' (768 dimensions)"));
+        assert!(message.contains("vector_db.auto_reindex_on_model_change"));
+    }
+
     #[test]
     fn test_vector_db_error_collection_creation() {
         let err = VectorDbError::CollectionCreationFailed {
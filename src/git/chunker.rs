@@ -1,4 +1,4 @@
-use crate::git::walker::CommitInfo;
+use crate::git::walker::{CommitInfo, TagInfo};
 use crate::indexer::CodeChunk;
 use crate::types::ChunkMetadata;
 use anyhow::Result;
@@ -7,6 +7,8 @@ use anyhow::Result;
 pub struct CommitChunker {
     /// Maximum content length before truncation
     max_content_length: usize,
+    /// Branch the commits are indexed from, recorded in chunk metadata
+    branch: Option<String>,
 }
 
 impl CommitChunker {
@@ -14,12 +16,22 @@ impl CommitChunker {
     pub fn new() -> Self {
         Self {
             max_content_length: 6000, // ~1500 tokens for all-MiniLM-L6-v2
+            branch: None,
         }
     }
 
     /// Create with custom max content length
     pub fn with_max_length(max_content_length: usize) -> Self {
-        Self { max_content_length }
+        Self {
+            max_content_length,
+            branch: None,
+        }
+    }
+
+    /// Record the branch the commits are indexed from in each chunk
+    pub fn with_branch(mut self, branch: Option<String>) -> Self {
+        self.branch = branch;
+        self
     }
 
     /// Convert a commit into a chunk for embedding
@@ -65,10 +77,7 @@ impl CommitChunker {
         }
 
         // Truncate if too long
-        if content.len() > self.max_content_length {
-            content.truncate(self.max_content_length);
-            content.push_str("\n\n[... content truncated for embedding ...]");
-        }
+        self.truncate(&mut content);
 
         // Create chunk metadata
         // Note: Git commits don't have line numbers, so we use 0
@@ -82,11 +91,68 @@ impl CommitChunker {
             extension: Some("commit".to_string()),
             file_hash: commit.hash.clone(),
             indexed_at: commit.commit_date,
+            symbol_kind: None,
+            binary: false,
+            file_size: None,
+            branch: self.branch.clone(),
+            commit_date: Some(commit.commit_date),
+            chunk_method: None,
+        };
+
+        Ok(CodeChunk { content, metadata })
+    }
+
+    /// Convert an annotated tag into a chunk for embedding
+    ///
+    /// Single-line fields come first, so a multi-paragraph message can be
+    /// recovered whole from the end of the content.
+    pub fn tag_to_chunk(
+        &self,
+        tag: &TagInfo,
+        repo_path: &str,
+        project: Option<String>,
+    ) -> Result<CodeChunk> {
+        let mut content = format!(
+            "Tag: {}\nTarget Commit: {}\nTagger: {}",
+            tag.name, tag.target_hash, tag.tagger_name
+        );
+        if !tag.tagger_email.is_empty() {
+            content.push_str(&format!(" <{}>", tag.tagger_email));
+        }
+        content.push_str(&format!("\nDate: {}\n\nTag Message:\n", tag.tag_date));
+        content.push_str(&tag.message);
+
+        self.truncate(&mut content);
+
+        let metadata = ChunkMetadata {
+            file_path: format!("git://{}", repo_path),
+            root_path: None,
+            project,
+            start_line: 0,
+            end_line: 0,
+            language: Some("git-tag".to_string()),
+            extension: Some("tag".to_string()),
+            file_hash: tag.hash.clone(),
+            indexed_at: tag.tag_date,
+            symbol_kind: None,
+            binary: false,
+            file_size: None,
+            branch: None,
+            commit_date: None,
+            chunk_method: None,
         };
 
         Ok(CodeChunk { content, metadata })
     }
 
+    /// Cut `content` down to the maximum length, on a character boundary
+    fn truncate(&self, content: &mut String) {
+        if content.len() > self.max_content_length {
+            content.truncate(content.floor_char_boundary(self.max_content_length));
+            content.push_str("\n\n[... content truncated for embedding ...]");
+        }
+    }
+
     /// Batch convert commits to chunks
     pub fn commits_to_chunks(
         &self,
@@ -126,6 +192,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tag_to_chunk() {
+        let chunker = CommitChunker::new();
+        let tag = TagInfo {
+            hash: "tag123".to_string(),
+            name: "v1.2.0".to_string(),
+            message: "Release 1.2.0\n\nFixes the memory leak in the indexer.".to_string(),
+            target_hash: "abc123def456".to_string(),
+            tagger_name: "Jane Doe".to_string(),
+            tagger_email: "jane@example.com".to_string(),
+            tag_date: 1704067200,
+        };
+
+        let chunk = chunker
+            .tag_to_chunk(&tag, "/repo/path", None)
+            .expect("Should convert tag to chunk");
+
+        let content = &chunk.content;
+        assert!(content.starts_with("Tag: v1.2.0\n"));
+        assert!(content.contains("Tagger: Jane Doe <jane@example.com>"));
+        assert!(content.ends_with("Fixes the memory leak in the indexer."));
+        assert_eq!(chunk.metadata.language, Some("git-tag".to_string()));
+        assert_eq!(chunk.metadata.file_hash, "tag123");
+    }
+
     #[test]
     fn test_commit_to_chunk() {
         let chunker = CommitChunker::new();
@@ -158,6 +249,36 @@ mod tests {
         assert!(chunk.content.contains("[... content truncated"));
     }
 
+    #[test]
+    fn test_truncation_respects_multibyte_characters() {
+        let tag = TagInfo {
+            hash: "tag123".to_string(),
+            name: "v2.0.0".to_string(),
+            message: "Améliorations ✓ ".repeat(50),
+            target_hash: "abc123def456".to_string(),
+            tagger_name: "Jane Doe".to_string(),
+            tagger_email: String::new(),
+            tag_date: 1704067200,
+        };
+        let mut commit = create_test_commit();
+        commit.message = tag.message.clone();
+
+        // A full period of the message, so some lengths cut into a character
+        for max_length in 100..120 {
+            let chunker = CommitChunker::with_max_length(max_length);
+
+            let chunk = chunker
+                .tag_to_chunk(&tag, "/repo/path", None)
+                .expect("Should convert tag");
+            assert!(chunk.content.contains("[... content truncated"));
+
+            let chunk = chunker
+                .commit_to_chunk(&commit, "/repo/path", None)
+                .expect("Should convert commit");
+            assert!(chunk.content.contains("[... content truncated"));
+        }
+    }
+
     #[test]
     fn test_commits_to_chunks_batch() {
         let chunker = CommitChunker::new();
@@ -9,4 +9,4 @@ pub mod chunker;
 pub mod walker;
 
 pub use chunker::CommitChunker;
-pub use walker::GitWalker;
+pub use walker::{GitWalker, TagInfo};
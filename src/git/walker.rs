@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use git2::{DiffOptions, Repository, Sort};
+use regex::Regex;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
@@ -24,6 +25,25 @@ pub struct CommitInfo {
     pub parent_hashes: Vec<String>,
 }
 
+/// Information about an annotated git tag
+#[derive(Debug, Clone)]
+pub struct TagInfo {
+    /// Full SHA hash of the tag object (40 characters)
+    pub hash: String,
+    /// Tag name (e.g. "v1.2.0")
+    pub name: String,
+    /// Tag message, typically release notes
+    pub message: String,
+    /// Full SHA hash of the tagged commit
+    pub target_hash: String,
+    /// Tagger's name
+    pub tagger_name: String,
+    /// Tagger's email address
+    pub tagger_email: String,
+    /// Tag timestamp (Unix epoch seconds)
+    pub tag_date: i64,
+}
+
 /// Git repository walker for extracting commit information
 pub struct GitWalker {
     repo: Repository,
@@ -61,29 +81,19 @@ impl GitWalker {
     }
 
     /// Iterate commits with filters
+    ///
+    /// Date and author filters are applied while walking, so commits outside
+    /// them are never extracted and don't count towards `max_count`.
     pub fn iter_commits(
         &self,
         branch: Option<&str>,
         max_count: Option<usize>,
         since_date: Option<i64>,
         until_date: Option<i64>,
+        author: Option<&Regex>,
         skip_hashes: &HashSet<String>,
     ) -> Result<Vec<CommitInfo>> {
-        let mut revwalk = self.repo.revwalk()?;
-        revwalk.set_sorting(Sort::TIME | Sort::TOPOLOGICAL)?;
-
-        // Determine starting point
-        if let Some(branch_name) = branch {
-            let reference = self
-                .repo
-                .find_branch(branch_name, git2::BranchType::Local)
-                .context("Failed to find branch")?;
-            let oid = reference.get().target().context("Branch has no target")?;
-            revwalk.push(oid)?;
-        } else {
-            // Use HEAD
-            revwalk.push_head()?;
-        }
+        let revwalk = self.branch_revwalk(branch)?;
 
         let mut commits = Vec::new();
         let mut count = 0;
@@ -119,6 +129,10 @@ impl GitWalker {
                 continue;
             }
 
+            if !authored_by(&commit, author) {
+                continue;
+            }
+
             // Extract commit info
             let commit_info = self.extract_commit_info(&commit)?;
             commits.push(commit_info);
@@ -133,6 +147,72 @@ impl GitWalker {
         Ok(commits)
     }
 
+    /// Hashes of the most recent commits on a branch, newest first
+    ///
+    /// Unlike [`iter_commits`](Self::iter_commits), already indexed commits count
+    /// towards `max_count`, so the result describes the branch's history window
+    /// regardless of what has been indexed.
+    pub fn recent_commit_hashes(
+        &self,
+        branch: Option<&str>,
+        max_count: usize,
+        since_date: Option<i64>,
+        until_date: Option<i64>,
+        author: Option<&Regex>,
+    ) -> Result<Vec<String>> {
+        let mut hashes = Vec::new();
+
+        for oid in self.branch_revwalk(branch)? {
+            if hashes.len() >= max_count {
+                break;
+            }
+
+            let commit = self.repo.find_commit(oid?)?;
+            let commit_time = commit.time().seconds();
+
+            if let Some(since) = since_date
+                && commit_time < since
+            {
+                break;
+            }
+
+            if let Some(until) = until_date
+                && commit_time > until
+            {
+                continue;
+            }
+
+            if !authored_by(&commit, author) {
+                continue;
+            }
+
+            hashes.push(format!("{}", commit.id()));
+        }
+
+        Ok(hashes)
+    }
+
+    /// Revision walk over a branch (or HEAD), newest commits first
+    fn branch_revwalk(&self, branch: Option<&str>) -> Result<git2::Revwalk<'_>> {
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(Sort::TIME | Sort::TOPOLOGICAL)?;
+
+        // Determine starting point
+        if let Some(branch_name) = branch {
+            let reference = self
+                .repo
+                .find_branch(branch_name, git2::BranchType::Local)
+                .context("Failed to find branch")?;
+            let oid = reference.get().target().context("Branch has no target")?;
+            revwalk.push(oid)?;
+        } else {
+            // Use HEAD
+            revwalk.push_head()?;
+        }
+
+        Ok(revwalk)
+    }
+
     /// Extract detailed information from a commit
     fn extract_commit_info(&self, commit: &git2::Commit) -> Result<CommitInfo> {
         let hash = format!("{}", commit.id());
@@ -253,6 +333,78 @@ impl GitWalker {
         Ok((files_changed, diff_content))
     }
 
+    /// Annotated tags of the repository, sorted by name
+    ///
+    /// Lightweight tags carry no message and are skipped, as are tags whose
+    /// object hash is in `skip_hashes`.
+    pub fn iter_tags(&self, skip_hashes: &HashSet<String>) -> Result<Vec<TagInfo>> {
+        let mut tags = Vec::new();
+
+        for name in self.repo.tag_names(None)?.iter().flatten() {
+            let object = self
+                .repo
+                .revparse_single(&format!("refs/tags/{}", name))
+                .with_context(|| format!("Failed to resolve tag {}", name))?;
+            let Some(tag) = object.as_tag() else {
+                continue; // Lightweight tag
+            };
+
+            let hash = format!("{}", tag.id());
+            if skip_hashes.contains(&hash) {
+                tracing::debug!("Skipping already indexed tag: {}", name);
+                continue;
+            }
+
+            let tagger = tag.tagger();
+            tags.push(TagInfo {
+                hash,
+                name: name.to_string(),
+                message: tag.message().unwrap_or("").trim_end().to_string(),
+                target_hash: format!("{}", tag.target_id()),
+                tagger_name: tagger
+                    .as_ref()
+                    .and_then(|t| t.name())
+                    .unwrap_or("Unknown")
+                    .to_string(),
+                tagger_email: tagger
+                    .as_ref()
+                    .and_then(|t| t.email())
+                    .unwrap_or("")
+                    .to_string(),
+                tag_date: tagger.as_ref().map(|t| t.when().seconds()).unwrap_or(0),
+            });
+        }
+
+        tracing::info!("Extracted {} new annotated tags", tags.len());
+        Ok(tags)
+    }
+
+    /// Hashes among `hashes` that no branch, tag or other reference reaches any more
+    ///
+    /// These are commits dropped by a rebase or force push, for example. History
+    /// is only walked until every hash has been seen.
+    pub fn unreachable_commits(&self, hashes: &HashSet<String>) -> Result<HashSet<String>> {
+        let mut remaining = hashes.clone();
+        if remaining.is_empty() {
+            return Ok(remaining);
+        }
+
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push_glob("*")?;
+        if self.has_commits() {
+            revwalk.push_head()?; // A detached HEAD has no reference of its own
+        }
+
+        for oid in revwalk {
+            remaining.remove(&format!("{}", oid?));
+            if remaining.is_empty() {
+                break;
+            }
+        }
+
+        Ok(remaining)
+    }
+
     /// Check if repository has any commits
     pub fn has_commits(&self) -> bool {
         self.repo.head().is_ok()
@@ -266,6 +418,16 @@ impl GitWalker {
     }
 }
 
+/// Whether the commit's author name or email matches the pattern (if any)
+fn authored_by(commit: &git2::Commit<'_>, pattern: Option<&Regex>) -> bool {
+    let Some(regex) = pattern else {
+        return true;
+    };
+    let author = commit.author();
+    author.name().is_some_and(|name| regex.is_match(name))
+        || author.email().is_some_and(|email| regex.is_match(email))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -291,7 +453,7 @@ mod tests {
         let skip = HashSet::new();
 
         let commits = walker
-            .iter_commits(None, Some(5), None, None, &skip)
+            .iter_commits(None, Some(5), None, None, None, &skip)
             .expect("Should iterate commits");
 
         assert!(commits.len() <= 5, "Should respect max_count");
@@ -311,7 +473,7 @@ mod tests {
         let skip = HashSet::new();
 
         let commits = walker
-            .iter_commits(None, Some(1), None, None, &skip)
+            .iter_commits(None, Some(1), None, None, None, &skip)
             .expect("Should get commits");
 
         if let Some(commit) = commits.first() {
@@ -327,7 +489,7 @@ mod tests {
 
         // Get first commit
         let commits = walker
-            .iter_commits(None, Some(1), None, None, &skip)
+            .iter_commits(None, Some(1), None, None, None, &skip)
             .expect("Should get commits");
 
         if let Some(first_commit) = commits.first() {
@@ -336,7 +498,7 @@ mod tests {
 
             // Try again with that commit in skip set
             let commits2 = walker
-                .iter_commits(None, Some(1), None, None, &skip_set)
+                .iter_commits(None, Some(1), None, None, None, &skip_set)
                 .expect("Should get commits");
 
             // Should get different commit (or fewer commits if only one exists)
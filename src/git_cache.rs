@@ -9,6 +9,9 @@ use std::path::{Path, PathBuf};
 pub struct GitCache {
     /// Map of repository path -> set of indexed commit hashes
     pub repos: HashMap<String, HashSet<String>>,
+    /// Map of repository path -> set of indexed annotated tag object hashes
+    #[serde(default)]
+    pub tags: HashMap<String, HashSet<String>>,
 }
 
 impl GitCache {
@@ -81,6 +84,16 @@ impl GitCache {
             .extend(commit_hashes);
     }
 
+    /// Get all indexed annotated tags for a repository
+    pub fn get_tags(&self, repo_path: &str) -> Option<&HashSet<String>> {
+        self.tags.get(repo_path)
+    }
+
+    /// Add indexed annotated tags for a repository
+    pub fn add_tags(&mut self, repo_path: String, tag_hashes: HashSet<String>) {
+        self.tags.entry(repo_path).or_default().extend(tag_hashes);
+    }
+
     /// Update commits for a repository (replaces existing)
     pub fn update_repo(&mut self, repo_path: String, commit_hashes: HashSet<String>) {
         self.repos.insert(repo_path, commit_hashes);
@@ -88,12 +101,14 @@ impl GitCache {
 
     /// Remove a repository from cache
     pub fn remove_repo(&mut self, repo_path: &str) -> bool {
+        self.tags.remove(repo_path);
         self.repos.remove(repo_path).is_some()
     }
 
     /// Clear all cached repositories
     pub fn clear(&mut self) {
         self.repos.clear();
+        self.tags.clear();
     }
 
     /// Get total number of indexed commits across all repos
@@ -1,6 +1,7 @@
 //! Glob pattern matching utilities for path filtering
 
 use globset::{Glob, GlobMatcher};
+use std::path::Path;
 
 /// Check if a file path matches any of the given glob patterns
 ///
@@ -113,6 +114,98 @@ pub fn matches_any_matcher(path: &str, matchers: &[GlobMatcher]) -> bool {
     })
 }
 
+/// Compile include/exclude patterns for matching paths relative to a root
+///
+/// A pattern without glob metacharacters names a file or directory anywhere
+/// in the path, so `target` is matched as `**/target` and `**/target/**`. A
+/// plain name without a dot also matches files of that name with any
+/// extension (`test` as `**/test.*`), and one starting with a dot matches as
+/// an extension (`.rs` as `**/*.rs`).
+/// Invalid patterns are skipped with a warning.
+pub fn compile_relative_patterns(patterns: &[String]) -> Vec<GlobMatcher> {
+    patterns
+        .iter()
+        .flat_map(|pattern| compile_relative_pattern(pattern))
+        .collect()
+}
+
+/// Compile a single pattern for matching paths relative to a root
+fn compile_relative_pattern(pattern: &str) -> Vec<GlobMatcher> {
+    let mut globs = Vec::new();
+    let pattern = pattern.trim_matches('/');
+    if pattern.contains(['*', '?', '[', '{']) {
+        globs.push(pattern.to_string());
+    } else {
+        globs.push(format!("**/{}", pattern));
+        globs.push(format!("**/{}/**", pattern));
+        if !pattern.contains(['.', '/']) {
+            globs.push(format!("**/{}.*", pattern));
+        }
+        if pattern.starts_with('.') && !pattern.contains('/') {
+            globs.push(format!("**/*{}", pattern));
+        }
+    }
+
+    globs
+        .iter()
+        .filter_map(|glob| match Glob::new(glob) {
+            Ok(glob) => Some(glob.compile_matcher()),
+            Err(e) => {
+                tracing::warn!("Skipping invalid glob pattern '{}': {}", glob, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Exclude patterns applied in order, like a `.gitignore`
+///
+/// A pattern starting with `!` re-includes paths excluded by the patterns
+/// before it, so the last pattern matching a path decides. Patterns are
+/// compiled like [`compile_relative_patterns`]. Unlike git, a file can be
+/// re-included even when its whole directory was excluded.
+#[derive(Debug, Clone, Default)]
+pub struct ExcludeRules {
+    rules: Vec<ExcludeRule>,
+}
+
+/// One compiled exclude pattern
+#[derive(Debug, Clone)]
+struct ExcludeRule {
+    matchers: Vec<GlobMatcher>,
+    /// Re-include matching paths instead of excluding them
+    negated: bool,
+}
+
+impl ExcludeRules {
+    /// Compile `patterns` in order, skipping invalid ones with a warning
+    pub fn compile(patterns: &[String]) -> Self {
+        let rules = patterns
+            .iter()
+            .map(|pattern| {
+                let (pattern, negated) = match pattern.strip_prefix('!') {
+                    Some(pattern) => (pattern, true),
+                    None => (pattern.as_str(), false),
+                };
+                ExcludeRule {
+                    matchers: compile_relative_pattern(pattern),
+                    negated,
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Whether `path`, relative to the root, is excluded
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matchers.iter().any(|m| m.is_match(path)))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -212,4 +305,73 @@ mod tests {
         assert!(matches_any_pattern("test.rs", &patterns));
         assert!(!matches_any_pattern("/project/src/main.rs", &patterns));
     }
+
+    #[test]
+    fn test_relative_plain_name_matches_path_component() {
+        let matchers = compile_relative_patterns(&["target".to_string()]);
+        let matches = |path: &str| matchers.iter().any(|m| m.is_match(path));
+
+        assert!(matches("target"));
+        assert!(matches("target/debug/main"));
+        assert!(matches("crates/core/target/debug/main"));
+        assert!(!matches("src/target_os.rs"));
+    }
+
+    #[test]
+    fn test_relative_plain_name_matches_file_stem() {
+        let matchers = compile_relative_patterns(&["test".to_string()]);
+        let matches = |path: &str| matchers.iter().any(|m| m.is_match(path));
+
+        assert!(matches("src/test.rs"));
+        assert!(matches("test.py"));
+        assert!(!matches("src/contest.rs"));
+        assert!(!matches("src/test_utils.rs"));
+    }
+
+    #[test]
+    fn test_relative_dot_name_matches_extension() {
+        let matchers = compile_relative_patterns(&[".rs".to_string()]);
+        let matches = |path: &str| matchers.iter().any(|m| m.is_match(path));
+
+        assert!(matches("src/main.rs"));
+        assert!(!matches("src/main.rsx"));
+    }
+
+    #[test]
+    fn test_relative_invalid_pattern_skipped() {
+        let matchers = compile_relative_patterns(&["[invalid".to_string(), "*.rs".to_string()]);
+        assert_eq!(matchers.len(), 1);
+    }
+
+    #[test]
+    fn test_exclude_rules_negation_re_includes() {
+        let rules = ExcludeRules::compile(&[
+            "**/generated/**".to_string(),
+            "!**/generated/schema.rs".to_string(),
+        ]);
+
+        assert!(rules.is_excluded(Path::new("src/generated/types.rs")));
+        assert!(!rules.is_excluded(Path::new("src/generated/schema.rs")));
+        assert!(!rules.is_excluded(Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn test_exclude_rules_last_match_wins() {
+        let rules = ExcludeRules::compile(&[
+            "generated".to_string(),
+            "!schema.rs".to_string(),
+            "legacy/**".to_string(),
+        ]);
+
+        assert!(!rules.is_excluded(Path::new("generated/schema.rs")));
+        assert!(rules.is_excluded(Path::new("legacy/schema.rs")));
+        assert!(rules.is_excluded(Path::new("generated/types.rs")));
+    }
+
+    #[test]
+    fn test_exclude_rules_negation_alone_excludes_nothing() {
+        let rules = ExcludeRules::compile(&["!*.rs".to_string()]);
+        assert!(!rules.is_excluded(Path::new("main.rs")));
+        assert!(!rules.is_excluded(Path::new("README.md")));
+    }
 }
@@ -11,6 +11,14 @@ pub struct AstNode {
     pub end_line: usize,
 }
 
+/// Semantic units of a parsed file, and how much of it failed to parse
+#[derive(Debug, Clone)]
+pub struct ParsedFile {
+    pub nodes: Vec<AstNode>,
+    /// Bytes of the source inside syntax error nodes
+    pub error_bytes: usize,
+}
+
 /// AST parser for extracting semantic code units
 pub struct AstParser {
     parser: Parser,
@@ -23,9 +31,9 @@ impl AstParser {
     pub fn new(extension: &str) -> Result<Self> {
         let (language, language_name) = match extension.to_lowercase().as_str() {
             "rs" => (tree_sitter_rust::LANGUAGE.into(), "Rust"),
-            "py" => (tree_sitter_python::LANGUAGE.into(), "Python"),
+            "py" | "pyi" => (tree_sitter_python::LANGUAGE.into(), "Python"),
             "js" | "mjs" | "cjs" | "jsx" => (tree_sitter_javascript::LANGUAGE.into(), "JavaScript"),
-            "ts" | "tsx" => (
+            "ts" | "mts" | "cts" | "tsx" => (
                 tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
                 "TypeScript",
             ),
@@ -39,6 +47,10 @@ impl AstParser {
             "cs" => (tree_sitter_c_sharp::LANGUAGE.into(), "C#"),
             "rb" => (tree_sitter_ruby::LANGUAGE.into(), "Ruby"),
             "php" => (tree_sitter_php::LANGUAGE_PHP.into(), "PHP"),
+            "zig" => (tree_sitter_zig::LANGUAGE.into(), "Zig"),
+            "ex" | "exs" => (tree_sitter_elixir::LANGUAGE.into(), "Elixir"),
+            "lua" => (tree_sitter_lua::LANGUAGE.into(), "Lua"),
+            "dart" => (tree_sitter_dart::LANGUAGE.into(), "Dart"),
             _ => anyhow::bail!("Unsupported language for AST parsing: {}", extension),
         };
 
@@ -56,6 +68,11 @@ impl AstParser {
 
     /// Parse source code and extract semantic units (functions, classes, etc.)
     pub fn parse(&mut self, source_code: &str) -> Result<Vec<AstNode>> {
+        Ok(self.parse_file(source_code)?.nodes)
+    }
+
+    /// Parse source code, extracting semantic units and measuring syntax errors
+    pub fn parse_file(&mut self, source_code: &str) -> Result<ParsedFile> {
         let tree = self
             .parser
             .parse(source_code, None)
@@ -67,11 +84,14 @@ impl AstParser {
         // Extract semantic units based on language
         self.extract_semantic_units(root_node, source_code, &mut nodes);
 
-        Ok(nodes)
+        Ok(ParsedFile {
+            nodes,
+            error_bytes: error_bytes(root_node),
+        })
     }
 
     /// Extract semantic units (functions, classes, methods) from the AST
-    fn extract_semantic_units(&self, node: Node, _source_code: &str, result: &mut Vec<AstNode>) {
+    fn extract_semantic_units(&self, node: Node, source_code: &str, result: &mut Vec<AstNode>) {
         // Define node types we want to chunk by language
         let target_kinds = match self.language_name.as_str() {
             "Rust" => vec![
@@ -157,19 +177,33 @@ impl AstParser {
                 "trait_declaration",
                 "namespace_definition",
             ],
+            // Type declarations and Elixir's definitions are found by `special_unit`
+            "Zig" => vec!["function_declaration", "test_declaration"],
+            "Lua" => vec!["function_declaration", "function_definition"],
+            "Dart" => vec![
+                "class_definition",
+                "mixin_declaration",
+                "extension_declaration",
+                "enum_declaration",
+            ],
             _ => vec![],
         };
 
         // Check if current node is a target kind
         let kind = node.kind();
-        if target_kinds.contains(&kind) {
+        let unit = if target_kinds.contains(&kind) {
+            Some((kind, node))
+        } else {
+            special_unit(node, source_code, &self.language_name)
+        };
+        if let Some((kind, last_node)) = unit {
             let start_position = node.start_position();
-            let end_position = node.end_position();
+            let end_position = last_node.end_position();
 
             result.push(AstNode {
                 kind: kind.to_string(),
                 start_byte: node.start_byte(),
-                end_byte: node.end_byte(),
+                end_byte: last_node.end_byte(),
                 start_line: start_position.row + 1, // Tree-sitter uses 0-indexed rows
                 end_line: end_position.row + 1,
             });
@@ -178,7 +212,7 @@ impl AstParser {
         // Recursively process children
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
-            self.extract_semantic_units(child, _source_code, result);
+            self.extract_semantic_units(child, source_code, result);
         }
     }
 
@@ -188,6 +222,95 @@ impl AstParser {
     }
 }
 
+/// Bytes covered by error nodes in the tree below `node`
+///
+/// Nested error nodes are counted once, and subtrees without errors are skipped.
+fn error_bytes(node: Node) -> usize {
+    if node.is_error() {
+        return node.end_byte() - node.start_byte();
+    }
+    if !node.has_error() {
+        return 0;
+    }
+    let mut cursor = node.walk();
+    node.children(&mut cursor).map(error_bytes).sum()
+}
+
+/// Elixir macros whose calls define a module, function or the like
+const ELIXIR_DEFINITIONS: &[&str] = &[
+    "defmodule",
+    "defprotocol",
+    "defimpl",
+    "def",
+    "defp",
+    "defmacro",
+    "defmacrop",
+    "defguard",
+    "defguardp",
+];
+
+/// Zig container types, which are anonymous until bound with `const`
+const ZIG_CONTAINERS: &[&str] = &[
+    "struct_declaration",
+    "enum_declaration",
+    "union_declaration",
+    "opaque_declaration",
+];
+
+/// Kind and last node of a definition the grammar has no node kind for
+///
+/// - Elixir: `defmodule`, `def` and friends are plain calls; the kind is the
+///   macro name
+/// - Zig: `const Point = struct { ... }` spans the whole declaration and takes
+///   the kind of the container
+/// - Dart: a function or method is a signature followed by a sibling body,
+///   which is the last node
+pub(crate) fn special_unit<'tree>(
+    node: Node<'tree>,
+    source: &str,
+    language: &str,
+) -> Option<(&'static str, Node<'tree>)> {
+    match language {
+        "Elixir" => elixir_definition(node, source).map(|keyword| (keyword, node)),
+        "Zig" if node.kind() == "variable_declaration" => {
+            let mut cursor = node.walk();
+            let container = node
+                .named_children(&mut cursor)
+                .find(|child| ZIG_CONTAINERS.contains(&child.kind()))?;
+            Some((container.kind(), node))
+        }
+        "Dart" if matches!(node.kind(), "function_signature" | "method_signature") => {
+            // The signature of a method is wrapped in its method_signature
+            if node
+                .parent()
+                .is_some_and(|parent| parent.kind() == "method_signature")
+            {
+                return None;
+            }
+            let body = node
+                .next_named_sibling()
+                .filter(|sibling| sibling.kind() == "function_body");
+            Some((node.kind(), body.unwrap_or(node)))
+        }
+        _ => None,
+    }
+}
+
+/// Macro name of an Elixir call defining a module, function or the like
+fn elixir_definition(node: Node, source: &str) -> Option<&'static str> {
+    if node.kind() != "call" {
+        return None;
+    }
+    let target = node
+        .child_by_field_name("target")
+        .filter(|target| target.kind() == "identifier")?;
+    let name = source.get(target.byte_range())?;
+    ELIXIR_DEFINITIONS
+        .iter()
+        .copied()
+        .find(|keyword| *keyword == name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -298,6 +421,15 @@ class MyClass {
         assert!(parser.language_name() == "Swift");
     }
 
+    #[test]
+    fn test_syntax_errors_are_measured() {
+        let mut parser = AstParser::new("rs").unwrap();
+        assert_eq!(parser.parse_file("fn main() {}\n").unwrap().error_bytes, 0);
+
+        let parsed = parser.parse_file("fn main() {}\n%% ]] ## ((\n").unwrap();
+        assert!(parsed.error_bytes > 0);
+    }
+
     #[test]
     fn test_unsupported_language() {
         let result = AstParser::new("xyz");
@@ -422,4 +554,124 @@ class MyClass {
         assert!(!nodes.is_empty());
         assert!(parser.language_name() == "PHP");
     }
+
+    #[test]
+    fn test_zig_parsing() {
+        let source = r#"
+const std = @import("std");
+const Point = struct {
+    x: i32,
+    y: i32,
+
+    pub fn init(x: i32, y: i32) Point {
+        return .{ .x = x, .y = y };
+    }
+};
+
+pub fn add(a: i32, b: i32) i32 {
+    return a + b;
+}
+
+test "add" {
+    try std.testing.expect(add(1, 2) == 3);
+}
+"#;
+
+        let mut parser = AstParser::new("zig").unwrap();
+        let nodes = parser.parse(source).unwrap();
+
+        assert_eq!(parser.language_name(), "Zig");
+        assert!(nodes.iter().any(|n| n.kind == "function_declaration"));
+        assert!(nodes.iter().any(|n| n.kind == "test_declaration"));
+        // The struct's chunk starts at the `const` naming it
+        let point = nodes
+            .iter()
+            .find(|n| n.kind == "struct_declaration")
+            .unwrap();
+        assert_eq!((point.start_line, point.end_line), (3, 10));
+    }
+
+    #[test]
+    fn test_elixir_parsing() {
+        let source = r#"
+defmodule Greeter do
+  def hello(name) do
+    "Hello, #{name}!"
+  end
+
+  defp shout(text), do: String.upcase(text)
+end
+"#;
+
+        let mut parser = AstParser::new("ex").unwrap();
+        let nodes = parser.parse(source).unwrap();
+
+        assert_eq!(parser.language_name(), "Elixir");
+        let kinds: Vec<&str> = nodes.iter().map(|n| n.kind.as_str()).collect();
+        assert_eq!(kinds, ["defmodule", "def", "defp"]);
+        assert_eq!((nodes[1].start_line, nodes[1].end_line), (3, 5));
+    }
+
+    #[test]
+    fn test_lua_parsing() {
+        let source = r#"
+local M = {}
+
+local function helper(x)
+  return x * 2
+end
+
+function M.double(x)
+  return helper(x)
+end
+
+M.triple = function(x)
+  return x * 3
+end
+
+return M
+"#;
+
+        let mut parser = AstParser::new("lua").unwrap();
+        let nodes = parser.parse(source).unwrap();
+
+        assert_eq!(parser.language_name(), "Lua");
+        let declarations = nodes
+            .iter()
+            .filter(|n| n.kind == "function_declaration")
+            .count();
+        assert_eq!(declarations, 2);
+        assert!(nodes.iter().any(|n| n.kind == "function_definition"));
+    }
+
+    #[test]
+    fn test_dart_parsing() {
+        let source = r#"
+int add(int a, int b) {
+  return a + b;
+}
+
+class Counter {
+  int value = 0;
+
+  void increment() {
+    value++;
+  }
+}
+"#;
+
+        let mut parser = AstParser::new("dart").unwrap();
+        let nodes = parser.parse(source).unwrap();
+
+        assert_eq!(parser.language_name(), "Dart");
+        // Functions span their signature and the body that follows it
+        let add = nodes
+            .iter()
+            .find(|n| n.kind == "function_signature")
+            .unwrap();
+        assert_eq!((add.start_line, add.end_line), (2, 4));
+        assert!(nodes.iter().any(|n| n.kind == "class_definition"));
+        let increment = nodes.iter().find(|n| n.kind == "method_signature").unwrap();
+        assert_eq!((increment.start_line, increment.end_line), (9, 11));
+    }
 }
@@ -1,7 +1,13 @@
 use super::CodeChunk;
+use crate::embedding::estimate_tokens;
 use crate::indexer::ast_parser::AstParser;
+use crate::indexer::docs;
 use crate::indexer::file_info::FileInfo;
+use crate::indexer::markup::{self, MarkupKind};
+use crate::relations::SymbolKind;
 use crate::types::ChunkMetadata;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Strategy for chunking code
@@ -14,15 +20,164 @@ pub enum ChunkStrategy {
     AstBased,
     /// Hybrid: AST-based with fallback to fixed lines
     Hybrid { fallback_lines: usize },
+    /// AST-based, with units over an approximate token budget split at line
+    /// boundaries, and files without a grammar packed into chunks of that budget
+    ChunkByTokens { max_tokens: usize },
+}
+
+/// Chunking strategy selectable in the configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkStrategyKind {
+    /// Functions, classes and methods, falling back to fixed lines for files
+    /// without a grammar ([`ChunkStrategy::Hybrid`])
+    #[default]
+    Ast,
+    /// Fixed number of lines per chunk ([`ChunkStrategy::FixedLines`])
+    FixedLines,
+    /// Windows of lines overlapping their neighbours ([`ChunkStrategy::SlidingWindow`])
+    SlidingWindow,
+    /// Functions, classes and methods kept within a token budget
+    /// ([`ChunkStrategy::ChunkByTokens`])
+    Tokens,
+}
+
+impl ChunkStrategyKind {
+    /// Strategy of this kind with `size` lines per chunk (or per fallback
+    /// chunk), `overlap` lines shared between sliding windows and
+    /// `max_tokens` estimated tokens per chunk for the token strategy
+    pub fn strategy(self, size: usize, overlap: usize, max_tokens: usize) -> ChunkStrategy {
+        match self {
+            Self::Ast => ChunkStrategy::Hybrid {
+                fallback_lines: size,
+            },
+            Self::FixedLines => ChunkStrategy::FixedLines(size),
+            Self::SlidingWindow => ChunkStrategy::SlidingWindow { size, overlap },
+            Self::Tokens => ChunkStrategy::ChunkByTokens { max_tokens },
+        }
+    }
+}
+
+/// Section size limit for markup chunking when the strategy has no line count
+const DEFAULT_MARKUP_MAX_LINES: usize = 50;
+
+/// Share of a file inside syntax errors above which its AST chunks are not
+/// trusted, as they may be degenerate or leave whole regions out
+const MAX_PARSE_ERROR_FRACTION: f64 = 0.2;
+
+/// Chunking method actually applied to a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AppliedChunking {
+    /// Semantic units (functions, classes, methods) from the file's grammar
+    #[serde(rename = "AST")]
+    Ast,
+    /// Fixed lines after AST chunking was not possible (Hybrid strategy)
+    #[serde(rename = "line fallback")]
+    LineFallback,
+    /// Lines packed up to the token budget after AST chunking was not
+    /// possible (ChunkByTokens strategy)
+    #[serde(rename = "token fallback")]
+    TokenFallback,
+    /// Fixed lines, as configured
+    #[serde(rename = "fixed lines")]
+    FixedLines,
+    /// Overlapping line windows, as configured
+    #[serde(rename = "sliding window")]
+    SlidingWindow,
+    /// Heading, paragraph or block element sections of a markup file
+    #[serde(rename = "markup sections")]
+    Markup,
+    /// Single metadata-only chunk of a binary file
+    #[serde(rename = "binary metadata")]
+    Binary,
+}
+
+impl fmt::Display for AppliedChunking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ast => "AST",
+            Self::LineFallback => "line fallback",
+            Self::TokenFallback => "token fallback",
+            Self::FixedLines => "fixed lines",
+            Self::SlidingWindow => "sliding window",
+            Self::Markup => "markup sections",
+            Self::Binary => "binary metadata",
+        })
+    }
+}
+
+/// How a file was chunked, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChunkingDiagnostic {
+    /// File path relative to the indexed root
+    pub file_path: String,
+    /// Chunking method actually applied
+    pub applied: AppliedChunking,
+    /// Why it was applied (grammar available, parse succeeded or failed, ...)
+    pub reason: String,
+    /// Number of chunks produced, doc comment chunks included
+    pub chunks: usize,
 }
 
 pub struct CodeChunker {
     strategy: ChunkStrategy,
+    /// Chunk Markdown, HTML and plain text by sections instead of `strategy`
+    markup_chunking: bool,
+    /// Route documentation files and doc comments into the docs project
+    docs_project: bool,
+    /// Lines consecutive chunks of a file share (see [`CodeChunker::with_overlap`])
+    overlap: usize,
+    /// Chunks shorter than this are merged or dropped (see [`CodeChunker::with_min_chunk_lines`])
+    min_chunk_lines: usize,
 }
 
 impl CodeChunker {
     pub fn new(strategy: ChunkStrategy) -> Self {
-        Self { strategy }
+        Self {
+            strategy,
+            markup_chunking: true,
+            docs_project: false,
+            overlap: 0,
+            min_chunk_lines: 1,
+        }
+    }
+
+    /// Make consecutive chunks of a file share `lines` lines
+    ///
+    /// Line-based chunks advance by the chunk size minus the overlap; AST and
+    /// markup chunks are extended upwards by the overlap, so each one also
+    /// carries the tail of what precedes it. The overlap is capped below the
+    /// strategy's chunk size, and chunk start lines include it.
+    pub fn with_overlap(mut self, lines: usize) -> Self {
+        self.overlap = lines;
+        self
+    }
+
+    /// Keep trivial fragments such as a lone `}` from being indexed
+    ///
+    /// A chunk of fewer than `lines` lines joins the chunk before it, or else
+    /// the one after it, when only blank lines separate them, and is dropped
+    /// otherwise. A file's only chunk is kept however short, so small files
+    /// stay findable.
+    pub fn with_min_chunk_lines(mut self, lines: usize) -> Self {
+        self.min_chunk_lines = lines;
+        self
+    }
+
+    /// Enable or disable markup-aware chunking for Markdown, HTML and plain text
+    pub fn with_markup_chunking(mut self, enabled: bool) -> Self {
+        self.markup_chunking = enabled;
+        self
+    }
+
+    /// Enable or disable routing documentation into a separate docs project
+    ///
+    /// Chunks of documentation files move to the docs project, and doc
+    /// comments of source files are added there as chunks of their own (see
+    /// [`docs::docs_project_name`]).
+    pub fn with_docs_project(mut self, enabled: bool) -> Self {
+        self.docs_project = enabled;
+        self
     }
 
     /// Create a chunker with default strategy (Hybrid AST with 50 line fallback)
@@ -32,26 +187,236 @@ impl CodeChunker {
 
     /// Chunk a file into multiple code chunks
     pub fn chunk_file(&self, file_info: &FileInfo) -> Vec<CodeChunk> {
-        match &self.strategy {
-            ChunkStrategy::FixedLines(lines_per_chunk) => {
-                self.chunk_fixed_lines(file_info, *lines_per_chunk)
+        self.chunk_file_with_diagnostic(file_info).0
+    }
+
+    /// Chunk a file and report which chunking method was applied and why
+    pub fn chunk_file_with_diagnostic(
+        &self,
+        file_info: &FileInfo,
+    ) -> (Vec<CodeChunk>, ChunkingDiagnostic) {
+        let (mut chunks, applied, reason) = self.chunk_by_strategy(file_info);
+        if applied != AppliedChunking::Binary {
+            chunks = self.merge_short_chunks(file_info, chunks);
+        }
+        for chunk in &mut chunks {
+            chunk.metadata.chunk_method = Some(applied.to_string());
+        }
+
+        if self.docs_project
+            && !file_info.binary
+            && let Some(language) = file_info.language.as_deref()
+        {
+            let docs_project = docs::docs_project_name(file_info.project.as_deref());
+            if docs::is_doc_language(language) {
+                for chunk in &mut chunks {
+                    chunk.metadata.project = Some(docs_project.clone());
+                }
+            } else {
+                chunks.extend(Self::doc_comment_chunks(file_info, language, &docs_project));
+            }
+        }
+
+        let diagnostic = ChunkingDiagnostic {
+            file_path: file_info.relative_path.clone(),
+            applied,
+            reason,
+            chunks: chunks.len(),
+        };
+        (chunks, diagnostic)
+    }
+
+    /// Chunk a file according to the strategy (or by sections, for markup),
+    /// along with the method applied and the reason for it
+    fn chunk_by_strategy(&self, file_info: &FileInfo) -> (Vec<CodeChunk>, AppliedChunking, String) {
+        if file_info.binary {
+            return (
+                vec![Self::binary_chunk(file_info)],
+                AppliedChunking::Binary,
+                "binary file, indexed by metadata only".to_string(),
+            );
+        }
+
+        if self.markup_chunking
+            && let Some(kind) = file_info
+                .language
+                .as_deref()
+                .and_then(MarkupKind::from_language)
+        {
+            let markup_chunks = self.chunk_markup(file_info, kind);
+            if !markup_chunks.is_empty() {
+                return (
+                    self.extend_by_overlap(file_info, markup_chunks),
+                    AppliedChunking::Markup,
+                    format!("markup chunking of {:?}", kind),
+                );
             }
+        }
+
+        match &self.strategy {
+            ChunkStrategy::FixedLines(lines_per_chunk) => (
+                self.chunk_lines(file_info, *lines_per_chunk),
+                AppliedChunking::FixedLines,
+                format!("FixedLines strategy, {} lines per chunk", lines_per_chunk),
+            ),
             ChunkStrategy::SlidingWindow { size, overlap } => {
-                self.chunk_sliding_window(file_info, *size, *overlap)
+                let overlap = (*overlap).max(self.overlap()).min(size.saturating_sub(1));
+                (
+                    self.chunk_sliding_window(file_info, *size, overlap),
+                    AppliedChunking::SlidingWindow,
+                    format!(
+                        "SlidingWindow strategy, {} lines with {} overlap",
+                        size, overlap
+                    ),
+                )
             }
-            ChunkStrategy::AstBased => self.chunk_ast_based(file_info),
+            ChunkStrategy::AstBased => match self.chunk_ast_based(file_info) {
+                Ok((chunks, reason)) | Err((chunks, reason)) => (
+                    self.extend_by_overlap(file_info, chunks),
+                    AppliedChunking::Ast,
+                    reason,
+                ),
+            },
             ChunkStrategy::Hybrid { fallback_lines } => {
                 // Try AST-based first, fallback to fixed lines if it fails
-                let ast_chunks = self.chunk_ast_based(file_info);
-                if ast_chunks.is_empty() {
-                    self.chunk_fixed_lines(file_info, *fallback_lines)
-                } else {
-                    ast_chunks
+                match self.chunk_ast_based(file_info) {
+                    Ok((ast_chunks, reason)) => (
+                        self.extend_by_overlap(file_info, ast_chunks),
+                        AppliedChunking::Ast,
+                        reason,
+                    ),
+                    Err((_, reason)) => (
+                        self.chunk_lines(file_info, *fallback_lines),
+                        AppliedChunking::LineFallback,
+                        reason,
+                    ),
                 }
             }
+            ChunkStrategy::ChunkByTokens { max_tokens } => match self.chunk_ast_based(file_info) {
+                Ok((ast_chunks, reason)) => (
+                    self.extend_by_overlap(file_info, split_by_tokens(ast_chunks, *max_tokens).0),
+                    AppliedChunking::Ast,
+                    format!("{}, split at {} tokens", reason, max_tokens),
+                ),
+                Err((_, reason)) => (
+                    self.extend_by_overlap(file_info, self.chunk_tokens(file_info, *max_tokens)),
+                    AppliedChunking::TokenFallback,
+                    reason,
+                ),
+            },
+        }
+    }
+
+    /// Lines per chunk of the strategy, or the markup section limit when it
+    /// has none
+    fn max_lines(&self) -> usize {
+        match self.strategy {
+            ChunkStrategy::FixedLines(lines) => lines,
+            ChunkStrategy::SlidingWindow { size, .. } => size,
+            ChunkStrategy::Hybrid { fallback_lines } => fallback_lines,
+            ChunkStrategy::AstBased | ChunkStrategy::ChunkByTokens { .. } => {
+                DEFAULT_MARKUP_MAX_LINES
+            }
         }
     }
 
+    /// Configured overlap, capped below the chunk size
+    fn overlap(&self) -> usize {
+        self.overlap.min(self.max_lines().saturating_sub(1))
+    }
+
+    /// Chunk into `size` lines, sharing the overlap between consecutive chunks
+    fn chunk_lines(&self, file_info: &FileInfo, size: usize) -> Vec<CodeChunk> {
+        match self.overlap() {
+            0 => self.chunk_fixed_lines(file_info, size),
+            overlap => self.chunk_sliding_window(file_info, size, overlap),
+        }
+    }
+
+    /// Extend chunks upwards by the overlap, so each one also carries the
+    /// lines just before it
+    fn extend_by_overlap(
+        &self,
+        file_info: &FileInfo,
+        mut chunks: Vec<CodeChunk>,
+    ) -> Vec<CodeChunk> {
+        let overlap = self.overlap();
+        if overlap == 0 {
+            return chunks;
+        }
+
+        let lines: Vec<&str> = file_info.content.lines().collect();
+        for chunk in &mut chunks {
+            let end_line = chunk.metadata.end_line.min(lines.len());
+            let start_line = chunk.metadata.start_line.saturating_sub(overlap).max(1);
+            if start_line >= chunk.metadata.start_line || start_line > end_line {
+                continue;
+            }
+            chunk.content = lines[start_line - 1..end_line].join("\n");
+            chunk.metadata.start_line = start_line;
+            chunk.metadata.end_line = end_line;
+        }
+        chunks
+    }
+
+    /// Fold chunks shorter than the minimum into an adjacent chunk, or drop
+    /// them (see [`CodeChunker::with_min_chunk_lines`])
+    fn merge_short_chunks(&self, file_info: &FileInfo, chunks: Vec<CodeChunk>) -> Vec<CodeChunk> {
+        if self.min_chunk_lines <= 1 || chunks.len() < 2 {
+            return chunks;
+        }
+
+        let lines: Vec<&str> = file_info.content.lines().collect();
+        let is_short = |chunk: &CodeChunk| {
+            let metadata = &chunk.metadata;
+            metadata.end_line + 1 < metadata.start_line + self.min_chunk_lines
+        };
+        // Overlapping, touching or separated by blank lines only
+        let adjacent = |end_line: usize, start_line: usize| {
+            start_line <= end_line + 1
+                || lines
+                    .get(end_line..start_line - 1)
+                    .is_some_and(|gap| gap.iter().all(|line| line.trim().is_empty()))
+        };
+        let span = |chunk: &mut CodeChunk, start_line: usize, end_line: usize| {
+            let end_line = end_line.min(lines.len());
+            chunk.content = lines[start_line - 1..end_line].join("\n");
+            chunk.metadata.start_line = start_line;
+            chunk.metadata.end_line = end_line;
+        };
+
+        let mut merged: Vec<CodeChunk> = Vec::with_capacity(chunks.len());
+        // Short chunk with no chunk before it to join, waiting for the next one
+        let mut pending: Option<CodeChunk> = None;
+        for mut chunk in chunks {
+            if let Some(short) = pending.take()
+                && adjacent(short.metadata.end_line, chunk.metadata.start_line)
+            {
+                let end_line = chunk.metadata.end_line.max(short.metadata.end_line);
+                span(&mut chunk, short.metadata.start_line, end_line);
+            }
+
+            if !is_short(&chunk) {
+                merged.push(chunk);
+            } else if let Some(previous) = merged.last_mut()
+                && adjacent(previous.metadata.end_line, chunk.metadata.start_line)
+            {
+                let (start_line, end_line) = (
+                    previous.metadata.start_line,
+                    previous.metadata.end_line.max(chunk.metadata.end_line),
+                );
+                span(previous, start_line, end_line);
+            } else {
+                pending = Some(chunk);
+            }
+        }
+        if merged.is_empty() {
+            // Nothing long enough to join: keep the file findable
+            merged.extend(pending);
+        }
+        merged
+    }
+
     /// Chunk using fixed number of lines
     fn chunk_fixed_lines(&self, file_info: &FileInfo, lines_per_chunk: usize) -> Vec<CodeChunk> {
         let lines: Vec<&str> = file_info.content.lines().collect();
@@ -86,6 +451,12 @@ impl CodeChunker {
                 extension: file_info.extension.clone(),
                 file_hash: file_info.hash.clone(),
                 indexed_at: timestamp,
+                symbol_kind: None,
+                binary: false,
+                file_size: None,
+                branch: None,
+                commit_date: None,
+                chunk_method: None,
             };
 
             chunks.push(CodeChunk { content, metadata });
@@ -94,6 +465,48 @@ impl CodeChunker {
         chunks
     }
 
+    /// Chunk into runs of lines of at most `max_tokens` estimated tokens
+    fn chunk_tokens(&self, file_info: &FileInfo, max_tokens: usize) -> Vec<CodeChunk> {
+        let lines: Vec<&str> = file_info.content.lines().collect();
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        pack_lines(&lines, max_tokens)
+            .into_iter()
+            .filter_map(|(start_idx, end_idx)| {
+                let content = lines[start_idx..end_idx].join("\n");
+
+                // Skip empty chunks
+                if content.trim().is_empty() {
+                    return None;
+                }
+
+                let metadata = ChunkMetadata {
+                    file_path: file_info.relative_path.clone(),
+                    root_path: Some(file_info.root_path.clone()),
+                    project: file_info.project.clone(),
+                    start_line: start_idx + 1,
+                    end_line: end_idx,
+                    language: file_info.language.clone(),
+                    extension: file_info.extension.clone(),
+                    file_hash: file_info.hash.clone(),
+                    indexed_at: timestamp,
+                    symbol_kind: None,
+                    binary: false,
+                    file_size: None,
+                    branch: None,
+                    commit_date: None,
+                    chunk_method: None,
+                };
+
+                Some(CodeChunk { content, metadata })
+            })
+            .collect()
+    }
+
     /// Chunk using sliding window with overlap
     fn chunk_sliding_window(
         &self,
@@ -140,6 +553,12 @@ impl CodeChunker {
                 extension: file_info.extension.clone(),
                 file_hash: file_info.hash.clone(),
                 indexed_at: timestamp,
+                symbol_kind: None,
+                binary: false,
+                file_size: None,
+                branch: None,
+                commit_date: None,
+                chunk_method: None,
             };
 
             chunks.push(CodeChunk { content, metadata });
@@ -155,14 +574,127 @@ impl CodeChunker {
         chunks
     }
 
+    /// Single metadata-only chunk for a binary file
+    fn binary_chunk(file_info: &FileInfo) -> CodeChunk {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        CodeChunk {
+            content: String::new(),
+            metadata: ChunkMetadata {
+                file_path: file_info.relative_path.clone(),
+                root_path: Some(file_info.root_path.clone()),
+                project: file_info.project.clone(),
+                start_line: 1,
+                end_line: 1,
+                language: file_info.language.clone(),
+                extension: file_info.extension.clone(),
+                file_hash: file_info.hash.clone(),
+                indexed_at: timestamp,
+                symbol_kind: None,
+                binary: true,
+                file_size: file_info.file_size,
+                branch: None,
+                commit_date: None,
+                chunk_method: None,
+            },
+        }
+    }
+
+    /// Chunk markup by headings and paragraphs (or HTML block elements)
+    fn chunk_markup(&self, file_info: &FileInfo, kind: MarkupKind) -> Vec<CodeChunk> {
+        let max_lines = self.max_lines();
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let lines: Vec<&str> = file_info.content.lines().collect();
+        let mut chunks = Vec::new();
+
+        for section in markup::split_sections(&file_info.content, kind, max_lines) {
+            let content = lines[section.start_line - 1..section.end_line].join("\n");
+
+            let metadata = ChunkMetadata {
+                file_path: file_info.relative_path.clone(),
+                root_path: Some(file_info.root_path.clone()),
+                project: file_info.project.clone(),
+                start_line: section.start_line,
+                end_line: section.end_line,
+                language: file_info.language.clone(),
+                extension: file_info.extension.clone(),
+                file_hash: file_info.hash.clone(),
+                indexed_at: timestamp,
+                symbol_kind: None,
+                binary: false,
+                file_size: None,
+                branch: None,
+                commit_date: None,
+                chunk_method: None,
+            };
+
+            chunks.push(CodeChunk { content, metadata });
+        }
+
+        chunks
+    }
+
+    /// One chunk per doc comment of a source file, in the docs project
+    fn doc_comment_chunks(
+        file_info: &FileInfo,
+        language: &str,
+        docs_project: &str,
+    ) -> Vec<CodeChunk> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let lines: Vec<&str> = file_info.content.lines().collect();
+        docs::doc_comments(&file_info.content, language)
+            .into_iter()
+            .map(|comment| CodeChunk {
+                content: lines[comment.start_line - 1..comment.end_line].join("\n"),
+                metadata: ChunkMetadata {
+                    file_path: file_info.relative_path.clone(),
+                    root_path: Some(file_info.root_path.clone()),
+                    project: Some(docs_project.to_string()),
+                    start_line: comment.start_line,
+                    end_line: comment.end_line,
+                    language: file_info.language.clone(),
+                    extension: file_info.extension.clone(),
+                    file_hash: file_info.hash.clone(),
+                    indexed_at: timestamp,
+                    symbol_kind: None,
+                    binary: false,
+                    file_size: None,
+                    branch: None,
+                    commit_date: None,
+                    chunk_method: None,
+                },
+            })
+            .collect()
+    }
+
     /// Chunk using AST-based parsing (functions, classes, methods)
-    fn chunk_ast_based(&self, file_info: &FileInfo) -> Vec<CodeChunk> {
+    ///
+    /// Returns the chunks with the reason they could be produced, or the reason
+    /// they could not (no grammar, parse failure, no semantic units found)
+    /// along with the chunks found anyway, if syntax errors cover too much of
+    /// the file to trust them.
+    fn chunk_ast_based(&self, file_info: &FileInfo) -> AstChunking {
         // Check if we have an extension and can parse it
         let extension = match &file_info.extension {
             Some(ext) => ext,
             None => {
                 tracing::debug!("No extension for AST parsing: {:?}", file_info.path);
-                return Vec::new();
+                return Err((
+                    Vec::new(),
+                    "no file extension to pick a grammar".to_string(),
+                ));
             }
         };
 
@@ -171,16 +703,23 @@ impl CodeChunker {
             Ok(p) => p,
             Err(_) => {
                 tracing::debug!("Unsupported language for AST parsing: {}", extension);
-                return Vec::new();
+                return Err((Vec::new(), format!("no grammar for '.{}' files", extension)));
             }
         };
 
         // Parse the file
-        let ast_nodes = match parser.parse(&file_info.content) {
-            Ok(nodes) => nodes,
+        let parsed = match parser.parse_file(&file_info.content) {
+            Ok(parsed) => parsed,
             Err(e) => {
                 tracing::warn!("Failed to parse file {:?}: {}", file_info.path, e);
-                return Vec::new();
+                return Err((
+                    Vec::new(),
+                    format!(
+                        "{} grammar available, parse failed: {}",
+                        parser.language_name(),
+                        e
+                    ),
+                ));
             }
         };
 
@@ -192,7 +731,7 @@ impl CodeChunker {
         let mut chunks = Vec::new();
         let lines: Vec<&str> = file_info.content.lines().collect();
 
-        for ast_node in ast_nodes {
+        for ast_node in parsed.nodes {
             // Extract the content for this node
             let start_idx = ast_node.start_line.saturating_sub(1);
             let end_idx = ast_node.end_line.min(lines.len());
@@ -219,6 +758,12 @@ impl CodeChunker {
                 extension: file_info.extension.clone(),
                 file_hash: file_info.hash.clone(),
                 indexed_at: timestamp,
+                symbol_kind: symbol_kind_for_node(&ast_node.kind),
+                binary: false,
+                file_size: None,
+                branch: None,
+                commit_date: None,
+                chunk_method: None,
             };
 
             chunks.push(CodeChunk { content, metadata });
@@ -227,12 +772,107 @@ impl CodeChunker {
         // If no chunks were created, log it
         if chunks.is_empty() {
             tracing::debug!("No AST chunks created for {:?}", file_info.path);
+            return Err((
+                chunks,
+                format!(
+                    "{} grammar available, parse succeeded but found no functions, classes or methods",
+                    parser.language_name()
+                ),
+            ));
         }
 
-        chunks
+        let error_fraction = parsed.error_bytes as f64 / file_info.content.len().max(1) as f64;
+        if error_fraction > MAX_PARSE_ERROR_FRACTION {
+            tracing::debug!(
+                "Syntax errors cover {:.0}% of {:?}, not chunking it by AST",
+                error_fraction * 100.0,
+                file_info.path
+            );
+            return Err((
+                chunks,
+                format!(
+                    "{} grammar available, but syntax errors cover {:.0}% of the file",
+                    parser.language_name(),
+                    error_fraction * 100.0
+                ),
+            ));
+        }
+
+        let reason = format!(
+            "{} grammar available, parse succeeded",
+            parser.language_name()
+        );
+        Ok((chunks, reason))
+    }
+}
+
+/// AST chunks with the reason they were produced, or the reason AST chunking
+/// was not possible with any (untrusted) chunks found
+type AstChunking = std::result::Result<(Vec<CodeChunk>, String), (Vec<CodeChunk>, String)>;
+
+/// Map an AST node kind to the stored symbol kind tag, if it is a known symbol
+fn symbol_kind_for_node(ast_kind: &str) -> Option<String> {
+    match SymbolKind::from_ast_kind(ast_kind) {
+        SymbolKind::Unknown => None,
+        kind => Some(kind.as_str().to_string()),
     }
 }
 
+/// Split chunks over `max_tokens` estimated tokens into runs of whole lines
+/// within the budget, along with the number of chunks that were split
+pub(crate) fn split_by_tokens(
+    chunks: Vec<CodeChunk>,
+    max_tokens: usize,
+) -> (Vec<CodeChunk>, usize) {
+    let mut split = Vec::with_capacity(chunks.len());
+    let mut split_count = 0;
+    for chunk in chunks {
+        if estimate_tokens(&chunk.content) <= max_tokens {
+            split.push(chunk);
+            continue;
+        }
+
+        split_count += 1;
+
+        let lines: Vec<&str> = chunk.content.lines().collect();
+        for (start_idx, end_idx) in pack_lines(&lines, max_tokens) {
+            let content = lines[start_idx..end_idx].join("\n");
+            if content.trim().is_empty() {
+                continue;
+            }
+            let mut metadata = chunk.metadata.clone();
+            metadata.start_line = chunk.metadata.start_line + start_idx;
+            metadata.end_line = chunk.metadata.start_line + end_idx - 1;
+            split.push(CodeChunk { content, metadata });
+        }
+    }
+    (split, split_count)
+}
+
+/// Ranges of consecutive lines of at most `max_tokens` estimated tokens each
+///
+/// A single line over the budget gets a range of its own; it is truncated
+/// when embedded.
+fn pack_lines(lines: &[&str], max_tokens: usize) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut start_idx = 0;
+    let mut tokens = 0;
+
+    for (idx, line) in lines.iter().enumerate() {
+        let line_tokens = estimate_tokens(line);
+        if idx > start_idx && tokens + line_tokens > max_tokens {
+            ranges.push((start_idx, idx));
+            start_idx = idx;
+            tokens = 0;
+        }
+        tokens += line_tokens;
+    }
+    if start_idx < lines.len() {
+        ranges.push((start_idx, lines.len()));
+    }
+    ranges
+}
+
 impl Default for CodeChunker {
     fn default() -> Self {
         Self::default_strategy()
@@ -254,6 +894,8 @@ mod tests {
             language: Some("Rust".to_string()),
             content: content.to_string(),
             hash: "test_hash".to_string(),
+            binary: false,
+            file_size: None,
         }
     }
 
@@ -367,6 +1009,72 @@ mod tests {
         assert!(chunks.len() > 10);
     }
 
+    fn line_ranges(chunks: &[CodeChunk]) -> Vec<(usize, usize)> {
+        chunks
+            .iter()
+            .map(|c| (c.metadata.start_line, c.metadata.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_fixed_lines_with_overlap() {
+        let content = (1..=10)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let file_info = create_test_file_info(&content);
+
+        let chunker = CodeChunker::new(ChunkStrategy::FixedLines(4)).with_overlap(1);
+        let chunks = chunker.chunk_file(&file_info);
+
+        assert_eq!(line_ranges(&chunks), [(1, 4), (4, 7), (7, 10)]);
+        assert!(chunks[1].content.starts_with("line 4\n"));
+        assert!(chunks[1].content.ends_with("line 7"));
+    }
+
+    #[test]
+    fn test_min_chunk_lines_folds_standalone_brace() {
+        let content = "fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}";
+        let file_info = create_test_file_info(content);
+
+        let chunks = CodeChunker::new(ChunkStrategy::FixedLines(3)).chunk_file(&file_info);
+        assert_eq!(line_ranges(&chunks), [(1, 3), (4, 4)]);
+        assert_eq!(chunks[1].content, "}");
+
+        let chunker = CodeChunker::new(ChunkStrategy::FixedLines(3)).with_min_chunk_lines(2);
+        let chunks = chunker.chunk_file(&file_info);
+        assert_eq!(line_ranges(&chunks), [(1, 4)]);
+        assert_eq!(chunks[0].content, content);
+    }
+
+    #[test]
+    fn test_overlap_capped_below_chunk_size() {
+        let content = (1..=5)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let file_info = create_test_file_info(&content);
+
+        let chunker = CodeChunker::new(ChunkStrategy::FixedLines(3)).with_overlap(10);
+        let chunks = chunker.chunk_file(&file_info);
+
+        // Capped at 2 lines: each chunk moves on by one line
+        assert_eq!(line_ranges(&chunks), [(1, 3), (2, 4), (3, 5)]);
+    }
+
+    #[test]
+    fn test_ast_chunks_extended_by_overlap() {
+        let content = "fn hello() {\n    println!(\"Hello\");\n}\n\nfn world() {\n    println!(\"World\");\n}\n";
+        let file_info = create_test_file_info(content);
+
+        let chunker = CodeChunker::new(ChunkStrategy::AstBased).with_overlap(2);
+        let chunks = chunker.chunk_file(&file_info);
+
+        assert_eq!(line_ranges(&chunks), [(1, 3), (3, 7)]);
+        assert!(chunks[1].content.starts_with("}\n\nfn world()"));
+        assert_eq!(chunks[1].metadata.symbol_kind.as_deref(), Some("function"));
+    }
+
     #[test]
     fn test_ast_based_rust() {
         let content = r#"
@@ -385,6 +1093,37 @@ fn world() {
         assert!(chunks.len() >= 2);
     }
 
+    #[test]
+    fn test_ast_based_tags_symbol_kind() {
+        let content = r#"
+struct Point {
+    x: i32,
+}
+
+fn origin() -> Point {
+    Point { x: 0 }
+}
+"#;
+        let file_info = create_test_file_info(content);
+        let chunker = CodeChunker::new(ChunkStrategy::AstBased);
+        let chunks = chunker.chunk_file(&file_info);
+
+        let kinds: Vec<_> = chunks
+            .iter()
+            .map(|c| c.metadata.symbol_kind.as_deref())
+            .collect();
+        assert!(kinds.contains(&Some("struct")));
+        assert!(kinds.contains(&Some("function")));
+    }
+
+    #[test]
+    fn test_fixed_lines_has_no_symbol_kind() {
+        let file_info = create_test_file_info("fn main() {}");
+        let chunker = CodeChunker::new(ChunkStrategy::FixedLines(10));
+        let chunks = chunker.chunk_file(&file_info);
+        assert!(chunks.iter().all(|c| c.metadata.symbol_kind.is_none()));
+    }
+
     #[test]
     fn test_ast_based_no_extension() {
         let mut file_info = create_test_file_info("fn main() {}");
@@ -427,6 +1166,80 @@ fn hello() {
         assert!(!chunks.is_empty());
     }
 
+    #[test]
+    fn test_chunk_by_tokens_splits_large_units() {
+        let body = (1..=40)
+            .map(|i| format!("    let value_{} = compute({}, {} + 1);", i, i, i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let content = format!("fn small() {{}}\n\nfn large() {{\n{}\n}}\n", body);
+        let file_info = create_test_file_info(&content);
+
+        let chunker = CodeChunker::new(ChunkStrategy::ChunkByTokens { max_tokens: 100 });
+        let (chunks, diagnostic) = chunker.chunk_file_with_diagnostic(&file_info);
+
+        assert_eq!(diagnostic.applied, AppliedChunking::Ast);
+        assert!(diagnostic.reason.contains("split at 100 tokens"));
+        assert_eq!(chunks[0].content, "fn small() {}");
+        assert!(chunks.len() > 2);
+        assert!(chunks.iter().all(|c| estimate_tokens(&c.content) <= 100));
+
+        // The pieces of the large function cover it without gaps
+        assert_eq!(chunks[1].metadata.start_line, 3);
+        for pair in chunks[1..].windows(2) {
+            assert_eq!(pair[1].metadata.start_line, pair[0].metadata.end_line + 1);
+        }
+        assert_eq!(chunks.last().unwrap().metadata.end_line, 44);
+    }
+
+    #[test]
+    fn test_chunk_by_tokens_packs_files_without_grammar() {
+        let content = (1..=30)
+            .map(|i| format!("key_{} = {}", i, i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut file_info = create_test_file_info(&content);
+        file_info.extension = Some("toml".to_string());
+        file_info.language = Some("TOML".to_string());
+
+        let chunker = CodeChunker::new(ChunkStrategy::ChunkByTokens { max_tokens: 20 });
+        let (chunks, diagnostic) = chunker.chunk_file_with_diagnostic(&file_info);
+
+        assert_eq!(diagnostic.applied, AppliedChunking::TokenFallback);
+        assert_eq!(diagnostic.applied.to_string(), "token fallback");
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| estimate_tokens(&c.content) <= 20));
+        assert_eq!(chunks[0].metadata.start_line, 1);
+        assert_eq!(chunks.last().unwrap().metadata.end_line, 30);
+    }
+
+    #[test]
+    fn test_diagnostic_reports_ast_for_supported_language() {
+        let file_info = create_test_file_info("fn hello() {\n    println!(\"Hello\");\n}\n");
+        let (chunks, diagnostic) =
+            CodeChunker::default_strategy().chunk_file_with_diagnostic(&file_info);
+
+        assert_eq!(diagnostic.applied, AppliedChunking::Ast);
+        assert_eq!(diagnostic.applied.to_string(), "AST");
+        assert!(diagnostic.reason.contains("parse succeeded"));
+        assert_eq!(diagnostic.chunks, chunks.len());
+        assert_eq!(diagnostic.file_path, "test.rs");
+    }
+
+    #[test]
+    fn test_diagnostic_reports_line_fallback_for_unsupported_language() {
+        let mut file_info = create_test_file_info("key = 1\nother = 2");
+        file_info.extension = Some("toml".to_string());
+        file_info.language = Some("TOML".to_string());
+        let (_, diagnostic) =
+            CodeChunker::default_strategy().chunk_file_with_diagnostic(&file_info);
+
+        assert_eq!(diagnostic.applied, AppliedChunking::LineFallback);
+        assert_eq!(diagnostic.applied.to_string(), "line fallback");
+        assert_eq!(diagnostic.reason, "no grammar for '.toml' files");
+        assert_eq!(diagnostic.chunks, 1);
+    }
+
     #[test]
     fn test_metadata_fields() {
         let mut file_info = create_test_file_info("fn main() {}");
@@ -483,4 +1296,111 @@ fn hello() {
         // Should handle parse errors gracefully
         assert_eq!(chunks.len(), 0);
     }
+
+    #[test]
+    fn test_hybrid_falls_back_to_lines_for_broken_syntax() {
+        let content = format!(
+            "fn valid() {{\n    1\n}}\n{}",
+            "]] )) }} %% ]] )) }}\n".repeat(20)
+        );
+        let file_info = create_test_file_info(&content);
+        let chunker = CodeChunker::new(ChunkStrategy::Hybrid { fallback_lines: 10 });
+        let (chunks, diagnostic) = chunker.chunk_file_with_diagnostic(&file_info);
+
+        assert_eq!(diagnostic.applied, AppliedChunking::LineFallback);
+        assert!(diagnostic.reason.contains("syntax errors cover"));
+        assert!(
+            chunks
+                .iter()
+                .all(|c| c.metadata.chunk_method.as_deref() == Some("line fallback"))
+        );
+
+        // The whole file is still chunked
+        assert_eq!(chunks[0].metadata.start_line, 1);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[1].metadata.start_line, pair[0].metadata.end_line + 1);
+        }
+        assert_eq!(chunks.last().unwrap().metadata.end_line, 23);
+    }
+
+    fn create_markdown_file_info(content: &str) -> FileInfo {
+        let mut file_info = create_test_file_info(content);
+        file_info.path = PathBuf::from("README.md");
+        file_info.relative_path = "README.md".to_string();
+        file_info.extension = Some("md".to_string());
+        file_info.language = Some("Markdown".to_string());
+        file_info
+    }
+
+    #[test]
+    fn test_markdown_chunks_align_to_headings() {
+        let content = "# Project\n\nOverview text.\n\n## Installation\n\nRun the installer.\n\n\
+                       Then configure it.\n\n## Usage\n\nCall the API.";
+        let file_info = create_markdown_file_info(content);
+        let chunker = CodeChunker::default_strategy();
+        let chunks = chunker.chunk_file(&file_info);
+
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[0].content.starts_with("# Project"));
+        assert!(chunks[0].content.contains("Overview text."));
+        assert!(chunks[1].content.starts_with("## Installation"));
+        assert!(chunks[1].content.ends_with("Then configure it."));
+        assert!(chunks[2].content.starts_with("## Usage"));
+        assert!(chunks[2].content.ends_with("Call the API."));
+
+        assert_eq!(
+            (chunks[0].metadata.start_line, chunks[0].metadata.end_line),
+            (1, 3)
+        );
+        assert_eq!(
+            (chunks[1].metadata.start_line, chunks[1].metadata.end_line),
+            (5, 9)
+        );
+        assert_eq!(
+            (chunks[2].metadata.start_line, chunks[2].metadata.end_line),
+            (11, 13)
+        );
+        assert!(chunks.iter().all(|c| c.metadata.symbol_kind.is_none()));
+    }
+
+    #[test]
+    fn test_markdown_chunking_disabled_uses_strategy() {
+        let content = "# One\ntext\n# Two\ntext";
+        let file_info = create_markdown_file_info(content);
+        let chunker = CodeChunker::new(ChunkStrategy::FixedLines(10)).with_markup_chunking(false);
+        let chunks = chunker.chunk_file(&file_info);
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].metadata.end_line, 4);
+    }
+
+    #[test]
+    fn test_markup_chunking_ignores_source_code() {
+        let content = "# not a heading in Rust\nfn main() {}";
+        let file_info = create_test_file_info(content);
+        let chunker = CodeChunker::new(ChunkStrategy::FixedLines(10));
+        let chunks = chunker.chunk_file(&file_info);
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].metadata.end_line, 2);
+    }
+
+    #[test]
+    fn test_binary_file_yields_metadata_only_chunk() {
+        let file_info = FileInfo {
+            relative_path: "assets/logo.png".to_string(),
+            extension: Some("png".to_string()),
+            language: None,
+            binary: true,
+            file_size: Some(2048),
+            ..create_test_file_info("")
+        };
+        let chunks = CodeChunker::default_strategy().chunk_file(&file_info);
+
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].content.is_empty());
+        assert!(chunks[0].metadata.binary);
+        assert_eq!(chunks[0].metadata.file_size, Some(2048));
+        assert_eq!(chunks[0].metadata.file_path, "assets/logo.png");
+    }
 }
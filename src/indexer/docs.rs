@@ -0,0 +1,178 @@
+//! Documentation routing for the separate docs project
+//!
+//! Documentation files (Markdown, reStructuredText, ...) and doc comments
+//! pulled out of source files can be indexed into a project of their own, so
+//! prose can be searched apart from code, or together with it.
+
+/// Suffix appended to a project name to form its docs project
+pub const DOCS_PROJECT_SUFFIX: &str = "-docs";
+
+/// Docs project for chunks of `project` (`docs` for chunks without a project)
+pub fn docs_project_name(project: Option<&str>) -> String {
+    match project {
+        Some(project) => format!("{}{}", project, DOCS_PROJECT_SUFFIX),
+        None => "docs".to_string(),
+    }
+}
+
+/// Whether files of `language` are documentation as a whole
+pub fn is_doc_language(language: &str) -> bool {
+    matches!(
+        language,
+        "Markdown" | "HTML" | "Text" | "reStructuredText" | "AsciiDoc" | "Org Mode" | "PDF"
+    )
+}
+
+/// Line range of a doc comment (1-based, inclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocComment {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// How a language writes doc comments
+enum CommentStyle {
+    /// Runs of lines starting with one of these prefixes (`///`, `//!`)
+    LinePrefix(&'static [&'static str]),
+    /// Blocks from `open` at the start of a line to `close` (`/** ... */`)
+    Block {
+        open: &'static str,
+        close: &'static str,
+    },
+    /// Triple-quoted strings opening a module, class or function body
+    Docstring,
+}
+
+const JAVADOC: CommentStyle = CommentStyle::Block {
+    open: "/**",
+    close: "*/",
+};
+
+fn comment_styles(language: &str) -> &'static [CommentStyle] {
+    match language {
+        "Rust" => &[CommentStyle::LinePrefix(&["///", "//!"])],
+        "Swift" | "C" | "C++" | "C/C++ Header" => &[CommentStyle::LinePrefix(&["///"]), JAVADOC],
+        "Java" | "JavaScript" | "TypeScript" | "Kotlin" | "Scala" | "PHP" => &[JAVADOC],
+        "Go" => &[CommentStyle::LinePrefix(&["//"])],
+        "Zig" => &[CommentStyle::LinePrefix(&["///", "//!"])],
+        "Dart" => &[CommentStyle::LinePrefix(&["///"]), JAVADOC],
+        "Lua" => &[CommentStyle::LinePrefix(&["---"])],
+        "Python" => &[CommentStyle::Docstring],
+        _ => &[],
+    }
+}
+
+/// Doc comments in source code of `language`
+pub fn doc_comments(content: &str, language: &str) -> Vec<DocComment> {
+    let styles = comment_styles(language);
+    if styles.is_empty() {
+        return Vec::new();
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut comments = Vec::new();
+    let mut idx = 0;
+
+    while idx < lines.len() {
+        match styles
+            .iter()
+            .find_map(|style| comment_end(style, &lines, idx))
+        {
+            Some(end) => {
+                comments.push(DocComment {
+                    start_line: idx + 1,
+                    end_line: end + 1,
+                });
+                idx = end + 1;
+            }
+            None => idx += 1,
+        }
+    }
+
+    comments
+}
+
+/// Last line (0-based) of a doc comment of `style` starting at line `start`
+fn comment_end(style: &CommentStyle, lines: &[&str], start: usize) -> Option<usize> {
+    let line = lines[start].trim_start();
+    match style {
+        CommentStyle::LinePrefix(prefixes) => {
+            let is_comment = |line: &str| {
+                let line = line.trim_start();
+                prefixes.iter().any(|prefix| line.starts_with(prefix))
+            };
+            if !is_comment(line) {
+                return None;
+            }
+            let run = lines[start..].iter().take_while(|l| is_comment(l)).count();
+            Some(start + run - 1)
+        }
+        CommentStyle::Block { open, close } => block_end(lines, start, open, close),
+        CommentStyle::Docstring => {
+            // Only the first statement of a module or of a `def`/`class` body
+            let opens_body = lines[..start]
+                .iter()
+                .rev()
+                .find(|l| !l.trim().is_empty())
+                .is_none_or(|prev| prev.trim_end().ends_with(':'));
+            if !opens_body {
+                return None;
+            }
+            ["\"\"\"", "'''"]
+                .iter()
+                .find_map(|quotes| block_end(lines, start, quotes, quotes))
+        }
+    }
+}
+
+/// Last line (0-based) of a block opened by `open` at the start of line `start`
+fn block_end(lines: &[&str], start: usize, open: &str, close: &str) -> Option<usize> {
+    let rest = lines[start].trim_start().strip_prefix(open)?;
+    if rest.contains(close) {
+        return Some(start);
+    }
+    (start + 1..lines.len()).find(|&idx| lines[idx].contains(close))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(content: &str, language: &str) -> Vec<(usize, usize)> {
+        doc_comments(content, language)
+            .iter()
+            .map(|c| (c.start_line, c.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_docs_project_name() {
+        assert_eq!(docs_project_name(Some("app")), "app-docs");
+        assert_eq!(docs_project_name(None), "docs");
+    }
+
+    #[test]
+    fn test_rust_line_doc_comments() {
+        let content =
+            "//! Crate docs\n\n/// Adds numbers\n/// together\nfn add() {}\n// plain comment\n";
+        assert_eq!(ranges(content, "Rust"), vec![(1, 1), (3, 4)]);
+    }
+
+    #[test]
+    fn test_javadoc_blocks() {
+        let content =
+            "/**\n * Greets.\n */\nfunction greet() {}\n/* not docs */\n/** One line */\n";
+        assert_eq!(ranges(content, "JavaScript"), vec![(1, 3), (6, 6)]);
+    }
+
+    #[test]
+    fn test_python_docstrings() {
+        let content = "\"\"\"Module docs.\"\"\"\n\ndef f():\n    \"\"\"Does f.\n\n    Really.\n    \"\"\"\n    x = 1\n    \"\"\"not a docstring\"\"\"\n";
+        assert_eq!(ranges(content, "Python"), vec![(1, 1), (4, 7)]);
+    }
+
+    #[test]
+    fn test_unknown_language_has_no_doc_comments() {
+        assert!(ranges("/// looks like docs", "TOML").is_empty());
+    }
+}
@@ -13,4 +13,8 @@ pub struct FileInfo {
     pub language: Option<String>,
     pub content: String,
     pub hash: String,
+    /// Binary file indexed by metadata only (`content` is empty)
+    pub binary: bool,
+    /// File size in bytes (binary files only)
+    pub file_size: Option<u64>,
 }
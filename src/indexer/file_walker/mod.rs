@@ -1,15 +1,69 @@
 //! File walking functionality for directory traversal
 
 use super::file_info::FileInfo;
-use super::language::detect_language;
+use super::language::{detect_language_from_content, detect_language_with_overrides};
 use super::pdf_extractor::extract_pdf_to_markdown;
+use crate::glob_utils::{ExcludeRules, compile_relative_patterns};
+use crate::types::FileIndexError;
 use anyhow::{Context, Result};
+use globset::GlobMatcher;
 use ignore::WalkBuilder;
+use rayon::prelude::*;
 use sha2::{Digest, Sha256};
-use std::fs;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Editor and tool cache directories skipped unless overridden
+pub const DEFAULT_EXCLUDED_DIRS: &[&str] = &[
+    ".idea",
+    ".vscode",
+    ".cache",
+    ".pytest_cache",
+    ".mypy_cache",
+    ".ruff_cache",
+    ".tox",
+    ".gradle",
+    ".next",
+    ".turbo",
+];
+
+/// Gitignore-syntax file listing paths to keep out of the index only
+pub const RAGIGNORE_FILE: &str = ".ragignore";
+
+/// Bytes read from the start of a file to tell text from binary
+pub const DEFAULT_TEXT_SAMPLE_BYTES: usize = 8 * 1024;
+
+/// Characters of a file's content kept for chunking; the rest is dropped
+pub const DEFAULT_MAX_CONTENT_CHARS: usize = 1_000_000;
+
+/// Files read, hashed and classified at once during a walk
+pub const DEFAULT_READ_CONCURRENCY: usize = 8;
+
+/// Files modified this shortly before a walk get no recorded mtime
+///
+/// Timestamps are coarse (a clock tick, or two seconds on FAT), so a file
+/// changed again right after being read could keep the mtime it was read
+/// with. Such recently modified files are hashed again on the next walk.
+const RACY_MTIME_WINDOW: Duration = Duration::from_secs(2);
+
+/// Files found by [`FileWalker::walk_changes`]
+#[derive(Debug, Default)]
+pub struct WalkedFiles {
+    /// Files that were read, being new or possibly modified
+    pub files: Vec<FileInfo>,
+    /// Relative paths of files skipped because their modification time is unchanged
+    pub unchanged: Vec<String>,
+    /// Modification time of every file found (nanoseconds since the Unix epoch),
+    /// by relative path, except files modified just before the walk
+    pub mtimes: HashMap<String, u64>,
+    /// Files left out because they are too large or could not be read
+    pub failed: Vec<FileIndexError>,
+}
 
 pub struct FileWalker {
     pub(crate) root: PathBuf,
@@ -17,8 +71,31 @@ pub struct FileWalker {
     pub(crate) max_file_size: usize,
     pub(crate) include_patterns: Vec<String>,
     pub(crate) exclude_patterns: Vec<String>,
+    /// `include_patterns` compiled for matching paths relative to `root`
+    include_matchers: Vec<GlobMatcher>,
+    /// `exclude_patterns` compiled, in order, for matching paths relative to `root`
+    exclude_rules: ExcludeRules,
     /// Optional cancellation flag - if set to true, walk() will exit early
     cancelled: Option<Arc<AtomicBool>>,
+    /// Keep binary files as metadata-only entries instead of skipping them
+    index_binary_metadata: bool,
+    /// Skip files and directories whose names start with a dot
+    skip_hidden: bool,
+    /// Directory names that are never descended into
+    excluded_dirs: Vec<String>,
+    /// Detect the language of files with a missing or unknown extension from their content
+    detect_language_from_content: bool,
+    /// Languages by file extension, overriding the built-in table
+    extension_languages: HashMap<String, String>,
+    /// Bytes sampled from the start of a file for binary detection
+    text_sample_bytes: usize,
+    /// Characters of content kept per file after reading or PDF extraction (0 = no limit)
+    max_content_chars: usize,
+    /// Threads reading files at once, bounding open file descriptors
+    read_concurrency: usize,
+    /// Modification times of files indexed before, by relative path; files
+    /// still carrying theirs are not read again
+    known_mtimes: HashMap<String, u64>,
 }
 
 impl FileWalker {
@@ -29,7 +106,21 @@ impl FileWalker {
             max_file_size,
             include_patterns: vec![],
             exclude_patterns: vec![],
+            include_matchers: vec![],
+            exclude_rules: ExcludeRules::default(),
             cancelled: None,
+            index_binary_metadata: false,
+            skip_hidden: false,
+            excluded_dirs: DEFAULT_EXCLUDED_DIRS
+                .iter()
+                .map(|d| d.to_string())
+                .collect(),
+            detect_language_from_content: true,
+            extension_languages: HashMap::new(),
+            text_sample_bytes: DEFAULT_TEXT_SAMPLE_BYTES,
+            max_content_chars: DEFAULT_MAX_CONTENT_CHARS,
+            read_concurrency: DEFAULT_READ_CONCURRENCY,
+            known_mtimes: HashMap::new(),
         }
     }
 
@@ -47,6 +138,71 @@ impl FileWalker {
             .is_some_and(|flag| flag.load(Ordering::Relaxed))
     }
 
+    /// Include binary files as metadata-only entries (path, extension, size)
+    pub fn with_binary_metadata(mut self, enabled: bool) -> Self {
+        self.index_binary_metadata = enabled;
+        self
+    }
+
+    /// Skip hidden files and directories (names starting with a dot)
+    pub fn with_skip_hidden(mut self, skip_hidden: bool) -> Self {
+        self.skip_hidden = skip_hidden;
+        self
+    }
+
+    /// Replace the directory names that are never walked
+    pub fn with_excluded_dirs(mut self, excluded_dirs: Vec<String>) -> Self {
+        self.excluded_dirs = excluded_dirs;
+        self
+    }
+
+    /// Fall back to content-based language detection (shebang lines, markers)
+    pub fn with_content_language_detection(mut self, enabled: bool) -> Self {
+        self.detect_language_from_content = enabled;
+        self
+    }
+
+    /// Map file extensions to language names, overriding the built-in table
+    pub fn with_extension_languages(
+        mut self,
+        extension_languages: HashMap<String, String>,
+    ) -> Self {
+        self.extension_languages = extension_languages;
+        self
+    }
+
+    /// Set how many bytes from the start of a file decide whether it is text
+    pub fn with_text_sample_size(mut self, bytes: usize) -> Self {
+        self.text_sample_bytes = bytes;
+        self
+    }
+
+    /// Truncate file content to this many characters before chunking (0 = no limit)
+    ///
+    /// Applies to text read from PDFs too, which can expand far beyond their
+    /// file size.
+    pub fn with_max_content_chars(mut self, chars: usize) -> Self {
+        self.max_content_chars = chars;
+        self
+    }
+
+    /// Read up to this many files at once (at least one)
+    ///
+    /// Reading in parallel hides the latency of network filesystems; the cap
+    /// bounds the threads and open file descriptors used for it.
+    pub fn with_read_concurrency(mut self, threads: usize) -> Self {
+        self.read_concurrency = threads.max(1);
+        self
+    }
+
+    /// Skip reading files whose modification time matches the one recorded here
+    ///
+    /// Such files are reported as unchanged by [`walk_changes`](Self::walk_changes).
+    pub fn with_known_mtimes(mut self, known_mtimes: HashMap<String, u64>) -> Self {
+        self.known_mtimes = known_mtimes;
+        self
+    }
+
     pub fn with_project(mut self, project: Option<String>) -> Self {
         self.project = project;
         self
@@ -57,6 +213,8 @@ impl FileWalker {
         include_patterns: Vec<String>,
         exclude_patterns: Vec<String>,
     ) -> Self {
+        self.include_matchers = compile_relative_patterns(&include_patterns);
+        self.exclude_rules = ExcludeRules::compile(&exclude_patterns);
         self.include_patterns = include_patterns;
         self.exclude_patterns = exclude_patterns;
         self
@@ -64,6 +222,16 @@ impl FileWalker {
 
     /// Walk the directory and collect all eligible files
     pub fn walk(&self) -> Result<Vec<FileInfo>> {
+        Ok(self.walk_changes()?.files)
+    }
+
+    /// Walk the directory, reading only the files not known to be unchanged
+    ///
+    /// A file whose modification time matches its entry in
+    /// [`with_known_mtimes`](Self::with_known_mtimes) is neither read nor
+    /// hashed; any other eligible file is read as by [`walk`](Self::walk).
+    /// Files are read in parallel but returned in walk order.
+    pub fn walk_changes(&self) -> Result<WalkedFiles> {
         // Verify root directory exists
         if !self.root.exists() {
             anyhow::bail!("Root directory does not exist: {:?}", self.root);
@@ -72,21 +240,38 @@ impl FileWalker {
             anyhow::bail!("Root path is not a directory: {:?}", self.root);
         }
 
-        let mut files = Vec::new();
+        let mut walked = WalkedFiles::default();
+        let racy_after = SystemTime::now()
+            .checked_sub(RACY_MTIME_WINDOW)
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |time| time.as_nanos() as u64);
 
+        let excluded_dirs = self.excluded_dirs.clone();
         let walker = WalkBuilder::new(&self.root)
             .standard_filters(true) // Respect .gitignore, .ignore, etc.
-            .hidden(false) // Don't skip hidden files by default
+            .hidden(self.skip_hidden) // Hidden files are walked unless skip_hidden is set
             .git_ignore(true) // Respect .gitignore files
             .git_exclude(true) // Respect .git/info/exclude
             .git_global(true) // Respect global gitignore
             .require_git(false) // Don't require a .git directory
+            .add_custom_ignore_filename(RAGIGNORE_FILE) // Respect .ragignore files
+            .filter_entry(move |entry| {
+                // Never prune the root itself, even if its name is excluded
+                entry.depth() == 0
+                    || !entry.file_type().is_some_and(|t| t.is_dir())
+                    || !excluded_dirs
+                        .iter()
+                        .any(|dir| entry.file_name() == dir.as_str())
+            })
             .build();
 
+        // Eligible files, read once the walk is done
+        let mut to_read = Vec::new();
+
         for entry in walker {
             // Check for cancellation at the start of each iteration
             if self.is_cancelled() {
-                tracing::info!("File walk cancelled after {} files", files.len());
+                tracing::info!("File walk cancelled after {} files", to_read.len());
                 anyhow::bail!("Indexing was cancelled");
             }
 
@@ -104,87 +289,231 @@ impl FileWalker {
                 continue;
             }
 
+            // Apply include/exclude patterns
+            if !self.matches_patterns(path) {
+                continue;
+            }
+
+            // Get relative path
+            let relative_path = path
+                .strip_prefix(&self.root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string();
+
             // Check file size
-            if let Ok(metadata) = fs::metadata(path)
+            let metadata = fs::metadata(path).ok();
+            if let Some(ref metadata) = metadata
                 && metadata.len() > self.max_file_size as u64
             {
                 tracing::debug!("Skipping large file: {:?}", path);
+                walked.failed.push(FileIndexError {
+                    path: relative_path,
+                    reason: format!(
+                        "file is {} bytes, over the {} byte limit",
+                        metadata.len(),
+                        self.max_file_size
+                    ),
+                });
                 continue;
             }
 
-            // Check if file is text (binary detection), but allow PDFs
-            let is_pdf = path
-                .extension()
-                .and_then(|e| e.to_str())
-                .map(|e| e.to_lowercase() == "pdf")
-                .unwrap_or(false);
-
-            if !is_pdf && !self.is_text_file(path)? {
-                tracing::debug!("Skipping binary file: {:?}", path);
-                continue;
+            // Skip reading files that have not been touched since they were indexed
+            if let Some(mtime) = metadata.as_ref().and_then(modified_nanos) {
+                if mtime < racy_after {
+                    walked.mtimes.insert(relative_path.clone(), mtime);
+                }
+                if self.known_mtimes.get(&relative_path) == Some(&mtime) {
+                    walked.unchanged.push(relative_path);
+                    continue;
+                }
             }
 
-            // Apply include/exclude patterns
-            if !self.matches_patterns(path) {
-                continue;
+            to_read.push((path.to_path_buf(), relative_path));
+        }
+
+        let outcomes = self.read_all(&to_read)?;
+        for ((_, relative_path), outcome) in to_read.into_iter().zip(outcomes) {
+            match outcome {
+                Ok(Some(file)) => walked.files.push(file),
+                Ok(None) => {}
+                Err(reason) => walked.failed.push(FileIndexError {
+                    path: relative_path,
+                    reason,
+                }),
             }
+        }
+
+        tracing::info!(
+            "Found {} files to index ({} unchanged since last indexed, {} failed)",
+            walked.files.len(),
+            walked.unchanged.len(),
+            walked.failed.len()
+        );
+        Ok(walked)
+    }
+
+    /// Read the files at these paths relative to the root, as the walk would
+    ///
+    /// Files that the walk would skip (unreadable, or binary without
+    /// metadata indexing) are left out.
+    pub fn read_files(&self, relative_paths: &[String]) -> Result<Vec<FileInfo>> {
+        let paths: Vec<_> = relative_paths
+            .iter()
+            .map(|relative_path| (self.root.join(relative_path), relative_path.clone()))
+            .collect();
+        Ok(self
+            .read_all(&paths)?
+            .into_iter()
+            .filter_map(|outcome| outcome.ok().flatten())
+            .collect())
+    }
 
-            // Read file content - extract text from PDFs or read as UTF-8
-            let content = if is_pdf {
-                match extract_pdf_to_markdown(path) {
-                    Ok(c) => c,
-                    Err(e) => {
-                        tracing::warn!("Failed to extract PDF {:?}: {}", path, e);
-                        continue;
+    /// Read files on up to `read_concurrency` threads, keeping their order
+    ///
+    /// Fails only if cancellation is requested; the outcome of each file is
+    /// that of [`read_file`](Self::read_file).
+    fn read_all(
+        &self,
+        paths: &[(PathBuf, String)],
+    ) -> Result<Vec<std::result::Result<Option<FileInfo>, String>>> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.read_concurrency)
+            .thread_name(|i| format!("file-reader-{}", i))
+            .build()
+            .context("Failed to create file reader thread pool")?;
+
+        pool.install(|| {
+            paths
+                .par_iter()
+                .map(|(path, relative_path)| {
+                    if self.is_cancelled() {
+                        anyhow::bail!("Indexing was cancelled");
                     }
+                    Ok(self.read_file(path, relative_path.clone()))
+                })
+                .collect()
+        })
+    }
+
+    /// Read an eligible file's content, hash and language
+    ///
+    /// Returns `None` for binary files skipped because they are not indexed by
+    /// metadata, and the reason as the error for files that cannot be read.
+    fn read_file(
+        &self,
+        path: &Path,
+        relative_path: String,
+    ) -> std::result::Result<Option<FileInfo>, String> {
+        // Check if file is text (binary detection), but allow PDFs
+        let is_pdf = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase() == "pdf")
+            .unwrap_or(false);
+
+        let is_binary = !is_pdf
+            && !self
+                .is_text_file(path)
+                .map_err(|e| format!("failed to read file: {:#}", e))?;
+        if is_binary && !self.index_binary_metadata {
+            tracing::debug!("Skipping binary file: {:?}", path);
+            return Ok(None);
+        }
+
+        // Read file content - extract text from PDFs or read as UTF-8.
+        // Binary files keep no content; their hash covers the raw bytes.
+        let mut file_size = None;
+        let mut binary_hash = None;
+        let mut content = if is_binary {
+            match fs::read(path) {
+                Ok(bytes) => {
+                    file_size = Some(bytes.len() as u64);
+                    binary_hash = Some(hash_bytes(&bytes));
+                    String::new()
                 }
-            } else {
-                match fs::read_to_string(path) {
-                    Ok(c) => c,
-                    Err(e) => {
-                        tracing::debug!(
-                            "Skipping file that can't be read as UTF-8: {:?}: {}",
-                            path,
-                            e
-                        );
-                        continue;
-                    }
+                Err(e) => {
+                    tracing::debug!("Skipping unreadable binary file: {:?}: {}", path, e);
+                    return Err(format!("failed to read file: {}", e));
                 }
-            };
+            }
+        } else if is_pdf {
+            match extract_pdf_to_markdown(path) {
+                Ok(c) => c,
+                Err(e) => {
+                    tracing::warn!("Failed to extract PDF {:?}: {}", path, e);
+                    return Err(format!("PDF text extraction failed: {}", e));
+                }
+            }
+        } else {
+            match fs::read_to_string(path) {
+                Ok(c) => c,
+                Err(e) => {
+                    tracing::debug!(
+                        "Skipping file that can't be read as UTF-8: {:?}: {}",
+                        path,
+                        e
+                    );
+                    return Err(format!("failed to read as UTF-8 text: {}", e));
+                }
+            }
+        };
 
-            // Calculate hash
-            let hash = self.calculate_hash(&content);
+        // Calculate hash (of the full content, so edits past the cap are noticed)
+        let hash = binary_hash.unwrap_or_else(|| self.calculate_hash(&content));
 
-            // Get relative path
-            let relative_path = path
-                .strip_prefix(&self.root)
-                .unwrap_or(path)
-                .to_string_lossy()
-                .to_string();
+        if self.max_content_chars > 0
+            && let Some((cut, _)) = content.char_indices().nth(self.max_content_chars)
+        {
+            tracing::warn!(
+                "Truncating {:?} to its first {} characters for indexing",
+                path,
+                self.max_content_chars
+            );
+            content.truncate(cut);
+        }
 
-            // Detect language
-            let extension = path.extension().and_then(|e| e.to_str()).map(String::from);
-            let language = extension.as_ref().and_then(|ext| detect_language(ext));
-
-            files.push(FileInfo {
-                path: path.to_path_buf(),
-                relative_path,
-                root_path: self.root.to_string_lossy().to_string(),
-                project: self.project.clone(),
-                extension,
-                language,
-                content,
-                hash,
+        // Detect language
+        let extension = path.extension().and_then(|e| e.to_str()).map(String::from);
+        let language = extension
+            .as_ref()
+            .and_then(|ext| detect_language_with_overrides(ext, &self.extension_languages))
+            .or_else(|| {
+                if self.detect_language_from_content && !is_binary {
+                    detect_language_from_content(&content)
+                } else {
+                    None
+                }
             });
-        }
 
-        tracing::info!("Found {} files to index", files.len());
-        Ok(files)
+        Ok(Some(FileInfo {
+            path: path.to_path_buf(),
+            relative_path,
+            root_path: self.root.to_string_lossy().to_string(),
+            project: self.project.clone(),
+            extension,
+            language,
+            content,
+            hash,
+            binary: is_binary,
+            file_size,
+        }))
     }
 
     /// Check if a file is likely text (not binary)
     pub(crate) fn is_text_file(&self, path: &Path) -> Result<bool> {
-        let content = fs::read(path).context("Failed to read file")?;
+        // Only the head of the file is sampled, so large files are not read twice
+        let mut content = Vec::with_capacity(self.text_sample_bytes);
+        File::open(path)
+            .context("Failed to open file")?
+            .take(self.text_sample_bytes as u64)
+            .read_to_end(&mut content)
+            .context("Failed to read file")?;
+
+        // Text files never contain null bytes
+        if content.contains(&0) {
+            return Ok(false);
+        }
 
         // Simple heuristic: if more than 30% of bytes are non-printable, it's binary
         let non_printable = content
@@ -196,38 +525,41 @@ impl FileWalker {
     }
 
     /// Check if file matches include/exclude patterns
+    ///
+    /// Patterns are globs matched against the path relative to the root; a
+    /// plain name such as `target` matches that file or directory anywhere in
+    /// it. Exclude patterns win over include patterns and apply in order,
+    /// with a leading `!` re-including what earlier ones excluded.
     pub(crate) fn matches_patterns(&self, path: &Path) -> bool {
-        let path_str = path.to_string_lossy();
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
 
-        // If include patterns are specified, file must match at least one
-        if !self.include_patterns.is_empty() {
-            let matches_include = self
-                .include_patterns
-                .iter()
-                .any(|pattern| path_str.contains(pattern));
-            if !matches_include {
-                return false;
-            }
-        }
-
-        // File must not match any exclude pattern
-        if self
-            .exclude_patterns
-            .iter()
-            .any(|pattern| path_str.contains(pattern))
-        {
+        // File must not be left excluded by the exclude patterns
+        if self.exclude_rules.is_excluded(relative) {
             return false;
         }
 
-        true
+        // If include patterns are specified, file must match at least one
+        self.include_patterns.is_empty()
+            || self.include_matchers.iter().any(|m| m.is_match(relative))
     }
 
     pub(crate) fn calculate_hash(&self, content: &str) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(content.as_bytes());
-        format!("{:x}", hasher.finalize())
+        hash_bytes(content.as_bytes())
     }
 }
 
+/// Modification time in nanoseconds since the Unix epoch, if the platform reports one
+fn modified_nanos(metadata: &fs::Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    u64::try_from(modified.as_nanos()).ok()
+}
+
+/// SHA256 hex digest of raw bytes
+fn hash_bytes(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
 #[cfg(test)]
 mod tests;
@@ -178,6 +178,31 @@ fn test_walk_with_include_and_exclude_patterns() {
     assert!(files[0].path.ends_with("src.rs"));
 }
 
+#[test]
+fn test_walk_with_negated_exclude_pattern() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("generated")).unwrap();
+    fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+    fs::write(temp_dir.path().join("generated/types.rs"), "struct T;").unwrap();
+    fs::write(temp_dir.path().join("generated/schema.rs"), "struct S;").unwrap();
+
+    let walker = FileWalker::new(temp_dir.path(), 1024).with_patterns(
+        vec![],
+        vec![
+            "**/generated/**".to_string(),
+            "!**/generated/schema.rs".to_string(),
+        ],
+    );
+    let mut paths: Vec<_> = walker
+        .walk()
+        .unwrap()
+        .into_iter()
+        .map(|f| f.relative_path)
+        .collect();
+    paths.sort();
+    assert_eq!(paths, vec!["generated/schema.rs", "main.rs"]);
+}
+
 #[test]
 fn test_walk_file_info_fields() {
     let temp_dir = TempDir::new().unwrap();
@@ -246,6 +271,32 @@ fn test_is_text_file_exactly_threshold() {
     assert!(!walker.is_text_file(&file_path).unwrap());
 }
 
+#[test]
+fn test_is_text_file_samples_head_of_large_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("large.log");
+    // Text head followed by a control-character tail: a full read would call
+    // this binary, the sample of the head sees only text
+    let mut content = "log line\n".repeat(1024).into_bytes();
+    content.extend(vec![0x01; 1024 * 1024]);
+    fs::write(&file_path, content).unwrap();
+
+    let walker = FileWalker::new(temp_dir.path(), 2 * 1024 * 1024).with_text_sample_size(4096);
+    assert!(walker.is_text_file(&file_path).unwrap());
+}
+
+#[test]
+fn test_is_text_file_null_byte_in_sample() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("mostly_text.bin");
+    let mut content = vec![b'A'; 1000];
+    content[10] = 0x00;
+    fs::write(&file_path, content).unwrap();
+
+    let walker = FileWalker::new(temp_dir.path(), 1024);
+    assert!(!walker.is_text_file(&file_path).unwrap());
+}
+
 #[test]
 fn test_is_text_file_nonexistent() {
     let walker = FileWalker::new("/tmp", 1024);
@@ -304,6 +355,87 @@ fn test_matches_patterns_include_and_exclude() {
     assert!(!walker.matches_patterns(Path::new("/tmp/src/main.txt")));
 }
 
+#[test]
+fn test_matches_patterns_glob_include() {
+    let walker = FileWalker::new("/tmp", 1024).with_patterns(vec!["**/*.rs".to_string()], vec![]);
+    assert!(walker.matches_patterns(Path::new("/tmp/main.rs")));
+    assert!(walker.matches_patterns(Path::new("/tmp/src/deep/lib.rs")));
+    assert!(!walker.matches_patterns(Path::new("/tmp/src/lib.py")));
+}
+
+#[test]
+fn test_matches_patterns_glob_exclude_wins() {
+    let walker = FileWalker::new("/tmp", 1024)
+        .with_patterns(vec!["**/*.rs".to_string()], vec!["**/tests/**".to_string()]);
+    assert!(walker.matches_patterns(Path::new("/tmp/src/lib.rs")));
+    assert!(!walker.matches_patterns(Path::new("/tmp/tests/integration.rs")));
+    assert!(!walker.matches_patterns(Path::new("/tmp/crates/core/tests/unit.rs")));
+}
+
+#[test]
+fn test_matches_patterns_relative_to_root() {
+    // Directories above the root don't count against the patterns
+    let walker = FileWalker::new("/work/tests/project", 1024)
+        .with_patterns(vec!["src/**".to_string()], vec!["**/tests/**".to_string()]);
+    assert!(walker.matches_patterns(Path::new("/work/tests/project/src/main.rs")));
+    assert!(!walker.matches_patterns(Path::new("/work/tests/project/docs/guide.md")));
+}
+
+#[test]
+fn test_matches_patterns_plain_name_is_path_component() {
+    let walker = FileWalker::new("/tmp", 1024).with_patterns(vec![], vec!["build".to_string()]);
+    assert!(!walker.matches_patterns(Path::new("/tmp/build/out.js")));
+    assert!(!walker.matches_patterns(Path::new("/tmp/web/build/out.js")));
+    assert!(!walker.matches_patterns(Path::new("/tmp/src/build.rs")));
+    // Unlike the former substring matching, partial names no longer match
+    assert!(walker.matches_patterns(Path::new("/tmp/src/rebuild.rs")));
+    assert!(walker.matches_patterns(Path::new("/tmp/builder/out.js")));
+}
+
+#[test]
+fn test_matches_patterns_negated_exclude_re_includes() {
+    let walker = FileWalker::new("/tmp", 1024).with_patterns(
+        vec![],
+        vec![
+            "**/generated/**".to_string(),
+            "!**/generated/schema.rs".to_string(),
+        ],
+    );
+    assert!(!walker.matches_patterns(Path::new("/tmp/src/generated/types.rs")));
+    assert!(walker.matches_patterns(Path::new("/tmp/src/generated/schema.rs")));
+    assert!(walker.matches_patterns(Path::new("/tmp/src/main.rs")));
+}
+
+#[test]
+fn test_matches_patterns_negation_before_exclude_has_no_effect() {
+    // Patterns apply in order, so a later exclude overrides an earlier `!`
+    let walker = FileWalker::new("/tmp", 1024).with_patterns(
+        vec![],
+        vec![
+            "!**/generated/schema.rs".to_string(),
+            "**/generated/**".to_string(),
+        ],
+    );
+    assert!(!walker.matches_patterns(Path::new("/tmp/src/generated/schema.rs")));
+}
+
+#[test]
+fn test_walk_with_glob_patterns() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("src/nested")).unwrap();
+    fs::create_dir_all(temp_dir.path().join("tests")).unwrap();
+    fs::write(temp_dir.path().join("src/nested/lib.rs"), "lib").unwrap();
+    fs::write(temp_dir.path().join("src/notes.txt"), "notes").unwrap();
+    fs::write(temp_dir.path().join("tests/it.rs"), "it").unwrap();
+
+    let walker = FileWalker::new(temp_dir.path(), 1024)
+        .with_patterns(vec!["**/*.rs".to_string()], vec!["**/tests/**".to_string()]);
+    let files = walker.walk().unwrap();
+
+    assert_eq!(files.len(), 1);
+    assert!(files[0].path.ends_with("src/nested/lib.rs"));
+}
+
 #[test]
 fn test_calculate_hash_consistency() {
     let walker = FileWalker::new("/tmp", 1024);
@@ -357,6 +489,188 @@ fn test_walk_skips_binary_files() {
     assert!(files[0].path.ends_with("text.txt"));
 }
 
+#[test]
+fn test_walk_includes_binary_metadata_when_enabled() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("text.txt"), "text content").unwrap();
+    fs::write(temp_dir.path().join("logo.png"), vec![0x00; 100]).unwrap();
+
+    let walker = FileWalker::new(temp_dir.path(), 1024).with_binary_metadata(true);
+    let mut files = walker.walk().unwrap();
+    files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    assert_eq!(files.len(), 2);
+    let binary = &files[0];
+    assert_eq!(binary.relative_path, "logo.png");
+    assert!(binary.binary);
+    assert!(binary.content.is_empty());
+    assert_eq!(binary.file_size, Some(100));
+    assert_eq!(binary.extension.as_deref(), Some("png"));
+    assert_ne!(binary.hash, walker.calculate_hash(""));
+    assert!(!files[1].binary);
+    assert_eq!(files[1].file_size, None);
+}
+
+#[test]
+fn test_walk_skips_hidden_when_enabled() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir(temp_dir.path().join(".idea")).unwrap();
+    fs::write(temp_dir.path().join(".idea/workspace.xml"), "<project/>").unwrap();
+    fs::write(temp_dir.path().join(".env"), "KEY=value").unwrap();
+    fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+
+    // Without the built-in exclusions, only skip_hidden keeps .idea out
+    let walker = FileWalker::new(temp_dir.path(), 1024).with_excluded_dirs(vec![]);
+    assert_eq!(walker.walk().unwrap().len(), 3);
+
+    let files = walker.with_skip_hidden(true).walk().unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].relative_path, "main.rs");
+}
+
+#[test]
+fn test_walk_excludes_noisy_dot_dirs_by_default() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir(temp_dir.path().join(".idea")).unwrap();
+    fs::write(temp_dir.path().join(".idea/workspace.xml"), "<project/>").unwrap();
+    fs::create_dir(temp_dir.path().join(".github")).unwrap();
+    fs::write(temp_dir.path().join(".github/ci.yml"), "on: push").unwrap();
+
+    let files = FileWalker::new(temp_dir.path(), 1024).walk().unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].relative_path, ".github/ci.yml");
+}
+
+#[test]
+fn test_walk_changes_reports_files_it_cannot_index() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("small.rs"), "fn main() {}").unwrap();
+    fs::write(temp_dir.path().join("large.rs"), "x".repeat(2048)).unwrap();
+    // Printable enough to pass for text, but not valid UTF-8
+    fs::write(temp_dir.path().join("latin1.txt"), b"caf\xe9 au lait").unwrap();
+
+    let walked = FileWalker::new(temp_dir.path(), 1024)
+        .walk_changes()
+        .unwrap();
+    assert_eq!(walked.files.len(), 1);
+    assert_eq!(walked.files[0].relative_path, "small.rs");
+
+    let mut failed = walked.failed;
+    failed.sort_by(|a, b| a.path.cmp(&b.path));
+    assert_eq!(failed.len(), 2);
+    assert_eq!(failed[0].path, "large.rs");
+    assert!(failed[0].reason.contains("over the 1024 byte limit"));
+    assert_eq!(failed[1].path, "latin1.txt");
+    assert!(failed[1].reason.contains("UTF-8"));
+}
+
+#[test]
+fn test_walk_truncates_content_past_max_content_chars() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("notes.txt"),
+        "héllo wörld\n".repeat(100),
+    )
+    .unwrap();
+
+    let full = FileWalker::new(temp_dir.path(), 1024 * 1024)
+        .walk()
+        .unwrap();
+    let files = FileWalker::new(temp_dir.path(), 1024 * 1024)
+        .with_max_content_chars(30)
+        .walk()
+        .unwrap();
+
+    assert_eq!(files[0].content.chars().count(), 30);
+    assert!(full[0].content.starts_with(&files[0].content));
+    // The hash still covers the whole file
+    assert_eq!(files[0].hash, full[0].hash);
+
+    let unlimited = FileWalker::new(temp_dir.path(), 1024 * 1024)
+        .with_max_content_chars(0)
+        .walk()
+        .unwrap();
+    assert_eq!(unlimited[0].content, full[0].content);
+}
+
+#[test]
+fn test_walk_applies_extension_language_overrides() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("page.tpl"), "<h1>{{title}}</h1>").unwrap();
+    fs::write(temp_dir.path().join("app.tsx"), "export default App;").unwrap();
+
+    let mut files = FileWalker::new(temp_dir.path(), 1024)
+        .with_extension_languages(HashMap::from([(
+            "tpl".to_string(),
+            "Handlebars".to_string(),
+        )]))
+        .walk()
+        .unwrap();
+    files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    assert_eq!(files[0].language, Some("TypeScript".to_string()));
+    assert_eq!(files[1].language, Some("Handlebars".to_string()));
+}
+
+#[test]
+fn test_parallel_reads_keep_walk_order() {
+    let temp_dir = TempDir::new().unwrap();
+    for i in 0..40 {
+        fs::write(
+            temp_dir.path().join(format!("file{:02}.rs", i)),
+            format!("fn f{}() {{}}", i),
+        )
+        .unwrap();
+    }
+
+    let paths = |threads: usize| -> Vec<String> {
+        FileWalker::new(temp_dir.path(), 1024)
+            .with_read_concurrency(threads)
+            .walk()
+            .unwrap()
+            .into_iter()
+            .map(|f| f.relative_path)
+            .collect()
+    };
+
+    let serial = paths(1);
+    assert_eq!(serial.len(), 40);
+    assert_eq!(paths(4), serial);
+    assert_eq!(paths(16), serial);
+}
+
+#[test]
+fn test_parallel_reads_stop_when_cancelled() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+
+    let cancelled = Arc::new(AtomicBool::new(true));
+    let walker = FileWalker::new(temp_dir.path(), 1024).with_cancellation_flag(cancelled);
+    let result = walker.read_files(&["main.rs".to_string()]);
+    assert!(result.unwrap_err().to_string().contains("cancelled"));
+}
+
+#[test]
+fn test_walk_detects_language_of_extensionless_script() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("deploy"),
+        "#!/usr/bin/env python3\nprint('deploying')\n",
+    )
+    .unwrap();
+
+    let files = FileWalker::new(temp_dir.path(), 1024).walk().unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].extension, None);
+    assert_eq!(files[0].language, Some("Python".to_string()));
+
+    let files = FileWalker::new(temp_dir.path(), 1024)
+        .with_content_language_detection(false)
+        .walk()
+        .unwrap();
+    assert_eq!(files[0].language, None);
+}
+
 #[test]
 fn test_walk_skips_invalid_utf8() {
     let temp_dir = TempDir::new().unwrap();
@@ -396,3 +710,69 @@ fn test_walk_respects_gitignore() {
     assert!(!filenames.contains(&"ignored.txt"));
     assert!(filenames.contains(&".gitignore"));
 }
+
+#[test]
+fn test_walk_respects_ragignore() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join(".gitignore"), "ignored.txt\n").unwrap();
+    fs::write(temp_dir.path().join(RAGIGNORE_FILE), "fixtures/\n").unwrap();
+
+    fs::create_dir_all(temp_dir.path().join("fixtures/nested")).unwrap();
+    fs::write(temp_dir.path().join("fixtures/sample.json"), "{}").unwrap();
+    fs::write(temp_dir.path().join("fixtures/nested/big.json"), "[]").unwrap();
+    fs::write(temp_dir.path().join("ignored.txt"), "ignore").unwrap();
+    fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+    fs::write(temp_dir.path().join("skip.log"), "log").unwrap();
+
+    let walker =
+        FileWalker::new(temp_dir.path(), 1024).with_patterns(vec![], vec!["skip.log".to_string()]);
+    let files = walker.walk().unwrap();
+
+    let paths: Vec<_> = files.iter().map(|f| f.relative_path.as_str()).collect();
+    assert!(paths.contains(&"main.rs"));
+    assert!(!paths.iter().any(|p| p.starts_with("fixtures")));
+    // Composes with .gitignore and exclude patterns
+    assert!(!paths.contains(&"ignored.txt"));
+    assert!(!paths.contains(&"skip.log"));
+}
+
+#[test]
+fn test_walk_changes_skips_files_with_known_mtime() {
+    let temp_dir = TempDir::new().unwrap();
+    let old = SystemTime::now() - Duration::from_secs(3600);
+    for name in ["kept.rs", "edited.rs", "fresh.rs"] {
+        let path = temp_dir.path().join(name);
+        fs::write(&path, "fn main() {}").unwrap();
+        if name != "fresh.rs" {
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        }
+    }
+
+    let first = FileWalker::new(temp_dir.path(), 1024)
+        .walk_changes()
+        .unwrap();
+    assert_eq!(first.files.len(), 3);
+    assert!(first.unchanged.is_empty());
+    // Just written, so its mtime could still change within the same tick
+    assert!(!first.mtimes.contains_key("fresh.rs"));
+
+    fs::write(temp_dir.path().join("edited.rs"), "fn edited() {}").unwrap();
+    let second = FileWalker::new(temp_dir.path(), 1024)
+        .with_known_mtimes(first.mtimes)
+        .walk_changes()
+        .unwrap();
+
+    assert_eq!(second.unchanged, vec!["kept.rs"]);
+    let mut read: Vec<_> = second
+        .files
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .collect();
+    read.sort();
+    assert_eq!(read, vec!["edited.rs", "fresh.rs"]);
+}
@@ -1,25 +1,30 @@
-//! Programming language detection from file extensions
+//! Programming language detection from file extensions, with a content-based
+//! fallback for extensionless scripts
+
+use std::collections::HashMap;
 
 /// Detect programming language from file extension
 pub fn detect_language(extension: &str) -> Option<String> {
     let lang = match extension.to_lowercase().as_str() {
         // Programming languages
         "rs" => "Rust",
-        "py" => "Python",
-        "js" | "mjs" | "cjs" => "JavaScript",
-        "ts" => "TypeScript",
-        "jsx" => "JavaScript (JSX)",
-        "tsx" => "TypeScript (TSX)",
+        "py" | "pyi" => "Python",
+        "js" | "mjs" | "cjs" | "jsx" => "JavaScript",
+        "ts" | "mts" | "cts" | "tsx" => "TypeScript",
         "java" => "Java",
-        "cpp" | "cc" | "cxx" => "C++",
+        "cpp" | "cc" | "cxx" | "hpp" | "hxx" | "hh" => "C++",
         "c" => "C",
-        "h" | "hpp" => "C/C++ Header",
+        "h" => "C/C++ Header",
         "go" => "Go",
         "rb" => "Ruby",
         "php" => "PHP",
         "swift" => "Swift",
         "kt" | "kts" => "Kotlin",
         "scala" => "Scala",
+        "zig" => "Zig",
+        "ex" | "exs" => "Elixir",
+        "lua" => "Lua",
+        "dart" => "Dart",
         "sh" | "bash" => "Shell",
         "sql" => "SQL",
 
@@ -53,6 +58,83 @@ pub fn detect_language(extension: &str) -> Option<String> {
     Some(lang.to_string())
 }
 
+/// Detect programming language from file extension, preferring user overrides
+///
+/// Override keys are extensions with or without the leading dot, matched
+/// case-insensitively (see `indexing.extension_languages`).
+pub fn detect_language_with_overrides(
+    extension: &str,
+    overrides: &HashMap<String, String>,
+) -> Option<String> {
+    overrides
+        .iter()
+        .find(|(ext, _)| ext.trim_start_matches('.').eq_ignore_ascii_case(extension))
+        .map(|(_, language)| language.clone())
+        .or_else(|| detect_language(extension))
+}
+
+/// Detect programming language from file content
+///
+/// Used when the extension is missing or unknown. Recognizes the interpreter
+/// of a shebang line (`#!/usr/bin/env python3`, `#!/bin/bash`, ...) and a few
+/// unambiguous opening markers (`<?php`, `<?xml`, `<!DOCTYPE html>`).
+pub fn detect_language_from_content(content: &str) -> Option<String> {
+    let first_line = content
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .next()?
+        .trim();
+
+    let lang = if let Some(shebang) = first_line.strip_prefix("#!") {
+        language_for_interpreter(shebang_interpreter(shebang)?)?
+    } else {
+        let lower = first_line.to_lowercase();
+        if lower.starts_with("<?php") {
+            "PHP"
+        } else if lower.starts_with("<?xml") {
+            "XML"
+        } else if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+            "HTML"
+        } else {
+            return None;
+        }
+    };
+
+    Some(lang.to_string())
+}
+
+/// Interpreter named by a shebang line, looking through `env` and its flags
+fn shebang_interpreter(shebang: &str) -> Option<&str> {
+    let mut words = shebang.split_whitespace();
+    let program = words.next()?.rsplit('/').next()?;
+    if program != "env" {
+        return Some(program);
+    }
+    // `#!/usr/bin/env -S node --flag` and `#!/usr/bin/env VAR=1 python`
+    words
+        .find(|word| !word.starts_with('-') && !word.contains('='))
+        .and_then(|word| word.rsplit('/').next())
+}
+
+/// Language of a script interpreter, ignoring version suffixes (`python3.11`)
+fn language_for_interpreter(interpreter: &str) -> Option<&'static str> {
+    let name = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let lang = match name {
+        "python" | "pypy" => "Python",
+        "sh" | "bash" | "zsh" | "dash" | "ksh" => "Shell",
+        "node" | "nodejs" => "JavaScript",
+        "ts-node" => "TypeScript",
+        "ruby" => "Ruby",
+        "php" => "PHP",
+        "kotlin" => "Kotlin",
+        "scala" => "Scala",
+        "lua" => "Lua",
+        "elixir" => "Elixir",
+        _ => return None,
+    };
+    Some(lang)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,12 +159,18 @@ mod tests {
     #[test]
     fn test_detect_language_typescript() {
         assert_eq!(detect_language("ts"), Some("TypeScript".to_string()));
-        assert_eq!(detect_language("tsx"), Some("TypeScript (TSX)".to_string()));
+        assert_eq!(detect_language("tsx"), Some("TypeScript".to_string()));
+        assert_eq!(detect_language("mts"), Some("TypeScript".to_string()));
     }
 
     #[test]
     fn test_detect_language_jsx() {
-        assert_eq!(detect_language("jsx"), Some("JavaScript (JSX)".to_string()));
+        assert_eq!(detect_language("jsx"), Some("JavaScript".to_string()));
+    }
+
+    #[test]
+    fn test_detect_language_python_stubs() {
+        assert_eq!(detect_language("pyi"), Some("Python".to_string()));
     }
 
     #[test]
@@ -105,7 +193,29 @@ mod tests {
     #[test]
     fn test_detect_language_headers() {
         assert_eq!(detect_language("h"), Some("C/C++ Header".to_string()));
-        assert_eq!(detect_language("hpp"), Some("C/C++ Header".to_string()));
+        assert_eq!(detect_language("hpp"), Some("C++".to_string()));
+    }
+
+    #[test]
+    fn test_detect_language_with_overrides() {
+        let overrides = HashMap::from([
+            (".tpl".to_string(), "Handlebars".to_string()),
+            ("h".to_string(), "C".to_string()),
+        ]);
+        assert_eq!(
+            detect_language_with_overrides("TPL", &overrides),
+            Some("Handlebars".to_string())
+        );
+        // Overrides win over the built-in table, which still covers the rest
+        assert_eq!(
+            detect_language_with_overrides("h", &overrides),
+            Some("C".to_string())
+        );
+        assert_eq!(
+            detect_language_with_overrides("tsx", &overrides),
+            Some("TypeScript".to_string())
+        );
+        assert_eq!(detect_language_with_overrides("xyz", &overrides), None);
     }
 
     #[test]
@@ -139,6 +249,15 @@ mod tests {
         assert_eq!(detect_language("scala"), Some("Scala".to_string()));
     }
 
+    #[test]
+    fn test_detect_language_zig_elixir_lua_dart() {
+        assert_eq!(detect_language("zig"), Some("Zig".to_string()));
+        assert_eq!(detect_language("ex"), Some("Elixir".to_string()));
+        assert_eq!(detect_language("exs"), Some("Elixir".to_string()));
+        assert_eq!(detect_language("lua"), Some("Lua".to_string()));
+        assert_eq!(detect_language("dart"), Some("Dart".to_string()));
+    }
+
     #[test]
     fn test_detect_language_shell() {
         assert_eq!(detect_language("sh"), Some("Shell".to_string()));
@@ -227,6 +346,44 @@ mod tests {
         assert_eq!(detect_language("CONF"), Some("Config".to_string()));
     }
 
+    #[test]
+    fn test_detect_language_from_shebang() {
+        assert_eq!(
+            detect_language_from_content("#!/usr/bin/env python3\nprint('hi')\n"),
+            Some("Python".to_string())
+        );
+        assert_eq!(
+            detect_language_from_content("#!/bin/bash\necho hi\n"),
+            Some("Shell".to_string())
+        );
+        assert_eq!(
+            detect_language_from_content("#!/usr/bin/env -S node --no-warnings\n"),
+            Some("JavaScript".to_string())
+        );
+        assert_eq!(
+            detect_language_from_content("#!/usr/local/bin/ruby2.7 -w\n"),
+            Some("Ruby".to_string())
+        );
+        assert_eq!(
+            detect_language_from_content("#!/usr/bin/env awk -f\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_detect_language_from_markers() {
+        assert_eq!(
+            detect_language_from_content("<?php\necho 'hi';\n"),
+            Some("PHP".to_string())
+        );
+        assert_eq!(
+            detect_language_from_content("<!DOCTYPE html>\n<html></html>\n"),
+            Some("HTML".to_string())
+        );
+        assert_eq!(detect_language_from_content("just some notes\n"), None);
+        assert_eq!(detect_language_from_content(""), None);
+    }
+
     #[test]
     fn test_detect_language_unknown() {
         assert_eq!(detect_language("unknown"), None);
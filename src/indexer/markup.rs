@@ -0,0 +1,324 @@
+//! Markup-aware section splitting for documentation files
+//!
+//! Markdown is split on headings, HTML on heading elements, and all markup
+//! (plus plain text) on paragraph or block boundaries, so each chunk covers a
+//! coherent section of prose instead of an arbitrary run of lines.
+
+/// Markup flavours with dedicated chunking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkupKind {
+    Markdown,
+    Html,
+    Text,
+}
+
+impl MarkupKind {
+    /// Markup kind for a detected language name, if it gets markup-aware chunking
+    pub fn from_language(language: &str) -> Option<Self> {
+        match language {
+            "Markdown" => Some(Self::Markdown),
+            "HTML" => Some(Self::Html),
+            "Text" => Some(Self::Text),
+            _ => None,
+        }
+    }
+}
+
+/// Line range of a section (1-based, inclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// Run of non-blank lines (0-based, end exclusive)
+struct Block {
+    start: usize,
+    end: usize,
+    /// Whether the block opens a new section (a heading)
+    starts_section: bool,
+}
+
+/// HTML elements that open a new block
+const HTML_BLOCK_TAGS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "header",
+    "footer",
+    "nav",
+    "aside",
+    "main",
+    "ul",
+    "ol",
+    "li",
+    "table",
+    "pre",
+    "blockquote",
+    "figure",
+    "form",
+    "dl",
+];
+
+/// Split `content` into sections of at most `max_lines` lines
+///
+/// Every heading starts a new section. Within a section, paragraphs are
+/// packed together up to `max_lines`; a single paragraph longer than that is
+/// cut into `max_lines`-line pieces.
+pub fn split_sections(content: &str, kind: MarkupKind, max_lines: usize) -> Vec<Section> {
+    let max_lines = max_lines.max(1);
+    let lines: Vec<&str> = content.lines().collect();
+
+    let mut sections = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+
+    for block in split_oversized(split_blocks(&lines, kind), max_lines) {
+        if let Some((start, end)) = current {
+            // A lone heading stays with the first block of its section
+            let lone_heading = end - start == 1 && is_section_start(lines[start], kind);
+            let fits = block.end - start <= max_lines || lone_heading;
+
+            if !block.starts_section && fits {
+                current = Some((start, block.end));
+                continue;
+            }
+
+            sections.push(Section {
+                start_line: start + 1,
+                end_line: end,
+            });
+        }
+        current = Some((block.start, block.end));
+    }
+
+    if let Some((start, end)) = current {
+        sections.push(Section {
+            start_line: start + 1,
+            end_line: end,
+        });
+    }
+
+    sections
+}
+
+/// Split lines into blocks separated by blank lines and block boundaries
+fn split_blocks(lines: &[&str], kind: MarkupKind) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Block> = None;
+    // Open Markdown code fence marker; headings and blank lines inside don't split
+    let mut fence: Option<&'static str> = None;
+
+    for (idx, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        let in_fence = fence.is_some();
+
+        if kind == MarkupKind::Markdown
+            && let Some(marker) = fence_marker(trimmed)
+        {
+            fence = match fence {
+                Some(open) if open == marker => None,
+                None => Some(marker),
+                open => open,
+            };
+        }
+
+        if !in_fence && trimmed.is_empty() {
+            blocks.extend(current.take());
+            continue;
+        }
+
+        let boundary = if in_fence {
+            None
+        } else {
+            block_boundary(trimmed, kind)
+        };
+
+        if boundary.is_none()
+            && let Some(block) = current.as_mut()
+        {
+            block.end = idx + 1;
+            continue;
+        }
+
+        blocks.extend(current.take());
+        current = Some(Block {
+            start: idx,
+            end: idx + 1,
+            starts_section: boundary.unwrap_or(false),
+        });
+    }
+
+    blocks.extend(current);
+    blocks
+}
+
+/// Cut blocks longer than `max_lines` into `max_lines`-line pieces
+fn split_oversized(blocks: Vec<Block>, max_lines: usize) -> Vec<Block> {
+    let mut result = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        let mut start = block.start;
+        let mut starts_section = block.starts_section;
+        while block.end - start > max_lines {
+            result.push(Block {
+                start,
+                end: start + max_lines,
+                starts_section,
+            });
+            start += max_lines;
+            starts_section = false;
+        }
+        result.push(Block {
+            start,
+            end: block.end,
+            starts_section,
+        });
+    }
+    result
+}
+
+/// Whether a trimmed line opens a new block: `Some(true)` for a heading,
+/// `Some(false)` for another block element, `None` if it continues the block
+fn block_boundary(trimmed: &str, kind: MarkupKind) -> Option<bool> {
+    match kind {
+        MarkupKind::Markdown => is_markdown_heading(trimmed).then_some(true),
+        MarkupKind::Html => {
+            if is_html_heading(trimmed) {
+                Some(true)
+            } else if HTML_BLOCK_TAGS
+                .iter()
+                .any(|tag| opens_html_tag(trimmed, tag))
+            {
+                Some(false)
+            } else {
+                None
+            }
+        }
+        MarkupKind::Text => None,
+    }
+}
+
+fn is_section_start(line: &str, kind: MarkupKind) -> bool {
+    block_boundary(line.trim(), kind) == Some(true)
+}
+
+/// ATX heading: one to six `#` followed by a space or end of line
+fn is_markdown_heading(trimmed: &str) -> bool {
+    let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&hashes) && (trimmed.len() == hashes || trimmed[hashes..].starts_with(' '))
+}
+
+fn is_html_heading(trimmed: &str) -> bool {
+    ["h1", "h2", "h3", "h4", "h5", "h6"]
+        .iter()
+        .any(|tag| opens_html_tag(trimmed, tag))
+}
+
+/// Whether the line starts with an opening `<tag>` (case-insensitive)
+fn opens_html_tag(trimmed: &str, tag: &str) -> bool {
+    let Some(rest) = trimmed.strip_prefix('<') else {
+        return false;
+    };
+    rest.len() > tag.len()
+        && rest.is_char_boundary(tag.len())
+        && rest[..tag.len()].eq_ignore_ascii_case(tag)
+        && matches!(rest.as_bytes()[tag.len()], b'>' | b' ' | b'\t' | b'/')
+}
+
+fn fence_marker(trimmed: &str) -> Option<&'static str> {
+    if trimmed.starts_with("```") {
+        Some("```")
+    } else if trimmed.starts_with("~~~") {
+        Some("~~~")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(sections: &[Section]) -> Vec<(usize, usize)> {
+        sections
+            .iter()
+            .map(|s| (s.start_line, s.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_from_language() {
+        assert_eq!(
+            MarkupKind::from_language("Markdown"),
+            Some(MarkupKind::Markdown)
+        );
+        assert_eq!(MarkupKind::from_language("HTML"), Some(MarkupKind::Html));
+        assert_eq!(MarkupKind::from_language("Text"), Some(MarkupKind::Text));
+        assert_eq!(MarkupKind::from_language("Rust"), None);
+    }
+
+    #[test]
+    fn test_markdown_splits_on_headings() {
+        let content =
+            "# Title\n\nIntro.\n\n## Install\n\nRun it.\n\nThen this.\n\n## Usage\nUse it.";
+        let sections = split_sections(content, MarkupKind::Markdown, 50);
+        assert_eq!(ranges(&sections), vec![(1, 3), (5, 9), (11, 12)]);
+    }
+
+    #[test]
+    fn test_markdown_ignores_headings_in_code_fences() {
+        let content = "# Title\n\n```sh\n# not a heading\n\necho hi\n```\n\n# Next";
+        let sections = split_sections(content, MarkupKind::Markdown, 50);
+        assert_eq!(ranges(&sections), vec![(1, 7), (9, 9)]);
+    }
+
+    #[test]
+    fn test_markdown_hashtag_is_not_heading() {
+        assert!(is_markdown_heading("# Title"));
+        assert!(is_markdown_heading("###"));
+        assert!(!is_markdown_heading("#hashtag"));
+        assert!(!is_markdown_heading("####### seven"));
+    }
+
+    #[test]
+    fn test_long_section_splits_on_paragraphs() {
+        let content = "# Title\none\ntwo\n\nthree\nfour\n\nfive";
+        let sections = split_sections(content, MarkupKind::Markdown, 3);
+        assert_eq!(ranges(&sections), vec![(1, 3), (5, 6), (8, 8)]);
+    }
+
+    #[test]
+    fn test_oversized_paragraph_is_cut() {
+        let content = "a\nb\nc\nd\ne";
+        let sections = split_sections(content, MarkupKind::Text, 2);
+        assert_eq!(ranges(&sections), vec![(1, 2), (3, 4), (5, 5)]);
+    }
+
+    #[test]
+    fn test_text_packs_paragraphs() {
+        let content = "one\n\ntwo\n\nthree";
+        let sections = split_sections(content, MarkupKind::Text, 3);
+        assert_eq!(ranges(&sections), vec![(1, 3), (5, 5)]);
+    }
+
+    #[test]
+    fn test_html_splits_on_heading_elements() {
+        let content = "<h1>Title</h1>\n<p>Intro</p>\n<h2 id=\"a\">Part</h2>\n<div>\nBody\n</div>";
+        let sections = split_sections(content, MarkupKind::Html, 50);
+        assert_eq!(ranges(&sections), vec![(1, 2), (3, 6)]);
+    }
+
+    #[test]
+    fn test_html_tag_matching() {
+        assert!(opens_html_tag("<p>text", "p"));
+        assert!(opens_html_tag("<P class=\"x\">", "p"));
+        assert!(!opens_html_tag("<pre>", "p"));
+        assert!(!opens_html_tag("</p>", "p"));
+    }
+
+    #[test]
+    fn test_empty_content() {
+        assert!(split_sections("", MarkupKind::Markdown, 50).is_empty());
+        assert!(split_sections("\n\n  \n", MarkupKind::Text, 50).is_empty());
+    }
+}
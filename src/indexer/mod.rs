@@ -5,16 +5,26 @@
 
 mod ast_parser;
 mod chunker;
+mod docs;
 mod file_info;
 mod file_walker;
 mod language;
+mod markup;
 mod pdf_extractor;
 
-pub use ast_parser::AstParser;
-pub use chunker::{ChunkStrategy, CodeChunker};
+pub(crate) use ast_parser::special_unit;
+pub use ast_parser::{AstParser, ParsedFile};
+pub(crate) use chunker::split_by_tokens;
+pub use chunker::{
+    AppliedChunking, ChunkStrategy, ChunkStrategyKind, ChunkingDiagnostic, CodeChunker,
+};
+pub use docs::{DOCS_PROJECT_SUFFIX, docs_project_name};
 pub use file_info::FileInfo;
-pub use file_walker::FileWalker;
-pub use language::detect_language;
+pub use file_walker::{
+    DEFAULT_EXCLUDED_DIRS, DEFAULT_MAX_CONTENT_CHARS, DEFAULT_READ_CONCURRENCY,
+    DEFAULT_TEXT_SAMPLE_BYTES, FileWalker, RAGIGNORE_FILE, WalkedFiles,
+};
+pub use language::{detect_language, detect_language_from_content, detect_language_with_overrides};
 pub use pdf_extractor::extract_pdf_to_markdown;
 
 use crate::types::ChunkMetadata;
@@ -21,7 +21,7 @@
 //! - **Hybrid Search**: Combines vector similarity with BM25 keyword matching (RRF)
 //! - **Dual Database Support**: LanceDB (embedded, default) or Qdrant (external server)
 //! - **Smart Indexing**: Auto-detects full vs incremental updates with persistent caching
-//! - **AST-Based Chunking**: Tree-sitter parsing for 12 programming languages
+//! - **AST-Based Chunking**: Tree-sitter parsing for 16 programming languages
 //! - **Git History Search**: Semantic search over commit history with on-demand indexing
 //! - **Dual API**: Use as a Rust library or as an MCP server for AI assistants
 //!
@@ -42,6 +42,7 @@
 //!         include_patterns: vec!["**/*.rs".to_string()],
 //!         exclude_patterns: vec!["**/target/**".to_string()],
 //!         max_file_size: 1_048_576,
+//!         model_name: None,
 //!     };
 //!     let index_response = client.index_codebase(index_req).await?;
 //!     println!("Indexed {} files", index_response.files_indexed);
@@ -50,9 +51,17 @@
 //!     let query_req = QueryRequest {
 //!         query: "authentication logic".to_string(),
 //!         project: Some("my-project".to_string()),
+//!         projects: vec![],
 //!         limit: 10,
+//!         offset: 0,
 //!         min_score: 0.7,
 //!         hybrid: true,
+//!         exclude_files: vec![],
+//!         include_signature: false,
+//!         include_summary: false,
+//!         include_relations_precision: false,
+//!         context_lines: 0,
+//!         include_git_commits: false,
 //!     };
 //!     let query_response = client.query_codebase(query_req).await?;
 //!     for result in query_response.results {
@@ -112,7 +121,9 @@
 //! - [`git`]: Git history walking and commit chunking
 //! - [`cache`]: Persistent hash cache for incremental updates
 //! - [`git_cache`]: Git commit tracking cache
+//! - [`query_cache`]: Persistent query embedding cache
 //! - [`config`]: Configuration management with environment variable support
+//! - [`telemetry`]: Optional tracing spans for pipeline phases
 //! - [`types`]: Request/response types with validation
 //! - [`error`]: Error types and result aliases
 //! - [`paths`]: Path normalization utilities
@@ -148,9 +159,15 @@ pub mod indexer;
 /// Path normalization and utility functions
 pub mod paths;
 
+/// Persistent, bounded cache of query embeddings
+pub mod query_cache;
+
 /// Code relationships: definitions, references, call graphs
 pub mod relations;
 
+/// Optional tracing spans for pipeline phases (walk, chunk, embed, store, search)
+pub mod telemetry;
+
 /// Request/response types with validation
 pub mod types;
 
@@ -163,14 +180,17 @@ pub use client::RagClient;
 
 // MCP server (wraps the client and exposes via MCP protocol)
 pub mod mcp_server;
-
 // Re-export commonly used types for convenience
 pub use types::{
-    AdvancedSearchRequest, ClearRequest, ClearResponse, FindDefinitionRequest,
-    FindDefinitionResponse, FindReferencesRequest, FindReferencesResponse, GetCallGraphRequest,
-    GetCallGraphResponse, GitSearchResult, IndexRequest, IndexResponse, IndexingMode,
-    LanguageStats, QueryRequest, QueryResponse, SearchGitHistoryRequest, SearchGitHistoryResponse,
-    SearchResult, StatisticsRequest, StatisticsResponse,
+    AdvancedSearchRequest, ClearRequest, ClearResponse, DeleteFilter, DualQueryResponse, FileMatch,
+    FindDefinitionRequest, FindDefinitionResponse, FindReferencesRequest, FindReferencesResponse,
+    FindSymbolRequest, GetCallGraphRequest, GetCallGraphResponse, GitEntryKind, GitSearchResult,
+    HealthCheckRequest, HealthReport, IndexPhase, IndexProgress, IndexRequest, IndexResponse,
+    IndexingMode, LanguageStats, ListSymbolsRequest, MatchSource, QueryFilesResponse, QueryRequest,
+    QueryResponse, QuerySummary, RelatedSearchResult, RelatedSymbol, SearchGitHistoryRequest,
+    SearchGitHistoryResponse, SearchResult, SearchWithRelationsRequest,
+    SearchWithRelationsResponse, StatisticsRequest, StatisticsResponse, SubsystemHealth,
+    SummaryBucket,
 };
 
 pub use config::Config;
@@ -1,7 +1,14 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use project_rag::client::{IndexingCancelled, cancel_on_file};
 use project_rag::mcp_server::RagMcpServer;
+use project_rag::{Config, IndexRequest, IndexResponse, RagClient};
 use std::panic;
+use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
+
+/// Exit code of a run interrupted by Ctrl-C or the cancel file (128 + SIGINT)
+const EXIT_CANCELLED: i32 = 130;
 
 /// Project-RAG: RAG-based codebase indexing and semantic search MCP server
 #[derive(Parser)]
@@ -18,6 +25,31 @@ enum Commands {
     /// Start the MCP server over stdio (default mode)
     Serve,
 
+    /// Index a codebase and exit
+    ///
+    /// Ctrl-C stops indexing at the next checkpoint and prints the progress
+    /// made so far; press it again to abort immediately.
+    Index {
+        /// Directory to index
+        path: String,
+
+        /// Project name for multi-project setups
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Only index files matching these glob patterns
+        #[arg(long = "include")]
+        include_patterns: Vec<String>,
+
+        /// Skip files matching these glob patterns
+        #[arg(long = "exclude")]
+        exclude_patterns: Vec<String>,
+
+        /// Stop indexing gracefully, like Ctrl-C, once this file is created
+        #[arg(long)]
+        cancel_file: Option<PathBuf>,
+    },
+
     /// Show version and system information
     Version,
 }
@@ -36,6 +68,25 @@ async fn main() -> Result<()> {
             show_version_info();
             return Ok(());
         }
+        Some(Commands::Index {
+            path,
+            project,
+            include_patterns,
+            exclude_patterns,
+            cancel_file,
+        }) => {
+            let config = Config::new().context("Failed to load configuration")?;
+            let request = IndexRequest {
+                path,
+                project,
+                include_patterns,
+                exclude_patterns,
+                max_file_size: config.indexing.max_file_size,
+                model_name: None,
+            };
+            let code = run_index(config, request, cancel_file).await?;
+            std::process::exit(code);
+        }
         Some(Commands::Serve) | None => {
             // Set up global panic handler
             setup_panic_handler();
@@ -52,6 +103,81 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Index a codebase, cancelling gracefully on Ctrl-C or when `cancel_file` appears
+///
+/// Returns the process exit code.
+async fn run_index(
+    config: Config,
+    request: IndexRequest,
+    cancel_file: Option<PathBuf>,
+) -> Result<i32> {
+    let client = RagClient::with_config(config).await?;
+    let cancel_token = CancellationToken::new();
+
+    let signal_token = cancel_token.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        eprintln!("\nCancelling after the current step (press Ctrl-C again to abort)...");
+        signal_token.cancel();
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("Aborted; the index is marked dirty and will be rebuilt on the next run");
+            std::process::exit(EXIT_CANCELLED);
+        }
+    });
+    if let Some(path) = cancel_file {
+        cancel_on_file(path, cancel_token.clone());
+    }
+
+    let path = request.path.clone();
+    match client
+        .index_codebase_with_cancellation(request, cancel_token)
+        .await
+    {
+        Ok(response) => {
+            print_index_summary(&path, &response);
+            Ok(0)
+        }
+        Err(e) => match e.downcast_ref::<IndexingCancelled>() {
+            Some(progress) => {
+                print_cancelled_summary(&path, progress);
+                Ok(EXIT_CANCELLED)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Print the outcome of a completed index run
+fn print_index_summary(path: &str, response: &IndexResponse) {
+    println!("Indexed {} ({:?})", path, response.mode);
+    println!("  Files indexed:   {}", response.files_indexed);
+    println!("  Files updated:   {}", response.files_updated);
+    println!("  Files removed:   {}", response.files_removed);
+    println!("  Chunks created:  {}", response.chunks_created);
+    println!("  Embeddings:      {}", response.embeddings_generated);
+    println!("  Duration:        {} ms", response.duration_ms);
+    for error in &response.errors {
+        println!("  Warning:         {}", error);
+    }
+    for failure in &response.file_errors {
+        println!("  Skipped:         {}: {}", failure.path, failure.reason);
+    }
+}
+
+/// Print the progress made before an index run was cancelled
+fn print_cancelled_summary(path: &str, progress: &IndexingCancelled) {
+    println!("Indexing of {} was cancelled", path);
+    println!("  Files indexed:   {}", progress.files_indexed);
+    println!("  Files updated:   {}", progress.files_updated);
+    println!("  Files removed:   {}", progress.files_removed);
+    println!("  Chunks created:  {}", progress.chunks_created);
+    println!("  Embeddings:      {}", progress.embeddings_generated);
+    println!("The index is marked dirty; the next run will re-index it in full.");
+}
+
 /// Display comprehensive version and system information
 fn show_version_info() {
     // Basic version info
@@ -106,7 +232,7 @@ fn show_version_info() {
     // Additional features
     println!("Features:");
     println!("  Hybrid Search:   Enabled (Vector + BM25 keyword search)");
-    println!("  AST Chunking:    12 languages supported");
+    println!("  AST Chunking:    16 languages supported");
     println!("  Git History:     Semantic search across commits");
     println!("  Incremental:     Smart indexing (auto-detects changes)");
     println!();
@@ -114,7 +240,8 @@ fn show_version_info() {
     // Supported languages
     println!("Supported Languages:");
     println!("  Programming:     Rust, Python, JavaScript, TypeScript, Go, Java,");
-    println!("                   Swift, C, C++, C#, Ruby, PHP, Kotlin, Scala");
+    println!("                   Swift, C, C++, C#, Ruby, PHP, Zig, Elixir, Lua,");
+    println!("                   Dart, Kotlin, Scala");
     println!("  Configuration:   JSON, YAML, TOML, XML");
     println!("  Markup:          HTML, CSS, SCSS, Markdown");
     println!("  Other:           Shell, SQL, Text");
@@ -97,6 +97,7 @@ impl RagMcpServer {
             max_file_size,
             peer,
             progress_token,
+            None,
             cancel_token,
         )
         .await
@@ -129,8 +130,14 @@ impl RagMcpServer {
         // Use a guard to cancel on drop
         let _cancel_guard = CancelOnDropGuard::new(cancel_token);
 
+        let client = self
+            .client
+            .for_indexing(&req.path, req.model_name.as_deref())
+            .await
+            .map_err(|e| format!("{:#}", e))?;
+
         let response = crate::client::indexing::do_index_smart(
-            &self.client,
+            &client,
             req.path,
             req.project,
             req.include_patterns,
@@ -138,6 +145,7 @@ impl RagMcpServer {
             req.max_file_size,
             Some(peer),
             progress_token,
+            None,
             cancel_token_for_index,
         )
         .await
@@ -163,6 +171,25 @@ impl RagMcpServer {
         serde_json::to_string_pretty(&response).map_err(|e| format!("Serialization failed: {}", e))
     }
 
+    #[tool(
+        description = "Search the indexed codebase like query_codebase, but return only the distinct matching files ranked by their best chunk score, without chunk content"
+    )]
+    async fn query_files(
+        &self,
+        Parameters(req): Parameters<QueryRequest>,
+    ) -> Result<String, String> {
+        // Validate request inputs
+        req.validate()?;
+
+        let response = self
+            .client
+            .query_files(req)
+            .await
+            .map_err(|e| format!("{:#}", e))?;
+
+        serde_json::to_string_pretty(&response).map_err(|e| format!("Serialization failed: {}", e))
+    }
+
     #[tool(description = "Get statistics about the indexed codebase")]
     async fn get_statistics(
         &self,
@@ -177,6 +204,22 @@ impl RagMcpServer {
         serde_json::to_string_pretty(&response).map_err(|e| format!("Serialization failed: {}", e))
     }
 
+    #[tool(
+        description = "Check that the embedding model, vector database and BM25 indexes are ready, with the latency of each"
+    )]
+    async fn health_check(
+        &self,
+        Parameters(_req): Parameters<HealthCheckRequest>,
+    ) -> Result<String, String> {
+        let response = self
+            .client
+            .health_check()
+            .await
+            .map_err(|e| format!("{:#}", e))?;
+
+        serde_json::to_string_pretty(&response).map_err(|e| format!("Serialization failed: {}", e))
+    }
+
     #[tool(description = "Clear all indexed data from the vector database")]
     async fn clear_index(
         &self,
@@ -191,7 +234,9 @@ impl RagMcpServer {
         serde_json::to_string_pretty(&response).map_err(|e| format!("Serialization failed: {}", e))
     }
 
-    #[tool(description = "Advanced search with filters for file type, language, and path patterns")]
+    #[tool(
+        description = "Advanced search with filters for file type, language, path patterns, and symbol kind"
+    )]
     async fn search_by_filters(
         &self,
         Parameters(req): Parameters<AdvancedSearchRequest>,
@@ -275,6 +320,63 @@ impl RagMcpServer {
 
         serde_json::to_string_pretty(&response).map_err(|e| format!("Serialization failed: {}", e))
     }
+
+    #[tool(
+        description = "List the functions, classes, methods and other symbols defined in a file, with their line ranges"
+    )]
+    async fn list_symbols(
+        &self,
+        Parameters(req): Parameters<ListSymbolsRequest>,
+    ) -> Result<String, String> {
+        // Validate request inputs
+        req.validate()?;
+
+        let symbols = self
+            .client
+            .list_symbols(&req.file_path, req.project)
+            .await
+            .map_err(|e| format!("{:#}", e))?;
+
+        serde_json::to_string_pretty(&symbols).map_err(|e| format!("Serialization failed: {}", e))
+    }
+
+    #[tool(
+        description = "Find where a symbol is defined by its exact name across the indexed codebases (exact match, not semantic search)"
+    )]
+    async fn find_symbol(
+        &self,
+        Parameters(req): Parameters<FindSymbolRequest>,
+    ) -> Result<String, String> {
+        // Validate request inputs
+        req.validate()?;
+
+        let symbols = self
+            .client
+            .find_symbol(&req.name, req.project)
+            .await
+            .map_err(|e| format!("{:#}", e))?;
+
+        serde_json::to_string_pretty(&symbols).map_err(|e| format!("Serialization failed: {}", e))
+    }
+
+    #[tool(
+        description = "Search the codebase and return the matching functions/methods together with their immediate callers and callees"
+    )]
+    async fn search_with_relations(
+        &self,
+        Parameters(req): Parameters<SearchWithRelationsRequest>,
+    ) -> Result<String, String> {
+        // Validate request inputs
+        req.validate()?;
+
+        let response = self
+            .client
+            .search_with_relations(req)
+            .await
+            .map_err(|e| format!("{:#}", e))?;
+
+        serde_json::to_string_pretty(&response).map_err(|e| format!("Serialization failed: {}", e))
+    }
 }
 
 // Prompts for slash commands
@@ -443,6 +545,25 @@ impl RagMcpServer {
             ),
         )])
     }
+
+    #[prompt(
+        name = "related",
+        description = "Search the codebase and show matching symbols with their callers and callees"
+    )]
+    async fn related_prompt(
+        &self,
+        Parameters(args): Parameters<serde_json::Value>,
+    ) -> Result<Vec<PromptMessage>, McpError> {
+        let query = args.get("query").and_then(|v| v.as_str()).unwrap_or("");
+
+        Ok(vec![PromptMessage::new_text(
+            PromptMessageRole::User,
+            format!(
+                "Please search the codebase for: {}. For each matching function, show what calls it and what it calls.",
+                query
+            ),
+        )])
+    }
 }
 
 #[tool_handler(router = self.tool_router)]
@@ -478,6 +599,11 @@ impl RagMcpServer {
 
         let server = Self::new().await.context("Failed to create MCP server")?;
 
+        // Pay the model's first-use cost before the first request arrives
+        if let Err(e) = server.client.warm_up().await {
+            tracing::warn!("Embedding model warm-up failed: {:#}", e);
+        }
+
         let transport = rmcp::transport::io::stdio();
 
         server.serve(transport).await?.waiting().await?;
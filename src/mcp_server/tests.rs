@@ -85,6 +85,7 @@ async fn test_do_index_empty_directory() {
         1024 * 1024,
         None,
         None,
+        None,
         CancellationToken::new(),
     )
     .await;
@@ -121,6 +122,7 @@ async fn test_do_index_with_files() {
         1024 * 1024,
         None,
         None,
+        None,
         CancellationToken::new(),
     )
     .await;
@@ -158,6 +160,7 @@ async fn test_do_index_with_exclude_patterns() {
         1024 * 1024,
         None,
         None,
+        None,
         CancellationToken::new(),
     )
     .await;
@@ -194,6 +197,7 @@ async fn test_do_incremental_update_no_cache() {
         1024 * 1024,
         None,
         None,
+        None,
         CancellationToken::new(),
     )
     .await;
@@ -226,6 +230,7 @@ async fn test_do_index_smart_new_codebase() {
         1024 * 1024,
         None,
         None,
+        None,
         CancellationToken::new(),
     )
     .await;
@@ -265,9 +270,17 @@ async fn test_tool_query_codebase_with_empty_index() {
         query: "test query".to_string(),
         path: None,
         project: None,
+        projects: vec![],
         limit: 10,
+        offset: 0,
         min_score: 0.7,
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
     // This should succeed even with empty index (just return no results)
@@ -293,9 +306,17 @@ async fn test_tool_query_codebase_validation_failure() {
         query: "   ".to_string(), // Whitespace only
         path: None,
         project: None,
+        projects: vec![],
         limit: 10,
+        offset: 0,
         min_score: 0.7,
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
     let result = req.validate();
@@ -345,6 +366,7 @@ async fn test_tool_get_statistics_with_data() {
         1024 * 1024,
         None,
         None,
+        None,
         CancellationToken::new(),
     )
     .await
@@ -360,6 +382,45 @@ async fn test_tool_get_statistics_with_data() {
     assert!(response.total_embeddings > 0);
 }
 
+#[tokio::test]
+async fn test_tool_health_check() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("db").to_string_lossy().to_string();
+    let cache_path = temp_dir.path().join("cache.json");
+    let client = RagClient::new_with_db_path(&db_path, cache_path)
+        .await
+        .unwrap();
+
+    let data_dir = temp_dir.path().join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    std::fs::write(data_dir.join("test.rs"), "fn main() {}").unwrap();
+
+    crate::client::indexing::do_index(
+        &client,
+        data_dir.to_string_lossy().to_string(),
+        None,
+        vec![],
+        vec![],
+        1024 * 1024,
+        None,
+        None,
+        None,
+        CancellationToken::new(),
+    )
+    .await
+    .unwrap();
+
+    let server = RagMcpServer::with_client(Arc::new(client)).unwrap();
+    let report = server.client().health_check().await.unwrap();
+
+    assert!(report.healthy, "{:?}", report);
+    assert!(report.embedding.healthy);
+    assert!(report.embedding.detail.contains("384 dimensions"));
+    assert!(report.vector_db.healthy);
+    assert!(report.bm25.healthy);
+    assert_eq!(report.bm25.detail, "1 keyword indexes opened");
+}
+
 #[tokio::test]
 async fn test_tool_clear_index() {
     let temp_dir = TempDir::new().unwrap();
@@ -383,6 +444,7 @@ async fn test_tool_clear_index() {
         1024 * 1024,
         None,
         None,
+        None,
         CancellationToken::new(),
     )
     .await
@@ -422,6 +484,7 @@ async fn test_tool_search_by_filters_validation_failure() {
         file_extensions: vec!["".to_string()],
         languages: vec![],
         path_patterns: vec![],
+        symbol_kinds: vec![],
     };
 
     let result = req.validate();
@@ -452,6 +515,7 @@ async fn test_tool_search_by_filters_valid_request() {
         file_extensions: vec!["rs".to_string()],
         languages: vec!["Rust".to_string()],
         path_patterns: vec!["src/**".to_string()],
+        symbol_kinds: vec![],
     };
 
     // Should succeed even with empty index
@@ -482,6 +546,8 @@ async fn test_tool_search_git_history_validation_failure() {
         since: None,
         until: None,
         file_pattern: None,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = req.validate();
@@ -511,6 +577,8 @@ async fn test_tool_search_git_history_nonexistent_path() {
         since: None,
         until: None,
         file_pattern: None,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = req.validate();
@@ -714,6 +782,30 @@ async fn test_prompt_git_search_default_path() {
     assert!(debug_str.contains("'.'"));
 }
 
+#[tokio::test]
+async fn test_prompt_related_with_query() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("db").to_string_lossy().to_string();
+    let cache_path = temp_dir.path().join("cache.json");
+    let client = RagClient::new_with_db_path(&db_path, cache_path)
+        .await
+        .unwrap();
+    let server = RagMcpServer::with_client(Arc::new(client)).unwrap();
+
+    let args = serde_json::json!({
+        "query": "token refresh"
+    });
+
+    let result = server.related_prompt(Parameters(args)).await;
+    assert!(result.is_ok());
+
+    let messages = result.unwrap();
+    assert!(!messages.is_empty());
+    let debug_str = format!("{:?}", messages[0].content);
+    assert!(debug_str.contains("token refresh"));
+    assert!(debug_str.contains("calls"));
+}
+
 // ===== ServerHandler Tests =====
 
 #[tokio::test]
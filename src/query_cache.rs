@@ -0,0 +1,385 @@
+//! Cache of query embeddings
+//!
+//! Recurring queries skip the embedding model. The cache is kept in memory, or
+//! saved to a file alongside the vector database and loaded at startup so it
+//! also survives a restart. Entries are keyed by a hash of the model name and
+//! query text, and the least recently used entries are evicted once the cache
+//! is full.
+//!
+//! Changes only mark the cache dirty. The file is written by [`PendingSave`]
+//! (which callers run off the async path), by [`QueryEmbeddingCache::flush`],
+//! or when the cache is dropped.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// File name of the cache inside the vector database directory
+pub const QUERY_EMBEDDING_CACHE_FILE: &str = "query_embeddings.json";
+
+/// A cached query embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedEmbedding {
+    /// Hash of the model name and query text
+    key: String,
+    /// Model that produced the embedding
+    model: String,
+    embedding: Vec<f32>,
+}
+
+/// Bounded LRU cache of query embeddings
+#[derive(Debug, Default)]
+pub struct QueryEmbeddingCache {
+    /// Entries by key, with the tick of their last use
+    entries: HashMap<String, (u64, CachedEmbedding)>,
+    /// Keys by tick of last use, least recently used first
+    recency: BTreeMap<u64, String>,
+    next_tick: u64,
+    /// Maximum number of entries (0 = disabled)
+    capacity: usize,
+    /// File the cache is saved to (None when disabled)
+    path: Option<PathBuf>,
+    /// Bumped on every change that should reach the file
+    generation: u64,
+    /// Last generation written to the file, locked while writing it
+    written: Arc<Mutex<u64>>,
+    /// Whether a [`PendingSave`] handed out by `begin_save` is still running
+    saving: bool,
+}
+
+/// Snapshot of the cache to write to its file
+pub struct PendingSave {
+    path: PathBuf,
+    entries: Vec<CachedEmbedding>,
+    generation: u64,
+    written: Arc<Mutex<u64>>,
+}
+
+impl PendingSave {
+    /// Atomically write the snapshot, unless a newer one was already written
+    pub fn write(&self) -> Result<()> {
+        let mut written = self
+            .written
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire query cache write lock: {}", e))?;
+        if *written >= self.generation {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("Failed to create query cache directory")?;
+        }
+        let content =
+            serde_json::to_string(&self.entries).context("Failed to serialize query cache")?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, content).context("Failed to write query cache file")?;
+        fs::rename(&tmp_path, &self.path).context("Failed to replace query cache file")?;
+        *written = self.generation;
+        Ok(())
+    }
+}
+
+impl QueryEmbeddingCache {
+    /// Load the cache from `path`, keeping at most `capacity` entries of `model`
+    ///
+    /// Entries of other models are dropped, as the configured model may have
+    /// changed since they were saved. A `capacity` of 0 disables the cache. A
+    /// missing or unreadable file starts an empty cache.
+    pub fn load(path: &Path, capacity: usize, model: &str) -> Self {
+        if capacity == 0 {
+            return Self::default();
+        }
+
+        // The file lists entries least recently used first
+        let mut entries: Vec<CachedEmbedding> = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable query cache {:?}: {}", path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        entries.retain(|entry| entry.model == model);
+        if entries.len() > capacity {
+            entries.drain(..entries.len() - capacity);
+        }
+
+        tracing::debug!("Loaded {} cached query embeddings", entries.len());
+        let mut cache = Self::in_memory(capacity);
+        cache.path = Some(path.to_path_buf());
+        for entry in entries {
+            cache.put(entry);
+        }
+        cache
+    }
+
+    /// Cache kept in memory only, holding at most `capacity` entries
+    ///
+    /// A `capacity` of 0 disables the cache.
+    pub fn in_memory(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            next_tick: 0,
+            capacity,
+            path: None,
+            generation: 0,
+            written: Arc::new(Mutex::new(0)),
+            saving: false,
+        }
+    }
+
+    /// Whether embeddings are cached at all
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Number of cached embeddings
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the cache holds no embeddings
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Cached embedding of `query` by `model`, marking it recently used
+    pub fn get(&mut self, model: &str, query: &str) -> Option<Vec<f32>> {
+        let key = Self::key(model, query);
+        let tick = self.next_tick;
+        let (last_used, entry) = self.entries.get_mut(&key)?;
+        self.recency.remove(last_used);
+        *last_used = tick;
+        self.recency.insert(tick, key);
+        self.next_tick += 1;
+        Some(entry.embedding.clone())
+    }
+
+    /// Cache the embedding of `query` by `model`, evicting the least recently
+    /// used entry once full
+    pub fn insert(&mut self, model: &str, query: &str, embedding: Vec<f32>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        self.put(CachedEmbedding {
+            key: Self::key(model, query),
+            model: model.to_string(),
+            embedding,
+        });
+        if self.entries.len() > self.capacity
+            && let Some((_, key)) = self.recency.pop_first()
+        {
+            self.entries.remove(&key);
+        }
+        self.generation += 1;
+    }
+
+    /// Drop every embedding produced by `model`, e.g. after it was reloaded
+    pub fn invalidate_model(&mut self, model: &str) {
+        let before = self.entries.len();
+        self.entries.retain(|_, (_, e)| e.model != model);
+        if self.entries.len() == before {
+            return;
+        }
+
+        let entries = &self.entries;
+        self.recency.retain(|_, key| entries.contains_key(key));
+        tracing::debug!(
+            "Invalidated {} cached query embeddings of model '{}'",
+            before - self.entries.len(),
+            model
+        );
+        self.generation += 1;
+    }
+
+    /// Snapshot to write if the cache changed and no earlier save is running
+    ///
+    /// The caller writes it outside the cache lock and then calls
+    /// [`finish_save`](Self::finish_save).
+    pub fn begin_save(&mut self) -> Option<PendingSave> {
+        if self.saving {
+            return None;
+        }
+        let save = self.snapshot()?;
+        self.saving = true;
+        Some(save)
+    }
+
+    /// Mark the running save done, returning the next one if the cache
+    /// changed meanwhile
+    pub fn finish_save(&mut self) -> Option<PendingSave> {
+        self.saving = false;
+        self.begin_save()
+    }
+
+    /// Write any unsaved changes to the file now
+    pub fn flush(&mut self) -> Result<()> {
+        match self.snapshot() {
+            Some(save) => save.write(),
+            None => Ok(()),
+        }
+    }
+
+    /// Snapshot of the current entries, if they are not written yet
+    fn snapshot(&self) -> Option<PendingSave> {
+        let path = self.path.clone()?;
+        let written = self.written.lock().map(|w| *w).unwrap_or_default();
+        if written >= self.generation {
+            return None;
+        }
+
+        let entries = self
+            .recency
+            .values()
+            .filter_map(|key| self.entries.get(key))
+            .map(|(_, entry)| entry.clone())
+            .collect();
+        Some(PendingSave {
+            path,
+            entries,
+            generation: self.generation,
+            written: self.written.clone(),
+        })
+    }
+
+    /// Store `entry` as the most recently used one
+    fn put(&mut self, entry: CachedEmbedding) {
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        let key = entry.key.clone();
+        if let Some((last_used, _)) = self.entries.insert(key.clone(), (tick, entry)) {
+            self.recency.remove(&last_used);
+        }
+        self.recency.insert(tick, key);
+    }
+
+    fn key(model: &str, query: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(model.as_bytes());
+        hasher.update([0]);
+        hasher.update(query.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+impl Drop for QueryEmbeddingCache {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::warn!("Failed to save query embedding cache: {:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_disabled_cache_stores_nothing() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(QUERY_EMBEDDING_CACHE_FILE);
+        let mut cache = QueryEmbeddingCache::load(&path, 0, "model");
+
+        cache.insert("model", "query", vec![1.0]);
+        assert!(cache.get("model", "query").is_none());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_in_memory_cache_writes_no_file() {
+        let mut cache = QueryEmbeddingCache::in_memory(2);
+
+        cache.insert("model", "query", vec![1.0]);
+        assert_eq!(cache.get("model", "query"), Some(vec![1.0]));
+
+        assert!(!QueryEmbeddingCache::in_memory(0).is_enabled());
+    }
+
+    #[test]
+    fn test_entries_are_keyed_by_model() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(QUERY_EMBEDDING_CACHE_FILE);
+        let mut cache = QueryEmbeddingCache::load(&path, 4, "model-a");
+
+        cache.insert("model-a", "query", vec![1.0]);
+        assert_eq!(cache.get("model-a", "query"), Some(vec![1.0]));
+        assert!(cache.get("model-b", "query").is_none());
+
+        cache.invalidate_model("model-a");
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(QUERY_EMBEDDING_CACHE_FILE);
+        let mut cache = QueryEmbeddingCache::load(&path, 2, "model");
+
+        cache.insert("model", "first", vec![1.0]);
+        cache.insert("model", "second", vec![2.0]);
+        cache.get("model", "first");
+        cache.insert("model", "third", vec![3.0]);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("model", "second").is_none());
+        assert!(cache.get("model", "first").is_some());
+    }
+
+    #[test]
+    fn test_reload_keeps_most_recent_within_capacity() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(QUERY_EMBEDDING_CACHE_FILE);
+        let mut cache = QueryEmbeddingCache::load(&path, 3, "model");
+        for (i, query) in ["a", "b", "c"].iter().enumerate() {
+            cache.insert("model", query, vec![i as f32]);
+        }
+        cache.flush().unwrap();
+
+        let mut reloaded = QueryEmbeddingCache::load(&path, 2, "model");
+        assert_eq!(reloaded.len(), 2);
+        assert!(reloaded.get("model", "a").is_none());
+        assert_eq!(reloaded.get("model", "c"), Some(vec![2.0]));
+    }
+
+    #[test]
+    fn test_load_drops_entries_of_other_models() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(QUERY_EMBEDDING_CACHE_FILE);
+        let mut cache = QueryEmbeddingCache::load(&path, 4, "old-model");
+        cache.insert("old-model", "query", vec![1.0]);
+        cache.insert("new-model", "query", vec![2.0]);
+        cache.flush().unwrap();
+
+        let mut reloaded = QueryEmbeddingCache::load(&path, 4, "new-model");
+        assert_eq!(reloaded.len(), 1);
+        assert!(reloaded.get("old-model", "query").is_none());
+        assert_eq!(reloaded.get("new-model", "query"), Some(vec![2.0]));
+    }
+
+    #[test]
+    fn test_saves_only_when_written() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(QUERY_EMBEDDING_CACHE_FILE);
+        let mut cache = QueryEmbeddingCache::load(&path, 2, "model");
+
+        cache.insert("model", "query", vec![1.0]);
+        assert!(!path.exists());
+
+        let save = cache.begin_save().unwrap();
+        cache.insert("model", "other", vec![2.0]);
+        assert!(cache.begin_save().is_none());
+        save.write().unwrap();
+        assert_eq!(QueryEmbeddingCache::load(&path, 2, "model").len(), 1);
+
+        // The change made while saving is picked up by the next save
+        cache.finish_save().unwrap().write().unwrap();
+        assert!(cache.finish_save().is_none());
+        assert_eq!(QueryEmbeddingCache::load(&path, 2, "model").len(), 2);
+    }
+}
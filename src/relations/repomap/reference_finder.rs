@@ -179,6 +179,8 @@ mod tests {
             language: Some("Rust".to_string()),
             content: content.to_string(),
             hash: "test_hash".to_string(),
+            binary: false,
+            file_size: None,
         }
     }
 
@@ -7,7 +7,7 @@ use anyhow::{Context, Result};
 use chrono::Utc;
 use tree_sitter::{Language, Node, Parser};
 
-use crate::indexer::FileInfo;
+use crate::indexer::{FileInfo, special_unit};
 use crate::relations::types::{Definition, SymbolId, SymbolKind, Visibility};
 
 /// Extracts symbol definitions from source code using AST parsing.
@@ -56,6 +56,22 @@ impl SymbolExtractor {
         Ok(definitions)
     }
 
+    /// Whether a file defines any public/exported symbols
+    ///
+    /// Returns `None` for files in languages without symbol extraction, whose
+    /// public surface is unknown. See [`is_exported`] for the per-language rules.
+    pub fn exports_public_symbols(&self, file_info: &FileInfo) -> Result<Option<bool>> {
+        let extension = file_info.extension.as_deref().unwrap_or("");
+        let Some((_, language_name)) = get_language_for_extension(extension) else {
+            return Ok(None);
+        };
+
+        let definitions = self.extract_definitions(file_info)?;
+        Ok(Some(definitions.iter().any(|def| {
+            is_exported(def, &language_name, &file_info.content)
+        })))
+    }
+
     /// Extract definitions from a node and its children
     fn extract_from_node(
         &self,
@@ -69,26 +85,32 @@ impl SymbolExtractor {
         let kind = node.kind();
 
         // Check if this node is a definition we care about
-        if is_definition_node(kind, language) {
-            if let Some(def) = self.node_to_definition(node, source, language, file_info, &parent_id)
-            {
-                let new_parent_id = Some(def.to_storage_id());
-                result.push(def);
-
-                // Extract nested definitions with this as parent
-                let mut cursor = node.walk();
-                for child in node.children(&mut cursor) {
-                    self.extract_from_node(
-                        child,
-                        source,
-                        language,
-                        file_info,
-                        new_parent_id.clone(),
-                        result,
-                    );
-                }
-                return;
+        let unit = if is_definition_node(kind, language) {
+            Some((kind, node))
+        } else {
+            special_unit(node, source, language)
+        };
+        if let Some((kind, last_node)) = unit
+            && let Some(def) = self.node_to_definition(
+                node, kind, last_node, source, language, file_info, &parent_id,
+            )
+        {
+            let new_parent_id = Some(def.to_storage_id());
+            result.push(def);
+
+            // Extract nested definitions with this as parent
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                self.extract_from_node(
+                    child,
+                    source,
+                    language,
+                    file_info,
+                    new_parent_id.clone(),
+                    result,
+                );
             }
+            return;
         }
 
         // Recurse into children
@@ -99,15 +121,20 @@ impl SymbolExtractor {
     }
 
     /// Convert an AST node to a Definition
+    ///
+    /// `kind` is the definition's kind, and the definition ends with
+    /// `last_node`; both usually come from `node` itself (see [`special_unit`]).
+    #[allow(clippy::too_many_arguments)]
     fn node_to_definition(
         &self,
         node: Node,
+        kind: &str,
+        last_node: Node,
         source: &str,
         language: &str,
         file_info: &FileInfo,
         parent_id: &Option<String>,
     ) -> Option<Definition> {
-        let kind = node.kind();
         let symbol_kind = SymbolKind::from_ast_kind(kind);
 
         // Extract the symbol name
@@ -115,7 +142,7 @@ impl SymbolExtractor {
 
         // Get position info
         let start_pos = node.start_position();
-        let end_pos = node.end_position();
+        let end_pos = last_node.end_position();
 
         // Extract signature (first line or declaration)
         let signature = extract_signature(node, source, language);
@@ -124,7 +151,7 @@ impl SymbolExtractor {
         let doc_comment = extract_doc_comment(node, source, language);
 
         // Determine visibility
-        let node_text = &source[node.start_byte()..node.end_byte().min(source.len())];
+        let node_text = &source[node.start_byte()..last_node.end_byte().min(source.len())];
         let visibility = Visibility::from_keywords(node_text);
 
         Some(Definition {
@@ -158,12 +185,12 @@ impl Default for SymbolExtractor {
 fn get_language_for_extension(extension: &str) -> Option<(Language, String)> {
     match extension.to_lowercase().as_str() {
         "rs" => Some((tree_sitter_rust::LANGUAGE.into(), "Rust".to_string())),
-        "py" => Some((tree_sitter_python::LANGUAGE.into(), "Python".to_string())),
+        "py" | "pyi" => Some((tree_sitter_python::LANGUAGE.into(), "Python".to_string())),
         "js" | "mjs" | "cjs" | "jsx" => Some((
             tree_sitter_javascript::LANGUAGE.into(),
             "JavaScript".to_string(),
         )),
-        "ts" | "tsx" => Some((
+        "ts" | "mts" | "cts" | "tsx" => Some((
             tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
             "TypeScript".to_string(),
         )),
@@ -177,10 +204,53 @@ fn get_language_for_extension(extension: &str) -> Option<(Language, String)> {
         "cs" => Some((tree_sitter_c_sharp::LANGUAGE.into(), "C#".to_string())),
         "rb" => Some((tree_sitter_ruby::LANGUAGE.into(), "Ruby".to_string())),
         "php" => Some((tree_sitter_php::LANGUAGE_PHP.into(), "PHP".to_string())),
+        "zig" => Some((tree_sitter_zig::LANGUAGE.into(), "Zig".to_string())),
+        "ex" | "exs" => Some((tree_sitter_elixir::LANGUAGE.into(), "Elixir".to_string())),
+        "lua" => Some((tree_sitter_lua::LANGUAGE.into(), "Lua".to_string())),
+        "dart" => Some((tree_sitter_dart::LANGUAGE.into(), "Dart".to_string())),
         _ => None,
     }
 }
 
+/// Whether a definition is part of its file's public surface
+///
+/// - Rust, Java, C#, Swift, Zig: declared `pub`/`public`/`open` (`pub(crate)` is not public)
+/// - JavaScript, TypeScript: declared with `export`
+/// - Python, Dart: name does not start with an underscore
+/// - Go: name starts with an uppercase letter
+/// - C, C++: not declared `static`
+/// - PHP, Ruby: not declared `private` or `protected`
+/// - Elixir: not defined with `defp`, `defmacrop` or `defguardp`
+/// - Lua: not declared `local`
+fn is_exported(def: &Definition, language: &str, source: &str) -> bool {
+    let name = &def.symbol_id.name;
+    match language {
+        "JavaScript" | "TypeScript" => {
+            // The definition node may start after an enclosing `export` statement
+            source
+                .lines()
+                .nth(def.symbol_id.start_line.saturating_sub(1))
+                .and_then(|line| line.get(..def.symbol_id.start_col))
+                .is_some_and(|prefix| has_keyword(prefix, &["export"]))
+                || has_keyword(&def.signature, &["export"])
+        }
+        "Python" | "Dart" => !name.starts_with('_'),
+        "Go" => name.chars().next().is_some_and(char::is_uppercase),
+        "C" | "C++" => !has_keyword(&def.signature, &["static"]),
+        "PHP" | "Ruby" => !has_keyword(&def.signature, &["private", "protected"]),
+        "Elixir" => !has_keyword(&def.signature, &["defp", "defmacrop", "defguardp"]),
+        "Lua" => !has_keyword(&def.signature, &["local"]),
+        // Only the declaration line counts: `Definition::visibility` looks at the
+        // whole body, where nested `pub` items would mark private types public
+        _ => has_keyword(&def.signature, &["pub", "public", "open"]),
+    }
+}
+
+/// Whether any whitespace-separated word of `text` is one of `keywords`
+fn has_keyword(text: &str, keywords: &[&str]) -> bool {
+    text.split_whitespace().any(|word| keywords.contains(&word))
+}
+
 /// Check if a node kind represents a definition
 fn is_definition_node(kind: &str, language: &str) -> bool {
     match language {
@@ -260,6 +330,12 @@ fn is_definition_node(kind: &str, language: &str) -> bool {
                 | "interface_declaration"
                 | "trait_declaration"
         ),
+        // Type declarations and Elixir's definitions are found by `special_unit`
+        "Zig" | "Lua" => kind == "function_declaration",
+        "Dart" => matches!(
+            kind,
+            "class_definition" | "mixin_declaration" | "extension_declaration" | "enum_declaration"
+        ),
         _ => false,
     }
 }
@@ -379,6 +455,39 @@ fn find_name_node<'a>(node: Node<'a>, language: &str) -> Option<Node<'a>> {
                 return Some(name_node);
             }
         }
+        "Zig" | "Dart" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                return Some(name_node);
+            }
+            // Dart: the name is in the function, getter, ... signature inside
+            if kind == "method_signature" {
+                return find_name_node(node.named_child(0)?, language);
+            }
+        }
+        "Lua" => {
+            // `function M.helper()` and `function M:method()` are named by the last part
+            let name_node = node.child_by_field_name("name")?;
+            return match name_node.kind() {
+                "dot_index_expression" => name_node.child_by_field_name("field"),
+                "method_index_expression" => name_node.child_by_field_name("method"),
+                _ => Some(name_node),
+            };
+        }
+        "Elixir" => {
+            // `defmodule Name`, `def name(args)` or `def name(args) when guard`
+            let mut cursor = node.walk();
+            let arguments = node
+                .named_children(&mut cursor)
+                .find(|child| child.kind() == "arguments")?;
+            let mut head = arguments.named_child(0)?;
+            if head.kind() == "binary_operator" {
+                head = head.child_by_field_name("left")?;
+            }
+            return match head.kind() {
+                "call" => head.child_by_field_name("target"),
+                _ => Some(head),
+            };
+        }
         _ => {}
     }
 
@@ -488,7 +597,9 @@ fn clean_comment(comment: &str, _language: &str) -> String {
         .map(|line| {
             let mut s = line.trim();
             // Remove common prefixes
-            for prefix in ["///", "//!", "//", "/*", "*/", "*", "#", "\"\"\"", "'''"] {
+            for prefix in [
+                "///", "//!", "//", "/*", "*/", "*", "#", "\"\"\"", "'''", "--",
+            ] {
                 s = s.trim_start_matches(prefix);
             }
             s.trim().to_string()
@@ -514,6 +625,8 @@ mod tests {
             language: None,
             content: content.to_string(),
             hash: "test_hash".to_string(),
+            binary: false,
+            file_size: None,
         }
     }
 
@@ -605,6 +718,69 @@ class Calculator {
         assert!(add.is_some(), "Should find add function");
     }
 
+    #[test]
+    fn test_zig_elixir_lua_dart_extraction() {
+        let extractor = SymbolExtractor::new();
+        let symbols = |source: &str, extension: &str| -> Vec<(String, SymbolKind)> {
+            extractor
+                .extract_definitions(&make_file_info(source, extension))
+                .unwrap()
+                .iter()
+                .map(|d| (d.name().to_string(), d.kind()))
+                .collect()
+        };
+
+        let zig = symbols(
+            "const Point = struct {\n    pub fn init() Point {\n        return .{};\n    }\n};\n",
+            "zig",
+        );
+        assert_eq!(
+            zig,
+            [
+                ("Point".to_string(), SymbolKind::Struct),
+                ("init".to_string(), SymbolKind::Function)
+            ]
+        );
+
+        let elixir = symbols(
+            "defmodule Greeter do\n  def hello(name) when is_binary(name), do: name\n  defp shout(text), do: text\nend\n",
+            "ex",
+        );
+        assert_eq!(
+            elixir,
+            [
+                ("Greeter".to_string(), SymbolKind::Module),
+                ("hello".to_string(), SymbolKind::Function),
+                ("shout".to_string(), SymbolKind::Function)
+            ]
+        );
+
+        let lua = symbols(
+            "local function helper() end\nfunction M.double(x) return x end\n",
+            "lua",
+        );
+        assert_eq!(
+            lua,
+            [
+                ("helper".to_string(), SymbolKind::Function),
+                ("double".to_string(), SymbolKind::Function)
+            ]
+        );
+
+        let dart = symbols(
+            "class Counter {\n  void increment() {\n  }\n}\nint add(int a, int b) {\n  return a + b;\n}\n",
+            "dart",
+        );
+        assert_eq!(
+            dart,
+            [
+                ("Counter".to_string(), SymbolKind::Class),
+                ("increment".to_string(), SymbolKind::Method),
+                ("add".to_string(), SymbolKind::Function)
+            ]
+        );
+    }
+
     #[test]
     fn test_unsupported_extension() {
         let source = "some content";
@@ -615,6 +791,42 @@ class Calculator {
         assert!(definitions.is_empty());
     }
 
+    #[test]
+    fn test_exports_public_symbols_per_language() {
+        let extractor = SymbolExtractor::new();
+        let exports = |source: &str, extension: &str| {
+            extractor
+                .exports_public_symbols(&make_file_info(source, extension))
+                .unwrap()
+        };
+
+        assert_eq!(exports("fn helper() {}", "rs"), Some(false));
+        assert_eq!(exports("pub(crate) fn helper() {}", "rs"), Some(false));
+        assert_eq!(exports("pub fn api() {}", "rs"), Some(true));
+        assert_eq!(exports("function helper() {}", "js"), Some(false));
+        assert_eq!(exports("export function api() {}", "ts"), Some(true));
+        assert_eq!(exports("def _helper():\n    pass\n", "py"), Some(false));
+        assert_eq!(exports("def api():\n    pass\n", "py"), Some(true));
+        assert_eq!(
+            exports("package main\nfunc helper() {}\n", "go"),
+            Some(false)
+        );
+        assert_eq!(exports("package main\nfunc Api() {}\n", "go"), Some(true));
+        assert_eq!(
+            exports("static int helper(void) { return 0; }", "c"),
+            Some(false)
+        );
+        assert_eq!(exports("int api(void) { return 0; }", "c"), Some(true));
+        assert_eq!(
+            exports("defmodule M do\n  defp helper, do: 1\nend\n", "ex"),
+            Some(true)
+        );
+        assert_eq!(exports("local function helper() end\n", "lua"), Some(false));
+        assert_eq!(exports("void _helper() {}\n", "dart"), Some(false));
+        assert_eq!(exports("pub fn api() void {}\n", "zig"), Some(true));
+        assert_eq!(exports("# Notes", "md"), None);
+    }
+
     #[test]
     fn test_definition_storage_id() {
         let source = "fn foo() {}";
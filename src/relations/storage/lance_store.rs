@@ -1,22 +1,37 @@
 //! LanceDB-based storage for code relationships.
 
 use anyhow::{Context, Result};
+use arrow_array::{Array, RecordBatch, RecordBatchIterator, StringArray, UInt32Array};
+use arrow_schema::{DataType, Field, Schema};
 use async_trait::async_trait;
+use futures::stream::TryStreamExt;
+use lancedb::Table;
+use lancedb::query::{ExecutableQuery, QueryBase};
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeSet, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
 use super::{RelationsStats, RelationsStore};
-use crate::relations::types::{CallEdge, Definition, Reference};
+use crate::relations::types::{CallEdge, Definition, Reference, ReferenceKind};
+
+/// Table holding one row per definition
+const DEFINITIONS_TABLE: &str = "definitions";
+/// Table holding one row per reference
+const REFERENCES_TABLE: &str = "references";
 
 /// LanceDB-based relations store.
 ///
 /// Stores definitions and references in separate LanceDB tables for efficient querying.
+/// The columns used in filters are stored alongside the full record, which is kept as JSON.
 pub struct LanceRelationsStore {
     /// Path to the database directory
     db_path: PathBuf,
     /// Database connection (lazy initialized)
     db: Arc<RwLock<Option<lancedb::Connection>>>,
+    /// Serializes table creation so concurrent writers don't race on it
+    create_lock: Mutex<()>,
 }
 
 impl LanceRelationsStore {
@@ -30,6 +45,7 @@ impl LanceRelationsStore {
         Ok(Self {
             db_path,
             db: Arc::new(RwLock::new(None)),
+            create_lock: Mutex::new(()),
         })
     }
 
@@ -50,20 +66,176 @@ impl LanceRelationsStore {
         Ok(db)
     }
 
-    /// Ensure definitions table exists
-    async fn ensure_definitions_table(&self) -> Result<()> {
-        let _db = self.get_connection().await?;
-        // Table will be created on first insert
-        // LanceDB creates tables lazily
-        Ok(())
+    fn definitions_schema() -> Arc<Schema> {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("file_path", DataType::Utf8, false),
+            Field::new("root_path", DataType::Utf8, true),
+            Field::new("start_line", DataType::UInt32, false),
+            Field::new("end_line", DataType::UInt32, false),
+            Field::new("data", DataType::Utf8, false),
+        ]))
+    }
+
+    fn references_schema() -> Arc<Schema> {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("target_symbol_id", DataType::Utf8, false),
+            Field::new("file_path", DataType::Utf8, false),
+            Field::new("root_path", DataType::Utf8, true),
+            Field::new("start_line", DataType::UInt32, false),
+            Field::new("data", DataType::Utf8, false),
+        ]))
+    }
+
+    /// Open a table, or `None` if nothing has been stored in it yet
+    async fn existing_table(&self, name: &str) -> Result<Option<Table>> {
+        let db = self.get_connection().await?;
+        let table_names = db
+            .table_names()
+            .execute()
+            .await
+            .context("Failed to list relations tables")?;
+        if !table_names.iter().any(|t| t == name) {
+            return Ok(None);
+        }
+
+        let table = db
+            .open_table(name)
+            .execute()
+            .await
+            .with_context(|| format!("Failed to open relations table '{}'", name))?;
+        Ok(Some(table))
+    }
+
+    /// Open a table, creating it empty if it doesn't exist yet
+    async fn ensure_table(&self, name: &str, schema: Arc<Schema>) -> Result<Table> {
+        let _guard = self.create_lock.lock().await;
+        if let Some(table) = self.existing_table(name).await? {
+            return Ok(table);
+        }
+
+        let empty_batch = RecordBatch::new_empty(schema.clone());
+        let batches = RecordBatchIterator::new(vec![empty_batch].into_iter().map(Ok), schema);
+        self.get_connection()
+            .await?
+            .create_table(name, Box::new(batches))
+            .execute()
+            .await
+            .with_context(|| format!("Failed to create relations table '{}'", name))
     }
 
-    /// Ensure references table exists
-    async fn ensure_references_table(&self) -> Result<()> {
-        let _db = self.get_connection().await?;
-        // Table will be created on first insert
+    /// Append a batch to a table, creating the table on first use
+    async fn append(&self, name: &str, batch: RecordBatch) -> Result<()> {
+        let schema = batch.schema();
+        let table = self.ensure_table(name, schema.clone()).await?;
+        let batches = RecordBatchIterator::new(vec![batch].into_iter().map(Ok), schema);
+        table
+            .add(Box::new(batches))
+            .execute()
+            .await
+            .with_context(|| format!("Failed to add rows to relations table '{}'", name))?;
         Ok(())
     }
+
+    /// Decode the JSON records of every row matching `filter`
+    async fn query_records<T: DeserializeOwned>(
+        &self,
+        name: &str,
+        filter: String,
+    ) -> Result<Vec<T>> {
+        let Some(table) = self.existing_table(name).await? else {
+            return Ok(Vec::new());
+        };
+
+        let batches: Vec<RecordBatch> = table
+            .query()
+            .only_if(filter)
+            .select(lancedb::query::Select::Columns(vec!["data".to_string()]))
+            .execute()
+            .await
+            .context("Failed to query relations")?
+            .try_collect()
+            .await
+            .context("Failed to read relations")?;
+
+        let mut records = Vec::new();
+        for batch in batches {
+            let data = batch
+                .column_by_name("data")
+                .context("Missing data column")?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .context("Invalid data type")?;
+            for idx in 0..batch.num_rows() {
+                records.push(
+                    serde_json::from_str(data.value(idx))
+                        .context("Failed to decode stored relation")?,
+                );
+            }
+        }
+        Ok(records)
+    }
+
+    /// Delete the rows matching `filter` from a table, returning how many there were
+    async fn delete_rows(&self, name: &str, filter: &str) -> Result<usize> {
+        let Some(table) = self.existing_table(name).await? else {
+            return Ok(0);
+        };
+
+        // LanceDB doesn't return the number of deleted rows, so count them first
+        let count = table
+            .count_rows(Some(filter.to_string()))
+            .await
+            .context("Failed to count relations to delete")?;
+        if count > 0 {
+            table
+                .delete(filter)
+                .await
+                .context("Failed to delete relations")?;
+        }
+        Ok(count)
+    }
+
+    /// The innermost definition in a file that spans `line`
+    async fn definition_containing(
+        &self,
+        file_path: &str,
+        root_path: Option<&str>,
+        line: usize,
+    ) -> Result<Option<Definition>> {
+        let filter = format!(
+            "file_path = '{}' AND start_line <= {} AND end_line >= {}",
+            escape(file_path),
+            line,
+            line
+        );
+        let definitions: Vec<Definition> = self.query_records(DEFINITIONS_TABLE, filter).await?;
+        Ok(definitions
+            .into_iter()
+            .filter(|def| def.root_path.as_deref() == root_path)
+            .min_by_key(|def| def.end_line - def.symbol_id.start_line))
+    }
+}
+
+/// Escape a value for use inside a single-quoted LanceDB filter literal
+fn escape(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
+fn line_column(lines: impl Iterator<Item = usize>) -> UInt32Array {
+    UInt32Array::from(lines.map(|l| l as u32).collect::<Vec<_>>())
+}
+
+fn call_edge(caller_id: String, callee_id: String, reference: &Reference) -> CallEdge {
+    CallEdge {
+        caller_id,
+        callee_id,
+        call_site_file: reference.file_path.clone(),
+        call_site_line: reference.start_line,
+        call_site_col: reference.start_col,
+    }
 }
 
 #[async_trait]
@@ -77,25 +249,98 @@ impl RelationsStore for LanceRelationsStore {
             return Ok(0);
         }
 
-        self.ensure_definitions_table().await?;
-
-        // TODO: Implement actual LanceDB storage
-        // For now, just return the count
-        let count = definitions.len();
+        let data = definitions
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to encode definitions")?;
+        let batch = RecordBatch::try_new(
+            Self::definitions_schema(),
+            vec![
+                Arc::new(StringArray::from(
+                    definitions
+                        .iter()
+                        .map(|d| d.to_storage_id())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from(
+                    definitions.iter().map(|d| d.name()).collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from(
+                    definitions
+                        .iter()
+                        .map(|d| d.file_path())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from(
+                    definitions
+                        .iter()
+                        .map(|d| d.root_path.as_deref())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(line_column(definitions.iter().map(|d| d.start_line()))),
+                Arc::new(line_column(definitions.iter().map(|d| d.end_line))),
+                Arc::new(StringArray::from(data)),
+            ],
+        )
+        .context("Failed to build definitions batch")?;
+
+        let count = batch.num_rows();
+        self.append(DEFINITIONS_TABLE, batch).await?;
 
         tracing::debug!("Stored {} definitions", count);
         Ok(count)
     }
 
-    async fn store_references(&self, references: Vec<Reference>, _root_path: &str) -> Result<usize> {
+    async fn store_references(
+        &self,
+        references: Vec<Reference>,
+        _root_path: &str,
+    ) -> Result<usize> {
         if references.is_empty() {
             return Ok(0);
         }
 
-        self.ensure_references_table().await?;
-
-        // TODO: Implement actual LanceDB storage
-        let count = references.len();
+        let data = references
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to encode references")?;
+        let batch = RecordBatch::try_new(
+            Self::references_schema(),
+            vec![
+                Arc::new(StringArray::from(
+                    references
+                        .iter()
+                        .map(|r| r.to_storage_id())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from(
+                    references
+                        .iter()
+                        .map(|r| r.target_symbol_id.as_str())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from(
+                    references
+                        .iter()
+                        .map(|r| r.file_path.as_str())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from(
+                    references
+                        .iter()
+                        .map(|r| r.root_path.as_deref())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(line_column(references.iter().map(|r| r.start_line))),
+                Arc::new(StringArray::from(data)),
+            ],
+        )
+        .context("Failed to build references batch")?;
+
+        let count = batch.num_rows();
+        self.append(REFERENCES_TABLE, batch).await?;
 
         tracing::debug!("Stored {} references", count);
         Ok(count)
@@ -103,55 +348,248 @@ impl RelationsStore for LanceRelationsStore {
 
     async fn find_definition_at(
         &self,
-        _file_path: &str,
-        _line: usize,
-        _column: usize,
+        file_path: &str,
+        line: usize,
+        column: usize,
     ) -> Result<Option<Definition>> {
-        // TODO: Implement query
-        Ok(None)
+        let filter = format!(
+            "file_path = '{}' AND start_line <= {} AND end_line >= {}",
+            escape(file_path),
+            line,
+            line
+        );
+        let definitions: Vec<Definition> = self.query_records(DEFINITIONS_TABLE, filter).await?;
+
+        // Prefer the innermost definition, e.g. a method over its class
+        Ok(definitions
+            .into_iter()
+            .filter(|def| column == 0 || column >= def.symbol_id.start_col)
+            .min_by_key(|def| def.end_line - def.symbol_id.start_line))
+    }
+
+    async fn find_definitions_by_name(&self, name: &str) -> Result<Vec<Definition>> {
+        let filter = format!("name = '{}'", escape(name));
+        self.query_records(DEFINITIONS_TABLE, filter).await
+    }
+
+    async fn find_references(&self, target_symbol_id: &str) -> Result<Vec<Reference>> {
+        let filter = format!("target_symbol_id = '{}'", escape(target_symbol_id));
+        let mut references: Vec<Reference> = self.query_records(REFERENCES_TABLE, filter).await?;
+        references.sort_by(|a, b| {
+            (&a.root_path, &a.file_path, a.start_line, a.start_col).cmp(&(
+                &b.root_path,
+                &b.file_path,
+                b.start_line,
+                b.start_col,
+            ))
+        });
+        Ok(references)
+    }
+
+    async fn get_callers(&self, symbol_id: &str) -> Result<Vec<CallEdge>> {
+        let mut edges = Vec::new();
+        for reference in self.find_references(symbol_id).await? {
+            if reference.reference_kind != ReferenceKind::Call {
+                continue;
+            }
+            let caller = self
+                .definition_containing(
+                    &reference.file_path,
+                    reference.root_path.as_deref(),
+                    reference.start_line,
+                )
+                .await?;
+            if let Some(caller) = caller {
+                edges.push(call_edge(
+                    caller.to_storage_id(),
+                    symbol_id.to_string(),
+                    &reference,
+                ));
+            }
+        }
+        Ok(edges)
     }
 
-    async fn find_definitions_by_name(&self, _name: &str) -> Result<Vec<Definition>> {
-        // TODO: Implement query
-        Ok(Vec::new())
+    async fn get_callees(&self, symbol_id: &str) -> Result<Vec<CallEdge>> {
+        let filter = format!("id = '{}'", escape(symbol_id));
+        let definitions: Vec<Definition> = self.query_records(DEFINITIONS_TABLE, filter).await?;
+
+        let mut edges = Vec::new();
+        for def in definitions {
+            let filter = format!(
+                "file_path = '{}' AND start_line >= {} AND start_line <= {}",
+                escape(def.file_path()),
+                def.start_line(),
+                def.end_line
+            );
+            let references: Vec<Reference> = self.query_records(REFERENCES_TABLE, filter).await?;
+            edges.extend(
+                references
+                    .iter()
+                    .filter(|r| {
+                        r.reference_kind == ReferenceKind::Call
+                            && r.root_path == def.root_path
+                            && r.target_symbol_id != symbol_id
+                    })
+                    .map(|r| call_edge(symbol_id.to_string(), r.target_symbol_id.clone(), r)),
+            );
+        }
+        Ok(edges)
     }
 
-    async fn find_references(&self, _target_symbol_id: &str) -> Result<Vec<Reference>> {
-        // TODO: Implement query
-        Ok(Vec::new())
+    async fn find_definitions_by_root(&self, root_path: &str) -> Result<Vec<Definition>> {
+        let filter = format!("root_path = '{}'", escape(root_path));
+        self.query_records(DEFINITIONS_TABLE, filter).await
     }
 
-    async fn get_callers(&self, _symbol_id: &str) -> Result<Vec<CallEdge>> {
-        // TODO: Implement call graph query
-        Ok(Vec::new())
+    async fn find_referencing_files(
+        &self,
+        file_path: &str,
+        root_path: &str,
+    ) -> Result<Vec<String>> {
+        // LIKE may over-match on wildcard characters in the path, so the
+        // prefix is checked exactly below
+        let prefix = format!("def:{}:", file_path);
+        let filter = format!(
+            "root_path = '{}' AND target_symbol_id LIKE '{}%'",
+            escape(root_path),
+            escape(&prefix)
+        );
+        let references: Vec<Reference> = self.query_records(REFERENCES_TABLE, filter).await?;
+
+        let files: BTreeSet<String> = references
+            .into_iter()
+            .filter(|r| r.target_symbol_id.starts_with(&prefix) && r.file_path != file_path)
+            .map(|r| r.file_path)
+            .collect();
+        Ok(files.into_iter().collect())
     }
 
-    async fn get_callees(&self, _symbol_id: &str) -> Result<Vec<CallEdge>> {
-        // TODO: Implement call graph query
-        Ok(Vec::new())
+    async fn delete_by_file(&self, file_path: &str, root_path: &str) -> Result<usize> {
+        let filter = format!(
+            "file_path = '{}' AND root_path = '{}'",
+            escape(file_path),
+            escape(root_path)
+        );
+        let deleted = self.delete_rows(DEFINITIONS_TABLE, &filter).await?
+            + self.delete_rows(REFERENCES_TABLE, &filter).await?;
+
+        tracing::debug!("Deleted {} relations for file: {}", deleted, file_path);
+        Ok(deleted)
     }
 
-    async fn delete_by_file(&self, _file_path: &str) -> Result<usize> {
-        // TODO: Implement deletion
-        Ok(0)
+    async fn delete_by_root(&self, root_path: &str) -> Result<usize> {
+        let filter = format!("root_path = '{}'", escape(root_path));
+        let deleted = self.delete_rows(DEFINITIONS_TABLE, &filter).await?
+            + self.delete_rows(REFERENCES_TABLE, &filter).await?;
+
+        tracing::debug!("Deleted {} relations under root: {}", deleted, root_path);
+        Ok(deleted)
     }
 
     async fn clear(&self) -> Result<()> {
-        // TODO: Drop and recreate tables
+        let db = self.get_connection().await?;
+        let _guard = self.create_lock.lock().await;
+        for name in [DEFINITIONS_TABLE, REFERENCES_TABLE] {
+            if self.existing_table(name).await?.is_some() {
+                db.drop_table(name, &[])
+                    .await
+                    .with_context(|| format!("Failed to drop relations table '{}'", name))?;
+            }
+        }
         Ok(())
     }
 
     async fn get_stats(&self) -> Result<RelationsStats> {
-        // TODO: Query actual counts
-        Ok(RelationsStats::default())
+        let mut stats = RelationsStats::default();
+
+        if let Some(table) = self.existing_table(DEFINITIONS_TABLE).await? {
+            stats.definition_count = table
+                .count_rows(None)
+                .await
+                .context("Failed to count definitions")?;
+
+            let batches: Vec<RecordBatch> = table
+                .query()
+                .select(lancedb::query::Select::Columns(vec![
+                    "file_path".to_string(),
+                    "root_path".to_string(),
+                ]))
+                .execute()
+                .await
+                .context("Failed to query definition files")?
+                .try_collect()
+                .await
+                .context("Failed to read definition files")?;
+
+            let mut files = HashSet::new();
+            for batch in batches {
+                let string_column = |name: &str| -> Result<&StringArray> {
+                    batch
+                        .column_by_name(name)
+                        .with_context(|| format!("Missing {} column", name))?
+                        .as_any()
+                        .downcast_ref::<StringArray>()
+                        .with_context(|| format!("Invalid {} type", name))
+                };
+                let file_paths = string_column("file_path")?;
+                let root_paths = string_column("root_path")?;
+                for idx in 0..batch.num_rows() {
+                    let root_path =
+                        (!root_paths.is_null(idx)).then(|| root_paths.value(idx).to_string());
+                    files.insert((root_path, file_paths.value(idx).to_string()));
+                }
+            }
+            stats.files_with_definitions = files.len();
+        }
+
+        if let Some(table) = self.existing_table(REFERENCES_TABLE).await? {
+            stats.reference_count = table
+                .count_rows(None)
+                .await
+                .context("Failed to count references")?;
+        }
+
+        Ok(stats)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::relations::types::{SymbolId, SymbolKind, Visibility};
     use tempfile::TempDir;
 
+    fn definition(file: &str, name: &str, start_line: usize, end_line: usize) -> Definition {
+        Definition {
+            symbol_id: SymbolId::new(file, name, SymbolKind::Function, start_line, 0),
+            root_path: Some("/project".to_string()),
+            project: None,
+            end_line,
+            end_col: 1,
+            signature: format!("fn {}()", name),
+            doc_comment: None,
+            visibility: Visibility::Public,
+            parent_id: None,
+            indexed_at: 0,
+        }
+    }
+
+    fn call(file: &str, line: usize, target: &Definition) -> Reference {
+        Reference {
+            file_path: file.to_string(),
+            root_path: Some("/project".to_string()),
+            project: None,
+            start_line: line,
+            end_line: line,
+            start_col: 4,
+            end_col: 10,
+            target_symbol_id: target.to_storage_id(),
+            reference_kind: ReferenceKind::Call,
+            indexed_at: 0,
+        }
+    }
+
     #[tokio::test]
     async fn test_store_creation() {
         let temp_dir = TempDir::new().unwrap();
@@ -173,4 +611,124 @@ mod tests {
         let count = store.store_definitions(Vec::new(), "/test").await.unwrap();
         assert_eq!(count, 0);
     }
+
+    #[tokio::test]
+    async fn test_store_and_query_relations() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LanceRelationsStore::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        let helper = definition("src/util.rs", "helper", 1, 3);
+        let main = definition("src/main.rs", "main", 1, 5);
+        store
+            .store_definitions(vec![helper.clone(), main.clone()], "/project")
+            .await
+            .unwrap();
+        store
+            .store_references(vec![call("src/main.rs", 2, &helper)], "/project")
+            .await
+            .unwrap();
+
+        let found = store.find_definitions_by_name("helper").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].file_path(), "src/util.rs");
+
+        let at = store.find_definition_at("src/main.rs", 3, 0).await.unwrap();
+        assert_eq!(at.map(|d| d.symbol_id.name), Some("main".to_string()));
+
+        let references = store
+            .find_references(&helper.to_storage_id())
+            .await
+            .unwrap();
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].file_path, "src/main.rs");
+
+        let callers = store.get_callers(&helper.to_storage_id()).await.unwrap();
+        assert_eq!(callers.len(), 1);
+        assert_eq!(callers[0].caller_id, main.to_storage_id());
+
+        let callees = store.get_callees(&main.to_storage_id()).await.unwrap();
+        assert_eq!(callees.len(), 1);
+        assert_eq!(callees[0].callee_id, helper.to_storage_id());
+
+        let referencing = store
+            .find_referencing_files("src/util.rs", "/project")
+            .await
+            .unwrap();
+        assert_eq!(referencing, vec!["src/main.rs".to_string()]);
+
+        let stats = store.get_stats().await.unwrap();
+        assert_eq!(stats.definition_count, 2);
+        assert_eq!(stats.reference_count, 1);
+        assert_eq!(stats.files_with_definitions, 2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_file_is_scoped_to_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LanceRelationsStore::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        let helper = definition("src/util.rs", "helper", 1, 3);
+        let mut other_root = helper.clone();
+        other_root.root_path = Some("/other".to_string());
+        store
+            .store_definitions(vec![helper.clone(), other_root], "/project")
+            .await
+            .unwrap();
+        store
+            .store_references(vec![call("src/util.rs", 2, &helper)], "/project")
+            .await
+            .unwrap();
+
+        let deleted = store
+            .delete_by_file("src/util.rs", "/project")
+            .await
+            .unwrap();
+        assert_eq!(deleted, 2);
+
+        let remaining = store.find_definitions_by_name("helper").await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].root_path.as_deref(), Some("/other"));
+
+        store.clear().await.unwrap();
+        let stats = store.get_stats().await.unwrap();
+        assert_eq!(stats.definition_count, 0);
+        assert_eq!(stats.reference_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_root_keeps_other_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = LanceRelationsStore::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        let helper = definition("src/util.rs", "helper", 1, 3);
+        let mut other_root = helper.clone();
+        other_root.root_path = Some("/other".to_string());
+        store
+            .store_definitions(vec![helper.clone(), other_root], "/project")
+            .await
+            .unwrap();
+        store
+            .store_references(vec![call("src/main.rs", 2, &helper)], "/project")
+            .await
+            .unwrap();
+
+        assert_eq!(store.delete_by_root("/project").await.unwrap(), 2);
+        assert!(
+            store
+                .find_definitions_by_root("/project")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let remaining = store.find_definitions_by_root("/other").await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].name(), "helper");
+    }
 }
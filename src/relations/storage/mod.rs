@@ -45,8 +45,18 @@ pub trait RelationsStore: Send + Sync {
     /// Get callees of a function (outgoing call edges)
     async fn get_callees(&self, symbol_id: &str) -> Result<Vec<CallEdge>>;
 
-    /// Delete all relationships for a file (for incremental updates)
-    async fn delete_by_file(&self, file_path: &str) -> Result<usize>;
+    /// All definitions stored for files under a root
+    async fn find_definitions_by_root(&self, root_path: &str) -> Result<Vec<Definition>>;
+
+    /// Files under a root holding references to definitions in `file_path`
+    async fn find_referencing_files(&self, file_path: &str, root_path: &str)
+    -> Result<Vec<String>>;
+
+    /// Delete all relationships for a file under a root (for incremental updates)
+    async fn delete_by_file(&self, file_path: &str, root_path: &str) -> Result<usize>;
+
+    /// Delete all relationships under a root (before storing it from scratch)
+    async fn delete_by_root(&self, root_path: &str) -> Result<usize>;
 
     /// Clear all relationships
     async fn clear(&self) -> Result<()>;
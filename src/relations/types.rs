@@ -60,10 +60,13 @@ impl SymbolKind {
             // Functions (various languages)
             "function_item" // Rust
             | "function_definition" // Python, C, PHP
-            | "function_declaration" // JS/TS, Go, Swift
+            | "function_declaration" // JS/TS, Go, Swift, Zig, Lua
             | "function_expression" // JS/TS
             | "arrow_function" // JS/TS
             | "decorated_definition" // Python (could be either, default to function)
+            | "test_declaration" // Zig
+            | "function_signature" // Dart (followed by its body)
+            | "def" | "defp" | "defmacro" | "defmacrop" | "defguard" | "defguardp" // Elixir
             => Self::Function,
 
             // Methods
@@ -72,6 +75,7 @@ impl SymbolKind {
             | "method" // Ruby
             | "singleton_method" // Ruby
             | "constructor_declaration" // Java
+            | "method_signature" // Dart (followed by its body)
             => Self::Method,
 
             // Classes
@@ -80,27 +84,33 @@ impl SymbolKind {
             | "class_declaration" // JS/TS, Java, PHP, Swift
             | "class_specifier" // C++
             | "class" // Ruby
+            | "extension_declaration" // Dart
+            | "defimpl" // Elixir
             => Self::Class,
 
             // Structs
             "struct_item" // Rust
             | "struct_specifier" // C/C++
-            | "struct_declaration" // Swift, C#
+            | "struct_declaration" // Swift, C#, Zig
+            | "union_declaration" // Zig
+            | "opaque_declaration" // Zig
             => Self::Struct,
 
             // Interfaces/Protocols
             "interface_declaration" // JS/TS, Java, PHP, C#
             | "protocol_declaration" // Swift
+            | "defprotocol" // Elixir
             => Self::Interface,
 
             // Traits
             "trait_item" // Rust
             | "trait_declaration" // PHP
+            | "mixin_declaration" // Dart
             => Self::Trait,
 
             // Enums
             "enum_item" // Rust
-            | "enum_declaration" // JS/TS, Java, Swift, C#
+            | "enum_declaration" // JS/TS, Java, Swift, C#, Zig, Dart
             | "enum_specifier" // C/C++
             => Self::Enum,
 
@@ -109,6 +119,7 @@ impl SymbolKind {
             | "module" // Ruby
             | "namespace_definition" // C++, PHP
             | "namespace_declaration" // C#
+            | "defmodule" // Elixir
             => Self::Module,
 
             // Variables
@@ -153,6 +164,29 @@ impl SymbolKind {
             Self::Unknown => "unknown",
         }
     }
+
+    /// Get the stable identifier for this kind (matches its serialized form)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Function => "function",
+            Self::Method => "method",
+            Self::Class => "class",
+            Self::Struct => "struct",
+            Self::Interface => "interface",
+            Self::Trait => "trait",
+            Self::Enum => "enum",
+            Self::Module => "module",
+            Self::Variable => "variable",
+            Self::Constant => "constant",
+            Self::Parameter => "parameter",
+            Self::Field => "field",
+            Self::Import => "import",
+            Self::Export => "export",
+            Self::EnumVariant => "enum_variant",
+            Self::TypeAlias => "type_alias",
+            Self::Unknown => "unknown",
+        }
+    }
 }
 
 /// Visibility/access modifier for a symbol
@@ -547,6 +581,8 @@ mod tests {
         assert_eq!(SymbolKind::from_ast_kind("function_item"), SymbolKind::Function);
         assert_eq!(SymbolKind::from_ast_kind("class_definition"), SymbolKind::Class);
         assert_eq!(SymbolKind::from_ast_kind("method_definition"), SymbolKind::Method);
+        assert_eq!(SymbolKind::from_ast_kind("defmodule"), SymbolKind::Module);
+        assert_eq!(SymbolKind::from_ast_kind("method_signature"), SymbolKind::Method);
         assert_eq!(SymbolKind::from_ast_kind("unknown_node"), SymbolKind::Unknown);
     }
 
@@ -557,6 +593,19 @@ mod tests {
         assert_eq!(SymbolKind::Unknown.display_name(), "unknown");
     }
 
+    #[test]
+    fn test_symbol_kind_as_str_matches_serde() {
+        for kind in [
+            SymbolKind::Function,
+            SymbolKind::Struct,
+            SymbolKind::EnumVariant,
+            SymbolKind::TypeAlias,
+        ] {
+            let json = serde_json::to_string(&kind).unwrap();
+            assert_eq!(json, format!("\"{}\"", kind.as_str()));
+        }
+    }
+
     #[test]
     fn test_visibility_from_keywords() {
         assert_eq!(Visibility::from_keywords("pub fn foo"), Visibility::Public);
@@ -0,0 +1,61 @@
+//! Optional tracing spans for the indexing and search pipelines
+//!
+//! When `observability.phase_spans` is enabled, the major pipeline phases
+//! (walk, chunk, embed, store, search, fuse) are wrapped in `tracing` spans
+//! carrying counts and durations, so any subscriber (e.g. an OpenTelemetry
+//! exporter) can pick them up. When disabled, no spans are created.
+
+use std::time::Instant;
+use tracing::Span;
+
+/// Factory for pipeline phase spans
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhaseSpans {
+    enabled: bool,
+}
+
+impl PhaseSpans {
+    /// Create a span factory; spans are only emitted when `enabled` is true
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    /// Whether phase spans are emitted
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Build a span with `make`, or return a disabled span when phase spans are off
+    pub fn span(&self, make: impl FnOnce() -> Span) -> Span {
+        if self.enabled { make() } else { Span::none() }
+    }
+}
+
+/// Record the time elapsed since `start` in the span's `duration_ms` field
+pub fn record_duration(span: &Span, start: Instant) {
+    span.record("duration_ms", start.elapsed().as_millis() as u64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_returns_none_span() {
+        let spans = PhaseSpans::new(false);
+        let span = spans.span(|| tracing::info_span!("embed"));
+        assert!(span.is_none());
+        assert!(!spans.enabled());
+    }
+
+    #[test]
+    fn test_default_is_disabled() {
+        assert!(!PhaseSpans::default().enabled());
+    }
+
+    #[test]
+    fn test_record_duration_on_disabled_span_is_noop() {
+        // Recording on a disabled span must not panic
+        record_duration(&Span::none(), Instant::now());
+    }
+}
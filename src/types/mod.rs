@@ -1,6 +1,16 @@
+use crate::relations::PrecisionLevel;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+/// Largest `limit` a query request may ask for
+pub const MAX_QUERY_LIMIT: usize = 1000;
+
+/// Largest `offset` a query request may ask for
+pub const MAX_QUERY_OFFSET: usize = 10_000;
+
+/// Largest `context_lines` a query request may ask for
+pub const MAX_CONTEXT_LINES: usize = 200;
+
 /// Request to index a codebase
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IndexRequest {
@@ -12,12 +22,20 @@ pub struct IndexRequest {
     /// Optional glob patterns to include (e.g., ["**/*.rs", "**/*.toml"])
     #[serde(default)]
     pub include_patterns: Vec<String>,
-    /// Optional glob patterns to exclude (e.g., ["**/target/**", "**/node_modules/**"])
+    /// Optional glob patterns to exclude (e.g., ["**/target/**", "**/node_modules/**"]),
+    /// applied in order; a leading `!` re-includes files excluded by earlier patterns
     #[serde(default)]
     pub exclude_patterns: Vec<String>,
     /// Maximum file size in bytes to index (default: 1MB)
     #[serde(default = "default_max_file_size")]
     pub max_file_size: usize,
+    /// Optional embedding model for this codebase. Defaults to the model the
+    /// codebase was last indexed with, or the configured model for new codebases.
+    /// Codebases indexed with another model are stored separately, and queries
+    /// filtered to their path use the same model. Changing the model of an
+    /// indexed codebase triggers a full reindex.
+    #[serde(default)]
+    pub model_name: Option<String>,
 }
 
 fn default_max_file_size() -> usize {
@@ -56,6 +74,49 @@ pub struct IndexResponse {
     /// Number of files removed (incremental mode only)
     #[serde(default)]
     pub files_removed: usize,
+    /// Files that were found but could not be indexed, with the reason
+    #[serde(default)]
+    pub file_errors: Vec<FileIndexError>,
+}
+
+/// A file left out of the index because it could not be indexed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct FileIndexError {
+    /// File path relative to the indexed directory
+    pub path: String,
+    /// Why the file was skipped (read error, PDF extraction failure, oversize)
+    pub reason: String,
+}
+
+/// Stage of an indexing run reported through [`IndexProgress`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexPhase {
+    /// Waiting for another indexing run on the same path, or repairing an interrupted one
+    Preparing,
+    /// Walking the directory and comparing files with the cache
+    Scanning,
+    /// Splitting files into chunks
+    Chunking,
+    /// Generating embeddings for the chunks
+    Embedding,
+    /// Writing embeddings to the vector database
+    Storing,
+    /// Saving the hash cache and flushing the index to disk
+    Finalizing,
+    /// Indexing finished
+    Complete,
+}
+
+/// Progress update emitted while indexing, mirroring the MCP progress notifications
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct IndexProgress {
+    /// Current stage of the run
+    pub phase: IndexPhase,
+    /// Overall completion, from 0 to 100
+    pub percent: f64,
+    /// Human-readable description of the current step
+    pub message: String,
 }
 
 /// Request to query the codebase
@@ -69,15 +130,45 @@ pub struct QueryRequest {
     /// Optional project name to filter by
     #[serde(default)]
     pub project: Option<String>,
+    /// Project names to search in together with `project` (empty = all projects)
+    #[serde(default)]
+    pub projects: Vec<String>,
     /// Number of results to return (default: 10)
     #[serde(default = "default_limit")]
     pub limit: usize,
+    /// Number of ranked results to skip, for paging through a large result
+    /// set `limit` at a time (default: 0)
+    #[serde(default)]
+    pub offset: usize,
     /// Minimum similarity score (0.0 to 1.0, default: 0.7)
     #[serde(default = "default_min_score")]
     pub min_score: f32,
     /// Enable hybrid search (vector + keyword) - default: true
     #[serde(default = "default_hybrid")]
     pub hybrid: bool,
+    /// Files to leave out of the results (exact relative paths or glob patterns)
+    #[serde(default)]
+    pub exclude_files: Vec<String>,
+    /// Prefix each result's content with the signature line of the function or
+    /// method enclosing the match, separated from the snippet by a `---` line
+    #[serde(default)]
+    pub include_signature: bool,
+    /// Also return a summary of the results: distinct files, projects,
+    /// languages and the score distribution
+    #[serde(default)]
+    pub include_summary: bool,
+    /// Also report, for each result inside a known symbol, how precise code
+    /// navigation (definitions, references, call graphs) is for its language
+    #[serde(default)]
+    pub include_relations_precision: bool,
+    /// Also return this many source lines before and after each result,
+    /// re-read from the file (0 = off, default)
+    #[serde(default)]
+    pub context_lines: usize,
+    /// Also return git commits and tags indexed by `search_git_history`, which
+    /// share the table with code (default: false)
+    #[serde(default)]
+    pub include_git_commits: bool,
 }
 
 fn default_hybrid() -> bool {
@@ -92,9 +183,37 @@ fn default_min_score() -> f32 {
     0.7
 }
 
+/// Which side of the search produced a result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchSource {
+    /// Found by vector (semantic) search only
+    #[default]
+    Vector,
+    /// Found by keyword (BM25) search only
+    Keyword,
+    /// Found by both vector and keyword search
+    Both,
+}
+
+impl MatchSource {
+    /// Provenance of a result from the candidate lists it appeared in
+    pub fn from_hits(vector: bool, keyword: bool) -> Self {
+        match (vector, keyword) {
+            (true, true) => Self::Both,
+            (false, true) => Self::Keyword,
+            _ => Self::Vector,
+        }
+    }
+}
+
 /// A single search result
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SearchResult {
+    /// Stable id of the chunk, the same across queries: `{root_path}/{file_path}:{start_line}`,
+    /// or `git:{hash}` for git commits and tags (see [`SearchResult::canonical_id`])
+    #[serde(default)]
+    pub chunk_id: String,
     /// File path relative to the indexed root
     pub file_path: String,
     /// Absolute path to the indexed root directory
@@ -116,6 +235,123 @@ pub struct SearchResult {
     pub language: String,
     /// Optional project name for multi-project support
     pub project: Option<String>,
+    /// Kind of the symbol the chunk was extracted from (e.g. "function", "struct")
+    #[serde(default)]
+    pub symbol_kind: Option<String>,
+    /// Whether the result came from vector search, keyword search, or both
+    #[serde(default)]
+    pub matched_via: MatchSource,
+    /// Byte ranges of `content` matching the keyword query (hybrid keyword
+    /// matches only, empty for vector-only results)
+    #[serde(default)]
+    pub highlights: Vec<(usize, usize)>,
+    /// Whether this is a metadata-only entry for a binary file (no content)
+    #[serde(default)]
+    pub binary: bool,
+    /// File size in bytes (binary files only)
+    #[serde(default)]
+    pub file_size: Option<u64>,
+    /// Branch a git commit was indexed from (git commit results only)
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Unix timestamp of the commit (git commit results only)
+    #[serde(default)]
+    pub commit_date: Option<i64>,
+    /// Unix timestamp of when the chunk was indexed
+    #[serde(default)]
+    pub indexed_at: Option<i64>,
+    /// Precision of code navigation for the result's language (only when
+    /// `include_relations_precision` was requested and the result is inside a
+    /// known symbol)
+    #[serde(default)]
+    pub relations_precision: Option<PrecisionLevel>,
+    /// Source lines around the chunk (only when `context_lines` was requested
+    /// and the file can still be read)
+    #[serde(default)]
+    pub context: Option<ResultContext>,
+    /// Rank positions behind the score, reported by [`QueryDebugResponse`]
+    #[serde(skip)]
+    pub ranking: Option<RankingDetail>,
+}
+
+impl SearchResult {
+    /// Id of the chunk at `start_line` of `file_path`, for deduplicating results
+    ///
+    /// File paths are relative, so the id is qualified with the indexed root
+    /// to stay unique across roots and projects. Git entries all share their
+    /// repository's `git://` path, so they are identified by the commit or
+    /// tag hash stored as their `file_hash`.
+    pub fn canonical_id(
+        root_path: Option<&str>,
+        file_path: &str,
+        start_line: usize,
+        file_hash: Option<&str>,
+    ) -> String {
+        match (file_hash, root_path) {
+            (Some(hash), _) if file_path.starts_with("git://") => format!("git:{}", hash),
+            (_, Some(root)) => format!(
+                "{}/{}:{}",
+                root.trim_end_matches('/'),
+                file_path,
+                start_line
+            ),
+            (_, None) => format!("{}:{}", file_path, start_line),
+        }
+    }
+
+    /// Whether `other` is a chunk of the same file sharing more than half the
+    /// lines of the longer of the two, as overlapping chunks do
+    pub fn overlaps_mostly(&self, other: &SearchResult) -> bool {
+        if self.file_path != other.file_path
+            || self.root_path != other.root_path
+            || self.project != other.project
+            || self.branch != other.branch
+        {
+            return false;
+        }
+
+        let shared_start = self.start_line.max(other.start_line);
+        let shared_end = self.end_line.min(other.end_line);
+        if shared_start > shared_end {
+            return false;
+        }
+        let shared = shared_end - shared_start + 1;
+        let lines = |r: &SearchResult| r.end_line.saturating_sub(r.start_line) + 1;
+        shared * 2 > lines(self).max(lines(other))
+    }
+
+    /// Drop results that mostly overlap a result ranked above them
+    pub fn dedup_overlapping(results: &mut Vec<SearchResult>) {
+        let mut kept: Vec<SearchResult> = Vec::with_capacity(results.len());
+        for result in results.drain(..) {
+            if !kept.iter().any(|k| k.overlaps_mostly(&result)) {
+                kept.push(result);
+            }
+        }
+        *results = kept;
+    }
+}
+
+/// Lines surrounding a search result, read from the file at query time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ResultContext {
+    /// First line of the context (1-based)
+    pub start_line: usize,
+    /// Last line of the context (1-based)
+    pub end_line: usize,
+    /// The context lines, including the chunk's own lines
+    pub content: String,
+}
+
+/// Where a result ranked in each candidate list before fusion
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RankingDetail {
+    /// Raw distance from the query vector
+    pub vector_distance: Option<f32>,
+    /// 1-based position in the vector candidate list
+    pub vector_rank: Option<usize>,
+    /// 1-based position in the keyword (BM25) candidate list
+    pub keyword_rank: Option<usize>,
 }
 
 /// Response from query operation
@@ -131,6 +367,274 @@ pub struct QueryResponse {
     /// Whether the threshold was automatically lowered to find results
     #[serde(default)]
     pub threshold_lowered: bool,
+    /// Whether the requested limit was clamped to the server's `max_results`
+    #[serde(default)]
+    pub truncated: bool,
+    /// Whether more results follow this page (request them with a larger `offset`)
+    #[serde(default)]
+    pub has_more: bool,
+    /// Overview of the returned results (only when `include_summary` was requested)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<QuerySummary>,
+}
+
+/// A file among the results of a files-only query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FileMatch {
+    /// File path relative to the indexed root
+    pub file_path: String,
+    /// Absolute path to the indexed root directory
+    #[serde(default)]
+    pub root_path: Option<String>,
+    /// Score of the file's best-matching chunk
+    pub best_score: f32,
+    /// Number of the file's chunks among the results
+    pub chunk_count: usize,
+}
+
+impl FileMatch {
+    /// Collapse `results` into their distinct files, best chunk score first
+    ///
+    /// Files with equal scores keep the order of their best chunks.
+    pub fn from_results(results: &[SearchResult]) -> Vec<Self> {
+        let mut files: Vec<Self> = Vec::new();
+        for result in results {
+            match files
+                .iter_mut()
+                .find(|f| f.file_path == result.file_path && f.root_path == result.root_path)
+            {
+                Some(file) => {
+                    file.chunk_count += 1;
+                    file.best_score = file.best_score.max(result.score);
+                }
+                None => files.push(Self {
+                    file_path: result.file_path.clone(),
+                    root_path: result.root_path.clone(),
+                    best_score: result.score,
+                    chunk_count: 1,
+                }),
+            }
+        }
+        files.sort_by(|a, b| b.best_score.total_cmp(&a.best_score));
+        files
+    }
+}
+
+/// Response from a files-only query
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct QueryFilesResponse {
+    /// Distinct files of the matching chunks, ordered by their best chunk score
+    pub files: Vec<FileMatch>,
+    /// Time taken in milliseconds
+    pub duration_ms: u64,
+    /// The actual threshold used (may be lower than requested if adaptive search kicked in)
+    #[serde(default)]
+    pub threshold_used: f32,
+    /// Whether the threshold was automatically lowered to find results
+    #[serde(default)]
+    pub threshold_lowered: bool,
+    /// Whether the requested limit was clamped to the server's `max_results`
+    #[serde(default)]
+    pub truncated: bool,
+    /// Whether more files follow this page (request them with a larger `offset`)
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+/// Aggregate view of a result set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct QuerySummary {
+    /// Number of results summarized
+    pub result_count: usize,
+    /// Number of distinct files among the results
+    pub file_count: usize,
+    /// Results per project, most results first (results without a project are not counted)
+    pub projects: Vec<SummaryBucket>,
+    /// Results per language, most results first
+    pub languages: Vec<SummaryBucket>,
+    /// File of the highest-scoring result
+    pub top_file: Option<String>,
+    /// Lowest result score
+    pub min_score: f32,
+    /// Highest result score
+    pub max_score: f32,
+    /// Mean result score
+    pub mean_score: f32,
+}
+
+/// Number of results sharing a project or language
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SummaryBucket {
+    /// Project or language name
+    pub name: String,
+    /// Number of results
+    pub results: usize,
+    /// Highest score among those results
+    pub best_score: f32,
+}
+
+impl QuerySummary {
+    /// Summarize `results`
+    pub fn from_results(results: &[SearchResult]) -> Self {
+        let files: std::collections::HashSet<(&Option<String>, &str)> = results
+            .iter()
+            .map(|r| (&r.root_path, r.file_path.as_str()))
+            .collect();
+
+        let (min_score, max_score, mean_score) = if results.is_empty() {
+            (0.0, 0.0, 0.0)
+        } else {
+            let scores = results.iter().map(|r| r.score);
+            (
+                scores.clone().fold(f32::INFINITY, f32::min),
+                scores.clone().fold(f32::NEG_INFINITY, f32::max),
+                scores.sum::<f32>() / results.len() as f32,
+            )
+        };
+
+        // First of the best-scoring results, so ties resolve to the higher-ranked one
+        let top_file = results
+            .iter()
+            .reduce(|best, r| if r.score > best.score { r } else { best })
+            .map(|r| r.file_path.clone());
+
+        Self {
+            result_count: results.len(),
+            file_count: files.len(),
+            projects: SummaryBucket::group(results, |r| r.project.as_deref()),
+            languages: SummaryBucket::group(results, |r| Some(r.language.as_str())),
+            top_file,
+            min_score,
+            max_score,
+            mean_score,
+        }
+    }
+}
+
+impl SummaryBucket {
+    /// Count results per key, most results first, then by name
+    fn group<'a>(
+        results: &'a [SearchResult],
+        key: impl Fn(&'a SearchResult) -> Option<&'a str>,
+    ) -> Vec<Self> {
+        let mut buckets: Vec<Self> = Vec::new();
+        for result in results {
+            let Some(name) = key(result) else { continue };
+            match buckets.iter_mut().find(|b| b.name == name) {
+                Some(bucket) => {
+                    bucket.results += 1;
+                    bucket.best_score = bucket.best_score.max(result.score);
+                }
+                None => buckets.push(Self {
+                    name: name.to_string(),
+                    results: 1,
+                    best_score: result.score,
+                }),
+            }
+        }
+        buckets.sort_by(|a, b| b.results.cmp(&a.results).then_with(|| a.name.cmp(&b.name)));
+        buckets
+    }
+}
+
+/// Fused results of a query alongside the candidate lists they were fused from
+///
+/// For offline retrieval evaluation: each side's list carries its native
+/// scores (vector similarity, raw BM25) so per-side metrics can be computed.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DualQueryResponse {
+    /// Hybrid results, as returned by a hybrid query
+    pub fused: Vec<SearchResult>,
+    /// Vector candidates, ordered by vector similarity
+    pub vector: Vec<SearchResult>,
+    /// Keyword candidates, ordered by BM25 score
+    pub keyword: Vec<SearchResult>,
+    /// The threshold the fused results were filtered with
+    pub threshold_used: f32,
+    /// Time taken in milliseconds
+    pub duration_ms: u64,
+}
+
+/// Full scoring of a query, in a stable form for golden-file ranking tests
+///
+/// Unlike [`QueryResponse`] this carries no timings, so identical queries
+/// against the same index serialize identically.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct QueryDebugResponse {
+    /// The query that was scored
+    pub query: String,
+    /// Whether vector and keyword results were fused
+    pub hybrid: bool,
+    /// The threshold the results were filtered with
+    pub threshold_used: f32,
+    /// Whether the threshold was automatically lowered to find results
+    pub threshold_lowered: bool,
+    /// Rank constant `k` of the reciprocal rank fusion `1 / (k + rank)`
+    pub rrf_k: f32,
+    /// Scoring of each result, in final order
+    pub results: Vec<ScoreBreakdown>,
+}
+
+/// How a single result's score was computed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScoreBreakdown {
+    /// 1-based position in the final ranking
+    pub rank: usize,
+    /// File path relative to the indexed root
+    pub file_path: String,
+    /// Absolute path to the indexed root directory
+    pub root_path: Option<String>,
+    /// Starting line number in the file
+    pub start_line: usize,
+    /// Ending line number in the file
+    pub end_line: usize,
+    /// Raw distance from the query vector, if the backend reports it
+    pub vector_distance: Option<f32>,
+    /// Vector similarity score
+    pub vector_score: f32,
+    /// 1-based position in the vector candidate list
+    pub vector_rank: Option<usize>,
+    /// BM25 keyword score
+    pub keyword_score: Option<f32>,
+    /// 1-based position in the keyword candidate list
+    pub keyword_rank: Option<usize>,
+    /// Contribution of the vector rank to the fused score
+    pub vector_rrf: Option<f32>,
+    /// Contribution of the keyword rank to the fused score
+    pub keyword_rrf: Option<f32>,
+    /// Final score used for ranking
+    pub score: f32,
+    /// Whether the result came from vector search, keyword search, or both
+    pub matched_via: MatchSource,
+}
+
+impl ScoreBreakdown {
+    /// Breakdown of the result at 1-based `rank` in the final ranking
+    pub(crate) fn new(rank: usize, result: &SearchResult, hybrid: bool) -> Self {
+        let ranking = result.ranking.unwrap_or_default();
+        // Only hybrid scores are fused from ranks
+        let rrf = |rank: Option<usize>| {
+            rank.filter(|_| hybrid)
+                .map(crate::bm25_search::rrf_contribution)
+        };
+
+        Self {
+            rank,
+            file_path: result.file_path.clone(),
+            root_path: result.root_path.clone(),
+            start_line: result.start_line,
+            end_line: result.end_line,
+            vector_distance: ranking.vector_distance,
+            vector_score: result.vector_score,
+            vector_rank: ranking.vector_rank,
+            keyword_score: result.keyword_score,
+            keyword_rank: ranking.keyword_rank,
+            vector_rrf: rrf(ranking.vector_rank),
+            keyword_rrf: rrf(ranking.keyword_rank),
+            score: result.score,
+            matched_via: result.matched_via,
+        }
+    }
 }
 
 /// Request to get statistics about the index
@@ -146,7 +650,7 @@ pub struct StatisticsResponse {
     pub total_chunks: usize,
     /// Total number of embeddings
     pub total_embeddings: usize,
-    /// Size of the vector database in bytes
+    /// Size of the vector table and its keyword indexes in bytes
     pub database_size_bytes: u64,
     /// Breakdown by programming language
     pub language_breakdown: Vec<LanguageStats>,
@@ -159,6 +663,45 @@ pub struct LanguageStats {
     pub chunk_count: usize,
 }
 
+/// Request to check that every subsystem is ready to serve queries
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HealthCheckRequest {}
+
+/// Readiness of the embedding model, vector database and keyword indexes
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HealthReport {
+    /// Whether every subsystem passed its probe
+    pub healthy: bool,
+    /// Embedding a short probe string
+    pub embedding: SubsystemHealth,
+    /// Counting the rows of the vector database
+    pub vector_db: SubsystemHealth,
+    /// Opening a reader on every BM25 keyword index
+    pub bm25: SubsystemHealth,
+}
+
+/// Outcome of one subsystem's probe in a [`HealthReport`]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SubsystemHealth {
+    /// Whether the probe succeeded
+    pub healthy: bool,
+    /// How long the probe took, in milliseconds
+    pub latency_ms: u64,
+    /// What the probe found, or why it failed
+    pub detail: String,
+}
+
+/// An indexed project, as listed by [`RagClient::list_projects`](crate::client::RagClient::list_projects)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectSummary {
+    /// Project name given at indexing time
+    pub name: String,
+    /// Number of chunks stored for the project
+    pub chunk_count: usize,
+    /// Distinct languages of those chunks, sorted
+    pub languages: Vec<String>,
+}
+
 /// Request to clear the index
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ClearRequest {}
@@ -172,6 +715,80 @@ pub struct ClearResponse {
     pub message: String,
 }
 
+/// Response from removing a project
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RemoveProjectResponse {
+    /// Number of chunks deleted
+    pub chunks_removed: usize,
+    /// Number of distinct files those chunks came from
+    pub files_removed: usize,
+    /// Root paths left without any chunks, whose keyword indexes and cache
+    /// entries were dropped as well
+    pub roots_removed: Vec<String>,
+}
+
+/// Response from optimizing the index
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct OptimizeResponse {
+    /// Data fragments of the vector table before compaction
+    pub fragments_before: usize,
+    /// Data fragments of the vector table after compaction
+    pub fragments_after: usize,
+    /// Number of BM25 indexes whose segments were merged
+    pub bm25_indexes: usize,
+    /// Segments across the BM25 indexes before merging
+    pub bm25_segments_before: usize,
+    /// Segments across the BM25 indexes after merging
+    pub bm25_segments_after: usize,
+    /// Time taken in milliseconds
+    pub duration_ms: u64,
+}
+
+/// Criteria selecting chunks for bulk deletion
+///
+/// A chunk is deleted when it matches every criterion that is set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DeleteFilter {
+    /// Delete chunks whose file path (relative to its root) starts with this prefix
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    /// Delete chunks of this language (e.g. "Rust")
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Delete chunks of this project
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Delete chunks indexed before this Unix timestamp
+    #[serde(default)]
+    pub indexed_before: Option<i64>,
+}
+
+impl DeleteFilter {
+    /// Whether no criterion is set (such a filter would match everything)
+    pub fn is_empty(&self) -> bool {
+        self.path_prefix.is_none()
+            && self.language.is_none()
+            && self.project.is_none()
+            && self.indexed_before.is_none()
+    }
+
+    /// Whether a chunk with these attributes matches every criterion that is set
+    pub fn matches(
+        &self,
+        file_path: &str,
+        language: &str,
+        project: Option<&str>,
+        indexed_at: i64,
+    ) -> bool {
+        self.path_prefix
+            .as_deref()
+            .is_none_or(|prefix| file_path.starts_with(prefix))
+            && self.language.as_deref().is_none_or(|l| l == language)
+            && self.project.as_deref().is_none_or(|p| Some(p) == project)
+            && self.indexed_before.is_none_or(|before| indexed_at < before)
+    }
+}
+
 /// Request for incremental update
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IncrementalUpdateRequest {
@@ -183,7 +800,7 @@ pub struct IncrementalUpdateRequest {
     /// Optional glob patterns to include
     #[serde(default)]
     pub include_patterns: Vec<String>,
-    /// Optional glob patterns to exclude
+    /// Optional glob patterns to exclude, applied in order (`!` re-includes)
     #[serde(default)]
     pub exclude_patterns: Vec<String>,
 }
@@ -229,6 +846,9 @@ pub struct AdvancedSearchRequest {
     /// Filter by file path patterns (glob)
     #[serde(default)]
     pub path_patterns: Vec<String>,
+    /// Filter by the kind of symbol a chunk was extracted from (e.g. ["struct", "trait"])
+    #[serde(default)]
+    pub symbol_kinds: Vec<crate::relations::SymbolKind>,
 }
 
 /// Request to search git history
@@ -266,6 +886,37 @@ pub struct SearchGitHistoryRequest {
     /// Filter by file path pattern (optional regex)
     #[serde(default)]
     pub file_pattern: Option<String>,
+    /// What to search: "commit" (default), "tag" (annotated tags) or "all"
+    #[serde(default)]
+    pub kind: GitEntryKind,
+    /// Drop this repository's indexed history and re-index it from scratch
+    #[serde(default)]
+    pub reset: bool,
+}
+
+/// Kind of git history entry to index and search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GitEntryKind {
+    /// Commits of the searched branch
+    #[default]
+    Commit,
+    /// Annotated tags, typically carrying release notes
+    Tag,
+    /// Both commits and annotated tags
+    All,
+}
+
+impl GitEntryKind {
+    /// Whether commits are included
+    pub fn includes_commits(self) -> bool {
+        matches!(self, Self::Commit | Self::All)
+    }
+
+    /// Whether annotated tags are included
+    pub fn includes_tags(self) -> bool {
+        matches!(self, Self::Tag | Self::All)
+    }
 }
 
 fn default_git_path() -> String {
@@ -299,6 +950,16 @@ pub struct GitSearchResult {
     pub files_changed: Vec<String>,
     /// Diff snippet (first ~500 characters)
     pub diff_snippet: String,
+    /// Branch the commit was indexed from; a commit shared by several branches
+    /// reports the branch that was searched when it was first indexed
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Whether this result is a commit or an annotated tag
+    #[serde(default)]
+    pub kind: GitEntryKind,
+    /// Tag name (tags only; `commit_hash` is then the tagged commit)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_name: Option<String>,
 }
 
 /// Response from git history search
@@ -479,6 +1140,133 @@ pub struct GetCallGraphResponse {
     pub duration_ms: u64,
 }
 
+/// Request to list the symbols defined in a file
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ListSymbolsRequest {
+    /// File path (relative or absolute)
+    pub file_path: String,
+    /// Optional project name to filter by
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+impl ListSymbolsRequest {
+    /// Validate the list symbols request
+    pub fn validate(&self) -> Result<(), String> {
+        if self.file_path.is_empty() {
+            return Err("file_path cannot be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Request to find the definitions of a symbol by its exact name
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FindSymbolRequest {
+    /// Symbol name, matched exactly (case-sensitive)
+    pub name: String,
+    /// Optional project name to filter by
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+impl FindSymbolRequest {
+    /// Validate the find symbol request
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name cannot be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Request to search the codebase and attach call relations to the matching symbols
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SearchWithRelationsRequest {
+    /// The question or search query
+    pub query: String,
+    /// Optional path to filter by specific indexed codebase
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Optional project name to filter by
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Number of search results to return (default: 10)
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    /// Minimum similarity score (0.0 to 1.0, default: 0.7)
+    #[serde(default = "default_min_score")]
+    pub min_score: f32,
+    /// Enable hybrid search (vector + keyword) - default: true
+    #[serde(default = "default_hybrid")]
+    pub hybrid: bool,
+}
+
+impl SearchWithRelationsRequest {
+    /// Validate the search with relations request
+    pub fn validate(&self) -> Result<(), String> {
+        self.to_query_request().validate()
+    }
+
+    /// Build the underlying semantic search request
+    pub fn to_query_request(&self) -> QueryRequest {
+        QueryRequest {
+            query: self.query.clone(),
+            path: self.path.clone(),
+            project: self.project.clone(),
+            projects: vec![],
+            limit: self.limit,
+            offset: 0,
+            min_score: self.min_score,
+            hybrid: self.hybrid,
+            exclude_files: vec![],
+            include_signature: false,
+            include_summary: false,
+            include_relations_precision: false,
+            context_lines: 0,
+            include_git_commits: false,
+        }
+    }
+}
+
+/// A function or method found in a search result, with its immediate call relations
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RelatedSymbol {
+    /// The symbol overlapping the matched chunk
+    pub symbol: crate::relations::SymbolInfo,
+    /// Functions/methods that call this symbol
+    pub callers: Vec<crate::relations::CallGraphNode>,
+    /// Functions/methods called by this symbol
+    pub callees: Vec<crate::relations::CallGraphNode>,
+}
+
+/// A search result together with the symbols it covers
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RelatedSearchResult {
+    /// The matching code chunk
+    pub result: SearchResult,
+    /// Functions/methods within the chunk (empty if the chunk is not inside a known symbol)
+    pub symbols: Vec<RelatedSymbol>,
+}
+
+/// Response from search_with_relations
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SearchWithRelationsResponse {
+    /// Search results ordered by relevance, each with its related symbols
+    pub results: Vec<RelatedSearchResult>,
+    /// Time taken in milliseconds
+    pub duration_ms: u64,
+    /// The actual threshold used (may be lower than requested if adaptive search kicked in)
+    #[serde(default)]
+    pub threshold_used: f32,
+    /// Whether the threshold was automatically lowered to find results
+    #[serde(default)]
+    pub threshold_lowered: bool,
+    /// Whether the requested limit was clamped to the server's `max_results`
+    #[serde(default)]
+    pub truncated: bool,
+}
+
 /// Metadata stored with each code chunk
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChunkMetadata {
@@ -501,6 +1289,25 @@ pub struct ChunkMetadata {
     pub file_hash: String,
     /// Timestamp when indexed
     pub indexed_at: i64,
+    /// Kind of the symbol this chunk was extracted from (AST chunking only)
+    #[serde(default)]
+    pub symbol_kind: Option<String>,
+    /// Whether this is a metadata-only entry for a binary file (no content)
+    #[serde(default)]
+    pub binary: bool,
+    /// File size in bytes (binary files only)
+    #[serde(default)]
+    pub file_size: Option<u64>,
+    /// Branch a git commit was indexed from (git commit chunks only)
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Unix timestamp of the commit (git commit chunks only)
+    #[serde(default)]
+    pub commit_date: Option<i64>,
+    /// Chunking method that produced the chunk, e.g. "AST" or "line fallback"
+    /// (set by the code chunker, not stored in the vector database)
+    #[serde(default)]
+    pub chunk_method: Option<String>,
 }
 
 /// Input validation for request types
@@ -553,6 +1360,13 @@ impl IndexRequest {
             }
         }
 
+        // Validate model name if provided
+        if let Some(ref model_name) = self.model_name {
+            if model_name.trim().is_empty() {
+                return Err("model_name cannot be empty".to_string());
+            }
+        }
+
         Ok(())
     }
 }
@@ -583,17 +1397,37 @@ impl QueryRequest {
             ));
         }
 
-        // Validate limit is reasonable (max 1000)
-        const MAX_LIMIT: usize = 1000;
-        if self.limit > MAX_LIMIT {
+        // Validate limit is reasonable
+        if self.limit > MAX_QUERY_LIMIT {
             return Err(format!(
                 "limit too large: {} (max: {})",
-                self.limit, MAX_LIMIT
+                self.limit, MAX_QUERY_LIMIT
             ));
         }
 
-        // Validate project name if provided
-        if let Some(ref project) = self.project {
+        // Validate offset is reasonable
+        if self.offset > MAX_QUERY_OFFSET {
+            return Err(format!(
+                "offset too large: {} (max: {})",
+                self.offset, MAX_QUERY_OFFSET
+            ));
+        }
+
+        // Validate exclude_files entries
+        if self.exclude_files.iter().any(|f| f.trim().is_empty()) {
+            return Err("exclude_files entries cannot be empty".to_string());
+        }
+
+        // Validate context_lines is reasonable
+        if self.context_lines > MAX_CONTEXT_LINES {
+            return Err(format!(
+                "context_lines too large: {} (max: {})",
+                self.context_lines, MAX_CONTEXT_LINES
+            ));
+        }
+
+        // Validate project names if provided
+        for project in self.project.iter().chain(&self.projects) {
             if project.is_empty() {
                 return Err("project name cannot be empty".to_string());
             }
@@ -604,6 +1438,15 @@ impl QueryRequest {
 
         Ok(())
     }
+
+    /// Projects to search in, from `project` and `projects` (empty = all projects)
+    pub fn project_filter(&self) -> Vec<String> {
+        let mut projects: Vec<String> =
+            self.project.iter().chain(&self.projects).cloned().collect();
+        projects.sort();
+        projects.dedup();
+        projects
+    }
 }
 
 impl AdvancedSearchRequest {
@@ -614,9 +1457,17 @@ impl AdvancedSearchRequest {
             query: self.query.clone(),
             path: None,
             project: self.project.clone(),
+            projects: vec![],
             limit: self.limit,
+            offset: 0,
             min_score: self.min_score,
             hybrid: true,
+            exclude_files: vec![],
+            include_signature: false,
+            include_summary: false,
+            include_relations_precision: false,
+            context_lines: 0,
+            include_git_commits: false,
         };
         query_req.validate()?;
 
@@ -8,6 +8,7 @@ fn test_index_request_defaults() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: default_max_file_size(),
+        model_name: None,
     };
 
     assert_eq!(req.max_file_size, 1_048_576);
@@ -26,6 +27,7 @@ fn test_index_response_full_mode() {
         errors: vec![],
         files_updated: 0,
         files_removed: 0,
+        file_errors: vec![],
     };
 
     assert!(matches!(response.mode, IndexingMode::Full));
@@ -45,6 +47,7 @@ fn test_index_response_incremental_mode() {
         errors: vec![],
         files_updated: 5,
         files_removed: 2,
+        file_errors: vec![],
     };
 
     assert!(matches!(response.mode, IndexingMode::Incremental));
@@ -59,9 +62,17 @@ fn test_query_request_defaults() {
         query: "test".to_string(),
         path: None,
         project: None,
+        projects: vec![],
         limit: default_limit(),
+        offset: 0,
         min_score: default_min_score(),
         hybrid: default_hybrid(),
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
     assert_eq!(req.limit, 10);
@@ -77,6 +88,7 @@ fn test_serialization_roundtrip() {
         include_patterns: vec!["**/*.rs".to_string()],
         exclude_patterns: vec!["**/target/**".to_string()],
         max_file_size: 2_000_000,
+        model_name: None,
     };
 
     let json = serde_json::to_string(&req).unwrap();
@@ -91,6 +103,7 @@ fn test_serialization_roundtrip() {
 #[test]
 fn test_search_result_creation() {
     let result = SearchResult {
+        chunk_id: "src/main.rs:1".to_string(),
         file_path: "src/main.rs".to_string(),
         root_path: None,
         content: "fn main() {}".to_string(),
@@ -101,6 +114,17 @@ fn test_search_result_creation() {
         end_line: 10,
         language: "Rust".to_string(),
         project: None,
+        symbol_kind: None,
+        matched_via: MatchSource::Vector,
+        highlights: Vec::new(),
+        binary: false,
+        file_size: None,
+        ranking: None,
+        branch: None,
+        commit_date: None,
+        indexed_at: None,
+        relations_precision: None,
+        context: None,
     };
 
     assert_eq!(result.score, 0.95);
@@ -109,6 +133,138 @@ fn test_search_result_creation() {
     assert_eq!(result.language, "Rust");
 }
 
+fn chunk_result(file_path: &str, start_line: usize, end_line: usize, score: f32) -> SearchResult {
+    SearchResult {
+        chunk_id: SearchResult::canonical_id(None, file_path, start_line, None),
+        file_path: file_path.to_string(),
+        root_path: None,
+        content: String::new(),
+        score,
+        vector_score: score,
+        keyword_score: None,
+        start_line,
+        end_line,
+        language: "Rust".to_string(),
+        project: None,
+        symbol_kind: None,
+        matched_via: MatchSource::Vector,
+        highlights: Vec::new(),
+        binary: false,
+        file_size: None,
+        ranking: None,
+        branch: None,
+        commit_date: None,
+        indexed_at: None,
+        relations_precision: None,
+        context: None,
+    }
+}
+
+#[test]
+fn test_canonical_id_is_unique_across_roots() {
+    let app = SearchResult::canonical_id(Some("/work/app"), "src/main.rs", 1, None);
+    let cli = SearchResult::canonical_id(Some("/work/cli/"), "src/main.rs", 1, None);
+    assert_eq!(app, "/work/app/src/main.rs:1");
+    assert_eq!(cli, "/work/cli/src/main.rs:1");
+
+    let commit =
+        SearchResult::canonical_id(Some("/work/app"), "git:///work/app", 0, Some("abc123"));
+    assert_eq!(commit, "git:abc123");
+}
+
+#[test]
+fn test_dedup_overlapping_results() {
+    let mut results = vec![
+        chunk_result("a.rs", 11, 20, 0.9),
+        // Shares 8 of 10 lines with the first
+        chunk_result("a.rs", 13, 22, 0.8),
+        // Shares 2 of 10 lines: a neighbouring chunk
+        chunk_result("a.rs", 19, 28, 0.7),
+        chunk_result("b.rs", 11, 20, 0.6),
+    ];
+
+    SearchResult::dedup_overlapping(&mut results);
+
+    let kept: Vec<_> = results
+        .iter()
+        .map(|r| (r.file_path.as_str(), r.start_line))
+        .collect();
+    assert_eq!(kept, [("a.rs", 11), ("a.rs", 19), ("b.rs", 11)]);
+}
+
+#[test]
+fn test_file_matches_collapse_chunks_per_file() {
+    let results = vec![
+        chunk_result("a.rs", 1, 10, 0.6),
+        chunk_result("b.rs", 1, 10, 0.8),
+        chunk_result("a.rs", 20, 30, 0.9),
+        chunk_result("a.rs", 40, 50, 0.5),
+    ];
+
+    let files = FileMatch::from_results(&results);
+
+    let summary: Vec<_> = files
+        .iter()
+        .map(|f| (f.file_path.as_str(), f.best_score, f.chunk_count))
+        .collect();
+    assert_eq!(summary, [("a.rs", 0.9, 3), ("b.rs", 0.8, 1)]);
+}
+
+#[test]
+fn test_nested_chunk_is_not_a_duplicate() {
+    let class = chunk_result("a.rs", 1, 100, 0.9);
+    let method = chunk_result("a.rs", 40, 50, 0.8);
+    assert!(!class.overlaps_mostly(&method));
+    assert!(class.overlaps_mostly(&class.clone()));
+}
+
+#[test]
+fn test_match_source_from_hits() {
+    // Pure keyword match
+    assert_eq!(MatchSource::from_hits(false, true), MatchSource::Keyword);
+    // Semantic match
+    assert_eq!(MatchSource::from_hits(true, false), MatchSource::Vector);
+    assert_eq!(MatchSource::from_hits(true, true), MatchSource::Both);
+}
+
+#[test]
+fn test_search_result_matched_via_serialization() {
+    let json = r#"{
+        "file_path": "src/main.rs",
+        "content": "fn main() {}",
+        "score": 0.9,
+        "vector_score": 0.0,
+        "keyword_score": 4.2,
+        "start_line": 1,
+        "end_line": 1,
+        "language": "Rust",
+        "project": null,
+        "matched_via": "keyword"
+    }"#;
+    let result: SearchResult = serde_json::from_str(json).unwrap();
+    assert_eq!(result.matched_via, MatchSource::Keyword);
+
+    // Results serialized before provenance was tracked default to vector
+    let legacy = r#"{
+        "file_path": "src/main.rs",
+        "content": "fn main() {}",
+        "score": 0.9,
+        "vector_score": 0.9,
+        "keyword_score": null,
+        "start_line": 1,
+        "end_line": 1,
+        "language": "Rust",
+        "project": null
+    }"#;
+    let result: SearchResult = serde_json::from_str(legacy).unwrap();
+    assert_eq!(result.matched_via, MatchSource::Vector);
+
+    assert_eq!(
+        serde_json::to_string(&MatchSource::Both).unwrap(),
+        "\"both\""
+    );
+}
+
 #[test]
 fn test_chunk_metadata_creation() {
     let metadata = ChunkMetadata {
@@ -121,6 +277,12 @@ fn test_chunk_metadata_creation() {
         extension: Some("rs".to_string()),
         file_hash: "abc123".to_string(),
         indexed_at: 1234567890,
+        symbol_kind: None,
+        binary: false,
+        file_size: None,
+        branch: None,
+        commit_date: None,
+        chunk_method: None,
     };
 
     assert_eq!(metadata.start_line, 1);
@@ -176,6 +338,7 @@ fn test_index_request_validate_nonexistent_path() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: default_max_file_size(),
+        model_name: None,
     };
 
     let result = req.validate();
@@ -191,6 +354,7 @@ fn test_index_request_validate_valid_path() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: default_max_file_size(),
+        model_name: None,
     };
 
     let result = req.validate();
@@ -205,6 +369,7 @@ fn test_index_request_validate_max_file_size_too_large() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 200_000_000, // 200MB, over the limit
+        model_name: None,
     };
 
     let result = req.validate();
@@ -220,6 +385,7 @@ fn test_index_request_validate_empty_project_name() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: default_max_file_size(),
+        model_name: None,
     };
 
     let result = req.validate();
@@ -235,6 +401,7 @@ fn test_index_request_validate_project_name_too_long() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: default_max_file_size(),
+        model_name: None,
     };
 
     let result = req.validate();
@@ -242,15 +409,39 @@ fn test_index_request_validate_project_name_too_long() {
     assert!(result.unwrap_err().contains("too long"));
 }
 
+#[test]
+fn test_index_request_validate_empty_model_name() {
+    let req = IndexRequest {
+        path: ".".to_string(),
+        project: None,
+        include_patterns: vec![],
+        exclude_patterns: vec![],
+        max_file_size: default_max_file_size(),
+        model_name: Some("  ".to_string()),
+    };
+
+    let result = req.validate();
+    assert!(result.is_err());
+    assert!(result.unwrap_err().contains("model_name"));
+}
+
 #[test]
 fn test_query_request_validate_empty_query() {
     let req = QueryRequest {
         query: "   ".to_string(),
         path: None, // Whitespace only
         project: None,
+        projects: vec![],
         limit: default_limit(),
+        offset: 0,
         min_score: default_min_score(),
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
     let result = req.validate();
@@ -264,9 +455,17 @@ fn test_query_request_validate_query_too_long() {
         query: "a".repeat(20_000),
         path: None, // 20KB, over the limit
         project: None,
+        projects: vec![],
         limit: default_limit(),
+        offset: 0,
         min_score: default_min_score(),
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
     let result = req.validate();
@@ -280,9 +479,17 @@ fn test_query_request_validate_min_score_out_of_range() {
         query: "test".to_string(),
         path: None,
         project: None,
+        projects: vec![],
         limit: default_limit(),
+        offset: 0,
         min_score: 1.5, // Out of range
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
     let result = req.validate();
@@ -290,15 +497,53 @@ fn test_query_request_validate_min_score_out_of_range() {
     assert!(result.unwrap_err().contains("must be between 0.0 and 1.0"));
 }
 
+#[test]
+fn test_query_request_validate_empty_exclude_file() {
+    let req = QueryRequest {
+        query: "test".to_string(),
+        path: None,
+        project: None,
+        projects: vec![],
+        limit: default_limit(),
+        offset: 0,
+        min_score: default_min_score(),
+        hybrid: true,
+        exclude_files: vec!["src/main.rs".to_string(), "  ".to_string()],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
+    };
+
+    let result = req.validate();
+    assert!(result.is_err());
+    assert!(result.unwrap_err().contains("exclude_files"));
+}
+
+#[test]
+fn test_query_request_exclude_files_defaults_to_empty() {
+    let req: QueryRequest = serde_json::from_str(r#"{"query": "test"}"#).unwrap();
+    assert!(req.exclude_files.is_empty());
+}
+
 #[test]
 fn test_query_request_validate_limit_too_large() {
     let req = QueryRequest {
         query: "test".to_string(),
         path: None,
         project: None,
+        projects: vec![],
         limit: 2000, // Over the limit
+        offset: 0,
         min_score: default_min_score(),
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
     let result = req.validate();
@@ -306,21 +551,80 @@ fn test_query_request_validate_limit_too_large() {
     assert!(result.unwrap_err().contains("limit too large"));
 }
 
+#[test]
+fn test_query_request_validate_offset_too_large() {
+    let req: QueryRequest = serde_json::from_str(r#"{"query": "test", "offset": 20000}"#).unwrap();
+
+    let result = req.validate();
+    assert!(result.is_err());
+    assert!(result.unwrap_err().contains("offset too large"));
+}
+
+#[test]
+fn test_query_request_validate_context_lines_too_large() {
+    let req = QueryRequest {
+        query: "test".to_string(),
+        path: None,
+        project: None,
+        projects: vec![],
+        limit: 10,
+        offset: 0,
+        min_score: default_min_score(),
+        hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: MAX_CONTEXT_LINES + 1,
+        include_git_commits: false,
+    };
+
+    let result = req.validate();
+    assert!(result.is_err());
+    assert!(result.unwrap_err().contains("context_lines too large"));
+}
+
 #[test]
 fn test_query_request_validate_valid() {
     let req = QueryRequest {
         query: "test query".to_string(),
         path: None,
         project: Some("my-project".to_string()),
+        projects: vec![],
         limit: 50,
+        offset: 0,
         min_score: 0.8,
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
 
     let result = req.validate();
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_query_request_project_filter_combines_project_and_projects() {
+    let mut req: QueryRequest = serde_json::from_value(serde_json::json!({
+        "query": "test query",
+        "project": "beta",
+        "projects": ["alpha", "beta"],
+    }))
+    .unwrap();
+    assert_eq!(req.project_filter(), vec!["alpha", "beta"]);
+
+    req.projects.push(String::new());
+    assert_eq!(req.validate().unwrap_err(), "project name cannot be empty");
+
+    req.project = None;
+    req.projects.clear();
+    assert!(req.project_filter().is_empty());
+}
+
 #[test]
 fn test_advanced_search_request_validate_empty_file_extension() {
     let req = AdvancedSearchRequest {
@@ -332,6 +636,7 @@ fn test_advanced_search_request_validate_empty_file_extension() {
         file_extensions: vec!["".to_string()],
         languages: vec![],
         path_patterns: vec![],
+        symbol_kinds: vec![],
     };
 
     let result = req.validate();
@@ -354,6 +659,7 @@ fn test_advanced_search_request_validate_file_extension_too_long() {
         file_extensions: vec!["a".repeat(25)],
         languages: vec![],
         path_patterns: vec![],
+        symbol_kinds: vec![],
     };
 
     let result = req.validate();
@@ -372,6 +678,7 @@ fn test_advanced_search_request_validate_empty_language() {
         file_extensions: vec![],
         languages: vec!["".to_string()],
         path_patterns: vec![],
+        symbol_kinds: vec![],
     };
 
     let result = req.validate();
@@ -394,6 +701,7 @@ fn test_advanced_search_request_validate_language_too_long() {
         file_extensions: vec![],
         languages: vec!["a".repeat(60)],
         path_patterns: vec![],
+        symbol_kinds: vec![],
     };
 
     let result = req.validate();
@@ -412,6 +720,7 @@ fn test_advanced_search_request_validate_valid() {
         file_extensions: vec!["rs".to_string(), "toml".to_string()],
         languages: vec!["Rust".to_string()],
         path_patterns: vec!["src/**".to_string()],
+        symbol_kinds: vec![],
     };
 
     let result = req.validate();
@@ -432,6 +741,8 @@ fn test_search_git_history_request_validate_empty_query() {
         since: None,
         until: None,
         file_pattern: None,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = req.validate();
@@ -453,6 +764,8 @@ fn test_search_git_history_request_validate_query_too_long() {
         since: None,
         until: None,
         file_pattern: None,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = req.validate();
@@ -474,6 +787,8 @@ fn test_search_git_history_request_validate_nonexistent_path() {
         since: None,
         until: None,
         file_pattern: None,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = req.validate();
@@ -495,6 +810,8 @@ fn test_search_git_history_request_validate_min_score_out_of_range() {
         since: None,
         until: None,
         file_pattern: None,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = req.validate();
@@ -516,6 +833,8 @@ fn test_search_git_history_request_validate_limit_too_large() {
         since: None,
         until: None,
         file_pattern: None,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = req.validate();
@@ -537,6 +856,8 @@ fn test_search_git_history_request_validate_max_commits_too_large() {
         since: None,
         until: None,
         file_pattern: None,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = req.validate();
@@ -558,6 +879,8 @@ fn test_search_git_history_request_validate_valid() {
         since: Some("2024-01-01".to_string()),
         until: Some("2024-12-31".to_string()),
         file_pattern: Some("src/**".to_string()),
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let result = req.validate();
@@ -570,6 +893,7 @@ fn test_search_git_history_request_validate_valid() {
 fn test_query_response_serialization() {
     let response = QueryResponse {
         results: vec![SearchResult {
+            chunk_id: "test.rs:1".to_string(),
             file_path: "test.rs".to_string(),
             root_path: None,
             content: "test content".to_string(),
@@ -580,10 +904,24 @@ fn test_query_response_serialization() {
             end_line: 10,
             language: "Rust".to_string(),
             project: None,
+            symbol_kind: None,
+            matched_via: MatchSource::Vector,
+            highlights: Vec::new(),
+            binary: false,
+            file_size: None,
+            ranking: None,
+            branch: None,
+            commit_date: None,
+            indexed_at: None,
+            relations_precision: None,
+            context: None,
         }],
         duration_ms: 100,
         threshold_used: 0.7,
         threshold_lowered: false,
+        truncated: false,
+        has_more: false,
+        summary: None,
     };
 
     let json = serde_json::to_string(&response).unwrap();
@@ -669,6 +1007,7 @@ fn test_advanced_search_request_serialization() {
         file_extensions: vec!["rs".to_string(), "toml".to_string()],
         languages: vec!["Rust".to_string()],
         path_patterns: vec!["src/**".to_string()],
+        symbol_kinds: vec![],
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -683,6 +1022,23 @@ fn test_advanced_search_request_serialization() {
     assert_eq!(request.path_patterns, deserialized.path_patterns);
 }
 
+#[test]
+fn test_advanced_search_request_symbol_kinds_from_json() {
+    let req: AdvancedSearchRequest =
+        serde_json::from_str(r#"{"query": "test", "symbol_kinds": ["struct", "enum_variant"]}"#)
+            .unwrap();
+    assert_eq!(
+        req.symbol_kinds,
+        vec![
+            crate::relations::SymbolKind::Struct,
+            crate::relations::SymbolKind::EnumVariant
+        ]
+    );
+
+    let req: AdvancedSearchRequest = serde_json::from_str(r#"{"query": "test"}"#).unwrap();
+    assert!(req.symbol_kinds.is_empty());
+}
+
 #[test]
 fn test_search_git_history_request_serialization() {
     let request = SearchGitHistoryRequest {
@@ -697,6 +1053,8 @@ fn test_search_git_history_request_serialization() {
         since: Some("2024-01-01".to_string()),
         until: Some("2024-12-31".to_string()),
         file_pattern: Some("src/**".to_string()),
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
 
     let json = serde_json::to_string(&request).unwrap();
@@ -728,6 +1086,9 @@ fn test_git_search_result_serialization() {
         keyword_score: Some(0.88),
         files_changed: vec!["src/main.rs".to_string(), "README.md".to_string()],
         diff_snippet: "diff --git a/src/main.rs".to_string(),
+        branch: None,
+        kind: GitEntryKind::Commit,
+        tag_name: None,
     };
 
     let json = serde_json::to_string(&result).unwrap();
@@ -759,6 +1120,9 @@ fn test_search_git_history_response_serialization() {
             keyword_score: Some(0.88),
             files_changed: vec!["src/main.rs".to_string()],
             diff_snippet: "diff --git a/src/main.rs".to_string(),
+            branch: None,
+            kind: GitEntryKind::Commit,
+            tag_name: None,
         }],
         commits_indexed: 10,
         total_cached_commits: 50,
@@ -786,9 +1150,17 @@ fn test_query_request_min_score_boundary_values() {
         query: "test".to_string(),
         path: None,
         project: None,
+        projects: vec![],
         limit: default_limit(),
+        offset: 0,
         min_score: 0.0,
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
     assert!(req.validate().is_ok());
 
@@ -797,9 +1169,17 @@ fn test_query_request_min_score_boundary_values() {
         query: "test".to_string(),
         path: None,
         project: None,
+        projects: vec![],
         limit: default_limit(),
+        offset: 0,
         min_score: 1.0,
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
     assert!(req.validate().is_ok());
 }
@@ -813,6 +1193,7 @@ fn test_index_request_max_file_size_boundary() {
         include_patterns: vec![],
         exclude_patterns: vec![],
         max_file_size: 100_000_000,
+        model_name: None,
     };
     assert!(req.validate().is_ok());
 }
@@ -824,9 +1205,17 @@ fn test_query_request_limit_boundary() {
         query: "test".to_string(),
         path: None,
         project: None,
+        projects: vec![],
         limit: 1000,
+        offset: 0,
         min_score: default_min_score(),
         hybrid: true,
+        exclude_files: vec![],
+        include_signature: false,
+        include_summary: false,
+        include_relations_precision: false,
+        context_lines: 0,
+        include_git_commits: false,
     };
     assert!(req.validate().is_ok());
 }
@@ -846,6 +1235,8 @@ fn test_search_git_history_request_max_commits_boundary() {
         since: None,
         until: None,
         file_pattern: None,
+        kind: GitEntryKind::Commit,
+        reset: false,
     };
     assert!(req.validate().is_ok());
 }
@@ -876,3 +1267,25 @@ fn test_default_functions() {
     assert_eq!(default_git_path(), ".");
     assert_eq!(default_max_commits(), 10);
 }
+
+#[test]
+fn test_delete_filter_matches_all_set_criteria() {
+    assert!(DeleteFilter::default().is_empty());
+
+    let filter = DeleteFilter {
+        path_prefix: Some("src/".to_string()),
+        indexed_before: Some(100),
+        ..Default::default()
+    };
+    assert!(!filter.is_empty());
+    assert!(filter.matches("src/lib.rs", "Rust", None, 99));
+    assert!(!filter.matches("src/lib.rs", "Rust", None, 100));
+    assert!(!filter.matches("tests/lib.rs", "Rust", None, 99));
+
+    let filter = DeleteFilter {
+        project: Some("app".to_string()),
+        ..Default::default()
+    };
+    assert!(filter.matches("lib.rs", "Rust", Some("app"), 0));
+    assert!(!filter.matches("lib.rs", "Rust", None, 0));
+}
@@ -8,33 +8,169 @@
 //!
 //! Future refactoring could extract search logic into traits if needed.
 
-use crate::bm25_search::BM25Search;
+use crate::bm25_search::{
+    AnalyzerMismatch, BM25Search, DEFAULT_BM25_ANALYZER, DEFAULT_STALE_LOCK_TIMEOUT,
+    DEFAULT_WRITER_LOCK_TIMEOUT,
+};
 use crate::glob_utils;
-use crate::types::{ChunkMetadata, SearchResult};
-use crate::vector_db::{DatabaseStats, VectorDatabase};
+use crate::telemetry::{self, PhaseSpans};
+use crate::types::{
+    ChunkMetadata, DeleteFilter, MatchSource, ProjectSummary, RankingDetail, SearchResult,
+};
+use crate::vector_db::{
+    DatabaseStats, DeletedChunks, DocBoost, GIT_HISTORY_LANGUAGES, HYBRID_CANDIDATE_FACTOR,
+    OptimizeStats, VectorDatabase, summarize_projects,
+};
 use anyhow::{Context, Result};
 use arrow_array::{
-    Array, FixedSizeListArray, Float32Array, RecordBatch, RecordBatchIterator, StringArray,
-    UInt32Array, types::Float32Type,
+    Array, BooleanArray, FixedSizeListArray, Float32Array, Int64Array, RecordBatch,
+    RecordBatchIterator, StringArray, UInt32Array, UInt64Array, types::Float32Type,
 };
 use arrow_schema::{DataType, Field, Schema};
 use futures::stream::TryStreamExt;
-use lancedb::Table;
 use lancedb::connection::Connection;
-use lancedb::query::{ExecutableQuery, QueryBase};
+use lancedb::index::Index;
+use lancedb::index::vector::IvfPqIndexBuilder;
+use lancedb::query::{ExecutableQuery, QueryBase, VectorQuery};
+use lancedb::table::{NewColumnTransform, OptimizeAction};
+use lancedb::{DistanceType, Table};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use stats::{StatsCounters, StatsState};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::Duration;
+
+/// Table holding embeddings produced by the configured model
+const DEFAULT_TABLE_NAME: &str = "code_embeddings";
+
+/// Chunk ids per `id IN (...)` delete predicate
+const DELETE_BATCH_SIZE: usize = 500;
+
+/// Share of rows added since the ANN index was trained, relative to the rows
+/// it covers, at which `flush` retrains it
+///
+/// Rows added after training are still found (by an exhaustive scan of the
+/// unindexed part), but the partitions drift from the data as the table grows.
+const ANN_RETRAIN_FRACTION: f64 = 0.2;
+
+/// Query-time recall/speed trade-off for tables with an ANN index
+///
+/// Tables without a vector index are searched exhaustively and ignore these.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AnnParams {
+    /// IVF partitions probed per query; fewer is faster but may miss neighbours
+    /// (LanceDB's default when unset)
+    pub nprobes: Option<usize>,
+    /// Re-rank `limit * refine_factor` candidates with exact distances, recovering
+    /// accuracy lost to quantization at some extra cost (no re-ranking when unset)
+    pub refine_factor: Option<u32>,
+}
+
+/// Distance vector queries and the ANN index compare embeddings with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DistanceMetric {
+    /// Squared Euclidean distance, scored as `1 / (1 + distance)`
+    #[default]
+    L2,
+    /// Cosine distance, scored as the cosine similarity clamped to 0-1
+    Cosine,
+    /// Dot product distance, scored as the dot product clamped to 0-1
+    /// (equal to cosine for normalized embeddings)
+    Dot,
+}
+
+impl DistanceMetric {
+    fn distance_type(self) -> DistanceType {
+        match self {
+            Self::L2 => DistanceType::L2,
+            Self::Cosine => DistanceType::Cosine,
+            Self::Dot => DistanceType::Dot,
+        }
+    }
+
+    /// Similarity score (0.0 to 1.0) of a `_distance` under this metric
+    pub fn score(self, distance: f32) -> f32 {
+        match self {
+            Self::L2 => 1.0 / (1.0 + distance),
+            // Cosine distance is 1 - similarity, and dot distance 1 - product
+            Self::Cosine | Self::Dot => (1.0 - distance).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Name of the ANN index built for this metric
+    ///
+    /// Indexes are named after their metric so one built for another metric is
+    /// recognized and rebuilt; L2 keeps LanceDB's default name, which indexes
+    /// built before the metric was configurable carry.
+    fn index_name(self) -> &'static str {
+        match self {
+            Self::L2 => "vector_idx",
+            Self::Cosine => "vector_cosine_idx",
+            Self::Dot => "vector_dot_idx",
+        }
+    }
+}
+
+/// When a table gets an ANN (IVF_PQ) index on its vector column, and its shape
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AnnIndexParams {
+    /// Rows the table needs before the index is built (0 = never)
+    pub min_rows: usize,
+    /// IVF partitions (LanceDB's default when unset)
+    pub num_partitions: Option<u32>,
+    /// PQ sub-vectors, dividing the vector dimension (LanceDB's default when unset)
+    pub num_sub_vectors: Option<u32>,
+}
+
+impl AnnIndexParams {
+    fn index(&self, distance: DistanceMetric) -> Index {
+        let mut builder = IvfPqIndexBuilder::default().distance_type(distance.distance_type());
+        if let Some(num_partitions) = self.num_partitions {
+            builder = builder.num_partitions(num_partitions);
+        }
+        if let Some(num_sub_vectors) = self.num_sub_vectors {
+            builder = builder.num_sub_vectors(num_sub_vectors);
+        }
+        Index::IvfPq(builder)
+    }
+}
 
 /// LanceDB vector database implementation (embedded, no server required)
 /// Includes BM25 hybrid search support using Tantivy with per-project indexes
 pub struct LanceVectorDB {
     connection: Connection,
-    table_name: String,
+    /// Logical table name, stable across warm reindexes
+    base_table_name: String,
+    /// Physical table currently serving reads and writes (replaced by warm reindexes)
+    table_name: RwLock<String>,
     db_path: String,
     /// Per-project BM25 search indexes for keyword matching
     /// Key: hashed root path, Value: BM25Search instance
     bm25_indexes: Arc<RwLock<HashMap<String, BM25Search>>>,
+    /// Emits a "fuse" span around hybrid result fusion when enabled
+    phase_spans: PhaseSpans,
+    /// ANN tuning applied to every vector query
+    ann_params: AnnParams,
+    /// When to build and retrain the ANN vector index
+    ann_index: AnnIndexParams,
+    /// Distance vector queries rank by and the ANN index is built for
+    distance: DistanceMetric,
+    /// Analyzer BM25 indexes tokenize content and queries with
+    bm25_analyzer: String,
+    /// How long BM25 writes wait for a writer in another process
+    bm25_writer_lock_timeout: Duration,
+    /// Heartbeat age after which BM25 lock files are reclaimed (`None` = never)
+    bm25_stale_lock_timeout: Option<Duration>,
+    /// Score multiplier for documentation files in hybrid search
+    doc_boost: DocBoost,
+    /// Running chunk counts of the active table, backing `get_statistics`
+    stats: Mutex<StatsState>,
+    /// Held shared by writes to the active table, and exclusively while a
+    /// warm reindex copies it into a staging table and swaps that in
+    write_lock: tokio::sync::RwLock<()>,
 }
 
 impl LanceVectorDB {
@@ -46,7 +182,16 @@ impl LanceVectorDB {
 
     /// Create a new LanceDB instance with custom path
     pub async fn with_path(db_path: &str) -> Result<Self> {
-        tracing::info!("Connecting to LanceDB at: {}", db_path);
+        Self::with_path_and_table(db_path, DEFAULT_TABLE_NAME).await
+    }
+
+    /// Create a new LanceDB instance storing embeddings in a specific table
+    pub async fn with_path_and_table(db_path: &str, table_name: &str) -> Result<Self> {
+        tracing::info!(
+            "Connecting to LanceDB at: {} (table '{}')",
+            db_path,
+            table_name
+        );
 
         let connection = lancedb::connect(db_path)
             .execute()
@@ -57,14 +202,181 @@ impl LanceVectorDB {
         // BM25 indexes are created on-demand per root path
         let bm25_indexes = Arc::new(RwLock::new(HashMap::new()));
 
+        // A previous warm reindex may have moved the data to another physical table
+        let active_table = staging::read_active_table(db_path, table_name)
+            .unwrap_or_else(|| table_name.to_string());
+        let stats = StatsState {
+            counters: StatsCounters::load(&StatsCounters::path(db_path, &active_table)),
+            unsaved: false,
+        };
+
         Ok(Self {
             connection,
-            table_name: "code_embeddings".to_string(),
+            base_table_name: table_name.to_string(),
+            table_name: RwLock::new(active_table),
             db_path: db_path.to_string(),
             bm25_indexes,
+            phase_spans: PhaseSpans::default(),
+            ann_params: AnnParams::default(),
+            ann_index: AnnIndexParams::default(),
+            distance: DistanceMetric::default(),
+            bm25_analyzer: DEFAULT_BM25_ANALYZER.to_string(),
+            bm25_writer_lock_timeout: DEFAULT_WRITER_LOCK_TIMEOUT,
+            bm25_stale_lock_timeout: Some(DEFAULT_STALE_LOCK_TIMEOUT),
+            doc_boost: DocBoost::default(),
+            stats: Mutex::new(stats),
+            write_lock: tokio::sync::RwLock::new(()),
         })
     }
 
+    /// Enable or disable pipeline phase spans
+    pub fn with_phase_spans(mut self, phase_spans: PhaseSpans) -> Self {
+        self.phase_spans = phase_spans;
+        self
+    }
+
+    /// Set the ANN recall/speed trade-off used by vector queries
+    pub fn with_ann_params(mut self, ann_params: AnnParams) -> Self {
+        self.ann_params = ann_params;
+        self
+    }
+
+    /// Build an ANN vector index once tables reach `ann_index.min_rows` rows
+    pub fn with_ann_index(mut self, ann_index: AnnIndexParams) -> Self {
+        self.ann_index = ann_index;
+        self
+    }
+
+    /// Set the distance vector queries rank by
+    ///
+    /// An existing ANN index built for another metric is rebuilt when the
+    /// table is initialized.
+    pub fn with_distance(mut self, distance: DistanceMetric) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Set the analyzer BM25 indexes are built and queried with
+    ///
+    /// Existing indexes built with another analyzer are rebuilt from the stored
+    /// content the next time their root is written to.
+    pub fn with_bm25_analyzer(mut self, analyzer: impl Into<String>) -> Self {
+        self.bm25_analyzer = analyzer.into();
+        self
+    }
+
+    /// Set how long BM25 writes wait for a writer in another process to finish
+    pub fn with_bm25_writer_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.bm25_writer_lock_timeout = timeout;
+        self
+    }
+
+    /// Set the heartbeat age after which BM25 lock files of a previous writer
+    /// are removed, or `None` to fail writes to a locked index instead
+    pub fn with_bm25_stale_lock_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.bm25_stale_lock_timeout = timeout;
+        self
+    }
+
+    /// Boost the fused scores of documentation files in hybrid search
+    pub fn with_doc_boost(mut self, doc_boost: DocBoost) -> Self {
+        self.doc_boost = doc_boost;
+        self
+    }
+
+    /// Vector search over `table`, tuned by the configured ANN parameters
+    fn vector_query(
+        &self,
+        table: &Table,
+        query_vector: Vec<f32>,
+        limit: usize,
+    ) -> Result<VectorQuery> {
+        let mut query = table
+            .vector_search(query_vector)
+            .context("Failed to create vector search")?
+            .distance_type(self.distance.distance_type())
+            .limit(limit);
+
+        if let Some(nprobes) = self.ann_params.nprobes {
+            query = query.nprobes(nprobes);
+        }
+        if let Some(refine_factor) = self.ann_params.refine_factor {
+            query = query.refine_factor(refine_factor);
+        }
+
+        Ok(query)
+    }
+
+    /// Build the ANN vector index once the table has enough rows
+    ///
+    /// With `retrain`, an existing index is also rebuilt once the rows added
+    /// since it was trained reach [`ANN_RETRAIN_FRACTION`] of the rows it covers.
+    /// An index built for another distance metric is always rebuilt.
+    async fn maintain_vector_index(&self, table: &Table, retrain: bool) -> Result<()> {
+        if self.ann_index.min_rows == 0 {
+            return Ok(());
+        }
+
+        let index = table
+            .list_indices()
+            .await
+            .context("Failed to list table indices")?
+            .into_iter()
+            .find(|index| index.columns == ["vector"]);
+
+        match index {
+            None => {
+                let rows = table
+                    .count_rows(None)
+                    .await
+                    .context("Failed to count rows")?;
+                if rows < self.ann_index.min_rows {
+                    return Ok(());
+                }
+                tracing::info!("Building ANN vector index over {} rows", rows);
+            }
+            Some(index) if index.name != self.distance.index_name() => {
+                tracing::info!(
+                    "Rebuilding ANN vector index '{}' for {:?} distance",
+                    index.name,
+                    self.distance
+                );
+                table
+                    .drop_index(&index.name)
+                    .await
+                    .context("Failed to drop ANN vector index")?;
+            }
+            Some(_) if !retrain => return Ok(()),
+            Some(index) => {
+                let Some(stats) = table
+                    .index_stats(&index.name)
+                    .await
+                    .context("Failed to read vector index statistics")?
+                else {
+                    return Ok(());
+                };
+                if (stats.num_unindexed_rows as f64)
+                    < stats.num_indexed_rows as f64 * ANN_RETRAIN_FRACTION
+                {
+                    return Ok(());
+                }
+                tracing::info!(
+                    "Retraining ANN vector index ({} indexed, {} new rows)",
+                    stats.num_indexed_rows,
+                    stats.num_unindexed_rows
+                );
+            }
+        }
+
+        table
+            .create_index(&["vector"], self.ann_index.index(self.distance))
+            .name(self.distance.index_name().to_string())
+            .replace(true)
+            .execute()
+            .await
+            .context("Failed to build ANN vector index")
+    }
+
     /// Get default database path (public for CLI version info)
     pub fn default_lancedb_path() -> String {
         crate::paths::PlatformPaths::default_lancedb_path()
@@ -81,10 +393,125 @@ impl LanceVectorDB {
         format!("{:x}", result)[..16].to_string()
     }
 
+    /// Physical table currently serving reads and writes
+    fn table_name(&self) -> String {
+        match self.table_name.read() {
+            Ok(name) => name.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Running chunk counts of the active table
+    fn lock_stats(&self) -> MutexGuard<'_, StatsState> {
+        self.stats
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Write the running chunk counts to their sidecar file if they changed
+    fn save_stats(&self) {
+        let mut stats = self.lock_stats();
+        if !stats.unsaved {
+            return;
+        }
+        if let Some(ref counters) = stats.counters {
+            let path = StatsCounters::path(&self.db_path, &self.table_name());
+            if let Err(e) = counters.save(&path) {
+                tracing::warn!("Failed to save chunk statistics: {:#}", e);
+                return;
+            }
+        }
+        stats.unsaved = false;
+    }
+
+    /// Count chunks per language and file by scanning the whole table
+    async fn scan_statistics(table: &Table) -> Result<StatsCounters> {
+        let stream = table
+            .query()
+            .select(lancedb::query::Select::Columns(vec![
+                "language".to_string(),
+                "file_path".to_string(),
+            ]))
+            .execute()
+            .await
+            .context("Failed to query languages")?;
+
+        let query_result: Vec<RecordBatch> = stream
+            .try_collect()
+            .await
+            .context("Failed to collect language data")?;
+
+        let mut counters = StatsCounters::default();
+        for batch in query_result {
+            let language_array = batch
+                .column_by_name("language")
+                .context("Missing language column")?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .context("Invalid language type")?;
+            let file_path_array = batch
+                .column_by_name("file_path")
+                .context("Missing file_path column")?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .context("Invalid file_path type")?;
+
+            for i in 0..batch.num_rows() {
+                counters.add(language_array.value(i), file_path_array.value(i), 1);
+            }
+        }
+        Ok(counters)
+    }
+
     /// Get the BM25 index path for a specific root path
     fn bm25_path_for_root(&self, root_path: &str) -> String {
-        let hash = Self::hash_root_path(root_path);
-        format!("{}/bm25_{}", self.db_path, hash)
+        self.bm25_path(&self.table_name(), &Self::hash_root_path(root_path))
+    }
+
+    /// BM25 index path for a table and hashed root path
+    ///
+    /// Non-default tables get their own BM25 directories, since BM25 document
+    /// ids are row offsets into the table they were stored with.
+    fn bm25_path(&self, table_name: &str, root_hash: &str) -> String {
+        if table_name == DEFAULT_TABLE_NAME {
+            format!("{}/bm25_{}", self.db_path, root_hash)
+        } else {
+            format!("{}/bm25_{}_{}", self.db_path, table_name, root_hash)
+        }
+    }
+
+    /// Paths of this table's BM25 indexes on disk, one per indexed root
+    fn keyword_index_paths(&self) -> Result<Vec<std::path::PathBuf>> {
+        // See `bm25_path`
+        let table_name = self.table_name();
+        let prefix = if table_name == DEFAULT_TABLE_NAME {
+            "bm25_".to_string()
+        } else {
+            format!("bm25_{}_", table_name)
+        };
+
+        let entries = match std::fs::read_dir(&self.db_path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to read database directory {}", self.db_path)
+                });
+            }
+        };
+
+        let mut paths = Vec::new();
+        for entry in entries {
+            let entry = entry.context("Failed to read database directory entry")?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let is_root_index = name.strip_prefix(&prefix).is_some_and(|hash| {
+                hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit())
+            });
+            if is_root_index {
+                paths.push(entry.path());
+            }
+        }
+        Ok(paths)
     }
 
     /// Get or create a BM25 index for a specific root path
@@ -119,14 +546,197 @@ impl LanceVectorDB {
             bm25_path
         );
 
-        let bm25_index = BM25Search::new(&bm25_path)
-            .with_context(|| format!("Failed to initialize BM25 index for root: {}", root_path))?;
+        let bm25_index = BM25Search::with_analyzer(&bm25_path, &self.bm25_analyzer)
+            .with_context(|| format!("Failed to initialize BM25 index for root: {}", root_path))?
+            .with_writer_lock_timeout(self.bm25_writer_lock_timeout)
+            .with_stale_lock_timeout(self.bm25_stale_lock_timeout);
 
         indexes.insert(hash, bm25_index);
 
         Ok(())
     }
 
+    /// Run `write` on the loaded BM25 indexes on a blocking thread
+    ///
+    /// Opening a BM25 writer may wait up to `bm25.writer_lock_timeout` for a
+    /// writer in another process, which must not stall the async runtime.
+    async fn write_bm25<T: Send + 'static>(
+        &self,
+        write: impl FnOnce(&HashMap<String, BM25Search>) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let bm25_indexes = self.bm25_indexes.clone();
+        tokio::task::spawn_blocking(move || {
+            let bm25_indexes = bm25_indexes
+                .read()
+                .map_err(|e| anyhow::anyhow!("Failed to acquire BM25 read lock: {}", e))?;
+            write(&bm25_indexes)
+        })
+        .await
+        .context("Failed to spawn blocking task for BM25 write")?
+    }
+
+    /// Commit the loaded BM25 indexes and close their writers
+    async fn flush_bm25(&self) -> Result<()> {
+        self.write_bm25(|bm25_indexes| {
+            for bm25 in bm25_indexes.values() {
+                bm25.flush().context("Failed to flush BM25 index")?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Get or create the BM25 index for a root path, rebuilding it from the
+    /// stored content if it was built with another analyzer
+    async fn ensure_bm25(&self, root_path: &str) -> Result<()> {
+        match self.get_or_create_bm25(root_path) {
+            Err(e) if e.downcast_ref::<AnalyzerMismatch>().is_some() => {
+                tracing::warn!("{:#}; rebuilding it from stored content", e);
+                self.rebuild_bm25(root_path).await
+            }
+            result => result,
+        }
+    }
+
+    /// Recreate the BM25 index of a root path from the content stored in the table
+    async fn rebuild_bm25(&self, root_path: &str) -> Result<()> {
+        let bm25_path = self.bm25_path_for_root(root_path);
+        if let Err(e) = std::fs::remove_dir_all(&bm25_path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            return Err(e).with_context(|| format!("Failed to remove BM25 index at {}", bm25_path));
+        }
+        let bm25_index = BM25Search::with_analyzer(&bm25_path, &self.bm25_analyzer)
+            .with_context(|| format!("Failed to recreate BM25 index for root: {}", root_path))?
+            .with_writer_lock_timeout(self.bm25_writer_lock_timeout)
+            .with_stale_lock_timeout(self.bm25_stale_lock_timeout);
+
+        let table = self.get_table().await?;
+        let mut stream = table
+            .query()
+            .select(lancedb::query::Select::Columns(vec![
+                "id".to_string(),
+                "file_path".to_string(),
+                "root_path".to_string(),
+                "content".to_string(),
+            ]))
+            .execute()
+            .await
+            .context("Failed to query stored content")?;
+
+        let mut documents = Vec::new();
+        while let Some(batch) = stream
+            .try_next()
+            .await
+            .context("Failed to read stored content")?
+        {
+            let string_column = |name: &str| -> Result<&StringArray> {
+                batch
+                    .column_by_name(name)
+                    .with_context(|| format!("Missing {} column", name))?
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .with_context(|| format!("Invalid {} type", name))
+            };
+            let ids = string_column("id")?;
+            let file_paths = string_column("file_path")?;
+            let contents = string_column("content")?;
+            let root_paths = string_column("root_path").ok();
+
+            for idx in 0..batch.num_rows() {
+                if optional_string(root_paths, idx).as_deref() == Some(root_path) {
+                    documents.push((
+                        row_bm25_id(ids.value(idx)),
+                        contents.value(idx).to_string(),
+                        file_paths.value(idx).to_string(),
+                    ));
+                }
+            }
+        }
+
+        let count = documents.len();
+        let bm25_index = tokio::task::spawn_blocking(move || {
+            bm25_index
+                .add_documents(documents)
+                .context("Failed to add documents to rebuilt BM25 index")?;
+            Ok::<_, anyhow::Error>(bm25_index)
+        })
+        .await
+        .context("Failed to spawn blocking task for BM25 write")??;
+
+        self.bm25_indexes
+            .write()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire BM25 write lock: {}", e))?
+            .insert(Self::hash_root_path(root_path), bm25_index);
+
+        tracing::info!(
+            "Rebuilt BM25 index for root '{}' with analyzer '{}' ({} chunks)",
+            root_path,
+            self.bm25_analyzer,
+            count
+        );
+        Ok(())
+    }
+
+    /// Rows whose chunk id is one of `ids`, and that match `filter` if given
+    async fn rows_by_id(
+        &self,
+        table: &Table,
+        ids: &[u64],
+        filter: Option<&str>,
+    ) -> Result<Vec<RecordBatch>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let id_list = ids
+            .iter()
+            .map(|id| format!("'{}'", id))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let condition = match filter {
+            Some(filter) => format!("id IN ({}) AND ({})", id_list, filter),
+            None => format!("id IN ({})", id_list),
+        };
+        table
+            .query()
+            .only_if(condition)
+            .execute()
+            .await
+            .context("Failed to query rows by id")?
+            .try_collect()
+            .await
+            .context("Failed to collect rows by id")
+    }
+
+    /// Fill in the keyword highlights of results matched by keyword search
+    ///
+    /// Each result is tokenized by the BM25 index of its root, falling back to
+    /// any index for results without a root.
+    fn add_keyword_highlights(&self, query_text: &str, results: &mut [SearchResult]) -> Result<()> {
+        let bm25_indexes = self
+            .bm25_indexes
+            .read()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire BM25 read lock: {}", e))?;
+
+        for result in results
+            .iter_mut()
+            .filter(|result| result.matched_via != MatchSource::Vector)
+        {
+            let bm25 = result
+                .root_path
+                .as_deref()
+                .and_then(|root| bm25_indexes.get(&Self::hash_root_path(root)))
+                .or_else(|| bm25_indexes.values().next());
+            if let Some(bm25) = bm25 {
+                result.highlights = bm25
+                    .highlights(query_text, &result.content)
+                    .context("Failed to compute keyword highlights")?;
+            }
+        }
+        Ok(())
+    }
+
     /// Create schema for the embeddings table
     fn create_schema(dimension: usize) -> Arc<Schema> {
         Arc::new(Schema::new(vec![
@@ -149,13 +759,160 @@ impl LanceVectorDB {
             Field::new("indexed_at", DataType::Utf8, false),
             Field::new("content", DataType::Utf8, false),
             Field::new("project", DataType::Utf8, true),
+            Field::new("symbol_kind", DataType::Utf8, true),
+            Field::new("binary", DataType::Boolean, true),
+            Field::new("file_size", DataType::UInt64, true),
+            Field::new("branch", DataType::Utf8, true),
+            Field::new("commit_date", DataType::Int64, true),
         ]))
     }
 
+    /// Bring a table created by an older version up to the current format
+    ///
+    /// Older tables lack the nullable columns introduced since, which would
+    /// make every new insert fail with a schema mismatch; existing rows get
+    /// nulls. Legacy row ids are then rewritten to chunk ids.
+    async fn migrate_table(&self, table: &Table) -> Result<()> {
+        let existing = table
+            .schema()
+            .await
+            .context("Failed to read table schema")?;
+
+        let missing: Vec<Field> = [
+            Field::new("symbol_kind", DataType::Utf8, true),
+            Field::new("binary", DataType::Boolean, true),
+            Field::new("file_size", DataType::UInt64, true),
+            Field::new("branch", DataType::Utf8, true),
+            Field::new("commit_date", DataType::Int64, true),
+        ]
+        .into_iter()
+        .filter(|field| existing.field_with_name(field.name()).is_err())
+        .collect();
+
+        if !missing.is_empty() {
+            tracing::info!(
+                "Adding {} missing column(s) to table '{}'",
+                missing.len(),
+                self.table_name()
+            );
+
+            table
+                .add_columns(
+                    NewColumnTransform::AllNulls(Arc::new(Schema::new(missing))),
+                    None,
+                )
+                .await
+                .context("Failed to add missing columns to table")?;
+        }
+
+        self.migrate_legacy_ids(table).await
+    }
+
+    /// Rewrite the `file_path:start_line` ids of rows stored before chunk ids
+    ///
+    /// Keyword hits are looked up by chunk id, so such rows could never be
+    /// returned by keyword search. They are re-added under their chunk id, and
+    /// the BM25 indexes of their roots, whose documents were numbered by row
+    /// offset, are rebuilt from the migrated rows.
+    async fn migrate_legacy_ids(&self, table: &Table) -> Result<()> {
+        // Chunk ids are plain numbers, legacy ids always contain a colon
+        const LEGACY_IDS: &str = "id LIKE '%:%'";
+
+        let batches: Vec<RecordBatch> = table
+            .query()
+            .only_if(LEGACY_IDS)
+            .execute()
+            .await
+            .context("Failed to query rows with legacy ids")?
+            .try_collect()
+            .await
+            .context("Failed to collect rows with legacy ids")?;
+        let count: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        if count == 0 {
+            return Ok(());
+        }
+
+        tracing::info!(
+            "Migrating {} legacy row id(s) in table '{}'",
+            count,
+            self.table_name()
+        );
+
+        let mut roots = BTreeSet::new();
+        let mut migrated = Vec::with_capacity(batches.len());
+        for batch in batches {
+            let string_column = |name: &str| -> Result<&StringArray> {
+                batch
+                    .column_by_name(name)
+                    .with_context(|| format!("Missing {} column", name))?
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .with_context(|| format!("Invalid {} type", name))
+            };
+            let line_column = |name: &str| -> Result<&UInt32Array> {
+                batch
+                    .column_by_name(name)
+                    .with_context(|| format!("Missing {} column", name))?
+                    .as_any()
+                    .downcast_ref::<UInt32Array>()
+                    .with_context(|| format!("Invalid {} type", name))
+            };
+            let file_paths = string_column("file_path")?;
+            let root_paths = string_column("root_path").ok();
+            let file_hashes = string_column("file_hash")?;
+            let start_lines = line_column("start_line")?;
+            let end_lines = line_column("end_line")?;
+
+            let ids: Vec<String> = (0..batch.num_rows())
+                .map(|idx| {
+                    let root_path = optional_string(root_paths, idx);
+                    let id = row_chunk_id(
+                        root_path.as_deref(),
+                        file_paths.value(idx),
+                        start_lines.value(idx) as usize,
+                        end_lines.value(idx) as usize,
+                        file_hashes.value(idx),
+                    );
+                    roots.extend(root_path);
+                    id.to_string()
+                })
+                .collect();
+
+            let ids: Arc<dyn Array> = Arc::new(StringArray::from(ids));
+            let schema = batch.schema();
+            let mut columns = batch.columns().to_vec();
+            columns[schema.index_of("id")?] = ids;
+            migrated.push(
+                RecordBatch::try_new(schema, columns)
+                    .context("Failed to build rows with migrated ids")?,
+            );
+        }
+
+        // Add before deleting, so a failed migration never loses rows
+        let schema = migrated[0].schema();
+        table
+            .add(Box::new(RecordBatchIterator::new(
+                migrated.into_iter().map(Ok),
+                schema,
+            )))
+            .execute()
+            .await
+            .context("Failed to add rows with migrated ids")?;
+        table
+            .delete(LEGACY_IDS)
+            .await
+            .context("Failed to delete rows with legacy ids")?;
+
+        for root_path in &roots {
+            self.rebuild_bm25(root_path).await?;
+        }
+        Ok(())
+    }
+
     /// Get or create table
     async fn get_table(&self) -> Result<Table> {
         self.connection
-            .open_table(&self.table_name)
+            .open_table(self.table_name())
             .execute()
             .await
             .context("Failed to open table")
@@ -168,7 +925,6 @@ impl LanceVectorDB {
         contents: Vec<String>,
         schema: Arc<Schema>,
     ) -> Result<RecordBatch> {
-        let num_rows = embeddings.len();
         let dimension = embeddings[0].len();
 
         // Create FixedSizeListArray for vectors
@@ -181,8 +937,9 @@ impl LanceVectorDB {
 
         // Create arrays for each field
         let id_array = StringArray::from(
-            (0..num_rows)
-                .map(|i| format!("{}:{}", metadata[i].file_path, metadata[i].start_line))
+            metadata
+                .iter()
+                .map(|m| chunk_id(m).to_string())
                 .collect::<Vec<_>>(),
         );
         let file_path_array = StringArray::from(
@@ -241,6 +998,24 @@ impl LanceVectorDB {
                 .map(|m| m.project.as_deref())
                 .collect::<Vec<_>>(),
         );
+        let symbol_kind_array = StringArray::from(
+            metadata
+                .iter()
+                .map(|m| m.symbol_kind.as_deref())
+                .collect::<Vec<_>>(),
+        );
+        let binary_array =
+            BooleanArray::from(metadata.iter().map(|m| Some(m.binary)).collect::<Vec<_>>());
+        let file_size_array =
+            UInt64Array::from(metadata.iter().map(|m| m.file_size).collect::<Vec<_>>());
+        let branch_array = StringArray::from(
+            metadata
+                .iter()
+                .map(|m| m.branch.as_deref())
+                .collect::<Vec<_>>(),
+        );
+        let commit_date_array =
+            Int64Array::from(metadata.iter().map(|m| m.commit_date).collect::<Vec<_>>());
 
         RecordBatch::try_new(
             schema,
@@ -257,145 +1032,49 @@ impl LanceVectorDB {
                 Arc::new(indexed_at_array),
                 Arc::new(content_array),
                 Arc::new(project_array),
+                Arc::new(symbol_kind_array),
+                Arc::new(binary_array),
+                Arc::new(file_size_array),
+                Arc::new(branch_array),
+                Arc::new(commit_date_array),
             ],
         )
         .context("Failed to create RecordBatch")
     }
-}
-
-#[async_trait::async_trait]
-impl VectorDatabase for LanceVectorDB {
-    async fn initialize(&self, dimension: usize) -> Result<()> {
-        tracing::info!(
-            "Initializing LanceDB with dimension {} at {}",
-            dimension,
-            self.db_path
-        );
 
-        // Check if table exists
-        let table_names = self
-            .connection
-            .table_names()
-            .execute()
-            .await
-            .context("Failed to list tables")?;
-
-        if table_names.contains(&self.table_name) {
-            tracing::info!("Table '{}' already exists", self.table_name);
-            return Ok(());
-        }
-
-        // Create empty table with schema
-        let schema = Self::create_schema(dimension);
-
-        // Create empty RecordBatch
-        let empty_batch = RecordBatch::new_empty(schema.clone());
-
-        // Need to wrap in iterator that returns Result<RecordBatch>
-        let batches =
-            RecordBatchIterator::new(vec![empty_batch].into_iter().map(Ok), schema.clone());
-
-        self.connection
-            .create_table(&self.table_name, Box::new(batches))
-            .execute()
-            .await
-            .context("Failed to create table")?;
-
-        tracing::info!("Created table '{}'", self.table_name);
-        Ok(())
-    }
-
-    async fn store_embeddings(
-        &self,
-        embeddings: Vec<Vec<f32>>,
-        metadata: Vec<ChunkMetadata>,
-        contents: Vec<String>,
-        root_path: &str,
-    ) -> Result<usize> {
-        if embeddings.is_empty() {
-            return Ok(0);
-        }
-
-        let dimension = embeddings[0].len();
-        let schema = Self::create_schema(dimension);
-
-        // Get current row count to use as starting ID for BM25
-        let table = self.get_table().await?;
-        let current_count = table.count_rows(None).await.unwrap_or(0) as u64;
-
-        let batch = Self::create_record_batch(
-            embeddings,
-            metadata.clone(),
-            contents.clone(),
-            schema.clone(),
-        )?;
-        let count = batch.num_rows();
-
-        let batches = RecordBatchIterator::new(vec![batch].into_iter().map(Ok), schema);
-
-        table
-            .add(Box::new(batches))
-            .execute()
-            .await
-            .context("Failed to add records to table")?;
-
-        // Ensure BM25 index exists for this root path
-        self.get_or_create_bm25(root_path)?;
-
-        // Add documents to per-project BM25 index with file_path for deletion tracking
-        let bm25_docs: Vec<_> = (0..count)
-            .map(|i| {
-                let id = current_count + i as u64;
-                (id, contents[i].clone(), metadata[i].file_path.clone())
-            })
-            .collect();
-
-        let hash = Self::hash_root_path(root_path);
-        let bm25_indexes = self
-            .bm25_indexes
-            .read()
-            .map_err(|e| anyhow::anyhow!("Failed to acquire BM25 read lock: {}", e))?;
-
-        if let Some(bm25) = bm25_indexes.get(&hash) {
-            bm25.add_documents(bm25_docs)
-                .context("Failed to add documents to BM25 index")?;
-        }
-        drop(bm25_indexes);
-
-        tracing::info!(
-            "Stored {} embeddings with BM25 indexing for root: {}",
-            count,
-            root_path
-        );
-        Ok(count)
-    }
-
-    async fn search(
+    /// Search like [`VectorDatabase::search`], keeping only chunks tagged
+    /// with one of `symbol_kinds` (all chunks when empty)
+    ///
+    /// The project, root, git history and symbol kind filters are part of the
+    /// vector query's WHERE clause, and keyword hits are checked against them
+    /// before fusion, so a selective filter still fills the limit.
+    #[allow(clippy::too_many_arguments)]
+    async fn search_where(
         &self,
         query_vector: Vec<f32>,
         query_text: &str,
         limit: usize,
         min_score: f32,
-        project: Option<String>,
+        projects: &[String],
         root_path: Option<String>,
         hybrid: bool,
+        include_git: bool,
+        symbol_kinds: &[String],
     ) -> Result<Vec<SearchResult>> {
         let table = self.get_table().await?;
+        let filter = search_filter(projects, root_path.as_deref(), include_git, symbol_kinds);
 
         if hybrid {
             // Hybrid search: combine vector and BM25 results with RRF
             // Get more results from each source for RRF to combine
-            let search_limit = limit * 3;
+            let search_limit = limit * HYBRID_CANDIDATE_FACTOR;
 
             // Vector search
-            let query = table
-                .vector_search(query_vector)
-                .context("Failed to create vector search")?
-                .limit(search_limit);
+            let query = self.vector_query(&table, query_vector, search_limit)?;
 
-            let stream = if let Some(ref project_name) = project {
+            let stream = if let Some(filter) = &filter {
                 query
-                    .only_if(format!("project = '{}'", project_name))
+                    .only_if(filter.as_str())
                     .execute()
                     .await
                     .context("Failed to execute search")?
@@ -403,38 +1082,50 @@ impl VectorDatabase for LanceVectorDB {
                 query.execute().await.context("Failed to execute search")?
             };
 
-            let results: Vec<RecordBatch> = stream
+            let mut results: Vec<RecordBatch> = stream
                 .try_collect()
                 .await
                 .context("Failed to collect search results")?;
 
-            // Build vector results with row-based IDs
+            // Build vector results keyed by chunk id
             let mut vector_results = Vec::new();
-            let mut row_offset = 0u64;
+            // Batch and row of every candidate row, by chunk id
+            let mut rows: HashMap<u64, (usize, usize)> = HashMap::new();
 
             // Store original scores for later reporting
             let mut original_scores: HashMap<u64, (f32, Option<f32>)> = HashMap::new();
+            // Rank and raw distance of vector candidates, for provenance and debug output
+            let mut vector_hits: HashMap<u64, (usize, f32)> = HashMap::new();
 
-            for batch in &results {
+            for (batch_idx, batch) in results.iter().enumerate() {
                 let distance_array = batch
                     .column_by_name("_distance")
                     .context("Missing _distance column")?
                     .as_any()
                     .downcast_ref::<Float32Array>()
                     .context("Invalid _distance type")?;
+                let id_array = batch
+                    .column_by_name("id")
+                    .context("Missing id column")?
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .context("Invalid id type")?;
 
                 for i in 0..batch.num_rows() {
                     let distance = distance_array.value(i);
-                    let score = 1.0 / (1.0 + distance);
-                    let id = row_offset + i as u64;
+                    let score = self.distance.score(distance);
+                    let id = row_bm25_id(id_array.value(i));
+                    if rows.insert(id, (batch_idx, i)).is_some() {
+                        continue; // Same chunk stored twice
+                    }
 
                     // For hybrid search, don't filter by min_score before RRF
                     // RRF will combine weak vector + strong keyword (or vice versa)
                     // Filtering happens after RRF based on the combined ranking
                     vector_results.push((id, score));
                     original_scores.insert(id, (score, None));
+                    vector_hits.insert(id, (vector_results.len(), distance));
                 }
-                row_offset += batch.num_rows() as u64;
             }
 
             // BM25 keyword search across all per-project indexes
@@ -463,131 +1154,225 @@ impl VectorDatabase for LanceVectorDB {
             }
             drop(bm25_indexes);
 
+            // Keyword hits come from the unfiltered indexes of every root, so
+            // drop the ones the filters exclude (other projects and roots, git
+            // history, other symbol kinds) before they take up places in the ranking
+            if let Some(filter) = &filter {
+                let unchecked: Vec<u64> = all_bm25_results
+                    .iter()
+                    .map(|hit| hit.id)
+                    .filter(|id| !rows.contains_key(id))
+                    .collect();
+                for batch in self.rows_by_id(&table, &unchecked, Some(filter)).await? {
+                    index_rows(&batch, results.len(), &mut rows)?;
+                    results.push(batch);
+                }
+                all_bm25_results.retain(|hit| rows.contains_key(&hit.id));
+            }
+
+            // Rank keyword hits from all indexes together, deterministically
+            all_bm25_results.sort_by(|a, b| {
+                b.score
+                    .partial_cmp(&a.score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a.id.cmp(&b.id))
+            });
+            let mut keyword_ranks: HashMap<u64, usize> = HashMap::new();
+            for (rank, result) in all_bm25_results.iter().enumerate() {
+                keyword_ranks.entry(result.id).or_insert(rank + 1);
+            }
+
             let bm25_results = all_bm25_results;
 
+            let fuse_span = self.phase_spans.span(|| {
+                tracing::info_span!(
+                    "fuse",
+                    vector_hits = vector_results.len(),
+                    keyword_hits = bm25_results.len(),
+                    results = tracing::field::Empty,
+                    duration_ms = tracing::field::Empty
+                )
+            });
+            let fuse_start = std::time::Instant::now();
+
+            // Boosted documentation can climb from below the cut, so keep every
+            // candidate until the boost is applied
+            let fuse_limit = if self.doc_boost.is_enabled() {
+                vector_results.len() + bm25_results.len()
+            } else {
+                limit
+            };
+
             // Combine results with Reciprocal Rank Fusion
             // RRF produces scores ~0.01-0.03, so don't apply min_score to combined scores
-            let combined =
-                crate::bm25_search::reciprocal_rank_fusion(vector_results, bm25_results, limit);
+            let combined = fuse_span.in_scope(|| {
+                crate::bm25_search::reciprocal_rank_fusion(vector_results, bm25_results, fuse_limit)
+            });
+            fuse_span.record("results", combined.len());
+            telemetry::record_duration(&fuse_span, fuse_start);
+            drop(fuse_span);
+
+            // Keyword-only hits are not among the vector candidates; fetch their rows
+            let missing: Vec<u64> = combined
+                .iter()
+                .map(|(id, _)| *id)
+                .filter(|id| !rows.contains_key(id))
+                .collect();
+            for batch in self.rows_by_id(&table, &missing, None).await? {
+                index_rows(&batch, results.len(), &mut rows)?;
+                results.push(batch);
+            }
 
-            // Build final results by looking up the combined IDs in the vector results
+            // Build final results by looking up the combined IDs
             let mut search_results = Vec::new();
 
             for (id, combined_score) in combined {
-                // Find this result in the original batch results
-                let mut found = false;
-                let mut batch_offset = 0u64;
-
-                for batch in &results {
-                    if id >= batch_offset && id < batch_offset + batch.num_rows() as u64 {
-                        let idx = (id - batch_offset) as usize;
-
-                        let file_path_array = batch
-                            .column_by_name("file_path")
-                            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
-                        let root_path_array = batch
-                            .column_by_name("root_path")
-                            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
-                        let start_line_array = batch
-                            .column_by_name("start_line")
-                            .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
-                        let end_line_array = batch
-                            .column_by_name("end_line")
-                            .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
-                        let language_array = batch
-                            .column_by_name("language")
-                            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
-                        let content_array = batch
-                            .column_by_name("content")
-                            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
-                        let project_array = batch
-                            .column_by_name("project")
-                            .and_then(|c| c.as_any().downcast_ref::<StringArray>());
-
-                        if let (
-                            Some(fp),
-                            Some(rp),
-                            Some(sl),
-                            Some(el),
-                            Some(lang),
-                            Some(cont),
-                            Some(proj),
-                        ) = (
-                            file_path_array,
-                            root_path_array,
-                            start_line_array,
-                            end_line_array,
-                            language_array,
-                            content_array,
-                            project_array,
-                        ) {
-                            // Look up original scores for filtering and reporting
-                            let (vector_score, keyword_score) =
-                                original_scores.get(&id).copied().unwrap_or((0.0, None));
-
-                            // For hybrid search, apply min_score intelligently:
-                            // Accept if EITHER vector or keyword score meets threshold
-                            // This allows pure keyword matches (weak vector) and pure semantic matches (weak keyword)
-                            let passes_filter = vector_score >= min_score
-                                || keyword_score.is_some_and(|k| k >= min_score);
-
-                            if passes_filter {
-                                let result_root_path = if rp.is_null(idx) {
-                                    None
-                                } else {
-                                    Some(rp.value(idx).to_string())
-                                };
-
-                                // Filter by root_path if specified
-                                if let Some(ref filter_path) = root_path {
-                                    if result_root_path.as_ref() != Some(filter_path) {
-                                        found = true;
-                                        break;
-                                    }
-                                }
-
-                                // Use RRF combined score as the main score for ranking
-                                // But report original vector/keyword scores for transparency
-                                search_results.push(SearchResult {
-                                    score: combined_score, // RRF score for ranking
-                                    vector_score,          // Original vector score
-                                    keyword_score,         // Original BM25 score
-                                    file_path: fp.value(idx).to_string(),
-                                    root_path: result_root_path,
-                                    start_line: sl.value(idx) as usize,
-                                    end_line: el.value(idx) as usize,
-                                    language: lang.value(idx).to_string(),
-                                    content: cont.value(idx).to_string(),
-                                    project: if proj.is_null(idx) {
-                                        None
-                                    } else {
-                                        Some(proj.value(idx).to_string())
-                                    },
-                                });
-                            }
-                            found = true;
-                            break;
-                        }
-                    }
-                    batch_offset += batch.num_rows() as u64;
+                let Some(&(batch_idx, idx)) = rows.get(&id) else {
+                    tracing::warn!("Could not find result for RRF ID {}", id);
+                    continue;
+                };
+                let batch = &results[batch_idx];
+
+                let file_path_array = batch
+                    .column_by_name("file_path")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let root_path_array = batch
+                    .column_by_name("root_path")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let start_line_array = batch
+                    .column_by_name("start_line")
+                    .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
+                let end_line_array = batch
+                    .column_by_name("end_line")
+                    .and_then(|c| c.as_any().downcast_ref::<UInt32Array>());
+                let language_array = batch
+                    .column_by_name("language")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let content_array = batch
+                    .column_by_name("content")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let project_array = batch
+                    .column_by_name("project")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let symbol_kind_array = batch
+                    .column_by_name("symbol_kind")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let binary_array = batch
+                    .column_by_name("binary")
+                    .and_then(|c| c.as_any().downcast_ref::<BooleanArray>());
+                let file_size_array = batch
+                    .column_by_name("file_size")
+                    .and_then(|c| c.as_any().downcast_ref::<UInt64Array>());
+                let branch_array = batch
+                    .column_by_name("branch")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let commit_date_array = batch
+                    .column_by_name("commit_date")
+                    .and_then(|c| c.as_any().downcast_ref::<Int64Array>());
+                let indexed_at_array = batch
+                    .column_by_name("indexed_at")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let file_hash_array = batch
+                    .column_by_name("file_hash")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+
+                let (Some(fp), Some(rp), Some(sl), Some(el), Some(lang), Some(cont), Some(proj)) = (
+                    file_path_array,
+                    root_path_array,
+                    start_line_array,
+                    end_line_array,
+                    language_array,
+                    content_array,
+                    project_array,
+                ) else {
+                    continue;
+                };
+
+                // Look up original scores for filtering and reporting
+                let (vector_score, keyword_score) =
+                    original_scores.get(&id).copied().unwrap_or((0.0, None));
+
+                // For hybrid search, apply min_score intelligently:
+                // Accept if EITHER vector or keyword score meets threshold
+                // This allows pure keyword matches (weak vector) and pure semantic matches (weak keyword)
+                let passes_filter =
+                    vector_score >= min_score || keyword_score.is_some_and(|k| k >= min_score);
+                if !passes_filter {
+                    continue;
                 }
 
-                if !found {
-                    tracing::warn!("Could not find result for RRF ID {}", id);
+                let result_root_path = optional_string(Some(rp), idx);
+                let result_project = optional_string(Some(proj), idx);
+
+                // Filter by root_path, project and git history if specified
+                if (root_path.is_some() && result_root_path != root_path)
+                    || !in_projects(projects, result_project.as_deref())
+                    || (!include_git && GIT_HISTORY_LANGUAGES.contains(&lang.value(idx)))
+                {
+                    continue;
+                }
+
+                // Use RRF combined score as the main score for ranking
+                // But report original vector/keyword scores for transparency
+                search_results.push(SearchResult {
+                    chunk_id: SearchResult::canonical_id(
+                        result_root_path.as_deref(),
+                        fp.value(idx),
+                        sl.value(idx) as usize,
+                        optional_string(file_hash_array, idx).as_deref(),
+                    ),
+                    score: combined_score, // RRF score for ranking
+                    vector_score,          // Original vector score
+                    keyword_score,         // Original BM25 score
+                    file_path: fp.value(idx).to_string(),
+                    root_path: result_root_path,
+                    start_line: sl.value(idx) as usize,
+                    end_line: el.value(idx) as usize,
+                    language: lang.value(idx).to_string(),
+                    content: cont.value(idx).to_string(),
+                    project: result_project,
+                    symbol_kind: optional_string(symbol_kind_array, idx),
+                    matched_via: MatchSource::from_hits(
+                        vector_hits.contains_key(&id),
+                        keyword_score.is_some(),
+                    ),
+                    highlights: Vec::new(),
+                    binary: optional_bool(binary_array, idx),
+                    file_size: optional_u64(file_size_array, idx),
+                    ranking: Some(RankingDetail {
+                        vector_distance: vector_hits.get(&id).map(|(_, distance)| *distance),
+                        vector_rank: vector_hits.get(&id).map(|(rank, _)| *rank),
+                        keyword_rank: keyword_ranks.get(&id).copied(),
+                    }),
+                    branch: optional_string(branch_array, idx),
+                    commit_date: optional_i64(commit_date_array, idx),
+                    indexed_at: optional_timestamp(indexed_at_array, idx),
+                    relations_precision: None,
+                    context: None,
+                });
+            }
+
+            if self.doc_boost.is_enabled() {
+                for result in &mut search_results {
+                    result.score *= self.doc_boost.factor_for(&result.file_path);
                 }
+                search_results.sort_by(|a, b| {
+                    b.score
+                        .partial_cmp(&a.score)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+                search_results.truncate(limit);
             }
 
+            self.add_keyword_highlights(query_text, &mut search_results)?;
             Ok(search_results)
         } else {
             // Pure vector search
-            let query = table
-                .vector_search(query_vector)
-                .context("Failed to create vector search")?
-                .limit(limit);
+            let query = self.vector_query(&table, query_vector, limit)?;
 
-            let stream = if let Some(ref project_name) = project {
+            let stream = if let Some(filter) = &filter {
                 query
-                    .only_if(format!("project = '{}'", project_name))
+                    .only_if(filter.as_str())
                     .execute()
                     .await
                     .context("Failed to execute search")?
@@ -601,6 +1386,7 @@ impl VectorDatabase for LanceVectorDB {
                 .context("Failed to collect search results")?;
 
             let mut search_results = Vec::new();
+            let mut vector_rank = 0;
 
             for batch in results {
                 let file_path_array = batch
@@ -652,6 +1438,29 @@ impl VectorDatabase for LanceVectorDB {
                     .downcast_ref::<StringArray>()
                     .context("Invalid project type")?;
 
+                // Tables created before symbol tagging may not have these columns
+                let symbol_kind_array = batch
+                    .column_by_name("symbol_kind")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let binary_array = batch
+                    .column_by_name("binary")
+                    .and_then(|c| c.as_any().downcast_ref::<BooleanArray>());
+                let file_size_array = batch
+                    .column_by_name("file_size")
+                    .and_then(|c| c.as_any().downcast_ref::<UInt64Array>());
+                let branch_array = batch
+                    .column_by_name("branch")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let commit_date_array = batch
+                    .column_by_name("commit_date")
+                    .and_then(|c| c.as_any().downcast_ref::<Int64Array>());
+                let indexed_at_array = batch
+                    .column_by_name("indexed_at")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+                let file_hash_array = batch
+                    .column_by_name("file_hash")
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+
                 let distance_array = batch
                     .column_by_name("_distance")
                     .context("Missing _distance column")?
@@ -661,7 +1470,8 @@ impl VectorDatabase for LanceVectorDB {
 
                 for i in 0..batch.num_rows() {
                     let distance = distance_array.value(i);
-                    let score = 1.0 / (1.0 + distance);
+                    let score = self.distance.score(distance);
+                    vector_rank += 1;
 
                     if score >= min_score {
                         let result_root_path = if root_path_array.is_null(i) {
@@ -678,6 +1488,12 @@ impl VectorDatabase for LanceVectorDB {
                         }
 
                         search_results.push(SearchResult {
+                            chunk_id: SearchResult::canonical_id(
+                                result_root_path.as_deref(),
+                                file_path_array.value(i),
+                                start_line_array.value(i) as usize,
+                                optional_string(file_hash_array, i).as_deref(),
+                            ),
                             score,
                             vector_score: score,
                             keyword_score: None,
@@ -692,6 +1508,21 @@ impl VectorDatabase for LanceVectorDB {
                             } else {
                                 Some(project_array.value(i).to_string())
                             },
+                            symbol_kind: optional_string(symbol_kind_array, i),
+                            matched_via: MatchSource::Vector,
+                            highlights: Vec::new(),
+                            binary: optional_bool(binary_array, i),
+                            file_size: optional_u64(file_size_array, i),
+                            ranking: Some(RankingDetail {
+                                vector_distance: Some(distance),
+                                vector_rank: Some(vector_rank),
+                                keyword_rank: None,
+                            }),
+                            branch: optional_string(branch_array, i),
+                            commit_date: optional_i64(commit_date_array, i),
+                            indexed_at: optional_timestamp(indexed_at_array, i),
+                            relations_precision: None,
+                            context: None,
                         });
                     }
                 }
@@ -700,6 +1531,160 @@ impl VectorDatabase for LanceVectorDB {
             Ok(search_results)
         }
     }
+}
+
+#[async_trait::async_trait]
+impl VectorDatabase for LanceVectorDB {
+    async fn initialize(&self, dimension: usize) -> Result<()> {
+        tracing::info!(
+            "Initializing LanceDB with dimension {} at {}",
+            dimension,
+            self.db_path
+        );
+
+        // Check if table exists
+        let table_names = self
+            .connection
+            .table_names()
+            .execute()
+            .await
+            .context("Failed to list tables")?;
+
+        let table_name = self.table_name();
+        if table_names.contains(&table_name) {
+            tracing::info!("Table '{}' already exists", table_name);
+            let table = self.get_table().await?;
+            self.migrate_table(&table).await?;
+            // An index built for another distance metric would rank by that one
+            if let Err(e) = self.maintain_vector_index(&table, false).await {
+                tracing::warn!("Failed to rebuild ANN vector index: {:#}", e);
+            }
+            return Ok(());
+        }
+
+        // Create empty table with schema
+        let schema = Self::create_schema(dimension);
+
+        // Create empty RecordBatch
+        let empty_batch = RecordBatch::new_empty(schema.clone());
+
+        // Need to wrap in iterator that returns Result<RecordBatch>
+        let batches =
+            RecordBatchIterator::new(vec![empty_batch].into_iter().map(Ok), schema.clone());
+
+        self.connection
+            .create_table(&table_name, Box::new(batches))
+            .execute()
+            .await
+            .context("Failed to create table")?;
+
+        // A new table is known to be empty, so there is nothing to scan for
+        *self.lock_stats() = StatsState {
+            counters: Some(StatsCounters::default()),
+            unsaved: true,
+        };
+
+        tracing::info!("Created table '{}'", table_name);
+        Ok(())
+    }
+
+    async fn store_embeddings(
+        &self,
+        embeddings: Vec<Vec<f32>>,
+        metadata: Vec<ChunkMetadata>,
+        contents: Vec<String>,
+        root_path: &str,
+    ) -> Result<usize> {
+        if embeddings.is_empty() {
+            return Ok(0);
+        }
+        let _writing = self.write_lock.read().await;
+
+        let dimension = embeddings[0].len();
+        let schema = Self::create_schema(dimension);
+
+        // Ensure BM25 index exists for this root path. A rebuild has to happen
+        // before the new rows are added, which are indexed separately below.
+        self.ensure_bm25(root_path).await?;
+
+        let table = self.get_table().await?;
+
+        let batch = Self::create_record_batch(
+            embeddings,
+            metadata.clone(),
+            contents.clone(),
+            schema.clone(),
+        )?;
+        let count = batch.num_rows();
+
+        let batches = RecordBatchIterator::new(vec![batch].into_iter().map(Ok), schema);
+
+        table
+            .add(Box::new(batches))
+            .execute()
+            .await
+            .context("Failed to add records to table")?;
+
+        self.lock_stats().update(|counters| {
+            for m in &metadata {
+                counters.add(m.language.as_deref().unwrap_or("Unknown"), &m.file_path, 1);
+            }
+        });
+
+        // Add documents to per-project BM25 index with file_path for deletion tracking
+        let bm25_docs: Vec<_> = metadata
+            .iter()
+            .zip(contents)
+            .map(|(m, content)| (chunk_id(m), content, m.file_path.clone()))
+            .collect();
+
+        let hash = Self::hash_root_path(root_path);
+        self.write_bm25(move |bm25_indexes| {
+            if let Some(bm25) = bm25_indexes.get(&hash) {
+                bm25.add_documents(bm25_docs)
+                    .context("Failed to add documents to BM25 index")?;
+            }
+            Ok(())
+        })
+        .await?;
+
+        // Searches stay exhaustive (and correct) if the index cannot be built
+        if let Err(e) = self.maintain_vector_index(&table, false).await {
+            tracing::warn!("Failed to build ANN vector index: {:#}", e);
+        }
+
+        tracing::info!(
+            "Stored {} embeddings with BM25 indexing for root: {}",
+            count,
+            root_path
+        );
+        Ok(count)
+    }
+
+    async fn search(
+        &self,
+        query_vector: Vec<f32>,
+        query_text: &str,
+        limit: usize,
+        min_score: f32,
+        projects: &[String],
+        root_path: Option<String>,
+        hybrid: bool,
+        include_git: bool,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_where(
+            query_vector,
+            query_text,
+            limit,
+            min_score,
+            projects,
+            root_path,
+            hybrid,
+            include_git,
+            &[],
+        )
+        .await
+    }
 
     async fn search_filtered(
         &self,
@@ -707,26 +1692,29 @@ impl VectorDatabase for LanceVectorDB {
         query_text: &str,
         limit: usize,
         min_score: f32,
-        project: Option<String>,
+        projects: &[String],
         root_path: Option<String>,
         hybrid: bool,
         file_extensions: Vec<String>,
         languages: Vec<String>,
         path_patterns: Vec<String>,
+        symbol_kinds: Vec<String>,
     ) -> Result<Vec<SearchResult>> {
         // Get more results than requested to account for filtering
-        let search_limit = limit * 3;
+        let search_limit = limit * HYBRID_CANDIDATE_FACTOR;
 
-        // Do basic search with hybrid support
+        // Do basic search with hybrid support; symbol kinds are filtered in the query
         let mut results = self
-            .search(
+            .search_where(
                 query_vector,
                 query_text,
                 search_limit,
                 min_score,
-                project.clone(),
+                projects,
                 root_path.clone(),
                 hybrid,
+                true, // Git history is kept or dropped by the language filter
+                &symbol_kinds,
             )
             .await?;
 
@@ -763,64 +1751,395 @@ impl VectorDatabase for LanceVectorDB {
         Ok(results)
     }
 
-    async fn delete_by_file(&self, file_path: &str) -> Result<usize> {
-        // Delete from BM25 index first (using file_path field)
-        // Delete from all per-project BM25 indexes
-        // Must be done in a scope to drop lock before await
+    async fn search_keyword(
+        &self,
+        query_text: &str,
+        limit: usize,
+        projects: &[String],
+        root_path: Option<String>,
+    ) -> Result<Vec<SearchResult>> {
+        // Rank keyword hits from all indexes together, as hybrid search does
+        let mut hits = Vec::new();
         {
             let bm25_indexes = self
                 .bm25_indexes
                 .read()
                 .map_err(|e| anyhow::anyhow!("Failed to acquire BM25 read lock: {}", e))?;
+            for bm25 in bm25_indexes.values() {
+                hits.extend(
+                    bm25.search(query_text, limit)
+                        .context("Failed to search BM25 index")?,
+                );
+            }
+        }
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.id.cmp(&b.id))
+        });
+
+        let mut ranked: HashMap<u64, (usize, f32)> = HashMap::new();
+        for hit in hits {
+            let rank = ranked.len() + 1;
+            ranked.entry(hit.id).or_insert((rank, hit.score));
+        }
+        if ranked.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let table = self.get_table().await?;
+        let ids: Vec<u64> = ranked.keys().copied().collect();
+
+        let mut results = Vec::new();
+        for batch in self.rows_by_id(&table, &ids, None).await? {
+            let string_column = |name: &str| -> Result<&StringArray> {
+                batch
+                    .column_by_name(name)
+                    .with_context(|| format!("Missing {} column", name))?
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .with_context(|| format!("Invalid {} type", name))
+            };
+            let line_column = |name: &str| -> Result<&UInt32Array> {
+                batch
+                    .column_by_name(name)
+                    .with_context(|| format!("Missing {} column", name))?
+                    .as_any()
+                    .downcast_ref::<UInt32Array>()
+                    .with_context(|| format!("Invalid {} type", name))
+            };
+            let row_ids = string_column("id")?;
+            let file_paths = string_column("file_path")?;
+            let start_lines = line_column("start_line")?;
+            let end_lines = line_column("end_line")?;
+            let languages = string_column("language")?;
+            let contents = string_column("content")?;
+            let root_paths = string_column("root_path").ok();
+            let project_names = string_column("project").ok();
+            let symbol_kinds = string_column("symbol_kind").ok();
+            let branches = string_column("branch").ok();
+            let commit_dates = batch
+                .column_by_name("commit_date")
+                .and_then(|c| c.as_any().downcast_ref::<Int64Array>());
+            let indexed_ats = string_column("indexed_at").ok();
+            let file_hashes = string_column("file_hash").ok();
+            let binaries = batch
+                .column_by_name("binary")
+                .and_then(|c| c.as_any().downcast_ref::<BooleanArray>());
+            let file_sizes = batch
+                .column_by_name("file_size")
+                .and_then(|c| c.as_any().downcast_ref::<UInt64Array>());
+
+            for idx in 0..batch.num_rows() {
+                let Some(&(rank, score)) = ranked.get(&row_bm25_id(row_ids.value(idx))) else {
+                    continue;
+                };
+                let result_root_path = optional_string(root_paths, idx);
+                let result_project = optional_string(project_names, idx);
+                if !in_projects(projects, result_project.as_deref())
+                    || (root_path.is_some() && result_root_path != root_path)
+                {
+                    continue;
+                }
 
+                results.push(SearchResult {
+                    chunk_id: SearchResult::canonical_id(
+                        result_root_path.as_deref(),
+                        file_paths.value(idx),
+                        start_lines.value(idx) as usize,
+                        optional_string(file_hashes, idx).as_deref(),
+                    ),
+                    score,
+                    vector_score: 0.0,
+                    keyword_score: Some(score),
+                    file_path: file_paths.value(idx).to_string(),
+                    root_path: result_root_path,
+                    start_line: start_lines.value(idx) as usize,
+                    end_line: end_lines.value(idx) as usize,
+                    language: languages.value(idx).to_string(),
+                    content: contents.value(idx).to_string(),
+                    project: result_project,
+                    symbol_kind: optional_string(symbol_kinds, idx),
+                    matched_via: MatchSource::Keyword,
+                    highlights: Vec::new(),
+                    binary: optional_bool(binaries, idx),
+                    file_size: optional_u64(file_sizes, idx),
+                    ranking: Some(RankingDetail {
+                        vector_distance: None,
+                        vector_rank: None,
+                        keyword_rank: Some(rank),
+                    }),
+                    branch: optional_string(branches, idx),
+                    commit_date: optional_i64(commit_dates, idx),
+                    indexed_at: optional_timestamp(indexed_ats, idx),
+                    relations_precision: None,
+                    context: None,
+                });
+            }
+        }
+
+        results.sort_by_key(|r| r.ranking.and_then(|detail| detail.keyword_rank));
+        results.truncate(limit);
+        self.add_keyword_highlights(query_text, &mut results)?;
+        Ok(results)
+    }
+
+    async fn delete_by_file(&self, file_path: &str) -> Result<usize> {
+        let _writing = self.write_lock.read().await;
+
+        // Delete from BM25 index first (using file_path field)
+        // Delete from all per-project BM25 indexes
+        let bm25_file_path = file_path.to_string();
+        self.write_bm25(move |bm25_indexes| {
             for (root_hash, bm25) in bm25_indexes.iter() {
-                bm25.delete_by_file_path(file_path)
+                let deleted = bm25
+                    .delete_by_file_path(&bm25_file_path)
                     .context("Failed to delete from BM25 index")?;
                 tracing::debug!(
-                    "Deleted BM25 entries for file: {} in index: {}",
-                    file_path,
+                    "Deleted {} BM25 entries for file: {} in index: {}",
+                    deleted,
+                    bm25_file_path,
                     root_hash
                 );
             }
-        } // bm25_indexes dropped here
+            Ok(())
+        })
+        .await?;
 
         let table = self.get_table().await?;
 
         // LanceDB uses SQL-like delete
-        let filter = format!("file_path = '{}'", file_path);
+        let filter = format!("file_path = {}", sql_string(file_path));
+
+        // LanceDB doesn't return the number of deleted rows, so count them first
+        let deleted = table
+            .count_rows(Some(filter.clone()))
+            .await
+            .context("Failed to count records to delete")?;
 
         table
             .delete(&filter)
             .await
             .context("Failed to delete records")?;
+        self.lock_stats()
+            .update(|counters| counters.remove_file(file_path));
+
+        tracing::info!("Deleted {} embeddings for file: {}", deleted, file_path);
+
+        Ok(deleted)
+    }
+
+    async fn delete_where(&self, filter: &DeleteFilter) -> Result<DeletedChunks> {
+        if filter.is_empty() {
+            anyhow::bail!("Delete filter has no criteria; use clear() to delete everything");
+        }
+        let _writing = self.write_lock.read().await;
+
+        let table = self.get_table().await?;
+
+        // Equality criteria are pushed down; the rest is checked per row below
+        // (indexed_at is stored as a string, so it can't be compared in SQL)
+        let mut conditions = Vec::new();
+        if let Some(ref language) = filter.language {
+            conditions.push(format!("language = {}", sql_string(language)));
+        }
+        if let Some(ref project) = filter.project {
+            conditions.push(format!("project = {}", sql_string(project)));
+        }
+        let mut query = table.query().select(lancedb::query::Select::Columns(vec![
+            "id".to_string(),
+            "file_path".to_string(),
+            "root_path".to_string(),
+            "language".to_string(),
+            "project".to_string(),
+            "indexed_at".to_string(),
+        ]));
+        if !conditions.is_empty() {
+            query = query.only_if(conditions.join(" AND "));
+        }
+        let mut stream = query
+            .execute()
+            .await
+            .context("Failed to query chunks to delete")?;
+
+        let mut ids = Vec::new();
+        let mut bm25_ids: HashMap<String, Vec<u64>> = HashMap::new();
+        let mut files = BTreeSet::new();
+        let mut removed: HashMap<(String, String), usize> = HashMap::new();
+        while let Some(batch) = stream
+            .try_next()
+            .await
+            .context("Failed to read chunks to delete")?
+        {
+            let string_column = |name: &str| -> Result<&StringArray> {
+                batch
+                    .column_by_name(name)
+                    .with_context(|| format!("Missing {} column", name))?
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .with_context(|| format!("Invalid {} type", name))
+            };
+            let row_ids = string_column("id")?;
+            let file_paths = string_column("file_path")?;
+            let languages = string_column("language")?;
+            let indexed_ats = string_column("indexed_at")?;
+            let root_paths = string_column("root_path").ok();
+            let projects = string_column("project").ok();
+
+            for idx in 0..batch.num_rows() {
+                let file_path = file_paths.value(idx);
+                if !filter.matches(
+                    file_path,
+                    languages.value(idx),
+                    optional_string(projects, idx).as_deref(),
+                    indexed_ats.value(idx).parse().unwrap_or(0),
+                ) {
+                    continue;
+                }
+
+                let id = row_ids.value(idx);
+                let result_root_path = optional_string(root_paths, idx);
+                if let Some(ref root) = result_root_path {
+                    bm25_ids
+                        .entry(root.clone())
+                        .or_default()
+                        .push(row_bm25_id(id));
+                }
+                files.insert((result_root_path, file_path.to_string()));
+                *removed
+                    .entry((languages.value(idx).to_string(), file_path.to_string()))
+                    .or_default() += 1;
+                ids.push(id.to_string());
+            }
+        }
+
+        if ids.is_empty() {
+            return Ok(DeletedChunks::default());
+        }
+
+        // Delete from the BM25 indexes first, loading any not opened yet
+        for (root, root_ids) in bm25_ids {
+            self.ensure_bm25(&root).await?;
+            let hash = Self::hash_root_path(&root);
+            self.write_bm25(move |bm25_indexes| {
+                if let Some(bm25) = bm25_indexes.get(&hash) {
+                    bm25.delete_by_ids(&root_ids)
+                        .context("Failed to delete from BM25 index")?;
+                }
+                Ok(())
+            })
+            .await?;
+        }
 
-        tracing::info!("Deleted embeddings for file: {}", file_path);
+        for batch in ids.chunks(DELETE_BATCH_SIZE) {
+            let id_list = batch
+                .iter()
+                .map(|id| sql_string(id))
+                .collect::<Vec<_>>()
+                .join(", ");
+            table
+                .delete(&format!("id IN ({})", id_list))
+                .await
+                .context("Failed to delete records")?;
+        }
+        self.lock_stats().update(|counters| {
+            for ((language, file_path), chunks) in &removed {
+                counters.remove(language, file_path, *chunks);
+            }
+        });
 
-        // LanceDB doesn't return count directly, return 0 as placeholder
-        Ok(0)
+        tracing::info!(
+            "Deleted {} chunks from {} files matching {:?}",
+            ids.len(),
+            files.len(),
+            filter
+        );
+        Ok(DeletedChunks {
+            count: ids.len(),
+            files: files.into_iter().collect(),
+        })
     }
 
     async fn clear(&self) -> Result<()> {
+        let _writing = self.write_lock.read().await;
+
         // Drop and recreate table (empty namespace array for default namespace)
         self.connection
-            .drop_table(&self.table_name, &[])
+            .drop_table(&self.table_name(), &[])
             .await
             .context("Failed to drop table")?;
+        *self.lock_stats() = StatsState {
+            counters: Some(StatsCounters::default()),
+            unsaved: true,
+        };
+        self.save_stats();
 
         // Clear all per-project BM25 indexes
-        let bm25_indexes = self
+        self.write_bm25(|bm25_indexes| {
+            for (root_hash, bm25) in bm25_indexes.iter() {
+                bm25.clear().context("Failed to clear BM25 index")?;
+                tracing::info!("Cleared BM25 index for root hash: {}", root_hash);
+            }
+            Ok(())
+        })
+        .await?;
+
+        tracing::info!("Cleared all embeddings and all per-project BM25 indexes");
+        Ok(())
+    }
+
+    async fn drop_keyword_index(&self, root_path: &str) -> Result<bool> {
+        let _writing = self.write_lock.read().await;
+
+        // Close the index before its directory goes away
+        let loaded = self
             .bm25_indexes
-            .read()
-            .map_err(|e| anyhow::anyhow!("Failed to acquire BM25 read lock: {}", e))?;
+            .write()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire BM25 write lock: {}", e))?
+            .remove(&Self::hash_root_path(root_path))
+            .is_some();
+
+        let bm25_path = self.bm25_path_for_root(root_path);
+        let removed = match std::fs::remove_dir_all(&bm25_path) {
+            Ok(()) => true,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to remove BM25 index at {}", bm25_path));
+            }
+        };
 
-        for (root_hash, bm25) in bm25_indexes.iter() {
-            bm25.clear().context("Failed to clear BM25 index")?;
-            tracing::info!("Cleared BM25 index for root hash: {}", root_hash);
+        if loaded || removed {
+            tracing::info!("Dropped BM25 index for root path '{}'", root_path);
         }
-        drop(bm25_indexes);
+        Ok(loaded || removed)
+    }
 
-        tracing::info!("Cleared all embeddings and all per-project BM25 indexes");
-        Ok(())
+    async fn indexed_dimension(&self) -> Result<Option<usize>> {
+        let table_names = self
+            .connection
+            .table_names()
+            .execute()
+            .await
+            .context("Failed to list tables")?;
+        if !table_names.contains(&self.table_name()) {
+            return Ok(None);
+        }
+
+        let schema = self
+            .get_table()
+            .await?
+            .schema()
+            .await
+            .context("Failed to read table schema")?;
+        match schema
+            .field_with_name("vector")
+            .map(|field| field.data_type())
+        {
+            Ok(DataType::FixedSizeList(_, dimension)) => Ok(Some(*dimension as usize)),
+            _ => anyhow::bail!("Table '{}' has no vector column", self.table_name()),
+        }
     }
 
     async fn get_statistics(&self) -> Result<DatabaseStats> {
@@ -832,24 +2151,86 @@ impl VectorDatabase for LanceVectorDB {
             .await
             .context("Failed to count rows")?;
 
-        // Get language breakdown by scanning the table
+        // Running counts are used as long as they agree with the table
+        let known = {
+            let stats = self.lock_stats();
+            stats
+                .counters
+                .as_ref()
+                .filter(|counters| counters.total_chunks() == count_result)
+                .map(|counters| (counters.total_files(), counters.language_breakdown()))
+        };
+        let (total_files, language_breakdown) = match known {
+            Some(known) => known,
+            None => {
+                tracing::info!(
+                    "Rebuilding chunk statistics of table '{}' from a scan",
+                    self.table_name()
+                );
+                let counters = Self::scan_statistics(&table).await?;
+                let known = (counters.total_files(), counters.language_breakdown());
+                *self.lock_stats() = StatsState {
+                    counters: Some(counters),
+                    unsaved: true,
+                };
+                self.save_stats();
+                known
+            }
+        };
+
+        // Walk the active table and its own bm25_* indexes, leaving out the
+        // other tables (per-model tables, relations) sharing the directory
+        let db_path = PathBuf::from(&self.db_path);
+        let size_bytes = if db_path.is_dir() {
+            let mut paths = self.keyword_index_paths()?;
+            paths.push(db_path.join(format!("{}.lance", self.table_name())));
+            let size = tokio::task::spawn_blocking(move || {
+                paths.iter().map(|path| directory_size(path)).sum::<u64>()
+            })
+            .await
+            .context("Failed to spawn database size task")?;
+            Some(size)
+        } else {
+            tracing::debug!("Database path {} is not a local directory", self.db_path);
+            None
+        };
+
+        Ok(DatabaseStats {
+            total_points: count_result,
+            total_vectors: count_result,
+            total_files,
+            language_breakdown,
+            size_bytes,
+        })
+    }
+
+    async fn list_projects(&self) -> Result<Vec<ProjectSummary>> {
+        let table = self.get_table().await?;
+
         let stream = table
             .query()
+            .only_if("project IS NOT NULL")
             .select(lancedb::query::Select::Columns(vec![
+                "project".to_string(),
                 "language".to_string(),
             ]))
             .execute()
             .await
-            .context("Failed to query languages")?;
+            .context("Failed to query projects")?;
 
-        let query_result: Vec<RecordBatch> = stream
+        let batches: Vec<RecordBatch> = stream
             .try_collect()
             .await
-            .context("Failed to collect language data")?;
-
-        let mut language_counts: HashMap<String, usize> = HashMap::new();
+            .context("Failed to collect project data")?;
 
-        for batch in query_result {
+        let mut chunks = Vec::new();
+        for batch in &batches {
+            let project_array = batch
+                .column_by_name("project")
+                .context("Missing project column")?
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .context("Invalid project type")?;
             let language_array = batch
                 .column_by_name("language")
                 .context("Missing language column")?
@@ -858,31 +2239,32 @@ impl VectorDatabase for LanceVectorDB {
                 .context("Invalid language type")?;
 
             for i in 0..batch.num_rows() {
-                let language = language_array.value(i);
-                *language_counts.entry(language.to_string()).or_insert(0) += 1;
+                if !project_array.is_null(i) {
+                    chunks.push((project_array.value(i), language_array.value(i)));
+                }
             }
         }
 
-        let mut language_breakdown: Vec<(String, usize)> = language_counts.into_iter().collect();
-        language_breakdown.sort_by(|a, b| b.1.cmp(&a.1));
-
-        Ok(DatabaseStats {
-            total_points: count_result,
-            total_vectors: count_result,
-            language_breakdown,
-        })
+        Ok(summarize_projects(chunks))
     }
 
     async fn flush(&self) -> Result<()> {
-        // LanceDB persists automatically, no explicit flush needed
-        Ok(())
+        self.flush_bm25().await?;
+        self.save_stats();
+
+        // LanceDB persists automatically; flushing only keeps the ANN index current
+        if self.ann_index.min_rows == 0 {
+            return Ok(());
+        }
+        let table = self.get_table().await?;
+        self.maintain_vector_index(&table, true).await
     }
 
     async fn count_by_root_path(&self, root_path: &str) -> Result<usize> {
         let table = self.get_table().await?;
 
         // Use SQL-like filter to count rows with matching root_path
-        let filter = format!("root_path = '{}'", root_path);
+        let filter = format!("root_path = {}", sql_string(root_path));
         let count = table
             .count_rows(Some(filter))
             .await
@@ -895,7 +2277,7 @@ impl VectorDatabase for LanceVectorDB {
         let table = self.get_table().await?;
 
         // Query file_path column filtered by root_path
-        let filter = format!("root_path = '{}'", root_path);
+        let filter = format!("root_path = {}", sql_string(root_path));
         let stream = table
             .query()
             .only_if(filter)
@@ -929,7 +2311,337 @@ impl VectorDatabase for LanceVectorDB {
 
         Ok(file_paths.into_iter().collect())
     }
+
+    async fn check_keyword_indexes(&self) -> Result<usize> {
+        let paths = self.keyword_index_paths()?;
+        for path in &paths {
+            // Opened with the analyzer it was built with, which may be due a rebuild
+            BM25Search::with_analyzer(path, &BM25Search::recorded_analyzer(path))
+                .and_then(|bm25| bm25.get_stats())
+                .with_context(|| format!("Failed to open BM25 index at {}", path.display()))?;
+        }
+        Ok(paths.len())
+    }
+
+    async fn optimize(&self) -> Result<OptimizeStats> {
+        let table = self.get_table().await?;
+        let fragments_before = table
+            .stats()
+            .await
+            .context("Failed to read table statistics")?
+            .fragment_stats
+            .num_fragments;
+        table
+            .optimize(OptimizeAction::All)
+            .await
+            .context("Failed to optimize table")?;
+        let fragments_after = table
+            .stats()
+            .await
+            .context("Failed to read table statistics")?
+            .fragment_stats
+            .num_fragments;
+
+        let mut stats = OptimizeStats {
+            fragments_before,
+            fragments_after,
+            ..Default::default()
+        };
+        // Loaded indexes hold their writers open until flushed
+        self.flush_bm25().await?;
+        for path in self.keyword_index_paths()? {
+            let bm25 = BM25Search::with_analyzer(&path, &BM25Search::recorded_analyzer(&path))
+                .with_context(|| format!("Failed to open BM25 index at {}", path.display()))?
+                .with_writer_lock_timeout(self.bm25_writer_lock_timeout)
+                .with_stale_lock_timeout(self.bm25_stale_lock_timeout);
+            let (before, after) = bm25
+                .merge_segments()
+                .with_context(|| format!("Failed to merge BM25 index at {}", path.display()))?;
+            stats.keyword_indexes += 1;
+            stats.keyword_segments_before += before;
+            stats.keyword_segments_after += after;
+        }
+
+        tracing::info!(
+            "Optimized table '{}': {} -> {} fragments, {} -> {} segments in {} BM25 indexes",
+            self.table_name(),
+            stats.fragments_before,
+            stats.fragments_after,
+            stats.keyword_segments_before,
+            stats.keyword_segments_after,
+            stats.keyword_indexes
+        );
+        Ok(stats)
+    }
+
+    async fn get_file_chunks(
+        &self,
+        file_path: &str,
+        project: Option<String>,
+    ) -> Result<Vec<SearchResult>> {
+        let table = self.get_table().await?;
+
+        let mut filter = format!("file_path = {}", sql_string(file_path));
+        if let Some(project) = project {
+            filter.push_str(&format!(" AND project = {}", sql_string(&project)));
+        }
+        let batches: Vec<RecordBatch> = table
+            .query()
+            .only_if(filter)
+            .execute()
+            .await
+            .context("Failed to query file chunks")?
+            .try_collect()
+            .await
+            .context("Failed to collect file chunks")?;
+
+        let mut results = Vec::new();
+        for batch in &batches {
+            results.extend(stored_chunks(batch)?);
+        }
+        results.sort_by_key(|r| r.start_line);
+        Ok(results)
+    }
+}
+
+/// SQL string literal holding `value`, with single quotes doubled
+///
+/// Every string compared in a filter goes through this, so names and paths
+/// containing quotes neither break the filter nor extend it.
+fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// SQL condition matching rows of any of `projects`, or `None` when all rows match
+fn project_filter(projects: &[String]) -> Option<String> {
+    if projects.is_empty() {
+        return None;
+    }
+    let names: Vec<String> = projects.iter().map(|project| sql_string(project)).collect();
+    Some(format!("project IN ({})", names.join(", ")))
+}
+
+/// SQL condition matching chunks tagged with any of `symbol_kinds`, or `None`
+/// when all rows match
+fn symbol_kind_filter(symbol_kinds: &[String]) -> Option<String> {
+    if symbol_kinds.is_empty() {
+        return None;
+    }
+    let kinds: Vec<String> = symbol_kinds.iter().map(|kind| sql_string(kind)).collect();
+    Some(format!("symbol_kind IN ({})", kinds.join(", ")))
+}
+
+/// SQL condition for the rows a search may return, or `None` when all rows may
+fn search_filter(
+    projects: &[String],
+    root_path: Option<&str>,
+    include_git: bool,
+    symbol_kinds: &[String],
+) -> Option<String> {
+    let mut conditions: Vec<String> = project_filter(projects).into_iter().collect();
+    if let Some(root_path) = root_path {
+        conditions.push(format!("root_path = {}", sql_string(root_path)));
+    }
+    conditions.extend(symbol_kind_filter(symbol_kinds));
+    if !include_git {
+        let languages: Vec<String> = GIT_HISTORY_LANGUAGES
+            .iter()
+            .map(|language| sql_string(language))
+            .collect();
+        conditions.push(format!("language NOT IN ({})", languages.join(", ")));
+    }
+    (!conditions.is_empty()).then(|| conditions.join(" AND "))
+}
+
+/// Record the batch and row of every chunk in `batch`, keeping earlier entries
+fn index_rows(
+    batch: &RecordBatch,
+    batch_idx: usize,
+    rows: &mut HashMap<u64, (usize, usize)>,
+) -> Result<()> {
+    let id_array = batch
+        .column_by_name("id")
+        .context("Missing id column")?
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .context("Invalid id type")?;
+    for i in 0..batch.num_rows() {
+        rows.entry(row_bm25_id(id_array.value(i)))
+            .or_insert((batch_idx, i));
+    }
+    Ok(())
+}
+
+/// Whether a row of `project` is among `projects` (all are when empty)
+fn in_projects(projects: &[String], project: Option<&str>) -> bool {
+    projects.is_empty() || project.is_some_and(|project| projects.iter().any(|p| p == project))
 }
 
+/// Rows of a batch as search results with a score of 1.0
+fn stored_chunks(batch: &RecordBatch) -> Result<Vec<SearchResult>> {
+    let string_column = |name: &str| -> Result<&StringArray> {
+        batch
+            .column_by_name(name)
+            .with_context(|| format!("Missing {} column", name))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .with_context(|| format!("Invalid {} type", name))
+    };
+    let line_column = |name: &str| -> Result<&UInt32Array> {
+        batch
+            .column_by_name(name)
+            .with_context(|| format!("Missing {} column", name))?
+            .as_any()
+            .downcast_ref::<UInt32Array>()
+            .with_context(|| format!("Invalid {} type", name))
+    };
+    let file_paths = string_column("file_path")?;
+    let start_lines = line_column("start_line")?;
+    let end_lines = line_column("end_line")?;
+    let languages = string_column("language")?;
+    let contents = string_column("content")?;
+    let root_paths = string_column("root_path").ok();
+    let projects = string_column("project").ok();
+    let symbol_kinds = string_column("symbol_kind").ok();
+    let branches = string_column("branch").ok();
+    let file_hashes = string_column("file_hash").ok();
+    let indexed_ats = string_column("indexed_at").ok();
+    let commit_dates = batch
+        .column_by_name("commit_date")
+        .and_then(|c| c.as_any().downcast_ref::<Int64Array>());
+    let binaries = batch
+        .column_by_name("binary")
+        .and_then(|c| c.as_any().downcast_ref::<BooleanArray>());
+    let file_sizes = batch
+        .column_by_name("file_size")
+        .and_then(|c| c.as_any().downcast_ref::<UInt64Array>());
+
+    Ok((0..batch.num_rows())
+        .map(|idx| SearchResult {
+            chunk_id: SearchResult::canonical_id(
+                optional_string(root_paths, idx).as_deref(),
+                file_paths.value(idx),
+                start_lines.value(idx) as usize,
+                optional_string(file_hashes, idx).as_deref(),
+            ),
+            score: 1.0,
+            vector_score: 1.0,
+            keyword_score: None,
+            file_path: file_paths.value(idx).to_string(),
+            root_path: optional_string(root_paths, idx),
+            start_line: start_lines.value(idx) as usize,
+            end_line: end_lines.value(idx) as usize,
+            language: languages.value(idx).to_string(),
+            content: contents.value(idx).to_string(),
+            project: optional_string(projects, idx),
+            symbol_kind: optional_string(symbol_kinds, idx),
+            matched_via: MatchSource::default(),
+            highlights: Vec::new(),
+            binary: optional_bool(binaries, idx),
+            file_size: optional_u64(file_sizes, idx),
+            ranking: None,
+            branch: optional_string(branches, idx),
+            commit_date: optional_i64(commit_dates, idx),
+            indexed_at: optional_timestamp(indexed_ats, idx),
+            relations_precision: None,
+            context: None,
+        })
+        .collect())
+}
+
+/// Stable id of a chunk, shared by its table row and its BM25 document
+///
+/// Derived from the chunk's origin rather than from the table's row count, so
+/// concurrent or partially failed stores cannot make ids collide or drift away
+/// from the rows they identify.
+fn chunk_id(metadata: &ChunkMetadata) -> u64 {
+    row_chunk_id(
+        metadata.root_path.as_deref(),
+        &metadata.file_path,
+        metadata.start_line,
+        metadata.end_line,
+        &metadata.file_hash,
+    )
+}
+
+/// Chunk id of a row from the columns it is derived from
+fn row_chunk_id(
+    root_path: Option<&str>,
+    file_path: &str,
+    start_line: usize,
+    end_line: usize,
+    file_hash: &str,
+) -> u64 {
+    hash_to_id(&[
+        root_path.unwrap_or(""),
+        file_path,
+        &start_line.to_string(),
+        &end_line.to_string(),
+        file_hash,
+    ])
+}
+
+/// Chunk id of a row from its `id` column
+///
+/// Rows stored before chunk ids were introduced carry a `file_path:start_line`
+/// id, which is hashed instead.
+fn row_bm25_id(id: &str) -> u64 {
+    id.parse().unwrap_or_else(|_| hash_to_id(&[id]))
+}
+
+fn hash_to_id(parts: &[&str]) -> u64 {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    let digest = hasher.finalize();
+    u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 digest is 32 bytes"))
+}
+
+/// Read a nullable string cell, treating a missing column as null
+fn optional_string(array: Option<&StringArray>, idx: usize) -> Option<String> {
+    array
+        .filter(|a| !a.is_null(idx))
+        .map(|a| a.value(idx).to_string())
+}
+
+/// Read a nullable boolean cell, treating a missing column or null as false
+fn optional_bool(array: Option<&BooleanArray>, idx: usize) -> bool {
+    array.is_some_and(|a| !a.is_null(idx) && a.value(idx))
+}
+
+/// Read a nullable u64 cell, treating a missing column as null
+fn optional_u64(array: Option<&UInt64Array>, idx: usize) -> Option<u64> {
+    array.filter(|a| !a.is_null(idx)).map(|a| a.value(idx))
+}
+
+/// Read a nullable i64 cell, treating a missing column as null
+fn optional_i64(array: Option<&Int64Array>, idx: usize) -> Option<i64> {
+    array.filter(|a| !a.is_null(idx)).map(|a| a.value(idx))
+}
+
+/// Read an `indexed_at` cell (stored as a string), treating unparsable values as null
+fn optional_timestamp(array: Option<&StringArray>, idx: usize) -> Option<i64> {
+    optional_string(array, idx).and_then(|value| value.parse().ok())
+}
+
+/// Total size in bytes of the files under `path`, tables and BM25 indexes alike
+fn directory_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+// Staged (blue-green) rebuilds
+mod staging;
+
+// Running chunk counts backing get_statistics
+mod stats;
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,309 @@
+//! Staged (blue-green) table rebuilds
+//!
+//! A warm reindex builds a fresh copy of the index in a staging table while
+//! the active table keeps serving queries, then swaps the staging table in.
+//! The name of the active physical table is recorded in a pointer file next to
+//! the tables, so reopening the database picks up the swapped-in table.
+
+use super::stats::StatsCounters;
+use super::{LanceVectorDB, sql_string};
+use crate::types::ChunkMetadata;
+use crate::vector_db::VectorDatabase;
+use anyhow::{Context, Result};
+use arrow_array::{
+    Array, BooleanArray, FixedSizeListArray, Float32Array, Int64Array, RecordBatch, StringArray,
+    UInt32Array, UInt64Array,
+};
+use futures::stream::TryStreamExt;
+use lancedb::query::{ExecutableQuery, QueryBase};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Pointer file recording the active physical table for a logical table
+fn pointer_path(db_path: &str, base_table_name: &str) -> PathBuf {
+    PathBuf::from(db_path).join(format!("{}.active", base_table_name))
+}
+
+/// Physical table recorded as active for `base_table_name`, if any
+pub(super) fn read_active_table(db_path: &str, base_table_name: &str) -> Option<String> {
+    let name = std::fs::read_to_string(pointer_path(db_path, base_table_name)).ok()?;
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Atomically record `table_name` as the active table for `base_table_name`
+fn write_active_table(db_path: &str, base_table_name: &str, table_name: &str) -> Result<()> {
+    let path = pointer_path(db_path, base_table_name);
+    let tmp_path = path.with_extension("active.tmp");
+    std::fs::create_dir_all(db_path)
+        .with_context(|| format!("Failed to create database directory: {}", db_path))?;
+    std::fs::write(&tmp_path, table_name)
+        .with_context(|| format!("Failed to write table pointer: {}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, &path)
+        .with_context(|| format!("Failed to replace table pointer: {}", path.display()))?;
+    Ok(())
+}
+
+impl LanceVectorDB {
+    /// Create an empty staging table alongside the active one
+    ///
+    /// The staging table is written to like any other table and becomes the
+    /// active table once passed to [`swap_in`](Self::swap_in).
+    pub async fn create_staging(&self, dimension: usize) -> Result<LanceVectorDB> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let staging_name = format!("{}_{}", self.base_table_name, millis);
+
+        let staging = LanceVectorDB::with_path_and_table(&self.db_path, &staging_name)
+            .await?
+            .with_phase_spans(self.phase_spans)
+            .with_ann_params(self.ann_params)
+            .with_ann_index(self.ann_index)
+            .with_distance(self.distance)
+            .with_bm25_analyzer(self.bm25_analyzer.clone())
+            .with_bm25_writer_lock_timeout(self.bm25_writer_lock_timeout)
+            .with_bm25_stale_lock_timeout(self.bm25_stale_lock_timeout)
+            .with_doc_boost(self.doc_boost.clone());
+        staging
+            .initialize(dimension)
+            .await
+            .with_context(|| format!("Failed to create staging table '{}'", staging_name))?;
+
+        tracing::info!("Created staging table '{}'", staging_name);
+        Ok(staging)
+    }
+
+    /// Copy every row not belonging to `exclude_root` into `staging`
+    ///
+    /// Vectors are copied as stored, so other projects keep their embeddings
+    /// without being re-embedded. BM25 indexes are rebuilt in the staging table.
+    pub async fn copy_roots_except(
+        &self,
+        staging: &LanceVectorDB,
+        exclude_root: &str,
+    ) -> Result<usize> {
+        let table = self.get_table().await?;
+        let filter = format!(
+            "root_path IS NULL OR root_path != {}",
+            sql_string(exclude_root)
+        );
+
+        let mut stream = table
+            .query()
+            .only_if(filter)
+            .execute()
+            .await
+            .context("Failed to query rows to copy")?;
+
+        let mut copied = 0;
+        while let Some(batch) = stream
+            .try_next()
+            .await
+            .context("Failed to read rows to copy")?
+        {
+            for (root_path, (embeddings, metadata, contents)) in rows_by_root(&batch)? {
+                copied += staging
+                    .store_embeddings(embeddings, metadata, contents, &root_path)
+                    .await
+                    .context("Failed to copy rows into staging table")?;
+            }
+        }
+
+        tracing::info!(
+            "Copied {} rows from '{}' into staging table '{}'",
+            copied,
+            self.table_name(),
+            staging.table_name()
+        );
+        Ok(copied)
+    }
+
+    /// Copy every row not belonging to `rebuilt_root` into `staging` and swap
+    /// it in, as one step
+    ///
+    /// Writes to the active table wait until the swap is done, so rows stored
+    /// for other roots during the copy cannot be left behind in the old table.
+    /// The staged keyword indexes are committed afterwards, releasing their
+    /// writers.
+    pub async fn swap_in_with_roots_except(
+        &self,
+        staging: &LanceVectorDB,
+        rebuilt_root: &str,
+    ) -> Result<()> {
+        {
+            let _exclusive = self.write_lock.write().await;
+            self.copy_roots_except(staging, rebuilt_root).await?;
+            self.swap_in(staging).await?;
+        }
+        self.flush_bm25().await
+    }
+
+    /// Make `staging` the active table and drop the previous one
+    ///
+    /// Queries see either the old or the new table, never a mix. The previous
+    /// table and its BM25 indexes are removed on a best-effort basis.
+    pub async fn swap_in(&self, staging: &LanceVectorDB) -> Result<()> {
+        let new_table = staging.table_name();
+        write_active_table(&self.db_path, &self.base_table_name, &new_table)?;
+
+        let (old_table, old_hashes) = {
+            // Hold the BM25 lock across the swap so searches see a consistent pair
+            let mut indexes = self
+                .bm25_indexes
+                .write()
+                .map_err(|e| anyhow::anyhow!("Failed to acquire BM25 write lock: {}", e))?;
+            let mut staged = staging
+                .bm25_indexes
+                .write()
+                .map_err(|e| anyhow::anyhow!("Failed to acquire BM25 write lock: {}", e))?;
+            let mut table_name = self
+                .table_name
+                .write()
+                .map_err(|e| anyhow::anyhow!("Failed to acquire table name lock: {}", e))?;
+
+            let old_table = std::mem::replace(&mut *table_name, new_table.clone());
+            let old_indexes = std::mem::replace(&mut *indexes, std::mem::take(&mut *staged));
+            (old_table, old_indexes.into_keys().collect::<Vec<_>>())
+        };
+
+        // The staging table's counts now describe the active table
+        let staged_stats = std::mem::take(&mut *staging.lock_stats());
+        *self.lock_stats() = staged_stats;
+        self.save_stats();
+        let _ = std::fs::remove_file(StatsCounters::path(&self.db_path, &old_table));
+
+        tracing::info!("Swapped table '{}' in for '{}'", new_table, old_table);
+
+        if let Err(e) = self.connection.drop_table(&old_table, &[]).await {
+            tracing::warn!("Failed to drop previous table '{}': {}", old_table, e);
+        }
+        for hash in old_hashes {
+            let path = self.bm25_path(&old_table, &hash);
+            if let Err(e) = std::fs::remove_dir_all(&path) {
+                tracing::warn!("Failed to remove BM25 index at {}: {}", path, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Discard a staging table that will not be swapped in
+    pub async fn drop_staging(&self, staging: &LanceVectorDB) -> Result<()> {
+        let staging_table = staging.table_name();
+        let hashes: Vec<String> = staging
+            .bm25_indexes
+            .write()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire BM25 write lock: {}", e))?
+            .drain()
+            .map(|(hash, _)| hash)
+            .collect();
+
+        self.connection
+            .drop_table(&staging_table, &[])
+            .await
+            .with_context(|| format!("Failed to drop staging table '{}'", staging_table))?;
+        for hash in hashes {
+            let _ = std::fs::remove_dir_all(staging.bm25_path(&staging_table, &hash));
+        }
+        let _ = std::fs::remove_file(StatsCounters::path(&self.db_path, &staging_table));
+
+        tracing::info!("Dropped staging table '{}'", staging_table);
+        Ok(())
+    }
+}
+
+/// Embeddings, metadata and contents of rows sharing a root path
+type RootRows = (Vec<Vec<f32>>, Vec<ChunkMetadata>, Vec<String>);
+
+/// Rebuild the stored rows of a batch, grouped by root path
+fn rows_by_root(batch: &RecordBatch) -> Result<HashMap<String, RootRows>> {
+    let string_column = |name: &str| -> Result<&StringArray> {
+        batch
+            .column_by_name(name)
+            .with_context(|| format!("Missing {} column", name))?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .with_context(|| format!("Invalid {} type", name))
+    };
+    let line_column = |name: &str| -> Result<&UInt32Array> {
+        batch
+            .column_by_name(name)
+            .with_context(|| format!("Missing {} column", name))?
+            .as_any()
+            .downcast_ref::<UInt32Array>()
+            .with_context(|| format!("Invalid {} type", name))
+    };
+    let optional_column = |name: &str| {
+        batch
+            .column_by_name(name)
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+    };
+
+    let vectors = batch
+        .column_by_name("vector")
+        .context("Missing vector column")?
+        .as_any()
+        .downcast_ref::<FixedSizeListArray>()
+        .context("Invalid vector type")?;
+    let file_paths = string_column("file_path")?;
+    let start_lines = line_column("start_line")?;
+    let end_lines = line_column("end_line")?;
+    let languages = string_column("language")?;
+    let extensions = string_column("extension")?;
+    let file_hashes = string_column("file_hash")?;
+    let indexed_ats = string_column("indexed_at")?;
+    let contents = string_column("content")?;
+    let root_paths = optional_column("root_path");
+    let projects = optional_column("project");
+    let symbol_kinds = optional_column("symbol_kind");
+    let branches = optional_column("branch");
+    let binaries = batch
+        .column_by_name("binary")
+        .and_then(|c| c.as_any().downcast_ref::<BooleanArray>());
+    let file_sizes = batch
+        .column_by_name("file_size")
+        .and_then(|c| c.as_any().downcast_ref::<UInt64Array>());
+    let commit_dates = batch
+        .column_by_name("commit_date")
+        .and_then(|c| c.as_any().downcast_ref::<Int64Array>());
+
+    let mut rows: HashMap<String, RootRows> = HashMap::new();
+    for idx in 0..batch.num_rows() {
+        let vector = vectors.value(idx);
+        let vector = vector
+            .as_any()
+            .downcast_ref::<Float32Array>()
+            .context("Invalid vector item type")?
+            .values()
+            .to_vec();
+
+        let root_path = super::optional_string(root_paths, idx);
+        let metadata = ChunkMetadata {
+            file_path: file_paths.value(idx).to_string(),
+            root_path: root_path.clone(),
+            project: super::optional_string(projects, idx),
+            start_line: start_lines.value(idx) as usize,
+            end_line: end_lines.value(idx) as usize,
+            language: Some(languages.value(idx).to_string()),
+            extension: Some(extensions.value(idx).to_string()),
+            file_hash: file_hashes.value(idx).to_string(),
+            indexed_at: indexed_ats.value(idx).parse().unwrap_or(0),
+            symbol_kind: super::optional_string(symbol_kinds, idx),
+            binary: super::optional_bool(binaries, idx),
+            file_size: super::optional_u64(file_sizes, idx),
+            branch: super::optional_string(branches, idx),
+            commit_date: super::optional_i64(commit_dates, idx),
+            chunk_method: None,
+        };
+
+        let entry = rows.entry(root_path.unwrap_or_default()).or_default();
+        entry.0.push(vector);
+        entry.1.push(metadata);
+        entry.2.push(contents.value(idx).to_string());
+    }
+
+    Ok(rows)
+}
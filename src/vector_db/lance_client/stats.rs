@@ -0,0 +1,140 @@
+//! Running chunk counts backing `get_statistics`
+//!
+//! Counting chunks per language and file by scanning the table gets slow on
+//! large indexes, so the counts are kept up to date as rows are stored and
+//! deleted, and persisted in a sidecar file next to the table when it is
+//! flushed. Counts whose total disagrees with the table's row count (or whose
+//! sidecar is missing) are rebuilt from a scan.
+
+use crate::types::LanguageStats;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Chunks per file, by language, of one physical table
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct StatsCounters {
+    languages: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+impl StatsCounters {
+    /// Sidecar file holding the counts of `table_name`
+    pub fn path(db_path: &str, table_name: &str) -> PathBuf {
+        PathBuf::from(db_path).join(format!("{}.stats.json", table_name))
+    }
+
+    /// Counts recorded in `path`, if any
+    pub fn load(path: &Path) -> Option<Self> {
+        let json = std::fs::read_to_string(path).ok()?;
+        match serde_json::from_str(&json) {
+            Ok(counters) => Some(counters),
+            Err(e) => {
+                tracing::warn!(
+                    "Ignoring unreadable statistics file {}: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Atomically write the counts to `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("json.tmp");
+        let json = serde_json::to_string(self).context("Failed to serialize statistics")?;
+        std::fs::write(&tmp_path, json)
+            .with_context(|| format!("Failed to write statistics: {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to replace statistics: {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Count `chunks` more chunks of `file_path` in `language`
+    pub fn add(&mut self, language: &str, file_path: &str, chunks: usize) {
+        *self
+            .languages
+            .entry(language.to_string())
+            .or_default()
+            .entry(file_path.to_string())
+            .or_default() += chunks;
+    }
+
+    /// Count `chunks` fewer chunks of `file_path` in `language`
+    pub fn remove(&mut self, language: &str, file_path: &str, chunks: usize) {
+        let Some(files) = self.languages.get_mut(language) else {
+            return;
+        };
+        if let Some(count) = files.get_mut(file_path) {
+            *count = count.saturating_sub(chunks);
+            if *count == 0 {
+                files.remove(file_path);
+            }
+        }
+        if files.is_empty() {
+            self.languages.remove(language);
+        }
+    }
+
+    /// Drop every chunk of `file_path`, whatever its language
+    pub fn remove_file(&mut self, file_path: &str) {
+        for files in self.languages.values_mut() {
+            files.remove(file_path);
+        }
+        self.languages.retain(|_, files| !files.is_empty());
+    }
+
+    /// Total number of chunks counted
+    pub fn total_chunks(&self) -> usize {
+        self.languages
+            .values()
+            .flat_map(|files| files.values())
+            .sum()
+    }
+
+    /// Number of distinct files counted
+    pub fn total_files(&self) -> usize {
+        let files: HashSet<&str> = self
+            .languages
+            .values()
+            .flat_map(|files| files.keys().map(String::as_str))
+            .collect();
+        files.len()
+    }
+
+    /// Files and chunks per language, most chunks first
+    pub fn language_breakdown(&self) -> Vec<LanguageStats> {
+        let mut breakdown: Vec<LanguageStats> = self
+            .languages
+            .iter()
+            .map(|(language, files)| LanguageStats {
+                language: language.clone(),
+                file_count: files.len(),
+                chunk_count: files.values().sum(),
+            })
+            .collect();
+        breakdown.sort_by(|a, b| b.chunk_count.cmp(&a.chunk_count));
+        breakdown
+    }
+}
+
+/// Counts of the active table and whether they changed since they were saved
+#[derive(Debug, Default)]
+pub(super) struct StatsState {
+    /// `None` until loaded from the sidecar or rebuilt from a scan
+    pub counters: Option<StatsCounters>,
+    pub unsaved: bool,
+}
+
+impl StatsState {
+    /// Apply `update` to the counts, if they are known
+    ///
+    /// Unknown counts stay unknown; they are rebuilt on the next `get_statistics`.
+    pub fn update(&mut self, update: impl FnOnce(&mut StatsCounters)) {
+        if let Some(counters) = &mut self.counters {
+            update(counters);
+            self.unsaved = true;
+        }
+    }
+}
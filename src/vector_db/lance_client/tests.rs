@@ -14,6 +14,12 @@ mod tests {
             extension: Some("rs".to_string()),
             file_hash: "test_hash_123".to_string(),
             indexed_at: 1234567890,
+            symbol_kind: None,
+            binary: false,
+            file_size: None,
+            branch: None,
+            commit_date: None,
+            chunk_method: None,
         }
     }
 
@@ -30,7 +36,7 @@ mod tests {
         assert!(db.is_ok());
 
         let db = db.unwrap();
-        assert_eq!(db.table_name, "code_embeddings");
+        assert_eq!(db.table_name(), "code_embeddings");
         assert_eq!(db.db_path, db_path);
     }
 
@@ -60,6 +66,204 @@ mod tests {
         assert!(table_names.contains(&"code_embeddings".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_custom_table_is_separate_from_default() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+
+        let default_db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        default_db.initialize(384).await.unwrap();
+
+        let custom_db = LanceVectorDB::with_path_and_table(&db_path, "code_embeddings_custom")
+            .await
+            .unwrap();
+        custom_db.initialize(768).await.unwrap();
+        custom_db
+            .store_embeddings(
+                vec![vec![0.1; 768]],
+                vec![create_test_metadata("/test/file.rs", 1, 10)],
+                vec!["fn custom() {}".to_string()],
+                "/test",
+            )
+            .await
+            .unwrap();
+
+        let table_names = default_db.connection.table_names().execute().await.unwrap();
+        assert!(table_names.contains(&"code_embeddings".to_string()));
+        assert!(table_names.contains(&"code_embeddings_custom".to_string()));
+
+        assert_eq!(default_db.get_statistics().await.unwrap().total_vectors, 0);
+        assert_eq!(custom_db.get_statistics().await.unwrap().total_vectors, 1);
+        assert_ne!(
+            default_db.bm25_path_for_root("/test"),
+            custom_db.bm25_path_for_root("/test")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_staged_table_serves_old_results_until_swapped_in() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+
+        let metadata = |file_path: &str, root: &str| ChunkMetadata {
+            root_path: Some(root.to_string()),
+            ..create_test_metadata(file_path, 1, 10)
+        };
+
+        let db = LanceVectorDB::with_path(&db_path)
+            .await
+            .unwrap()
+            .with_distance(crate::vector_db::DistanceMetric::Cosine);
+        db.initialize(384).await.unwrap();
+        db.store_embeddings(
+            vec![vec![0.1; 384]],
+            vec![metadata("/a/old.rs", "/a")],
+            vec!["fn old_version() {}".to_string()],
+            "/a",
+        )
+        .await
+        .unwrap();
+        db.store_embeddings(
+            vec![vec![0.2; 384]],
+            vec![metadata("/b/other.rs", "/b")],
+            vec!["fn other_project() {}".to_string()],
+            "/b",
+        )
+        .await
+        .unwrap();
+
+        // Rebuild root /a into a staging table, compared with the same metric
+        let staging = db.create_staging(384).await.unwrap();
+        assert_eq!(staging.distance, db.distance);
+        staging
+            .store_embeddings(
+                vec![vec![0.1; 384]],
+                vec![metadata("/a/new.rs", "/a")],
+                vec!["fn new_version() {}".to_string()],
+                "/a",
+            )
+            .await
+            .unwrap();
+        assert_eq!(db.copy_roots_except(&staging, "/a").await.unwrap(), 1);
+
+        let files = |results: Vec<crate::types::SearchResult>| {
+            let mut files: Vec<_> = results.into_iter().map(|r| r.file_path).collect();
+            files.sort();
+            files
+        };
+
+        // The live table still serves the old index during the rebuild
+        let results = db
+            .search(vec![0.1; 384], "version", 10, 0.0, &[], None, false, false)
+            .await
+            .unwrap();
+        assert_eq!(files(results), vec!["/a/old.rs", "/b/other.rs"]);
+
+        db.swap_in(&staging).await.unwrap();
+
+        let results = db
+            .search(vec![0.1; 384], "version", 10, 0.0, &[], None, false, false)
+            .await
+            .unwrap();
+        assert_eq!(files(results), vec!["/a/new.rs", "/b/other.rs"]);
+
+        // The old table is gone and reopening picks up the swapped-in table
+        let table_names = db.connection.table_names().execute().await.unwrap();
+        assert!(!table_names.contains(&"code_embeddings".to_string()));
+
+        let reopened = LanceVectorDB::with_path(&db_path).await.unwrap();
+        assert_eq!(reopened.table_name(), staging.table_name());
+        assert_eq!(reopened.get_statistics().await.unwrap().total_vectors, 2);
+    }
+
+    #[tokio::test]
+    async fn test_store_during_swap_in_is_kept() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+
+        let metadata = |file_path: &str, root: &str| ChunkMetadata {
+            root_path: Some(root.to_string()),
+            ..create_test_metadata(file_path, 1, 10)
+        };
+
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+        db.store_embeddings(
+            vec![vec![0.2; 384]],
+            vec![metadata("/b/other.rs", "/b")],
+            vec!["fn other_project() {}".to_string()],
+            "/b",
+        )
+        .await
+        .unwrap();
+
+        let staging = db.create_staging(384).await.unwrap();
+        staging
+            .store_embeddings(
+                vec![vec![0.1; 384]],
+                vec![metadata("/a/new.rs", "/a")],
+                vec!["fn new_version() {}".to_string()],
+                "/a",
+            )
+            .await
+            .unwrap();
+
+        // A store for another root lands either before the copy or after the swap
+        let (swapped, stored) = tokio::join!(
+            db.swap_in_with_roots_except(&staging, "/a"),
+            db.store_embeddings(
+                vec![vec![0.3; 384]],
+                vec![metadata("/c/late.rs", "/c")],
+                vec!["fn late_project() {}".to_string()],
+                "/c",
+            )
+        );
+        swapped.unwrap();
+        stored.unwrap();
+
+        let mut files: Vec<_> = db
+            .search(vec![0.1; 384], "project", 10, 0.0, &[], None, false, false)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.file_path)
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["/a/new.rs", "/b/other.rs", "/c/late.rs"]);
+    }
+
+    #[tokio::test]
+    async fn test_drop_staging_keeps_live_table() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        let staging = db.create_staging(384).await.unwrap();
+        db.drop_staging(&staging).await.unwrap();
+
+        let table_names = db.connection.table_names().execute().await.unwrap();
+        assert_eq!(table_names, vec!["code_embeddings".to_string()]);
+        assert_eq!(db.table_name(), "code_embeddings");
+    }
+
     #[tokio::test]
     async fn test_initialize_idempotent() {
         let temp_dir = TempDir::new().unwrap();
@@ -122,7 +326,7 @@ mod tests {
         // Verify storage by searching
         let query = vec![0.1; 384];
         let results = db
-            .search(query, "main", 10, 0.0, None, None, false)
+            .search(query, "main", 10, 0.0, &[], None, false, false)
             .await
             .unwrap();
         assert_eq!(results.len(), 2);
@@ -150,7 +354,7 @@ mod tests {
         // Search with pure vector (hybrid=false)
         let query = vec![0.1; 384];
         let results = db
-            .search(query, "main", 10, 0.0, None, None, false)
+            .search(query, "main", 10, 0.0, &[], None, false, false)
             .await
             .unwrap();
 
@@ -183,7 +387,7 @@ mod tests {
         // Search with hybrid (hybrid=true)
         let query = vec![0.1; 384];
         let results = db
-            .search(query, "println", 10, 0.0, None, None, true)
+            .search(query, "println", 10, 0.0, &[], None, true, false)
             .await
             .unwrap();
 
@@ -193,6 +397,299 @@ mod tests {
         assert!(results[0].keyword_score.is_some());
     }
 
+    #[tokio::test]
+    async fn test_search_results_carry_chunk_ids() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        let mut commit = create_test_metadata("git:///repo", 0, 0);
+        commit.file_hash = "abc123def456".to_string();
+        db.store_embeddings(
+            vec![vec![0.1; 384], vec![0.1; 384]],
+            vec![create_test_metadata("test.rs", 5, 10), commit],
+            vec![
+                "fn parse_config() {}".to_string(),
+                "Commit Message:\nparse config".to_string(),
+            ],
+            "/test/root",
+        )
+        .await
+        .unwrap();
+
+        for hybrid in [false, true] {
+            let mut ids: Vec<String> = db
+                .search(
+                    vec![0.1; 384],
+                    "parse config",
+                    10,
+                    0.0,
+                    &[],
+                    None,
+                    hybrid,
+                    false,
+                )
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| r.chunk_id)
+                .collect();
+            ids.sort();
+            assert_eq!(ids, ["git:abc123def456", "test.rs:5"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_distance_metric_changes_ranking() {
+        use crate::vector_db::DistanceMetric;
+
+        // `aligned` points exactly along the query but is far from it; `near`
+        // is close to the query at an angle
+        let axis = |x: f32, y: f32| {
+            let mut v = vec![0.0; 384];
+            v[0] = x;
+            v[1] = y;
+            v
+        };
+        let query = axis(1.0, 0.0);
+
+        let mut rankings = Vec::new();
+        for distance in [DistanceMetric::L2, DistanceMetric::Cosine] {
+            let temp_dir = TempDir::new().unwrap();
+            let db_path = temp_dir
+                .path()
+                .join("lancedb")
+                .to_string_lossy()
+                .to_string();
+            let db = LanceVectorDB::with_path(&db_path)
+                .await
+                .unwrap()
+                .with_distance(distance);
+            db.initialize(384).await.unwrap();
+            db.store_embeddings(
+                vec![axis(3.0, 0.0), axis(0.8, 0.6)],
+                vec![
+                    create_test_metadata("aligned.rs", 1, 1),
+                    create_test_metadata("near.rs", 1, 1),
+                ],
+                vec!["fn aligned() {}".to_string(), "fn near() {}".to_string()],
+                "/test/root",
+            )
+            .await
+            .unwrap();
+
+            let results = db
+                .search(query.clone(), "", 10, 0.0, &[], None, false, false)
+                .await
+                .unwrap();
+            rankings.push(
+                results
+                    .iter()
+                    .map(|r| (r.file_path.clone(), r.score))
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        // L2: 0.4 for near against 4.0 for aligned
+        assert_eq!(rankings[0][0].0, "near.rs");
+        assert!((rankings[0][0].1 - 1.0 / 1.4).abs() < 1e-4);
+        // Cosine: similarity 1.0 for aligned against 0.8 for near
+        assert_eq!(rankings[1][0].0, "aligned.rs");
+        assert!((rankings[1][0].1 - 1.0).abs() < 1e-4);
+        assert!((rankings[1][1].1 - 0.8).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn test_search_reports_match_source() {
+        use crate::types::MatchSource;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        let embeddings = vec![vec![0.1; 384]];
+        let metadata = vec![create_test_metadata("test.rs", 1, 10)];
+        let contents = vec!["fn main() { println!(\"hello\"); }".to_string()];
+        db.store_embeddings(embeddings, metadata, contents, "/test/root")
+            .await
+            .unwrap();
+
+        // Keyword and vector both match
+        let results = db
+            .search(vec![0.1; 384], "println", 10, 0.0, &[], None, true, false)
+            .await
+            .unwrap();
+        assert_eq!(results[0].matched_via, MatchSource::Both);
+
+        // No keyword overlap: semantic match only
+        let results = db
+            .search(vec![0.1; 384], "zebra", 10, 0.0, &[], None, true, false)
+            .await
+            .unwrap();
+        assert_eq!(results[0].matched_via, MatchSource::Vector);
+
+        // Pure vector search
+        let results = db
+            .search(vec![0.1; 384], "println", 10, 0.0, &[], None, false, false)
+            .await
+            .unwrap();
+        assert_eq!(results[0].matched_via, MatchSource::Vector);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_keyword_only_hit_reports_keyword_source() {
+        use crate::types::MatchSource;
+        use crate::vector_db::HYBRID_CANDIDATE_FACTOR;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        // Enough close neighbours to fill every vector candidate slot, and one
+        // distant chunk that only the keyword side can find
+        let limit = 2;
+        let neighbours = limit * HYBRID_CANDIDATE_FACTOR + 2;
+        let mut embeddings = vec![vec![0.1; 384]; neighbours];
+        let mut metadata: Vec<_> = (0..neighbours)
+            .map(|i| create_test_metadata(&format!("near_{}.rs", i), 1, 1))
+            .collect();
+        let mut contents: Vec<_> = (0..neighbours)
+            .map(|i| format!("fn helper_{}() {{}}", i))
+            .collect();
+        embeddings.push(vec![-0.1; 384]);
+        metadata.push(create_test_metadata("far.rs", 1, 1));
+        contents.push("fn frobnicate_widget() {}".to_string());
+        db.store_embeddings(embeddings, metadata, contents, "/test/root")
+            .await
+            .unwrap();
+
+        let results = db
+            .search(
+                vec![0.1; 384],
+                "frobnicate_widget",
+                limit,
+                0.0,
+                &[],
+                None,
+                true,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let far = results
+            .iter()
+            .find(|r| r.file_path == "far.rs")
+            .expect("the keyword-only hit is returned");
+        assert_eq!(far.matched_via, MatchSource::Keyword);
+        assert_eq!(far.vector_score, 0.0);
+        assert!(far.keyword_score.is_some());
+        assert!(
+            results
+                .iter()
+                .filter(|r| r.file_path != "far.rs")
+                .all(|r| r.matched_via == MatchSource::Vector)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_results_highlight_keyword_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        let content = "fn main() {\n    println!(\"hello\");\n}";
+        let metadata = vec![create_test_metadata("test.rs", 1, 3)];
+        db.store_embeddings(
+            vec![vec![0.1; 384]],
+            metadata,
+            vec![content.to_string()],
+            "/test/root",
+        )
+        .await
+        .unwrap();
+
+        let highlighted = |highlights: &[(usize, usize)]| -> Vec<String> {
+            highlights
+                .iter()
+                .map(|&(start, end)| content[start..end].to_string())
+                .collect()
+        };
+
+        let results = db
+            .search(vec![0.1; 384], "println", 10, 0.0, &[], None, true, false)
+            .await
+            .unwrap();
+        assert_eq!(highlighted(&results[0].highlights), ["println"]);
+
+        let results = db.search_keyword("println", 10, &[], None).await.unwrap();
+        assert_eq!(highlighted(&results[0].highlights), ["println"]);
+
+        // Vector-only results carry no highlights
+        let results = db
+            .search(vec![0.1; 384], "println", 10, 0.0, &[], None, false, false)
+            .await
+            .unwrap();
+        assert!(results[0].highlights.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_flushed_keyword_docs_survive_a_crash() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        let metadata = vec![
+            create_test_metadata("main.rs", 1, 3),
+            create_test_metadata("lib.rs", 1, 3),
+        ];
+        let contents = vec![
+            "fn main() { println!(\"hello\"); }".to_string(),
+            "pub fn parse_config() {}".to_string(),
+        ];
+        db.store_embeddings(vec![vec![0.1; 384]; 2], metadata, contents, "/test/root")
+            .await
+            .unwrap();
+        db.flush().await.unwrap();
+
+        // Skip every destructor, as a crash right after indexing would
+        std::mem::forget(db);
+
+        let reopened = LanceVectorDB::with_path(&db_path).await.unwrap();
+        reopened.get_or_create_bm25("/test/root").unwrap();
+        let results = reopened
+            .search_keyword("parse_config", 10, &[], None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_path, "lib.rs");
+    }
+
     #[tokio::test]
     async fn test_search_with_min_score() {
         let temp_dir = TempDir::new().unwrap();
@@ -215,7 +712,7 @@ mod tests {
         // Search with high min_score (should filter out results)
         let query = vec![0.9; 384]; // Very different from stored embedding
         let results = db
-            .search(query, "main", 10, 0.99, None, None, false)
+            .search(query, "main", 10, 0.99, &[], None, false, false)
             .await
             .unwrap();
 
@@ -255,9 +752,10 @@ mod tests {
                 "main",
                 10,
                 0.0,
-                Some("project-a".to_string()),
+                &["project-a".to_string()],
                 None,
                 false,
+                false,
             )
             .await
             .unwrap();
@@ -268,6 +766,55 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_search_across_several_projects() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        let projects = ["alpha", "beta", "o'gamma"];
+        let embeddings = vec![vec![0.1; 384]; projects.len()];
+        let metadata = projects
+            .iter()
+            .enumerate()
+            .map(|(i, project)| {
+                let mut meta = create_test_metadata(&format!("file{}.rs", i), 1, 10);
+                meta.project = Some(project.to_string());
+                meta
+            })
+            .collect();
+        let contents = vec!["fn handle_request() {}".to_string(); projects.len()];
+        db.store_embeddings(embeddings, metadata, contents, "/test/root")
+            .await
+            .unwrap();
+
+        let wanted = ["alpha".to_string(), "o'gamma".to_string()];
+        for hybrid in [false, true] {
+            let results = db
+                .search(
+                    vec![0.1; 384],
+                    "handle_request",
+                    10,
+                    0.0,
+                    &wanted,
+                    None,
+                    hybrid,
+                    false,
+                )
+                .await
+                .unwrap();
+
+            let mut found: Vec<_> = results.iter().filter_map(|r| r.project.clone()).collect();
+            found.sort();
+            assert_eq!(found, wanted, "hybrid: {}", hybrid);
+        }
+    }
+
     #[tokio::test]
     async fn test_search_filtered_by_extension() {
         let temp_dir = TempDir::new().unwrap();
@@ -299,12 +846,13 @@ mod tests {
                 "main",
                 10,
                 0.0,
-                None,
+                &[],
                 None,
                 false,
                 vec!["rs".to_string()],
                 vec![],
                 vec![],
+                vec![],
             )
             .await
             .unwrap();
@@ -315,6 +863,115 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_search_filtered_by_selective_symbol_kind_fills_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        // Far more functions than structs, all closer to the query
+        let tagged = |file_path: &str, kind: &str| ChunkMetadata {
+            symbol_kind: Some(kind.to_string()),
+            ..create_test_metadata(file_path, 1, 1)
+        };
+        let mut embeddings = vec![vec![0.1; 384]; 40];
+        let mut metadata: Vec<_> = (0..40)
+            .map(|i| tagged(&format!("function_{}.rs", i), "function"))
+            .collect();
+        let mut contents: Vec<_> = (0..40).map(|i| format!("fn parse_{}() {{}}", i)).collect();
+        for i in 0..2 {
+            embeddings.push(vec![-0.1; 384]);
+            metadata.push(tagged(&format!("struct_{}.rs", i), "struct"));
+            contents.push(format!("struct Parser{} {{}}", i));
+        }
+        db.store_embeddings(embeddings, metadata, contents, "/test/root")
+            .await
+            .unwrap();
+
+        for hybrid in [false, true] {
+            let results = db
+                .search_filtered(
+                    vec![0.1; 384],
+                    "parse",
+                    2,
+                    0.0,
+                    &[],
+                    None,
+                    hybrid,
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec!["struct".to_string()],
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(results.len(), 2, "hybrid: {}", hybrid);
+            assert!(
+                results
+                    .iter()
+                    .all(|r| r.symbol_kind.as_deref() == Some("struct"))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_excluded_keyword_hits_do_not_crowd_out_results() {
+        use crate::vector_db::HYBRID_CANDIDATE_FACTOR;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        // Only git commits mention the query, and there are more of them than
+        // the limit, so unfiltered keyword hits would fill the fused ranking
+        let limit = 2;
+        let commits = limit * HYBRID_CANDIDATE_FACTOR + 2;
+        let mut embeddings = vec![vec![0.1; 384]; 3];
+        let mut metadata: Vec<_> = (0..3)
+            .map(|i| create_test_metadata(&format!("code_{}.rs", i), 1, 1))
+            .collect();
+        let mut contents: Vec<_> = (0..3).map(|i| format!("fn helper_{}() {{}}", i)).collect();
+        for i in 0..commits {
+            embeddings.push(vec![0.1; 384]);
+            metadata.push(ChunkMetadata {
+                language: Some("git-commit".to_string()),
+                ..create_test_metadata(&format!("commit_{}", i), 1, 1)
+            });
+            contents.push(format!("Frobnicate the widget, take {}", i));
+        }
+        db.store_embeddings(embeddings, metadata, contents, "/test/root")
+            .await
+            .unwrap();
+
+        let results = db
+            .search(
+                vec![0.1; 384],
+                "frobnicate",
+                limit,
+                0.0,
+                &[],
+                None,
+                true,
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), limit);
+        assert!(results.iter().all(|r| r.language == "Rust"));
+    }
+
     #[tokio::test]
     async fn test_search_filtered_by_language() {
         let temp_dir = TempDir::new().unwrap();
@@ -343,12 +1000,13 @@ mod tests {
                 "main",
                 10,
                 0.0,
-                None,
+                &[],
                 None,
                 false,
                 vec![],
                 vec!["Rust".to_string()],
                 vec![],
+                vec![],
             )
             .await
             .unwrap();
@@ -390,12 +1048,13 @@ mod tests {
                 "main",
                 10,
                 0.0,
-                None,
+                &[],
                 None,
                 false,
                 vec![],
                 vec![],
                 vec!["src/".to_string()],
+                vec![],
             )
             .await
             .unwrap();
@@ -425,25 +1084,134 @@ mod tests {
         ];
         let contents = vec!["fn main() {}".to_string(), "fn test() {}".to_string()];
 
-        db.store_embeddings(embeddings, metadata, contents, "/test/root")
+        db.store_embeddings(embeddings, metadata, contents, "/test/root")
+            .await
+            .unwrap();
+
+        // Delete one file
+        let result = db.delete_by_file("test1.rs").await;
+        assert_eq!(result.unwrap(), 1);
+
+        // Nothing left to delete the second time
+        assert_eq!(db.delete_by_file("test1.rs").await.unwrap(), 0);
+
+        // Verify deletion
+        let query = vec![0.15; 384];
+        let results = db
+            .search(query, "main", 10, 0.0, &[], None, false, false)
+            .await
+            .unwrap();
+
+        // Should not contain deleted file
+        for result in &results {
+            assert_ne!(result.file_path, "test1.rs");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quotes_in_paths_and_projects_are_escaped() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        let metadata = ChunkMetadata {
+            project: Some("o'brien".to_string()),
+            ..create_test_metadata("src/it's.rs", 1, 10)
+        };
+        db.store_embeddings(
+            vec![vec![0.1; 384]],
+            vec![metadata],
+            vec!["fn main() {}".to_string()],
+            "/test/root",
+        )
+        .await
+        .unwrap();
+
+        let results = db
+            .search(
+                vec![0.1; 384],
+                "main",
+                10,
+                0.0,
+                &["o'brien".to_string()],
+                None,
+                false,
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        assert_eq!(db.delete_by_file("src/it's.rs").await.unwrap(), 1);
+        assert_eq!(db.get_statistics().await.unwrap().total_points, 0);
+    }
+
+    #[tokio::test]
+    async fn test_delete_where_path_prefix() {
+        use crate::types::DeleteFilter;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        let root = "/test/root";
+        let metadata = ["src/a.rs", "src/b.rs", "tests/c.rs"]
+            .iter()
+            .map(|path| ChunkMetadata {
+                root_path: Some(root.to_string()),
+                ..create_test_metadata(path, 1, 10)
+            })
+            .collect();
+        let contents = vec![
+            "fn widget_a() {}".to_string(),
+            "fn widget_b() {}".to_string(),
+            "fn widget_c() {}".to_string(),
+        ];
+        db.store_embeddings(vec![vec![0.5; 384]; 3], metadata, contents, root)
             .await
             .unwrap();
 
-        // Delete one file
-        let result = db.delete_by_file("test1.rs").await;
-        assert!(result.is_ok());
+        // An empty filter would match everything
+        assert!(db.delete_where(&DeleteFilter::default()).await.is_err());
+
+        let filter = DeleteFilter {
+            path_prefix: Some("src/".to_string()),
+            ..Default::default()
+        };
+        let deleted = db.delete_where(&filter).await.unwrap();
+        assert_eq!(deleted.count, 2);
+        assert_eq!(
+            deleted.files,
+            vec![
+                (Some(root.to_string()), "src/a.rs".to_string()),
+                (Some(root.to_string()), "src/b.rs".to_string()),
+            ]
+        );
 
-        // Verify deletion
-        let query = vec![0.15; 384];
+        // Only the chunk outside the prefix is left, in both indexes
         let results = db
-            .search(query, "main", 10, 0.0, None, None, false)
+            .search(vec![0.5; 384], "", 10, 0.0, &[], None, false, false)
             .await
             .unwrap();
+        let paths: Vec<_> = results.iter().map(|r| r.file_path.as_str()).collect();
+        assert_eq!(paths, vec!["tests/c.rs"]);
 
-        // Should not contain deleted file
-        for result in &results {
-            assert_ne!(result.file_path, "test1.rs");
-        }
+        let bm25_indexes = db.bm25_indexes.read().unwrap();
+        let keyword_hits: usize = bm25_indexes
+            .values()
+            .map(|bm25| bm25.search("widget_a widget_b widget_c", 10).unwrap().len())
+            .sum();
+        assert_eq!(keyword_hits, 1);
     }
 
     #[tokio::test]
@@ -529,10 +1297,222 @@ mod tests {
         assert_eq!(stats.language_breakdown.len(), 2);
 
         // Verify language counts (sorted by count descending)
-        assert_eq!(stats.language_breakdown[0].0, "Rust");
-        assert_eq!(stats.language_breakdown[0].1, 2);
-        assert_eq!(stats.language_breakdown[1].0, "Python");
-        assert_eq!(stats.language_breakdown[1].1, 1);
+        assert_eq!(stats.language_breakdown[0].language, "Rust");
+        assert_eq!(stats.language_breakdown[0].chunk_count, 2);
+        assert_eq!(stats.language_breakdown[1].language, "Python");
+        assert_eq!(stats.language_breakdown[1].chunk_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_statistics_counts_files_and_chunks_apart() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        // Two files with three and two chunks
+        let chunks = [
+            ("a.rs", 1),
+            ("a.rs", 11),
+            ("a.rs", 21),
+            ("b.rs", 1),
+            ("b.rs", 11),
+        ];
+        let metadata: Vec<_> = chunks
+            .into_iter()
+            .map(|(file_path, start_line)| {
+                let mut meta = create_test_metadata(file_path, start_line, start_line + 9);
+                meta.language = Some("Rust".to_string());
+                meta
+            })
+            .collect();
+        let contents = (0..metadata.len())
+            .map(|i| format!("fn f{}() {{}}", i))
+            .collect();
+
+        db.store_embeddings(vec![vec![0.1; 384]; 5], metadata, contents, "/test/root")
+            .await
+            .unwrap();
+
+        let stats = db.get_statistics().await.unwrap();
+        assert_eq!(stats.total_points, 5);
+        assert_eq!(stats.total_files, 2);
+        assert_eq!(stats.language_breakdown.len(), 1);
+        assert_eq!(stats.language_breakdown[0].file_count, 2);
+        assert_eq!(stats.language_breakdown[0].chunk_count, 5);
+    }
+
+    #[tokio::test]
+    async fn test_statistics_counters_follow_stores_and_deletes() {
+        use super::super::stats::StatsCounters;
+        use crate::types::DeleteFilter;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        let chunks = [
+            ("a.rs", "Rust", 1),
+            ("a.rs", "Rust", 11),
+            ("b.rs", "Rust", 1),
+            ("c.py", "Python", 1),
+            ("c.py", "Python", 11),
+            ("c.py", "Python", 21),
+        ];
+        let metadata: Vec<_> = chunks
+            .into_iter()
+            .map(|(file_path, language, start_line)| {
+                let mut meta = create_test_metadata(file_path, start_line, start_line + 9);
+                meta.language = Some(language.to_string());
+                meta
+            })
+            .collect();
+        let contents = (0..metadata.len())
+            .map(|i| format!("fn f{}() {{}}", i))
+            .collect();
+        db.store_embeddings(vec![vec![0.1; 384]; 6], metadata, contents, "/test/root")
+            .await
+            .unwrap();
+
+        db.delete_by_file("a.rs").await.unwrap();
+        let filter = DeleteFilter {
+            language: Some("Python".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(db.delete_where(&filter).await.unwrap().count, 3);
+
+        // The running counts agree with the table without a rescan
+        let counters = db.lock_stats().counters.clone().unwrap();
+        assert_eq!(counters.total_chunks(), 1);
+        assert_eq!(counters.total_files(), 1);
+        let stats = db.get_statistics().await.unwrap();
+        assert_eq!(stats.total_points, 1);
+        assert_eq!(stats.total_files, 1);
+        assert_eq!(stats.language_breakdown.len(), 1);
+        assert_eq!(stats.language_breakdown[0].language, "Rust");
+        assert_eq!(stats.language_breakdown[0].chunk_count, 1);
+
+        // Flushed counts are picked up again on reopen
+        db.flush().await.unwrap();
+        let sidecar = StatsCounters::path(&db_path, super::super::DEFAULT_TABLE_NAME);
+        assert!(sidecar.exists());
+        let reopened = LanceVectorDB::with_path(&db_path).await.unwrap();
+        assert_eq!(reopened.lock_stats().counters, Some(counters.clone()));
+
+        // Counts disagreeing with the row count are rebuilt from a scan
+        let mut stale = counters;
+        stale.add("Rust", "gone.rs", 4);
+        stale.save(&sidecar).unwrap();
+        let reopened = LanceVectorDB::with_path(&db_path).await.unwrap();
+        let stats = reopened.get_statistics().await.unwrap();
+        assert_eq!(stats.total_files, 1);
+        assert_eq!(stats.language_breakdown[0].chunk_count, 1);
+        assert_eq!(StatsCounters::load(&sidecar).unwrap().total_chunks(), 1);
+
+        // As are missing ones
+        std::fs::remove_file(&sidecar).unwrap();
+        let reopened = LanceVectorDB::with_path(&db_path).await.unwrap();
+        assert!(reopened.lock_stats().counters.is_none());
+        let stats = reopened.get_statistics().await.unwrap();
+        assert_eq!(stats.total_files, 1);
+        assert!(sidecar.exists());
+    }
+
+    #[tokio::test]
+    async fn test_list_projects() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+        assert!(db.list_projects().await.unwrap().is_empty());
+
+        let mut web = create_test_metadata("app.ts", 1, 10);
+        web.project = Some("web".to_string());
+        web.language = Some("TypeScript".to_string());
+        let mut api_rust = create_test_metadata("main.rs", 1, 10);
+        api_rust.project = Some("api".to_string());
+        let mut api_python = create_test_metadata("tool.py", 1, 10);
+        api_python.project = Some("api".to_string());
+        api_python.language = Some("Python".to_string());
+        let mut api_rust2 = create_test_metadata("lib.rs", 1, 10);
+        api_rust2.project = Some("api".to_string());
+        let mut unnamed = create_test_metadata("notes.md", 1, 10);
+        unnamed.project = None;
+
+        db.store_embeddings(
+            vec![vec![0.1; 384]; 5],
+            vec![web, api_rust, api_python, api_rust2, unnamed],
+            vec!["chunk".to_string(); 5],
+            "/test/root",
+        )
+        .await
+        .unwrap();
+
+        let projects = db.list_projects().await.unwrap();
+        let summary: Vec<_> = projects
+            .iter()
+            .map(|p| (p.name.as_str(), p.chunk_count, p.languages.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("api", 3, vec!["Python".to_string(), "Rust".to_string()]),
+                ("web", 1, vec!["TypeScript".to_string()]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_statistics_reports_database_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        let count = 300;
+        let embeddings = (0..count)
+            .map(|i| vec![i as f32 / count as f32; 384])
+            .collect();
+        let metadata = (0..count)
+            .map(|i| create_test_metadata(&format!("file{}.rs", i), 1, 10))
+            .collect();
+        let contents = (0..count)
+            .map(|i| format!("fn item_{}() {{}}", i))
+            .collect();
+        db.store_embeddings(embeddings, metadata, contents, "/test/root")
+            .await
+            .unwrap();
+
+        let stats = db.get_statistics().await.unwrap();
+        assert_eq!(stats.total_vectors, count);
+        // 300 vectors of 384 f32s alone take over 450 KB
+        let size = stats.size_bytes.unwrap();
+        assert!(size > (count * 384 * 4) as u64, "size was {}", size);
+
+        // Another table in the same directory only counts its own files
+        let other = LanceVectorDB::with_path_and_table(&db_path, "other_embeddings")
+            .await
+            .unwrap();
+        other.initialize(384).await.unwrap();
+        let other_size = other.get_statistics().await.unwrap().size_bytes.unwrap();
+        assert!(other_size < size / 2, "other table size was {}", other_size);
     }
 
     #[tokio::test]
@@ -554,8 +1534,8 @@ mod tests {
     async fn test_create_schema() {
         let schema = LanceVectorDB::create_schema(384);
 
-        // Verify schema has expected fields (12 fields including root_path)
-        assert_eq!(schema.fields().len(), 12);
+        // Verify schema has expected fields (16 fields including root_path)
+        assert_eq!(schema.fields().len(), 16);
         assert_eq!(schema.field(0).name(), "vector");
         assert_eq!(schema.field(1).name(), "id");
         assert_eq!(schema.field(2).name(), "file_path");
@@ -585,7 +1565,18 @@ mod tests {
 
         let batch = batch.unwrap();
         assert_eq!(batch.num_rows(), 2);
-        assert_eq!(batch.num_columns(), 12); // 12 columns including root_path
+        assert_eq!(batch.num_columns(), 16); // 16 columns including root_path
+
+        // Chunk ids are derived from each chunk's origin
+        let ids = batch
+            .column_by_name("id")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<arrow_array::StringArray>()
+            .unwrap();
+        let expected = super::super::chunk_id(&create_test_metadata("test1.rs", 1, 10));
+        assert_eq!(ids.value(0), expected.to_string());
+        assert_ne!(ids.value(0), ids.value(1));
     }
 
     #[tokio::test]
@@ -611,7 +1602,7 @@ mod tests {
         for _ in 0..3 {
             let query = vec![0.1; 384];
             let results = db
-                .search(query, "main", 10, 0.0, None, None, false)
+                .search(query, "main", 10, 0.0, &[], None, false, false)
                 .await
                 .unwrap();
             assert_eq!(results.len(), 1);
@@ -670,7 +1661,7 @@ mod tests {
         // Verify both projects can be searched (hybrid search across all BM25 indexes)
         let query = vec![0.15; 384];
         let results = db
-            .search(query.clone(), "main", 10, 0.0, None, None, true)
+            .search(query.clone(), "main", 10, 0.0, &[], None, true, false)
             .await
             .unwrap();
 
@@ -698,4 +1689,532 @@ mod tests {
             "Should have index for project2"
         );
     }
+
+    #[tokio::test]
+    async fn test_bm25_analyzer_change_rebuilds_index() {
+        use crate::bm25_search::{AnalyzerMismatch, BM25Search};
+
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let root = "/normalized/project";
+        let metadata = |file_path: &str| ChunkMetadata {
+            root_path: Some(root.to_string()),
+            ..create_test_metadata(file_path, 1, 10)
+        };
+
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+        db.store_embeddings(
+            vec![vec![0.1; 384]],
+            vec![metadata("auth.rs")],
+            vec!["fn authenticate_user() {}".to_string()],
+            root,
+        )
+        .await
+        .unwrap();
+        let bm25_path = db.bm25_path_for_root(root);
+        drop(db);
+
+        // Opening the index with another analyzer is detected
+        let err = BM25Search::with_analyzer(&bm25_path, "en_stem")
+            .err()
+            .expect("analyzer mismatch should be detected");
+        assert!(err.downcast_ref::<AnalyzerMismatch>().is_some());
+
+        // Writing with the new analyzer rebuilds the index from stored content
+        let db = LanceVectorDB::with_path(&db_path)
+            .await
+            .unwrap()
+            .with_bm25_analyzer("en_stem");
+        db.store_embeddings(
+            vec![vec![0.9; 384]],
+            vec![metadata("logout.rs")],
+            vec!["fn logout_user() {}".to_string()],
+            root,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            BM25Search::recorded_analyzer(std::path::Path::new(&bm25_path)),
+            "en_stem"
+        );
+        {
+            let indexes = db.bm25_indexes.read().unwrap();
+            let bm25 = indexes.get(&LanceVectorDB::hash_root_path(root)).unwrap();
+            assert_eq!(bm25.get_stats().unwrap().total_documents, 2);
+        }
+
+        // The stemmed index matches other forms of the indexed word
+        let results = db
+            .search(
+                vec![0.1; 384],
+                "authenticating",
+                10,
+                0.0,
+                &[],
+                None,
+                true,
+                false,
+            )
+            .await
+            .unwrap();
+        assert!(results.iter().any(|r| r.keyword_score.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_table_ids_are_migrated_for_keyword_search() {
+        use crate::bm25_search::BM25Search;
+        use crate::types::MatchSource;
+        use crate::vector_db::HYBRID_CANDIDATE_FACTOR;
+        use arrow_array::{
+            FixedSizeListArray, RecordBatch, RecordBatchIterator, StringArray, UInt32Array,
+            types::Float32Type,
+        };
+        use arrow_schema::{DataType, Field, Schema};
+        use std::sync::Arc;
+
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let root = "/legacy/project";
+
+        // A table in the original format: no later columns, `path:line` ids,
+        // and a BM25 index numbering its documents by row offset
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "vector",
+                DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), 384),
+                false,
+            ),
+            Field::new("id", DataType::Utf8, false),
+            Field::new("file_path", DataType::Utf8, false),
+            Field::new("root_path", DataType::Utf8, true),
+            Field::new("start_line", DataType::UInt32, false),
+            Field::new("end_line", DataType::UInt32, false),
+            Field::new("language", DataType::Utf8, false),
+            Field::new("extension", DataType::Utf8, false),
+            Field::new("file_hash", DataType::Utf8, false),
+            Field::new("indexed_at", DataType::Utf8, false),
+            Field::new("content", DataType::Utf8, false),
+            Field::new("project", DataType::Utf8, true),
+        ]));
+
+        // Enough close neighbours to fill every vector candidate slot, and one
+        // distant chunk that only the keyword side can find
+        let rows = HYBRID_CANDIDATE_FACTOR + 2;
+        let files: Vec<String> = (1..rows)
+            .map(|i| format!("near_{}.rs", i))
+            .chain(["db.rs".to_string()])
+            .collect();
+        let contents: Vec<String> = (1..rows)
+            .map(|i| format!("fn helper_{}() {{}}", i))
+            .chain(["fn open_connection() {}".to_string()])
+            .collect();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(
+                    FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+                        (0..rows).map(|i| {
+                            let v = if i + 1 < rows { 0.1f32 } else { -0.1 };
+                            Some(vec![Some(v); 384])
+                        }),
+                        384,
+                    ),
+                ),
+                Arc::new(StringArray::from_iter_values(
+                    files.iter().map(|file| format!("{}:1", file)),
+                )),
+                Arc::new(StringArray::from_iter_values(&files)),
+                Arc::new(StringArray::from(vec![Some(root); rows])),
+                Arc::new(UInt32Array::from(vec![1; rows])),
+                Arc::new(UInt32Array::from(vec![3; rows])),
+                Arc::new(StringArray::from(vec!["Rust"; rows])),
+                Arc::new(StringArray::from(vec!["rs"; rows])),
+                Arc::new(StringArray::from(vec!["hash"; rows])),
+                Arc::new(StringArray::from(vec!["1234567890"; rows])),
+                Arc::new(StringArray::from_iter_values(&contents)),
+                Arc::new(StringArray::from(vec![Some("legacy"); rows])),
+            ],
+        )
+        .unwrap();
+        lancedb::connect(&db_path)
+            .execute()
+            .await
+            .unwrap()
+            .create_table(
+                "code_embeddings",
+                Box::new(RecordBatchIterator::new(vec![Ok(batch)], schema)),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        let legacy_bm25 = BM25Search::new(db.bm25_path_for_root(root)).unwrap();
+        legacy_bm25
+            .add_documents(
+                (0..rows)
+                    .map(|i| (i as u64, contents[i].clone(), files[i].clone()))
+                    .collect(),
+            )
+            .unwrap();
+        legacy_bm25.flush().unwrap();
+        drop(legacy_bm25);
+
+        db.initialize(384).await.unwrap();
+
+        // The chunk far from the query vector is only reachable by keyword
+        let results = db
+            .search(
+                vec![0.1; 384],
+                "open_connection",
+                1,
+                0.0,
+                &[],
+                None,
+                true,
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_path, "db.rs");
+        assert_eq!(results[0].matched_via, MatchSource::Keyword);
+        assert_eq!(db.get_statistics().await.unwrap().total_chunks, rows);
+    }
+
+    #[tokio::test]
+    async fn test_doc_boost_lifts_readme_above_code() {
+        use crate::vector_db::DocBoost;
+
+        let root = "/normalized/project";
+        let metadata = |file_path: &str| ChunkMetadata {
+            root_path: Some(root.to_string()),
+            ..create_test_metadata(file_path, 1, 10)
+        };
+
+        // Without a boost the code ranks first on both vector and keyword
+        for (doc_boost, expected) in [
+            (DocBoost::default(), ["src/plugin.rs", "README.md"]),
+            (
+                DocBoost::new(3.0, &["README*".to_string()]),
+                ["README.md", "src/plugin.rs"],
+            ),
+        ] {
+            let temp_dir = tempdir().unwrap();
+            let db_path = temp_dir
+                .path()
+                .join("lancedb")
+                .to_string_lossy()
+                .to_string();
+            let db = LanceVectorDB::with_path(&db_path)
+                .await
+                .unwrap()
+                .with_doc_boost(doc_boost);
+            db.initialize(384).await.unwrap();
+            db.store_embeddings(
+                vec![vec![0.5; 384], vec![0.45; 384]],
+                vec![metadata("src/plugin.rs"), metadata("README.md")],
+                vec![
+                    "fn install_plugin() { install(plugin) }".to_string(),
+                    "Run the installer to install the plugin into your editor.".to_string(),
+                ],
+                root,
+            )
+            .await
+            .unwrap();
+
+            let results = db
+                .search(
+                    vec![0.5; 384],
+                    "install plugin",
+                    2,
+                    0.0,
+                    &[],
+                    None,
+                    true,
+                    false,
+                )
+                .await
+                .unwrap();
+            let files: Vec<&str> = results.iter().map(|r| r.file_path.as_str()).collect();
+            assert_eq!(files, expected);
+            assert!(results[0].score > results[1].score);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ann_params_applied_to_indexed_table() {
+        use crate::vector_db::AnnParams;
+        use lancedb::index::Index;
+        use lancedb::index::vector::IvfFlatIndexBuilder;
+
+        const DIMENSION: usize = 16;
+        const ROWS: usize = 300;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+
+        // Deterministic pseudo-random vectors so partitions are non-trivial
+        let mut seed = 42u64;
+        let mut next_vector = || {
+            (0..DIMENSION)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    (seed >> 33) as f32 / (1u64 << 31) as f32
+                })
+                .collect::<Vec<f32>>()
+        };
+        let embeddings: Vec<Vec<f32>> = (0..ROWS).map(|_| next_vector()).collect();
+        let query = next_vector();
+
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(DIMENSION).await.unwrap();
+        db.store_embeddings(
+            embeddings,
+            (0..ROWS)
+                .map(|i| create_test_metadata(&format!("/test/file{}.rs", i), 1, 10))
+                .collect(),
+            (0..ROWS).map(|i| format!("fn item{}() {{}}", i)).collect(),
+            "/test",
+        )
+        .await
+        .unwrap();
+
+        // Exact results before any ANN index exists
+        let exact = db
+            .search(query.clone(), "", 5, 0.0, &[], None, false, false)
+            .await
+            .unwrap();
+        assert_eq!(exact.len(), 5);
+
+        db.get_table()
+            .await
+            .unwrap()
+            .create_index(
+                &["vector"],
+                Index::IvfFlat(IvfFlatIndexBuilder::default().num_partitions(4)),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // Probing every partition with re-ranking finds the exact neighbours
+        let thorough = LanceVectorDB::with_path(&db_path)
+            .await
+            .unwrap()
+            .with_ann_params(AnnParams {
+                nprobes: Some(4),
+                refine_factor: Some(2),
+            });
+        assert_eq!(thorough.ann_params.nprobes, Some(4));
+        assert_eq!(thorough.ann_params.refine_factor, Some(2));
+        let results = thorough
+            .search(query.clone(), "", 5, 0.0, &[], None, false, false)
+            .await
+            .unwrap();
+        let paths = |results: &[crate::types::SearchResult]| {
+            results
+                .iter()
+                .map(|r| r.file_path.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(paths(&results), paths(&exact));
+
+        // Probing a single partition is cheaper but still returns a full page
+        let fast = LanceVectorDB::with_path(&db_path)
+            .await
+            .unwrap()
+            .with_ann_params(AnnParams {
+                nprobes: Some(1),
+                refine_factor: None,
+            });
+        let results = fast
+            .search(query, "", 5, 0.0, &[], None, false, false)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|r| r.vector_score > 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_ann_index_built_at_threshold_and_retrained_on_flush() {
+        use crate::vector_db::{AnnIndexParams, AnnParams};
+
+        const DIMENSION: usize = 16;
+        const BATCH: usize = 10_000;
+        const ROWS: usize = 50_000;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path)
+            .await
+            .unwrap()
+            .with_ann_index(AnnIndexParams {
+                min_rows: ROWS,
+                num_partitions: Some(16),
+                num_sub_vectors: Some(4),
+            })
+            .with_ann_params(AnnParams {
+                nprobes: Some(16),
+                refine_factor: Some(50),
+            });
+        db.initialize(DIMENSION).await.unwrap();
+
+        let mut seed = 7u64;
+        let mut next_vector = || {
+            (0..DIMENSION)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    (seed >> 33) as f32 / (1u64 << 31) as f32
+                })
+                .collect::<Vec<f32>>()
+        };
+        // Stores rows `start..start + BATCH`, returning a sample of them by row number
+        let mut store_batch = async |start: usize| {
+            let embeddings: Vec<Vec<f32>> = (0..BATCH).map(|_| next_vector()).collect();
+            let sample: Vec<(Vec<f32>, usize)> = embeddings
+                .iter()
+                .cloned()
+                .zip(start..start + BATCH)
+                .step_by(BATCH / 10)
+                .collect();
+            db.store_embeddings(
+                embeddings,
+                (start..start + BATCH)
+                    .map(|i| create_test_metadata(&format!("/test/file{}.rs", i), 1, 10))
+                    .collect(),
+                (start..start + BATCH)
+                    .map(|i| format!("fn item{}() {{}}", i))
+                    .collect(),
+                "/test",
+            )
+            .await
+            .unwrap();
+            sample
+        };
+
+        let vector_index = async || {
+            let table = db.get_table().await.unwrap();
+            let index = table
+                .list_indices()
+                .await
+                .unwrap()
+                .into_iter()
+                .find(|index| index.columns == ["vector"])?;
+            table.index_stats(&index.name).await.unwrap()
+        };
+
+        // No index below the threshold, one as soon as it is reached
+        for start in (0..ROWS - BATCH).step_by(BATCH) {
+            store_batch(start).await;
+        }
+        assert!(vector_index().await.is_none());
+        let sample = store_batch(ROWS - BATCH).await;
+        let stats = vector_index().await.unwrap();
+        assert_eq!(stats.num_indexed_rows, ROWS);
+
+        // Indexed search still finds each stored vector as its own nearest neighbour
+        for (vector, i) in &sample {
+            let results = db
+                .search(vector.clone(), "", 1, 0.0, &[], None, false, false)
+                .await
+                .unwrap();
+            assert_eq!(results[0].file_path, format!("/test/file{}.rs", i));
+        }
+
+        // A fifth more rows makes flush retrain the index over all of them
+        store_batch(ROWS).await;
+        assert_eq!(vector_index().await.unwrap().num_unindexed_rows, BATCH);
+        db.flush().await.unwrap();
+        let stats = vector_index().await.unwrap();
+        assert_eq!(stats.num_indexed_rows, ROWS + BATCH);
+        assert_eq!(stats.num_unindexed_rows, 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_stores_keep_chunk_ids_unique() {
+        use futures::TryStreamExt;
+        use lancedb::query::ExecutableQuery;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir
+            .path()
+            .join("lancedb")
+            .to_string_lossy()
+            .to_string();
+        let db = LanceVectorDB::with_path(&db_path).await.unwrap();
+        db.initialize(384).await.unwrap();
+
+        // Same relative file and lines in every root, stored concurrently
+        let roots = ["/roots/a", "/roots/b", "/roots/c", "/roots/d"];
+        futures::future::try_join_all(roots.iter().map(|root| {
+            let letter = &root[root.len() - 1..];
+            let metadata = (1..=3)
+                .map(|i| ChunkMetadata {
+                    root_path: Some(root.to_string()),
+                    ..create_test_metadata("lib.rs", i * 10, i * 10 + 5)
+                })
+                .collect();
+            let contents = (1..=3)
+                .map(|i| format!("fn handler() {{ marker{}{} }}", letter, i))
+                .collect();
+            db.store_embeddings(vec![vec![0.5; 384]; 3], metadata, contents, root)
+        }))
+        .await
+        .unwrap();
+
+        let table = db.get_table().await.unwrap();
+        let batches: Vec<arrow_array::RecordBatch> = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let mut ids = std::collections::HashSet::new();
+        for batch in &batches {
+            let column = batch
+                .column_by_name("id")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<arrow_array::StringArray>()
+                .unwrap();
+            for i in 0..batch.num_rows() {
+                assert!(ids.insert(column.value(i).to_string()), "duplicate id");
+            }
+        }
+        assert_eq!(ids.len(), 12);
+
+        // A keyword hit is hydrated from its own row
+        let results = db
+            .search(vec![0.5; 384], "markerc2", 12, 0.0, &[], None, true, false)
+            .await
+            .unwrap();
+        let hit = results
+            .iter()
+            .find(|r| r.keyword_score.is_some())
+            .expect("keyword hit should be returned");
+        assert!(hit.content.contains("markerc2"));
+        assert_eq!(hit.root_path.as_deref(), Some("/roots/c"));
+        assert_eq!(hit.start_line, 20);
+    }
 }
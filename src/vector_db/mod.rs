@@ -1,6 +1,6 @@
 // LanceDB is the default embedded vector database (stable, feature-rich)
 pub mod lance_client;
-pub use lance_client::LanceVectorDB;
+pub use lance_client::{AnnIndexParams, AnnParams, DistanceMetric, LanceVectorDB};
 
 // Qdrant is optional (requires external server)
 #[cfg(feature = "qdrant-backend")]
@@ -8,8 +8,17 @@ pub mod qdrant_client;
 #[cfg(feature = "qdrant-backend")]
 pub use qdrant_client::QdrantVectorDB;
 
-use crate::types::{ChunkMetadata, SearchResult};
+use crate::glob_utils;
+use crate::types::{ChunkMetadata, DeleteFilter, LanguageStats, ProjectSummary, SearchResult};
 use anyhow::Result;
+use globset::GlobMatcher;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Candidates fetched from each side of a hybrid search per requested result
+pub const HYBRID_CANDIDATE_FACTOR: usize = 3;
+
+/// Languages git history is stored under, alongside the code it belongs to
+pub const GIT_HISTORY_LANGUAGES: &[&str] = &["git-commit", "git-tag"];
 
 /// Trait for vector database operations
 #[async_trait::async_trait]
@@ -28,6 +37,8 @@ pub trait VectorDatabase: Send + Sync {
     ) -> Result<usize>;
 
     /// Search for similar vectors
+    /// projects: Projects to search in; empty searches all projects
+    /// include_git: Also return git history entries (see [`GIT_HISTORY_LANGUAGES`])
     #[allow(clippy::too_many_arguments)]
     async fn search(
         &self,
@@ -35,12 +46,14 @@ pub trait VectorDatabase: Send + Sync {
         query_text: &str,
         limit: usize,
         min_score: f32,
-        project: Option<String>,
+        projects: &[String],
         root_path: Option<String>,
         hybrid: bool,
+        include_git: bool,
     ) -> Result<Vec<SearchResult>>;
 
     /// Search with filters
+    /// symbol_kinds: Serialized `SymbolKind` names; untagged chunks never match a non-empty filter
     #[allow(clippy::too_many_arguments)]
     async fn search_filtered(
         &self,
@@ -48,23 +61,77 @@ pub trait VectorDatabase: Send + Sync {
         query_text: &str,
         limit: usize,
         min_score: f32,
-        project: Option<String>,
+        projects: &[String],
         root_path: Option<String>,
         hybrid: bool,
         file_extensions: Vec<String>,
         languages: Vec<String>,
         path_patterns: Vec<String>,
+        symbol_kinds: Vec<String>,
     ) -> Result<Vec<SearchResult>>;
 
+    /// Keyword (BM25) search alone, ranked by raw BM25 score
+    ///
+    /// Backends without a keyword index return no results.
+    async fn search_keyword(
+        &self,
+        _query_text: &str,
+        _limit: usize,
+        _projects: &[String],
+        _root_path: Option<String>,
+    ) -> Result<Vec<SearchResult>> {
+        Ok(Vec::new())
+    }
+
     /// Delete embeddings for a specific file
     async fn delete_by_file(&self, file_path: &str) -> Result<usize>;
 
+    /// Delete every chunk matching `filter`, from the vectors and keyword index alike
+    ///
+    /// Fails for an empty filter; use [`clear`](Self::clear) to delete everything.
+    async fn delete_where(&self, filter: &DeleteFilter) -> Result<DeletedChunks>;
+
     /// Clear all embeddings
     async fn clear(&self) -> Result<()>;
 
+    /// Drop the keyword index of a root path, e.g. once none of its chunks remain
+    ///
+    /// Returns whether an index was dropped. Backends without a keyword index
+    /// have nothing to drop.
+    async fn drop_keyword_index(&self, _root_path: &str) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Vector dimension of the existing index, or `None` if there is none yet
+    ///
+    /// Backends that can't tell report `None`.
+    async fn indexed_dimension(&self) -> Result<Option<usize>> {
+        Ok(None)
+    }
+
     /// Get statistics
     async fn get_statistics(&self) -> Result<DatabaseStats>;
 
+    /// Open a reader on every keyword index on disk, returning how many were opened
+    ///
+    /// Backends without a keyword index have none to open.
+    async fn check_keyword_indexes(&self) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// Compact the stored data and merge keyword index segments
+    ///
+    /// Incremental updates leave many small fragments and segments behind,
+    /// slowing scans down. Backends that compact on their own have nothing to do.
+    async fn optimize(&self) -> Result<OptimizeStats> {
+        Ok(OptimizeStats::default())
+    }
+
+    /// Distinct projects with their chunk counts and languages, sorted by name
+    ///
+    /// Chunks indexed without a project are not listed.
+    async fn list_projects(&self) -> Result<Vec<ProjectSummary>>;
+
     /// Flush/save changes to disk
     async fn flush(&self) -> Result<()>;
 
@@ -75,11 +142,120 @@ pub trait VectorDatabase: Send + Sync {
     /// Get unique file paths indexed for a specific root path
     /// Returns a list of file paths that have embeddings in the database
     async fn get_indexed_files(&self, root_path: &str) -> Result<Vec<String>>;
+
+    /// Every chunk stored for `file_path`, optionally of one project, ordered
+    /// by start line
+    ///
+    /// No similarity is computed, so every result has a score of 1.0.
+    async fn get_file_chunks(
+        &self,
+        file_path: &str,
+        project: Option<String>,
+    ) -> Result<Vec<SearchResult>>;
+}
+
+/// Score multiplier for documentation files in hybrid search
+///
+/// Applied to fused scores before the final ranking is cut, so a README that
+/// answers a usage question can outrank equally relevant code.
+#[derive(Debug, Clone)]
+pub struct DocBoost {
+    /// Multiplier for matching files (1.0 leaves scores unchanged)
+    pub factor: f32,
+    matchers: Vec<GlobMatcher>,
+}
+
+impl Default for DocBoost {
+    fn default() -> Self {
+        Self {
+            factor: 1.0,
+            matchers: Vec::new(),
+        }
+    }
+}
+
+impl DocBoost {
+    /// Boost files matching any of `patterns` by `factor`
+    ///
+    /// Patterns that don't compile are skipped with a warning.
+    pub fn new(factor: f32, patterns: &[String]) -> Self {
+        let matchers = patterns
+            .iter()
+            .filter_map(|pattern| glob_utils::compile_patterns(std::slice::from_ref(pattern)))
+            .flatten()
+            .collect();
+        Self { factor, matchers }
+    }
+
+    /// Whether any score can change
+    pub fn is_enabled(&self) -> bool {
+        self.factor != 1.0 && !self.matchers.is_empty()
+    }
+
+    /// Multiplier for a result from `file_path`
+    pub fn factor_for(&self, file_path: &str) -> f32 {
+        if self.is_enabled() && glob_utils::matches_any_matcher(file_path, &self.matchers) {
+            self.factor
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Chunks removed by [`VectorDatabase::delete_where`]
+#[derive(Debug, Clone, Default)]
+pub struct DeletedChunks {
+    /// Number of chunks deleted
+    pub count: usize,
+    /// Distinct (root path, file path) pairs the deleted chunks came from
+    pub files: Vec<(Option<String>, String)>,
+}
+
+/// Outcome of [`VectorDatabase::optimize`]
+#[derive(Debug, Clone, Default)]
+pub struct OptimizeStats {
+    /// Data fragments of the table before compaction
+    pub fragments_before: usize,
+    /// Data fragments of the table after compaction
+    pub fragments_after: usize,
+    /// Keyword indexes whose segments were merged
+    pub keyword_indexes: usize,
+    /// Segments across those keyword indexes before merging
+    pub keyword_segments_before: usize,
+    /// Segments across those keyword indexes after merging
+    pub keyword_segments_after: usize,
+}
+
+/// Summarize `(project, language)` pairs, one per chunk, into sorted project summaries
+pub(crate) fn summarize_projects<'a>(
+    chunks: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<ProjectSummary> {
+    let mut projects: BTreeMap<&str, (usize, BTreeSet<&str>)> = BTreeMap::new();
+    for (project, language) in chunks {
+        let (chunk_count, languages) = projects.entry(project).or_default();
+        *chunk_count += 1;
+        languages.insert(language);
+    }
+
+    projects
+        .into_iter()
+        .map(|(name, (chunk_count, languages))| ProjectSummary {
+            name: name.to_string(),
+            chunk_count,
+            languages: languages.into_iter().map(str::to_string).collect(),
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone)]
 pub struct DatabaseStats {
     pub total_points: usize,
     pub total_vectors: usize,
-    pub language_breakdown: Vec<(String, usize)>,
+    /// Distinct indexed files
+    pub total_files: usize,
+    /// Files and chunks per language, most chunks first
+    pub language_breakdown: Vec<LanguageStats>,
+    /// Size of the table and its keyword indexes on disk, if the backend can
+    /// report it
+    pub size_bytes: Option<u64>,
 }
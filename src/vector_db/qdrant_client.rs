@@ -1,6 +1,10 @@
-use super::{DatabaseStats, VectorDatabase};
+use super::{
+    DatabaseStats, DeletedChunks, DocBoost, GIT_HISTORY_LANGUAGES, VectorDatabase,
+    summarize_projects,
+};
 use crate::glob_utils;
-use crate::types::{ChunkMetadata, SearchResult};
+use crate::telemetry::{self, PhaseSpans};
+use crate::types::{ChunkMetadata, DeleteFilter, MatchSource, ProjectSummary, SearchResult};
 use anyhow::{Context, Result};
 use qdrant_client::qdrant::vectors_config::Config;
 use qdrant_client::qdrant::{
@@ -13,6 +17,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Default collection for embeddings produced by the configured model
 const COLLECTION_NAME: &str = "code_embeddings";
 
 /// Document frequency statistics for IDF calculation
@@ -26,8 +31,13 @@ struct IdfStats {
 
 pub struct QdrantVectorDB {
     client: Qdrant,
+    collection_name: String,
     /// IDF statistics for BM25 calculation
     idf_stats: Arc<RwLock<IdfStats>>,
+    /// Emits a "fuse" span around hybrid scoring when enabled
+    phase_spans: PhaseSpans,
+    /// Score multiplier for documentation files in hybrid search
+    doc_boost: DocBoost,
 }
 
 impl QdrantVectorDB {
@@ -43,7 +53,16 @@ impl QdrantVectorDB {
 
     /// Create a new Qdrant client with a custom URL
     pub async fn with_url(url: &str) -> Result<Self> {
-        tracing::info!("Connecting to Qdrant at {}", url);
+        Self::with_url_and_collection(url, COLLECTION_NAME).await
+    }
+
+    /// Create a new Qdrant client storing embeddings in a specific collection
+    pub async fn with_url_and_collection(url: &str, collection_name: &str) -> Result<Self> {
+        tracing::info!(
+            "Connecting to Qdrant at {} (collection '{}')",
+            url,
+            collection_name
+        );
 
         let client = Qdrant::from_url(url)
             .build()
@@ -51,7 +70,10 @@ impl QdrantVectorDB {
 
         let db = Self {
             client,
+            collection_name: collection_name.to_string(),
             idf_stats: Arc::new(RwLock::new(IdfStats::default())),
+            phase_spans: PhaseSpans::default(),
+            doc_boost: DocBoost::default(),
         };
 
         // Initialize IDF stats by scanning existing documents
@@ -62,6 +84,18 @@ impl QdrantVectorDB {
         Ok(db)
     }
 
+    /// Enable or disable pipeline phase spans
+    pub fn with_phase_spans(mut self, phase_spans: PhaseSpans) -> Self {
+        self.phase_spans = phase_spans;
+        self
+    }
+
+    /// Boost the combined scores of documentation files in hybrid search
+    pub fn with_doc_boost(mut self, doc_boost: DocBoost) -> Self {
+        self.doc_boost = doc_boost;
+        self
+    }
+
     /// Refresh IDF statistics by scanning the entire corpus
     async fn refresh_idf_stats(&self) -> Result<()> {
         use qdrant_client::qdrant::ScrollPointsBuilder;
@@ -73,7 +107,7 @@ impl QdrantVectorDB {
         let mut offset: Option<qdrant_client::qdrant::PointId> = None;
 
         loop {
-            let mut builder = ScrollPointsBuilder::new(COLLECTION_NAME)
+            let mut builder = ScrollPointsBuilder::new(self.collection_name.as_str())
                 .with_payload(true)
                 .limit(100);
 
@@ -144,7 +178,7 @@ impl QdrantVectorDB {
         Ok(collections
             .collections
             .iter()
-            .any(|c| c.name == COLLECTION_NAME))
+            .any(|c| c.name == self.collection_name))
     }
 
     /// Calculate full BM25 score with IDF for a query against content
@@ -189,145 +223,48 @@ impl QdrantVectorDB {
         // Clamp to [0, 1]
         normalized_score.min(1.0).max(0.0)
     }
-}
-
-#[async_trait::async_trait]
-impl VectorDatabase for QdrantVectorDB {
-    async fn initialize(&self, dimension: usize) -> Result<()> {
-        if self.collection_exists().await? {
-            tracing::info!("Collection '{}' already exists", COLLECTION_NAME);
-            return Ok(());
-        }
-
-        tracing::info!(
-            "Creating collection '{}' with dimension {}",
-            COLLECTION_NAME,
-            dimension
-        );
-
-        self.client
-            .create_collection(
-                CreateCollectionBuilder::new(COLLECTION_NAME).vectors_config(VectorsConfig {
-                    config: Some(Config::Params(VectorParams {
-                        size: dimension as u64,
-                        distance: Distance::Cosine.into(),
-                        ..Default::default()
-                    })),
-                }),
-            )
-            .await
-            .context("Failed to create collection")?;
-
-        Ok(())
-    }
-
-    async fn store_embeddings(
-        &self,
-        embeddings: Vec<Vec<f32>>,
-        metadata: Vec<ChunkMetadata>,
-        contents: Vec<String>,
-        _root_path: &str,
-    ) -> Result<usize> {
-        if embeddings.is_empty() {
-            return Ok(0);
-        }
-
-        let count = embeddings.len();
-        tracing::debug!("Storing {} embeddings", count);
-
-        let points: Vec<PointStruct> = embeddings
-            .into_iter()
-            .zip(metadata.into_iter())
-            .zip(contents.into_iter())
-            .enumerate()
-            .map(|(idx, ((embedding, meta), content))| {
-                let payload: Payload = json!({
-                    "file_path": meta.file_path,
-                    "project": meta.project,
-                    "start_line": meta.start_line,
-                    "end_line": meta.end_line,
-                    "language": meta.language,
-                    "extension": meta.extension,
-                    "file_hash": meta.file_hash,
-                    "indexed_at": meta.indexed_at,
-                    "content": content,
-                })
-                .try_into()
-                .unwrap();
-
-                PointStruct::new(idx as u64, embedding, payload)
-            })
-            .collect();
-
-        self.client
-            .upsert_points(UpsertPointsBuilder::new(COLLECTION_NAME, points))
-            .await
-            .context("Failed to upsert points")?;
-
-        // Refresh IDF statistics after adding new documents
-        if let Err(e) = self.refresh_idf_stats().await {
-            tracing::warn!("Failed to refresh IDF stats after indexing: {}", e);
-        }
-
-        Ok(count)
-    }
-
-    async fn search(
-        &self,
-        query_vector: Vec<f32>,
-        query_text: &str,
-        limit: usize,
-        min_score: f32,
-        project: Option<String>,
-        root_path: Option<String>,
-        hybrid: bool,
-    ) -> Result<Vec<SearchResult>> {
-        self.search_filtered(
-            query_vector,
-            query_text,
-            limit,
-            min_score,
-            project,
-            root_path,
-            hybrid,
-            vec![],
-            vec![],
-            vec![],
-        )
-        .await
-    }
 
-    async fn search_filtered(
+    /// Search with filters, leaving out chunks of `excluded_languages`
+    #[allow(clippy::too_many_arguments)]
+    async fn search_points(
         &self,
         query_vector: Vec<f32>,
         query_text: &str,
         limit: usize,
         min_score: f32,
-        project: Option<String>,
+        projects: &[String],
         root_path: Option<String>,
         hybrid: bool,
         file_extensions: Vec<String>,
         languages: Vec<String>,
         path_patterns: Vec<String>,
+        symbol_kinds: Vec<String>,
+        excluded_languages: &[&str],
     ) -> Result<Vec<SearchResult>> {
         tracing::debug!(
-            "Searching with limit={}, min_score={}, project={:?}, root_path={:?}, hybrid={}, filters: ext={:?}, lang={:?}, path={:?}",
+            "Searching with limit={}, min_score={}, projects={:?}, root_path={:?}, hybrid={}, filters: ext={:?}, lang={:?}, path={:?}, kinds={:?}",
             limit,
             min_score,
-            project,
+            projects,
             root_path,
             hybrid,
             file_extensions,
             languages,
-            path_patterns
+            path_patterns,
+            symbol_kinds
         );
 
         let mut filter = Filter::default();
         let mut must_conditions = vec![];
 
-        // Add project filter
-        if let Some(proj) = project {
-            must_conditions.push(Condition::matches("project", proj));
+        // Add project filter, matching any of the projects
+        if !projects.is_empty() {
+            must_conditions.push(Condition::matches("project", projects.to_vec()));
+        }
+
+        // Add root path filter, isolating codebases indexed into the same collection
+        if let Some(root) = root_path {
+            must_conditions.push(Condition::matches("root_path", root));
         }
 
         // Add file extension filter
@@ -346,6 +283,22 @@ impl VectorDatabase for QdrantVectorDB {
             ));
         }
 
+        // Add symbol kind filter
+        if !symbol_kinds.is_empty() {
+            must_conditions.push(Condition::matches("symbol_kind", symbol_kinds));
+        }
+
+        // Leave out languages that were not asked for, such as git history
+        if !excluded_languages.is_empty() {
+            filter.must_not = vec![Condition::matches(
+                "language",
+                excluded_languages
+                    .iter()
+                    .map(|language| language.to_string())
+                    .collect::<Vec<_>>(),
+            )];
+        }
+
         // Note: Path pattern filtering would require more complex logic
         // For now, we'll do post-filtering in memory for path patterns
 
@@ -354,11 +307,11 @@ impl VectorDatabase for QdrantVectorDB {
         }
 
         let mut search_builder =
-            SearchPointsBuilder::new(COLLECTION_NAME, query_vector, limit as u64)
+            SearchPointsBuilder::new(self.collection_name.as_str(), query_vector, limit as u64)
                 .score_threshold(min_score)
                 .with_payload(true);
 
-        if !filter.must.is_empty() {
+        if !filter.must.is_empty() || !filter.must_not.is_empty() {
             search_builder = search_builder.filter(filter);
         }
 
@@ -371,6 +324,20 @@ impl VectorDatabase for QdrantVectorDB {
         // Collect results with async BM25 scoring
         let mut results: Vec<SearchResult> = Vec::new();
 
+        let fuse_span = if hybrid {
+            self.phase_spans.span(|| {
+                tracing::info_span!(
+                    "fuse",
+                    vector_hits = search_result.result.len(),
+                    results = tracing::field::Empty,
+                    duration_ms = tracing::field::Empty
+                )
+            })
+        } else {
+            tracing::Span::none()
+        };
+        let fuse_start = std::time::Instant::now();
+
         for point in search_result.result {
             let payload = point.payload;
             let vector_score = point.score;
@@ -394,6 +361,13 @@ impl VectorDatabase for QdrantVectorDB {
                 None => continue,
             };
 
+            // Boost documentation files before the hybrid re-sort
+            let final_score = if hybrid {
+                final_score * self.doc_boost.factor_for(&file_path)
+            } else {
+                final_score
+            };
+
             let start_line = match payload.get("start_line").and_then(|v| v.as_integer()) {
                 Some(l) => l as usize,
                 None => continue,
@@ -413,18 +387,42 @@ impl VectorDatabase for QdrantVectorDB {
                 .get("project")
                 .and_then(|v| v.as_str().map(String::from));
 
+            let symbol_kind = payload
+                .get("symbol_kind")
+                .and_then(|v| v.as_str().map(String::from));
+
+            let binary = payload
+                .get("binary")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            let file_size = payload
+                .get("file_size")
+                .and_then(|v| v.as_integer())
+                .map(|size| size as u64);
+
+            let branch = payload
+                .get("branch")
+                .and_then(|v| v.as_str().map(String::from));
+
+            let indexed_at = payload.get("indexed_at").and_then(|v| v.as_integer());
+            let commit_date = payload.get("commit_date").and_then(|v| v.as_integer());
+
             let result_root_path = payload
                 .get("root_path")
                 .and_then(|v| v.as_str().map(String::from));
 
-            // Filter by root_path if specified
-            if let Some(ref filter_path) = root_path {
-                if result_root_path.as_ref() != Some(filter_path) {
-                    continue;
-                }
-            }
+            let file_hash = payload
+                .get("file_hash")
+                .and_then(|v| v.as_str().map(String::from));
 
             results.push(SearchResult {
+                chunk_id: SearchResult::canonical_id(
+                    result_root_path.as_deref(),
+                    &file_path,
+                    start_line,
+                    file_hash.as_deref(),
+                ),
                 file_path,
                 root_path: result_root_path,
                 content,
@@ -435,6 +433,18 @@ impl VectorDatabase for QdrantVectorDB {
                 end_line,
                 language,
                 project,
+                symbol_kind,
+                // Every result is a vector hit; keyword scoring only re-ranks them
+                matched_via: MatchSource::from_hits(true, keyword_score.is_some_and(|k| k > 0.0)),
+                highlights: Vec::new(),
+                binary,
+                file_size,
+                ranking: None,
+                branch,
+                commit_date,
+                indexed_at,
+                relations_precision: None,
+                context: None,
             });
         }
 
@@ -446,6 +456,9 @@ impl VectorDatabase for QdrantVectorDB {
                     .unwrap_or(std::cmp::Ordering::Equal)
             });
         }
+        fuse_span.record("results", results.len());
+        telemetry::record_duration(&fuse_span, fuse_start);
+        drop(fuse_span);
 
         // Post-filter by path patterns using proper glob matching
         if !path_patterns.is_empty() {
@@ -454,27 +467,282 @@ impl VectorDatabase for QdrantVectorDB {
 
         Ok(results)
     }
+}
+
+#[async_trait::async_trait]
+impl VectorDatabase for QdrantVectorDB {
+    async fn initialize(&self, dimension: usize) -> Result<()> {
+        if self.collection_exists().await? {
+            tracing::info!("Collection '{}' already exists", self.collection_name);
+            return Ok(());
+        }
+
+        tracing::info!(
+            "Creating collection '{}' with dimension {}",
+            self.collection_name,
+            dimension
+        );
+
+        self.client
+            .create_collection(
+                CreateCollectionBuilder::new(self.collection_name.as_str()).vectors_config(
+                    VectorsConfig {
+                        config: Some(Config::Params(VectorParams {
+                            size: dimension as u64,
+                            distance: Distance::Cosine.into(),
+                            ..Default::default()
+                        })),
+                    },
+                ),
+            )
+            .await
+            .context("Failed to create collection")?;
+
+        Ok(())
+    }
+
+    async fn store_embeddings(
+        &self,
+        embeddings: Vec<Vec<f32>>,
+        metadata: Vec<ChunkMetadata>,
+        contents: Vec<String>,
+        root_path: &str,
+    ) -> Result<usize> {
+        if embeddings.is_empty() {
+            return Ok(0);
+        }
+
+        let count = embeddings.len();
+        tracing::debug!("Storing {} embeddings", count);
+
+        let points: Vec<PointStruct> = embeddings
+            .into_iter()
+            .zip(metadata.into_iter())
+            .zip(contents.into_iter())
+            .enumerate()
+            .map(|(idx, ((embedding, meta), content))| {
+                let payload: Payload = json!({
+                    "file_path": meta.file_path,
+                    "root_path": root_path,
+                    "project": meta.project,
+                    "start_line": meta.start_line,
+                    "end_line": meta.end_line,
+                    "language": meta.language,
+                    "extension": meta.extension,
+                    "file_hash": meta.file_hash,
+                    "indexed_at": meta.indexed_at,
+                    "content": content,
+                    "symbol_kind": meta.symbol_kind,
+                    "binary": meta.binary,
+                    "file_size": meta.file_size,
+                    "branch": meta.branch,
+                    "commit_date": meta.commit_date,
+                })
+                .try_into()
+                .unwrap();
+
+                PointStruct::new(idx as u64, embedding, payload)
+            })
+            .collect();
+
+        self.client
+            .upsert_points(UpsertPointsBuilder::new(
+                self.collection_name.as_str(),
+                points,
+            ))
+            .await
+            .context("Failed to upsert points")?;
+
+        // Refresh IDF statistics after adding new documents
+        if let Err(e) = self.refresh_idf_stats().await {
+            tracing::warn!("Failed to refresh IDF stats after indexing: {}", e);
+        }
+
+        Ok(count)
+    }
+
+    async fn search(
+        &self,
+        query_vector: Vec<f32>,
+        query_text: &str,
+        limit: usize,
+        min_score: f32,
+        projects: &[String],
+        root_path: Option<String>,
+        hybrid: bool,
+        include_git: bool,
+    ) -> Result<Vec<SearchResult>> {
+        let excluded_languages = if include_git {
+            &[][..]
+        } else {
+            GIT_HISTORY_LANGUAGES
+        };
+        self.search_points(
+            query_vector,
+            query_text,
+            limit,
+            min_score,
+            projects,
+            root_path,
+            hybrid,
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            excluded_languages,
+        )
+        .await
+    }
+
+    async fn search_filtered(
+        &self,
+        query_vector: Vec<f32>,
+        query_text: &str,
+        limit: usize,
+        min_score: f32,
+        projects: &[String],
+        root_path: Option<String>,
+        hybrid: bool,
+        file_extensions: Vec<String>,
+        languages: Vec<String>,
+        path_patterns: Vec<String>,
+        symbol_kinds: Vec<String>,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_points(
+            query_vector,
+            query_text,
+            limit,
+            min_score,
+            projects,
+            root_path,
+            hybrid,
+            file_extensions,
+            languages,
+            path_patterns,
+            symbol_kinds,
+            &[],
+        )
+        .await
+    }
 
     async fn delete_by_file(&self, file_path: &str) -> Result<usize> {
+        use qdrant_client::qdrant::CountPointsBuilder;
+
         tracing::debug!("Deleting embeddings for file: {}", file_path);
 
         let filter = Filter::must([Condition::matches("file_path", file_path.to_string())]);
 
+        // Qdrant doesn't return the count of deleted points, so count them first
+        let count_result = self
+            .client
+            .count(
+                CountPointsBuilder::new(self.collection_name.as_str())
+                    .filter(filter.clone())
+                    .exact(true),
+            )
+            .await
+            .context("Failed to count points to delete")?;
+
+        self.client
+            .delete_points(DeletePointsBuilder::new(self.collection_name.as_str()).points(filter))
+            .await
+            .context("Failed to delete points")?;
+
+        Ok(count_result.result.map(|r| r.count).unwrap_or(0) as usize)
+    }
+
+    async fn delete_where(&self, filter: &DeleteFilter) -> Result<DeletedChunks> {
+        use qdrant_client::qdrant::{PointsIdsList, ScrollPointsBuilder};
+
+        if filter.is_empty() {
+            anyhow::bail!("Delete filter has no criteria; use clear() to delete everything");
+        }
+
+        // Equality criteria are pushed down; the rest is checked per point below
+        let mut conditions = Vec::new();
+        if let Some(ref language) = filter.language {
+            conditions.push(Condition::matches("language", language.clone()));
+        }
+        if let Some(ref project) = filter.project {
+            conditions.push(Condition::matches("project", project.clone()));
+        }
+        let scroll_filter = Filter::must(conditions);
+
+        let mut point_ids = Vec::new();
+        let mut files = std::collections::BTreeSet::new();
+        let mut offset: Option<qdrant_client::qdrant::PointId> = None;
+
+        loop {
+            let mut builder = ScrollPointsBuilder::new(self.collection_name.as_str())
+                .filter(scroll_filter.clone())
+                .with_payload(true)
+                .limit(1000);
+
+            if let Some(ref point_id) = offset {
+                builder = builder.offset(point_id.clone());
+            }
+
+            let scroll_result = self
+                .client
+                .scroll(builder)
+                .await
+                .context("Failed to scroll points")?;
+
+            for point in &scroll_result.result {
+                let payload = &point.payload;
+                let str_field = |name: &str| payload.get(name).and_then(|v| v.as_str()).cloned();
+                let file_path = str_field("file_path").unwrap_or_default();
+                let language = str_field("language").unwrap_or_default();
+                let indexed_at = payload
+                    .get("indexed_at")
+                    .and_then(|v| v.as_integer())
+                    .unwrap_or(0);
+                if !filter.matches(
+                    &file_path,
+                    &language,
+                    str_field("project").as_deref(),
+                    indexed_at,
+                ) {
+                    continue;
+                }
+
+                if let Some(ref id) = point.id {
+                    point_ids.push(id.clone());
+                    files.insert((str_field("root_path"), file_path));
+                }
+            }
+
+            offset = scroll_result.next_page_offset;
+            if offset.is_none() {
+                break;
+            }
+        }
+
+        if point_ids.is_empty() {
+            return Ok(DeletedChunks::default());
+        }
+
+        let count = point_ids.len();
         self.client
-            .delete_points(DeletePointsBuilder::new(COLLECTION_NAME).points(filter))
+            .delete_points(
+                DeletePointsBuilder::new(self.collection_name.as_str())
+                    .points(PointsIdsList { ids: point_ids }),
+            )
             .await
             .context("Failed to delete points")?;
 
-        // Note: Qdrant doesn't return the count of deleted points directly
-        // We return 0 as a placeholder
-        Ok(0)
+        tracing::info!("Deleted {} points matching {:?}", count, filter);
+        Ok(DeletedChunks {
+            count,
+            files: files.into_iter().collect(),
+        })
     }
 
     async fn clear(&self) -> Result<()> {
         tracing::info!("Clearing all embeddings from collection");
 
         self.client
-            .delete_collection(COLLECTION_NAME)
+            .delete_collection(self.collection_name.as_str())
             .await
             .context("Failed to delete collection")?;
 
@@ -486,10 +754,33 @@ impl VectorDatabase for QdrantVectorDB {
         Ok(())
     }
 
+    async fn indexed_dimension(&self) -> Result<Option<usize>> {
+        if !self.collection_exists().await? {
+            return Ok(None);
+        }
+
+        let collection_info = self
+            .client
+            .collection_info(self.collection_name.as_str())
+            .await
+            .context("Failed to get collection info")?;
+        let dimension = collection_info
+            .result
+            .and_then(|info| info.config)
+            .and_then(|config| config.params)
+            .and_then(|params| params.vectors_config)
+            .and_then(|vectors| vectors.config)
+            .and_then(|config| match config {
+                Config::Params(params) => Some(params.size as usize),
+                Config::ParamsMap(_) => None,
+            });
+        Ok(dimension)
+    }
+
     async fn get_statistics(&self) -> Result<DatabaseStats> {
         let collection_info = self
             .client
-            .collection_info(COLLECTION_NAME)
+            .collection_info(self.collection_name.as_str())
             .await
             .context("Failed to get collection info")?;
 
@@ -498,15 +789,69 @@ impl VectorDatabase for QdrantVectorDB {
             .and_then(|r| r.points_count)
             .unwrap_or(0);
 
+        // Qdrant's collection info does not report the on-disk size
+        tracing::debug!("Qdrant does not report the collection size on disk");
+
         // For language breakdown, we'd need to scroll through all points
         // For now, return a simplified version
         Ok(DatabaseStats {
             total_points: points_count as usize,
             total_vectors: points_count as usize,
+            // Counting files would also need a scroll through all points
+            total_files: 0,
             language_breakdown: vec![],
+            size_bytes: None,
         })
     }
 
+    async fn list_projects(&self) -> Result<Vec<ProjectSummary>> {
+        use qdrant_client::qdrant::ScrollPointsBuilder;
+
+        let mut chunks: Vec<(String, String)> = Vec::new();
+        let mut offset: Option<qdrant_client::qdrant::PointId> = None;
+
+        loop {
+            let mut builder = ScrollPointsBuilder::new(self.collection_name.as_str())
+                .with_payload(true)
+                .limit(1000);
+
+            if let Some(ref point_id) = offset {
+                builder = builder.offset(point_id.clone());
+            }
+
+            let scroll_result = self
+                .client
+                .scroll(builder)
+                .await
+                .context("Failed to scroll points")?;
+
+            if scroll_result.result.is_empty() {
+                break;
+            }
+
+            for point in &scroll_result.result {
+                if let Some(project) = point.payload.get("project").and_then(|v| v.as_str()) {
+                    let language = point
+                        .payload
+                        .get("language")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| "Unknown".to_string());
+                    chunks.push((project.to_string(), language));
+                }
+            }
+
+            offset = scroll_result.next_page_offset;
+            if offset.is_none() {
+                break;
+            }
+        }
+
+        Ok(summarize_projects(
+            chunks.iter().map(|(p, l)| (p.as_str(), l.as_str())),
+        ))
+    }
+
     async fn flush(&self) -> Result<()> {
         // Qdrant persists automatically, no explicit flush needed
         Ok(())
@@ -519,7 +864,7 @@ impl VectorDatabase for QdrantVectorDB {
 
         let count_result = self
             .client
-            .count(CountPointsBuilder::new(COLLECTION_NAME).filter(filter))
+            .count(CountPointsBuilder::new(self.collection_name.as_str()).filter(filter))
             .await
             .context("Failed to count points by root path")?;
 
@@ -535,7 +880,7 @@ impl VectorDatabase for QdrantVectorDB {
         let mut offset: Option<qdrant_client::qdrant::PointId> = None;
 
         loop {
-            let mut builder = ScrollPointsBuilder::new(COLLECTION_NAME)
+            let mut builder = ScrollPointsBuilder::new(self.collection_name.as_str())
                 .filter(filter.clone())
                 .with_payload(true)
                 .limit(1000);
@@ -568,6 +913,88 @@ impl VectorDatabase for QdrantVectorDB {
 
         Ok(file_paths.into_iter().collect())
     }
+
+    async fn get_file_chunks(
+        &self,
+        file_path: &str,
+        project: Option<String>,
+    ) -> Result<Vec<SearchResult>> {
+        use qdrant_client::qdrant::ScrollPointsBuilder;
+
+        let mut conditions = vec![Condition::matches("file_path", file_path.to_string())];
+        if let Some(project) = project {
+            conditions.push(Condition::matches("project", project));
+        }
+        let filter = Filter::must(conditions);
+
+        let mut results = Vec::new();
+        let mut offset: Option<qdrant_client::qdrant::PointId> = None;
+
+        loop {
+            let mut builder = ScrollPointsBuilder::new(self.collection_name.as_str())
+                .filter(filter.clone())
+                .with_payload(true)
+                .limit(1000);
+
+            if let Some(ref point_id) = offset {
+                builder = builder.offset(point_id.clone());
+            }
+
+            let scroll_result = self
+                .client
+                .scroll(builder)
+                .await
+                .context("Failed to scroll points")?;
+
+            for point in &scroll_result.result {
+                let payload = &point.payload;
+                let str_field = |name: &str| payload.get(name).and_then(|v| v.as_str()).cloned();
+                let int_field = |name: &str| payload.get(name).and_then(|v| v.as_integer());
+                let start_line = int_field("start_line").unwrap_or(0) as usize;
+
+                results.push(SearchResult {
+                    chunk_id: SearchResult::canonical_id(
+                        str_field("root_path").as_deref(),
+                        file_path,
+                        start_line,
+                        str_field("file_hash").as_deref(),
+                    ),
+                    file_path: file_path.to_string(),
+                    root_path: str_field("root_path"),
+                    content: str_field("content").unwrap_or_default(),
+                    score: 1.0,
+                    vector_score: 1.0,
+                    keyword_score: None,
+                    start_line,
+                    end_line: int_field("end_line").unwrap_or(0) as usize,
+                    language: str_field("language").unwrap_or_else(|| "Unknown".to_string()),
+                    project: str_field("project"),
+                    symbol_kind: str_field("symbol_kind"),
+                    matched_via: MatchSource::default(),
+                    highlights: Vec::new(),
+                    binary: payload
+                        .get("binary")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false),
+                    file_size: int_field("file_size").map(|size| size as u64),
+                    ranking: None,
+                    branch: str_field("branch"),
+                    commit_date: int_field("commit_date"),
+                    indexed_at: int_field("indexed_at"),
+                    relations_precision: None,
+                    context: None,
+                });
+            }
+
+            offset = scroll_result.next_page_offset;
+            if offset.is_none() {
+                break;
+            }
+        }
+
+        results.sort_by_key(|r| r.start_line);
+        Ok(results)
+    }
 }
 
 impl Default for QdrantVectorDB {
@@ -20,7 +20,7 @@ fn test_git_walker_iter_commits() {
 
     // Get last 5 commits
     let commits = walker
-        .iter_commits(None, Some(5), None, None, &skip)
+        .iter_commits(None, Some(5), None, None, None, &skip)
         .expect("Should get commits");
 
     assert!(commits.len() <= 5, "Should get at most 5 commits");
@@ -50,7 +50,7 @@ fn test_commit_chunker() {
 
     // Get one commit
     let commits = walker
-        .iter_commits(None, Some(1), None, None, &skip)
+        .iter_commits(None, Some(1), None, None, None, &skip)
         .expect("Should get commits");
 
     assert!(!commits.is_empty(), "Should have at least one commit");
@@ -121,7 +121,7 @@ fn test_git_search_with_recent_commits() {
 
     // Get last 3 commits
     let commits = walker
-        .iter_commits(None, Some(3), None, None, &skip)
+        .iter_commits(None, Some(3), None, None, None, &skip)
         .expect("Should get commits");
 
     println!("\n=== Recent Commits ===");